@@ -0,0 +1,4 @@
+-- Content hash used to collapse accidental double-submits of the same recipe
+ALTER TABLE recipes ADD COLUMN content_hash TEXT DEFAULT NULL;
+
+CREATE INDEX idx_recipes_content_hash ON recipes(content_hash);
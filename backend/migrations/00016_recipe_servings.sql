@@ -0,0 +1,3 @@
+-- Structured servings parsed from the free-text yield (NULL when not servings-like).
+-- Existing rows are backfilled at startup by the Rust yield parser.
+ALTER TABLE recipes ADD COLUMN servings REAL DEFAULT NULL;
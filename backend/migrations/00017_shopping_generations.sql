@@ -0,0 +1,11 @@
+-- History of recipes sent to the shopping list, used to warn about double-adding
+CREATE TABLE shopping_generations (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  recipe_id  INTEGER NOT NULL,
+  day        TEXT,                          -- meal-plan day 'YYYY-MM-DD' (optional)
+  merge_id   TEXT    NOT NULL,              -- groups the items added by one merge
+  created_at TEXT    NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
+
+CREATE INDEX idx_shopping_generations_recipe_day ON shopping_generations(recipe_id, day);
+CREATE INDEX idx_shopping_generations_created_at ON shopping_generations(created_at);
@@ -0,0 +1,10 @@
+-- User-defined keywords that map shopping item names to a category,
+-- checked before the built-in keyword map and the LLM.
+CREATE TABLE category_keywords (
+  id          INTEGER PRIMARY KEY AUTOINCREMENT,
+  category_id INTEGER NOT NULL REFERENCES shopping_categories(id) ON DELETE CASCADE,
+  keyword     TEXT    NOT NULL UNIQUE,        -- normalized (lowercase, single spaces)
+  created_at  TEXT    NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
+
+CREATE INDEX idx_category_keywords_category ON category_keywords(category_id);
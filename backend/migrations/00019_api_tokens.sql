@@ -0,0 +1,9 @@
+-- Long-lived tokens for scripts and integrations (Authorization: Bearer blz_...)
+CREATE TABLE api_tokens (
+  id           INTEGER PRIMARY KEY AUTOINCREMENT,
+  name         TEXT NOT NULL,
+  token_hash   TEXT NOT NULL UNIQUE,              -- sha256 hex of the full token
+  scopes       TEXT NOT NULL DEFAULT '["read"]',  -- JSON array: read, write, shopping-only
+  created_at   TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+  last_used_at TEXT
+);
@@ -0,0 +1,2 @@
+-- Tools and appliances a recipe needs, as a JSON array of normalized names.
+ALTER TABLE recipes ADD COLUMN equipment TEXT NOT NULL DEFAULT '[]';
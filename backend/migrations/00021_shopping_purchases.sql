@@ -0,0 +1,25 @@
+-- One row per item ticked off the shopping list, for quantity suggestions.
+-- name/unit/quantity are the normalized values from shopping_items.
+CREATE TABLE shopping_purchases (
+  id           INTEGER PRIMARY KEY AUTOINCREMENT,
+  name         TEXT NOT NULL,
+  quantity     REAL,
+  unit         TEXT,
+  category     TEXT,
+  purchased_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
+
+-- Covers the per-name GROUP BY quantity, unit used by /shopping/suggest.
+CREATE INDEX idx_shopping_purchases_name_qty ON shopping_purchases(name, quantity, unit);
+
+-- Every code path that ticks an item off (PATCH, bulk, combine, merge) goes
+-- through an UPDATE of done, so record purchases here rather than in each
+-- handler. Ticking off also clears the quantity in the same UPDATE, so the
+-- amount bought is the OLD one.
+CREATE TRIGGER shopping_items_record_purchase
+AFTER UPDATE OF done ON shopping_items
+WHEN NEW.done = 1 AND OLD.done = 0
+BEGIN
+  INSERT INTO shopping_purchases (name, quantity, unit, category)
+  VALUES (NEW.name, OLD.quantity, OLD.unit, NEW.category);
+END;
@@ -0,0 +1,4 @@
+-- The upserts in the shopping handlers target ON CONFLICT(key); give them an
+-- explicit, named unique index instead of relying on the column constraint
+-- that survived the 00006 table rebuild. Rows without a key (NULL) are exempt.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shopping_items_key ON shopping_items(key);
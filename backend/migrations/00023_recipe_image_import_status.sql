@@ -0,0 +1,4 @@
+-- Outcome of the image fetch during URL import: 'ok', 'none_found' or
+-- 'failed: <reason>'. NULL for recipes that were not imported from a URL or
+-- whose image was uploaded by hand.
+ALTER TABLE recipes ADD COLUMN image_import_status TEXT;
@@ -0,0 +1,53 @@
+-- Stores the user shops at. Items without a store can be bought anywhere.
+CREATE TABLE stores (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  name       TEXT NOT NULL UNIQUE,
+  created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
+
+-- Per-store aisle order. Categories missing here fall back to the global order.
+CREATE TABLE store_category_order (
+  store_id    INTEGER NOT NULL,
+  category_id INTEGER NOT NULL,
+  sort_order  INTEGER NOT NULL,
+  PRIMARY KEY (store_id, category_id)
+);
+
+ALTER TABLE shopping_items ADD COLUMN store_id INTEGER;
+
+DROP VIEW IF EXISTS shopping_items_view;
+
+CREATE VIEW shopping_items_view AS
+SELECT
+  si.id,
+  CASE
+    WHEN si.quantity IS NOT NULL AND si.unit IS NOT NULL AND si.unit <> ''
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.unit || ' ' || si.name
+    WHEN si.quantity IS NOT NULL
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.name
+    ELSE si.name
+  END AS text,
+  si.done,
+  si.category,
+  si.notes,
+  si.recipe_ids,
+  (
+    SELECT GROUP_CONCAT(
+      r.title || 
+      CASE 
+        WHEN mp.day IS NOT NULL THEN ' (' || mp.day || ')'
+        ELSE ''
+      END,
+      ', '
+    )
+    FROM recipes r
+    JOIN json_each(si.recipe_ids) je ON r.id = je.value
+    LEFT JOIN (
+      SELECT recipe_id, MIN(day) as day
+      FROM meal_plan
+      WHERE date(day) >= date('now')
+      GROUP BY recipe_id
+    ) mp ON r.id = mp.recipe_id
+  ) AS recipe_titles,
+  si.store_id
+FROM shopping_items si;
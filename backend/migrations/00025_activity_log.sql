@@ -0,0 +1,12 @@
+-- Who changed what, newest rows kept up to --activity-log-max-rows.
+CREATE TABLE activity_log (
+  id          INTEGER PRIMARY KEY AUTOINCREMENT,
+  created_at  TEXT    NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+  actor       TEXT    NOT NULL,
+  action      TEXT    NOT NULL,
+  entity_type TEXT    NOT NULL,
+  entity_id   INTEGER,
+  summary     TEXT    NOT NULL
+);
+
+CREATE INDEX idx_activity_log_entity ON activity_log(entity_type, entity_id, id);
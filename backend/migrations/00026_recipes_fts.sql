@@ -0,0 +1,48 @@
+-- Full-text index for GET /recipes?q=. Row ids are recipe ids; ingredients
+-- are indexed by name only and instructions as one text. Legacy string
+-- ingredients ("2 carrots") are indexed as-is.
+CREATE VIRTUAL TABLE recipes_fts USING fts5(
+  title,
+  ingredients,
+  notes,
+  instructions,
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO recipes_fts (rowid, title, ingredients, notes, instructions)
+SELECT r.id,
+       r.title,
+       (SELECT group_concat(CASE WHEN type = 'object' THEN json_extract(value, '$.name') ELSE value END, ' ')
+          FROM json_each(r.ingredients)),
+       r.notes,
+       (SELECT group_concat(value, ' ') FROM json_each(r.instructions))
+  FROM recipes r;
+
+CREATE TRIGGER recipes_fts_insert AFTER INSERT ON recipes BEGIN
+  INSERT INTO recipes_fts (rowid, title, ingredients, notes, instructions)
+  VALUES (
+    NEW.id,
+    NEW.title,
+    (SELECT group_concat(CASE WHEN type = 'object' THEN json_extract(value, '$.name') ELSE value END, ' ')
+       FROM json_each(NEW.ingredients)),
+    NEW.notes,
+    (SELECT group_concat(value, ' ') FROM json_each(NEW.instructions))
+  );
+END;
+
+CREATE TRIGGER recipes_fts_update AFTER UPDATE OF title, ingredients, notes, instructions ON recipes BEGIN
+  DELETE FROM recipes_fts WHERE rowid = OLD.id;
+  INSERT INTO recipes_fts (rowid, title, ingredients, notes, instructions)
+  VALUES (
+    NEW.id,
+    NEW.title,
+    (SELECT group_concat(CASE WHEN type = 'object' THEN json_extract(value, '$.name') ELSE value END, ' ')
+       FROM json_each(NEW.ingredients)),
+    NEW.notes,
+    (SELECT group_concat(value, ' ') FROM json_each(NEW.instructions))
+  );
+END;
+
+CREATE TRIGGER recipes_fts_delete AFTER DELETE ON recipes BEGIN
+  DELETE FROM recipes_fts WHERE rowid = OLD.id;
+END;
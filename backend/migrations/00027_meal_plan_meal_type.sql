@@ -0,0 +1,59 @@
+-- Meal slots: the same recipe may be planned for lunch and dinner on one
+-- day. Existing entries become dinner. SQLite can't change a UNIQUE
+-- constraint in place, so the table is rebuilt; the shopping view reads
+-- meal_plan and is recreated around it.
+DROP VIEW IF EXISTS shopping_items_view;
+
+CREATE TABLE meal_plan_new (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  day        TEXT    NOT NULL,              -- 'YYYY-MM-DD'
+  recipe_id  INTEGER NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+  title      TEXT    NOT NULL,
+  meal_type  TEXT    NOT NULL DEFAULT 'dinner'
+             CHECK (meal_type IN ('breakfast', 'lunch', 'dinner', 'snack')),
+
+  UNIQUE(day, recipe_id, meal_type)
+);
+
+INSERT INTO meal_plan_new (id, day, recipe_id, title)
+SELECT id, day, recipe_id, title FROM meal_plan;
+
+DROP TABLE meal_plan;
+ALTER TABLE meal_plan_new RENAME TO meal_plan;
+
+CREATE INDEX IF NOT EXISTS idx_meal_plan_day ON meal_plan(day);
+
+CREATE VIEW shopping_items_view AS
+SELECT
+  si.id,
+  CASE
+    WHEN si.quantity IS NOT NULL AND si.unit IS NOT NULL AND si.unit <> ''
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.unit || ' ' || si.name
+    WHEN si.quantity IS NOT NULL
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.name
+    ELSE si.name
+  END AS text,
+  si.done,
+  si.category,
+  si.notes,
+  si.recipe_ids,
+  (
+    SELECT GROUP_CONCAT(
+      r.title || 
+      CASE 
+        WHEN mp.day IS NOT NULL THEN ' (' || mp.day || ')'
+        ELSE ''
+      END,
+      ', '
+    )
+    FROM recipes r
+    JOIN json_each(si.recipe_ids) je ON r.id = je.value
+    LEFT JOIN (
+      SELECT recipe_id, MIN(day) as day
+      FROM meal_plan
+      WHERE date(day) >= date('now')
+      GROUP BY recipe_id
+    ) mp ON r.id = mp.recipe_id
+  ) AS recipe_titles,
+  si.store_id
+FROM shopping_items si;
@@ -0,0 +1,2 @@
+-- Display units for recipes; stored quantities stay metric.
+INSERT OR IGNORE INTO settings (key, value) VALUES ('unit_system', 'metric');
@@ -0,0 +1,52 @@
+-- Manual order within a category. New rows go to the end; existing rows keep
+-- their id order.
+DROP VIEW IF EXISTS shopping_items_view;
+
+ALTER TABLE shopping_items ADD COLUMN position REAL;
+UPDATE shopping_items SET position = id;
+
+-- Fill the position of every insert here rather than in each handler.
+CREATE TRIGGER shopping_items_default_position
+AFTER INSERT ON shopping_items
+WHEN NEW.position IS NULL
+BEGIN
+  UPDATE shopping_items
+     SET position = (SELECT COALESCE(MAX(position), 0) + 1 FROM shopping_items)
+   WHERE id = NEW.id;
+END;
+
+CREATE VIEW shopping_items_view AS
+SELECT
+  si.id,
+  CASE
+    WHEN si.quantity IS NOT NULL AND si.unit IS NOT NULL AND si.unit <> ''
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.unit || ' ' || si.name
+    WHEN si.quantity IS NOT NULL
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.name
+    ELSE si.name
+  END AS text,
+  si.done,
+  si.category,
+  si.notes,
+  si.recipe_ids,
+  (
+    SELECT GROUP_CONCAT(
+      r.title || 
+      CASE 
+        WHEN mp.day IS NOT NULL THEN ' (' || mp.day || ')'
+        ELSE ''
+      END,
+      ', '
+    )
+    FROM recipes r
+    JOIN json_each(si.recipe_ids) je ON r.id = je.value
+    LEFT JOIN (
+      SELECT recipe_id, MIN(day) as day
+      FROM meal_plan
+      WHERE date(day) >= date('now')
+      GROUP BY recipe_id
+    ) mp ON r.id = mp.recipe_id
+  ) AS recipe_titles,
+  si.store_id,
+  COALESCE(si.position, si.id) AS position
+FROM shopping_items si;
@@ -0,0 +1,8 @@
+-- Staples that are always at home; recipe merges leave them off the list.
+-- `name` is stored normalized (see units::normalize_name) and matched exactly.
+CREATE TABLE pantry_items (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  name       TEXT NOT NULL UNIQUE,
+  note       TEXT NOT NULL DEFAULT '',
+  created_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
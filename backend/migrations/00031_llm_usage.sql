@@ -0,0 +1,13 @@
+-- One row per successful LLM call, from the provider's `usage` object.
+-- `route` is a short label for the feature that made the call.
+CREATE TABLE llm_usage (
+  id                INTEGER PRIMARY KEY AUTOINCREMENT,
+  created_at        TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP),
+  route             TEXT NOT NULL,
+  model             TEXT NOT NULL,
+  prompt_tokens     INTEGER NOT NULL DEFAULT 0,
+  completion_tokens INTEGER NOT NULL DEFAULT 0,
+  recipe_id         INTEGER REFERENCES recipes(id) ON DELETE SET NULL
+);
+
+CREATE INDEX llm_usage_created_at ON llm_usage(created_at);
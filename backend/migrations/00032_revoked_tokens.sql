@@ -0,0 +1,7 @@
+-- Logged-out login tokens, by their `jti` claim. A row is only needed until
+-- the token is past `expires_at` (unix seconds) plus the refresh grace window.
+CREATE TABLE revoked_tokens (
+  jti        TEXT PRIMARY KEY,
+  expires_at INTEGER NOT NULL,
+  revoked_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
@@ -0,0 +1,11 @@
+-- Single-row login state. A password set through the API or
+-- `blaz reset-password` overrides BLAZ_PASSWORD_HASH. Bumping
+-- `token_version` invalidates every login token issued before.
+CREATE TABLE auth_credentials (
+  id            INTEGER PRIMARY KEY CHECK (id = 1),
+  password_hash TEXT,
+  token_version INTEGER NOT NULL DEFAULT 0,
+  updated_at    TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
+
+INSERT INTO auth_credentials (id) VALUES (1);
@@ -0,0 +1,3 @@
+-- First characters of each API token (e.g. "blz_a1B2"), so the token list
+-- can tell tokens apart without showing them. Empty for older tokens.
+ALTER TABLE api_tokens ADD COLUMN token_prefix TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,7 @@
+-- Favorites and a cooking history per recipe. `meal_plan.cooked` marks
+-- entries already counted by POST /meal-plan/{day}/complete.
+ALTER TABLE recipes ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE recipes ADD COLUMN last_cooked_at TEXT;
+ALTER TABLE recipes ADD COLUMN times_cooked INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE meal_plan ADD COLUMN cooked INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- Cooking journal: one row each time a recipe was made, with an optional
+-- 1-5 rating and a note ("too salty").
+CREATE TABLE recipe_logs (
+  id         INTEGER PRIMARY KEY AUTOINCREMENT,
+  recipe_id  INTEGER NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+  date       TEXT    NOT NULL,              -- 'YYYY-MM-DD'
+  rating     INTEGER CHECK (rating BETWEEN 1 AND 5),
+  note       TEXT    NOT NULL DEFAULT '',
+  created_at TEXT    NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
+
+CREATE INDEX recipe_logs_recipe_date ON recipe_logs(recipe_id, date);
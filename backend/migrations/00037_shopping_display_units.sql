@@ -0,0 +1,45 @@
+-- The quantity and unit as written ("2 cups") for units stored converted
+-- (480 ml). Both NULL when the canonical form is what was written, or when
+-- merged amounts were written in different units.
+DROP VIEW IF EXISTS shopping_items_view;
+
+ALTER TABLE shopping_items ADD COLUMN display_unit TEXT;
+ALTER TABLE shopping_items ADD COLUMN display_qty REAL;
+
+CREATE VIEW shopping_items_view AS
+SELECT
+  si.id,
+  CASE
+    WHEN si.quantity IS NOT NULL AND si.display_qty IS NOT NULL AND si.display_unit IS NOT NULL
+      THEN TRIM(printf('%g', si.display_qty)) || ' ' || si.display_unit || ' ' || si.name
+    WHEN si.quantity IS NOT NULL AND si.unit IS NOT NULL AND si.unit <> ''
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.unit || ' ' || si.name
+    WHEN si.quantity IS NOT NULL
+      THEN TRIM(printf('%g', si.quantity)) || ' ' || si.name
+    ELSE si.name
+  END AS text,
+  si.done,
+  si.category,
+  si.notes,
+  si.recipe_ids,
+  (
+    SELECT GROUP_CONCAT(
+      r.title || 
+      CASE 
+        WHEN mp.day IS NOT NULL THEN ' (' || mp.day || ')'
+        ELSE ''
+      END,
+      ', '
+    )
+    FROM recipes r
+    JOIN json_each(si.recipe_ids) je ON r.id = je.value
+    LEFT JOIN (
+      SELECT recipe_id, MIN(day) as day
+      FROM meal_plan
+      WHERE date(day) >= date('now')
+      GROUP BY recipe_id
+    ) mp ON r.id = mp.recipe_id
+  ) AS recipe_titles,
+  si.store_id,
+  COALESCE(si.position, si.id) AS position
+FROM shopping_items si;
@@ -0,0 +1,6 @@
+-- Data backfills that need the Rust parsers, so they run at startup rather
+-- than here. Each one records its name once done and never runs again.
+CREATE TABLE backfills (
+  name    TEXT PRIMARY KEY,
+  done_at TEXT NOT NULL DEFAULT (CURRENT_TIMESTAMP)
+);
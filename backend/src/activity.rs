@@ -0,0 +1,158 @@
+//! Activity log: who changed which recipe, shopping item, meal plan entry or
+//! setting, and when.
+//!
+//! Writes are best effort. A failed log write is reported with a warning and
+//! never fails the change it describes.
+
+use std::convert::Infallible;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use serde::Deserialize;
+use sqlx::SqliteConnection;
+
+use crate::models::AppState;
+
+/// Who made a change. `require_auth` attaches it to the request; handlers
+/// take it as an extractor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Actor {
+    /// Logged in with the password.
+    User,
+    /// An API token, by id.
+    ApiToken(i64),
+    /// A recipe import writing on the user's behalf.
+    Import,
+}
+
+impl Actor {
+    fn label(self) -> String {
+        match self {
+            Self::User => "user".to_string(),
+            Self::ApiToken(id) => format!("api_token:{id}"),
+            Self::Import => "import".to_string(),
+        }
+    }
+}
+
+/// Falls back to [`Actor::User`] outside the auth middleware (public routes).
+impl<S: Send + Sync> FromRequestParts<S> for Actor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<Self>()
+            .copied()
+            .unwrap_or(Self::User))
+    }
+}
+
+/// What kind of row an entry is about; `?entity=` takes the same names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Entity {
+    Recipe,
+    ShoppingItem,
+    MealPlan,
+    Settings,
+    Auth,
+    ApiToken,
+}
+
+impl Entity {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Recipe => "recipe",
+            Self::ShoppingItem => "shopping_item",
+            Self::MealPlan => "meal_plan",
+            Self::Settings => "settings",
+            Self::Auth => "auth",
+            Self::ApiToken => "api_token",
+        }
+    }
+}
+
+/// One change to record.
+pub struct Event {
+    pub entity: Entity,
+    pub entity_id: Option<i64>,
+    /// Short verb such as `create`, `update` or `delete`.
+    pub action: &'static str,
+    /// Human-readable description, e.g. "Updated 'Soup': ingredients".
+    pub summary: String,
+}
+
+impl Event {
+    pub fn new(
+        entity: Entity,
+        entity_id: impl Into<Option<i64>>,
+        action: &'static str,
+        summary: impl Into<String>,
+    ) -> Self {
+        Self {
+            entity,
+            entity_id: entity_id.into(),
+            action,
+            summary: summary.into(),
+        }
+    }
+}
+
+/// Record `event` on its own connection.
+pub async fn record(state: &AppState, actor: Actor, event: Event) {
+    match state.pool.acquire().await {
+        Ok(mut conn) => record_in(&mut conn, state, actor, event).await,
+        Err(e) => tracing::warn!(error = %e, "failed to record activity"),
+    }
+}
+
+/// Record `event` on `conn`, typically the handler's own transaction so the
+/// entry commits or rolls back with the change.
+pub async fn record_in(conn: &mut SqliteConnection, state: &AppState, actor: Actor, event: Event) {
+    if let Err(e) = write(conn, state.config.activity_log_max_rows, actor, &event).await {
+        tracing::warn!(
+            error = %e,
+            entity = event.entity.as_str(),
+            action = event.action,
+            "failed to record activity"
+        );
+    }
+}
+
+async fn write(
+    conn: &mut SqliteConnection,
+    max_rows: u32,
+    actor: Actor,
+    event: &Event,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r"INSERT INTO activity_log (actor, action, entity_type, entity_id, summary)
+          VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(actor.label())
+    .bind(event.action)
+    .bind(event.entity.as_str())
+    .bind(event.entity_id)
+    .bind(&event.summary)
+    .execute(&mut *conn)
+    .await?;
+
+    prune(conn, max_rows).await
+}
+
+/// Delete all but the newest `max_rows` entries; 0 keeps everything.
+async fn prune(conn: &mut SqliteConnection, max_rows: u32) -> sqlx::Result<()> {
+    if max_rows == 0 {
+        return Ok(());
+    }
+    sqlx::query(
+        r"DELETE FROM activity_log
+           WHERE id <= (SELECT id FROM activity_log ORDER BY id DESC LIMIT 1 OFFSET ?)",
+    )
+    .bind(max_rows)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
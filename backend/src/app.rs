@@ -3,22 +3,28 @@ use crate::{
     auth_middleware::require_auth,
     config::Config,
     embedded_web::serve_embedded_web,
-    logging::{access_log, log_payloads},
+    logging::{AccessLogOptions, access_log, log_payloads},
+    metrics::{self, track_http},
     models::AppState,
     routes::{
-        categories, import_recipe_images, import_recipesage, llm_credits, meal_plan, parse_recipe,
-        recipes, settings, share_recipe, shopping,
+        activity, api_tokens, app_state, categories, db_backup, db_check, digest,
+        import_recipe_images, import_recipesage, ingredient_suggest, llm_credits, llm_models,
+        llm_usage, meal_plan, meal_plan_export, media_gc, pantry, parse_recipe, recipe_backup,
+        recipe_logs, recipe_suggest, recipes, settings, share_recipe, shopping, stores,
     },
 };
 
-use axum::extract::DefaultBodyLimit;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::HeaderValue;
 use axum::middleware::{from_fn, from_fn_with_state};
 use axum::routing::{delete, get, patch, post};
 use axum::{Json, Router};
 use serde::Serialize;
 
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::cors::{AllowHeaders, AllowMethods, Any, CorsLayer};
 use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::services::ServeDir;
 
@@ -26,6 +32,23 @@ async fn healthz() -> Json<&'static str> {
     Json("ok")
 }
 
+#[derive(Serialize)]
+struct Readiness {
+    /// `ready`, or `degraded` when some features are unavailable.
+    status: &'static str,
+    media: bool,
+}
+
+/// Always 200: a degraded server still serves everything but images, so it
+/// should stay in rotation. The body says what is missing.
+async fn readyz(State(state): State<AppState>) -> Json<Readiness> {
+    let media = state.media.is_available();
+    Json(Readiness {
+        status: if media { "ready" } else { "degraded" },
+        media,
+    })
+}
+
 #[derive(Serialize)]
 struct VersionInfo {
     version: &'static str,
@@ -38,92 +61,155 @@ async fn version() -> Json<VersionInfo> {
 }
 
 fn cors_layer(config: &Config) -> CorsLayer {
-    let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
-
-    if let Some(origin) = &config.cors_origin {
-        // Specific origin for production
-        cors.allow_origin(
-            origin
-                .parse::<axum::http::HeaderValue>()
-                .expect("Invalid CORS origin"),
-        )
-    } else {
+    if config.cors_allows_any() {
         // Allow any origin (development only)
         tracing::warn!("CORS configured to allow any origin - not secure for production!");
-        cors.allow_origin(Any)
+        return CorsLayer::new()
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .allow_origin(Any);
     }
+    // Specific origins for production. Credentials can't be combined with
+    // wildcards, so methods and headers mirror the preflight instead.
+    let origins: Vec<HeaderValue> = config
+        .cors_origins
+        .iter()
+        .map(|o| HeaderValue::from_str(o).expect("validated by parse_cors_origin"))
+        .collect();
+    CorsLayer::new()
+        .allow_methods(AllowMethods::mirror_request())
+        .allow_headers(AllowHeaders::mirror_request())
+        .allow_origin(origins)
+        .allow_credentials(true)
 }
 
-#[allow(clippy::needless_pass_by_value)] // Axum requires AppState ownership
-pub fn build_app(state: AppState) -> Router {
-    let media_service = ServeDir::new(state.config.media_dir.clone());
+/// Responses smaller than this are sent as-is; compressing them costs more than it saves.
+const COMPRESSION_MIN_SIZE: u16 = 1024;
 
-    let request_id_layer = ServiceBuilder::new()
-        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
-        .layer(PropagateRequestIdLayer::x_request_id());
+/// gzip/brotli for API responses. Images (webp media) are already compressed
+/// and SSE streams must not be buffered, so both are skipped.
+fn compression_layer() -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(COMPRESSION_MIN_SIZE)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE);
 
-    // Public routes (no authentication required)
-    let public_routes = Router::new()
-        .route("/healthz", get(healthz))
-        .route("/version", get(version))
-        .route("/auth/login", post(auth::login))
-        .route("/api/share/{token}", get(share_recipe::get_shared_recipe))
-        .route("/recipes", get(recipes::list))
-        .route("/recipes/{id}", get(recipes::get));
+    CompressionLayer::new()
+        .gzip(true)
+        .br(true)
+        .compress_when(predicate)
+}
 
-    // Protected routes (authentication required)
-    let protected_routes = Router::new()
-        .route("/recipes", post(recipes::create))
-        .route("/recipes/deleted", get(recipes::list_deleted))
-        .route("/recipes/check-duplicate", post(recipes::check_duplicate))
+/// Meal plan and its exports (protected).
+fn meal_plan_routes() -> Router<AppState> {
+    Router::new()
         .route(
-            "/recipes/{id}",
-            delete(recipes::delete).patch(recipes::update),
+            "/meal-plan",
+            get(meal_plan::get_for_day).post(meal_plan::assign),
         )
-        .route("/recipes/{id}/restore", post(recipes::restore))
-        .route("/recipes/{id}/permanent", delete(recipes::permanent_delete))
-        .route("/recipes/{id}/image", post(recipes::upload_image))
+        .route("/meal-plan/reminders", get(meal_plan::list_reminders))
+        .route("/meal-plan/autofill", post(meal_plan::autofill))
+        .route("/meal-plan/nutrition", get(meal_plan::nutrition))
+        .route("/meal-plan/{day}/complete", post(meal_plan::complete_day))
+        .route("/meal-plan/export", get(meal_plan_export::export_week))
         .route(
-            "/recipes/{id}/share",
-            post(share_recipe::create_share_token).delete(share_recipe::revoke_share_token),
+            "/meal-plan/recipe/{recipe_id}",
+            get(meal_plan::get_for_recipe),
         )
         .route(
-            "/recipes/{id}/macros/estimate",
-            post(recipes::estimate_macros),
+            "/meal-plan/{day}/{recipe_id}",
+            delete(meal_plan::unassign).patch(meal_plan::move_entry),
         )
+}
+
+/// Bodies of JSON routes; uploads and bulk imports set their own limits.
+const JSON_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Multipart photo uploads: up to three photos for an import.
+const UPLOAD_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Recipe image upload and maintenance (protected).
+fn recipe_image_routes(config: &Config) -> Router<AppState> {
+    // Leave room for `--max-image-upload-mb`: past it the handler answers
+    // 413 itself.
+    let upload_limit = usize::try_from(config.max_image_upload_mb.saturating_mul(1024 * 1024))
+        .unwrap_or(usize::MAX)
+        .saturating_add(1024 * 1024)
+        .max(UPLOAD_BODY_LIMIT);
+    Router::new()
         .route(
-            "/recipes/{id}/reparse-ingredients",
-            post(recipes::reparse_ingredients),
+            "/recipes/{id}/image",
+            post(recipes::upload_image)
+                .layer(DefaultBodyLimit::max(upload_limit))
+                .delete(recipes::delete_image),
         )
-        .route("/recipes/import", post(parse_recipe::import_from_url))
         .route(
-            "/recipes/import/images",
-            post(import_recipe_images::import_from_images),
+            "/recipes/{id}/image/regenerate",
+            post(recipes::regenerate_image),
         )
         .route(
-            "/recipes/import/recipesage",
-            post(import_recipesage::import_recipesage),
+            "/recipes/{id}/image/retry",
+            post(parse_recipe::retry_image_import),
         )
+}
+
+/// LLM macro estimation, per recipe or in the background (protected).
+fn macro_routes() -> Router<AppState> {
+    Router::new()
         .route(
-            "/meal-plan",
-            get(meal_plan::get_for_day).post(meal_plan::assign),
+            "/recipes/{id}/macros/estimate",
+            post(recipes::estimate_macros),
         )
-        .route("/meal-plan/reminders", get(meal_plan::list_reminders))
         .route(
-            "/meal-plan/recipe/{recipe_id}",
-            get(meal_plan::get_for_recipe),
+            "/recipes/macros/estimate-all",
+            post(recipes::estimate_all_macros),
         )
         .route(
-            "/meal-plan/{day}/{recipe_id}",
-            delete(meal_plan::unassign).patch(meal_plan::move_entry),
+            "/recipes/macros/estimate-all/status",
+            get(recipes::estimate_all_macros_status),
         )
+}
+
+/// Shopping list (protected).
+fn shopping_routes() -> Router<AppState> {
+    Router::new()
         .route("/shopping", get(shopping::list).post(shopping::create))
         .route("/shopping/all-texts", get(shopping::list_all_texts))
+        .route("/shopping/events", get(shopping::events))
         .route(
             "/shopping/{id}",
             patch(shopping::patch_shopping_item).delete(shopping::delete),
         )
+        .route("/shopping/bulk", post(shopping::bulk))
+        .route("/shopping/reorder", post(shopping::reorder))
+        .route("/shopping/recategorize", post(shopping::recategorize))
         .route("/shopping/merge", post(shopping::merge_items))
+        .route("/shopping/combine", post(shopping::combine_items))
+        .route("/shopping/generations", get(shopping::list_generations))
+        .route("/shopping/suggest", get(shopping::suggest))
+}
+
+/// Stores and their per-store category order (protected).
+fn store_routes() -> Router<AppState> {
+    Router::new()
+        .route("/stores", get(stores::list).post(stores::create))
+        .route("/stores/{id}", patch(stores::update).delete(stores::delete))
+        .route(
+            "/stores/{id}/category-order",
+            get(stores::get_category_order).put(stores::set_category_order),
+        )
+}
+
+/// Pantry staples left off recipe merges (protected).
+fn pantry_routes() -> Router<AppState> {
+    Router::new()
+        .route("/pantry", get(pantry::list).post(pantry::create))
+        .route("/pantry/{id}", patch(pantry::update).delete(pantry::delete))
+}
+
+/// Shopping category management and classification (protected).
+fn category_routes() -> Router<AppState> {
+    Router::new()
         .route(
             "/categories",
             get(categories::list).post(categories::create),
@@ -133,19 +219,173 @@ pub fn build_app(state: AppState) -> Router {
             patch(categories::update).delete(categories::delete),
         )
         .route("/categories/reorder", post(categories::reorder))
+        .route("/categories/classify", get(categories::classify))
+        .route(
+            "/categories/{name}/keywords",
+            get(categories::list_keywords).post(categories::add_keyword),
+        )
+        .route(
+            "/categories/{name}/keywords/{keyword}",
+            delete(categories::delete_keyword),
+        )
+}
+
+/// Backups and `RecipeSage` exports with inlined images easily outgrow the
+/// global body limit.
+const BULK_IMPORT_BODY_LIMIT: usize = 1024 * 1024 * 1024;
+
+/// Recipe importers and the backup export (protected).
+fn import_routes() -> Router<AppState> {
+    Router::new()
+        .route("/recipes/export", get(recipe_backup::export))
+        .route(
+            "/recipes/import-backup",
+            post(recipe_backup::import_backup).layer(DefaultBodyLimit::max(BULK_IMPORT_BODY_LIMIT)),
+        )
+        .route("/recipes/import", post(parse_recipe::import_from_url))
+        .route("/recipes/import/text", post(parse_recipe::import_from_text))
+        .route(
+            "/recipes/import/images",
+            post(import_recipe_images::import_from_images)
+                .layer(DefaultBodyLimit::max(UPLOAD_BODY_LIMIT)),
+        )
+        .route(
+            "/recipes/import/recipesage",
+            post(import_recipesage::import_recipesage)
+                .layer(DefaultBodyLimit::max(BULK_IMPORT_BODY_LIMIT)),
+        )
+        .route(
+            "/recipes/import/recipesage/stream",
+            post(import_recipesage::import_recipesage_stream)
+                .layer(DefaultBodyLimit::max(BULK_IMPORT_BODY_LIMIT)),
+        )
+}
+
+/// Routes that need no authentication.
+fn public_routes() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/version", get(version))
+        .route("/metrics", get(metrics::scrape))
+        .route("/auth/login", post(auth::login))
+        .route("/auth/refresh", post(auth::refresh))
+        .route("/auth/logout", post(auth::logout))
+        .route("/api/share/{token}", get(share_recipe::get_shared_recipe))
+        .route("/share/{token}", get(share_recipe::share_page))
+        .route("/share/{token}/card.png", get(share_recipe::share_card))
+        .route("/share/{token}/html", get(share_recipe::share_html))
+        // Loaded by <img> tags, which can't send a token; the same files are
+        // public under /media anyway.
+        .route("/recipes/{id}/image", get(recipes::image))
+}
+
+#[allow(clippy::needless_pass_by_value)] // Axum requires AppState ownership
+pub fn build_app(state: AppState) -> Router {
+    let media_service = ServiceBuilder::new()
+        .layer(from_fn_with_state(
+            state.clone(),
+            crate::media_health::require_media,
+        ))
+        .service(ServeDir::new(state.config.media_dir.clone()));
+
+    let request_id_layer = ServiceBuilder::new()
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .layer(PropagateRequestIdLayer::x_request_id());
+
+    // Protected routes (authentication required)
+    let protected_routes = Router::new()
+        .route("/recipes", get(recipes::list).post(recipes::create))
+        .route("/recipes/equipment", get(recipes::list_equipment))
+        .route("/recipes/deleted", get(recipes::list_deleted))
+        .route("/recipes/check-duplicate", post(recipes::check_duplicate))
+        .route("/recipes/bulk", post(recipes::bulk_update))
+        .route("/recipes/suggest", post(recipe_suggest::suggest))
+        .route("/ingredients/suggest", get(ingredient_suggest::suggest))
+        .route(
+            "/recipes/{id}",
+            get(recipes::get)
+                .delete(recipes::delete)
+                .patch(recipes::update),
+        )
+        .route("/recipes/{id}/scaled", get(recipes::scaled))
+        .route("/recipes/{id}/restore", post(recipes::restore))
+        .route("/recipes/{id}/cooked", post(recipes::mark_cooked))
+        .route(
+            "/recipes/{id}/logs",
+            get(recipe_logs::list).post(recipe_logs::create),
+        )
+        .route("/recipes/{id}/logs/{log_id}", delete(recipe_logs::delete))
+        .route("/recipes/{id}/permanent", delete(recipes::permanent_delete))
+        .route("/recipes/{id}/jsonld", get(share_recipe::get_recipe_jsonld))
+        .route("/recipes/{id}/export", get(share_recipe::export_recipe))
+        .route(
+            "/recipes/{id}/share",
+            post(share_recipe::create_share_token).delete(share_recipe::revoke_share_token),
+        )
+        .route(
+            "/recipes/{id}/reparse-ingredients",
+            post(recipes::reparse_ingredients),
+        )
+        .route(
+            "/recipes/{id}/add-to-shopping",
+            post(recipes::add_to_shopping),
+        )
+        .merge(recipe_image_routes(&state.config))
+        .merge(macro_routes())
+        .merge(import_routes())
+        .merge(meal_plan_routes())
+        .merge(shopping_routes())
+        .merge(pantry_routes())
+        .merge(category_routes())
+        .merge(store_routes())
         .route("/llm/credits", get(llm_credits::get))
+        .route("/llm/models", get(llm_models::list))
+        .route("/llm/usage", get(llm_usage::get))
+        .route("/app-state", get(app_state::get))
+        .route("/admin/digest/send-now", post(digest::send_now))
+        .route("/admin/media/gc", post(media_gc::collect))
+        .route("/admin/backup", post(db_backup::create))
+        .route("/admin/backup/latest", get(db_backup::latest))
+        .route("/admin/backups", get(db_backup::list))
+        .route("/admin/db/check", get(db_check::check))
+        .route("/admin/db/repair", post(db_check::repair))
         .route("/settings", get(settings::get_all).patch(settings::update))
+        .route("/activity", get(activity::list))
+        .route(
+            "/auth/tokens",
+            get(api_tokens::list).post(api_tokens::create),
+        )
+        .route("/auth/tokens/{id}", delete(api_tokens::delete))
+        .route(
+            "/auth/api-keys",
+            get(api_tokens::list).post(api_tokens::create),
+        )
+        .route("/auth/api-keys/{id}", delete(api_tokens::delete))
+        .route("/auth/change-password", post(auth::change_password))
         .route_layer(from_fn_with_state(state.clone(), require_auth));
 
-    Router::new()
-        .merge(public_routes)
+    let app = Router::new()
+        .merge(public_routes())
         .merge(protected_routes)
         .nest_service("/media", media_service)
         .fallback(serve_embedded_web)
         .with_state(state.clone())
-        .layer(DefaultBodyLimit::max(50 * 1024 * 1024)) // 50MB for large imports
+        .layer(DefaultBodyLimit::max(JSON_BODY_LIMIT))
         .layer(request_id_layer)
-        .layer(from_fn(access_log))
+        .layer(from_fn_with_state(
+            AccessLogOptions::from_config(&state.config),
+            access_log,
+        ))
         .layer(from_fn(log_payloads))
-        .layer(cors_layer(&state.config))
+        .layer(from_fn(track_http));
+
+    // Compression sits outside the payload logger so logged previews stay readable.
+    let app = if state.config.disable_compression {
+        app
+    } else {
+        app.layer(compression_layer())
+    };
+
+    app.layer(cors_layer(&state.config))
 }
@@ -1,48 +1,188 @@
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
+    http::{HeaderMap, Method, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
-use serde::Deserialize;
+use jsonwebtoken::{Algorithm, Validation, decode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::models::AppState;
+use crate::activity::Actor;
+use crate::models::{AppState, TokenScope};
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-struct Claims {
-    sub: i64,
-    exp: u64,
+/// Prefix that marks a bearer token as an API token rather than a JWT.
+pub const API_TOKEN_PREFIX: &str = "blz_";
+
+/// `api_tokens.last_used_at` is only rewritten once it is this old, so busy
+/// scripts don't turn every request into a database write.
+const LAST_USED_RESOLUTION_SECS: u32 = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: i64, // Always 1 for single-user
+    pub exp: u64,
+    #[serde(default)]
+    pub iat: u64,
+    /// Identifies the token for logout. Tokens issued before logout existed
+    /// have none and can't be revoked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// `auth_credentials.token_version` at issue time; a password change
+    /// bumps it and so invalidates the token.
+    #[serde(default)]
+    pub tv: i64,
+}
+
+/// The token of an `Authorization: Bearer <token>` header.
+#[must_use]
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())?
+        .strip_prefix("Bearer ")
+}
+
+/// Decode and verify a login JWT, also accepting tokens that expired less
+/// than `extra_leeway` seconds ago (on top of the configured clock leeway).
+/// Revoked tokens and tokens from before the last password change are
+/// rejected.
+///
+/// # Errors
+/// 401 for invalid, expired, revoked or outdated tokens; 500 if the lookup
+/// fails.
+pub async fn verify_jwt(
+    state: &AppState,
+    token: &str,
+    extra_leeway: u64,
+) -> Result<Claims, StatusCode> {
+    // Tolerate client clocks running a little ahead of the server.
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.leeway = state.config.jwt_leeway_secs.saturating_add(extra_leeway);
+    let claims = decode::<Claims>(token, &state.jwt_decoding, &validation)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?
+        .claims;
+
+    let (token_version, revoked): (i64, bool) = sqlx::query_as(
+        r"SELECT (SELECT token_version FROM auth_credentials WHERE id = 1),
+                 EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = ?)",
+    )
+    .bind(&claims.jti)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if revoked || claims.tv != token_version {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(claims)
 }
 
 pub async fn require_auth(
     State(state): State<AppState>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    // Extract token from Authorization header
-    let auth_header = request
-        .headers()
-        .get("authorization")
-        .and_then(|v| v.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let token = bearer_token(request.headers())
+        .ok_or(StatusCode::UNAUTHORIZED)?
+        .to_owned();
 
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    // Decode and verify JWT using the config's JWT secret
-    let jwt_secret = state
-        .config
-        .jwt_secret
-        .as_ref()
-        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
-    let decoding_key = DecodingKey::from_secret(jwt_secret.as_bytes());
+    if token.starts_with(API_TOKEN_PREFIX) {
+        let (id, scopes) = verify_api_token(&state, &token).await?;
+        if !scopes
+            .iter()
+            .any(|s| scope_allows(*s, request.method(), request.uri().path()))
+        {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        request.extensions_mut().insert(Actor::ApiToken(id));
+        return Ok(next.run(request).await);
+    }
 
-    decode::<Claims>(token, &decoding_key, &Validation::new(Algorithm::HS256))
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    verify_jwt(&state, &token, 0).await?;
 
+    request.extensions_mut().insert(Actor::User);
     Ok(next.run(request).await)
 }
+
+/// Hex SHA-256 of a full API token, as stored in `api_tokens.token_hash`.
+#[must_use]
+pub fn hash_api_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Look up an API token by hash and record its use in the background (at
+/// most once per [`LAST_USED_RESOLUTION_SECS`]). Returns the token's id and
+/// scopes.
+async fn verify_api_token(
+    state: &AppState,
+    token: &str,
+) -> Result<(i64, Vec<TokenScope>), StatusCode> {
+    let hash = hash_api_token(token);
+    let row: Option<(i64, sqlx::types::Json<Vec<TokenScope>>, bool)> = sqlx::query_as(
+        r"SELECT id, scopes,
+                 last_used_at IS NULL OR last_used_at <= datetime('now', ?)
+            FROM api_tokens
+           WHERE token_hash = ?",
+    )
+    .bind(format!("-{LAST_USED_RESOLUTION_SECS} seconds"))
+    .bind(&hash)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (id, sqlx::types::Json(scopes), due) = row.ok_or(StatusCode::UNAUTHORIZED)?;
+    if !due {
+        return Ok((id, scopes));
+    }
+
+    let pool = state.pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) =
+            sqlx::query(r"UPDATE api_tokens SET last_used_at = CURRENT_TIMESTAMP WHERE id = ?")
+                .bind(id)
+                .execute(&pool)
+                .await
+        {
+            tracing::warn!(token_id = id, error = %e, "failed to record API token use");
+        }
+    });
+
+    Ok((id, scopes))
+}
+
+/// Whether an API token scope permits a request. Token management,
+/// password changes and admin endpoints (backups hold every secret) are
+/// only available with a login session.
+fn scope_allows(scope: TokenScope, method: &Method, path: &str) -> bool {
+    if path.starts_with("/auth/") || path.starts_with("/admin/") {
+        return false;
+    }
+    match scope {
+        TokenScope::Read => matches!(*method, Method::GET | Method::HEAD),
+        TokenScope::Write => true,
+        TokenScope::ShoppingOnly => path == "/shopping" || path.starts_with("/shopping/"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_allows() {
+        use TokenScope::{Read, ShoppingOnly, Write};
+
+        assert!(scope_allows(Read, &Method::GET, "/recipes"));
+        assert!(!scope_allows(Read, &Method::POST, "/shopping"));
+        assert!(scope_allows(Write, &Method::POST, "/recipes"));
+        assert!(scope_allows(ShoppingOnly, &Method::POST, "/shopping"));
+        assert!(scope_allows(ShoppingOnly, &Method::PATCH, "/shopping/3"));
+        assert!(!scope_allows(ShoppingOnly, &Method::GET, "/shoppingx"));
+        assert!(!scope_allows(ShoppingOnly, &Method::POST, "/recipes"));
+        assert!(!scope_allows(Write, &Method::GET, "/auth/tokens"));
+        assert!(!scope_allows(Write, &Method::POST, "/auth/change-password"));
+        assert!(!scope_allows(Read, &Method::GET, "/admin/backup/latest"));
+        assert!(!scope_allows(Write, &Method::POST, "/admin/db/repair"));
+    }
+}
@@ -1,9 +1,12 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::RwLock;
 
-use crate::llm::LlmClient;
+use crate::llm::{LlmClient, RetryPolicy};
 use crate::models::AppState;
-use crate::routes::settings::LlmSettings;
+use crate::routes::settings::{LlmSettings, get_setting};
 use crate::units::normalize_name;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -42,25 +45,6 @@ impl Category {
         Self::OnlineAlcohol,
     ];
 
-    pub const fn sort_key(self) -> u8 {
-        match self {
-            Self::Other => 0,
-            Self::Fruits => 1,
-            Self::Vegetables => 2,
-            Self::Bakery => 3,
-            Self::Vegan => 4,
-            Self::Drinks => 5,
-            Self::Alcohol => 6,
-            Self::Seasoning => 7,
-            Self::Canned => 8,
-            Self::Pantry => 9,
-            Self::NonFood => 10,
-            Self::Pharmacy => 11,
-            Self::Online => 12,
-            Self::OnlineAlcohol => 13,
-        }
-    }
-
     pub const fn as_str(self) -> &'static str {
         match self {
             Self::Other => "Other",
@@ -79,55 +63,514 @@ impl Category {
             Self::OnlineAlcohol => "Online Alcohol",
         }
     }
+}
+
+/* =========================
+ * Keyword classifier
+ * ========================= */
+
+/// Words that decide the category wherever they appear in the name
+/// ("vegan butter", "canned tuna"), checked before [`BUILTIN_KEYWORDS`].
+const MODIFIER_KEYWORDS: &[(&str, Category)] = &[
+    ("vegan", Category::Vegan),
+    ("plant based", Category::Vegan),
+    ("dairy free", Category::Vegan),
+    ("canned", Category::Canned),
+    ("tinned", Category::Canned),
+];
+
+/// Built-in keyword map. Keywords are singular and lowercase; plurals are
+/// matched by [`token_matches`]. Dairy, meat and fish have no category of
+/// their own and map to `Other` so they skip the LLM.
+const BUILTIN_KEYWORDS: &[(&str, Category)] = &[
+    // Fruits
+    ("apple", Category::Fruits),
+    ("apricot", Category::Fruits),
+    ("avocado", Category::Fruits),
+    ("banana", Category::Fruits),
+    ("blackberry", Category::Fruits),
+    ("blueberry", Category::Fruits),
+    ("cherry", Category::Fruits),
+    ("clementine", Category::Fruits),
+    ("coconut", Category::Fruits),
+    ("cranberry", Category::Fruits),
+    ("fig", Category::Fruits),
+    ("fruit", Category::Fruits),
+    ("grape", Category::Fruits),
+    ("grapefruit", Category::Fruits),
+    ("kiwi", Category::Fruits),
+    ("lemon", Category::Fruits),
+    ("lime", Category::Fruits),
+    ("mandarin", Category::Fruits),
+    ("mango", Category::Fruits),
+    ("melon", Category::Fruits),
+    ("nectarine", Category::Fruits),
+    ("orange", Category::Fruits),
+    ("papaya", Category::Fruits),
+    ("peach", Category::Fruits),
+    ("pear", Category::Fruits),
+    ("pineapple", Category::Fruits),
+    ("plum", Category::Fruits),
+    ("pomegranate", Category::Fruits),
+    ("raspberry", Category::Fruits),
+    ("rhubarb", Category::Fruits),
+    ("strawberry", Category::Fruits),
+    ("watermelon", Category::Fruits),
+    // Vegetables and fresh herbs
+    ("artichoke", Category::Vegetables),
+    ("arugula", Category::Vegetables),
+    ("asparagus", Category::Vegetables),
+    ("aubergine", Category::Vegetables),
+    ("basil", Category::Vegetables),
+    ("beet", Category::Vegetables),
+    ("beetroot", Category::Vegetables),
+    ("bell pepper", Category::Vegetables),
+    ("broccoli", Category::Vegetables),
+    ("cabbage", Category::Vegetables),
+    ("carrot", Category::Vegetables),
+    ("cauliflower", Category::Vegetables),
+    ("celery", Category::Vegetables),
+    ("chili", Category::Vegetables),
+    ("chive", Category::Vegetables),
+    ("cilantro", Category::Vegetables),
+    ("corn", Category::Vegetables),
+    ("courgette", Category::Vegetables),
+    ("cucumber", Category::Vegetables),
+    ("dill", Category::Vegetables),
+    ("eggplant", Category::Vegetables),
+    ("fennel", Category::Vegetables),
+    ("garlic", Category::Vegetables),
+    ("garlic clove", Category::Vegetables),
+    ("ginger", Category::Vegetables),
+    ("green bean", Category::Vegetables),
+    ("green pepper", Category::Vegetables),
+    ("jalapeño", Category::Vegetables),
+    ("kale", Category::Vegetables),
+    ("leek", Category::Vegetables),
+    ("lemongrass", Category::Vegetables),
+    ("lettuce", Category::Vegetables),
+    ("mint", Category::Vegetables),
+    ("mushroom", Category::Vegetables),
+    ("onion", Category::Vegetables),
+    ("parsley", Category::Vegetables),
+    ("parsnip", Category::Vegetables),
+    ("pea", Category::Vegetables),
+    ("potato", Category::Vegetables),
+    ("pumpkin", Category::Vegetables),
+    ("radish", Category::Vegetables),
+    ("red pepper", Category::Vegetables),
+    ("salad", Category::Vegetables),
+    ("scallion", Category::Vegetables),
+    ("shallot", Category::Vegetables),
+    ("spinach", Category::Vegetables),
+    ("spring onion", Category::Vegetables),
+    ("squash", Category::Vegetables),
+    ("tomato", Category::Vegetables),
+    ("turnip", Category::Vegetables),
+    ("yellow pepper", Category::Vegetables),
+    ("zucchini", Category::Vegetables),
+    // Bakery
+    ("bagel", Category::Bakery),
+    ("baguette", Category::Bakery),
+    ("bread", Category::Bakery),
+    ("brioche", Category::Bakery),
+    ("bun", Category::Bakery),
+    ("ciabatta", Category::Bakery),
+    ("croissant", Category::Bakery),
+    ("muffin", Category::Bakery),
+    ("naan", Category::Bakery),
+    ("pita", Category::Bakery),
+    ("sourdough", Category::Bakery),
+    ("tortilla", Category::Bakery),
+    // Vegan
+    ("almond milk", Category::Vegan),
+    ("hummus", Category::Vegan),
+    ("oat cream", Category::Vegan),
+    ("oat milk", Category::Vegan),
+    ("rice milk", Category::Vegan),
+    ("seitan", Category::Vegan),
+    ("soy milk", Category::Vegan),
+    ("soy yogurt", Category::Vegan),
+    ("tempeh", Category::Vegan),
+    ("tofu", Category::Vegan),
+    // Drinks
+    ("coffee", Category::Drinks),
+    ("coffee bean", Category::Drinks),
+    ("cola", Category::Drinks),
+    ("ginger beer", Category::Drinks),
+    ("juice", Category::Drinks),
+    ("kombucha", Category::Drinks),
+    ("lemonade", Category::Drinks),
+    ("soda", Category::Drinks),
+    ("tea", Category::Drinks),
+    ("water", Category::Drinks),
+    // Alcohol
+    ("beer", Category::Alcohol),
+    ("brandy", Category::Alcohol),
+    ("champagne", Category::Alcohol),
+    ("cider", Category::Alcohol),
+    ("gin", Category::Alcohol),
+    ("prosecco", Category::Alcohol),
+    ("rum", Category::Alcohol),
+    ("sake", Category::Alcohol),
+    ("tequila", Category::Alcohol),
+    ("vermouth", Category::Alcohol),
+    ("vodka", Category::Alcohol),
+    ("whiskey", Category::Alcohol),
+    ("whisky", Category::Alcohol),
+    ("wine", Category::Alcohol),
+    // Seasoning
+    ("bay leaf", Category::Seasoning),
+    ("black pepper", Category::Seasoning),
+    ("cardamom", Category::Seasoning),
+    ("cayenne", Category::Seasoning),
+    ("chili flake", Category::Seasoning),
+    ("chili powder", Category::Seasoning),
+    ("cinnamon", Category::Seasoning),
+    ("clove", Category::Seasoning),
+    ("cumin", Category::Seasoning),
+    ("curry powder", Category::Seasoning),
+    ("garam masala", Category::Seasoning),
+    ("garlic powder", Category::Seasoning),
+    ("ground coriander", Category::Seasoning),
+    ("ground ginger", Category::Seasoning),
+    ("nutmeg", Category::Seasoning),
+    ("onion powder", Category::Seasoning),
+    ("oregano", Category::Seasoning),
+    ("paprika", Category::Seasoning),
+    ("pepper", Category::Seasoning),
+    ("pepper flake", Category::Seasoning),
+    ("rosemary", Category::Seasoning),
+    ("salt", Category::Seasoning),
+    ("thyme", Category::Seasoning),
+    ("turmeric", Category::Seasoning),
+    ("vanilla", Category::Seasoning),
+    // Canned
+    ("baked bean", Category::Canned),
+    ("bean", Category::Canned),
+    ("black bean", Category::Canned),
+    ("chickpea", Category::Canned),
+    ("chopped tomato", Category::Canned),
+    ("coconut cream", Category::Canned),
+    ("coconut milk", Category::Canned),
+    ("diced tomato", Category::Canned),
+    ("kidney bean", Category::Canned),
+    ("passata", Category::Canned),
+    ("sweetcorn", Category::Canned),
+    ("tomato paste", Category::Canned),
+    ("tuna", Category::Canned),
+    // Pantry
+    ("almond", Category::Pantry),
+    ("baking powder", Category::Pantry),
+    ("baking soda", Category::Pantry),
+    ("bouillon", Category::Pantry),
+    ("breadcrumb", Category::Pantry),
+    ("broth", Category::Pantry),
+    ("cashew", Category::Pantry),
+    ("cereal", Category::Pantry),
+    ("chip", Category::Pantry),
+    ("chocolate", Category::Pantry),
+    ("cocoa", Category::Pantry),
+    ("cornstarch", Category::Pantry),
+    ("couscous", Category::Pantry),
+    ("cracker", Category::Pantry),
+    ("crisp", Category::Pantry),
+    ("flour", Category::Pantry),
+    ("honey", Category::Pantry),
+    ("jam", Category::Pantry),
+    ("ketchup", Category::Pantry),
+    ("lentil", Category::Pantry),
+    ("maple syrup", Category::Pantry),
+    ("mayonnaise", Category::Pantry),
+    ("mustard", Category::Pantry),
+    ("noodle", Category::Pantry),
+    ("oat", Category::Pantry),
+    ("oil", Category::Pantry),
+    ("olive", Category::Pantry),
+    ("pasta", Category::Pantry),
+    ("peanut", Category::Pantry),
+    ("peanut butter", Category::Pantry),
+    ("penne", Category::Pantry),
+    ("quinoa", Category::Pantry),
+    ("raisin", Category::Pantry),
+    ("rice", Category::Pantry),
+    ("sauce", Category::Pantry),
+    ("seed", Category::Pantry),
+    ("spaghetti", Category::Pantry),
+    ("stock", Category::Pantry),
+    ("sugar", Category::Pantry),
+    ("syrup", Category::Pantry),
+    ("vinegar", Category::Pantry),
+    ("walnut", Category::Pantry),
+    ("wine vinegar", Category::Pantry),
+    ("yeast", Category::Pantry),
+    // Non-food
+    ("aluminium foil", Category::NonFood),
+    ("baking paper", Category::NonFood),
+    ("battery", Category::NonFood),
+    ("bin bag", Category::NonFood),
+    ("candle", Category::NonFood),
+    ("cling film", Category::NonFood),
+    ("detergent", Category::NonFood),
+    ("dishwasher", Category::NonFood),
+    ("foil", Category::NonFood),
+    ("light bulb", Category::NonFood),
+    ("napkin", Category::NonFood),
+    ("paper towel", Category::NonFood),
+    ("soap", Category::NonFood),
+    ("sponge", Category::NonFood),
+    ("toilet paper", Category::NonFood),
+    ("trash bag", Category::NonFood),
+    // Pharmacy
+    ("aspirin", Category::Pharmacy),
+    ("bandage", Category::Pharmacy),
+    ("deodorant", Category::Pharmacy),
+    ("floss", Category::Pharmacy),
+    ("ibuprofen", Category::Pharmacy),
+    ("paracetamol", Category::Pharmacy),
+    ("plaster", Category::Pharmacy),
+    ("razor", Category::Pharmacy),
+    ("shampoo", Category::Pharmacy),
+    ("sunscreen", Category::Pharmacy),
+    ("toothbrush", Category::Pharmacy),
+    ("toothpaste", Category::Pharmacy),
+    ("vitamin", Category::Pharmacy),
+];
+
+/// Split a name into lowercase alphanumeric words.
+fn tokenize(s: &str) -> Vec<String> {
+    normalize_name(s)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Canonical form of a keyword: lowercase words joined by single spaces.
+#[must_use]
+pub fn normalize_keyword(s: &str) -> String {
+    tokenize(s).join(" ")
+}
+
+/// Whether a name word matches a singular keyword word, allowing the usual
+/// English plurals (tomatoes, berries, leaves).
+fn token_matches(tok: &str, kw: &str) -> bool {
+    if tok == kw {
+        return true;
+    }
+    if let Some(t) = tok.strip_suffix('s')
+        && (t == kw || t.strip_suffix('e') == Some(kw))
+    {
+        return true;
+    }
+    if let Some(stem) = kw.strip_suffix('y')
+        && tok.strip_suffix("ies") == Some(stem)
+    {
+        return true;
+    }
+    kw.strip_suffix('f')
+        .is_some_and(|stem| tok.strip_suffix("ves") == Some(stem))
+}
+
+/// Pick the best keyword found in `tokens`: most words first, then the match
+/// that ends last (the head noun in "chicken stock"), then the longest keyword.
+fn best_match<'a, T: Copy>(
+    tokens: &[String],
+    entries: impl IntoIterator<Item = (&'a str, T)>,
+) -> Option<T> {
+    let mut best: Option<((usize, usize, usize), T)> = None;
+    for (kw, value) in entries {
+        let kw_tokens: Vec<&str> = kw.split(' ').collect();
+        if kw_tokens.len() > tokens.len() {
+            continue;
+        }
+        for start in (0..=tokens.len() - kw_tokens.len()).rev() {
+            let hit = kw_tokens
+                .iter()
+                .zip(&tokens[start..])
+                .all(|(k, t)| token_matches(t, k));
+            if hit {
+                let rank = (kw_tokens.len(), start + kw_tokens.len(), kw.len());
+                if best.as_ref().is_none_or(|(r, _)| rank > *r) {
+                    best = Some((rank, value));
+                }
+                break;
+            }
+        }
+    }
+    best.map(|(_, v)| v)
+}
+
+/// Category from the built-in keyword map, if any keyword matches.
+#[must_use]
+pub fn builtin_category(name: &str) -> Option<Category> {
+    let tokens = tokenize(name);
+    best_match(&tokens, MODIFIER_KEYWORDS.iter().copied())
+        .or_else(|| best_match(&tokens, BUILTIN_KEYWORDS.iter().copied()))
+}
+
+/// Where a classification came from, in lookup order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategorySource {
+    /// An existing shopping item with the same normalized name.
+    Cache,
+    /// A keyword from the `category_keywords` table.
+    User,
+    /// The built-in keyword map.
+    Builtin,
+    Llm,
+    /// Nothing matched; "Other".
+    Fallback,
+}
 
-    pub fn from_str(s: &str) -> Option<Self> {
-        Some(match s {
-            "Other" => Self::Other,
-            "Fruits" => Self::Fruits,
-            "Vegetables" => Self::Vegetables,
-            "Bakery" => Self::Bakery,
-            "Vegan" => Self::Vegan,
-            "Drinks" => Self::Drinks,
-            "Alcohol" => Self::Alcohol,
-            "Seasoning" => Self::Seasoning,
-            "Canned" => Self::Canned,
-            "Pantry" => Self::Pantry,
-            "Non-Food" => Self::NonFood,
-            "Pharmacy" => Self::Pharmacy,
-            "Online" => Self::Online,
-            "Online Alcohol" => Self::OnlineAlcohol,
-            _ => return None,
-        })
+#[derive(Debug, Serialize)]
+pub struct Classification {
+    pub category: String,
+    pub source: CategorySource,
+}
+
+/// Category of a shopping item already on the list under the same name.
+async fn cached_category(state: &AppState, name_norm: &str) -> Option<String> {
+    sqlx::query_scalar::<_, String>(
+        r"
+        SELECT si.category
+          FROM shopping_items si
+          JOIN shopping_categories c ON c.name = si.category
+         WHERE substr(si.key, instr(si.key, '|') + 1) = ?
+         ORDER BY si.id DESC
+         LIMIT 1
+        ",
+    )
+    .bind(name_norm)
+    .fetch_optional(&state.pool)
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Category from the user's keywords, if any matches.
+async fn user_keyword_category(state: &AppState, name: &str) -> Option<String> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        r"
+        SELECT k.keyword, c.name
+          FROM category_keywords k
+          JOIN shopping_categories c ON c.id = k.category_id
+        ",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .ok()?;
+
+    best_match(
+        &tokenize(name),
+        rows.iter().map(|(k, c)| (k.as_str(), c.as_str())),
+    )
+    .map(str::to_string)
+}
+
+/// Classify a shopping item name: exact-name cache → user keywords →
+/// built-in keywords → LLM → "Other".
+pub async fn classify(state: &AppState, name_raw: &str) -> Classification {
+    classify_with(state, name_raw, true).await
+}
+
+/// [`classify`] without the exact-name cache, for items whose own category
+/// is what is being replaced.
+pub async fn classify_uncached(state: &AppState, name_raw: &str) -> Classification {
+    classify_with(state, name_raw, false).await
+}
+
+async fn classify_with(state: &AppState, name_raw: &str, use_cache: bool) -> Classification {
+    let name_norm = normalize_name(name_raw);
+    let hit = |category: String, source| Classification { category, source };
+
+    if use_cache && let Some(c) = cached_category(state, &name_norm).await {
+        return hit(c, CategorySource::Cache);
+    }
+    if let Some(c) = user_keyword_category(state, &name_norm).await {
+        return hit(c, CategorySource::User);
+    }
+    // The built-in map uses the default names; skip it if the user renamed
+    // or deleted the category.
+    if let Some(c) = builtin_category(&name_norm)
+        && validate_category(state, c.as_str()).await
+    {
+        return hit(c.as_str().to_string(), CategorySource::Builtin);
     }
+    if llm_categories_enabled(state).await
+        && let Some(c) = llm_category(state, name_raw).await
+    {
+        return hit(c, CategorySource::Llm);
+    }
+    hit("Other".to_string(), CategorySource::Fallback)
+}
+
+pub async fn guess_category(state: &AppState, name_raw: &str) -> String {
+    classify(state, name_raw).await.category
 }
 
 /* =========================
  * LLM category classifier
  * ========================= */
 
-/// Fetch all category names from the database.
+/// Category names in `sort_order`, loaded from `shopping_categories` on first
+/// use. The `/categories` routes drop it whenever the table changes.
+#[derive(Clone, Default)]
+pub struct CategoryCache {
+    names: Arc<RwLock<Option<Arc<Vec<String>>>>>,
+}
+
+impl CategoryCache {
+    /// The live category names, in `sort_order`.
+    ///
+    /// # Errors
+    /// Err if reading the table fails.
+    pub async fn names(&self, pool: &SqlitePool) -> sqlx::Result<Arc<Vec<String>>> {
+        if let Some(names) = self.names.read().await.as_ref() {
+            return Ok(names.clone());
+        }
+        // Loading under the write lock makes `invalidate` wait for a load that
+        // may have read the table before the change.
+        let mut slot = self.names.write().await;
+        if let Some(names) = slot.as_ref() {
+            return Ok(names.clone());
+        }
+        let names = Arc::new(
+            sqlx::query_scalar(r"SELECT name FROM shopping_categories ORDER BY sort_order, id")
+                .fetch_all(pool)
+                .await?,
+        );
+        Ok(slot.insert(names).clone())
+    }
+
+    /// Forget the cached names; the next read reloads them.
+    pub async fn invalidate(&self) {
+        *self.names.write().await = None;
+    }
+}
+
+/// All category names, in `sort_order`.
 async fn fetch_category_names(state: &AppState) -> Vec<String> {
-    sqlx::query_scalar::<_, String>(r"SELECT name FROM shopping_categories ORDER BY sort_order")
-        .fetch_all(&state.pool)
-        .await
-        .unwrap_or_else(|_| {
+    state.categories.names(&state.pool).await.map_or_else(
+        |_| {
             // Fallback to hardcoded categories if DB fails
             Category::ALL
                 .iter()
                 .map(|c| c.as_str().to_string())
                 .collect()
-        })
+        },
+        |names| names.to_vec(),
+    )
 }
 
 /// Check if a category name exists in the database.
 pub async fn validate_category(state: &AppState, name: &str) -> bool {
-    sqlx::query_scalar::<_, i64>(r"SELECT 1 FROM shopping_categories WHERE name = ?")
-        .bind(name)
-        .fetch_optional(&state.pool)
+    state
+        .categories
+        .names(&state.pool)
         .await
-        .ok()
-        .flatten()
-        .is_some()
+        .is_ok_and(|names| names.iter().any(|n| n == name))
 }
 
 async fn build_llm_system_prompt(state: &AppState) -> String {
@@ -147,34 +590,38 @@ async fn build_llm_system_prompt(state: &AppState) -> String {
     )
 }
 
+/// Items are added while the user waits; a slow provider falls back to
+/// "Other" rather than holding up the list.
+const LLM_CATEGORY_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// The `use_llm_categories` setting; anything but "false" leaves the LLM
+/// step on, as it was before the setting existed.
+pub async fn llm_categories_enabled(state: &AppState) -> bool {
+    get_setting(&state.pool, "use_llm_categories")
+        .await
+        .is_none_or(|v| v != "false")
+}
+
 #[derive(Deserialize)]
 struct LlmCatOut {
     category: String,
 }
 
-pub async fn guess_category(state: &AppState, name_raw: &str) -> String {
-    let fallback = "Other".to_string();
+async fn llm_category(state: &AppState, name_raw: &str) -> Option<String> {
+    // Load LLM settings from database
+    let llm_settings = LlmSettings::load(state).await;
 
-    let token = state.config.llm_api_key.clone().unwrap_or_default();
-    if token.trim().is_empty() {
-        return fallback;
-    }
+    // The shopping list waits on this call, so no retries.
+    let llm = LlmClient::from_config(&state.config, llm_settings.model.clone())
+        .ok()?
+        .with_retry(RetryPolicy::NONE)
+        .tracked(&state.pool, "shopping.categories");
 
-    let Ok(http) = reqwest::Client::builder()
-        .timeout(Duration::from_secs(12))
+    let http = reqwest::Client::builder()
+        .timeout(LLM_CATEGORY_TIMEOUT)
         .build()
-    else {
-        return fallback;
-    };
+        .ok()?;
 
-    // Load LLM settings from database
-    let llm_settings = LlmSettings::load(&state.pool).await;
-
-    let llm = LlmClient::new(
-        state.config.llm_api_url.clone(),
-        token,
-        llm_settings.model.clone(),
-    );
     let system = build_llm_system_prompt(state).await;
 
     let user = format!(
@@ -183,62 +630,23 @@ pub async fn guess_category(state: &AppState, name_raw: &str) -> String {
         norm = normalize_name(name_raw),
     );
 
-    let Ok(val) = llm
-        .chat_json(
-            &http,
-            &system,
-            &user,
-            0.0,
-            Duration::from_secs(12),
-            Some(120),
-        )
+    let val = llm
+        .chat_json(&http, &system, &user, 0.0, LLM_CATEGORY_TIMEOUT, Some(120))
         .await
-    else {
-        return fallback;
-    };
+        .ok()?;
 
-    let parsed: LlmCatOut = match serde_json::from_value(val) {
-        Ok(p) => p,
-        Err(_) => return fallback,
-    };
+    let parsed: LlmCatOut = serde_json::from_value(val).ok()?;
 
     // Validate that the returned category exists in DB
-    if validate_category(state, &parsed.category).await {
-        parsed.category
-    } else {
-        fallback
-    }
+    validate_category(state, &parsed.category)
+        .await
+        .then_some(parsed.category)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_category_from_str() {
-        assert_eq!(Category::from_str("Other"), Some(Category::Other));
-        assert_eq!(Category::from_str("Fruits"), Some(Category::Fruits));
-        assert_eq!(Category::from_str("Vegetables"), Some(Category::Vegetables));
-        assert_eq!(Category::from_str("Bakery"), Some(Category::Bakery));
-        assert_eq!(Category::from_str("Vegan"), Some(Category::Vegan));
-        assert_eq!(Category::from_str("Drinks"), Some(Category::Drinks));
-        assert_eq!(Category::from_str("Alcohol"), Some(Category::Alcohol));
-        assert_eq!(Category::from_str("Seasoning"), Some(Category::Seasoning));
-        assert_eq!(Category::from_str("Canned"), Some(Category::Canned));
-        assert_eq!(Category::from_str("Pantry"), Some(Category::Pantry));
-        assert_eq!(Category::from_str("Non-Food"), Some(Category::NonFood));
-        assert_eq!(Category::from_str("Pharmacy"), Some(Category::Pharmacy));
-        assert_eq!(Category::from_str("Online"), Some(Category::Online));
-        assert_eq!(
-            Category::from_str("Online Alcohol"),
-            Some(Category::OnlineAlcohol)
-        );
-
-        assert_eq!(Category::from_str("Invalid"), None);
-        assert_eq!(Category::from_str("fruits"), None); // Case sensitive
-        assert_eq!(Category::from_str(""), None);
-    }
-
     #[test]
     fn test_category_as_str() {
         assert_eq!(Category::Other.as_str(), "Other");
@@ -257,28 +665,70 @@ mod tests {
         assert_eq!(Category::OnlineAlcohol.as_str(), "Online Alcohol");
     }
 
+    #[tokio::test]
+    async fn test_seeded_categories_match_enum_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pool = crate::db::make_pool(tmp.path().join("db.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let names = CategoryCache::default().names(&pool).await.unwrap();
+        let expected: Vec<&str> = Category::ALL.iter().map(|c| c.as_str()).collect();
+        assert_eq!(*names, expected);
+    }
+
     #[test]
-    fn test_category_sort_key() {
-        assert_eq!(Category::Other.sort_key(), 0);
-        assert_eq!(Category::Fruits.sort_key(), 1);
-        assert_eq!(Category::Vegetables.sort_key(), 2);
-        assert_eq!(Category::OnlineAlcohol.sort_key(), 13);
-
-        assert!(Category::Other.sort_key() < Category::Fruits.sort_key());
-        assert!(Category::Fruits.sort_key() < Category::Vegetables.sort_key());
+    fn test_all_categories_count() {
+        assert_eq!(Category::ALL.len(), 14);
     }
 
     #[test]
-    fn test_category_roundtrip() {
-        for cat in Category::ALL {
-            let s = cat.as_str();
-            let parsed = Category::from_str(s);
-            assert_eq!(parsed, Some(*cat));
+    fn test_builtin_keywords_grocery_table() {
+        // Strict: report every miss at once so contributors see the full gap.
+        let mut failures = Vec::new();
+        let mut rows = 0;
+        for line in include_str!("../tests/fixtures/grocery_categories.csv")
+            .lines()
+            .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+            .skip(1)
+        {
+            let (name, expected) = line.rsplit_once(',').expect("name,category");
+            let expected = Some(expected).filter(|e| !e.is_empty());
+            assert!(
+                expected.is_none_or(|e| Category::ALL.iter().any(|c| c.as_str() == e)),
+                "unknown category {expected:?}"
+            );
+            let got = builtin_category(name).map(Category::as_str);
+            if got != expected {
+                failures.push(format!("{name}: expected {expected:?}, got {got:?}"));
+            }
+            rows += 1;
         }
+        assert_eq!(rows, 100);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
     }
 
     #[test]
-    fn test_all_categories_count() {
-        assert_eq!(Category::ALL.len(), 14);
+    fn test_builtin_keywords_use_known_categories() {
+        for (kw, _) in BUILTIN_KEYWORDS.iter().chain(MODIFIER_KEYWORDS) {
+            assert_eq!(normalize_keyword(kw), *kw, "keyword not normalized");
+        }
+        assert_eq!(builtin_category("mystery item"), None);
+        assert_eq!(builtin_category(""), None);
+    }
+
+    #[test]
+    fn test_token_matches_plurals() {
+        assert!(token_matches("tomatoes", "tomato"));
+        assert!(token_matches("berries", "berry"));
+        assert!(token_matches("leaves", "leaf"));
+        assert!(token_matches("onions", "onion"));
+        assert!(!token_matches("eggplant", "egg"));
+    }
+
+    #[test]
+    fn test_normalize_keyword() {
+        assert_eq!(normalize_keyword("  Oat-Milk "), "oat milk");
+        assert_eq!(normalize_keyword("Plant  based"), "plant based");
+        assert_eq!(normalize_keyword("!!"), "");
     }
 }
@@ -1,6 +1,9 @@
 use clap::{ArgAction, Parser, Subcommand};
 use std::{net::SocketAddr, path::PathBuf};
 
+use crate::llm::CassetteMode;
+use crate::logging::{AccessLogLevel, LogFormat};
+
 #[derive(Parser, Debug)]
 #[command(name = "blaz", version, about = "HTTP API server for Blaz")]
 pub struct Cli {
@@ -11,14 +14,22 @@ pub struct Cli {
     pub config: Config,
 }
 
-#[derive(Subcommand, Debug, Clone, Copy)]
+#[derive(Subcommand, Debug, Clone)]
 pub enum Commands {
     /// Generate an Argon2 password hash for authentication
     HashPassword,
+    /// Set a new login password in the database, e.g. when it was forgotten.
+    /// Logs out every session.
+    ResetPassword {
+        /// New password; prompted for when omitted
+        #[arg(long)]
+        password: Option<String>,
+    },
 }
 
 /// Blaz server configuration
 #[derive(Parser, Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)] // independent CLI flags
 pub struct Config {
     /// Increase verbosity (-v, -vv, -vvv)
     #[arg(short = 'v', action = ArgAction::Count, global = true)]
@@ -36,23 +47,97 @@ pub struct Config {
     #[arg(long, env = "BLAZ_MEDIA_DIR", default_value = "media")]
     pub media_dir: PathBuf,
 
+    /// Seconds to keep retrying at startup while the media directory is
+    /// missing or read-only (e.g. a network mount that comes up late). After
+    /// that the server starts with image endpoints disabled until it appears.
+    #[arg(long, env = "BLAZ_MEDIA_WAIT_SECS", default_value_t = 30)]
+    pub media_wait_secs: u64,
+
+    /// Delete recipe images no recipe references on startup
+    #[arg(long, env = "BLAZ_MEDIA_GC_ON_STARTUP")]
+    pub media_gc_on_startup: bool,
+
+    /// Largest accepted recipe image upload, in megabytes
+    #[arg(long, env = "BLAZ_MAX_IMAGE_UPLOAD_MB", default_value_t = 15)]
+    pub max_image_upload_mb: u64,
+
+    /// Let imports fetch pages and images from private, loopback and
+    /// link-local addresses (e.g. a recipe site on the LAN)
+    #[arg(long, env = "BLAZ_FETCH_ALLOW_PRIVATE")]
+    pub fetch_allow_private: bool,
+
     /// Database path
     #[arg(long, env = "BLAZ_DATABASE_PATH", default_value = "blaz.sqlite")]
     pub database_path: String,
 
+    /// Directory for database snapshots made by `POST /admin/backup`
+    #[arg(long, env = "BLAZ_BACKUP_DIR", default_value = "backups")]
+    pub backup_dir: PathBuf,
+
     /// Optional log file path (logs are written to stdout + this file)
     #[arg(long, env = "BLAZ_LOG_FILE", default_value = "blaz.logs")]
     pub log_file: PathBuf,
 
-    /// CORS allowed origin (e.g., <https://blaz.yourdomain.com>)
-    /// If not set, allows all origins (⚠️ insecure for production!)
-    #[arg(long, env = "BLAZ_CORS_ORIGIN")]
-    pub cors_origin: Option<String>,
+    /// Log line format for stdout and the log file: `text` or `json` (one
+    /// object per line, for log shippers)
+    #[arg(long, env = "BLAZ_LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Path prefixes whose successful requests are left out of the access
+    /// log (failures are still logged)
+    #[arg(
+        long,
+        env = "BLAZ_ACCESS_LOG_EXCLUDE",
+        value_delimiter = ',',
+        default_value = "/healthz,/metrics"
+    )]
+    pub access_log_exclude: Vec<String>,
+
+    /// Path prefixes whose successful requests are logged only 1 in
+    /// `--access-log-sample-rate` times (failures are always logged)
+    #[arg(long, env = "BLAZ_ACCESS_LOG_SAMPLE", value_delimiter = ',')]
+    pub access_log_sample: Vec<String>,
+
+    /// Log 1 in N successful requests under `--access-log-sample` prefixes
+    #[arg(long, env = "BLAZ_ACCESS_LOG_SAMPLE_RATE", default_value_t = 100)]
+    pub access_log_sample_rate: u64,
+
+    /// Level for 404 responses in the access log (other 4xx and all 5xx
+    /// stay at ERROR)
+    #[arg(long, env = "BLAZ_ACCESS_LOG_NOT_FOUND_LEVEL", value_enum, default_value_t = AccessLogLevel::Warn)]
+    pub access_log_not_found_level: AccessLogLevel,
+
+    /// CORS allowed origins, comma-separated (e.g.
+    /// <https://blaz.yourdomain.com>); credentialed requests are allowed from
+    /// them. `*` allows all origins (⚠️ insecure for production!)
+    #[arg(
+        long,
+        alias = "cors-origin",
+        env = "BLAZ_CORS_ORIGIN",
+        value_delimiter = ',',
+        default_value = "*",
+        value_parser = parse_cors_origin
+    )]
+    pub cors_origins: Vec<String>,
+
+    /// Public base URL used for absolute links in exported data (e.g.
+    /// `<https://blaz.yourdomain.com>`). Defaults to the request's Host header.
+    #[arg(long, env = "BLAZ_PUBLIC_URL")]
+    pub public_url: Option<String>,
 
     /// JWT secret for authentication (if not set, generates a random one)
     #[arg(long, env = "BLAZ_JWT_SECRET")]
     pub jwt_secret: Option<String>,
 
+    /// Lifetime of login tokens in hours (default: 7 years). Only affects
+    /// newly issued tokens.
+    #[arg(long, env = "BLAZ_JWT_TTL_HOURS", default_value_t = 7 * 365 * 24)]
+    pub jwt_ttl_hours: u64,
+
+    /// Seconds of clock skew tolerated when checking a token's expiry
+    #[arg(long, env = "BLAZ_JWT_LEEWAY_SECS", default_value_t = 60)]
+    pub jwt_leeway_secs: u64,
+
     /// Argon2 password hash for authentication (required for production)
     /// Generate with: blaz hash-password
     #[arg(long, env = "BLAZ_PASSWORD_HASH")]
@@ -70,6 +155,24 @@ pub struct Config {
     )]
     pub llm_api_url: String,
 
+    /// Chat model to use instead of the `llm_model` setting. Not saved, so
+    /// removing it brings the setting back.
+    #[arg(long, env = "BLAZ_LLM_MODEL")]
+    pub llm_model: Option<String>,
+
+    /// Record LLM responses to, or replay them from, `--llm-cassette-dir`
+    /// (for frontend development without an API key or credits)
+    #[arg(long, env = "BLAZ_LLM_CASSETTE_MODE", value_enum, default_value_t = CassetteMode::Off)]
+    pub llm_cassette_mode: CassetteMode,
+
+    /// Directory holding LLM cassettes (one JSON file per request)
+    #[arg(long, env = "BLAZ_LLM_CASSETTE_DIR", default_value = "llm-cassettes")]
+    pub llm_cassette_dir: PathBuf,
+
+    /// Disable all LLM features; no requests are made to the LLM provider
+    #[arg(long, env = "BLAZ_OFFLINE")]
+    pub offline: bool,
+
     /// System prompt for recipe import
     #[arg(long, env = "BLAZ_SYSTEM_PROMPT_IMPORT", default_value = DEFAULT_SYSTEM_PROMPT_IMPORT)]
     pub system_prompt_import: String,
@@ -101,6 +204,38 @@ pub struct Config {
     /// ntfy URL to send error notifications to (e.g. `<https://ntfy.sh/my-topic>`)
     #[arg(long, env = "BLAZ_NTFY_URL")]
     pub ntfy_url: Option<String>,
+
+    /// Weekday to send the weekly meal plan and shopping digest on (e.g.
+    /// `sun`); unset disables the digest
+    #[arg(long, env = "BLAZ_DIGEST_WEEKDAY")]
+    pub digest_weekday: Option<chrono::Weekday>,
+
+    /// Hour (0-23, server local time; set `TZ` to change it) at which the
+    /// digest is sent
+    #[arg(long, env = "BLAZ_DIGEST_HOUR", default_value_t = 18, value_parser = clap::value_parser!(u32).range(0..24))]
+    pub digest_hour: u32,
+
+    /// ntfy URL the digest is posted to; defaults to `--ntfy-url`
+    #[arg(long, env = "BLAZ_DIGEST_NTFY_URL")]
+    pub digest_ntfy_url: Option<String>,
+
+    /// Days within which re-sending the same recipe/day to the shopping list is
+    /// rejected with 409 unless forced
+    #[arg(
+        long,
+        env = "BLAZ_SHOPPING_GENERATION_WINDOW_DAYS",
+        default_value_t = 7
+    )]
+    pub shopping_generation_window_days: u32,
+
+    /// Rows kept in the activity log; older entries are pruned on write (0
+    /// keeps everything)
+    #[arg(long, env = "BLAZ_ACTIVITY_LOG_MAX_ROWS", default_value_t = 10_000)]
+    pub activity_log_max_rows: u32,
+
+    /// Disable gzip/brotli response compression (e.g. behind a reverse proxy that compresses)
+    #[arg(long, env = "BLAZ_DISABLE_COMPRESSION")]
+    pub disable_compression: bool,
 }
 
 const DEFAULT_SYSTEM_PROMPT_IMPORT: &str = r###"You are a precise recipe data extractor and normalizer.
@@ -109,6 +244,8 @@ INPUT: plain text from a recipe page (any language).
 OUTPUT: STRICT JSON with exactly these keys:
 {
   "title": string,
+  "yield": null | number | string,   ← servings as a number, or e.g. "1 loaf"
+  "notes": null | string,            ← tips, storage and substitutions; one per line
   "ingredients": [
     {"section": string}              ← section header (use when recipe has named groups)
     |
@@ -152,11 +289,17 @@ TASK:
     insert a string "## Section Name" BEFORE the steps of that section.
     Example: ["## Pulled Jackfruit", "Shred the jackfruit.", "## Tzatziki", "Mix yogurt."]
   * Only add section headers when the recipe text clearly names the groups.
+- "yield": how many servings the recipe makes, as a number; use a string only
+  for non-serving yields ("1 loaf", "24 cookies"); null if not stated.
+- "notes": the recipe's tips, storage advice or substitutions, translated;
+  null if there are none. Never put steps or ingredients here.
 - Remove all mentions of "Vegan" inside the title.
 
 FORMAT EXAMPLE (with sections):
 {
   "title": "BBQ Pulled Jackfruit",
+  "yield": 4,
+  "notes": "Keeps for 3 days in the fridge.",
   "ingredients": [
     {"section": "Pulled Jackfruit"},
     {"quantity":560,"unit":"g","name":"jackfruit","prep":"drained and rinsed"},
@@ -220,6 +363,8 @@ Return STRICT JSON with per-ingredient macros estimates:
       "protein_g": number,
       "fat_g": number,
       "carbs_g": number,
+      "fiber_g": number,
+      "kcal": number,
       "skip": boolean  // true if ingredient is negligible (< 5 calories)
     }
   ]
@@ -229,7 +374,8 @@ Rules:
 - Estimate macros for EACH ingredient separately based on the quantity given.
 - Use common nutrition databases and reasonable approximations.
 - fat_g includes saturated + unsaturated combined.
-- carbs_g excludes fiber (i.e., net carbs).
+- carbs_g excludes fiber (i.e., net carbs); report fiber separately in fiber_g.
+- kcal is the ingredient's energy in kilocalories.
 - Set "skip": true for ingredients with negligible calories (< 5 kcal):
   * Water, broth (unless cream-based)
   * Salt, pepper, spices in small amounts (< 1 tsp)
@@ -271,7 +417,8 @@ OUTPUT: STRICT JSON with exactly these keys:
 {
   "title": string,
   "ingredients": [string],
-  "instructions": [string]
+  "instructions": [string],
+  "equipment": [string]
 }
 
 CRITICAL: Your job is to EXTRACT, not parse or simplify. Extract EVERY SINGLE ingredient from the recipe, preserving ALL details.
@@ -300,6 +447,12 @@ RULES FOR INSTRUCTIONS:
 - Preserve all details and timing information
 - Translate to English if needed
 
+RULES FOR EQUIPMENT:
+- List the tools and appliances the recipe needs (e.g. "food processor", "dutch oven")
+- Skip everyday items like knives, bowls, spoons and cutting boards
+- Return an empty array if none are mentioned
+- Translate to English if needed
+
 RULES FOR TITLE:
 - Extract a clean, concise title
 - Remove "Vegan" if present
@@ -347,7 +500,8 @@ FORMAT EXAMPLE:
     "Roast beets for 45-90 minutes until fork-tender.",
     "## Make Vinaigrette",
     "Combine all vinaigrette ingredients in a jar and shake."
-  ]
+  ],
+  "equipment": ["baking sheet", "jar"]
 }
 
 Answer only with the final JSON."###;
@@ -500,7 +654,46 @@ OUTPUT:
 
 Answer only with the JSON array."#;
 
+/// One `--cors-origins` entry: `*`, or an http(s) origin normalized to how
+/// browsers send it (`https://Blaz.example.com/` -> `https://blaz.example.com`).
+///
+/// # Errors
+///
+/// A message naming the entry when it isn't a bare `scheme://host[:port]`.
+pub fn parse_cors_origin(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    if raw == "*" {
+        return Ok(raw.to_string());
+    }
+    let bad = |why: &str| format!("invalid CORS origin '{raw}': {why}");
+    let url = url::Url::parse(raw).map_err(|e| bad(&e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(bad("scheme must be http or https"));
+    }
+    if url.path() != "/" || url.query().is_some() || url.fragment().is_some() {
+        return Err(bad("an origin has no path, query or fragment"));
+    }
+    let origin = url.origin();
+    if !origin.is_tuple() {
+        return Err(bad("missing host"));
+    }
+    Ok(origin.ascii_serialization())
+}
+
 impl Config {
+    /// Whether CORS lets any origin in (`*` among the allowed origins).
+    #[must_use]
+    pub fn cors_allows_any(&self) -> bool {
+        self.cors_origins.is_empty() || self.cors_origins.iter().any(|o| o == "*")
+    }
+
+    /// Where the generated JWT secret is kept when `BLAZ_JWT_SECRET` isn't
+    /// set: next to the database.
+    #[must_use]
+    pub fn jwt_secret_path(&self) -> PathBuf {
+        std::path::Path::new(&self.database_path).with_file_name("jwt_secret")
+    }
+
     #[must_use]
     pub fn verbosity_delta(&self) -> i16 {
         i16::from(self.verbose) - i16::from(self.quiet)
@@ -517,3 +710,51 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cors_origins_are_normalized_or_rejected() {
+        assert_eq!(parse_cors_origin(" * ").unwrap(), "*");
+        assert_eq!(
+            parse_cors_origin("https://Blaz.Example.com/").unwrap(),
+            "https://blaz.example.com"
+        );
+        assert_eq!(
+            parse_cors_origin("http://localhost:8080").unwrap(),
+            "http://localhost:8080"
+        );
+        for bad in [
+            "blaz.example.com",
+            "ftp://blaz.example.com",
+            "https://blaz.example.com/app",
+            "https://blaz.example.com?x=1",
+            "",
+        ] {
+            let err = parse_cors_origin(bad).unwrap_err();
+            assert!(err.contains("invalid CORS origin"), "{bad}: {err}");
+        }
+    }
+
+    #[test]
+    fn cors_origins_split_on_commas() {
+        let cli = Cli::try_parse_from([
+            "blaz",
+            "--cors-origins",
+            "https://a.example.com,https://b.example.com",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.config.cors_origins,
+            ["https://a.example.com", "https://b.example.com"]
+        );
+        assert!(!cli.config.cors_allows_any());
+
+        let cli = Cli::try_parse_from(["blaz"]).unwrap();
+        assert!(cli.config.cors_allows_any());
+
+        assert!(Cli::try_parse_from(["blaz", "--cors-origin", "not an origin"]).is_err());
+    }
+}
@@ -26,3 +26,45 @@ pub async fn make_pool(database_path: String) -> anyhow::Result<SqlitePool> {
     MIGRATOR.run(&pool).await?;
     Ok(pool)
 }
+
+/// Fill the `servings` column of recipes created before it existed, once per
+/// database (recorded in `backfills`). Returns how many rows were filled.
+///
+/// # Errors
+///
+/// Err if a query fails; nothing is changed then.
+pub async fn backfill_recipe_servings(pool: &SqlitePool) -> sqlx::Result<u64> {
+    const NAME: &str = "recipe_servings";
+    let mut tx = pool.begin().await?;
+    let done: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM backfills WHERE name = ?)")
+        .bind(NAME)
+        .fetch_one(&mut *tx)
+        .await?;
+    if done {
+        return Ok(0);
+    }
+
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        r#"SELECT id, "yield" FROM recipes WHERE servings IS NULL AND trim("yield") != ''"#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut filled = 0;
+    for (id, y) in rows {
+        if let Some(servings) = crate::units::servings_from_yield(&y) {
+            sqlx::query("UPDATE recipes SET servings = ? WHERE id = ?")
+                .bind(servings)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            filled += 1;
+        }
+    }
+
+    sqlx::query("INSERT INTO backfills (name) VALUES (?)")
+        .bind(NAME)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(filled)
+}
@@ -0,0 +1,168 @@
+//! Consistent snapshots of the live database.
+//!
+//! Copying the file of a WAL-mode database can miss pages still in the WAL,
+//! so snapshots are written by `SQLite` itself with `VACUUM INTO`. Each lands
+//! under a `.partial` name first and is renamed once complete, so a listing
+//! never shows half-written files.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+const PREFIX: &str = "blaz-";
+const EXTENSION: &str = ".sqlite";
+
+/// Snapshots kept when the `backup_retention` setting is unset.
+pub const DEFAULT_RETENTION: usize = 7;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub name: String,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// RFC 3339, from the file's modification time.
+    pub created_at: String,
+}
+
+/// Whether `name` looks like a snapshot written by [`create`].
+fn is_snapshot_name(name: &str) -> bool {
+    name.starts_with(PREFIX) && name.ends_with(EXTENSION)
+}
+
+async fn describe(path: PathBuf) -> io::Result<Snapshot> {
+    let meta = tokio::fs::metadata(&path).await?;
+    let created_at = DateTime::<Utc>::from(meta.modified()?).to_rfc3339();
+    Ok(Snapshot {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path,
+        size_bytes: meta.len(),
+        created_at,
+    })
+}
+
+/// Write a snapshot of the database behind `pool` into `dir`, named after the
+/// current time (`blaz-20250101-120000-000000.sqlite`). `VACUUM INTO` runs
+/// on a connection of its own, so other queries keep the rest of the pool.
+///
+/// # Errors
+/// Err if `dir` can't be created or `SQLite` fails to write the snapshot.
+pub async fn create(pool: &SqlitePool, dir: &Path) -> anyhow::Result<Snapshot> {
+    tokio::fs::create_dir_all(dir).await?;
+    let name = format!(
+        "{PREFIX}{}{EXTENSION}",
+        Utc::now().format("%Y%m%d-%H%M%S-%6f")
+    );
+    let path = dir.join(&name);
+    let partial = dir.join(format!("{name}.partial"));
+    let Some(partial_str) = partial.to_str() else {
+        anyhow::bail!("backup dir {} is not valid UTF-8", dir.display());
+    };
+    // VACUUM INTO refuses to overwrite; drop leftovers of an interrupted run.
+    let _ = tokio::fs::remove_file(&partial).await;
+
+    let mut conn = pool.acquire().await?;
+    if let Err(e) = sqlx::query("VACUUM INTO ?")
+        .bind(partial_str)
+        .execute(&mut *conn)
+        .await
+    {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e.into());
+    }
+    drop(conn);
+
+    tokio::fs::rename(&partial, &path).await?;
+    Ok(describe(path).await?)
+}
+
+/// Snapshots in `dir`, newest first. A missing dir has none.
+///
+/// # Errors
+/// Err if `dir` exists but can't be read.
+pub async fn list(dir: &Path) -> io::Result<Vec<Snapshot>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut out = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if !entry.file_name().to_str().is_some_and(is_snapshot_name) {
+            continue;
+        }
+        match describe(entry.path()).await {
+            Ok(s) => out.push(s),
+            // Pruned by a concurrent backup.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    // Names carry the timestamp, so they sort chronologically.
+    out.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(out)
+}
+
+/// Delete all but the `keep` newest snapshots (0 keeps everything) and
+/// return the names of those removed.
+///
+/// # Errors
+/// Err if `dir` can't be read.
+pub async fn prune(dir: &Path, keep: usize) -> io::Result<Vec<String>> {
+    if keep == 0 {
+        return Ok(Vec::new());
+    }
+    let mut removed = Vec::new();
+    for old in list(dir).await?.into_iter().skip(keep) {
+        match tokio::fs::remove_file(&old.path).await {
+            Ok(()) => removed.push(old.name),
+            Err(e) => {
+                tracing::warn!(path = %old.path.display(), error = %e, "failed to prune backup");
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn prune_keeps_newest_and_ignores_other_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        for name in [
+            "blaz-20250101-000000-000000.sqlite",
+            "blaz-20250102-000000-000000.sqlite",
+            "blaz-20250103-000000-000000.sqlite",
+            "blaz-20250104-000000-000000.sqlite.partial",
+            "notes.txt",
+        ] {
+            tokio::fs::write(tmp.path().join(name), b"x").await.unwrap();
+        }
+
+        let removed = prune(tmp.path(), 2).await.unwrap();
+        assert_eq!(removed, ["blaz-20250101-000000-000000.sqlite"]);
+        let names: Vec<String> = list(tmp.path())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "blaz-20250103-000000-000000.sqlite",
+                "blaz-20250102-000000-000000.sqlite"
+            ]
+        );
+        assert!(tmp.path().join("notes.txt").exists());
+        assert!(prune(tmp.path(), 0).await.unwrap().is_empty());
+        assert!(list(&tmp.path().join("missing")).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,301 @@
+//! Database health report and repairs for damage older versions or manual
+//! edits can leave behind.
+//!
+//! Recipe JSON columns are checked against the types `RecipeRow` decodes
+//! them into, since a single bad row otherwise fails every query that loads
+//! it. Ingredient lists from before structured ingredients (`["2 carrots"]`)
+//! are the common case: they still load, but are rewritten at startup and by
+//! a repair. Anything else is only reported.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+
+use crate::models::{Ingredient, PrepReminder, RecipeMacros};
+use crate::routes::shopping::parse_ingredient_line;
+
+/// Ids of recipes whose JSON column doesn't decode, per column.
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct InvalidColumns {
+    pub ingredients: Vec<i64>,
+    pub instructions: Vec<i64>,
+    pub equipment: Vec<i64>,
+    pub macros: Vec<i64>,
+    pub prep_reminders: Vec<i64>,
+}
+
+impl InvalidColumns {
+    const fn is_empty(&self) -> bool {
+        self.ingredients.is_empty()
+            && self.instructions.is_empty()
+            && self.equipment.is_empty()
+            && self.macros.is_empty()
+            && self.prep_reminders.is_empty()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct CheckReport {
+    /// Whether nothing below needs attention.
+    pub ok: bool,
+    /// Output of `PRAGMA integrity_check`: `["ok"]` for a sound file.
+    pub integrity: Vec<String>,
+    pub invalid_columns: InvalidColumns,
+    /// Meal-plan entry ids pointing at recipes that no longer exist.
+    pub orphaned_meal_plan: Vec<i64>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct RepairReport {
+    /// Recipes whose ingredients were rewritten from plain lines.
+    pub ingredients_rewritten: Vec<i64>,
+    /// Recipes whose ingredients are still unreadable.
+    pub ingredients_unrepaired: Vec<i64>,
+    /// Deleted orphaned meal-plan entry ids.
+    pub meal_plan_deleted: Vec<i64>,
+}
+
+/// JSON columns of every recipe, trash included, cast to text so a stray
+/// number or blob is reported rather than failing the query.
+#[derive(sqlx::FromRow)]
+struct JsonColumns {
+    id: i64,
+    ingredients: Option<String>,
+    instructions: Option<String>,
+    equipment: Option<String>,
+    macros: Option<String>,
+    prep_reminders: Option<String>,
+}
+
+async fn json_columns<'e>(db: impl SqliteExecutor<'e>) -> sqlx::Result<Vec<JsonColumns>> {
+    sqlx::query_as(
+        r"
+        SELECT id,
+               CAST(ingredients AS TEXT)    AS ingredients,
+               CAST(instructions AS TEXT)   AS instructions,
+               CAST(equipment AS TEXT)      AS equipment,
+               CAST(macros AS TEXT)         AS macros,
+               CAST(prep_reminders AS TEXT) AS prep_reminders
+          FROM recipes
+         ORDER BY id
+        ",
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Whether a required column holds a `T`.
+fn decodes<T: DeserializeOwned>(raw: Option<&str>) -> bool {
+    raw.is_some_and(|s| serde_json::from_str::<T>(s).is_ok())
+}
+
+/// Whether a nullable column is NULL or holds a `T`.
+fn decodes_or_null<T: DeserializeOwned>(raw: Option<&str>) -> bool {
+    raw.is_none_or(|s| serde_json::from_str::<T>(s).is_ok())
+}
+
+async fn orphaned_meal_plan<'e>(db: impl SqliteExecutor<'e>) -> sqlx::Result<Vec<i64>> {
+    sqlx::query_scalar(
+        r"
+        SELECT mp.id
+          FROM meal_plan mp
+          LEFT JOIN recipes r ON r.id = mp.recipe_id
+         WHERE r.id IS NULL
+         ORDER BY mp.id
+        ",
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Run all checks. Read-only.
+///
+/// # Errors
+/// Err if a query fails.
+pub async fn check(pool: &SqlitePool) -> sqlx::Result<CheckReport> {
+    let integrity: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await?;
+
+    let mut invalid = InvalidColumns::default();
+    for row in json_columns(pool).await? {
+        if !decodes::<Vec<Ingredient>>(row.ingredients.as_deref()) {
+            invalid.ingredients.push(row.id);
+        }
+        if !decodes::<Vec<String>>(row.instructions.as_deref()) {
+            invalid.instructions.push(row.id);
+        }
+        if !decodes::<Vec<String>>(row.equipment.as_deref()) {
+            invalid.equipment.push(row.id);
+        }
+        if !decodes_or_null::<RecipeMacros>(row.macros.as_deref()) {
+            invalid.macros.push(row.id);
+        }
+        if !decodes_or_null::<Vec<PrepReminder>>(row.prep_reminders.as_deref()) {
+            invalid.prep_reminders.push(row.id);
+        }
+    }
+    let orphaned_meal_plan = orphaned_meal_plan(pool).await?;
+
+    Ok(CheckReport {
+        ok: integrity == ["ok"] && invalid.is_empty() && orphaned_meal_plan.is_empty(),
+        integrity,
+        invalid_columns: invalid,
+        orphaned_meal_plan,
+    })
+}
+
+/// Rebuild an ingredient list that doesn't decode. String entries are parsed
+/// as ingredient lines, entries that already are ingredients are kept, and a
+/// column that isn't a JSON array at all is read as one line per row of text.
+/// `None` when it can't be salvaged.
+fn rebuild_ingredients(raw: &str) -> Option<Vec<Ingredient>> {
+    let entries: Vec<Value> = match serde_json::from_str::<Value>(raw) {
+        Ok(Value::Array(entries)) => entries,
+        Ok(Value::String(text)) => return Some(lines_to_ingredients(&text)),
+        Ok(_) => return None,
+        Err(_) => return Some(lines_to_ingredients(raw)),
+    };
+    let mut out = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match entry {
+            Value::String(line) => out.extend(parse_ingredient_line(&line)),
+            Value::Null => {}
+            other => out.push(serde_json::from_value(other).ok()?),
+        }
+    }
+    Some(out)
+}
+
+fn lines_to_ingredients(text: &str) -> Vec<Ingredient> {
+    text.lines().filter_map(parse_ingredient_line).collect()
+}
+
+/// Rewrite ingredient lists that don't decode (see [`rebuild_ingredients`])
+/// into `report`.
+async fn rewrite_ingredients(
+    conn: &mut SqliteConnection,
+    report: &mut RepairReport,
+) -> sqlx::Result<()> {
+    for row in json_columns(&mut *conn).await? {
+        let raw = row.ingredients.unwrap_or_default();
+        if decodes::<Vec<Ingredient>>(Some(&raw)) {
+            continue;
+        }
+        let Some(ingredients) = rebuild_ingredients(&raw) else {
+            report.ingredients_unrepaired.push(row.id);
+            continue;
+        };
+        sqlx::query(
+            "UPDATE recipes SET ingredients = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(sqlx::types::Json(&ingredients))
+        .bind(row.id)
+        .execute(&mut *conn)
+        .await?;
+        report.ingredients_rewritten.push(row.id);
+    }
+    Ok(())
+}
+
+/// The ingredient half of [`repair`], run at startup so lists from before
+/// structured ingredients don't have to be parsed on every load.
+///
+/// # Errors
+/// Err if a query fails; nothing is changed then.
+pub async fn rewrite_legacy_ingredients(pool: &SqlitePool) -> sqlx::Result<RepairReport> {
+    let mut report = RepairReport::default();
+    let mut tx = pool.begin().await?;
+    rewrite_ingredients(&mut tx, &mut report).await?;
+    tx.commit().await?;
+    Ok(report)
+}
+
+/// Rewrite ingredient lists that don't decode (see [`rebuild_ingredients`])
+/// and delete orphaned meal-plan entries, in one transaction.
+///
+/// # Errors
+/// Err if a query fails; nothing is changed then.
+pub async fn repair(pool: &SqlitePool) -> sqlx::Result<RepairReport> {
+    let mut report = RepairReport::default();
+    let mut tx = pool.begin().await?;
+
+    rewrite_ingredients(&mut tx, &mut report).await?;
+
+    report.meal_plan_deleted = orphaned_meal_plan(&mut *tx).await?;
+    for id in &report.meal_plan_deleted {
+        sqlx::query("DELETE FROM meal_plan WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(ingredients: &[Ingredient]) -> Vec<&str> {
+        ingredients.iter().map(|i| i.name.as_str()).collect()
+    }
+
+    #[test]
+    fn rebuild_parses_string_entries_and_keeps_structured_ones() {
+        let rebuilt =
+            rebuild_ingredients(r#"["200 g flour", {"name": "salt"}, null, "2 eggs"]"#).unwrap();
+        assert_eq!(names(&rebuilt), ["flour", "salt", "eggs"]);
+        assert_eq!(rebuilt[0].quantity, Some(200.0));
+        assert_eq!(rebuilt[0].unit.as_deref(), Some("g"));
+
+        let rebuilt = rebuild_ingredients("1 onion\n\n2 carrots").unwrap();
+        assert_eq!(names(&rebuilt), ["onion", "carrots"]);
+
+        assert!(rebuild_ingredients(r#"{"name": "flour"}"#).is_none());
+        assert!(rebuild_ingredients(r"[42]").is_none());
+    }
+
+    #[tokio::test]
+    async fn rewrite_legacy_ingredients_leaves_structured_lists_alone() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pool = crate::db::make_pool(tmp.path().join("db.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for ingredients in [
+            r#"[{"name": "salt"}]"#,
+            r#"["2 carrots", "200 g flour"]"#,
+            "42",
+        ] {
+            let id: i64 = sqlx::query_scalar(
+                "INSERT INTO recipes (title, ingredients, instructions) \
+                 VALUES ('r', ?, '[]') RETURNING id",
+            )
+            .bind(ingredients)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            ids.push(id);
+        }
+
+        let report = rewrite_legacy_ingredients(&pool).await.unwrap();
+        assert_eq!(report.ingredients_rewritten, [ids[1]]);
+        assert_eq!(report.ingredients_unrepaired, [ids[2]]);
+        assert!(report.meal_plan_deleted.is_empty());
+
+        let stored: String = sqlx::query_scalar("SELECT ingredients FROM recipes WHERE id = ?")
+            .bind(ids[1])
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let stored: Vec<Ingredient> = serde_json::from_str(&stored).unwrap();
+        assert_eq!(names(&stored), ["carrots", "flour"]);
+        assert_eq!(stored[1].unit.as_deref(), Some("g"));
+
+        let again = rewrite_legacy_ingredients(&pool).await.unwrap();
+        assert!(again.ingredients_rewritten.is_empty());
+    }
+}
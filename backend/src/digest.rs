@@ -0,0 +1,381 @@
+//! Weekly digest: next week's meal plan and the current shopping list,
+//! posted to ntfy on a configured weekday and hour.
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::fmt::Write as _;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::models::AppState;
+use crate::routes::settings::get_setting;
+
+/// Settings-table key holding the RFC 3339 time of the last scheduled send.
+const LAST_SENT_KEY: &str = "digest_last_sent";
+
+/// How often the scheduler checks whether a digest is due.
+pub const CHECK_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Everything the digest shows, loaded up front so [`compose`] stays pure.
+pub struct DigestData {
+    /// First day of the week the digest covers.
+    pub week_start: NaiveDate,
+    /// `(day, recipe title)` for planned meals in that week.
+    pub meals: Vec<(NaiveDate, String)>,
+    /// Category names in list order.
+    pub categories: Vec<String>,
+    /// `(category, text)` for every item still to buy.
+    pub shopping: Vec<(Option<String>, String)>,
+}
+
+/// Plain-text digest body.
+#[must_use]
+pub fn compose(data: &DigestData) -> String {
+    let end = data.week_start + Days::new(6);
+    let mut out = format!(
+        "Meal plan {} – {}\n",
+        data.week_start.format("%a %d %b"),
+        end.format("%a %d %b")
+    );
+
+    let mut unplanned = 0;
+    for day in data.week_start.iter_days().take(7) {
+        let titles: Vec<&str> = data
+            .meals
+            .iter()
+            .filter(|(d, _)| *d == day)
+            .map(|(_, t)| t.as_str())
+            .collect();
+        if titles.is_empty() {
+            unplanned += 1;
+            let _ = writeln!(out, "{}: —", day.format("%a %d"));
+        } else {
+            let _ = writeln!(out, "{}: {}", day.format("%a %d"), titles.join(", "));
+        }
+    }
+    match unplanned {
+        0 => out.push_str("Every day is planned.\n"),
+        1 => out.push_str("1 day without a planned meal.\n"),
+        n => {
+            let _ = writeln!(out, "{n} days without a planned meal.");
+        }
+    }
+
+    let _ = write!(out, "\nShopping list ({} items)\n", data.shopping.len());
+    if data.shopping.is_empty() {
+        out.push_str("Nothing to buy.\n");
+    }
+    for (category, items) in group_shopping(data) {
+        let _ = writeln!(out, "{category}:");
+        for text in items {
+            let _ = writeln!(out, "- {text}");
+        }
+    }
+    out
+}
+
+/// Items grouped by category in list order; unknown categories follow in
+/// name order and uncategorized items come last under "Other".
+fn group_shopping(data: &DigestData) -> Vec<(&str, Vec<&str>)> {
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (category, text) in &data.shopping {
+        let category = category
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+            .unwrap_or("Other");
+        if let Some((_, items)) = groups.iter_mut().find(|(c, _)| *c == category) {
+            items.push(text);
+        } else {
+            groups.push((category, vec![text]));
+        }
+    }
+    groups.sort_by_key(|(c, _)| {
+        let rank = data
+            .categories
+            .iter()
+            .position(|known| known == c)
+            .unwrap_or(usize::MAX);
+        (*c == "Other", rank, c.to_string())
+    });
+    groups
+}
+
+/// First `starts_on` day after `today`, i.e. the start of the coming week.
+#[must_use]
+pub fn next_week_start(today: NaiveDate, starts_on: Weekday) -> NaiveDate {
+    today
+        .iter_days()
+        .skip(1)
+        .find(|d| d.weekday() == starts_on)
+        .unwrap_or(today)
+}
+
+/// The most recent scheduled slot at or before `now`.
+fn last_slot(now: NaiveDateTime, weekday: Weekday, hour: u32) -> NaiveDateTime {
+    let at = NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or(NaiveTime::MIN);
+    let back = now.weekday().days_since(weekday);
+    let slot = (now.date() - Days::new(u64::from(back))).and_time(at);
+    if slot > now {
+        slot - Days::new(7)
+    } else {
+        slot
+    }
+}
+
+/// Whether the digest for the slot on or before `now` still has to go out.
+/// A slot more than a day old is skipped rather than sent late.
+#[must_use]
+pub fn is_due(
+    now: NaiveDateTime,
+    weekday: Weekday,
+    hour: u32,
+    last_sent: Option<NaiveDateTime>,
+) -> bool {
+    let slot = last_slot(now, weekday, hour);
+    now - slot < chrono::Duration::days(1) && last_sent.is_none_or(|t| t < slot)
+}
+
+/// Load the data for the week after `today`.
+///
+/// # Errors
+///
+/// Err if querying the database fails.
+pub async fn load(pool: &sqlx::SqlitePool, today: NaiveDate) -> sqlx::Result<DigestData> {
+    let starts_on = get_setting(pool, "week_start")
+        .await
+        .and_then(|s| s.parse::<Weekday>().ok())
+        .unwrap_or(Weekday::Mon);
+    let week_start = next_week_start(today, starts_on);
+    let end = week_start + Days::new(6);
+
+    let meals: Vec<(String, String)> = sqlx::query_as(
+        r"
+        SELECT mp.day, r.title
+          FROM meal_plan mp
+          JOIN recipes r ON r.id = mp.recipe_id
+         WHERE mp.day >= ? AND mp.day <= ?
+         ORDER BY mp.day, mp.id
+        ",
+    )
+    .bind(week_start.format("%Y-%m-%d").to_string())
+    .bind(end.format("%Y-%m-%d").to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let categories: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM shopping_categories ORDER BY sort_order, id")
+            .fetch_all(pool)
+            .await?;
+
+    let shopping: Vec<(Option<String>, String)> = sqlx::query_as(
+        r"
+        SELECT category, text
+          FROM shopping_items_view
+         WHERE done = 0
+         ORDER BY id
+        ",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(DigestData {
+        week_start,
+        meals: meals
+            .into_iter()
+            .filter_map(|(day, title)| {
+                Some((NaiveDate::parse_from_str(&day, "%Y-%m-%d").ok()?, title))
+            })
+            .collect(),
+        categories,
+        shopping,
+    })
+}
+
+/// Where the digest goes: `--digest-ntfy-url`, else `--ntfy-url`.
+#[must_use]
+pub fn ntfy_target(state: &AppState) -> Option<&str> {
+    state
+        .config
+        .digest_ntfy_url
+        .as_deref()
+        .or(state.config.ntfy_url.as_deref())
+}
+
+/// Post `body` to the configured ntfy topic.
+///
+/// # Errors
+///
+/// Err if no ntfy URL is configured or the request fails.
+pub async fn send(state: &AppState, body: &str) -> anyhow::Result<()> {
+    let Some(url) = ntfy_target(state) else {
+        anyhow::bail!("no ntfy URL configured for the digest");
+    };
+    let resp = state
+        .http
+        .post(url)
+        .header("Title", "Blaz weekly digest")
+        .body(body.to_string())
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        anyhow::bail!("ntfy returned {}", resp.status());
+    }
+    Ok(())
+}
+
+async fn last_sent(pool: &sqlx::SqlitePool) -> Option<NaiveDateTime> {
+    let raw = get_setting(pool, LAST_SENT_KEY).await?;
+    DateTime::parse_from_rfc3339(&raw)
+        .ok()
+        .map(|t| t.with_timezone(&Local).naive_local())
+}
+
+/// Compose and send the digest if one is due at `now`, recording the send
+/// so restarts don't repeat it. Returns whether a digest went out.
+///
+/// # Errors
+///
+/// Err if loading the data, sending or recording the send fails.
+pub async fn run_if_due(
+    state: &AppState,
+    weekday: Weekday,
+    hour: u32,
+    now: DateTime<Local>,
+) -> anyhow::Result<bool> {
+    let naive = now.naive_local();
+    if !is_due(naive, weekday, hour, last_sent(&state.pool).await) {
+        return Ok(false);
+    }
+    let data = load(&state.pool, naive.date()).await?;
+    send(state, &compose(&data)).await?;
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(LAST_SENT_KEY)
+        .bind(now.to_rfc3339())
+        .execute(&state.pool)
+        .await?;
+    tracing::info!("sent weekly digest for the week of {}", data.week_start);
+    Ok(true)
+}
+
+/// Check every `interval` whether the digest is due, using `clock` for the
+/// current time, until `shutdown` flips to `true` or its sender is dropped.
+pub fn spawn_scheduler<C>(
+    state: AppState,
+    weekday: Weekday,
+    hour: u32,
+    interval: Duration,
+    clock: C,
+    mut shutdown: watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()>
+where
+    C: Fn() -> DateTime<Local> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = tick.tick() => {
+                    if let Err(e) = run_if_due(&state, weekday, hour, clock()).await {
+                        tracing::warn!("weekly digest failed: {e:#}");
+                    }
+                }
+                changed = shutdown.changed() => {
+                    if changed.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+        tracing::debug!("digest scheduler stopped");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn at(d: NaiveDate, h: u32, min: u32) -> NaiveDateTime {
+        d.and_hms_opt(h, min, 0).unwrap()
+    }
+
+    fn sample() -> DigestData {
+        // Week of Mon 2026-01-05.
+        DigestData {
+            week_start: date(2026, 1, 5),
+            meals: vec![
+                (date(2026, 1, 5), "Shakshuka".to_string()),
+                (date(2026, 1, 7), "Carbonara".to_string()),
+                (date(2026, 1, 7), "Green salad".to_string()),
+                (date(2026, 1, 10), "Pizza".to_string()),
+            ],
+            categories: vec!["Fruits".to_string(), "Vegetables".to_string()],
+            shopping: vec![
+                (Some("Vegetables".to_string()), "2 onions".to_string()),
+                (None, "foil".to_string()),
+                (Some("Fruits".to_string()), "6 apples".to_string()),
+                (Some("Vegetables".to_string()), "1 kg tomatoes".to_string()),
+                (Some("Garden".to_string()), "basil plant".to_string()),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_compose_snapshot() {
+        assert_eq!(
+            compose(&sample()),
+            include_str!("../tests/fixtures/digest_week.txt")
+        );
+    }
+
+    #[test]
+    fn test_compose_empty_week() {
+        let text = compose(&DigestData {
+            week_start: date(2026, 1, 5),
+            meals: Vec::new(),
+            categories: Vec::new(),
+            shopping: Vec::new(),
+        });
+        assert!(text.contains("7 days without a planned meal."));
+        assert!(text.ends_with("Shopping list (0 items)\nNothing to buy.\n"));
+    }
+
+    #[test]
+    fn test_next_week_start() {
+        let sunday = date(2026, 1, 4);
+        assert_eq!(next_week_start(sunday, Weekday::Mon), date(2026, 1, 5));
+        assert_eq!(next_week_start(sunday, Weekday::Sun), date(2026, 1, 11));
+        assert_eq!(
+            next_week_start(date(2026, 1, 5), Weekday::Mon),
+            date(2026, 1, 12)
+        );
+    }
+
+    #[test]
+    fn test_is_due() {
+        let sunday = date(2026, 1, 4);
+        // Before the hour, at the hour, and later that evening.
+        assert!(!is_due(at(sunday, 17, 59), Weekday::Sun, 18, None));
+        assert!(is_due(at(sunday, 18, 0), Weekday::Sun, 18, None));
+        assert!(is_due(at(sunday, 23, 30), Weekday::Sun, 18, None));
+        // Already sent for this slot.
+        assert!(!is_due(
+            at(sunday, 19, 0),
+            Weekday::Sun,
+            18,
+            Some(at(sunday, 18, 1))
+        ));
+        // Last week's send doesn't count.
+        assert!(is_due(
+            at(sunday, 19, 0),
+            Weekday::Sun,
+            18,
+            Some(at(date(2025, 12, 28), 18, 1))
+        ));
+        // A slot missed by more than a day is skipped.
+        assert!(!is_due(at(date(2026, 1, 6), 9, 0), Weekday::Sun, 18, None));
+    }
+}
@@ -33,6 +33,11 @@ pub async fn serve_embedded_web(uri: Uri) -> impl IntoResponse {
     (StatusCode::NOT_FOUND, "Not found").into_response()
 }
 
+/// The SPA entry document, if the web build is embedded.
+pub fn index_html() -> Option<String> {
+    WebAssets::get("index.html").map(|c| String::from_utf8_lossy(&c.data).into_owned())
+}
+
 fn serve_asset(path: &str, content: Vec<u8>) -> Response<Body> {
     let mime = mime_guess::from_path(path)
         .first_or_octet_stream()
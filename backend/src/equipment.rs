@@ -0,0 +1,118 @@
+/// Canonical names for equipment that shows up under several aliases.
+const SYNONYMS: &[(&str, &str)] = &[
+    ("crockpot", "slow cooker"),
+    ("crock pot", "slow cooker"),
+    ("crock-pot", "slow cooker"),
+    ("instant pot", "pressure cooker"),
+    ("instapot", "pressure cooker"),
+    ("multicooker", "pressure cooker"),
+    ("dutch-oven", "dutch oven"),
+    ("french oven", "dutch oven"),
+    ("cocotte", "dutch oven"),
+    ("immersion blender", "stick blender"),
+    ("hand blender", "stick blender"),
+    ("kitchenaid", "stand mixer"),
+    ("electric mixer", "hand mixer"),
+    ("sheet pan", "baking sheet"),
+    ("baking tray", "baking sheet"),
+    ("oven tray", "baking sheet"),
+    ("frying pan", "skillet"),
+    ("fry pan", "skillet"),
+    ("airfryer", "air fryer"),
+    ("microwave oven", "microwave"),
+    ("grill pan", "griddle pan"),
+    ("barbecue", "grill"),
+    ("bbq", "grill"),
+];
+
+/// Canonical form of one equipment entry: trimmed, lowercased, inner
+/// whitespace collapsed and synonyms resolved. Empty input yields `None`.
+#[must_use]
+pub fn normalize_one(raw: &str) -> Option<String> {
+    let cleaned = raw
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if cleaned.is_empty() {
+        return None;
+    }
+    let canonical = SYNONYMS
+        .iter()
+        .find(|(alias, _)| *alias == cleaned)
+        .map_or(cleaned, |(_, name)| (*name).to_string());
+    Some(canonical)
+}
+
+/// Normalize a whole list, dropping blanks and duplicates while keeping the
+/// first-seen order.
+#[must_use]
+pub fn normalize(raw: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for name in raw.iter().filter_map(|s| normalize_one(s)) {
+        if !out.contains(&name) {
+            out.push(name);
+        }
+    }
+    out
+}
+
+/// `current` plus `add` minus `remove`, normalized the same way as a full
+/// replacement so bulk edits and PATCH agree.
+#[must_use]
+pub fn apply_changes(current: &[String], add: &[String], remove: &[String]) -> Vec<String> {
+    let remove = normalize(remove);
+    let merged: Vec<String> = current.iter().chain(add).cloned().collect();
+    normalize(&merged)
+        .into_iter()
+        .filter(|name| !remove.contains(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn lowercases_and_collapses_whitespace() {
+        assert_eq!(
+            normalize_one("  Food   Processor "),
+            Some("food processor".into())
+        );
+        assert_eq!(normalize_one("   "), None);
+    }
+
+    #[test]
+    fn resolves_synonyms() {
+        assert_eq!(normalize_one("Crockpot"), Some("slow cooker".into()));
+        assert_eq!(normalize_one("Instant Pot"), Some("pressure cooker".into()));
+        assert_eq!(normalize_one("Sheet pan"), Some("baking sheet".into()));
+    }
+
+    #[test]
+    fn dedupes_after_normalizing() {
+        let got = normalize(&strings(&[
+            "Crock-Pot",
+            "slow cooker",
+            "Dutch Oven",
+            "",
+            "dutch oven",
+            "Blender",
+        ]));
+        assert_eq!(got, strings(&["slow cooker", "dutch oven", "blender"]));
+    }
+
+    #[test]
+    fn apply_changes_adds_and_removes_normalized() {
+        let got = apply_changes(
+            &strings(&["oven", "wok"]),
+            &strings(&["Crockpot", "OVEN"]),
+            &strings(&[" Wok "]),
+        );
+        assert_eq!(got, strings(&["oven", "slow cooker"]));
+    }
+}
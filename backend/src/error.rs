@@ -7,10 +7,55 @@ pub enum AppError {
     Status(StatusCode),
     /// Return a status code with a plain-text message body.
     Msg(StatusCode, String),
+    /// Return a status code with a JSON body carrying a machine-readable code.
+    Code(StatusCode, &'static str, String),
+    /// Return a status code with an arbitrary JSON body (structured details).
+    Json(StatusCode, serde_json::Value),
     /// Internal error -> 500 with JSON body; logged.
     Anyhow(anyhow::Error),
 }
 
+impl AppError {
+    /// 409 with a machine-readable code and the record that is in the way, so
+    /// clients can offer to open it:
+    /// `{"error": {"code", "message", "existing"}}`.
+    pub fn conflict(code: &'static str, msg: impl Into<String>, existing: impl Serialize) -> Self {
+        Self::Json(
+            StatusCode::CONFLICT,
+            serde_json::json!({
+                "error": {
+                    "code": code,
+                    "message": msg.into(),
+                    "existing": existing,
+                },
+            }),
+        )
+    }
+
+    /// Add the fields of the `details` object to a [`Self::conflict`] error.
+    /// Anything else is returned unchanged.
+    #[must_use]
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        if let (Self::Json(_, body), serde_json::Value::Object(details)) = (&mut self, details)
+            && let Some(serde_json::Value::Object(error)) = body.get_mut("error")
+        {
+            error.extend(details);
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Status(code) => write!(f, "{code}"),
+            Self::Msg(code, msg) | Self::Code(code, _, msg) => write!(f, "{code}: {msg}"),
+            Self::Json(code, body) => write!(f, "{code}: {body}"),
+            Self::Anyhow(e) => write!(f, "{e}"),
+        }
+    }
+}
+
 impl From<StatusCode> for AppError {
     fn from(code: StatusCode) -> Self {
         Self::Status(code)
@@ -55,17 +100,15 @@ impl From<tokio::task::JoinError> for AppError {
     }
 }
 
-impl From<axum::extract::rejection::JsonRejection> for AppError {
-    fn from(rejection: axum::extract::rejection::JsonRejection) -> Self {
-        let msg = rejection.body_text();
-        tracing::debug!("JSON deserialization failed: {}", msg);
-        Self::Msg(StatusCode::UNPROCESSABLE_ENTITY, msg)
-    }
+#[derive(Serialize)]
+struct ErrBody {
+    error: String,
 }
 
 #[derive(Serialize)]
-struct ErrBody {
+struct CodedErrBody {
     error: String,
+    code: &'static str,
 }
 
 impl IntoResponse for AppError {
@@ -85,6 +128,15 @@ impl IntoResponse for AppError {
                 }
                 (code, msg).into_response()
             }
+            Self::Code(status, code, msg) => {
+                tracing::debug!("{} ({}): {}", status, code, msg);
+                let body = Json(CodedErrBody { error: msg, code });
+                (status, body).into_response()
+            }
+            Self::Json(status, body) => {
+                tracing::debug!("{}: {}", status, body);
+                (status, Json(body)).into_response()
+            }
             Self::Anyhow(err) => {
                 tracing::error!("{:#}", err);
                 crate::ntfy::notify(&format!("blaz error: {err:#}"));
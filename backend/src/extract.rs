@@ -0,0 +1,115 @@
+use axum::{
+    extract::{
+        FromRequest, Request,
+        rejection::{BytesRejection, FailedToBufferBody, JsonRejection},
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::error::AppError;
+
+/// [`axum::Json`], except that a body which can't be read or parsed is
+/// answered with `{"error", "code", "field_hint", "request_id"}` the client
+/// can show instead of axum's plain-text rejection. Malformed and mistyped
+/// JSON both answer 422.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    axum::Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let request_id = req
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(rejection_error(&rejection, request_id.as_deref())),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// Status and machine-readable code for a rejected JSON body.
+fn rejection_error(rejection: &JsonRejection, request_id: Option<&str>) -> AppError {
+    let msg = rejection.body_text();
+    let (status, code) = match rejection {
+        JsonRejection::JsonDataError(_) | JsonRejection::JsonSyntaxError(_) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, "invalid_json")
+        }
+        JsonRejection::MissingJsonContentType(_) => {
+            (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type")
+        }
+        JsonRejection::BytesRejection(BytesRejection::FailedToBufferBody(
+            FailedToBufferBody::LengthLimitError(_),
+        )) => (StatusCode::PAYLOAD_TOO_LARGE, "body_too_large"),
+        _ => return AppError::Msg(rejection.status(), msg),
+    };
+    let mut body = serde_json::json!({
+        "error": msg,
+        "code": code,
+        "request_id": request_id,
+    });
+    if matches!(rejection, JsonRejection::JsonDataError(_))
+        && let Some(hint) = field_hint(&msg)
+    {
+        body["field_hint"] = hint.into();
+    }
+    AppError::Json(status, body)
+}
+
+/// The field a JSON deserialize error points at: the path axum prefixes
+/// the serde message with (`ingredients[0].quantity: invalid type ...`), or
+/// the name in "missing field `title`".
+fn field_hint(msg: &str) -> Option<String> {
+    let detail = msg.split_once("target type: ")?.1;
+    if let Some((path, _)) = detail.split_once(": ")
+        && !path.is_empty()
+        && path != "."
+        && path
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '[' | ']'))
+    {
+        return Some(path.to_string());
+    }
+    let rest = detail.split_once("missing field `")?.1;
+    Some(rest.split_once('`')?.0.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_hint_finds_the_offending_field() {
+        let prefix = "Failed to deserialize the JSON body into the target type: ";
+        assert_eq!(
+            field_hint(&format!(
+                "{prefix}ingredients[0].quantity: invalid type: map, expected f64 at line 1 column 40"
+            ))
+            .as_deref(),
+            Some("ingredients[0].quantity")
+        );
+        assert_eq!(
+            field_hint(&format!("{prefix}missing field `title` at line 1 column 2")).as_deref(),
+            Some("title")
+        );
+        assert_eq!(
+            field_hint("Failed to parse the request body as JSON: EOF while parsing"),
+            None
+        );
+    }
+}
@@ -1,4 +1,7 @@
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
 use regex::Regex;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::fmt::Write as _;
 use std::sync::LazyLock;
 
 /// Extract <title>...</title> from raw HTML and decode basic entities.
@@ -28,24 +31,197 @@ pub fn fallback_title_from_url(url: &str) -> Option<String> {
     })
 }
 
-/// Convert HTML to readable plain text.
+/// Elements dropped with their content when flattening a page.
+const SKIPPED_TAGS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "iframe", "nav", "footer", "aside",
+    "form",
+];
+
+/// Class/id fragments of cookie banners and comment sections.
+const NOISE_MARKERS: &[&str] = &["comment", "cookie", "consent", "gdpr"];
+
+/// Elements that start a new line in the flattened text.
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// A content root with less text than this is a widget ("Jump to recipe",
+/// a rating), not the recipe, and the next kind of root is tried.
+const MIN_ROOT_CHARS: usize = 200;
+
+fn class_and_id(el: ElementRef) -> String {
+    let v = el.value();
+    format!(
+        "{} {}",
+        v.attr("class").unwrap_or_default(),
+        v.attr("id").unwrap_or_default()
+    )
+    .to_ascii_lowercase()
+}
+
+/// Whether `el` is page chrome rather than content. Structural containers
+/// are kept even when a theme gives them a class like "comments-open".
+fn is_noise(el: ElementRef) -> bool {
+    let name = el.value().name();
+    if SKIPPED_TAGS.contains(&name) {
+        return true;
+    }
+    if matches!(name, "html" | "body" | "main" | "article") {
+        return false;
+    }
+    let attrs = class_and_id(el);
+    NOISE_MARKERS.iter().any(|m| attrs.contains(m))
+}
+
+/// Append the text under `el` to `lines`, one line per block element.
+fn flatten(el: ElementRef, lines: &mut Vec<String>) {
+    let block = BLOCK_TAGS.contains(&el.value().name());
+    let cell = matches!(el.value().name(), "td" | "th");
+    if block {
+        lines.push(String::new());
+    }
+    for child in el.children() {
+        match child.value() {
+            Node::Text(t) => {
+                if let Some(line) = lines.last_mut() {
+                    line.push_str(t);
+                } else {
+                    lines.push(t.to_string());
+                }
+            }
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child)
+                    && !is_noise(child)
+                {
+                    flatten(child, lines);
+                }
+            }
+            _ => {}
+        }
+    }
+    if block {
+        lines.push(String::new());
+    } else if cell && let Some(line) = lines.last_mut() {
+        line.push(' ');
+    }
+}
+
+/// The text of `roots`, whitespace collapsed and empty lines dropped.
+fn roots_text<'a>(roots: impl IntoIterator<Item = ElementRef<'a>>) -> String {
+    let mut lines = Vec::new();
+    for root in roots {
+        lines.push(String::new());
+        flatten(root, &mut lines);
+    }
+    lines
+        .iter()
+        .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Outermost elements matching `pred` that are not inside page chrome.
+fn outermost<'a>(doc: &'a Html, pred: impl Fn(ElementRef) -> bool) -> Vec<ElementRef<'a>> {
+    static ALL: LazyLock<Selector> = LazyLock::new(|| Selector::parse("*").unwrap());
+    let mut found: Vec<ElementRef<'a>> = Vec::new();
+    for el in doc.select(&ALL) {
+        if !pred(el) {
+            continue;
+        }
+        let mut ancestors = el.ancestors().filter_map(ElementRef::wrap);
+        if ancestors.any(|a| is_noise(a) || found.contains(&a)) {
+            continue;
+        }
+        found.push(el);
+    }
+    found
+}
+
+/// Convert a page to readable plain text, focused on the recipe.
+///
+/// The text comes from the elements whose class or id mentions "recipe",
+/// else `<main>`, else `<article>`, else the whole page. Navigation, footers,
+/// asides, forms, cookie banners and comment sections are left out, and each
+/// paragraph, heading or list item gets its own line.
 pub fn html_to_plain_text(html: &str) -> String {
-    static SCRIPT_RE: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"(?is)<script[^>]*>.*?</script>").unwrap());
-    static STYLE_RE: LazyLock<Regex> =
-        LazyLock::new(|| Regex::new(r"(?is)<style[^>]*>.*?</style>").unwrap());
-    static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<[^>]+>").unwrap());
-    static WS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[ \t\r\f]+").unwrap());
-    static NL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\n{3,}").unwrap());
-
-    let mut s = SCRIPT_RE.replace_all(html, " ").into_owned();
-    s = STYLE_RE.replace_all(&s, " ").into_owned();
-    s = TAG_RE.replace_all(&s, "\n").into_owned();
-    s = decode_entities_basic(&s);
-    s = WS_RE.replace_all(&s, " ").into_owned();
-    s = s.replace("\r\n", "\n").replace('\r', "\n");
-    s = NL_RE.replace_all(&s, "\n\n").into_owned();
-    s.trim().to_string()
+    let doc = Html::parse_document(html);
+    let tiers: [&dyn Fn(ElementRef) -> bool; 3] = [
+        &|el| el.value().name() != "html" && class_and_id(el).contains("recipe"),
+        &|el| el.value().name() == "main",
+        &|el| el.value().name() == "article",
+    ];
+    for pred in tiers {
+        let text = roots_text(outermost(&doc, pred));
+        if text.chars().count() >= MIN_ROOT_CHARS {
+            return text;
+        }
+    }
+    roots_text([doc.root_element()])
+}
+
+/// How much of a page is searched for a `<meta charset>` declaration.
+const META_SCAN_BYTES: usize = 4096;
+
+/// Decode a fetched page to text. The charset comes from a byte-order mark,
+/// the `Content-Type` header, or a `<meta>` tag near the top, in that order.
+/// Undeclared pages are read as UTF-8, or as windows-1252 (which covers
+/// latin-1) when they are not valid UTF-8.
+#[must_use]
+pub fn decode_html(body: &[u8], content_type: Option<&str>) -> String {
+    static CHARSET_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"(?i)charset\s*=\s*["']?([\w.:-]+)"#).unwrap());
+    static META_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?([\w.:-]+)"#).unwrap());
+
+    let label = |re: &Regex, s: &str| {
+        re.captures(s)
+            .and_then(|c| Encoding::for_label(c[1].as_bytes()))
+    };
+    let head = String::from_utf8_lossy(&body[..body.len().min(META_SCAN_BYTES)]);
+    let declared = content_type
+        .and_then(|ct| label(&CHARSET_RE, ct))
+        .or_else(|| label(&META_RE, &head));
+    let encoding = declared.map_or_else(
+        || {
+            if std::str::from_utf8(body).is_ok() {
+                UTF_8
+            } else {
+                WINDOWS_1252
+            }
+        },
+        // A page can't really be UTF-16 if its declaration is readable as
+        // ASCII; this maps such labels to UTF-8 as browsers do.
+        Encoding::output_encoding,
+    );
+    // `decode` lets a byte-order mark override the declaration.
+    encoding.decode(body).0.into_owned()
 }
 
 #[must_use]
@@ -64,6 +240,95 @@ pub fn decode_entities_basic(s: &str) -> String {
         .replace("&nbsp;", " ")
 }
 
+/// Escape text for HTML element content and attribute values.
+#[must_use]
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// A tag-looking `<...>`: `<` directly followed by a letter, `/`, `!` or `?`,
+/// so comparisons like "x < 5" are left alone. Captures `/` and the name.
+static TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<(/?)([A-Za-z][A-Za-z0-9]*|[!?])[^>]*>").unwrap());
+
+/// Comments and `<script>` / `<style>` blocks with their content (or, when
+/// unterminated, everything to the end).
+static HIDDEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<!--.*?(-->|$)|<(script|style)\b[^>]*>.*?(</(script|style)\s*>|$)").unwrap()
+});
+
+/// Tags that sit inside a word or sentence; removing them must not add a space.
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "code", "em", "i", "mark", "small", "span", "strong", "sub", "sup", "u",
+];
+
+/// Tags [`sanitize_fragment`] keeps.
+const ALLOWED_TAGS: &[&str] = &["b", "i", "em", "strong", "br"];
+
+/// Plain text from a string that may carry stray markup (e.g. an instruction
+/// an LLM copied with its HTML). Comments, script and style blocks go with
+/// their content; entities are decoded, and anything that decodes into a tag is
+/// stripped too.
+#[must_use]
+pub fn strip_tags(s: &str) -> String {
+    let strip = |s: &str| {
+        let s = HIDDEN_RE.replace_all(s, " ");
+        TAG_RE
+            .replace_all(&s, |c: &regex::Captures| {
+                let name = c[2].to_ascii_lowercase();
+                if INLINE_TAGS.contains(&name.as_str()) {
+                    String::new()
+                } else {
+                    " ".to_string()
+                }
+            })
+            .into_owned()
+    };
+    let s = strip(&decode_entities_basic(&strip(s)));
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Safe HTML for rich text that is meant to be rendered: `<b>`, `<i>`,
+/// `<em>`, `<strong>` and `<br>` survive (without attributes and with
+/// unclosed tags closed), other tags are dropped and all text is escaped.
+#[must_use]
+pub fn sanitize_fragment(s: &str) -> String {
+    let s = HIDDEN_RE.replace_all(s, "");
+    let text = |t: &str| escape_html(&decode_entities_basic(t));
+    let mut out = String::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut last = 0;
+    for c in TAG_RE.captures_iter(&s) {
+        let m = c.get(0).unwrap_or_else(|| unreachable!());
+        out.push_str(&text(&s[last..m.start()]));
+        last = m.end();
+
+        let name = c[2].to_ascii_lowercase();
+        if !ALLOWED_TAGS.contains(&name.as_str()) {
+            continue;
+        }
+        if name == "br" {
+            out.push_str("<br>");
+        } else if c[1].is_empty() {
+            let _ = write!(out, "<{name}>");
+            open.push(name);
+        } else if let Some(pos) = open.iter().rposition(|t| *t == name) {
+            for t in open.drain(pos..).rev() {
+                let _ = write!(out, "</{t}>");
+            }
+        }
+    }
+    out.push_str(&text(&s[last..]));
+    for t in open.into_iter().rev() {
+        let _ = write!(out, "</{t}>");
+    }
+    out
+}
+
 /// Normalize noisy page titles.
 pub fn clean_title(input: &str) -> String {
     // Strip adjectives / diet tags
@@ -165,7 +430,7 @@ mod tests {
     fn test_html_to_plain_text() {
         assert_eq!(
             html_to_plain_text("<p>Hello <b>world</b></p>"),
-            "Hello \nworld"
+            "Hello world"
         );
         assert_eq!(
             html_to_plain_text("<div><script>alert('test')</script>Content</div>"),
@@ -177,11 +442,231 @@ mod tests {
         );
         assert_eq!(
             html_to_plain_text("<p>Line 1</p><p>Line 2</p>"),
-            "Line 1\n\nLine 2"
+            "Line 1\nLine 2"
+        );
+        assert_eq!(
+            html_to_plain_text("<ul><li>1 egg</li><li>2 cups &amp; a bit<br>flour</li></ul>"),
+            "1 egg\n2 cups & a bit\nflour"
+        );
+        assert_eq!(
+            html_to_plain_text("<table><tr><td>Prep</td><td>10 min</td></tr></table>"),
+            "Prep 10 min"
         );
         assert_eq!(html_to_plain_text(""), "");
     }
 
+    /// A blog-style recipe page: menu, cookie banner, story, recipe card,
+    /// comments and footer.
+    const BLOG_PAGE: &str = r##"
+        <html><head><title>Pancakes</title><style>.x{}</style></head>
+        <body class="post-template">
+          <div id="cookie-notice"><p>We use cookies.</p><button>Accept</button></div>
+          <nav><ul><li><a href="/">Home</a></li><li><a href="/dinner">Dinner</a></li></ul></nav>
+          <main>
+            <article>
+              <h1>Fluffy pancakes</h1>
+              <p>My grandmother made these every Sunday, and the kitchen smelled of butter.</p>
+              <a class="jump-to-recipe" href="#recipe">Jump to Recipe</a>
+              <div class="wprm-recipe-container" id="recipe">
+                <h2 class="wprm-recipe-name">Fluffy pancakes</h2>
+                <h3>Ingredients</h3>
+                <ul class="wprm-recipe-ingredients">
+                  <li><span>200</span> <span>g</span> <span>flour</span></li>
+                  <li><span>2</span> <span>eggs</span></li>
+                  <li><span>300</span> <span>ml</span> <span>milk</span></li>
+                </ul>
+                <h3>Instructions</h3>
+                <ol>
+                  <li>Whisk the flour, eggs and milk into a smooth batter.</li>
+                  <li>Fry ladlefuls in a hot buttered pan until golden on both sides.</li>
+                </ol>
+              </div>
+              <div class="comments-area"><p>Made these twice, lovely!</p></div>
+            </article>
+          </main>
+          <aside><h3>Popular</h3><p>Best brownies</p></aside>
+          <form><input name="email"><p>Subscribe to the newsletter</p></form>
+          <footer>© Someone</footer>
+        </body></html>
+    "##;
+
+    #[test]
+    fn test_html_to_plain_text_prefers_recipe_card() {
+        assert_eq!(
+            html_to_plain_text(BLOG_PAGE),
+            "Jump to Recipe\n\
+             Fluffy pancakes\n\
+             Ingredients\n\
+             200 g flour\n\
+             2 eggs\n\
+             300 ml milk\n\
+             Instructions\n\
+             Whisk the flour, eggs and milk into a smooth batter.\n\
+             Fry ladlefuls in a hot buttered pan until golden on both sides."
+        );
+    }
+
+    #[test]
+    fn test_html_to_plain_text_falls_back_to_main_then_page() {
+        let story =
+            "Slow-cooked beans are the best thing to come out of a cold weekend. ".repeat(4);
+        let page = format!(
+            r#"<body><nav>Home · Recipes</nav>
+               <div class="recipe-rating">★★★★★</div>
+               <main><h1>Beans</h1><p>{story}</p></main>
+               <div id="comments">So good</div></body>"#
+        );
+        assert_eq!(
+            html_to_plain_text(&page),
+            format!("Beans\n{}", story.trim_end())
+        );
+
+        // Too little text anywhere for a root: the whole page, minus chrome.
+        assert_eq!(
+            html_to_plain_text(
+                r#"<nav>Menu</nav><article><p>Toast</p></article><p>Butter it.</p>
+                   <div class="gdpr-consent">Allow all</div>"#
+            ),
+            "Toast\nButter it."
+        );
+    }
+
+    #[test]
+    fn test_html_to_plain_text_finds_recipe_past_the_llm_cutoff() {
+        const LLM_CHARS: usize = 12_000;
+        let mut menu = String::new();
+        for i in 0..400 {
+            let _ = write!(menu, r#"<li><a href="/c/{i}">Category number {i}</a></li>"#);
+        }
+        let mut comments = String::new();
+        for i in 0..100 {
+            let _ = write!(
+                comments,
+                r#"<li class="comment"><p>Comment {i}: looks delicious, will try!</p></li>"#
+            );
+        }
+        let page = format!(
+            r#"<html><body>
+               <header><nav><ul>{menu}</ul></nav></header>
+               <div class="consent-banner"><p>{}</p></div>
+               <ol class="comment-list">{comments}</ol>
+               <div class="recipe-card">
+                 <h2>Spaghetti aglio e olio</h2>
+                 <ul><li>200 g spaghetti</li><li>4 cloves garlic</li><li>60 ml olive oil</li></ul>
+                 <ol><li>Boil the pasta.</li><li>Fry the garlic gently in the oil.</li></ol>
+               </div>
+               <footer>{menu}</footer>
+               </body></html>"#,
+            "We and our partners store cookies. ".repeat(50),
+        );
+
+        // Tag stripping alone leaves the ingredients past the cutoff.
+        assert!(strip_tags(&page).find("200 g spaghetti").unwrap() > LLM_CHARS);
+
+        let text = html_to_plain_text(&page);
+        assert!(text.len() < LLM_CHARS);
+        assert_eq!(
+            text,
+            "Spaghetti aglio e olio\n200 g spaghetti\n4 cloves garlic\n60 ml olive oil\n\
+             Boil the pasta.\nFry the garlic gently in the oil."
+        );
+    }
+
+    #[test]
+    fn test_decode_html_charsets() {
+        // "Crème brûlée" in latin-1.
+        let latin1 = b"<p>Cr\xe8me br\xfbl\xe9e</p>";
+        assert_eq!(
+            decode_html(latin1, Some("text/html; charset=ISO-8859-1")),
+            "<p>Crème brûlée</p>"
+        );
+        let with_meta = [
+            b"<head><meta charset=\"windows-1252\"></head>".as_slice(),
+            latin1,
+        ]
+        .concat();
+        assert!(decode_html(&with_meta, None).ends_with("<p>Crème brûlée</p>"));
+        let http_equiv = [
+            br#"<meta http-equiv="Content-Type" content="text/html; charset=iso-8859-1">"#
+                .as_slice(),
+            latin1,
+        ]
+        .concat();
+        assert!(decode_html(&http_equiv, Some("text/html")).ends_with("<p>Crème brûlée</p>"));
+        assert_eq!(decode_html(latin1, None), "<p>Crème brûlée</p>");
+
+        let utf8 = "<p>Crème brûlée</p>".as_bytes();
+        assert_eq!(decode_html(utf8, None), "<p>Crème brûlée</p>");
+        assert_eq!(
+            decode_html(utf8, Some("text/html; charset=utf-8")),
+            "<p>Crème brûlée</p>"
+        );
+        // A byte-order mark wins over a wrong header.
+        let bom = [b"\xef\xbb\xbf".as_slice(), utf8].concat();
+        assert_eq!(
+            decode_html(&bom, Some("text/html; charset=iso-8859-1")),
+            "<p>Crème brûlée</p>"
+        );
+        // An unknown label falls back to sniffing.
+        assert_eq!(
+            decode_html(latin1, Some("text/html; charset=bogus")),
+            "<p>Crème brûlée</p>"
+        );
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom's & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom&#39;s &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_strip_tags() {
+        assert_eq!(
+            strip_tags("<script>alert(1)</script>Boil <b>the</b> pasta."),
+            "Boil the pasta."
+        );
+        assert_eq!(strip_tags("<p>Mix</p><p>Bake</p>"), "Mix Bake");
+        assert_eq!(
+            strip_tags("Serve <img src=x onerror=alert(1)>hot"),
+            "Serve hot"
+        );
+        assert_eq!(
+            strip_tags("&lt;script&gt;alert(1)&lt;/script&gt;Stir"),
+            "Stir"
+        );
+        assert_eq!(
+            strip_tags("<style>p{}</style>Heat to < 80°C"),
+            "Heat to < 80°C"
+        );
+        assert_eq!(strip_tags("Rest<script>never closed"), "Rest");
+        assert_eq!(strip_tags("Salt & pepper"), "Salt & pepper");
+        assert_eq!(strip_tags("Chop<!-- <b>x</b> -->finely"), "Chop finely");
+    }
+
+    #[test]
+    fn test_sanitize_fragment() {
+        assert_eq!(
+            sanitize_fragment(r#"<b onclick="x()">Tip</b>: <em>really</em><br/>hot"#),
+            "<b>Tip</b>: <em>really</em><br>hot"
+        );
+        assert_eq!(
+            sanitize_fragment("<script>alert(1)</script><a href=\"javascript:x\">link</a>"),
+            "link"
+        );
+        assert_eq!(
+            sanitize_fragment("<strong>open <i>nested"),
+            "<strong>open <i>nested</i></strong>"
+        );
+        assert_eq!(sanitize_fragment("</b>1 < 2 & 3"), "1 &lt; 2 &amp; 3");
+        assert_eq!(
+            sanitize_fragment("&lt;img src=x onerror=alert(1)&gt;"),
+            "&lt;img src=x onerror=alert(1)&gt;"
+        );
+    }
+
     #[test]
     fn test_clean_title() {
         assert_eq!(clean_title("Best Pasta Recipe"), "Pasta");
@@ -0,0 +1,141 @@
+//! Resized renditions of recipe images, rendered on first request and kept
+//! under [`CACHE_DIR`] in the media dir.
+//!
+//! Widths snap to [`WIDTHS`] so the cache stays small. A rendition is reused
+//! while it is newer than its source; replacing the image re-renders it.
+
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::media_path;
+
+/// Renditions live here, relative to the media dir.
+pub const CACHE_DIR: &str = "cache";
+
+/// Widths a rendition can have; requests snap to the nearest one.
+pub const WIDTHS: [u32; 4] = [320, 640, 1024, 1600];
+
+/// The allowed width closest to `w` (the larger one on a tie).
+#[must_use]
+pub fn snap_width(w: u32) -> u32 {
+    WIDTHS
+        .into_iter()
+        .min_by_key(|&allowed| (allowed.abs_diff(w), u32::MAX - allowed))
+        .unwrap_or(WIDTHS[0])
+}
+
+/// Relative media name of the `width` rendition of `source` for a recipe.
+#[must_use]
+pub fn cache_name(recipe_id: i64, source: &str, width: u32) -> String {
+    let stem = Path::new(source)
+        .file_stem()
+        .map_or_else(|| "image".into(), |s| s.to_string_lossy());
+    format!("{CACHE_DIR}/{recipe_id}-{stem}-{width}.webp")
+}
+
+/// The recipe a name produced by [`cache_name`] belongs to.
+#[must_use]
+pub fn owner(name: &str) -> Option<i64> {
+    let file = name.strip_prefix(CACHE_DIR)?.strip_prefix('/')?;
+    file.split_once('-')?.0.parse().ok()
+}
+
+/// A rendition ready to serve.
+pub struct Rendition {
+    pub webp: Vec<u8>,
+    /// Changes whenever the source image or the width does.
+    pub etag: String,
+}
+
+/// The `width` rendition of the recipe image `source`, from the cache when it
+/// is up to date. Cache write failures only cost a re-render next time.
+///
+/// # Errors
+/// Err if the source can't be read or decoded, or encoding fails.
+pub async fn load_or_render(
+    media_dir: &Path,
+    recipe_id: i64,
+    source: &str,
+    width: u32,
+) -> anyhow::Result<Rendition> {
+    let source_path = media_path::resolve(media_dir, source)?;
+    let source_mtime = tokio::fs::metadata(&source_path).await?.modified()?;
+    let stamp = source_mtime
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let etag = format!("\"{stamp}-{width}\"");
+
+    let name = cache_name(recipe_id, source, width);
+    let cached = media_path::resolve(media_dir, &name);
+    if let Ok(path) = &cached
+        && is_fresh(path, source_mtime).await
+        && let Ok(webp) = tokio::fs::read(path).await
+    {
+        return Ok(Rendition { webp, etag });
+    }
+
+    let bytes = tokio::fs::read(&source_path).await?;
+    let webp = tokio::task::spawn_blocking(move || {
+        let img = crate::image_io::decode_oriented(&bytes)?;
+        crate::image_io::to_width_webp(&img, width)
+    })
+    .await??;
+
+    match cached {
+        Ok(path) => {
+            if let Err(e) = store(&path, &webp).await {
+                tracing::warn!(recipe_id, "could not cache image rendition: {e}");
+            }
+        }
+        Err(e) => tracing::warn!(recipe_id, "image rendition cache unavailable: {e}"),
+    }
+    Ok(Rendition { webp, etag })
+}
+
+async fn is_fresh(path: &Path, source_mtime: SystemTime) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|m| m.modified())
+        .is_ok_and(|m| m >= source_mtime)
+}
+
+async fn store(path: &Path, webp: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let tmp = path.with_extension("webp.tmp");
+    tokio::fs::write(&tmp, webp).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_width_picks_nearest_allowed_width() {
+        let cases = [
+            (0, 320),
+            (320, 320),
+            (480, 640),
+            (479, 320),
+            (700, 640),
+            (900, 1024),
+            (1312, 1600),
+            (5000, 1600),
+        ];
+        for (w, want) in cases {
+            assert_eq!(snap_width(w), want, "w={w}");
+        }
+    }
+
+    #[test]
+    fn cache_names_are_per_recipe_source_and_width() {
+        let name = cache_name(12, "recipes/12/full.webp", 640);
+        assert_eq!(name, "cache/12-full-640.webp");
+        assert_eq!(owner(&name), Some(12));
+        assert_eq!(owner("recipes/12/full.webp"), None);
+    }
+}
@@ -1,5 +1,7 @@
-use image::DynamicImage;
 use image::GenericImageView;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+use std::time::Instant;
 use webp::Encoder as WebpEncoder;
 
 pub const FULL_WEBP_QUALITY: f32 = 90.0;
@@ -10,6 +12,7 @@ pub const THUMB_MAX_DIM: u32 = 1024;
 ///
 /// Returns Err if the image incoding fails
 pub fn to_full_and_thumb_webp(img: &DynamicImage) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+    let started = Instant::now();
     // full
     let full_mem = WebpEncoder::from_image(img)
         .map_err(err_other)?
@@ -30,9 +33,61 @@ pub fn to_full_and_thumb_webp(img: &DynamicImage) -> std::io::Result<(Vec<u8>, V
         .map_err(err_other)?
         .encode(THUMB_WEBP_QUALITY);
 
+    crate::metrics::observe_image("full_and_thumb", started.elapsed());
     Ok((full_mem.to_vec(), thumb_mem.to_vec()))
 }
 
+/// Quality of on-demand renditions (see [`crate::image_cache`]).
+pub const RENDITION_WEBP_QUALITY: f32 = 75.0;
+
+/// Scale `img` down to `width` (never up), keeping its aspect ratio.
+///
+/// # Errors
+///
+/// Returns Err if the image encoding fails
+pub fn to_width_webp(img: &DynamicImage, width: u32) -> std::io::Result<Vec<u8>> {
+    let started = Instant::now();
+    let resized = if img.width() > width {
+        img.resize(width, u32::MAX, image::imageops::FilterType::Triangle)
+    } else {
+        img.clone()
+    };
+    let mem = WebpEncoder::from_image(&resized)
+        .map_err(err_other)?
+        .encode(RENDITION_WEBP_QUALITY);
+    crate::metrics::observe_image("rendition", started.elapsed());
+    Ok(mem.to_vec())
+}
+
+/// Formats we can decode, i.e. the `image` crate features we build with.
+pub const ACCEPTED_FORMATS: [ImageFormat; 3] =
+    [ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::WebP];
+
+/// The image format `bytes` start with, if it is one we accept.
+#[must_use]
+pub fn sniff_format(bytes: &[u8]) -> Option<ImageFormat> {
+    image::guess_format(bytes)
+        .ok()
+        .filter(|f| ACCEPTED_FORMATS.contains(f))
+}
+
+/// Decode `bytes` and apply the EXIF orientation, so portrait phone photos
+/// come out upright.
+///
+/// # Errors
+///
+/// Returns Err if the format is unknown or decoding fails
+pub fn decode_oriented(bytes: &[u8]) -> std::io::Result<DynamicImage> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(err_other)?;
+    let orientation = decoder.orientation().map_err(err_other)?;
+    let mut img = DynamicImage::from_decoder(decoder).map_err(err_other)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
 fn err_other<E: std::fmt::Display>(e: E) -> std::io::Error {
     std::io::Error::other(e.to_string())
 }
@@ -0,0 +1,301 @@
+//! Cleanup of imported instruction steps.
+//!
+//! Extracted instructions often arrive as one paragraph with the numbering
+//! inline ("1. Preheat... 2. Mix..."), with "Step 3:" or bullet prefixes, or
+//! as fragments like "Enjoy!". [`tidy_steps`] turns them into one readable
+//! step per entry. Section headers (`## Sauce`) pass through untouched.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Steps shorter than this are folded into a neighbour.
+pub const MIN_STEP_CHARS: usize = 15;
+/// Steps longer than this are split on sentence boundaries.
+pub const MAX_STEP_CHARS: usize = 600;
+
+/// Words that introduce a step number, in the languages recipes come in.
+const STEP_WORDS: &str = "step|schritt|étape|etape|paso|passo|stap";
+
+/// An inline step number: "Step 2", "Schritt 2:", "2." or "2)". A bare
+/// number needs whitespace after its dot, so "1.5 cups" never matches.
+static MARKER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?i)\b(?:(?:{STEP_WORDS})\s*(\d{{1,2}})\s*[.:)-]?|(\d{{1,2}})[.)])(?:\s+|$)"
+    ))
+    .unwrap()
+});
+
+/// Numbering or a bullet at the start of a step.
+static LEADING_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?i)^\s*(?:[-*•·–—]\s+|(?:{STEP_WORDS})\s*\d{{1,2}}\s*[.:)-]?\s*|\(?\d{{1,2}}\s*[.)](?:\s+|$))"
+    ))
+    .unwrap()
+});
+
+static SENTENCE_END_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[.!?]\s+").unwrap());
+
+fn is_header(step: &str) -> bool {
+    step.starts_with("## ")
+}
+
+/// Split, strip, cap and merge `steps` as described in the module docs.
+#[must_use]
+pub fn tidy_steps(steps: Vec<String>) -> Vec<String> {
+    let split = steps.into_iter().flat_map(|step| {
+        if is_header(&step) {
+            return vec![step];
+        }
+        split_numbered(&step)
+            .into_iter()
+            .map(strip_marker)
+            .filter(|s| !s.is_empty())
+            .flat_map(|s| split_long(s, MAX_STEP_CHARS))
+            .collect()
+    });
+    merge_short(split.collect(), MIN_STEP_CHARS)
+}
+
+/// Cut `step` before each inline step number. A number only counts when it
+/// starts a sentence (text before it is empty or ends in `.!?:;`) and
+/// continues the sequence of the numbers already accepted, so "Repeat step
+/// 2" or "bake 10. Cool" stay in one piece.
+#[must_use]
+pub fn split_numbered(step: &str) -> Vec<&str> {
+    let mut cuts = vec![0];
+    let mut next: Option<u32> = None;
+    for caps in MARKER_RE.captures_iter(step) {
+        let start = caps.get(0).map_or(0, |m| m.start());
+        let before = step[..start].trim_end();
+        if !(before.is_empty() || before.ends_with(['.', '!', '?', ':', ';'])) {
+            continue;
+        }
+        let Some(n) = caps
+            .get(1)
+            .or_else(|| caps.get(2))
+            .and_then(|d| d.as_str().parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if next.is_some_and(|want| want != n) {
+            continue;
+        }
+        next = Some(n + 1);
+        if start > 0 {
+            cuts.push(start);
+        }
+    }
+    cuts.push(step.len());
+    cuts.windows(2)
+        .map(|w| step[w[0]..w[1]].trim())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Drop leading numbering and bullets ("3.", "Step 3:", "- ", "• 2)").
+#[must_use]
+pub fn strip_marker(step: &str) -> &str {
+    let mut rest = step.trim();
+    while let Some(m) = LEADING_RE.find(rest) {
+        if m.end() == 0 {
+            break;
+        }
+        rest = rest[m.end()..].trim_start();
+    }
+    rest
+}
+
+/// Split a step longer than `max` chars into runs of whole sentences of at
+/// most `max` chars each. A single sentence over the limit stays whole.
+#[must_use]
+pub fn split_long(step: &str, max: usize) -> Vec<String> {
+    if step.chars().count() <= max {
+        return vec![step.to_string()];
+    }
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for m in SENTENCE_END_RE.find_iter(step) {
+        sentences.push(&step[start..=m.start()]);
+        start = m.end();
+    }
+    sentences.push(&step[start..]);
+
+    let mut out: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for sentence in sentences
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        if !current.is_empty() && current.chars().count() + 1 + sentence.chars().count() > max {
+            out.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(sentence);
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+/// Append steps shorter than `min` chars to the previous step, or prepend
+/// them to the next one when they open the list or a section.
+#[must_use]
+pub fn merge_short(steps: Vec<String>, min: usize) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut carry: Option<String> = None;
+    for step in steps {
+        if is_header(&step) {
+            out.extend(carry.take());
+            out.push(step);
+            continue;
+        }
+        let step = match carry.take() {
+            Some(short) => format!("{short} {step}"),
+            None => step,
+        };
+        if step.chars().count() >= min {
+            out.push(step);
+            continue;
+        }
+        match out.last_mut() {
+            Some(prev) if !is_header(prev) => {
+                prev.push(' ');
+                prev.push_str(&step);
+            }
+            _ => carry = Some(step),
+        }
+    }
+    out.extend(carry);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tidy(steps: &[&str]) -> Vec<String> {
+        tidy_steps(steps.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn test_tidy_steps_table() {
+        let cases: &[(&[&str], &[&str])] = &[
+            // inline numbering in one element
+            (
+                &["1. Preheat the oven to 180C. 2. Mix flour and sugar. 3. Bake for 20 minutes."],
+                &[
+                    "Preheat the oven to 180C.",
+                    "Mix flour and sugar.",
+                    "Bake for 20 minutes.",
+                ],
+            ),
+            // decimals are not step numbers
+            (
+                &["Add 1.5 cups of flour and 2.5 tbsp of sugar, then stir well."],
+                &["Add 1.5 cups of flour and 2.5 tbsp of sugar, then stir well."],
+            ),
+            (
+                &["1. Whisk 1.5 cups of milk with the eggs. 2. Pour into the pan."],
+                &[
+                    "Whisk 1.5 cups of milk with the eggs.",
+                    "Pour into the pan.",
+                ],
+            ),
+            // "Step N" and the German variant
+            (
+                &["Step 1: Chop the onions finely. Step 2: Fry them until golden."],
+                &["Chop the onions finely.", "Fry them until golden."],
+            ),
+            (
+                &["Schritt 1 Zwiebeln fein hacken. Schritt 2 Zwiebeln goldbraun braten."],
+                &["Zwiebeln fein hacken.", "Zwiebeln goldbraun braten."],
+            ),
+            // numbers inside a sentence or out of sequence stay put
+            (
+                &["Repeat step 2 with the remaining dough and bake."],
+                &["Repeat step 2 with the remaining dough and bake."],
+            ),
+            (
+                &["Bake for 20 minutes. 2 eggs go in the glaze afterwards."],
+                &["Bake for 20 minutes. 2 eggs go in the glaze afterwards."],
+            ),
+            (
+                &["1. Roll out the dough thinly. 5. Cut into squares and fill them."],
+                &["Roll out the dough thinly. 5. Cut into squares and fill them."],
+            ),
+            // leading numbering and bullets
+            (
+                &[
+                    "1) Rinse the lentils well.",
+                    "• Simmer for 20 minutes.",
+                    "- 3. Blend until smooth.",
+                ],
+                &[
+                    "Rinse the lentils well.",
+                    "Simmer for 20 minutes.",
+                    "Blend until smooth.",
+                ],
+            ),
+            // short fragments merge into a neighbour
+            (
+                &["Toast the bread in a pan.", "Enjoy!"],
+                &["Toast the bread in a pan. Enjoy!"],
+            ),
+            (
+                &["Preheat.", "Then bake the bread for an hour."],
+                &["Preheat. Then bake the bread for an hour."],
+            ),
+            // section headers are kept and never merged into
+            (
+                &[
+                    "## Sauce",
+                    "Stir.",
+                    "Simmer the tomatoes slowly.",
+                    "## Pasta",
+                    "Boil the pasta.",
+                ],
+                &[
+                    "## Sauce",
+                    "Stir. Simmer the tomatoes slowly.",
+                    "## Pasta",
+                    "Boil the pasta.",
+                ],
+            ),
+            (&["Mix."], &["Mix."]),
+            (&["2.", "   "], &[]),
+        ];
+        for (input, want) in cases {
+            assert_eq!(tidy(input), *want, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_split_long_on_sentences() {
+        let sentence = "Stir the sauce slowly over low heat until thick.";
+        let step = vec![sentence; 20].join(" ");
+        let parts = split_long(&step, MAX_STEP_CHARS);
+        assert!(parts.len() > 1);
+        assert!(parts.iter().all(|p| p.chars().count() <= MAX_STEP_CHARS));
+        assert!(parts.iter().all(|p| p.ends_with('.')));
+        assert_eq!(parts.join(" "), step);
+
+        let one_sentence = "a".repeat(700);
+        assert_eq!(split_long(&one_sentence, MAX_STEP_CHARS), [one_sentence]);
+    }
+
+    #[test]
+    fn test_split_long_keeps_decimals() {
+        let step = format!("{} Add 1.5 cups of water. Stir.", "x".repeat(20));
+        assert_eq!(
+            split_long(&step, 30),
+            [
+                format!("{} Add 1.5 cups of water.", "x".repeat(20)),
+                "Stir.".into()
+            ]
+        );
+    }
+}
@@ -1,19 +1,283 @@
 use regex::Regex;
 use serde::Serialize;
 use serde_json::{Value as JsonValue, json};
-use std::{sync::LazyLock, time::Duration};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::{path::PathBuf, sync::LazyLock, time::Duration};
+
+use crate::config::Config;
+use crate::error::AppError;
+use axum::http::StatusCode;
 
 #[derive(Debug, Clone)]
 pub struct LlmClient {
     pub base: String,
     pub token: String,
     pub model: String,
+    /// Record/replay of provider responses for offline development.
+    pub cassettes: Option<Cassettes>,
+    pub retry: RetryPolicy,
+    /// Where each call's token usage is logged; untracked when `None`.
+    pub usage_log: Option<UsageLog>,
+}
+
+/// How `LlmClient` retries rate limits (429), server errors (5xx) and
+/// timeouts. Other failures are returned right away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt.
+    pub max_retries: u32,
+    /// First backoff; doubles on every retry, plus up to 50% jitter.
+    pub base_delay: Duration,
+    /// Total time spent waiting between attempts. A wait that would go over
+    /// it (including a long `Retry-After`) ends the retries instead.
+    pub budget: Duration,
+}
+
+impl RetryPolicy {
+    /// For one-off interactive calls such as URL imports.
+    pub const DEFAULT: Self = Self {
+        max_retries: 3,
+        base_delay: Duration::from_millis(500),
+        budget: Duration::from_secs(20),
+    };
+
+    /// For calls made in bulk or where a quick answer matters more.
+    pub const TIGHT: Self = Self {
+        max_retries: 2,
+        base_delay: Duration::from_millis(250),
+        budget: Duration::from_secs(3),
+    };
+
+    pub const NONE: Self = Self {
+        max_retries: 0,
+        base_delay: Duration::ZERO,
+        budget: Duration::ZERO,
+    };
+
+    /// Wait before retry number `retry` (0-based): the server's `Retry-After`
+    /// if given, else exponential backoff with `jitter` in `0.0..=1.0`
+    /// scaling an extra 0–50%.
+    #[must_use]
+    pub fn delay(self, retry: u32, retry_after: Option<Duration>, jitter: f64) -> Duration {
+        retry_after.unwrap_or_else(|| {
+            let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(retry));
+            backoff.mul_f64(jitter.clamp(0.0, 1.0).mul_add(0.5, 1.0))
+        })
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Whether a failed response is worth another attempt.
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// `Retry-After` in its delay-seconds form; HTTP dates fall back to backoff.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// One attempt's failure and whether to try again.
+enum Attempt {
+    Retry(anyhow::Error, Option<Duration>),
+    Fail(anyhow::Error),
+}
+
+/// What to do with LLM cassettes (recorded request/response pairs).
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CassetteMode {
+    /// Talk to the provider; cassettes are not touched.
+    #[default]
+    Off,
+    /// Talk to the provider and save every successful response.
+    Record,
+    /// Never talk to the provider; answer from saved responses only.
+    Replay,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cassettes {
+    pub mode: CassetteMode,
+    pub dir: PathBuf,
+}
+
+/// Timestamps in prompts (e.g. "today is 2025-03-01T10:22:03Z") would make
+/// every recording unique.
+static VOLATILE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:Z|[+-]\d{2}:?\d{2})?")
+        .unwrap()
+});
+
+/// Normalization hook applied to the serialized request before hashing.
+fn normalize_for_cassette(request: &str) -> String {
+    VOLATILE_RE.replace_all(request, "<timestamp>").into_owned()
+}
+
+impl Cassettes {
+    /// File name for a request body: hash of the normalized body (model,
+    /// prompts, parameters; never the API key).
+    fn path_for(&self, body: &JsonValue) -> PathBuf {
+        let normalized = normalize_for_cassette(&body.to_string());
+        let key = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+        self.dir.join(format!("{key}.json"))
+    }
+
+    async fn replay(&self, body: &JsonValue) -> anyhow::Result<String> {
+        let path = self.path_for(body);
+        let raw = tokio::fs::read_to_string(&path).await.map_err(|e| {
+            anyhow::anyhow!(
+                "no LLM cassette for this request ({}): {e}; record it with --llm-cassette-mode record",
+                path.display()
+            )
+        })?;
+        let cassette: JsonValue = serde_json::from_str(&raw)?;
+        let response = cassette
+            .get("response")
+            .ok_or_else(|| anyhow::anyhow!("cassette {} has no response", path.display()))?;
+        Ok(response.to_string())
+    }
+
+    async fn record(&self, body: &JsonValue, response_text: &str) -> anyhow::Result<()> {
+        let path = self.path_for(body);
+        let response: JsonValue = serde_json::from_str(response_text)?;
+        let cassette = json!({ "request": body, "response": response });
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(&path, serde_json::to_vec_pretty(&cassette)?).await?;
+        tracing::debug!("recorded LLM cassette {}", path.display());
+        Ok(())
+    }
+}
+
+/// Token counts of one completion, from the response's `usage` object.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+impl Usage {
+    /// Providers that omit `usage` count as zero tokens.
+    #[must_use]
+    pub fn from_envelope(envelope: &JsonValue) -> Self {
+        let tokens = |key: &str| {
+            envelope
+                .pointer(&format!("/usage/{key}"))
+                .and_then(JsonValue::as_i64)
+                .unwrap_or(0)
+        };
+        Self {
+            prompt_tokens: tokens("prompt_tokens"),
+            completion_tokens: tokens("completion_tokens"),
+        }
+    }
+}
+
+/// Logs a client's calls into the `llm_usage` table.
+#[derive(Debug, Clone)]
+pub struct UsageLog {
+    pool: SqlitePool,
+    /// Feature label, e.g. `recipes.import`.
+    route: &'static str,
+    recipe_id: Option<i64>,
+}
+
+impl UsageLog {
+    /// Accounting must never fail the call it accounts for.
+    async fn record(&self, model: &str, response_text: &str) {
+        let usage = serde_json::from_str(response_text)
+            .map(|envelope| Usage::from_envelope(&envelope))
+            .unwrap_or_default();
+        if let Err(e) = sqlx::query(
+            "INSERT INTO llm_usage (route, model, prompt_tokens, completion_tokens, recipe_id)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(self.route)
+        .bind(model)
+        .bind(usage.prompt_tokens)
+        .bind(usage.completion_tokens)
+        .bind(self.recipe_id)
+        .execute(&self.pool)
+        .await
+        {
+            tracing::warn!("failed to record LLM usage: {e}");
+        }
+    }
+}
+
+/// Why an `LlmClient` could not be built from the server config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmUnavailable {
+    /// The server runs with `--offline`; LLM features are disabled.
+    Offline,
+    /// No API key is configured.
+    MissingApiKey,
+}
+
+impl From<LlmUnavailable> for AppError {
+    fn from(e: LlmUnavailable) -> Self {
+        match e {
+            LlmUnavailable::Offline => Self::Code(
+                StatusCode::NOT_IMPLEMENTED,
+                "feature_disabled",
+                "LLM features are disabled (offline mode)".into(),
+            ),
+            LlmUnavailable::MissingApiKey => Self::Msg(
+                StatusCode::BAD_REQUEST,
+                "LLM API key not configured (use --llm-api-key or BLAZ_LLM_API_KEY)".into(),
+            ),
+        }
+    }
 }
 
 impl LlmClient {
     #[must_use]
     pub const fn new(base: String, token: String, model: String) -> Self {
-        Self { base, token, model }
+        Self {
+            base,
+            token,
+            model,
+            cassettes: None,
+            retry: RetryPolicy::DEFAULT,
+            usage_log: None,
+        }
+    }
+
+    /// Builds a client from the server config. This is the single gate for
+    /// LLM access: in offline mode no client (and thus no request) can exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LlmUnavailable` when offline mode is on or no API key is set.
+    pub fn from_config(config: &Config, model: String) -> Result<Self, LlmUnavailable> {
+        if config.offline {
+            return Err(LlmUnavailable::Offline);
+        }
+        let token = config.llm_api_key.clone().unwrap_or_default();
+        // Replaying needs no provider, so no key either.
+        if token.trim().is_empty() && config.llm_cassette_mode != CassetteMode::Replay {
+            return Err(LlmUnavailable::MissingApiKey);
+        }
+        let mut client = Self::new(config.llm_api_url.clone(), token, model);
+        if config.llm_cassette_mode != CassetteMode::Off {
+            client.cassettes = Some(Cassettes {
+                mode: config.llm_cassette_mode,
+                dir: config.llm_cassette_dir.clone(),
+            });
+        }
+        Ok(client)
     }
 
     /// Creates a new client with a different model (for fallback scenarios)
@@ -23,6 +287,131 @@ impl LlmClient {
             base: self.base.clone(),
             token: self.token.clone(),
             model,
+            cassettes: self.cassettes.clone(),
+            retry: self.retry,
+            usage_log: self.usage_log.clone(),
+        }
+    }
+
+    /// The same client with a different retry policy.
+    #[must_use]
+    pub const fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The same client, logging every call's token usage under `route`.
+    #[must_use]
+    pub fn tracked(mut self, pool: &SqlitePool, route: &'static str) -> Self {
+        self.usage_log = Some(UsageLog {
+            pool: pool.clone(),
+            route,
+            recipe_id: None,
+        });
+        self
+    }
+
+    /// Attributes the logged usage to a recipe (no-op when untracked).
+    #[must_use]
+    pub const fn for_recipe(mut self, recipe_id: i64) -> Self {
+        if let Some(log) = &mut self.usage_log {
+            log.recipe_id = Some(recipe_id);
+        }
+        self
+    }
+
+    /// POST a chat completion body and return the raw response text, going
+    /// through the cassettes when configured.
+    async fn post_chat(
+        &self,
+        http: &reqwest::Client,
+        body: &JsonValue,
+        timeout: Duration,
+    ) -> anyhow::Result<String> {
+        tracing::info!("LLM call with model '{}'", self.model);
+        if let Some(c) = &self.cassettes
+            && c.mode == CassetteMode::Replay
+        {
+            return c.replay(body).await;
+        }
+
+        let route = self.usage_log.as_ref().map_or("untracked", |log| log.route);
+        let mut waited = Duration::ZERO;
+        let mut retry = 0;
+        let text = loop {
+            let (err, after) = match self.attempt(http, body, timeout).await {
+                Ok(text) => break text,
+                Err(Attempt::Fail(e)) => {
+                    crate::metrics::record_llm_call(route, false);
+                    return Err(e);
+                }
+                Err(Attempt::Retry(e, after)) => (e, after),
+            };
+            let delay = self.retry.delay(retry, after, rand::random::<f64>());
+            if retry >= self.retry.max_retries || waited + delay > self.retry.budget {
+                crate::metrics::record_llm_call(route, false);
+                return Err(err);
+            }
+            tracing::warn!("LLM call failed ({err}); retrying in {delay:?}");
+            tokio::time::sleep(delay).await;
+            waited += delay;
+            retry += 1;
+        };
+        crate::metrics::record_llm_call(route, true);
+
+        if let Some(c) = &self.cassettes
+            && c.mode == CassetteMode::Record
+            && let Err(e) = c.record(body, &text).await
+        {
+            tracing::warn!("failed to record LLM cassette: {e}");
+        }
+        if let Some(log) = &self.usage_log {
+            log.record(&self.model, &text).await;
+        }
+        Ok(text)
+    }
+
+    async fn attempt(
+        &self,
+        http: &reqwest::Client,
+        body: &JsonValue,
+        timeout: Duration,
+    ) -> Result<String, Attempt> {
+        let url = format!("{}/chat/completions", self.base.trim_end_matches('/'));
+        let mut req = http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .timeout(timeout)
+            .json(body);
+
+        if !self.token.trim().is_empty() {
+            req = req.bearer_auth(&self.token);
+        }
+
+        let resp = match req.send().await {
+            Ok(resp) => resp,
+            Err(e) if e.is_timeout() || e.is_connect() => {
+                return Err(Attempt::Retry(e.into(), None));
+            }
+            Err(e) => return Err(Attempt::Fail(e.into())),
+        };
+        let status = resp.status();
+        let after = retry_after(resp.headers());
+        let text = match resp.text().await {
+            Ok(text) => text,
+            Err(e) if e.is_timeout() => return Err(Attempt::Retry(e.into(), None)),
+            Err(_) => String::new(),
+        };
+
+        if status.is_success() {
+            Ok(text)
+        } else if is_retryable_status(status) {
+            Err(Attempt::Retry(
+                anyhow::anyhow!("LLM HTTP {status}: {text}"),
+                after,
+            ))
+        } else {
+            Err(Attempt::Fail(anyhow::anyhow!("LLM HTTP {status}: {text}")))
         }
     }
 
@@ -112,8 +501,6 @@ impl LlmClient {
             response_format: JsonValue,
         }
 
-        let url = format!("{}/chat/completions", self.base.trim_end_matches('/'));
-
         let body = Body {
             model: &self.model,
             messages: vec![
@@ -131,23 +518,9 @@ impl LlmClient {
             response_format: json!({ "type": "json_object" }),
         };
 
-        let mut req = http
-            .post(url)
-            .header(reqwest::header::CONTENT_TYPE, "application/json")
-            .timeout(timeout)
-            .json(&body);
-
-        if !self.token.trim().is_empty() {
-            req = req.bearer_auth(&self.token);
-        }
-
-        let resp = req.send().await?;
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-
-        if !status.is_success() {
-            anyhow::bail!("LLM HTTP {status}: {text}");
-        }
+        let text = self
+            .post_chat(http, &serde_json::to_value(&body)?, timeout)
+            .await?;
 
         let envelope: JsonValue = serde_json::from_str(&text)?;
 
@@ -214,8 +587,6 @@ impl LlmClient {
     ///
     /// Will return err if the request fails or if the response can't be parsed as JSON.
     pub async fn chat_json_images(&self, req: ImageChatRequest<'_>) -> anyhow::Result<JsonValue> {
-        let url = format!("{}/chat/completions", self.base.trim_end_matches('/'));
-
         let mut content: Vec<JsonValue> = req
             .images
             .iter()
@@ -239,24 +610,7 @@ impl LlmClient {
             "response_format": { "type": "json_object" }
         });
 
-        let mut http_req = req
-            .http
-            .post(url)
-            .header(reqwest::header::CONTENT_TYPE, "application/json")
-            .timeout(req.timeout)
-            .json(&body);
-
-        if !self.token.trim().is_empty() {
-            http_req = http_req.bearer_auth(&self.token);
-        }
-
-        let resp = http_req.send().await?;
-        let status = resp.status();
-        let text = resp.text().await.unwrap_or_default();
-
-        if !status.is_success() {
-            anyhow::bail!("LLM HTTP {status}: {text}");
-        }
+        let text = self.post_chat(req.http, &body, req.timeout).await?;
 
         let envelope: JsonValue = serde_json::from_str(&text)?;
         let content_str = envelope
@@ -388,25 +742,23 @@ pub fn extract_largest_json_object(s: &str) -> Option<String> {
                 }
                 depth += 1;
             }
-            '}' => {
-                if depth > 0 {
-                    depth -= 1;
-                    if depth == 0 {
-                        if let Some(st) = start {
-                            let cand = (st, i);
-                            let cand_len = cand.1.saturating_sub(cand.0);
-
-                            let better = match best {
-                                None => true,
-                                Some((a, b)) => cand_len > b.saturating_sub(a),
-                            };
-
-                            if better {
-                                best = Some(cand);
-                            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(st) = start {
+                        let cand = (st, i);
+                        let cand_len = cand.1.saturating_sub(cand.0);
+
+                        let better = match best {
+                            None => true,
+                            Some((a, b)) => cand_len > b.saturating_sub(a),
+                        };
+
+                        if better {
+                            best = Some(cand);
                         }
-                        start = None;
                     }
+                    start = None;
                 }
             }
             _ => {}
@@ -419,6 +771,117 @@ pub fn extract_largest_json_object(s: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // ── retries ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn retry_delay_doubles_with_jitter_and_honors_retry_after() {
+        let p = RetryPolicy::DEFAULT;
+        assert_eq!(p.delay(0, None, 0.0), Duration::from_millis(500));
+        assert_eq!(p.delay(1, None, 0.0), Duration::from_secs(1));
+        assert_eq!(p.delay(2, None, 1.0), Duration::from_secs(3));
+        assert_eq!(
+            p.delay(2, Some(Duration::from_secs(7)), 1.0),
+            Duration::from_secs(7)
+        );
+    }
+
+    /// A provider answering `statuses` in turn (then 200), counting requests.
+    async fn flaky_provider(statuses: &'static [u16]) -> (String, Arc<AtomicUsize>) {
+        use axum::response::IntoResponse;
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/chat/completions",
+            axum::routing::post(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    match statuses.get(n) {
+                        Some(&code) => (
+                            StatusCode::from_u16(code).unwrap(),
+                            [(axum::http::header::RETRY_AFTER, "0")],
+                            format!("provider says {code}"),
+                        )
+                            .into_response(),
+                        None => axum::Json(json!({
+                            "choices": [{"message": {"content": "{\"ok\": true}"}}]
+                        }))
+                        .into_response(),
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), hits)
+    }
+
+    async fn call(base: String) -> anyhow::Result<JsonValue> {
+        LlmClient::new(base, "key".into(), "model".into())
+            .chat_json(
+                &reqwest::Client::new(),
+                "sys",
+                "user",
+                0.0,
+                Duration::from_secs(5),
+                None,
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn rate_limits_are_retried_until_the_provider_answers() {
+        let (base, hits) = flaky_provider(&[429, 429]).await;
+        assert_eq!(call(base).await.unwrap(), json!({"ok": true}));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_after_max_retries() {
+        let (base, hits) = flaky_provider(&[502, 503, 500, 502, 200]).await;
+        let err = call(base).await.unwrap_err().to_string();
+        assert!(
+            err.contains("502") && err.contains("provider says 502"),
+            "{err}"
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn client_errors_fail_fast_with_the_provider_message() {
+        let (base, hits) = flaky_provider(&[401]).await;
+        let err = call(base).await.unwrap_err().to_string();
+        assert!(
+            err.contains("401") && err.contains("provider says 401"),
+            "{err}"
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    // ── cassettes ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn cassette_key_ignores_timestamps() {
+        let c = Cassettes {
+            mode: CassetteMode::Replay,
+            dir: PathBuf::from("cassettes"),
+        };
+        let body =
+            |user: &str| json!({"model": "m", "messages": [{"role": "user", "content": user}]});
+        assert_eq!(
+            c.path_for(&body("now: 2025-03-01T10:22:03Z, plan 2025-03-03")),
+            c.path_for(&body("now: 2026-11-30 08:00:00, plan 2025-03-03"))
+        );
+        // Plain dates are part of the question, not noise.
+        assert_ne!(
+            c.path_for(&body("plan 2025-03-03")),
+            c.path_for(&body("plan 2025-03-04"))
+        );
+    }
 
     // ── extract_fenced_json ──────────────────────────────────────────────────
 
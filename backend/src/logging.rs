@@ -1,15 +1,26 @@
 use crate::config::Config;
 
 use axum::body::{Body, Bytes};
+use axum::extract::State;
 use axum::http::{HeaderMap, Request, Response, Uri, header};
 use axum::middleware::Next;
 
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use std::time::Instant;
+
+use tracing::Subscriber;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{
-    EnvFilter, filter::Directive, fmt, layer::SubscriberExt, util::SubscriberInitExt,
+    EnvFilter, Layer,
+    filter::Directive,
+    fmt::{self, MakeWriter, time::ChronoLocal},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
 };
 
 /// Keep guards alive for the lifetime of the app.
@@ -50,74 +61,184 @@ fn build_filter(config: &Config) -> EnvFilter {
     filter
 }
 
+/// Shape of log lines, on stdout and in the log file alike.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Compact human-readable lines.
+    Text,
+    /// One JSON object per line, event fields as top-level keys.
+    Json,
+}
+
+fn timer() -> ChronoLocal {
+    ChronoLocal::new("%Y-%m-%d %H:%M:%S".to_string())
+}
+
+/// A formatting layer writing `format` lines to `writer`. `ansi` only
+/// applies to text.
+fn fmt_layer<S, W>(format: LogFormat, ansi: bool, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => fmt::layer()
+            .with_target(false)
+            .with_ansi(ansi)
+            .compact()
+            .with_timer(timer())
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .with_target(false)
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .with_timer(ChronoLocal::rfc_3339())
+            .with_writer(writer)
+            .boxed(),
+    }
+}
+
 #[must_use]
 pub fn init_logging(config: &Config) -> LogGuards {
     let filter = build_filter(config);
 
-    // Stdout layer (pretty enough, ANSI enabled)
-    let stdout_layer = fmt::layer()
-        .with_target(false)
-        .with_ansi(true)
-        .compact()
-        .with_timer(tracing_subscriber::fmt::time::ChronoLocal::new(
-            "%Y-%m-%d %H:%M:%S".to_string(),
-        ));
+    // Stdout layer (ANSI enabled for text)
+    let stdout_layer = fmt_layer(config.log_format, true, std::io::stdout);
 
     // File layer (ANSI disabled)
-    let (file_layer, guard) = {
-        let path: &Path = config.log_file.as_ref();
+    let (dir, file) = split_path(&config.log_file);
+    let appender = tracing_appender::rolling::never(dir, file);
+    let (nb, guard) = tracing_appender::non_blocking(appender);
+    let file_layer = fmt_layer(config.log_format, false, nb);
 
-        let (dir, file) = split_path(path);
-        let appender = tracing_appender::rolling::never(dir, file);
-        let (nb, guard) = tracing_appender::non_blocking(appender);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stdout_layer)
+        .with(file_layer)
+        .init();
 
-        let layer = fmt::layer()
-            .with_target(false)
-            .with_ansi(false)
-            .compact()
-            .with_timer(tracing_subscriber::fmt::time::ChronoLocal::new(
-                "%Y-%m-%d %H:%M:%S".to_string(),
-            ))
-            .with_writer(nb);
+    LogGuards {
+        _file_guard: Some(guard),
+    }
+}
 
-        (Some(layer), Some(guard))
-    };
+/// Level an access-log line can be emitted at.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessLogLevel {
+    Info,
+    Warn,
+    Error,
+}
 
-    let subscriber = tracing_subscriber::registry()
-        .with(filter)
-        .with(stdout_layer);
+/// Access-log behaviour, built once from `Config` and handed to the
+/// middleware as its state.
+#[derive(Clone, Debug)]
+pub struct AccessLogOptions {
+    /// Successful requests under these prefixes are not logged.
+    pub exclude: Vec<String>,
+    /// Successful requests under these prefixes are logged 1 in `sample_rate`.
+    pub sample: Vec<String>,
+    pub sample_rate: u64,
+    pub not_found_level: AccessLogLevel,
+    sampled: Arc<AtomicU64>,
+}
 
-    if let Some(file_layer) = file_layer {
-        subscriber.with(file_layer).init();
-    } else {
-        subscriber.init();
+impl AccessLogOptions {
+    #[must_use]
+    pub fn new(
+        exclude: Vec<String>,
+        sample: Vec<String>,
+        sample_rate: u64,
+        not_found_level: AccessLogLevel,
+    ) -> Self {
+        Self {
+            exclude,
+            sample,
+            sample_rate: sample_rate.max(1),
+            not_found_level,
+            sampled: Arc::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.access_log_exclude.clone(),
+            config.access_log_sample.clone(),
+            config.access_log_sample_rate,
+            config.access_log_not_found_level,
+        )
     }
 
-    LogGuards { _file_guard: guard }
+    /// Level for a response, or `None` when the line should be dropped.
+    /// Failures are always logged, whatever the path.
+    fn level_for(&self, path: &str, status: u16) -> Option<AccessLogLevel> {
+        match status {
+            404 => Some(self.not_found_level),
+            400..=599 => Some(AccessLogLevel::Error),
+            _ if matches_prefix(&self.exclude, path) => None,
+            _ if matches_prefix(&self.sample, path) => {
+                let n = self.sampled.fetch_add(1, Ordering::Relaxed);
+                n.is_multiple_of(self.sample_rate)
+                    .then_some(AccessLogLevel::Info)
+            }
+            _ => Some(AccessLogLevel::Info),
+        }
+    }
+}
+
+fn matches_prefix(prefixes: &[String], path: &str) -> bool {
+    prefixes
+        .iter()
+        .any(|p| !p.is_empty() && path.starts_with(p.as_str()))
 }
 
-/// One-line access log.
-/// 2xx/3xx -> INFO
+/// One-line access log with `method`, `path` (including the query string),
+/// `status`, `latency_ms` and `request_id` as fields.
+/// 2xx/3xx -> INFO (unless excluded or sampled out)
+/// 404     -> configurable, WARN by default
 /// 4xx/5xx -> ERROR
-///
-/// Includes query string.
-pub async fn access_log(request: Request<Body>, next: Next) -> Response<Body> {
+pub async fn access_log(
+    State(options): State<AccessLogOptions>,
+    request: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let started = Instant::now();
     let method = request.method().clone();
 
     let uri = request.uri().clone();
-    let path = uri
-        .path_and_query()
-        .map_or_else(|| uri.path().to_string(), |pq| pq.as_str().to_string());
+    let path = get_path(&uri);
 
     let res = next.run(request).await;
     let status = res.status().as_u16();
 
-    let msg = format!("{method:<6} {path:<40} {status}");
+    let Some(level) = options.level_for(uri.path(), status) else {
+        return res;
+    };
+
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    // Set by the request-id layer inside this one, echoed on the response.
+    let request_id = get_request_id(res.headers());
 
-    if (400..=599).contains(&status) {
-        tracing::error!("{msg}");
-    } else {
-        tracing::info!("{msg}");
+    macro_rules! emit {
+        ($level:ident) => {
+            tracing::$level!(
+                method = %method,
+                path = %path,
+                status,
+                latency_ms,
+                request_id = %request_id,
+                "request"
+            )
+        };
+    }
+    match level {
+        AccessLogLevel::Info => emit!(info),
+        AccessLogLevel::Warn => emit!(warn),
+        AccessLogLevel::Error => emit!(error),
     }
 
     res
@@ -131,11 +252,14 @@ const BODY_READ_LIMIT: usize = 64 * 1024;
 const BODY_PREVIEW_LIMIT: usize = 16 * 1024;
 
 /// Logs request & response bodies (dev-friendly).
-/// Skips multipart requests and likely-binary responses, truncates previews.
-/// Includes request-id for correlation.
+/// Skips multipart requests, likely-binary responses and metrics scrapes,
+/// truncates previews. Includes request-id for correlation.
 ///
 /// These logs are DEBUG so default verbosity stays clean.
 pub async fn log_payloads(request: Request<Body>, next: Next) -> Response<Body> {
+    if request.uri().path() == "/metrics" {
+        return next.run(request).await;
+    }
     let request_id = get_request_id(request.headers());
     let path = get_path(request.uri());
 
@@ -286,3 +410,141 @@ fn make_preview(bytes: &Bytes) -> String {
         String::from_utf8_lossy(bytes).to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, http::StatusCode, middleware::from_fn_with_state, routing::get};
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+
+    /// Collects formatted log lines so tests can assert on them.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Captured {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8_lossy(&self.0.lock().unwrap())
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    fn app(options: AccessLogOptions) -> Router {
+        Router::new()
+            .route("/healthz", get(|| async { "ok" }))
+            .route(
+                "/metrics",
+                get(|| async { StatusCode::INTERNAL_SERVER_ERROR }),
+            )
+            .route("/poll", get(|| async { "ok" }))
+            .route("/recipes", get(|| async { "ok" }))
+            .route("/bad", get(|| async { StatusCode::BAD_REQUEST }))
+            .layer(from_fn_with_state(options, access_log))
+    }
+
+    /// Send `paths` through the access log and return what it wrote.
+    async fn run(options: AccessLogOptions, paths: &[&str]) -> Vec<String> {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .without_time()
+            .with_target(false)
+            .with_max_level(tracing::Level::TRACE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = app(options);
+        for path in paths {
+            let req = Request::get(*path).body(Body::empty()).unwrap();
+            app.clone().oneshot(req).await.unwrap();
+        }
+        captured.lines()
+    }
+
+    fn defaults() -> AccessLogOptions {
+        AccessLogOptions::new(
+            vec!["/healthz".into(), "/metrics".into()],
+            Vec::new(),
+            100,
+            AccessLogLevel::Warn,
+        )
+    }
+
+    #[tokio::test]
+    async fn excluded_paths_only_log_failures() {
+        let lines = run(defaults(), &["/healthz", "/metrics", "/recipes"]).await;
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert!(lines[0].contains("ERROR") && lines[0].contains("/metrics"));
+        assert!(lines[1].contains("INFO") && lines[1].contains("/recipes"));
+    }
+
+    #[tokio::test]
+    async fn not_found_level_is_configurable() {
+        let lines = run(defaults(), &["/missing", "/bad"]).await;
+        assert!(lines[0].contains("WARN") && lines[0].contains("404"));
+        assert!(lines[1].contains("ERROR") && lines[1].contains("400"));
+
+        let mut options = defaults();
+        options.not_found_level = AccessLogLevel::Error;
+        let lines = run(options, &["/missing"]).await;
+        assert!(lines[0].contains("ERROR") && lines[0].contains("404"));
+    }
+
+    #[tokio::test]
+    async fn json_format_puts_access_fields_at_top_level() {
+        use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber =
+            tracing_subscriber::registry()
+                .with(fmt_layer(LogFormat::Json, false, move || writer.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route("/recipes", get(|| async { "ok" }))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(from_fn_with_state(defaults(), access_log));
+        let req = Request::get("/recipes?q=soup").body(Body::empty()).unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        let request_id = res.headers()["x-request-id"].to_str().unwrap();
+
+        let lines = captured.lines();
+        assert_eq!(lines.len(), 1, "{lines:?}");
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["message"], "request");
+        assert_eq!(line["method"], "GET");
+        assert_eq!(line["path"], "/recipes?q=soup");
+        assert_eq!(line["status"], 200);
+        assert!(line["latency_ms"].is_u64());
+        assert_eq!(line["request_id"], request_id);
+        assert!(line["timestamp"].is_string());
+    }
+
+    #[tokio::test]
+    async fn sampled_paths_log_one_in_n() {
+        let options =
+            AccessLogOptions::new(Vec::new(), vec!["/poll".into()], 3, AccessLogLevel::Warn);
+        let paths = ["/poll"; 7];
+        let lines = run(options, &paths).await;
+        assert_eq!(lines.len(), 3, "{lines:?}");
+        assert!(lines.iter().all(|l| l.contains("INFO")));
+    }
+}
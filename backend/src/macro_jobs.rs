@@ -0,0 +1,85 @@
+//! Progress of the "estimate macros for every recipe" background job.
+//!
+//! One job runs at a time. The handle lives in `AppState` so the status
+//! endpoint can report on a job started by an earlier request.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct JobFailure {
+    pub recipe_id: i64,
+    pub title: String,
+    pub error: String,
+}
+
+/// Progress of the latest job; all zeros before the first one.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct JobStatus {
+    pub running: bool,
+    /// Recipes without macros when the job started.
+    pub total: usize,
+    /// Recipes estimated so far.
+    pub done: usize,
+    /// Recipes whose estimate failed; the job carries on past them.
+    pub failed: usize,
+    /// Title of the recipe most recently sent to the LLM.
+    pub current: Option<String>,
+    pub failures: Vec<JobFailure>,
+}
+
+#[derive(Clone, Default)]
+pub struct MacroJobs {
+    status: Arc<Mutex<JobStatus>>,
+}
+
+impl MacroJobs {
+    fn with<R>(&self, f: impl FnOnce(&mut JobStatus) -> R) -> R {
+        f(&mut self.status.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    #[must_use]
+    pub fn status(&self) -> JobStatus {
+        self.with(|s| s.clone())
+    }
+
+    /// Reset the status for a job over `total` recipes. `false` if one is
+    /// already running.
+    #[must_use]
+    pub fn try_start(&self, total: usize) -> bool {
+        self.with(|s| {
+            if s.running {
+                return false;
+            }
+            *s = JobStatus {
+                running: total > 0,
+                total,
+                ..JobStatus::default()
+            };
+            true
+        })
+    }
+
+    pub fn begin(&self, title: &str) {
+        self.with(|s| s.current = Some(title.to_string()));
+    }
+
+    pub fn succeeded(&self) {
+        self.with(|s| s.done += 1);
+    }
+
+    pub fn failed(&self, failure: JobFailure) {
+        self.with(|s| {
+            s.failed += 1;
+            s.failures.push(failure);
+        });
+    }
+
+    pub fn finish(&self) {
+        self.with(|s| {
+            s.running = false;
+            s.current = None;
+        });
+    }
+}
@@ -7,24 +7,42 @@
 )]
 #![allow(clippy::multiple_crate_versions)]
 
+mod activity;
 mod app;
 mod auth_middleware;
 mod categories;
 mod config;
 mod db;
+mod db_backup;
+mod db_check;
+mod digest;
 mod embedded_web;
+mod equipment;
 mod error;
+mod extract;
 mod html;
+mod image_cache;
 mod image_io;
+mod instructions;
 mod llm;
 mod logging;
+mod macro_jobs;
+mod media_gc;
+mod media_health;
+mod media_path;
+mod media_txn;
+mod metrics;
 mod models;
 mod ntfy;
 mod routes;
+mod safe_fetch;
 mod schema_org;
+mod share_card;
+mod shopping_events;
 #[cfg(test)]
 mod tests;
 mod units;
+mod youtube;
 
 use clap::Parser;
 use tokio::net::TcpListener;
@@ -43,7 +61,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Handle subcommands
     if let Some(command) = cli.command {
-        return handle_command(command);
+        return handle_command(command, &cli.config).await;
     }
 
     let mut config = cli.config;
@@ -53,36 +71,169 @@ async fn main() -> anyhow::Result<()> {
 
     crate::ntfy::init(config.ntfy_url.clone());
 
-    // Generate JWT secret if not provided
+    log_config(&config);
+
+    let pool = make_pool(config.database_path.clone()).await?;
+
+    // Without BLAZ_JWT_SECRET, sign with one kept next to the database.
     if config.jwt_secret.is_none() {
-        use rand::Rng;
-        let secret: String = rand::thread_rng()
-            .sample_iter(&rand::distributions::Alphanumeric)
-            .take(32)
-            .map(char::from)
-            .collect();
-        tracing::warn!(
-            "No JWT secret provided, generated random secret (tokens will be invalid on restart)"
-        );
-        config.jwt_secret = Some(secret);
+        config.jwt_secret = Some(routes::auth::stored_jwt_secret(&config.jwt_secret_path())?);
+    }
+
+    let media = media_health::MediaHealth::default();
+    let media_grace = std::time::Duration::from_secs(config.media_wait_secs);
+    if media_health::wait_for_media(&config.media_dir, media_grace).await {
+        // Only meaningful when the files are actually reachable.
+        media_txn::recover(&pool, &config.media_dir, media_txn::STALE_AFTER).await;
+        if config.media_gc_on_startup {
+            match media_gc::collect(&pool, &config.media_dir, media_gc::GRACE).await {
+                Ok(r) => tracing::info!(
+                    "Media GC deleted {} orphaned file(s), {} bytes",
+                    r.deleted,
+                    r.bytes_freed
+                ),
+                Err(e) => tracing::warn!("Media GC failed: {e}"),
+            }
+        }
+    } else {
+        tracing::warn!("Starting in degraded mode: image endpoints return 503 until media is back");
+        media.set_available(false);
+    }
+    media_health::spawn_reprobe(media.clone(), config.media_dir.clone());
+
+    match db::backfill_recipe_servings(&pool).await {
+        Ok(filled) if filled > 0 => tracing::info!("Backfilled servings for {filled} recipe(s)"),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Backfilling servings failed: {e}"),
+    }
+    match db_check::rewrite_legacy_ingredients(&pool).await {
+        Ok(r) if !r.ingredients_rewritten.is_empty() => tracing::info!(
+            "Rewrote plain-text ingredients of {} recipe(s)",
+            r.ingredients_rewritten.len()
+        ),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Rewriting plain-text ingredients failed: {e}"),
+    }
+
+    let jwt_secret = config.jwt_secret.as_ref().unwrap();
+    let state = AppState {
+        pool,
+        jwt_encoding: jsonwebtoken::EncodingKey::from_secret(jwt_secret.as_bytes()),
+        jwt_decoding: jsonwebtoken::DecodingKey::from_secret(jwt_secret.as_bytes()),
+        config: config.clone(),
+        media,
+        http: reqwest::Client::new(),
+        macro_jobs: macro_jobs::MacroJobs::default(),
+        shopping_events: shopping_events::ShoppingEvents::default(),
+        ingredient_suggest: routes::ingredient_suggest::SuggestCache::default(),
+        categories: categories::CategoryCache::default(),
+    };
+
+    // Ctrl-C/SIGTERM flips this; the server and background tasks watch it.
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let digest_task = config.digest_weekday.map(|weekday| {
+        digest::spawn_scheduler(
+            state.clone(),
+            weekday,
+            config.digest_hour,
+            digest::CHECK_INTERVAL,
+            chrono::Local::now,
+            shutdown_rx.clone(),
+        )
+    });
+
+    let app = build_app(state);
+
+    let listener = TcpListener::bind(config.bind).await?;
+    let mut server_rx = shutdown_rx;
+    let server = axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            server_rx.wait_for(|stop| *stop).await.ok();
+        })
+        .into_future();
+    let signalled = async {
+        shutdown_signal().await;
+        tracing::info!("Shutting down");
+        shutdown_tx.send(true).ok();
+    };
+
+    tokio::pin!(server);
+    let outcome = tokio::select! {
+        done = &mut server => done,
+        () = signalled => {
+            // Event streams never end on their own; don't wait on them forever.
+            tokio::time::timeout(SHUTDOWN_GRACE, server)
+                .await
+                .unwrap_or_else(|_| {
+                    tracing::warn!("Connections still open after {SHUTDOWN_GRACE:?}; closing");
+                    Ok(())
+                })
+        }
+    };
+
+    // Also reached when the server fails without a signal.
+    shutdown_tx.send(true).ok();
+    if let Some(task) = digest_task {
+        task.await.ok();
+    }
+    outcome?;
+    Ok(())
+}
+
+/// How long open connections may take to finish after a shutdown signal.
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix (what `docker stop` and systemd send).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Ctrl-C handler unavailable: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("SIGTERM handler unavailable: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
     }
+}
 
-    // Log all configuration (mask sensitive values)
+/// Log all configuration (mask sensitive values)
+fn log_config(config: &config::Config) {
     tracing::info!("=== Configuration ===");
     tracing::info!("Bind address: {}", config.bind);
     tracing::info!("Media directory: {}", config.media_dir.display());
+    tracing::info!("Media wait: {}s", config.media_wait_secs);
     tracing::info!("Database path: {}", config.database_path);
     tracing::info!("Log file: {}", config.log_file.display());
     tracing::info!(
-        "CORS origin: {}",
-        config.cors_origin.as_deref().unwrap_or("<allow all>")
+        "CORS origins: {}",
+        if config.cors_allows_any() {
+            "<allow all>".to_string()
+        } else {
+            config.cors_origins.join(", ")
+        }
     );
     tracing::info!(
         "JWT secret: {}",
         if config.jwt_secret.is_some() {
             "<set>"
         } else {
-            "<not set>"
+            "<generated, kept next to the database>"
         }
     );
     tracing::info!(
@@ -94,6 +245,25 @@ async fn main() -> anyhow::Result<()> {
         }
     );
     tracing::info!("LLM API URL: {}", config.llm_api_url);
+    if let Some(model) = &config.llm_model {
+        tracing::info!("LLM model: {model} (overrides the llm_model setting)");
+    }
+    if config.llm_cassette_mode != crate::llm::CassetteMode::Off {
+        tracing::info!(
+            "LLM cassettes: {:?} in {}",
+            config.llm_cassette_mode,
+            config.llm_cassette_dir.display()
+        );
+    }
+    if config.offline {
+        tracing::info!("Offline mode: LLM features are disabled");
+    }
+    if let Some(day) = config.digest_weekday {
+        tracing::info!("Weekly digest: {day} at {:02}:00", config.digest_hour);
+    }
+    if config.disable_compression {
+        tracing::info!("Response compression: disabled");
+    }
     tracing::info!("LLM models: configured via /settings API (database)");
     tracing::info!(
         "System prompt import: {} chars",
@@ -116,105 +286,19 @@ async fn main() -> anyhow::Result<()> {
         config.ntfy_url.as_deref().unwrap_or("<not set>")
     );
     tracing::info!("====================");
-
-    let pool = make_pool(config.database_path.clone()).await?;
-    tokio::fs::create_dir_all(&config.media_dir).await.ok();
-
-    cleanup_broken_image_paths(&pool, &config.media_dir).await;
-
-    let jwt_secret = config.jwt_secret.as_ref().unwrap();
-    let state = AppState {
-        pool,
-        jwt_encoding: jsonwebtoken::EncodingKey::from_secret(jwt_secret.as_bytes()),
-        config: config.clone(),
-    };
-
-    let app = build_app(state);
-
-    let listener = TcpListener::bind(config.bind).await?;
-    axum::serve(listener, app).await?;
-    Ok(())
 }
 
-fn handle_command(command: Commands) -> anyhow::Result<()> {
+async fn handle_command(command: Commands, config: &config::Config) -> anyhow::Result<()> {
     match command {
         Commands::HashPassword => hash_password_interactive(),
-    }
-}
-
-/// On startup, null-out `image_path_small` / `image_path_full` for recipes
-/// whose image files no longer exist on disk, so clients never request them.
-async fn cleanup_broken_image_paths(pool: &sqlx::SqlitePool, media_dir: &std::path::Path) {
-    #[derive(sqlx::FromRow)]
-    struct Row {
-        id: i64,
-        image_path_small: Option<String>,
-        image_path_full: Option<String>,
-    }
-
-    let Ok(rows) = sqlx::query_as::<_, Row>(
-        "SELECT id, image_path_small, image_path_full FROM recipes \
-         WHERE image_path_small IS NOT NULL OR image_path_full IS NOT NULL",
-    )
-    .fetch_all(pool)
-    .await
-    else {
-        return;
-    };
-
-    let mut cleared = 0u32;
-    for row in rows {
-        let small_missing = row
-            .image_path_small
-            .as_deref()
-            .is_some_and(|p| !media_dir.join(p).exists());
-        let full_missing = row
-            .image_path_full
-            .as_deref()
-            .is_some_and(|p| !media_dir.join(p).exists());
-
-        if small_missing || full_missing {
-            let _ = sqlx::query(
-                "UPDATE recipes SET \
-                 image_path_small = CASE WHEN ? THEN NULL ELSE image_path_small END, \
-                 image_path_full  = CASE WHEN ? THEN NULL ELSE image_path_full  END \
-                 WHERE id = ?",
-            )
-            .bind(small_missing)
-            .bind(full_missing)
-            .bind(row.id)
-            .execute(pool)
-            .await;
-            cleared += 1;
-        }
-    }
-
-    if cleared > 0 {
-        tracing::info!("Cleared broken image paths from {cleared} recipe(s)");
+        Commands::ResetPassword { password } => reset_password(config, password).await,
     }
 }
 
 fn hash_password_interactive() -> anyhow::Result<()> {
-    use argon2::Argon2;
-    use password_hash::{PasswordHasher, SaltString};
-    use rand::rngs::OsRng;
-
     println!("Enter password to hash:");
     let password = rpassword::read_password()?;
-
-    if password.trim().is_empty() {
-        anyhow::bail!("Password cannot be empty");
-    }
-
-    if password.len() < 8 {
-        anyhow::bail!("Password must be at least 8 characters");
-    }
-
-    let salt = SaltString::generate(&mut OsRng);
-    let hash = Argon2::default()
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|e| anyhow::anyhow!("Failed to hash password: {e}"))?
-        .to_string();
+    let hash = routes::auth::hash_new_password(&password)?;
 
     println!("\nArgon2 password hash:");
     println!("{hash}");
@@ -223,3 +307,25 @@ fn hash_password_interactive() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Write a new password hash straight into the database. It takes precedence
+/// over `BLAZ_PASSWORD_HASH`.
+async fn reset_password(config: &config::Config, password: Option<String>) -> anyhow::Result<()> {
+    let password = if let Some(p) = password {
+        p
+    } else {
+        println!("Enter new password:");
+        rpassword::read_password()?
+    };
+    let hash = routes::auth::hash_new_password(&password)?;
+
+    let pool = make_pool(config.database_path.clone()).await?;
+    routes::auth::set_password_hash(&pool, &hash).await?;
+    pool.close().await;
+
+    println!(
+        "Password updated in {}; existing sessions are logged out.",
+        config.database_path
+    );
+    Ok(())
+}
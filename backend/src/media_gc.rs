@@ -0,0 +1,172 @@
+//! Removal of recipe images no row points at any more.
+//!
+//! Only `.webp` files are considered (share cards and staged temp files have
+//! their own cleanup), and recently written files are left alone so a
+//! collection never races an upload whose row isn't committed yet.
+
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::image_cache;
+
+/// Files younger than this may belong to an upload still in flight.
+pub const GRACE: Duration = Duration::from_hours(1);
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub deleted: u64,
+    pub kept: u64,
+    pub bytes_freed: u64,
+}
+
+/// Delete `.webp` files under `media_dir` that no recipe (including those in
+/// the trash) references and that were last modified at least `grace` ago.
+///
+/// # Errors
+/// Err if the referenced paths cannot be loaded or `media_dir` cannot be read.
+pub async fn collect(
+    pool: &SqlitePool,
+    media_dir: &Path,
+    grace: Duration,
+) -> anyhow::Result<GcReport> {
+    let (referenced, with_image) = referenced_paths(pool, media_dir).await?;
+    let now = SystemTime::now();
+    let mut report = GcReport::default();
+
+    for (path, meta) in webp_files(media_dir).await? {
+        // Renditions stay as long as their recipe still has an image.
+        let rendition_of = path
+            .strip_prefix(media_dir)
+            .ok()
+            .and_then(|rel| image_cache::owner(&rel.to_string_lossy()));
+        if referenced.contains(&path) || rendition_of.is_some_and(|id| with_image.contains(&id)) {
+            report.kept += 1;
+            continue;
+        }
+        let age = meta
+            .modified()
+            .map(|m| now.duration_since(m).unwrap_or_default())?;
+        if age < grace {
+            report.kept += 1;
+            continue;
+        }
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {
+                report.deleted += 1;
+                report.bytes_freed += meta.len();
+            }
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "failed to delete orphaned media");
+                report.kept += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Absolute paths of every image a recipe row points at, and the ids of
+/// recipes with a full-size image.
+async fn referenced_paths(
+    pool: &SqlitePool,
+    media_dir: &Path,
+) -> sqlx::Result<(HashSet<PathBuf>, HashSet<i64>)> {
+    let rows: Vec<(i64, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT id, image_path_small, image_path_full FROM recipes")
+            .fetch_all(pool)
+            .await?;
+    let mut paths = HashSet::new();
+    let mut with_image = HashSet::new();
+    for (id, small, full) in rows {
+        if full.is_some() {
+            with_image.insert(id);
+        }
+        paths.extend(small.into_iter().chain(full).map(|rel| media_dir.join(rel)));
+    }
+    Ok((paths, with_image))
+}
+
+/// Walk `media_dir` without following symlinks and list its `.webp` files.
+async fn webp_files(media_dir: &Path) -> io::Result<Vec<(PathBuf, std::fs::Metadata)>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![media_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let kind = entry.file_type().await?;
+            let path = entry.path();
+            if kind.is_dir() {
+                dirs.push(path);
+            } else if kind.is_file() && path.extension().is_some_and(|e| e == "webp") {
+                files.push((path, entry.metadata().await?));
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn collect_deletes_only_unreferenced_webp_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pool = crate::db::make_pool(tmp.path().join("db.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let media = tmp.path().join("media");
+        for rel in [
+            "recipes/1/full.webp",
+            "recipes/1/small.webp",
+            "recipes/2/full.webp",
+            "recipe_1_old.webp",
+            "cache/1-full-640.webp",
+            "cache/2-full-640.webp",
+            "share-cards/1-2024.png",
+        ] {
+            let path = media.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"12345").unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO recipes (id, title, ingredients, instructions, image_path_full, image_path_small)
+             VALUES (1, 'Soup', '[]', '[]', 'recipes/1/full.webp', 'recipes/1/small.webp')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Everything is brand new, so the grace period protects the orphans.
+        let report = collect(&pool, &media, GRACE).await.unwrap();
+        assert_eq!(
+            report,
+            GcReport {
+                deleted: 0,
+                kept: 6,
+                bytes_freed: 0
+            }
+        );
+
+        let report = collect(&pool, &media, Duration::ZERO).await.unwrap();
+        assert_eq!(
+            report,
+            GcReport {
+                deleted: 3,
+                kept: 3,
+                bytes_freed: 15
+            }
+        );
+        assert!(media.join("recipes/1/full.webp").exists());
+        assert!(media.join("recipes/1/small.webp").exists());
+        assert!(!media.join("recipes/2/full.webp").exists());
+        assert!(!media.join("recipe_1_old.webp").exists());
+        assert!(media.join("cache/1-full-640.webp").exists());
+        assert!(!media.join("cache/2-full-640.webp").exists());
+        assert!(media.join("share-cards/1-2024.png").exists());
+    }
+}
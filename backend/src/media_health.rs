@@ -0,0 +1,131 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+use crate::models::AppState;
+
+/// How often a degraded server re-checks the media directory.
+const REPROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Longest pause between startup probes while waiting for the media dir.
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+const PROBE_FILE: &str = ".blaz-write-probe";
+
+/// Whether the media directory is usable. Shared by all clones of `AppState`.
+///
+/// When it is not, image endpoints answer 503 `media_unavailable` instead of
+/// failing halfway through a write.
+#[derive(Clone, Default)]
+pub struct MediaHealth {
+    unavailable: Arc<AtomicBool>,
+}
+
+impl MediaHealth {
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        !self.unavailable.load(Ordering::Relaxed)
+    }
+
+    pub fn set_available(&self, available: bool) {
+        self.unavailable.store(!available, Ordering::Relaxed);
+    }
+
+    /// Probe `dir` and record the result, logging transitions.
+    pub async fn refresh(&self, dir: &Path) -> bool {
+        let result = probe(dir).await;
+        let was = self.is_available();
+        match &result {
+            Ok(()) if !was => tracing::info!("media dir {} is available again", dir.display()),
+            Err(e) if was => {
+                tracing::error!("media dir {} is unavailable: {e}", dir.display());
+            }
+            _ => {}
+        }
+        self.set_available(result.is_ok());
+        result.is_ok()
+    }
+
+    /// # Errors
+    ///
+    /// 503 `media_unavailable` while the media dir is degraded.
+    pub fn ensure_available(&self) -> AppResult<()> {
+        if self.is_available() {
+            Ok(())
+        } else {
+            Err(AppError::Code(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "media_unavailable",
+                "Media storage is not available; try again later".to_string(),
+            ))
+        }
+    }
+}
+
+/// Create `dir` if needed and check that a file can be written in it.
+///
+/// # Errors
+///
+/// The underlying I/O error when the directory cannot be created or written.
+pub async fn probe(dir: &Path) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    let probe = dir.join(PROBE_FILE);
+    tokio::fs::write(&probe, b"ok").await?;
+    tokio::fs::remove_file(&probe).await
+}
+
+/// Probe `dir` with exponential backoff for up to `grace` (for mounts that
+/// come up after the server). Returns whether the directory became usable.
+pub async fn wait_for_media(dir: &Path, grace: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + grace;
+    let mut delay = Duration::from_millis(500);
+    loop {
+        match probe(dir).await {
+            Ok(()) => return true,
+            Err(e) => {
+                let now = tokio::time::Instant::now();
+                if now >= deadline {
+                    tracing::error!("media dir {} is unavailable: {e}", dir.display());
+                    return false;
+                }
+                tracing::warn!(
+                    "media dir {} not ready ({e}); retrying in {delay:?}",
+                    dir.display()
+                );
+                tokio::time::sleep(delay.min(deadline - now)).await;
+                delay = (delay * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Re-probe in the background and clear the degraded flag once the media
+/// dir appears.
+pub fn spawn_reprobe(health: MediaHealth, dir: PathBuf) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(REPROBE_INTERVAL);
+        tick.tick().await;
+        loop {
+            tick.tick().await;
+            if !health.is_available() {
+                health.refresh(&dir).await;
+            }
+        }
+    });
+}
+
+/// Middleware for routes that read or write media files.
+pub async fn require_media(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    match state.media.ensure_available() {
+        Ok(()) => next.run(req).await,
+        Err(e) => e.into_response(),
+    }
+}
@@ -0,0 +1,176 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolve a relative media name (e.g. `recipes/12/full.webp`) under `root`.
+///
+/// Rejects empty and absolute names and `..` components, and names whose
+/// deepest existing ancestor canonicalizes outside `root` (a symlink placed
+/// inside the media dir that points elsewhere).
+///
+/// # Errors
+///
+/// `InvalidInput` for malformed names, `PermissionDenied` for escapes, or the
+/// underlying error if `root` cannot be canonicalized.
+pub fn resolve(root: &Path, name: &str) -> io::Result<PathBuf> {
+    let rel = Path::new(name);
+    if name.trim().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "empty media path",
+        ));
+    }
+    if !rel
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("media path must be relative without '..': {name}"),
+        ));
+    }
+
+    let path = root.join(rel);
+    let canon_root = root.canonicalize()?;
+
+    // Walk up to the deepest entry that exists (a dangling symlink counts and
+    // then fails to canonicalize, which is what we want).
+    let mut existing = path.as_path();
+    while existing.symlink_metadata().is_err() {
+        existing = existing.parent().unwrap_or(root);
+        if existing == root {
+            break;
+        }
+    }
+
+    if !existing.canonicalize()?.starts_with(&canon_root) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("media path escapes the media directory: {name}"),
+        ));
+    }
+
+    Ok(path)
+}
+
+/// Delete a media file by relative name. Unlike [`resolve`], any symlink
+/// between `root` and the file is refused rather than followed, so a link
+/// planted in the media dir can never redirect a delete.
+///
+/// # Errors
+///
+/// See [`resolve`]; also `PermissionDenied` for symlinks and `NotFound` if the
+/// file does not exist.
+pub async fn remove_file(root: &Path, name: &str) -> io::Result<()> {
+    let path = resolve(root, name)?;
+
+    let mut cur = root.to_path_buf();
+    for part in Path::new(name).components() {
+        if let Component::Normal(part) = part {
+            cur.push(part);
+            if tokio::fs::symlink_metadata(&cur)
+                .await?
+                .file_type()
+                .is_symlink()
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("refusing to delete through symlink: {name}"),
+                ));
+            }
+        }
+    }
+
+    tokio::fs::remove_file(path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_accepts_nested_names() {
+        let root = tempfile::tempdir().unwrap();
+        let path = resolve(root.path(), "recipes/7/full.webp").unwrap();
+        assert_eq!(path, root.path().join("recipes/7/full.webp"));
+        assert!(resolve(root.path(), "./small.webp").is_ok());
+    }
+
+    #[test]
+    fn test_resolve_rejects_crafted_names() {
+        let root = tempfile::tempdir().unwrap();
+        for name in [
+            "",
+            "  ",
+            "/etc/passwd",
+            "../outside.webp",
+            "recipes/../../outside.webp",
+            "recipes/7/..",
+        ] {
+            let err = resolve(root.path(), name).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{name:?}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_rejects_symlinked_dir_outside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("recipes")).unwrap();
+
+        let err = resolve(root.path(), "recipes/7/full.webp").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remove_file_refuses_symlink_escape() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let victim = outside.path().join("precious.txt");
+        std::fs::write(&victim, "keep me").unwrap();
+        std::os::unix::fs::symlink(&victim, root.path().join("evil.webp")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("linked")).unwrap();
+
+        assert!(remove_file(root.path(), "evil.webp").await.is_err());
+        assert!(
+            remove_file(root.path(), "linked/precious.txt")
+                .await
+                .is_err()
+        );
+        assert!(victim.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remove_file_refuses_symlink_inside_root() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("real.webp"), b"x").unwrap();
+        std::os::unix::fs::symlink(
+            root.path().join("real.webp"),
+            root.path().join("alias.webp"),
+        )
+        .unwrap();
+
+        let err = remove_file(root.path(), "alias.webp").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(root.path().join("real.webp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_remove_file_deletes_regular_file() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("recipes/3")).unwrap();
+        std::fs::write(root.path().join("recipes/3/full.webp"), b"x").unwrap();
+
+        remove_file(root.path(), "recipes/3/full.webp")
+            .await
+            .unwrap();
+        assert!(!root.path().join("recipes/3/full.webp").exists());
+
+        let err = remove_file(root.path(), "recipes/3/full.webp")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,363 @@
+//! Crash-safe writes of media files that database rows point at.
+//!
+//! Files are first written under a temporary name next to their destination,
+//! the row is updated inside a transaction, and only once that commits are the
+//! files renamed into place. A crash can therefore leave temp files behind, or
+//! a row naming a file that was never renamed in, but never a half-written
+//! file under a real name. [`recover`] cleans up both on startup.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use sqlx::{Sqlite, SqlitePool, Transaction};
+
+use crate::media_path;
+
+/// Extension of staged files: `full.webp` is staged as `full.webp.<uuid>.tmp`.
+pub const TMP_EXT: &str = "tmp";
+
+/// Temp files younger than this may belong to a write still in flight.
+pub const STALE_AFTER: Duration = Duration::from_hours(1);
+
+/// Where [`MediaTxn::commit`] stops when a test asks it to, as if the
+/// process died there.
+#[cfg(test)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FailPoint {
+    BeforeDbCommit,
+    BeforeRename,
+}
+
+struct Staged {
+    tmp: PathBuf,
+    dest: PathBuf,
+}
+
+/// Files staged for one database update. Dropping it without a successful
+/// [`commit`](Self::commit) removes the staged files.
+#[derive(Default)]
+pub struct MediaTxn {
+    staged: Vec<Staged>,
+    finished: bool,
+    #[cfg(test)]
+    fail_at: Option<FailPoint>,
+}
+
+impl MediaTxn {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop at `point` without cleaning up, leaving what a crash would.
+    #[cfg(test)]
+    #[must_use]
+    pub const fn fail_at(mut self, point: FailPoint) -> Self {
+        self.fail_at = Some(point);
+        self
+    }
+
+    /// Write `bytes` to a temp file beside the media name `rel`.
+    ///
+    /// # Errors
+    /// Err if `rel` is not a valid media name or the write fails.
+    pub async fn stage(&mut self, media_dir: &Path, rel: &str, bytes: &[u8]) -> io::Result<()> {
+        let dest = media_path::resolve(media_dir, rel)?;
+        if let Some(dir) = dest.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut tmp = dest.clone().into_os_string();
+        tmp.push(format!(".{}.{TMP_EXT}", uuid::Uuid::new_v4().simple()));
+        let tmp = PathBuf::from(tmp);
+        tokio::fs::write(&tmp, bytes).await?;
+        self.staged.push(Staged { tmp, dest });
+        Ok(())
+    }
+
+    /// Commit `tx`, which should point rows at the staged names, then move
+    /// the staged files into place.
+    ///
+    /// # Errors
+    /// Err if the commit or a rename fails. Staged files are removed either
+    /// way; a row left naming a missing file is cleared by [`recover`].
+    pub async fn commit(mut self, tx: Transaction<'_, Sqlite>) -> anyhow::Result<()> {
+        #[cfg(test)]
+        self.crash_if(FailPoint::BeforeDbCommit)?;
+        tx.commit().await?;
+        #[cfg(test)]
+        self.crash_if(FailPoint::BeforeRename)?;
+        for staged in &self.staged {
+            tokio::fs::rename(&staged.tmp, &staged.dest).await?;
+        }
+        self.finished = true;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn crash_if(&mut self, point: FailPoint) -> anyhow::Result<()> {
+        if self.fail_at == Some(point) {
+            self.finished = true;
+            anyhow::bail!("injected failure at {point:?}");
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MediaTxn {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        for staged in &self.staged {
+            let _ = std::fs::remove_file(&staged.tmp);
+        }
+    }
+}
+
+/// Startup reconciliation: delete temp files older than `stale_after`, then
+/// null out recipe image paths whose files are missing.
+pub async fn recover(pool: &SqlitePool, media_dir: &Path, stale_after: Duration) {
+    match remove_stale_temp_files(media_dir, stale_after).await {
+        Ok(0) => {}
+        Ok(n) => tracing::info!("Removed {n} stale temporary media file(s)"),
+        Err(e) => tracing::warn!("Could not scan media dir for temp files: {e}"),
+    }
+    clear_broken_image_paths(pool, media_dir).await;
+}
+
+/// Walk `media_dir` (without following symlinks) and delete `*.tmp` files
+/// last modified more than `stale_after` ago.
+///
+/// # Errors
+/// Err if `media_dir` cannot be read.
+pub async fn remove_stale_temp_files(media_dir: &Path, stale_after: Duration) -> io::Result<u32> {
+    let now = SystemTime::now();
+    let mut removed = 0;
+    let mut dirs = vec![media_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let kind = entry.file_type().await?;
+            let path = entry.path();
+            if kind.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            if !kind.is_file() || path.extension().is_none_or(|e| e != TMP_EXT) {
+                continue;
+            }
+            let age = entry
+                .metadata()
+                .await?
+                .modified()
+                .map(|m| now.duration_since(m).unwrap_or_default())?;
+            if age >= stale_after && tokio::fs::remove_file(&path).await.is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Null out `image_path_small` / `image_path_full` for recipes whose image
+/// files no longer exist on disk, so clients never request them.
+pub async fn clear_broken_image_paths(pool: &SqlitePool, media_dir: &Path) {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        id: i64,
+        image_path_small: Option<String>,
+        image_path_full: Option<String>,
+    }
+
+    let Ok(rows) = sqlx::query_as::<_, Row>(
+        "SELECT id, image_path_small, image_path_full FROM recipes \
+         WHERE image_path_small IS NOT NULL OR image_path_full IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await
+    else {
+        return;
+    };
+
+    // Paths that escape the media dir are treated as broken too.
+    let missing = |p: &str| !media_path::resolve(media_dir, p).is_ok_and(|abs| abs.exists());
+
+    let mut cleared = 0u32;
+    for row in rows {
+        let small_missing = row.image_path_small.as_deref().is_some_and(missing);
+        let full_missing = row.image_path_full.as_deref().is_some_and(missing);
+
+        if small_missing || full_missing {
+            let _ = sqlx::query(
+                "UPDATE recipes SET \
+                 image_path_small = CASE WHEN ? THEN NULL ELSE image_path_small END, \
+                 image_path_full  = CASE WHEN ? THEN NULL ELSE image_path_full  END \
+                 WHERE id = ?",
+            )
+            .bind(small_missing)
+            .bind(full_missing)
+            .bind(row.id)
+            .execute(pool)
+            .await;
+            cleared += 1;
+        }
+    }
+
+    if cleared > 0 {
+        tracing::info!("Cleared broken image paths from {cleared} recipe(s)");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup() -> (tempfile::TempDir, SqlitePool) {
+        let tmp = tempfile::tempdir().unwrap();
+        let pool = crate::db::make_pool(tmp.path().join("db.sqlite").display().to_string())
+            .await
+            .unwrap();
+        std::fs::create_dir(tmp.path().join("media")).unwrap();
+        sqlx::query(
+            "INSERT INTO recipes (id, title, ingredients, instructions) VALUES (1, 'Soup', '[]', '[]')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        (tmp, pool)
+    }
+
+    /// Stage two images and point recipe 1 at them, stopping at `fail`.
+    async fn write_images(pool: &SqlitePool, media: &Path, fail: Option<FailPoint>) -> bool {
+        let mut txn = MediaTxn::new();
+        if let Some(point) = fail {
+            txn = txn.fail_at(point);
+        }
+        txn.stage(media, "recipes/1/full.webp", b"full")
+            .await
+            .unwrap();
+        txn.stage(media, "recipes/1/small.webp", b"small")
+            .await
+            .unwrap();
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query(
+            "UPDATE recipes SET image_path_full = 'recipes/1/full.webp', \
+             image_path_small = 'recipes/1/small.webp' WHERE id = 1",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        txn.commit(tx).await.is_ok()
+    }
+
+    async fn image_paths(pool: &SqlitePool) -> (Option<String>, Option<String>) {
+        sqlx::query_as("SELECT image_path_full, image_path_small FROM recipes WHERE id = 1")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn temp_files(media: &Path) -> Vec<String> {
+        let dir = media.join("recipes/1");
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == TMP_EXT))
+            .map(|p| p.display().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_commit_moves_files_into_place() {
+        let (tmp, pool) = setup().await;
+        let media = tmp.path().join("media");
+
+        assert!(write_images(&pool, &media, None).await);
+        assert_eq!(
+            std::fs::read(media.join("recipes/1/full.webp")).unwrap(),
+            b"full"
+        );
+        assert!(temp_files(&media).is_empty());
+
+        recover(&pool, &media, Duration::ZERO).await;
+        assert_eq!(
+            image_paths(&pool).await,
+            (
+                Some("recipes/1/full.webp".into()),
+                Some("recipes/1/small.webp".into())
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_crash_before_db_commit_recovers() {
+        let (tmp, pool) = setup().await;
+        let media = tmp.path().join("media");
+
+        assert!(!write_images(&pool, &media, Some(FailPoint::BeforeDbCommit)).await);
+        assert_eq!(
+            temp_files(&media).len(),
+            2,
+            "a crash leaves the staged files"
+        );
+        assert_eq!(image_paths(&pool).await, (None, None));
+
+        recover(&pool, &media, Duration::ZERO).await;
+        assert!(temp_files(&media).is_empty());
+        assert!(!media.join("recipes/1/full.webp").exists());
+        assert_eq!(image_paths(&pool).await, (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_crash_before_rename_recovers() {
+        let (tmp, pool) = setup().await;
+        let media = tmp.path().join("media");
+
+        assert!(!write_images(&pool, &media, Some(FailPoint::BeforeRename)).await);
+        assert_eq!(temp_files(&media).len(), 2);
+        assert!(
+            image_paths(&pool).await.0.is_some(),
+            "the row already committed"
+        );
+
+        recover(&pool, &media, Duration::ZERO).await;
+        assert!(temp_files(&media).is_empty());
+        assert_eq!(
+            image_paths(&pool).await,
+            (None, None),
+            "no refs to missing files"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recent_temp_files_survive_recovery() {
+        let (tmp, pool) = setup().await;
+        let media = tmp.path().join("media");
+
+        assert!(!write_images(&pool, &media, Some(FailPoint::BeforeDbCommit)).await);
+        recover(&pool, &media, STALE_AFTER).await;
+        assert_eq!(
+            temp_files(&media).len(),
+            2,
+            "may belong to a write in flight"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_txn_removes_staged_files() {
+        let (tmp, _pool) = setup().await;
+        let media = tmp.path().join("media");
+
+        let mut txn = MediaTxn::new();
+        txn.stage(&media, "recipes/1/full.webp", b"full")
+            .await
+            .unwrap();
+        assert_eq!(temp_files(&media).len(), 1);
+        drop(txn);
+        assert!(temp_files(&media).is_empty());
+        assert!(!media.join("recipes/1/full.webp").exists());
+    }
+}
@@ -0,0 +1,309 @@
+//! Prometheus metrics at `GET /metrics`.
+//!
+//! Counters and histograms live in one process-wide registry so code without
+//! an `AppState` (LLM client, image encoding) can record into it. Row counts
+//! are read from the database at scrape time.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::extract::{MatchedPath, State};
+use axum::http::{Method, Request, Response, header};
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+
+use crate::error::AppResult;
+use crate::models::AppState;
+
+/// Upper bounds (seconds) of the histogram buckets, Prometheus' defaults.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Route label for requests no route matched (the web app fallback).
+const UNMATCHED: &str = "unmatched";
+
+#[derive(Clone, Default)]
+struct Histogram {
+    /// Non-cumulative count per bucket; the last slot is `+Inf`.
+    buckets: [u64; BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        let slot = BUCKETS
+            .iter()
+            .position(|b| secs <= *b)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[slot] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    /// `_bucket`, `_sum` and `_count` lines for one label set.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            cumulative += n;
+            let le = BUCKETS
+                .get(i)
+                .map_or_else(|| "+Inf".to_string(), f64::to_string);
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels}{sep}le=\"{le}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+#[derive(Default)]
+struct LlmCalls {
+    calls: u64,
+    failures: u64,
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Keyed by (method, route template, status).
+    http: Mutex<BTreeMap<(&'static str, String, u16), Histogram>>,
+    /// Keyed by the usage-log route of the client (`untracked` otherwise).
+    llm: Mutex<BTreeMap<&'static str, LlmCalls>>,
+    /// Keyed by the kind of encoding.
+    images: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::default);
+
+fn lock<T>(m: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Count one LLM call made on behalf of `route`.
+pub fn record_llm_call(route: &'static str, ok: bool) {
+    let mut llm = lock(&REGISTRY.llm);
+    let entry = llm.entry(route).or_default();
+    entry.calls += 1;
+    entry.failures += u64::from(!ok);
+    drop(llm);
+}
+
+/// Record how long encoding an image of `kind` took.
+pub fn observe_image(kind: &'static str, elapsed: Duration) {
+    lock(&REGISTRY.images)
+        .entry(kind)
+        .or_default()
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Methods reported by name; anything else is counted as `other` so
+/// arbitrary extension methods cannot add labels.
+const KNOWN_METHODS: [&str; 9] = [
+    "GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS", "CONNECT", "TRACE",
+];
+
+fn method_label(method: &Method) -> &'static str {
+    KNOWN_METHODS
+        .into_iter()
+        .find(|m| *m == method.as_str())
+        .unwrap_or("other")
+}
+
+/// Count every request with its duration, labeled by the route template
+/// (`/recipes/{id}`, not `/recipes/42`) so the label set stays small.
+pub async fn track_http(request: Request<Body>, next: Next) -> Response<Body> {
+    let method = method_label(request.method());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| UNMATCHED.to_string(), |p| p.as_str().to_string());
+    let started = Instant::now();
+
+    let res = next.run(request).await;
+
+    lock(&REGISTRY.http)
+        .entry((method, route, res.status().as_u16()))
+        .or_default()
+        .observe(started.elapsed().as_secs_f64());
+    res
+}
+
+/// Escape a label value for the text format.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+fn header_lines(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn render(recipes: i64, shopping_items: i64) -> String {
+    let mut out = String::new();
+
+    let http = lock(&REGISTRY.http).clone();
+    header_lines(
+        &mut out,
+        "blaz_http_requests_total",
+        "counter",
+        "HTTP requests handled.",
+    );
+    for ((method, route, status), h) in &http {
+        let _ = writeln!(
+            out,
+            "blaz_http_requests_total{{method=\"{method}\",route=\"{}\",status=\"{status}\"}} {}",
+            label(route),
+            h.count
+        );
+    }
+    header_lines(
+        &mut out,
+        "blaz_http_request_duration_seconds",
+        "histogram",
+        "Time to handle an HTTP request.",
+    );
+    for ((method, route, status), h) in &http {
+        let labels = format!(
+            "method=\"{method}\",route=\"{}\",status=\"{status}\"",
+            label(route)
+        );
+        h.render(&mut out, "blaz_http_request_duration_seconds", &labels);
+    }
+
+    {
+        let llm = lock(&REGISTRY.llm);
+        header_lines(
+            &mut out,
+            "blaz_llm_calls_total",
+            "counter",
+            "LLM calls, including failed ones.",
+        );
+        for (route, c) in llm.iter() {
+            let _ = writeln!(out, "blaz_llm_calls_total{{route=\"{route}\"}} {}", c.calls);
+        }
+        header_lines(
+            &mut out,
+            "blaz_llm_failures_total",
+            "counter",
+            "LLM calls that failed after retries.",
+        );
+        for (route, c) in llm.iter() {
+            let _ = writeln!(
+                out,
+                "blaz_llm_failures_total{{route=\"{route}\"}} {}",
+                c.failures
+            );
+        }
+    }
+
+    header_lines(
+        &mut out,
+        "blaz_image_processing_seconds",
+        "histogram",
+        "Time to encode an image.",
+    );
+    for (kind, h) in lock(&REGISTRY.images).iter() {
+        h.render(
+            &mut out,
+            "blaz_image_processing_seconds",
+            &format!("kind=\"{kind}\""),
+        );
+    }
+
+    header_lines(
+        &mut out,
+        "blaz_recipes",
+        "gauge",
+        "Recipes, not counting deleted ones.",
+    );
+    let _ = writeln!(out, "blaz_recipes {recipes}");
+    header_lines(
+        &mut out,
+        "blaz_shopping_items",
+        "gauge",
+        "Items on the shopping list.",
+    );
+    let _ = writeln!(out, "blaz_shopping_items {shopping_items}");
+
+    out
+}
+
+/// `GET /metrics` in the Prometheus text exposition format.
+///
+/// # Errors
+///
+/// Err if counting the rows fails
+pub async fn scrape(State(state): State<AppState>) -> AppResult<impl IntoResponse> {
+    let recipes: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM recipes WHERE deleted_at IS NULL")
+        .fetch_one(&state.pool)
+        .await?;
+    let shopping_items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM shopping_items")
+        .fetch_one(&state.pool)
+        .await?;
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        render(recipes, shopping_items),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let mut h = Histogram::default();
+        h.observe(0.001);
+        h.observe(0.2);
+        h.observe(60.0);
+        let mut out = String::new();
+        h.render(&mut out, "t", "k=\"v\"");
+        assert!(out.contains("t_bucket{k=\"v\",le=\"0.005\"} 1\n"), "{out}");
+        assert!(out.contains("t_bucket{k=\"v\",le=\"0.25\"} 2\n"), "{out}");
+        assert!(out.contains("t_bucket{k=\"v\",le=\"10\"} 2\n"), "{out}");
+        assert!(out.contains("t_bucket{k=\"v\",le=\"+Inf\"} 3\n"), "{out}");
+        assert!(out.contains("t_count{k=\"v\"} 3\n"), "{out}");
+        assert_eq!(label("a\"b\\c"), r#"a\"b\\c"#);
+    }
+
+    #[tokio::test]
+    async fn extension_methods_share_one_label() {
+        use axum::{Router, middleware::from_fn, routing::any};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/metrics-probe", any(|| async { "ok" }))
+            .layer(from_fn(track_http));
+        for method in ["FROBNICATE", "FROBNICATE2", "get"] {
+            let req = Request::builder()
+                .method(method)
+                .uri("/metrics-probe")
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(req).await.unwrap();
+        }
+
+        let out = render(0, 0);
+        assert!(!out.contains("FROBNICATE"), "{out}");
+        assert!(
+            out.contains(
+                "blaz_http_requests_total{method=\"other\",route=\"/metrics-probe\",status=\"200\"} 3\n"
+            ),
+            "{out}"
+        );
+        assert_eq!(method_label(&Method::PATCH), "PATCH");
+    }
+}
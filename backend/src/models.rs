@@ -9,7 +9,19 @@ use crate::config::Config;
 pub struct AppState {
     pub pool: SqlitePool,
     pub jwt_encoding: jsonwebtoken::EncodingKey,
+    pub jwt_decoding: jsonwebtoken::DecodingKey,
     pub config: Config,
+    pub media: crate::media_health::MediaHealth,
+    /// Shared client for outbound fetches (recipe pages, images, LLM calls).
+    pub http: reqwest::Client,
+    /// Progress of `POST /recipes/macros/estimate-all`.
+    pub macro_jobs: crate::macro_jobs::MacroJobs,
+    /// Changes to the shopping list, for `GET /shopping/events`.
+    pub shopping_events: crate::shopping_events::ShoppingEvents,
+    /// Aggregated names for `GET /ingredients/suggest`.
+    pub ingredient_suggest: crate::routes::ingredient_suggest::SuggestCache,
+    /// Shopping category names, for classification and list order.
+    pub categories: crate::categories::CategoryCache,
 }
 
 /* ---------- API models ---------- */
@@ -18,8 +30,8 @@ pub struct AppState {
 pub struct Ingredient {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub section: Option<String>, // if Some, this item is a section header
-    #[serde(default)]
-    pub quantity: Option<f64>, // e.g. 120.0
+    #[serde(default, deserialize_with = "crate::units::deserialize_locale_number")]
+    pub quantity: Option<f64>, // e.g. 120.0, also accepts "1,5"
     #[serde(default)]
     pub unit: Option<String>, // "g","kg","ml","L","tsp","tbsp" (normalized)
     #[serde(default)]
@@ -37,9 +49,38 @@ pub struct IngredientMacros {
     pub protein_g: f64,
     pub fat_g: f64,
     pub carbs_g: f64,
+    #[serde(default)]
+    pub kcal: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiber_g: Option<f64>,
     pub skipped: bool,
 }
 
+/// Macro sums for one basis (a serving or the whole recipe).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct MacroTotals {
+    pub protein_g: f64,
+    pub fat_g: f64,
+    pub carbs_g: f64,
+    pub kcal: f64,
+    /// `None` when no ingredient reported fiber.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiber_g: Option<f64>,
+}
+
+impl std::ops::AddAssign for MacroTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.protein_g += other.protein_g;
+        self.fat_g += other.fat_g;
+        self.carbs_g += other.carbs_g;
+        self.kcal += other.kcal;
+        self.fiber_g = match (self.fiber_g, other.fiber_g) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+        };
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RecipeMacros {
     /// `per_serving` if yield could be parsed as N servings, otherwise `per_recipe`.
@@ -47,8 +88,19 @@ pub struct RecipeMacros {
     pub protein_g: f64,
     pub fat_g: f64,   // saturated + unsaturated combined
     pub carbs_g: f64, // excluding fiber
+    /// Older estimates have none; 0 then.
+    #[serde(default)]
+    pub kcal: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fiber_g: Option<f64>,
+    /// Whole-recipe sums when `basis` is `per_serving`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_recipe_totals: Option<MacroTotals>,
     #[serde(default)]
     pub ingredients: Vec<IngredientMacros>,
+    /// `chunked` when the ingredients were estimated in groups and summed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -64,16 +116,46 @@ pub struct Recipe {
     pub source: String,
     #[serde(rename = "yield")]
     pub r#yield: String,
+    /// Servings parsed from `yield`, or `None` when the yield isn't servings-like.
+    pub servings: Option<f64>,
     pub notes: String,
     pub created_at: String,
     pub updated_at: String,
     pub ingredients: Vec<Ingredient>,
     pub instructions: Vec<String>,
+    /// Normalized tool/appliance names, e.g. `["food processor"]`.
+    pub equipment: Vec<String>,
     pub image_path_small: Option<String>,
     pub image_path_full: Option<String>,
+    /// Image fetch outcome of a URL import: `ok`, `none_found` or
+    /// `failed: <reason>`. `None` when the recipe wasn't imported from a URL.
+    pub image_import_status: Option<String>,
     pub macros: Option<RecipeMacros>,
     pub share_token: Option<String>,
     pub prep_reminders: Option<Vec<PrepReminder>>,
+    pub is_favorite: bool,
+    /// When the recipe was last marked cooked (`YYYY-MM-DD HH:MM:SS`, UTC).
+    pub last_cooked_at: Option<String>,
+    pub times_cooked: i64,
+    /// Mean rating of the cooking log; `None` when no entry is rated.
+    pub avg_rating: Option<f64>,
+    pub log_count: i64,
+    /// Set when a create request matched a recently created recipe and the
+    /// existing row was returned instead of inserting a duplicate.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deduplicated: bool,
+    /// How a URL import extracted the recipe; only set on import responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_method: Option<ImportMethod>,
+}
+
+/// Where a URL import got its ingredients and steps from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMethod {
+    /// The page's schema.org JSON-LD, parsed without the LLM.
+    SchemaOrg,
+    Llm,
 }
 
 #[derive(Deserialize, Debug)]
@@ -89,6 +171,11 @@ pub struct NewRecipe {
     pub ingredients: Vec<Ingredient>,
     #[serde(default)]
     pub instructions: Vec<String>,
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    /// Skip the double-submit guard and always insert a new row.
+    #[serde(default)]
+    pub allow_duplicate: bool,
 }
 
 #[derive(Deserialize, Debug, Default)]
@@ -97,10 +184,62 @@ pub struct UpdateRecipe {
     pub source: Option<String>,
     #[serde(rename = "yield")]
     pub r#yield: Option<String>,
+    /// Sets servings directly; `yield` is regenerated unless also provided.
+    #[serde(default, deserialize_with = "crate::units::deserialize_locale_number")]
+    pub servings: Option<f64>,
     pub notes: Option<String>,
-    pub ingredients: Option<Vec<Ingredient>>,
+    pub ingredients: Option<Vec<IngredientRepr>>,
     pub instructions: Option<Vec<String>>,
+    pub equipment: Option<Vec<String>>,
     pub prep_reminders: Option<Vec<PrepReminder>>,
+    #[serde(default)]
+    pub macros: MacrosField,
+    pub is_favorite: Option<bool>,
+}
+
+/// An ingredient as written: a structured object, or a line of text
+/// ("200 g flour") that is parsed like a schema.org ingredient. PATCH bodies
+/// may send either, and recipes saved before ingredients were structured
+/// still hold lines.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum IngredientRepr {
+    Line(String),
+    Structured(Ingredient),
+}
+
+impl From<IngredientRepr> for Ingredient {
+    /// Lines that don't parse are kept as raw text.
+    fn from(repr: IngredientRepr) -> Self {
+        match repr {
+            IngredientRepr::Structured(ing) => ing,
+            IngredientRepr::Line(line) => crate::routes::shopping::parse_ingredient_line(&line)
+                .unwrap_or_else(|| Self {
+                    section: None,
+                    quantity: None,
+                    unit: None,
+                    name: crate::units::preclean_text(&line).trim().to_string(),
+                    prep: None,
+                    raw: true,
+                }),
+        }
+    }
+}
+
+/// A `macros` PATCH field, where `null` and absent mean different things.
+#[derive(Debug, Clone, Default)]
+pub enum MacrosField {
+    /// Leave the estimate alone.
+    #[default]
+    Absent,
+    Clear,
+    Set(RecipeMacros),
+}
+
+impl<'de> Deserialize<'de> for MacrosField {
+    fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        Ok(Option::<RecipeMacros>::deserialize(de)?.map_or(Self::Clear, Self::Set))
+    }
 }
 
 /* ---------- DB row model ---------- */
@@ -112,17 +251,25 @@ pub struct RecipeRow {
     pub source: String,
     #[sqlx(rename = "yield")] // ensure mapping from column "yield"
     pub r#yield: String,
+    pub servings: Option<f64>,
     pub notes: String,
     pub created_at: String,
     pub updated_at: String,
-    // IMPORTANT: let rows load even if they still have ["2 carrots", ...]
-    pub ingredients: Json<Vec<Ingredient>>,
+    // Lets rows load even if they still have ["2 carrots", ...]
+    pub ingredients: Json<Vec<IngredientRepr>>,
     pub instructions: Json<Vec<String>>,
+    pub equipment: Json<Vec<String>>,
     pub image_path_small: Option<String>,
     pub image_path_full: Option<String>,
+    pub image_import_status: Option<String>,
     pub macros: Option<Json<RecipeMacros>>,
     pub share_token: Option<String>,
     pub prep_reminders: Option<Json<Vec<PrepReminder>>>,
+    pub is_favorite: bool,
+    pub last_cooked_at: Option<String>,
+    pub times_cooked: i64,
+    pub avg_rating: Option<f64>,
+    pub log_count: i64,
 }
 
 impl From<RecipeRow> for Recipe {
@@ -132,35 +279,109 @@ impl From<RecipeRow> for Recipe {
             title: r.title,
             source: r.source,
             r#yield: r.r#yield,
+            servings: r.servings,
             notes: r.notes,
             created_at: r.created_at,
             updated_at: r.updated_at,
-            ingredients: r.ingredients.0,
+            ingredients: r.ingredients.0.into_iter().map(Ingredient::from).collect(),
             instructions: r.instructions.0,
+            equipment: r.equipment.0,
             image_path_full: r.image_path_full,
             image_path_small: r.image_path_small,
+            image_import_status: r.image_import_status,
             macros: r.macros.map(|j| j.0),
             share_token: r.share_token,
             prep_reminders: r.prep_reminders.map(|j| j.0),
+            is_favorite: r.is_favorite,
+            last_cooked_at: r.last_cooked_at,
+            times_cooked: r.times_cooked,
+            avg_rating: r.avg_rating,
+            log_count: r.log_count,
+            deduplicated: false,
+            import_method: None,
         }
     }
 }
 
+/// A recipe card for `GET /recipes?view=summary`: everything the grid shows,
+/// without the ingredients, instructions and notes.
+#[derive(Serialize, FromRow, Clone, Debug)]
+pub struct RecipeSummary {
+    pub id: i64,
+    pub title: String,
+    pub servings: Option<f64>,
+    pub image_path_small: Option<String>,
+    pub equipment: Json<Vec<String>>,
+    pub macros: Option<Json<RecipeMacros>>,
+    pub is_favorite: bool,
+    pub last_cooked_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Body of `GET /recipes`, shaped by `?view=`.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum RecipeList {
+    Full(Vec<Recipe>),
+    Summary(Vec<RecipeSummary>),
+}
+
 /* ---------- Meal plan ---------- */
 
 #[derive(Serialize, Deserialize, FromRow, Clone)]
 pub struct MealPlanEntry {
     pub id: i64,
     pub day: String, // "YYYY-MM-DD"
+    pub meal_type: MealType,
     pub recipe_id: i64,
     pub title: String,                    // joined from recipes for convenience
     pub image_path_small: Option<String>, // joined from recipes
 }
 
+/// A meal plan entry with the recipe details the week view shows
+/// (`GET /meal-plan?include=recipe`).
+#[derive(Serialize, Clone)]
+pub struct MealPlanEntryDetailed {
+    #[serde(flatten)]
+    pub entry: MealPlanEntry,
+    #[serde(rename = "yield")]
+    pub r#yield: String,
+    pub servings: Option<f64>,
+    /// `None` when never estimated or when the stored JSON is unreadable.
+    pub macros: Option<RecipeMacros>,
+}
+
+/// Meal slot of a planned recipe. Entries without one are dinner.
+#[derive(Serialize, Deserialize, sqlx::Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum MealType {
+    Breakfast,
+    Lunch,
+    #[default]
+    Dinner,
+    Snack,
+}
+
+impl MealType {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Breakfast => "breakfast",
+            Self::Lunch => "lunch",
+            Self::Dinner => "dinner",
+            Self::Snack => "snack",
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct AssignRecipe {
     pub day: String, // "YYYY-MM-DD"
     pub recipe_id: i64,
+    #[serde(default)]
+    pub meal_type: MealType,
 }
 
 /* ---------- Shopping list ---------- */
@@ -174,6 +395,8 @@ pub struct ShoppingItemView {
     pub notes: String,
     pub recipe_ids: String,            // JSON array like "[1,2,3]"
     pub recipe_titles: Option<String>, // Comma-separated like "Recipe A, Recipe B"
+    pub store_id: Option<i64>,         // None = can be bought at any store
+    pub position: f64,                 // manual order within a category
 }
 
 #[derive(Deserialize)]
@@ -206,3 +429,132 @@ pub struct UpdateCategory {
 pub struct ReorderCategories {
     pub order: Vec<i64>,
 }
+
+#[derive(Deserialize)]
+pub struct NewCategoryKeyword {
+    pub keyword: String,
+}
+
+/* ---------- Cooking log ---------- */
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct RecipeLog {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub date: String, // "YYYY-MM-DD"
+    /// 1-5
+    pub rating: Option<i64>,
+    pub note: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct NewRecipeLog {
+    /// "YYYY-MM-DD"; today when omitted.
+    pub date: Option<String>,
+    pub rating: Option<i64>,
+    #[serde(default)]
+    pub note: String,
+}
+
+/* ---------- Pantry ---------- */
+
+#[derive(Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct PantryItem {
+    pub id: i64,
+    /// Normalized name; ingredients match it exactly.
+    pub name: String,
+    pub note: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct NewPantryItem {
+    pub name: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePantryItem {
+    pub name: Option<String>,
+    pub note: Option<String>,
+}
+
+/* ---------- Stores ---------- */
+
+#[derive(Serialize, Deserialize, sqlx::FromRow, Clone)]
+pub struct Store {
+    pub id: i64,
+    pub name: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct NewStore {
+    pub name: String,
+}
+
+/// One category's position in a store's aisle order.
+#[derive(Serialize, sqlx::FromRow, Clone)]
+pub struct StoreCategoryOrder {
+    pub category_id: i64,
+    pub name: String,
+    /// `None` when the store has no position for it (global order applies).
+    pub sort_order: Option<i64>,
+}
+
+/* ---------- API tokens ---------- */
+
+/// Coarse permission for an API token.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenScope {
+    /// GET/HEAD on any endpoint.
+    Read,
+    /// Everything except token management.
+    Write,
+    /// Any method, but only on `/shopping` endpoints.
+    ShoppingOnly,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    /// Start of the token, e.g. `blz_a1B2`; empty for tokens created before
+    /// prefixes were stored.
+    #[sqlx(rename = "token_prefix")]
+    pub prefix: String,
+    pub scopes: Json<Vec<TokenScope>>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NewApiToken {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+}
+
+/// Returned once on creation; only the hash is stored.
+#[derive(Serialize)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub info: ApiToken,
+    pub token: String,
+}
+
+/* ---------- Activity log ---------- */
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub created_at: String,
+    /// `user`, `api_token:<id>` or `import`.
+    pub actor: String,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i64>,
+    pub summary: String,
+}
@@ -0,0 +1,53 @@
+use axum::extract::{Query, State};
+use serde::Deserialize;
+
+use crate::{
+    activity::Entity,
+    error::AppResult,
+    extract::Json,
+    models::{ActivityEntry, AppState},
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+#[derive(Deserialize)]
+pub struct ActivityQuery {
+    pub entity: Option<Entity>,
+    pub id: Option<i64>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+const fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+/// GET /activity?entity=recipe&id=12&limit=50
+///
+/// Newest entries first, optionally narrowed to one entity type and id.
+///
+/// # Errors
+/// Err if querying the database fails.
+pub async fn list(
+    State(state): State<AppState>,
+    Query(q): Query<ActivityQuery>,
+) -> AppResult<Json<Vec<ActivityEntry>>> {
+    let entity = q.entity.map(Entity::as_str);
+    let rows: Vec<ActivityEntry> = sqlx::query_as(
+        r"
+        SELECT id, created_at, actor, action, entity_type, entity_id, summary
+          FROM activity_log
+         WHERE (?1 IS NULL OR entity_type = ?1)
+           AND (?2 IS NULL OR entity_id = ?2)
+         ORDER BY id DESC
+         LIMIT ?3
+        ",
+    )
+    .bind(entity)
+    .bind(q.id)
+    .bind(q.limit.clamp(1, MAX_LIMIT))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(rows))
+}
@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use rand::Rng;
+
+use crate::{
+    activity::{self, Actor, Entity, Event},
+    auth_middleware::{API_TOKEN_PREFIX, hash_api_token},
+    error::AppResult,
+    extract::Json,
+    models::{ApiToken, AppState, CreatedApiToken, NewApiToken},
+};
+
+const TOKEN_COLS: &str = "id, name, token_prefix, scopes, created_at, last_used_at";
+
+/// Characters of a new token kept in `token_prefix`: the `blz_` marker and
+/// four characters of the secret.
+const SHOWN_PREFIX_LEN: usize = API_TOKEN_PREFIX.len() + 4;
+
+/// GET /auth/tokens (also `/auth/api-keys`)
+/// List API tokens (without the secret, only its first characters).
+pub async fn list(State(state): State<AppState>) -> AppResult<Json<Vec<ApiToken>>> {
+    let sql = format!("SELECT {TOKEN_COLS} FROM api_tokens ORDER BY id");
+    let rows: Vec<ApiToken> = sqlx::query_as(&sql).fetch_all(&state.pool).await?;
+    Ok(Json(rows))
+}
+
+/// POST /auth/tokens (also `/auth/api-keys`)  `{ "name": "...", "scopes": ["shopping-only"] }`
+/// Create an API token. The token is only ever returned here.
+pub async fn create(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(req): Json<NewApiToken>,
+) -> AppResult<Json<CreatedApiToken>> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Token name cannot be empty".to_string(),
+        )
+            .into());
+    }
+    if req.scopes.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "At least one scope is required".to_string(),
+        )
+            .into());
+    }
+
+    let secret: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+    let token = format!("{API_TOKEN_PREFIX}{secret}");
+
+    let sql = format!(
+        "INSERT INTO api_tokens (name, token_hash, token_prefix, scopes)
+         VALUES (?, ?, ?, ?) RETURNING {TOKEN_COLS}"
+    );
+    let info: ApiToken = sqlx::query_as(&sql)
+        .bind(name)
+        .bind(hash_api_token(&token))
+        .bind(&token[..SHOWN_PREFIX_LEN])
+        .bind(sqlx::types::Json(&req.scopes))
+        .fetch_one(&state.pool)
+        .await?;
+
+    let summary = format!("Created API token '{}'", info.name);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::ApiToken, info.id, "create", summary),
+    )
+    .await;
+    Ok(Json(CreatedApiToken { info, token }))
+}
+
+/// DELETE /auth/tokens/{id} (also `/auth/api-keys/{id}`)
+/// Revoke an API token; requests using it fail immediately.
+pub async fn delete(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> AppResult<StatusCode> {
+    let name: Option<String> =
+        sqlx::query_scalar(r"DELETE FROM api_tokens WHERE id = ? RETURNING name")
+            .bind(id)
+            .fetch_optional(&state.pool)
+            .await?;
+
+    let Some(name) = name else {
+        return Err(StatusCode::NOT_FOUND.into());
+    };
+    let summary = format!("Revoked API token '{name}'");
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::ApiToken, id, "revoke", summary),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,31 @@
+use axum::extract::State;
+use serde::Serialize;
+
+use crate::extract::Json;
+use crate::models::AppState;
+use crate::routes::settings::get_setting;
+use crate::units::UnitSystem;
+
+/// Server-side feature flags the frontend uses to decide which UI to show.
+#[derive(Serialize)]
+pub struct AppStateDto {
+    /// LLM features are disabled (`--offline`); hide import/macros/etc.
+    pub offline: bool,
+    /// Preferred display units; pass as `?units=` when showing a recipe.
+    pub unit_system: UnitSystem,
+    /// Unknown shopping items are classified by the LLM (`use_llm_categories`).
+    pub use_llm_categories: bool,
+}
+
+/// `GET /app-state`
+pub async fn get(State(state): State<AppState>) -> Json<AppStateDto> {
+    let unit_system = get_setting(&state.pool, "unit_system")
+        .await
+        .and_then(|s| UnitSystem::parse(&s))
+        .unwrap_or_default();
+    Json(AppStateDto {
+        offline: state.config.offline,
+        unit_system,
+        use_llm_categories: crate::categories::llm_categories_enabled(&state).await,
+    })
+}
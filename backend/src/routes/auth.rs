@@ -1,9 +1,15 @@
-use crate::error::AppResult;
+use crate::activity::{self, Actor, Entity, Event};
+use crate::auth_middleware::{Claims, bearer_token, verify_jwt};
+use crate::error::{AppError, AppResult};
+use crate::extract::Json;
 use crate::models::AppState;
 use argon2::Argon2;
-use axum::{Json, extract::State, http::StatusCode};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
 use jsonwebtoken::{Algorithm, Header, encode};
-use password_hash::{PasswordHash, PasswordVerifier};
+use password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
@@ -14,14 +20,23 @@ pub struct LoginReq {
 #[derive(Serialize)]
 pub struct LoginResp {
     pub token: String,
+    /// Unix timestamp (seconds) after which the token is rejected, so clients
+    /// can log in again before it lapses.
+    pub expires_at: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: i64, // Always 1 for single-user
-    exp: u64,
+#[derive(Deserialize)]
+pub struct ChangePasswordReq {
+    pub current_password: String,
+    pub new_password: String,
 }
 
+/// How long after expiry a token can still be exchanged at `/auth/refresh`.
+pub const REFRESH_GRACE_SECS: u64 = 24 * 3600;
+
+/// Shortest password accepted by `change-password` and the CLI.
+pub const MIN_PASSWORD_LEN: usize = 8;
+
 fn now_ts() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -29,6 +44,93 @@ fn now_ts() -> u64 {
         .as_secs()
 }
 
+/// The password hash logins are checked against (the stored one, else
+/// `BLAZ_PASSWORD_HASH`) and the current token version.
+async fn credentials(state: &AppState) -> AppResult<(Option<String>, i64)> {
+    let (stored, version): (Option<String>, i64) =
+        sqlx::query_as(r"SELECT password_hash, token_version FROM auth_credentials WHERE id = 1")
+            .fetch_one(&state.pool)
+            .await?;
+    Ok((
+        stored.or_else(|| state.config.password_hash.clone()),
+        version,
+    ))
+}
+
+/// Whether `password` matches the Argon2 `hash`.
+///
+/// # Errors
+/// 500 if `hash` isn't a valid password hash.
+fn password_matches(password: &str, hash: &str) -> AppResult<bool> {
+    let parsed = PasswordHash::new(hash).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok())
+}
+
+/// Argon2 hash of a new password.
+///
+/// # Errors
+/// If the password is shorter than [`MIN_PASSWORD_LEN`] or hashing fails.
+pub fn hash_new_password(password: &str) -> anyhow::Result<String> {
+    if password.trim().chars().count() < MIN_PASSWORD_LEN {
+        anyhow::bail!("Password must be at least {MIN_PASSWORD_LEN} characters");
+    }
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| anyhow::anyhow!("Failed to hash password: {e}"))
+}
+
+/// Store a new password hash and bump the token version, which logs out
+/// every existing session. Returns the new version.
+///
+/// # Errors
+/// If the database write fails.
+pub async fn set_password_hash(pool: &sqlx::SqlitePool, hash: &str) -> sqlx::Result<i64> {
+    sqlx::query_scalar(
+        r"UPDATE auth_credentials
+             SET password_hash = ?, token_version = token_version + 1,
+                 updated_at = CURRENT_TIMESTAMP
+           WHERE id = 1
+       RETURNING token_version",
+    )
+    .bind(hash)
+    .fetch_one(pool)
+    .await
+}
+
+/// The JWT signing secret kept in the file at `path`, generated on first
+/// use. The file is readable by its owner only and, unlike the database,
+/// never ends up in a backup.
+///
+/// # Errors
+/// If the file can't be read or written.
+pub fn stored_jwt_secret(path: &std::path::Path) -> std::io::Result<String> {
+    use rand::Rng;
+    match std::fs::read_to_string(path) {
+        Ok(secret) if !secret.trim().is_empty() => return Ok(secret.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let generated: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect();
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(path)?, generated.as_bytes())?;
+    #[cfg(unix)]
+    std::fs::set_permissions(path, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    Ok(generated)
+}
+
 /// Authenticate with the configured password and return a JWT token.
 ///
 /// # Errors
@@ -41,28 +143,121 @@ pub async fn login(
     State(state): State<AppState>,
     Json(req): Json<LoginReq>,
 ) -> AppResult<Json<LoginResp>> {
-    let stored_hash = state
-        .config
-        .password_hash
-        .as_ref()
-        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
-
-    let parsed = PasswordHash::new(stored_hash).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    if Argon2::default()
-        .verify_password(req.password.as_bytes(), &parsed)
-        .is_err()
-    {
+    let (stored_hash, token_version) = credentials(&state).await?;
+    let stored_hash = stored_hash.ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    if !password_matches(&req.password, &stored_hash)? {
+        let event = Event::new(Entity::Auth, None, "login_failed", "Failed login attempt");
+        activity::record(&state, Actor::User, event).await;
         return Err(StatusCode::UNAUTHORIZED.into());
     }
 
-    let exp = now_ts() + 7 * 365 * 24 * 3600; // 7 years
-    let token = encode(
-        &Header::new(Algorithm::HS256),
-        &Claims { sub: 1, exp },
-        &state.jwt_encoding,
+    let resp = issue_token(&state, token_version)?;
+    activity::record(
+        &state,
+        Actor::User,
+        Event::new(Entity::Auth, None, "login", "Logged in"),
     )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await;
+    Ok(Json(resp))
+}
+
+/// A fresh token valid for the configured TTL.
+fn issue_token(state: &AppState, token_version: i64) -> AppResult<LoginResp> {
+    let iat = now_ts();
+    let exp = iat + state.config.jwt_ttl_hours.saturating_mul(3600);
+    let claims = Claims {
+        sub: 1,
+        exp,
+        iat,
+        jti: Some(uuid::Uuid::new_v4().to_string()),
+        tv: token_version,
+    };
+    let token = encode(&Header::new(Algorithm::HS256), &claims, &state.jwt_encoding)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(LoginResp {
+        token,
+        expires_at: exp,
+    })
+}
+
+/// Exchange the bearer token for a new one with a fresh expiry. Tokens that
+/// expired less than [`REFRESH_GRACE_SECS`] ago are still accepted.
+///
+/// # Errors
+/// 401 if the token is missing, invalid, revoked or expired for longer than
+/// the grace window.
+pub async fn refresh(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<LoginResp>> {
+    let token = bearer_token(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let claims = verify_jwt(&state, token, REFRESH_GRACE_SECS).await?;
+    Ok(Json(issue_token(&state, claims.tv)?))
+}
+
+/// Revoke the bearer token so it's rejected from now on, even by
+/// `/auth/refresh`.
+///
+/// # Errors
+/// 401 if the token is missing or no longer valid; 400 for tokens issued
+/// without a `jti`, which can't be revoked.
+pub async fn logout(State(state): State<AppState>, headers: HeaderMap) -> AppResult<StatusCode> {
+    let token = bearer_token(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let claims = verify_jwt(&state, token, REFRESH_GRACE_SECS).await?;
+    let jti = claims.jti.ok_or_else(|| {
+        AppError::Msg(
+            StatusCode::BAD_REQUEST,
+            "token predates logout support; it expires on its own".into(),
+        )
+    })?;
+
+    sqlx::query(r"INSERT OR IGNORE INTO revoked_tokens (jti, expires_at) VALUES (?, ?)")
+        .bind(&jti)
+        .bind(i64::try_from(claims.exp).unwrap_or(i64::MAX))
+        .execute(&state.pool)
+        .await?;
+    // Rows are only needed while the token could still be refreshed.
+    let cutoff = now_ts().saturating_sub(REFRESH_GRACE_SECS + state.config.jwt_leeway_secs);
+    sqlx::query(r"DELETE FROM revoked_tokens WHERE expires_at < ?")
+        .bind(i64::try_from(cutoff).unwrap_or(i64::MAX))
+        .execute(&state.pool)
+        .await?;
 
-    Ok(Json(LoginResp { token }))
+    activity::record(
+        &state,
+        Actor::User,
+        Event::new(Entity::Auth, None, "logout", "Logged out"),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace the login password. All existing login tokens stop working; the
+/// response carries a new one for the caller.
+///
+/// # Errors
+/// 401 if `current_password` is wrong, 400 if the new one is too short,
+/// 503 if no password is configured at all.
+pub async fn change_password(
+    State(state): State<AppState>,
+    Json(req): Json<ChangePasswordReq>,
+) -> AppResult<Json<LoginResp>> {
+    let (stored_hash, _) = credentials(&state).await?;
+    let stored_hash = stored_hash.ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    if !password_matches(&req.current_password, &stored_hash)? {
+        return Err(StatusCode::UNAUTHORIZED.into());
+    }
+
+    let hash = hash_new_password(&req.new_password)
+        .map_err(|e| AppError::Msg(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let token_version = set_password_hash(&state.pool, &hash).await?;
+
+    activity::record(
+        &state,
+        Actor::User,
+        Event::new(Entity::Auth, None, "password_changed", "Changed password"),
+    )
+    .await;
+    Ok(Json(issue_token(&state, token_version)?))
 }
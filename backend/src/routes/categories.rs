@@ -1,13 +1,16 @@
-use axum::{
-    Json,
-    extract::{Path, State},
-};
+use axum::extract::{Path, Query, State};
 use reqwest::StatusCode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    categories::{Classification, classify as classify_name, normalize_keyword},
     error::AppResult,
-    models::{AppState, NewCategory, ReorderCategories, ShoppingCategory, UpdateCategory},
+    extract::Json,
+    models::{
+        AppState, NewCategory, NewCategoryKeyword, ReorderCategories, ShoppingCategory,
+        UpdateCategory,
+    },
+    shopping_events::Change,
 };
 
 /// GET /categories
@@ -65,6 +68,7 @@ pub async fn create(
             return Err(e.into());
         }
     }
+    state.categories.invalidate().await;
 
     // Fetch the created category
     let row: ShoppingCategory = sqlx::query_as(
@@ -112,6 +116,14 @@ pub async fn update(
             )
                 .into());
         }
+        // Deleted categories hand their items to "Other" by name.
+        if old_name == "Other" && name != &old_name {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Cannot rename the 'Other' category".to_string(),
+            )
+                .into());
+        }
         updates.push("name = ?");
         binds.push(name.clone());
     }
@@ -136,7 +148,9 @@ pub async fn update(
     }
     query = query.bind(id);
 
-    let result = query.execute(&state.pool).await;
+    // The rename and its cascade to shopping items land together.
+    let mut tx = state.pool.begin().await?;
+    let result = query.execute(&mut *tx).await;
 
     match result {
         Ok(_) => {}
@@ -155,14 +169,22 @@ pub async fn update(
     }
 
     // If name changed, update all shopping_items using the old category name
-    if let Some(ref name) = new_name
-        && name != &old_name
-    {
-        sqlx::query(r"UPDATE shopping_items SET category = ? WHERE category = ?")
-            .bind(name)
-            .bind(&old_name)
-            .execute(&state.pool)
-            .await?;
+    let renamed = match new_name {
+        Some(ref name) if name != &old_name => {
+            sqlx::query(r"UPDATE shopping_items SET category = ? WHERE category = ?")
+                .bind(name)
+                .bind(&old_name)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+        }
+        _ => 0,
+    };
+    tx.commit().await?;
+
+    state.categories.invalidate().await;
+    if renamed > 0 {
+        state.shopping_events.publish(Change::Refresh);
     }
 
     // Fetch updated
@@ -179,12 +201,13 @@ pub async fn update(
 #[derive(Serialize)]
 pub struct DeleteResponse {
     pub deleted: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub items_using: Option<i64>,
+    /// Shopping items moved to "Other".
+    pub moved_items: u64,
 }
 
 /// DELETE /categories/{id}
-/// Delete a category. Fails if "Other" category or if items are using it.
+/// Delete a category and move its shopping items to "Other", which cannot be
+/// deleted.
 pub async fn delete(
     State(state): State<AppState>,
     Path(id): Path<i64>,
@@ -210,29 +233,27 @@ pub async fn delete(
             .into());
     }
 
-    // Check if any shopping items use this category
-    let count: i64 = sqlx::query_scalar(r"SELECT COUNT(*) FROM shopping_items WHERE category = ?")
-        .bind(&existing.name)
-        .fetch_one(&state.pool)
-        .await?;
-
-    if count > 0 {
-        return Err((
-            StatusCode::CONFLICT,
-            format!("{count} item(s) are using this category. Reassign them first."),
-        )
-            .into());
-    }
-
-    // Delete the category
+    let mut tx = state.pool.begin().await?;
+    let moved_items =
+        sqlx::query(r"UPDATE shopping_items SET category = 'Other' WHERE category = ?")
+            .bind(&existing.name)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
     sqlx::query(r"DELETE FROM shopping_categories WHERE id = ?")
         .bind(id)
-        .execute(&state.pool)
+        .execute(&mut *tx)
         .await?;
+    tx.commit().await?;
+
+    state.categories.invalidate().await;
+    if moved_items > 0 {
+        state.shopping_events.publish(Change::Refresh);
+    }
 
     Ok(Json(DeleteResponse {
         deleted: true,
-        items_using: None,
+        moved_items,
     }))
 }
 
@@ -252,6 +273,7 @@ pub async fn reorder(
             .execute(&state.pool)
             .await?;
     }
+    state.categories.invalidate().await;
 
     // Return updated list
     let rows: Vec<ShoppingCategory> = sqlx::query_as(
@@ -262,3 +284,105 @@ pub async fn reorder(
 
     Ok(Json(rows))
 }
+
+#[derive(Deserialize)]
+pub struct ClassifyQuery {
+    pub name: String,
+}
+
+/// GET /categories/classify?name=...
+/// Classify an item name without creating a shopping item.
+pub async fn classify(
+    State(state): State<AppState>,
+    Query(q): Query<ClassifyQuery>,
+) -> AppResult<Json<Classification>> {
+    if q.name.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "name cannot be empty".to_string()).into());
+    }
+    Ok(Json(classify_name(&state, &q.name).await))
+}
+
+async fn category_id_by_name(state: &AppState, name: &str) -> AppResult<i64> {
+    sqlx::query_scalar(r"SELECT id FROM shopping_categories WHERE name = ?")
+        .bind(name)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into())
+}
+
+async fn keywords_for(state: &AppState, category_id: i64) -> AppResult<Vec<String>> {
+    Ok(sqlx::query_scalar(
+        r"SELECT keyword FROM category_keywords WHERE category_id = ? ORDER BY keyword",
+    )
+    .bind(category_id)
+    .fetch_all(&state.pool)
+    .await?)
+}
+
+/// GET /categories/{name}/keywords
+/// List the user keywords that classify items into this category.
+pub async fn list_keywords(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> AppResult<Json<Vec<String>>> {
+    let id = category_id_by_name(&state, &name).await?;
+    Ok(Json(keywords_for(&state, id).await?))
+}
+
+/// POST /categories/{name}/keywords
+/// Add a keyword to this category. A keyword belongs to one category only.
+pub async fn add_keyword(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(req): Json<NewCategoryKeyword>,
+) -> AppResult<Json<Vec<String>>> {
+    let id = category_id_by_name(&state, &name).await?;
+    let keyword = normalize_keyword(&req.keyword);
+    if keyword.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Keyword cannot be empty".to_string(),
+        )
+            .into());
+    }
+
+    let result = sqlx::query(r"INSERT INTO category_keywords (category_id, keyword) VALUES (?, ?)")
+        .bind(id)
+        .bind(&keyword)
+        .execute(&state.pool)
+        .await;
+
+    if let Err(e) = result {
+        if let sqlx::Error::Database(db) = &e
+            && db.is_unique_violation()
+        {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Keyword '{keyword}' is already assigned"),
+            )
+                .into());
+        }
+        return Err(e.into());
+    }
+
+    Ok(Json(keywords_for(&state, id).await?))
+}
+
+/// DELETE /categories/{name}/keywords/{keyword}
+/// Remove a keyword from this category.
+pub async fn delete_keyword(
+    State(state): State<AppState>,
+    Path((name, keyword)): Path<(String, String)>,
+) -> AppResult<Json<Vec<String>>> {
+    let id = category_id_by_name(&state, &name).await?;
+    let res = sqlx::query(r"DELETE FROM category_keywords WHERE category_id = ? AND keyword = ?")
+        .bind(id)
+        .bind(normalize_keyword(&keyword))
+        .execute(&state.pool)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    Ok(Json(keywords_for(&state, id).await?))
+}
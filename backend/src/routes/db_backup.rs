@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+use crate::db_backup::{self, Snapshot};
+use crate::error::{AppError, AppResult};
+use crate::extract::Json;
+use crate::models::AppState;
+use crate::routes::settings::get_setting;
+
+#[derive(Serialize)]
+pub struct BackupResponse {
+    #[serde(flatten)]
+    pub snapshot: Snapshot,
+    /// Older snapshots removed by the `backup_retention` setting.
+    pub pruned: Vec<String>,
+}
+
+/// POST /admin/backup
+///
+/// Snapshot the database into the backup dir, then prune old snapshots.
+///
+/// # Errors
+/// Err if the snapshot can't be written or the backup dir can't be read.
+pub async fn create(State(state): State<AppState>) -> AppResult<Json<BackupResponse>> {
+    let snapshot = db_backup::create(&state.pool, &state.config.backup_dir).await?;
+    let keep = get_setting(&state.pool, "backup_retention")
+        .await
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(db_backup::DEFAULT_RETENTION);
+    let pruned = db_backup::prune(&state.config.backup_dir, keep).await?;
+    tracing::info!(name = %snapshot.name, bytes = snapshot.size_bytes, "database backed up");
+    Ok(Json(BackupResponse { snapshot, pruned }))
+}
+
+/// GET /admin/backups
+///
+/// Snapshots in the backup dir, newest first.
+///
+/// # Errors
+/// Err if the backup dir can't be read.
+pub async fn list(State(state): State<AppState>) -> AppResult<Json<Vec<Snapshot>>> {
+    Ok(Json(db_backup::list(&state.config.backup_dir).await?))
+}
+
+/// GET /admin/backup/latest
+///
+/// Download the newest snapshot.
+///
+/// # Errors
+/// 404 `no_backup` when there is none; Err if the backup dir can't be read.
+pub async fn latest(State(state): State<AppState>, request: Request) -> AppResult<Response> {
+    let Some(snapshot) = db_backup::list(&state.config.backup_dir)
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Err(AppError::Code(
+            StatusCode::NOT_FOUND,
+            "no_backup",
+            "no backup has been made yet".into(),
+        ));
+    };
+    let mime: mime_guess::mime::Mime = "application/vnd.sqlite3".parse().expect("valid mime type");
+    let mut res = ServeFile::new_with_mime(&snapshot.path, &mime)
+        .oneshot(request)
+        .await
+        .map_err(anyhow::Error::from)?
+        .into_response();
+    if let Ok(v) = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", snapshot.name)) {
+        res.headers_mut().insert(header::CONTENT_DISPOSITION, v);
+    }
+    Ok(res)
+}
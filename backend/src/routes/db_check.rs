@@ -0,0 +1,43 @@
+use axum::extract::State;
+
+use crate::activity::{self, Actor, Entity, Event};
+use crate::db_check::{self, CheckReport, RepairReport};
+use crate::error::AppResult;
+use crate::extract::Json;
+use crate::models::AppState;
+
+/// GET /admin/db/check
+///
+/// Report integrity problems, recipe JSON columns that don't decode and
+/// orphaned meal-plan entries. Changes nothing.
+///
+/// # Errors
+/// Err if a query fails.
+pub async fn check(State(state): State<AppState>) -> AppResult<Json<CheckReport>> {
+    Ok(Json(db_check::check(&state.pool).await?))
+}
+
+/// POST /admin/db/repair
+///
+/// Rewrite unreadable ingredient lists from their lines and delete orphaned
+/// meal-plan entries.
+///
+/// # Errors
+/// Err if a query fails; nothing is changed then.
+pub async fn repair(State(state): State<AppState>, actor: Actor) -> AppResult<Json<RepairReport>> {
+    let report = db_check::repair(&state.pool).await?;
+    for id in &report.ingredients_rewritten {
+        activity::record(
+            &state,
+            actor,
+            Event::new(
+                Entity::Recipe,
+                Some(*id),
+                "repair",
+                "Rebuilt unreadable ingredients".to_string(),
+            ),
+        )
+        .await;
+    }
+    Ok(Json(report))
+}
@@ -0,0 +1,34 @@
+use axum::extract::State;
+use serde::Serialize;
+
+use crate::digest;
+use crate::error::AppResult;
+use crate::extract::Json;
+use crate::models::AppState;
+
+#[derive(Serialize)]
+pub struct SentDigest {
+    /// The composed digest body.
+    pub text: String,
+    /// `false` when no ntfy URL is configured; the text is still returned.
+    pub sent: bool,
+}
+
+/// POST /admin/digest/send-now
+///
+/// Compose the weekly digest for the coming week and send it right away.
+/// Does not count as the scheduled send.
+///
+/// # Errors
+/// Err if loading the data fails or ntfy rejects the message.
+pub async fn send_now(State(state): State<AppState>) -> AppResult<Json<SentDigest>> {
+    let data = digest::load(&state.pool, chrono::Local::now().date_naive()).await?;
+    let text = digest::compose(&data);
+    let sent = if digest::ntfy_target(&state).is_some() {
+        digest::send(&state, &text).await?;
+        true
+    } else {
+        false
+    };
+    Ok(Json(SentDigest { text, sent }))
+}
@@ -1,9 +1,11 @@
 use axum::extract::Multipart;
-use axum::{Json, extract::State, http::StatusCode};
+use axum::{extract::State, http::StatusCode};
 use base64::{Engine as _, engine::general_purpose::STANDARD as B64};
 use std::time::Duration;
 
+use crate::activity::Actor;
 use crate::error::AppResult;
+use crate::extract::Json;
 use crate::llm::{ImageChatRequest, LlmClient};
 use crate::models::{AppState, NewRecipe, Recipe};
 use crate::routes::settings::LlmSettings;
@@ -12,31 +14,17 @@ use crate::routes::{parse_recipe::ExtractRaw, recipes};
 const MAX_IMAGES: usize = 3;
 const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024; // 10 MB per image
 
-/// Import a recipe from up to 3 photos using the configured vision LLM.
-///
-/// Accepts a multipart form with:
-/// - `image` fields (repeat up to 3×)
-/// - Optional `model` field to override the vision model
-///
-/// # Errors
-///
-/// Returns an error if the API key is missing, the LLM call fails, or the
-/// multipart payload cannot be parsed.
-pub async fn import_from_images(
-    State(state): State<AppState>,
-    mut multipart: Multipart,
-) -> AppResult<Json<Recipe>> {
-    let token = state.config.llm_api_key.clone().unwrap_or_default();
-    if token.is_empty() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "LLM API key is not configured".into(),
-        )
-            .into());
-    }
+/// Photos (as `(mime, base64)`) and the model override from the form.
+struct Upload {
+    images: Vec<(String, String)>,
+    /// Raw bytes of the first photo, kept for the recipe image.
+    first_photo: Option<Vec<u8>>,
+    model_override: Option<String>,
+}
 
-    // Collect images and optional model override from the multipart body
+async fn read_upload(multipart: &mut Multipart) -> AppResult<Upload> {
     let mut images: Vec<(String, String)> = Vec::new(); // (mime, base64)
+    let mut first_photo: Option<Vec<u8>> = None;
     let mut model_override: Option<String> = None;
 
     while let Some(field) = multipart
@@ -83,25 +71,72 @@ pub async fn import_from_images(
         }
 
         images.push((mime, B64.encode(&bytes)));
+        if first_photo.is_none() {
+            first_photo = Some(bytes.to_vec());
+        }
+    }
+
+    Ok(Upload {
+        images,
+        first_photo,
+        model_override,
+    })
+}
+
+/// Use an uploaded photo as the recipe image. The recipe is imported either
+/// way; a photo that won't decode or can't be stored leaves it without one.
+async fn attach_photo(state: &AppState, recipe_id: i64, photo: Vec<u8>) {
+    if !state.media.is_available() {
+        return;
+    }
+    if let Err(e) = recipes::save_recipe_image(state, recipe_id, photo).await {
+        tracing::warn!("storing import photo failed for id {}: {}", recipe_id, e);
     }
+}
+
+/// Import a recipe from up to 3 photos using the configured vision LLM.
+/// The first photo becomes the recipe image.
+///
+/// Accepts a multipart form with:
+/// - `image` fields (repeat up to 3×)
+/// - Optional `model` field to override the vision model (must be allowed by
+///   the `allowed_models` setting)
+///
+/// # Errors
+///
+/// Returns an error if the API key is missing, the LLM call fails, or the
+/// multipart payload cannot be parsed.
+pub async fn import_from_images(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> AppResult<Json<Recipe>> {
+    // Fail fast (before reading the upload) when LLM features are unavailable
+    let llm = LlmClient::from_config(&state.config, String::new())?
+        .tracked(&state.pool, "recipes.import_images");
+
+    let Upload {
+        images,
+        first_photo,
+        model_override,
+    } = read_upload(&mut multipart).await?;
 
     if images.is_empty() {
         return Err((StatusCode::BAD_REQUEST, "no images provided".into()).into());
     }
 
     // Load LLM settings from database
-    let llm_settings = LlmSettings::load(&state.pool).await;
+    let llm_settings = LlmSettings::load(&state).await;
+    llm_settings.check_override(model_override.as_deref())?;
     let model = model_override
         .as_deref()
         .unwrap_or(&llm_settings.vision_model);
-    let base = state.config.llm_api_url.as_str();
     let system = state.config.system_prompt_import.as_str();
     let prompt = "Extract the recipe from the image(s). \
                   If multiple images are provided they show different parts of the same recipe. \
                   Return the combined recipe as JSON.";
 
-    let http = reqwest::Client::new();
-    let llm = LlmClient::new(base.to_string(), token, model.to_string());
+    let http = state.http.clone();
+    let llm = llm.with_model(model.to_string());
 
     let llm_json = llm
         .chat_json_images_with_fallback(
@@ -112,7 +147,7 @@ pub async fn import_from_images(
                 text_prompt: prompt,
                 images: &images,
                 temperature: 0.1,
-                timeout: Duration::from_secs(120),
+                timeout: Duration::from_mins(2),
                 max_tokens: Some(5000),
             },
         )
@@ -124,19 +159,32 @@ pub async fn import_from_images(
         .title
         .clone()
         .unwrap_or_else(|| "Imported recipe".to_string());
+    let r#yield = raw.r#yield.clone().unwrap_or_default();
+    let notes = raw.notes.clone().unwrap_or_default();
     let norm = raw.normalize();
 
     let payload = NewRecipe {
         title,
         source: String::new(),
-        r#yield: String::new(),
-        notes: String::new(),
+        r#yield,
+        notes,
         ingredients: norm.ingredients,
         instructions: norm.instructions,
+        equipment: Vec::new(),
+        allow_duplicate: false,
     };
 
-    let created = recipes::create(State(state.clone()), Json(payload)).await?;
+    let created = recipes::create(State(state.clone()), Actor::Import, Json(payload)).await?;
     let recipe_id = created.0.id;
-    let fresh = recipes::get(State(state), axum::extract::Path(recipe_id)).await?;
+    if let Some(photo) = first_photo {
+        attach_photo(&state, recipe_id, photo).await;
+    }
+
+    let fresh = recipes::get(
+        State(state),
+        axum::extract::Path(recipe_id),
+        axum::extract::Query(recipes::GetQuery::default()),
+    )
+    .await?;
     Ok(fresh)
 }
@@ -1,10 +1,25 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
 use base64::Engine;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::Row;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc;
 
-use crate::models::{AppState, Ingredient};
+use crate::activity::{self, Actor, Entity};
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::models::{AppState, Ingredient, NewRecipe};
+use crate::safe_fetch;
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -25,6 +40,8 @@ struct JsonLdRecipe {
     notes: Option<String>,
     #[serde(default, deserialize_with = "string_or_array")]
     image: Option<String>,
+    #[serde(default)]
+    tool: Option<Value>,
 }
 
 fn string_or_array<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
@@ -60,58 +77,420 @@ where
     })
 }
 
+/// Why a single export entry could not be imported. Codes are stable API.
+#[derive(Debug)]
+enum RecipeImportError {
+    /// The entry is not a recipe object of the expected shape.
+    InvalidRecipe(String),
+    /// `recipeInstructions` is present but holds no usable steps.
+    InvalidInstructions(String),
+    /// A local `recipeImage/...` file referenced by the entry does not exist.
+    ImageNotFound(String),
+    /// An inline data-URI image could not be decoded.
+    InvalidImage(String),
+    Database(String),
+}
+
+impl RecipeImportError {
+    const fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidRecipe(_) => "invalid_recipe",
+            Self::InvalidInstructions(_) => "invalid_instructions",
+            Self::ImageNotFound(_) => "image_not_found",
+            Self::InvalidImage(_) => "invalid_image",
+            Self::Database(_) => "database_error",
+        }
+    }
+
+    fn detail(self) -> String {
+        match self {
+            Self::InvalidRecipe(d)
+            | Self::InvalidInstructions(d)
+            | Self::ImageNotFound(d)
+            | Self::InvalidImage(d)
+            | Self::Database(d) => d,
+        }
+    }
+}
+
+impl From<sqlx::Error> for RecipeImportError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Database(e.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    /// Validate every entry and report what would happen, without writing.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct ImportFailure {
+    index: usize,
+    title: String,
+    error_code: &'static str,
+    detail: String,
+}
+
+#[derive(Serialize)]
+struct ImportSkip {
+    index: usize,
+    title: String,
+    /// `duplicate_source` or `duplicate_content`.
+    reason: &'static str,
+}
+
+/// The recipe was imported, but its image could not be attached.
 #[derive(Serialize)]
+struct ImageFailure {
+    index: usize,
+    title: String,
+    recipe_id: i64,
+    detail: String,
+}
+
+#[derive(Serialize, Default)]
 struct ImportResponse {
     imported_count: usize,
-    failed: Vec<String>,
-}
-
-pub async fn import_recipesage(State(state): State<AppState>, body: String) -> impl IntoResponse {
-    // Parse the JSON manually to avoid exposing private types in the signature
-    let recipes: Vec<JsonLdRecipe> = match serde_json::from_str(&body) {
-        Ok(r) => r,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ImportResponse {
-                    imported_count: 0,
-                    failed: vec![format!("Invalid JSON: {e}")],
-                }),
-            );
-        }
+    /// Only set for dry runs: entries that would have been imported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    would_import: Option<usize>,
+    failed: Vec<ImportFailure>,
+    skipped: Vec<ImportSkip>,
+    image_failed: Vec<ImageFailure>,
+}
+
+/// Images are fetched and encoded this many at a time, after all recipe rows
+/// have been written.
+const IMAGE_CONCURRENCY: usize = 4;
+
+/// Upper bound for fetching and storing one image, so a single slow site
+/// cannot hold up the whole import.
+const IMAGE_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Progress events sent by `POST /recipes/import/recipesage/stream`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ImportProgress {
+    /// An entry was validated and inserted, skipped, or rejected.
+    Recipe {
+        index: usize,
+        total: usize,
+        title: String,
+        /// `imported`, `skipped` or `failed`.
+        status: &'static str,
+    },
+    /// An image finished (or gave up) for an imported recipe.
+    Image {
+        index: usize,
+        recipe_id: i64,
+        ok: bool,
+    },
+}
+
+/// An image still to be attached to an inserted recipe.
+struct ImageJob {
+    index: usize,
+    title: String,
+    recipe_id: i64,
+    image: ImageSource,
+}
+
+/// Image attached to an entry: fetched from the source page, or local to the export.
+enum ImageSource {
+    SourcePage(String),
+    Bytes(Vec<u8>),
+    File(std::path::PathBuf),
+}
+
+/// An entry that passed validation and is ready to insert.
+struct PreparedRecipe {
+    recipe: NewRecipe,
+    content_hash: String,
+    image: Option<ImageSource>,
+}
+
+/// Sources and content hashes already claimed earlier in the same upload, so
+/// repeated entries are reported even when nothing has been written yet.
+#[derive(Default)]
+struct SeenKeys {
+    sources: HashSet<String>,
+    hashes: HashSet<String>,
+}
+
+fn parse_entries(body: &str) -> Result<Vec<Value>, AppError> {
+    serde_json::from_str(body).map_err(|e| {
+        AppError::Code(
+            StatusCode::BAD_REQUEST,
+            "invalid_json",
+            format!("Invalid JSON: {e}"),
+        )
+    })
+}
+
+/// Import a `RecipeSage` JSON-LD export. Only a non-array body is a 400;
+/// per-recipe problems are reported in the response. `?dry_run=true` runs all
+/// validation and duplicate checks without writing anything.
+pub async fn import_recipesage(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Response {
+    let entries = match parse_entries(&body) {
+        Ok(entries) => entries,
+        Err(e) => return e.into_response(),
     };
 
-    tracing::info!("Starting RecipeSage import of {} recipes", recipes.len());
+    let report = run_import(&state, entries, query.dry_run, None).await;
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+/// Same as [`import_recipesage`], reported as server-sent events: a
+/// `progress` event per recipe and per image, then a `done` event carrying
+/// the full report. The import runs detached, so a dropped connection (e.g. a
+/// proxy timeout) does not stop it half-way.
+pub async fn import_recipesage_stream(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Response {
+    let entries = match parse_entries(&body) {
+        Ok(entries) => entries,
+        Err(e) => return e.into_response(),
+    };
 
-    let mut imported_count = 0;
-    let mut failed = Vec::new();
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let report = run_import(&state, entries, query.dry_run, Some(&tx)).await;
+        let done = Event::default().event("done").json_data(&report);
+        let _ = tx.send(done);
+    });
+
+    let events = futures_util::stream::unfold(rx, |mut rx| async move {
+        let event = rx.recv().await?;
+        Some((event, rx))
+    });
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+type EventSender = mpsc::UnboundedSender<Result<Event, axum::Error>>;
+
+fn send_progress(tx: Option<&EventSender>, progress: &ImportProgress) {
+    if let Some(tx) = tx {
+        // The receiver is gone once the client disconnects; keep importing.
+        let _ = tx.send(Event::default().event("progress").json_data(progress));
+    }
+}
 
-    for recipe in recipes {
-        match import_single_recipe(&state, recipe).await {
-            Ok(()) => imported_count += 1,
+/// Insert every entry, then attach images with bounded concurrency.
+async fn run_import(
+    state: &AppState,
+    entries: Vec<Value>,
+    dry_run: bool,
+    progress: Option<&EventSender>,
+) -> ImportResponse {
+    let total = entries.len();
+    tracing::info!("Starting RecipeSage import of {total} recipes (dry_run={dry_run})");
+
+    let mut report = ImportResponse::default();
+    let mut seen = SeenKeys::default();
+    let mut images = Vec::new();
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let title = entry_title(&entry);
+        let status = match import_single_recipe(state, entry, &mut seen, dry_run).await {
+            Ok(ImportOutcome::Imported(pending)) => {
+                report.imported_count += 1;
+                if let Some((recipe_id, image)) = pending {
+                    images.push(ImageJob {
+                        index,
+                        title: title.clone(),
+                        recipe_id,
+                        image,
+                    });
+                }
+                "imported"
+            }
+            Ok(ImportOutcome::Skipped(reason)) => {
+                tracing::info!("  Skipping duplicate recipe: {}", title);
+                report.skipped.push(ImportSkip {
+                    index,
+                    title: title.clone(),
+                    reason,
+                });
+                "skipped"
+            }
             Err(e) => {
-                tracing::error!("Import failed: {}", e);
-                failed.push(e);
+                tracing::error!("Import of '{}' failed: {:?}", title, e);
+                report.failed.push(ImportFailure {
+                    index,
+                    title: title.clone(),
+                    error_code: e.code(),
+                    detail: e.detail(),
+                });
+                "failed"
             }
+        };
+        send_progress(
+            progress,
+            &ImportProgress::Recipe {
+                index,
+                total,
+                title,
+                status,
+            },
+        );
+    }
+
+    let mut attached = futures_util::stream::iter(images)
+        .map(|job| async move {
+            let result =
+                tokio::time::timeout(IMAGE_TIMEOUT, attach_image(state, job.recipe_id, job.image))
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("timed out after {IMAGE_TIMEOUT:?}")));
+            (job.index, job.title, job.recipe_id, result)
+        })
+        .buffer_unordered(IMAGE_CONCURRENCY);
+
+    while let Some((index, title, recipe_id, result)) = attached.next().await {
+        if let Err(e) = &result {
+            tracing::warn!(recipe_id, error = %e, "Failed to import image");
+            report.image_failed.push(ImageFailure {
+                index,
+                title,
+                recipe_id,
+                detail: format!("{e:#}"),
+            });
         }
+        send_progress(
+            progress,
+            &ImportProgress::Image {
+                index,
+                recipe_id,
+                ok: result.is_ok(),
+            },
+        );
+    }
+    report.image_failed.sort_by_key(|f| f.index);
+
+    if dry_run {
+        report.would_import = Some(report.imported_count);
+        report.imported_count = 0;
     }
 
     tracing::info!(
-        "RecipeSage import complete: {} succeeded, {} failed",
-        imported_count,
-        failed.len()
+        "RecipeSage import complete: {} succeeded, {} skipped, {} failed, {} without image",
+        report.would_import.unwrap_or(report.imported_count),
+        report.skipped.len(),
+        report.failed.len(),
+        report.image_failed.len()
     );
 
-    (
-        StatusCode::OK,
-        Json(ImportResponse {
-            imported_count,
-            failed,
-        }),
+    report
+}
+
+fn entry_title(entry: &Value) -> String {
+    let name = match entry.get("name") {
+        Some(Value::Array(arr)) => arr.iter().find_map(Value::as_str),
+        Some(v) => v.as_str(),
+        None => None,
+    };
+    name.unwrap_or("Untitled Recipe").to_string()
+}
+
+enum ImportOutcome {
+    /// Inserted, or (for dry runs) would have been. Carries the image still to
+    /// attach to the new row.
+    Imported(Option<(i64, ImageSource)>),
+    Skipped(&'static str),
+}
+
+async fn import_single_recipe(
+    state: &AppState,
+    entry: Value,
+    seen: &mut SeenKeys,
+    dry_run: bool,
+) -> Result<ImportOutcome, RecipeImportError> {
+    let prepared = prepare_recipe(entry)?;
+
+    if let Some(reason) = find_duplicate(state, &prepared, seen).await? {
+        return Ok(ImportOutcome::Skipped(reason));
+    }
+
+    if dry_run {
+        return Ok(ImportOutcome::Imported(None));
+    }
+
+    let PreparedRecipe {
+        recipe,
+        content_hash,
+        image,
+    } = prepared;
+    let title = recipe.title.clone();
+
+    let ingredients_json = serde_json::to_string(&recipe.ingredients)
+        .map_err(|e| RecipeImportError::InvalidRecipe(format!("ingredients: {e}")))?;
+    let instructions_json = serde_json::to_string(&recipe.instructions)
+        .map_err(|e| RecipeImportError::InvalidRecipe(format!("instructions: {e}")))?;
+    let equipment_json = serde_json::to_string(&crate::equipment::normalize(&recipe.equipment))
+        .map_err(|e| RecipeImportError::InvalidRecipe(format!("equipment: {e}")))?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO recipes (title, source, "yield", servings, notes, ingredients, instructions, equipment, content_hash)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(&recipe.title)
+    .bind(&recipe.source)
+    .bind(&recipe.r#yield)
+    .bind(crate::units::servings_from_yield(&recipe.r#yield))
+    .bind(&recipe.notes)
+    .bind(&ingredients_json)
+    .bind(&instructions_json)
+    .bind(&equipment_json)
+    .bind(&content_hash)
+    .fetch_one(&state.pool)
+    .await?;
+
+    let recipe_id: i64 = result.get("id");
+    tracing::info!("  Created recipe with ID: {}", recipe_id);
+    activity::record(
+        state,
+        Actor::Import,
+        activity::Event::new(
+            Entity::Recipe,
+            recipe_id,
+            "create",
+            format!("Imported '{title}' from RecipeSage"),
+        ),
     )
+    .await;
+
+    tracing::info!("✓ Successfully imported: {}", title);
+    Ok(ImportOutcome::Imported(image.map(|img| (recipe_id, img))))
 }
 
-async fn import_single_recipe(state: &AppState, recipe: JsonLdRecipe) -> Result<(), String> {
+async fn attach_image(state: &AppState, recipe_id: i64, image: ImageSource) -> anyhow::Result<()> {
+    match image {
+        ImageSource::SourcePage(url) => {
+            tracing::info!("  Fetching image from URL: {}", url);
+            import_image_from_url(state, recipe_id, &url).await
+        }
+        local => import_recipe_image(state, recipe_id, local).await,
+    }
+}
+
+/// Parse and validate one entry without touching the database.
+fn prepare_recipe(entry: Value) -> Result<PreparedRecipe, RecipeImportError> {
+    let recipe: JsonLdRecipe = serde_json::from_value(entry)
+        .map_err(|e| RecipeImportError::InvalidRecipe(e.to_string()))?;
+
     let title = recipe
         .name
         .clone()
@@ -127,19 +506,13 @@ async fn import_single_recipe(state: &AppState, recipe: JsonLdRecipe) -> Result<
             section: None,
             quantity: None,
             unit: None,
-            name: ing_str.clone(),
+            name: crate::units::preclean_text(ing_str).trim().to_string(),
             prep: None,
             raw: true,
         })
         .collect();
 
-    let ingredients_json = serde_json::to_string(&ingredients)
-        .map_err(|e| format!("{title}: Failed to serialize ingredients: {e}"))?;
-
-    // Parse instructions from various formats
-    let instructions = parse_instructions(recipe.recipe_instructions);
-    let instructions_json = serde_json::to_string(&instructions)
-        .map_err(|e| format!("{title}: Failed to serialize instructions: {e}"))?;
+    let instructions = validate_instructions(recipe.recipe_instructions)?;
 
     // Use only the notes field from RecipeSage
     let notes = recipe.notes.unwrap_or_default();
@@ -160,69 +533,88 @@ async fn import_single_recipe(state: &AppState, recipe: JsonLdRecipe) -> Result<
         })
         .unwrap_or_default();
 
-    // Skip if a recipe with the same source URL (or title, if no source) already exists.
-    let duplicate_exists = if source.is_empty() {
-        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM recipes WHERE title = ?)")
-            .bind(&title)
-            .fetch_one(&state.pool)
-            .await
-            .map_err(|e| format!("{title}: Database error: {e}"))?
+    // If there's a URL source, the image comes from the web; otherwise use the local image
+    let image = if source.starts_with("http://") || source.starts_with("https://") {
+        Some(ImageSource::SourcePage(source.clone()))
     } else {
-        sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM recipes WHERE source = ?)")
-            .bind(&source)
-            .fetch_one(&state.pool)
-            .await
-            .map_err(|e| format!("{title}: Database error: {e}"))?
+        recipe
+            .image
+            .as_deref()
+            .map(resolve_local_image)
+            .transpose()?
     };
 
-    if duplicate_exists {
-        tracing::info!("  Skipping duplicate recipe: {}", title);
-        return Ok(());
-    }
-
-    let result = sqlx::query(
-        r#"
-        INSERT INTO recipes (title, source, "yield", notes, ingredients, instructions)
-        VALUES (?, ?, ?, ?, ?, ?)
-        RETURNING id
-        "#,
-    )
-    .bind(&title)
-    .bind(&source)
-    .bind(&yield_str)
-    .bind(&notes)
-    .bind(&ingredients_json)
-    .bind(&instructions_json)
-    .fetch_one(&state.pool)
-    .await
-    .map_err(|e| format!("{title}: Database error: {e}"))?;
+    let recipe = NewRecipe {
+        title,
+        source,
+        r#yield: yield_str,
+        notes,
+        ingredients,
+        instructions,
+        equipment: crate::schema_org::extract_tools(recipe.tool.as_ref()),
+        allow_duplicate: false,
+    };
+    let content_hash = crate::routes::recipes::recipe_content_hash(&recipe);
 
-    let recipe_id: i64 = result.get("id");
-    tracing::info!("  Created recipe with ID: {}", recipe_id);
+    Ok(PreparedRecipe {
+        recipe,
+        content_hash,
+        image,
+    })
+}
 
-    // Import image - if there's a URL source, fetch from web; otherwise use local image
-    if !source.is_empty() && (source.starts_with("http://") || source.starts_with("https://")) {
-        // Fetch image from the source URL
-        tracing::info!("  Fetching image from URL: {}", source);
-        if let Err(e) = import_image_from_url(state, recipe_id, &source).await {
-            tracing::warn!(recipe_id, source, error = %e, "Failed to import image from URL");
-        } else {
-            tracing::info!("  ✓ Image imported from URL");
+/// Reject instructions that are present but yield no steps (e.g. a number or an
+/// object); a missing or empty list is fine.
+fn validate_instructions(raw: Option<Value>) -> Result<Vec<String>, RecipeImportError> {
+    let malformed = match &raw {
+        None | Some(Value::Null | Value::String(_)) => false,
+        Some(Value::Array(arr)) => !arr.is_empty() && parse_instructions(raw.clone()).is_empty(),
+        Some(other) => {
+            return Err(RecipeImportError::InvalidInstructions(format!(
+                "recipeInstructions must be a string or an array, got {other}"
+            )));
         }
-    } else if let Some(image_url) = recipe.image {
-        // Use local image from recipeImage directory
-        tracing::info!("  Using local image: {}", image_url);
-        if let Err(e) = import_recipe_image(state, recipe_id, &image_url).await {
-            tracing::warn!(recipe_id, image_url, error = %e, "Failed to import local image");
-        } else {
-            tracing::info!("  ✓ Local image imported");
+    };
+
+    if malformed {
+        return Err(RecipeImportError::InvalidInstructions(
+            "recipeInstructions contains no text steps".to_string(),
+        ));
+    }
+    Ok(parse_instructions(raw))
+}
+
+/// Skip entries whose source URL (or, without a source, whose content) was
+/// already imported — before, or earlier in this upload.
+async fn find_duplicate(
+    state: &AppState,
+    prepared: &PreparedRecipe,
+    seen: &mut SeenKeys,
+) -> Result<Option<&'static str>, RecipeImportError> {
+    let source = &prepared.recipe.source;
+
+    if !source.is_empty() {
+        let exists =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM recipes WHERE source = ?)")
+                .bind(source)
+                .fetch_one(&state.pool)
+                .await?;
+        if exists || !seen.sources.insert(source.clone()) {
+            return Ok(Some("duplicate_source"));
         }
-    } else {
-        tracing::info!("  No image available");
+        return Ok(None);
     }
 
-    tracing::info!("✓ Successfully imported: {}", title);
-    Ok(())
+    let exists = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM recipes WHERE content_hash = ?)",
+    )
+    .bind(&prepared.content_hash)
+    .fetch_one(&state.pool)
+    .await?;
+    if exists || !seen.hashes.insert(prepared.content_hash.clone()) {
+        return Ok(Some("duplicate_content"));
+    }
+    Ok(None)
 }
 
 fn parse_instructions(instructions: Option<Value>) -> Vec<String> {
@@ -249,130 +641,82 @@ async fn import_image_from_url(
     source_url: &str,
 ) -> anyhow::Result<()> {
     // Fetch the page HTML to extract the image
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(source_url)
-        .timeout(std::time::Duration::from_secs(45))
-        .send()
-        .await?;
-
-    if !resp.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "HTTP {} fetching {}",
-            resp.status(),
-            source_url
-        ));
-    }
-
-    let html = resp.text().await?;
+    let allow_private = state.config.fetch_allow_private;
+    let page = safe_fetch::get_page(source_url, safe_fetch::MAX_HTML_BYTES, allow_private).await?;
+    let html = crate::html::decode_html(&page.body, page.content_type.as_deref());
 
     // Use the same image extraction logic as URL import
     if let Some(img_url) =
-        crate::routes::parse_recipe_image::extract_main_image_url(&html, source_url)
+        crate::routes::parse_recipe_image::extract_main_image_url(&html, page.url.as_str())
     {
-        let (rel_full, rel_small) = crate::routes::recipes::fetch_and_store_recipe_image(
-            &client, &img_url, state, recipe_id,
-        )
-        .await?;
-
-        sqlx::query(
-            r"
-            UPDATE recipes
-            SET image_path_small = ?,
-                image_path_full  = ?
-            WHERE id = ?
-            ",
-        )
-        .bind(&rel_small)
-        .bind(&rel_full)
-        .bind(recipe_id)
-        .execute(&state.pool)
-        .await?;
+        crate::routes::recipes::fetch_and_store_recipe_image(&img_url, state, recipe_id).await?;
     }
 
     Ok(())
 }
 
-async fn import_recipe_image(
-    state: &AppState,
-    recipe_id: i64,
-    image_url: &str,
-) -> anyhow::Result<()> {
-    let bytes = if let Some(data_uri) = image_url.strip_prefix("data:") {
+/// Decode a data URI or locate a file under the export's `recipeImage/` folder.
+fn resolve_local_image(image_url: &str) -> Result<ImageSource, RecipeImportError> {
+    if let Some(data_uri) = image_url.strip_prefix("data:") {
         // Handle base64-encoded data URI
         // Format: "data:image/png;base64,..."
         let parts: Vec<&str> = data_uri.split(',').collect();
         if parts.len() != 2 {
-            return Err(anyhow::anyhow!("Invalid data URI format"));
+            return Err(RecipeImportError::InvalidImage(
+                "Invalid data URI format".to_string(),
+            ));
         }
 
         // Decode base64
-        base64::engine::general_purpose::STANDARD
+        let bytes = base64::engine::general_purpose::STANDARD
             .decode(parts[1])
-            .map_err(|e| anyhow::anyhow!("Failed to decode base64: {e}"))?
-    } else {
-        // Handle file path (for local RecipeSage files)
-        let path = image_url
-            .strip_prefix("/api/")
-            .or_else(|| image_url.strip_prefix("api/"))
-            .unwrap_or(image_url);
-
-        let image_path = std::path::Path::new("recipeImage")
-            .join(path.strip_prefix("recipeImage/").unwrap_or(path));
-
-        if !image_path.exists() {
-            return Err(anyhow::anyhow!(
-                "Image file not found: {}",
-                image_path.display()
-            ));
-        }
-
-        tokio::fs::read(&image_path).await?
-    };
+            .map_err(|e| {
+                RecipeImportError::InvalidImage(format!("Failed to decode base64: {e}"))
+            })?;
+        return Ok(ImageSource::Bytes(bytes));
+    }
 
-    // Process and store using the existing image processing logic
-    store_recipe_image_bytes(state, recipe_id, bytes).await?;
+    // Handle file path (for local RecipeSage files)
+    let path = image_url
+        .strip_prefix("/api/")
+        .or_else(|| image_url.strip_prefix("api/"))
+        .unwrap_or(image_url);
+    let name = path.strip_prefix("recipeImage/").unwrap_or(path);
+
+    // Only plain names below `recipeImage/`: no absolute paths or `..`.
+    let rel = std::path::Path::new(name);
+    if name.is_empty()
+        || !rel
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(RecipeImportError::InvalidImage(
+            "Image path must be a file name under recipeImage/".to_string(),
+        ));
+    }
 
-    Ok(())
+    // Symlinks out of the folder count as missing; report only the name
+    // given in the export, never a resolved server path.
+    match crate::media_path::resolve(std::path::Path::new("recipeImage"), name) {
+        Ok(image_path) if image_path.is_file() => Ok(ImageSource::File(image_path)),
+        _ => Err(RecipeImportError::ImageNotFound(format!(
+            "Image file not found: {name}"
+        ))),
+    }
 }
 
-async fn store_recipe_image_bytes(
+async fn import_recipe_image(
     state: &AppState,
     recipe_id: i64,
-    bytes: Vec<u8>,
+    image: ImageSource,
 ) -> anyhow::Result<()> {
-    let (full_webp, thumb_webp) =
-        tokio::task::spawn_blocking(move || -> std::io::Result<(Vec<u8>, Vec<u8>)> {
-            let img = image::load_from_memory(&bytes)
-                .map_err(|e| std::io::Error::other(format!("decode error: {e}")))?;
-            crate::image_io::to_full_and_thumb_webp(&img)
-        })
-        .await??;
-
-    let rel_dir = format!("recipes/{recipe_id}");
-    let rel_full = format!("{rel_dir}/full.webp");
-    let rel_small = format!("{rel_dir}/small.webp");
-
-    let abs_dir = state.config.media_dir.join(&rel_dir);
-    tokio::fs::create_dir_all(&abs_dir).await?;
-    tokio::fs::write(abs_dir.join("full.webp"), &full_webp).await?;
-    tokio::fs::write(abs_dir.join("small.webp"), &thumb_webp).await?;
-
-    // Update the recipe with image paths
-    sqlx::query(
-        r"
-        UPDATE recipes
-        SET image_path_full = ?, image_path_small = ?
-        WHERE id = ?
-        ",
-    )
-    .bind(&rel_full)
-    .bind(&rel_small)
-    .bind(recipe_id)
-    .execute(&state.pool)
-    .await?;
+    let bytes = match image {
+        ImageSource::Bytes(bytes) => bytes,
+        ImageSource::File(path) => tokio::fs::read(&path).await?,
+        ImageSource::SourcePage(_) => anyhow::bail!("not a local image"),
+    };
 
-    Ok(())
+    crate::routes::recipes::save_recipe_image(state, recipe_id, bytes).await
 }
 
 #[cfg(test)]
@@ -380,6 +724,32 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    // ── resolve_local_image ──────────────────────────────────────────────────
+
+    #[test]
+    fn resolve_local_image_rejects_paths_outside_the_export() {
+        for url in [
+            "/etc/passwd",
+            "recipeImage//etc/passwd",
+            "recipeImage/../Cargo.toml",
+            "../../etc/hostname",
+            "api/recipeImage/a/../../b.jpg",
+            "",
+        ] {
+            let Err(RecipeImportError::InvalidImage(detail)) = resolve_local_image(url) else {
+                panic!("{url:?} accepted");
+            };
+            assert!(!detail.contains("etc"), "{detail}");
+        }
+
+        let Err(RecipeImportError::ImageNotFound(detail)) =
+            resolve_local_image("recipeImage/missing.jpg")
+        else {
+            panic!("missing image accepted");
+        };
+        assert_eq!(detail, "Image file not found: missing.jpg");
+    }
+
     // ── parse_instructions ───────────────────────────────────────────────────
 
     #[test]
@@ -436,4 +806,24 @@ mod tests {
         let v = json!(null);
         assert_eq!(parse_instructions(Some(v)), Vec::<String>::new());
     }
+
+    // ── validate_instructions ────────────────────────────────────────────────
+
+    #[test]
+    fn validate_instructions_accepts_missing_and_empty() {
+        assert!(validate_instructions(None).unwrap().is_empty());
+        assert!(validate_instructions(Some(json!([]))).unwrap().is_empty());
+        assert_eq!(
+            validate_instructions(Some(json!(["Mix."]))).unwrap(),
+            vec!["Mix."]
+        );
+    }
+
+    #[test]
+    fn validate_instructions_rejects_malformed() {
+        for v in [json!(42), json!({"text": "Mix."}), json!([1, 2])] {
+            let err = validate_instructions(Some(v)).unwrap_err();
+            assert_eq!(err.code(), "invalid_instructions");
+        }
+    }
 }
@@ -0,0 +1,247 @@
+//! Ingredient name autocomplete from what recipes and the shopping list have
+//! used before.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::{
+    error::{AppError, AppResult},
+    extract::Json,
+    models::AppState,
+    units::normalize_name,
+};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+const MIN_QUERY_CHARS: usize = 2;
+
+/// How long an aggregation is reused. Edits show up after at most this long.
+const CACHE_TTL: Duration = Duration::from_mins(1);
+
+#[derive(Deserialize)]
+pub struct SuggestQuery {
+    #[serde(default)]
+    pub q: String,
+    /// Number of names to return (default 10, at most 50).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    /// Lowercased, whitespace-collapsed name.
+    pub name: String,
+    /// Uses across live recipes' ingredients and shopping items.
+    pub count: usize,
+    /// Unit most often used with the name; `None` when it never had one.
+    pub unit: Option<String>,
+}
+
+/// Every known name, most used first, and when it was computed.
+type Snapshot = (Instant, Arc<Vec<Suggestion>>);
+
+#[derive(Clone, Default)]
+pub struct SuggestCache {
+    entry: Arc<Mutex<Option<Snapshot>>>,
+}
+
+impl SuggestCache {
+    /// The aggregated names, recomputed when older than [`CACHE_TTL`].
+    async fn names(&self, pool: &SqlitePool) -> sqlx::Result<Arc<Vec<Suggestion>>> {
+        {
+            let entry = self.entry.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some((at, names)) = entry.as_ref()
+                && at.elapsed() < CACHE_TTL
+            {
+                return Ok(names.clone());
+            }
+        }
+        // Concurrent misses may both aggregate; the last one wins, harmlessly.
+        let names = Arc::new(aggregate(pool).await?);
+        *self.entry.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((Instant::now(), names.clone()));
+        Ok(names)
+    }
+}
+
+/// Count every ingredient name of live recipes and every shopping item name,
+/// sorted by count (descending), then name.
+async fn aggregate(pool: &SqlitePool) -> sqlx::Result<Vec<Suggestion>> {
+    // Unreadable ingredient columns (see `db_check`) are skipped rather than
+    // failing the whole query.
+    let rows: Vec<(Option<String>, Option<String>)> = sqlx::query_as(
+        r"
+        SELECT CAST(json_extract(je.value, '$.name') AS TEXT),
+               CAST(json_extract(je.value, '$.unit') AS TEXT)
+          FROM recipes r,
+               json_each(CASE WHEN json_valid(r.ingredients) THEN r.ingredients ELSE '[]' END) je
+         WHERE r.deleted_at IS NULL AND je.type = 'object'
+        UNION ALL
+        SELECT name, unit FROM shopping_items
+        ",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_name: HashMap<String, (usize, BTreeMap<String, usize>)> = HashMap::new();
+    for (name, unit) in rows {
+        let name = normalize_name(name.as_deref().unwrap_or_default());
+        if name.is_empty() {
+            continue;
+        }
+        let (count, units) = by_name.entry(name).or_default();
+        *count += 1;
+        if let Some(unit) = unit.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+            *units.entry(unit.to_string()).or_default() += 1;
+        }
+    }
+
+    let mut names: Vec<Suggestion> = by_name
+        .into_iter()
+        .map(|(name, (count, units))| Suggestion {
+            name,
+            count,
+            // Ties go to the alphabetically first unit.
+            unit: units
+                .into_iter()
+                .min_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)))
+                .map(|(unit, _)| unit),
+        })
+        .collect();
+    names.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    Ok(names)
+}
+
+/// Names starting with `q` first, then names containing it elsewhere, each
+/// group in `names` order. `q` must be normalized.
+fn matching(names: &[Suggestion], q: &str, limit: usize) -> Vec<Suggestion> {
+    let prefixed = names.iter().filter(|s| s.name.starts_with(q));
+    let inner = names
+        .iter()
+        .filter(|s| !s.name.starts_with(q) && s.name.contains(q));
+    prefixed.chain(inner).take(limit).cloned().collect()
+}
+
+/// GET /ingredients/suggest?q=flo&limit=10
+///
+/// # Errors
+///
+/// 400 `query_too_short` when `q` has fewer than two characters; Err if
+/// querying the db fails
+pub async fn suggest(
+    State(state): State<AppState>,
+    Query(query): Query<SuggestQuery>,
+) -> AppResult<Json<Vec<Suggestion>>> {
+    let q = normalize_name(&query.q);
+    if q.chars().count() < MIN_QUERY_CHARS {
+        return Err(AppError::Code(
+            StatusCode::BAD_REQUEST,
+            "query_too_short",
+            format!("q needs at least {MIN_QUERY_CHARS} characters"),
+        ));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let names = state.ingredient_suggest.names(&state.pool).await?;
+    Ok(Json(matching(&names, &q, limit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn seeded_pool(tmp: &tempfile::TempDir) -> SqlitePool {
+        let pool = crate::db::make_pool(tmp.path().join("db.sqlite").display().to_string())
+            .await
+            .unwrap();
+        for (ingredients, deleted) in [
+            (
+                r#"[{"name": "Flour", "unit": "g"}, {"name": "sugar", "unit": "g"}]"#,
+                false,
+            ),
+            (
+                r#"[{"name": "flour ", "unit": "cup"}, {"name": "Cauliflower"}]"#,
+                false,
+            ),
+            (
+                r#"[{"name": "flour", "unit": "g"}, {"name": "egg"}]"#,
+                false,
+            ),
+            (r#"[{"name": "flour tortillas"}]"#, true),
+            (r#""2 carrots""#, false),
+        ] {
+            sqlx::query(
+                "INSERT INTO recipes (title, ingredients, instructions, deleted_at) \
+                 VALUES ('r', ?, '[]', CASE WHEN ? THEN CURRENT_TIMESTAMP END)",
+            )
+            .bind(ingredients)
+            .bind(deleted)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        for (name, unit, key) in [
+            ("cauliflower", None, "|cauliflower"),
+            ("Sunflower oil", Some("ml"), "ml|sunflower oil"),
+        ] {
+            sqlx::query("INSERT INTO shopping_items (name, unit, key) VALUES (?, ?, ?)")
+                .bind(name)
+                .bind(unit)
+                .bind(key)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        pool
+    }
+
+    fn suggestion(name: &str, count: usize, unit: Option<&str>) -> Suggestion {
+        Suggestion {
+            name: name.to_string(),
+            count,
+            unit: unit.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn aggregate_counts_normalized_names_with_their_usual_unit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pool = seeded_pool(&tmp).await;
+
+        assert_eq!(
+            aggregate(&pool).await.unwrap(),
+            [
+                suggestion("flour", 3, Some("g")),
+                suggestion("cauliflower", 2, None),
+                suggestion("egg", 1, None),
+                suggestion("sugar", 1, Some("g")),
+                suggestion("sunflower oil", 1, Some("ml")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn matching_ranks_prefix_matches_before_substring_matches() {
+        let tmp = tempfile::tempdir().unwrap();
+        let names = aggregate(&seeded_pool(&tmp).await).await.unwrap();
+        let found = |q: &str, limit| -> Vec<String> {
+            matching(&names, q, limit)
+                .into_iter()
+                .map(|s| s.name)
+                .collect()
+        };
+
+        assert_eq!(found("flo", 10), ["flour", "cauliflower", "sunflower oil"]);
+        assert_eq!(found("flo", 2), ["flour", "cauliflower"]);
+        assert_eq!(found("su", 10), ["sugar", "sunflower oil"]);
+        assert!(found("xyz", 10).is_empty());
+    }
+}
@@ -1,9 +1,12 @@
-use axum::{Json, extract::State};
+use axum::extract::State;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 
 use crate::AppState;
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+use crate::extract::Json;
+use crate::llm::{LlmClient, LlmUnavailable};
+use crate::routes::llm_usage::{self, UsageTotals};
 
 #[derive(Serialize)]
 pub struct LlmCredits {
@@ -12,35 +15,32 @@ pub struct LlmCredits {
     /// Credit limit in USD, or null for pay-as-you-go / unlimited.
     pub limit: Option<f64>,
     pub is_free_tier: bool,
+    /// Tokens logged locally for every call Blaz made (see `/llm/usage`).
+    pub local_tokens: UsageTotals,
 }
 
 /// Fetch credit/usage info from the configured LLM provider.
 /// Only works with `OpenRouter` (`/auth/key` endpoint).
 ///
 /// # Errors
-/// Returns an error if the API key is not set, the request fails, or the
-/// provider does not expose a `/auth/key` endpoint.
+/// Returns an error if LLM features are disabled, the API key is not set, the
+/// request fails, or the provider does not expose a `/auth/key` endpoint.
 pub async fn get(State(state): State<AppState>) -> AppResult<Json<LlmCredits>> {
-    let Some(ref api_key) = state.config.llm_api_key else {
-        return Err(crate::error::AppError::Msg(
+    let llm = LlmClient::from_config(&state.config, String::new()).map_err(|e| match e {
+        LlmUnavailable::MissingApiKey => AppError::Msg(
             axum::http::StatusCode::BAD_REQUEST,
             "LLM API key is not configured".into(),
-        ));
-    };
-    if api_key.trim().is_empty() {
-        return Err(crate::error::AppError::Msg(
-            axum::http::StatusCode::BAD_REQUEST,
-            "LLM API key is not configured".into(),
-        ));
-    }
+        ),
+        LlmUnavailable::Offline => e.into(),
+    })?;
 
-    let base = state.config.llm_api_url.trim_end_matches('/');
+    let base = llm.base.trim_end_matches('/');
     let url = format!("{base}/auth/key");
 
     let client = reqwest::Client::new();
     let resp = client
         .get(&url)
-        .bearer_auth(api_key)
+        .bearer_auth(&llm.token)
         .send()
         .await
         .map_err(|e| anyhow::anyhow!("Credits request failed: {e}"))?;
@@ -69,5 +69,6 @@ pub async fn get(State(state): State<AppState>) -> AppResult<Json<LlmCredits>> {
         usage,
         limit,
         is_free_tier,
+        local_tokens: llm_usage::totals(&state.pool).await?,
     }))
 }
@@ -0,0 +1,79 @@
+use axum::extract::State;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::AppState;
+use crate::error::{AppError, AppResult};
+use crate::extract::Json;
+use crate::llm::{LlmClient, LlmUnavailable};
+use crate::routes::settings::LlmSettings;
+
+#[derive(Serialize)]
+pub struct LlmModel {
+    pub id: String,
+    pub name: String,
+    /// Whether import requests may pick this model as an override.
+    pub allowed: bool,
+}
+
+/// List the provider's models (`GET {llm_api_url}/models`), marking which
+/// ones the `allowed_models` setting permits as a per-request override.
+///
+/// # Errors
+/// Returns an error if LLM features are disabled, the API key is not set, or
+/// the provider request fails.
+pub async fn list(State(state): State<AppState>) -> AppResult<Json<Vec<LlmModel>>> {
+    let llm = LlmClient::from_config(&state.config, String::new()).map_err(|e| match e {
+        LlmUnavailable::MissingApiKey => AppError::Msg(
+            axum::http::StatusCode::BAD_REQUEST,
+            "LLM API key is not configured".into(),
+        ),
+        LlmUnavailable::Offline => e.into(),
+    })?;
+
+    let base = llm.base.trim_end_matches('/');
+    let url = format!("{base}/models");
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(&url)
+        .bearer_auth(&llm.token)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Models request failed: {e}"))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!("Models endpoint returned {status}: {body}").into());
+    }
+
+    let body: JsonValue = resp
+        .json()
+        .await
+        .map_err(|e| anyhow::anyhow!("Invalid models response: {e}"))?;
+
+    let settings = LlmSettings::load(&state).await;
+    let models = body
+        .get("data")
+        .and_then(JsonValue::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|m| {
+            let id = m.get("id")?.as_str()?.to_string();
+            let name = m
+                .get("name")
+                .and_then(JsonValue::as_str)
+                .unwrap_or(&id)
+                .to_string();
+            Some(LlmModel {
+                allowed: settings.is_model_allowed(&id),
+                id,
+                name,
+            })
+        })
+        .collect();
+
+    Ok(Json(models))
+}
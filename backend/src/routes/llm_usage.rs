@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::error::{AppError, AppResult};
+use crate::extract::Json;
+use crate::models::AppState;
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    pub from: Option<String>, // "YYYY-MM-DD", inclusive
+    pub to: Option<String>,   // "YYYY-MM-DD", inclusive
+}
+
+#[derive(Debug, Default, Serialize, sqlx::FromRow)]
+pub struct UsageTotals {
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct DayUsage {
+    pub day: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct RouteUsage {
+    pub route: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Serialize)]
+pub struct UsageReport {
+    pub totals: UsageTotals,
+    pub by_day: Vec<DayUsage>,
+    pub by_route: Vec<RouteUsage>,
+}
+
+const SUMS: &str = "COUNT(*) AS calls,
+        COALESCE(SUM(prompt_tokens), 0) AS prompt_tokens,
+        COALESCE(SUM(completion_tokens), 0) AS completion_tokens";
+
+/// Days are UTC, as `created_at` is.
+const IN_RANGE: &str = "(?1 IS NULL OR date(created_at) >= ?1)
+       AND (?2 IS NULL OR date(created_at) <= ?2)";
+
+fn parse_day(s: Option<&str>) -> AppResult<Option<String>> {
+    s.map(|s| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(|d| d.to_string())
+            .map_err(|_| AppError::from((StatusCode::BAD_REQUEST, format!("invalid date: {s}"))))
+    })
+    .transpose()
+}
+
+/// Token totals over every logged call.
+///
+/// # Errors
+/// Err if querying the database fails.
+pub async fn totals(pool: &SqlitePool) -> sqlx::Result<UsageTotals> {
+    sqlx::query_as(&format!("SELECT {SUMS} FROM llm_usage"))
+        .fetch_one(pool)
+        .await
+}
+
+/// GET /llm/usage?from=2025-03-01&to=2025-03-31
+///
+/// Locally logged token usage, summed per day and per feature. Both bounds
+/// are optional and inclusive.
+///
+/// # Errors
+/// 400 for a malformed date or `from` after `to`; Err if querying fails.
+pub async fn get(
+    State(state): State<AppState>,
+    Query(q): Query<UsageQuery>,
+) -> AppResult<Json<UsageReport>> {
+    let from = parse_day(q.from.as_deref())?;
+    let to = parse_day(q.to.as_deref())?;
+    if let (Some(from), Some(to)) = (&from, &to)
+        && from > to
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "from must not be after to".to_string(),
+        )
+            .into());
+    }
+
+    let totals: UsageTotals =
+        sqlx::query_as(&format!("SELECT {SUMS} FROM llm_usage WHERE {IN_RANGE}"))
+            .bind(&from)
+            .bind(&to)
+            .fetch_one(&state.pool)
+            .await?;
+    let by_day: Vec<DayUsage> = sqlx::query_as(&format!(
+        "SELECT date(created_at) AS day, {SUMS} FROM llm_usage
+         WHERE {IN_RANGE} GROUP BY day ORDER BY day"
+    ))
+    .bind(&from)
+    .bind(&to)
+    .fetch_all(&state.pool)
+    .await?;
+    let by_route: Vec<RouteUsage> = sqlx::query_as(&format!(
+        "SELECT route, {SUMS} FROM llm_usage
+         WHERE {IN_RANGE} GROUP BY route ORDER BY route"
+    ))
+    .bind(&from)
+    .bind(&to)
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(UsageReport {
+        totals,
+        by_day,
+        by_route,
+    }))
+}
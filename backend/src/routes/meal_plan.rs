@@ -1,60 +1,341 @@
-use axum::{
-    Json,
-    extract::{Path, Query, State},
-};
+use axum::extract::{Path, Query, State};
 use chrono::NaiveDate;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 
 use crate::{
-    error::AppResult,
-    models::{AppState, AssignRecipe, MealPlanEntry, PrepReminder},
+    activity::{self, Actor, Entity, Event},
+    error::{AppError, AppResult},
+    extract::Json,
+    models::{
+        AppState, AssignRecipe, MacroTotals, MealPlanEntry, MealPlanEntryDetailed, MealType,
+        PrepReminder, RecipeMacros,
+    },
 };
 
 #[derive(Deserialize)]
 pub struct DayQuery {
-    pub day: String, // "YYYY-MM-DD"
+    pub day: Option<String>, // "YYYY-MM-DD"
+    /// First day of a range, inclusive; needs `to`.
+    pub from: Option<String>,
+    /// Last day of a range, inclusive; needs `from`.
+    pub to: Option<String>,
+    /// Only entries for this meal.
+    pub meal_type: Option<MealType>,
+    /// Comma-separated extras; `recipe` embeds servings and macros per entry.
+    #[serde(default)]
+    pub include: Option<String>,
+}
+
+/// Lean entries by default; detailed ones when `include=recipe` is requested.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum DayEntries {
+    Lean(Vec<MealPlanEntry>),
+    Detailed(Vec<MealPlanEntryDetailed>),
+}
+
+/// Longest range `GET /meal-plan?from=&to=` serves, in days.
+const MAX_RANGE_DAYS: i64 = 62;
+
+/// The inclusive day range a query asks for: `day` alone, or `from`..=`to`.
+/// `day` is passed through unparsed as it always was.
+fn day_range(q: &DayQuery) -> AppResult<(String, String)> {
+    let bad = |msg: &str| AppError::from((StatusCode::BAD_REQUEST, msg.to_string()));
+    match (&q.day, &q.from, &q.to) {
+        (Some(day), None, None) => Ok((day.clone(), day.clone())),
+        (None, Some(from), Some(to)) => {
+            let parse = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+            let (Some(start), Some(end)) = (parse(from), parse(to)) else {
+                return Err(bad("from and to must be YYYY-MM-DD"));
+            };
+            if start > end {
+                return Err(bad("from must not be after to"));
+            }
+            if (end - start).num_days() >= MAX_RANGE_DAYS {
+                return Err(bad(&format!("range is limited to {MAX_RANGE_DAYS} days")));
+            }
+            Ok((start.to_string(), end.to_string()))
+        }
+        _ => Err(bad("give either day, or both from and to")),
+    }
 }
 
-/// GET /meal-plan?day=YYYY-MM-DD
-/// Get meal plan entries for a specific day.
+/// `GET /meal-plan?day=YYYY-MM-DD[&meal_type=lunch][&include=recipe]`
+/// `GET /meal-plan?from=YYYY-MM-DD&to=YYYY-MM-DD[&meal_type=lunch][&include=recipe]`
+/// Get meal plan entries for a day or an inclusive range of days, ordered by
+/// day then id.
 ///
 /// # Errors
-/// Returns an error if querying the meal plan entries for the given day fails.
+/// Returns an error if `include` names an unknown extra, if the range is
+/// missing, malformed, reversed or too long, or if querying the meal plan
+/// entries fails.
 pub async fn get_for_day(
     State(state): State<AppState>,
     Query(q): Query<DayQuery>,
-) -> AppResult<Json<Vec<MealPlanEntry>>> {
-    // Return entries for the day; join recipes to reflect latest title.
+) -> AppResult<Json<DayEntries>> {
+    let mut include_recipe = false;
+    for part in q.include.as_deref().unwrap_or_default().split(',') {
+        match part.trim() {
+            "" => {}
+            "recipe" => include_recipe = true,
+            other => {
+                return Err((StatusCode::BAD_REQUEST, format!("unknown include: {other}")).into());
+            }
+        }
+    }
+    let (from, to) = day_range(&q)?;
+
+    if include_recipe {
+        let rows = fetch_days_detailed(&state.pool, &from, &to, q.meal_type).await?;
+        return Ok(Json(DayEntries::Detailed(rows)));
+    }
+
+    // Return entries for the days; join recipes to reflect latest title.
     let rows: Vec<MealPlanEntry> = sqlx::query_as::<_, MealPlanEntry>(
         r"
         SELECT mp.id,
                mp.day,
+               mp.meal_type,
                mp.recipe_id,
                r.title AS title,
                r.image_path_small
           FROM meal_plan mp
           JOIN recipes r ON r.id = mp.recipe_id
-         WHERE mp.day = ?
-         ORDER BY mp.id
+         WHERE mp.day BETWEEN ? AND ?
+           AND (? IS NULL OR mp.meal_type = ?)
+         ORDER BY mp.day, mp.id
         ",
     )
-    .bind(&q.day)
+    .bind(&from)
+    .bind(&to)
+    .bind(q.meal_type)
+    .bind(q.meal_type)
     .fetch_all(&state.pool)
     .await?;
 
-    Ok(Json(rows))
+    Ok(Json(DayEntries::Lean(rows)))
+}
+
+/// All entries from `from` to `to` with their recipe details, in a single
+/// query.
+async fn fetch_days_detailed(
+    pool: &SqlitePool,
+    from: &str,
+    to: &str,
+    meal_type: Option<MealType>,
+) -> sqlx::Result<Vec<MealPlanEntryDetailed>> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        id: i64,
+        day: String,
+        meal_type: MealType,
+        recipe_id: i64,
+        title: String,
+        image_path_small: Option<String>,
+        #[sqlx(rename = "yield")]
+        r#yield: String,
+        servings: Option<f64>,
+        macros: Option<String>,
+    }
+
+    let rows: Vec<Row> = sqlx::query_as(
+        r#"
+        SELECT mp.id,
+               mp.day,
+               mp.meal_type,
+               mp.recipe_id,
+               r.title AS title,
+               r.image_path_small,
+               r."yield",
+               r.servings,
+               r.macros
+          FROM meal_plan mp
+          JOIN recipes r ON r.id = mp.recipe_id
+         WHERE mp.day BETWEEN ? AND ?
+           AND (? IS NULL OR mp.meal_type = ?)
+         ORDER BY mp.day, mp.id
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(meal_type)
+    .bind(meal_type)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| MealPlanEntryDetailed {
+            macros: parse_macros(row.recipe_id, row.macros.as_deref()),
+            entry: MealPlanEntry {
+                id: row.id,
+                day: row.day,
+                meal_type: row.meal_type,
+                recipe_id: row.recipe_id,
+                title: row.title,
+                image_path_small: row.image_path_small,
+            },
+            r#yield: row.r#yield,
+            servings: row.servings,
+        })
+        .collect())
+}
+
+#[derive(Serialize)]
+pub struct NutritionEntry {
+    #[serde(flatten)]
+    pub entry: MealPlanEntry,
+    /// `true` when the recipe has no servings, so its whole-recipe macros
+    /// were counted: the totals then depend on how much was actually eaten.
+    pub per_recipe: bool,
+    #[serde(flatten)]
+    pub macros: MacroTotals,
+}
+
+#[derive(Serialize)]
+pub struct NutritionDay {
+    pub day: String,
+    #[serde(flatten)]
+    pub totals: MacroTotals,
+    pub entries: Vec<NutritionEntry>,
 }
 
-/// POST /meal-plan  { "day": "YYYY-MM-DD", "`recipe_id"`: 123 }
-/// Assign a recipe to a specific day in the meal plan.
+#[derive(Serialize)]
+pub struct NutritionSummary {
+    pub days: Vec<NutritionDay>,
+    pub totals: MacroTotals,
+    /// Planned recipes without a macro estimate, left out of the totals.
+    pub unestimated: Vec<MealPlanEntry>,
+}
+
+/// What one planned meal contributes: a serving of `per_serving` recipes,
+/// the whole recipe otherwise.
+fn entry_nutrition(entry: MealPlanEntryDetailed) -> Result<NutritionEntry, MealPlanEntry> {
+    let Some(m) = entry.macros else {
+        return Err(entry.entry);
+    };
+    Ok(NutritionEntry {
+        entry: entry.entry,
+        per_recipe: m.basis != "per_serving",
+        macros: MacroTotals {
+            protein_g: m.protein_g,
+            fat_g: m.fat_g,
+            carbs_g: m.carbs_g,
+            kcal: m.kcal,
+            fiber_g: m.fiber_g,
+        },
+    })
+}
+
+/// `GET /meal-plan/nutrition?from=YYYY-MM-DD&to=YYYY-MM-DD[&meal_type=lunch]`
+/// Macros eaten per day and over the range, counting one serving per
+/// planned meal. Recipes estimated for the whole recipe only are counted
+/// whole and flagged `per_recipe`; recipes without an estimate are listed in
+/// `unestimated`. Days without an estimated meal are left out.
+///
+/// # Errors
+/// 400 when the range is missing, malformed, reversed or too long; Err if
+/// querying the meal plan fails.
+pub async fn nutrition(
+    State(state): State<AppState>,
+    Query(q): Query<DayQuery>,
+) -> AppResult<Json<NutritionSummary>> {
+    let (from, to) = day_range(&q)?;
+    let rows = fetch_days_detailed(&state.pool, &from, &to, q.meal_type).await?;
+
+    let mut summary = NutritionSummary {
+        days: Vec::new(),
+        totals: MacroTotals::default(),
+        unestimated: Vec::new(),
+    };
+    for row in rows {
+        let entry = match entry_nutrition(row) {
+            Ok(entry) => entry,
+            Err(unestimated) => {
+                summary.unestimated.push(unestimated);
+                continue;
+            }
+        };
+        summary.totals += entry.macros;
+        if summary.days.last().is_none_or(|d| d.day != entry.entry.day) {
+            summary.days.push(NutritionDay {
+                day: entry.entry.day.clone(),
+                totals: MacroTotals::default(),
+                entries: Vec::new(),
+            });
+        }
+        if let Some(day) = summary.days.last_mut() {
+            day.totals += entry.macros;
+            day.entries.push(entry);
+        }
+    }
+    Ok(Json(summary))
+}
+
+/// Tolerant macros decoding: a corrupt row loses its macros, not the whole day.
+fn parse_macros(recipe_id: i64, raw: Option<&str>) -> Option<RecipeMacros> {
+    let raw = raw?;
+    match serde_json::from_str::<RecipeMacros>(raw) {
+        Ok(macros) => Some(macros),
+        Err(e) => {
+            tracing::warn!(recipe_id, error = %e, "ignoring unreadable macros");
+            None
+        }
+    }
+}
+
+async fn fetch_entry(
+    pool: &SqlitePool,
+    day: &str,
+    recipe_id: i64,
+    meal_type: MealType,
+) -> sqlx::Result<MealPlanEntry> {
+    sqlx::query_as::<_, MealPlanEntry>(
+        r"
+        SELECT mp.id, mp.day, mp.meal_type, mp.recipe_id, r.title AS title, r.image_path_small
+          FROM meal_plan mp
+          JOIN recipes r ON r.id = mp.recipe_id
+         WHERE mp.day = ? AND mp.recipe_id = ? AND mp.meal_type = ?
+        ",
+    )
+    .bind(day)
+    .bind(recipe_id)
+    .bind(meal_type)
+    .fetch_one(pool)
+    .await
+}
+
+/// 409 `already_assigned` carrying the entry that already holds the meal.
+async fn already_assigned(
+    pool: &SqlitePool,
+    day: &str,
+    recipe_id: i64,
+    meal_type: MealType,
+) -> AppError {
+    match fetch_entry(pool, day, recipe_id, meal_type).await {
+        Ok(existing) => AppError::conflict(
+            "already_assigned",
+            "recipe is already planned for this meal",
+            existing,
+        ),
+        Err(e) => e.into(),
+    }
+}
+
+/// POST /meal-plan  { "day": "YYYY-MM-DD", "`recipe_id"`: 123, "`meal_type"`: "lunch" }
+/// Assign a recipe to a meal on a specific day; `meal_type` defaults to dinner.
 ///
 /// # Errors
 /// Returns an error if:
 /// - The recipe title cannot be fetched (e.g., recipe does not exist).
+/// - The recipe is already planned for that meal (409 `already_assigned`,
+///   with the existing entry).
 /// - Inserting the meal plan entry fails.
 pub async fn assign(
     State(state): State<AppState>,
+    actor: Actor,
     Json(req): Json<AssignRecipe>,
 ) -> AppResult<Json<MealPlanEntry>> {
     // 1) Fetch the current recipe title
@@ -66,13 +347,14 @@ pub async fn assign(
     // 2) Insert into meal_plan including the title (NOT NULL)
     let insert = sqlx::query(
         r"
-        INSERT INTO meal_plan (day, recipe_id, title)
-        VALUES (?, ?, ?)
+        INSERT INTO meal_plan (day, recipe_id, title, meal_type)
+        VALUES (?, ?, ?, ?)
         ",
     )
     .bind(&req.day)
     .bind(req.recipe_id)
     .bind(&title)
+    .bind(req.meal_type)
     .execute(&state.pool)
     .await;
 
@@ -82,49 +364,303 @@ pub async fn assign(
             if let sqlx::Error::Database(db) = &e
                 && db.is_unique_violation()
             {
-                return Err(StatusCode::CONFLICT.into());
+                return Err(
+                    already_assigned(&state.pool, &req.day, req.recipe_id, req.meal_type).await,
+                );
             }
             return Err(e.into());
         }
     }
 
     // 3) Fetch back with joined image_path_small
-    let row = sqlx::query_as::<_, MealPlanEntry>(
-        r"
-        SELECT mp.id, mp.day, mp.recipe_id, r.title AS title, r.image_path_small
-          FROM meal_plan mp
-          JOIN recipes r ON r.id = mp.recipe_id
-         WHERE mp.day = ? AND mp.recipe_id = ?
-        ",
+    let row = fetch_entry(&state.pool, &req.day, req.recipe_id, req.meal_type).await?;
+
+    let summary = format!(
+        "Planned '{}' for {} {}",
+        row.title,
+        row.day,
+        row.meal_type.as_str()
+    );
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::MealPlan, row.id, "assign", summary),
     )
-    .bind(&req.day)
-    .bind(req.recipe_id)
-    .fetch_one(&state.pool)
-    .await?;
+    .await;
 
     Ok(Json(row))
 }
 
-/// DELETE /meal-plan/{day}/{recipe_id}
-/// Unassign a recipe from a specific day in the meal plan.
+/// `?meal_type=` on routes addressing one entry by day and recipe.
+#[derive(Deserialize)]
+pub struct EntryQuery {
+    #[serde(default)]
+    pub meal_type: MealType,
+}
+
+/// DELETE /meal-plan/{day}/{recipe_id}?meal_type=lunch
+/// Unassign a recipe from a meal on a specific day; `meal_type` defaults to
+/// dinner.
 ///
 /// # Errors
 /// Returns an error if deleting the meal plan entry fails.
 pub async fn unassign(
     State(state): State<AppState>,
+    actor: Actor,
     Path((day, recipe_id)): Path<(String, i64)>,
+    Query(q): Query<EntryQuery>,
 ) -> AppResult<Json<serde_json::Value>> {
-    let res = sqlx::query(r"DELETE FROM meal_plan WHERE day = ? AND recipe_id = ?")
-        .bind(day)
-        .bind(recipe_id)
-        .execute(&state.pool)
-        .await?;
+    let removed: Option<(i64, String)> = sqlx::query_as(
+        r"DELETE FROM meal_plan WHERE day = ? AND recipe_id = ? AND meal_type = ? RETURNING id, title",
+    )
+    .bind(&day)
+    .bind(recipe_id)
+    .bind(q.meal_type)
+    .fetch_optional(&state.pool)
+    .await?;
 
+    if let Some((id, title)) = &removed {
+        let summary = format!("Removed '{title}' from {day} {}", q.meal_type.as_str());
+        activity::record(
+            &state,
+            actor,
+            Event::new(Entity::MealPlan, *id, "unassign", summary),
+        )
+        .await;
+    }
     Ok(Json(serde_json::json!({
-        "deleted": res.rows_affected()
+        "deleted": u64::from(removed.is_some())
     })))
 }
 
+#[derive(Serialize)]
+pub struct CompletedDay {
+    /// Recipes newly marked cooked; entries completed before are skipped.
+    pub cooked: u64,
+}
+
+/// POST /meal-plan/{day}/complete
+/// Mark every recipe planned on a past (or today's) day as cooked on that
+/// day. Entries are only counted once, so repeating the call is harmless.
+///
+/// # Errors
+/// 400 if `day` is malformed or in the future; Err if the db update fails.
+pub async fn complete_day(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(day): Path<String>,
+) -> AppResult<Json<CompletedDay>> {
+    let date = NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+        .map_err(|_| AppError::from((StatusCode::BAD_REQUEST, "day must be YYYY-MM-DD".into())))?;
+    if date > chrono::Local::now().date_naive() {
+        return Err((StatusCode::BAD_REQUEST, "day is in the future".to_string()).into());
+    }
+    let day = date.to_string();
+
+    let mut tx = state.pool.begin().await?;
+    let cooked = sqlx::query(
+        r"UPDATE recipes
+             SET times_cooked = times_cooked + (SELECT COUNT(*) FROM meal_plan mp
+                                                 WHERE mp.recipe_id = recipes.id
+                                                   AND mp.day = ? AND mp.cooked = 0),
+                 last_cooked_at = MAX(COALESCE(last_cooked_at, ''), datetime(?))
+           WHERE id IN (SELECT recipe_id FROM meal_plan WHERE day = ? AND cooked = 0)",
+    )
+    .bind(&day)
+    .bind(&day)
+    .bind(&day)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    sqlx::query(r"UPDATE meal_plan SET cooked = 1 WHERE day = ?")
+        .bind(&day)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    if cooked > 0 {
+        let summary = format!("Marked {cooked} recipe(s) from {day} as cooked");
+        activity::record(
+            &state,
+            actor,
+            Event::new(Entity::MealPlan, None, "complete", summary),
+        )
+        .await;
+    }
+    Ok(Json(CompletedDay { cooked }))
+}
+
+const fn default_autofill_days() -> i64 {
+    7
+}
+
+const fn default_avoid_recent_days() -> i64 {
+    14
+}
+
+#[derive(Deserialize)]
+pub struct AutofillReq {
+    pub from: String, // "YYYY-MM-DD"
+    #[serde(default = "default_autofill_days")]
+    pub days: i64,
+    #[serde(default)]
+    pub meal_type: MealType,
+    /// Recipes planned this many days before `from` are left out if possible.
+    #[serde(default = "default_avoid_recent_days")]
+    pub avoid_recent_days: i64,
+    /// Makes the picks reproducible.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// A recipe autofill may pick, with its selection weight.
+struct Candidate {
+    id: i64,
+    title: String,
+    weight: f64,
+    recent: bool,
+}
+
+/// Weighted pick preferring recipes neither planned recently nor already
+/// used in this fill, then ones merely not used in this fill, then anything.
+fn pick_candidate<'a>(
+    candidates: &'a [Candidate],
+    used: &[i64],
+    rng: &mut impl rand::Rng,
+) -> Option<&'a Candidate> {
+    use rand::distributions::{Distribution, WeightedIndex};
+
+    let tiers: [&dyn Fn(&Candidate) -> bool; 3] = [
+        &|c| !c.recent && !used.contains(&c.id),
+        &|c| !used.contains(&c.id),
+        &|_| true,
+    ];
+    for tier in tiers {
+        let pool: Vec<&Candidate> = candidates.iter().filter(|c| tier(c)).collect();
+        if pool.is_empty() {
+            continue;
+        }
+        let dist = WeightedIndex::new(pool.iter().map(|c| c.weight)).ok()?;
+        return Some(pool[dist.sample(rng)]);
+    }
+    None
+}
+
+/// POST /meal-plan/autofill
+/// `{ "from": "YYYY-MM-DD", "days": 7, "meal_type": "dinner", "avoid_recent_days": 14, "seed": 1 }`
+/// Plan one recipe per day for `meal_type`, skipping days that already have
+/// one. Picks are random, favoured towards favorites and well-rated recipes,
+/// and avoid recipes planned in the `avoid_recent_days` before `from` or
+/// earlier in the same fill while other recipes are left. Returns the
+/// created entries.
+///
+/// # Errors
+/// 400 if `from` is malformed or `days`/`avoid_recent_days` are out of
+/// range; Err if the db fails.
+pub async fn autofill(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(req): Json<AutofillReq>,
+) -> AppResult<Json<Vec<MealPlanEntry>>> {
+    use rand::SeedableRng;
+
+    let bad = |msg: String| AppError::from((StatusCode::BAD_REQUEST, msg));
+    let start = NaiveDate::parse_from_str(&req.from, "%Y-%m-%d")
+        .map_err(|_| bad("from must be YYYY-MM-DD".into()))?;
+    if !(1..=MAX_RANGE_DAYS).contains(&req.days) {
+        return Err(bad(format!("days must be between 1 and {MAX_RANGE_DAYS}")));
+    }
+    if !(0..=365).contains(&req.avoid_recent_days) {
+        return Err(bad("avoid_recent_days must be between 0 and 365".into()));
+    }
+    let end = start + chrono::Duration::days(req.days - 1);
+    let recent_from = start - chrono::Duration::days(req.avoid_recent_days);
+
+    let rows: Vec<(i64, String, bool, Option<f64>, bool)> = sqlx::query_as(
+        r"
+        SELECT r.id, r.title, r.is_favorite,
+               (SELECT AVG(rating) FROM recipe_logs l WHERE l.recipe_id = r.id),
+               EXISTS (SELECT 1 FROM meal_plan mp
+                        WHERE mp.recipe_id = r.id AND mp.day >= ? AND mp.day < ?)
+          FROM recipes r
+         WHERE r.deleted_at IS NULL
+         ORDER BY r.id
+        ",
+    )
+    .bind(recent_from.to_string())
+    .bind(start.to_string())
+    .fetch_all(&state.pool)
+    .await?;
+    let candidates: Vec<Candidate> = rows
+        .into_iter()
+        .map(|(id, title, favorite, rating, recent)| Candidate {
+            id,
+            title,
+            weight: 1.0 + if favorite { 1.0 } else { 0.0 } + rating.unwrap_or(0.0) / 5.0,
+            recent,
+        })
+        .collect();
+
+    // Recipes already in the window count as used, and their days are taken.
+    let planned: Vec<(String, i64)> = sqlx::query_as(
+        r"SELECT day, recipe_id FROM meal_plan WHERE day BETWEEN ? AND ? AND meal_type = ?",
+    )
+    .bind(start.to_string())
+    .bind(end.to_string())
+    .bind(req.meal_type)
+    .fetch_all(&state.pool)
+    .await?;
+    let mut used: Vec<i64> = planned.iter().map(|(_, id)| *id).collect();
+
+    let mut rng = req.seed.map_or_else(
+        rand::rngs::StdRng::from_entropy,
+        rand::rngs::StdRng::seed_from_u64,
+    );
+    let mut created = Vec::new();
+    for day in start.iter_days().take_while(|d| *d <= end) {
+        let day = day.to_string();
+        if planned.iter().any(|(d, _)| *d == day) {
+            continue;
+        }
+        let Some(pick) = pick_candidate(&candidates, &used, &mut rng) else {
+            break;
+        };
+        used.push(pick.id);
+        let inserted: Option<(i64,)> = sqlx::query_as(
+            r"
+            INSERT INTO meal_plan (day, recipe_id, title, meal_type)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT DO NOTHING
+            RETURNING id
+            ",
+        )
+        .bind(&day)
+        .bind(pick.id)
+        .bind(&pick.title)
+        .bind(req.meal_type)
+        .fetch_optional(&state.pool)
+        .await?;
+        if inserted.is_some() {
+            created.push(fetch_entry(&state.pool, &day, pick.id, req.meal_type).await?);
+        }
+    }
+
+    if !created.is_empty() {
+        let summary = format!(
+            "Auto-planned {} {} recipe(s) from {start}",
+            created.len(),
+            req.meal_type.as_str()
+        );
+        activity::record(
+            &state,
+            actor,
+            Event::new(Entity::MealPlan, None, "autofill", summary),
+        )
+        .await;
+    }
+    Ok(Json(created))
+}
+
 #[derive(Deserialize)]
 pub struct ReminderRangeQuery {
     pub from: String, // "YYYY-MM-DD"
@@ -158,11 +694,11 @@ pub async fn get_for_recipe(
         .to_string();
     let rows: Vec<MealPlanEntry> = sqlx::query_as::<_, MealPlanEntry>(
         r"
-        SELECT mp.id, mp.day, mp.recipe_id, r.title AS title, r.image_path_small
+        SELECT mp.id, mp.day, mp.meal_type, mp.recipe_id, r.title AS title, r.image_path_small
           FROM meal_plan mp
           JOIN recipes r ON r.id = mp.recipe_id
          WHERE mp.recipe_id = ? AND mp.day >= ?
-         ORDER BY mp.day
+         ORDER BY mp.day, mp.id
         ",
     )
     .bind(recipe_id)
@@ -178,28 +714,34 @@ pub struct MoveEntry {
     pub new_day: String,
 }
 
-/// PATCH /meal-plan/{day}/{recipe_id}  { `"new_day"`: "YYYY-MM-DD" }
-/// Move a meal plan entry to a different day.
+/// PATCH /meal-plan/{day}/{recipe_id}?meal_type=lunch  { `"new_day"`: "YYYY-MM-DD" }
+/// Move a meal plan entry to a different day, keeping its meal.
 ///
 /// # Errors
 /// Returns an error if:
 /// - The target day format is invalid.
 /// - The entry does not exist (404).
-/// - The recipe is already scheduled on the target day (409).
+/// - The recipe is already scheduled for that meal on the target day
+///   (409 `already_assigned`).
 /// - The database update fails.
 pub async fn move_entry(
     State(state): State<AppState>,
+    actor: Actor,
     Path((day, recipe_id)): Path<(String, i64)>,
+    Query(q): Query<EntryQuery>,
     Json(req): Json<MoveEntry>,
 ) -> AppResult<Json<MealPlanEntry>> {
     NaiveDate::parse_from_str(&req.new_day, "%Y-%m-%d").map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    let res = sqlx::query(r"UPDATE meal_plan SET day = ? WHERE day = ? AND recipe_id = ?")
-        .bind(&req.new_day)
-        .bind(&day)
-        .bind(recipe_id)
-        .execute(&state.pool)
-        .await;
+    let res = sqlx::query(
+        r"UPDATE meal_plan SET day = ? WHERE day = ? AND recipe_id = ? AND meal_type = ?",
+    )
+    .bind(&req.new_day)
+    .bind(&day)
+    .bind(recipe_id)
+    .bind(q.meal_type)
+    .execute(&state.pool)
+    .await;
 
     match res {
         Ok(r) if r.rows_affected() == 0 => return Err(StatusCode::NOT_FOUND.into()),
@@ -208,25 +750,23 @@ pub async fn move_entry(
             if let sqlx::Error::Database(db) = &e
                 && db.is_unique_violation()
             {
-                return Err(StatusCode::CONFLICT.into());
+                return Err(
+                    already_assigned(&state.pool, &req.new_day, recipe_id, q.meal_type).await,
+                );
             }
             return Err(e.into());
         }
     }
 
-    let row = sqlx::query_as::<_, MealPlanEntry>(
-        r"
-        SELECT mp.id, mp.day, mp.recipe_id, r.title AS title, r.image_path_small
-          FROM meal_plan mp
-          JOIN recipes r ON r.id = mp.recipe_id
-         WHERE mp.day = ? AND mp.recipe_id = ?
-        ",
-    )
-    .bind(&req.new_day)
-    .bind(recipe_id)
-    .fetch_one(&state.pool)
-    .await?;
+    let row = fetch_entry(&state.pool, &req.new_day, recipe_id, q.meal_type).await?;
 
+    let summary = format!("Moved '{}' from {day} to {}", row.title, row.day);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::MealPlan, row.id, "move", summary),
+    )
+    .await;
     Ok(Json(row))
 }
 
@@ -0,0 +1,348 @@
+use axum::{
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{Days, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::fmt::Write as _;
+
+use crate::{
+    error::AppResult,
+    html::escape_html,
+    models::{AppState, Ingredient},
+    routes::{
+        settings::{get_setting, today},
+        shopping::{MergeKey, merge_key, merge_quantities},
+    },
+};
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    /// First day of the week; defaults to the current week per the `week_start`
+    /// and `timezone` settings.
+    pub week_start: Option<String>,
+    /// `markdown` (default) or `html`.
+    pub format: Option<String>,
+}
+
+struct PlannedMeal {
+    day: NaiveDate,
+    title: String,
+    r#yield: String,
+    share_token: Option<String>,
+    ingredients: Vec<Ingredient>,
+}
+
+struct WeekPlan {
+    start: NaiveDate,
+    meals: Vec<PlannedMeal>,
+}
+
+impl WeekPlan {
+    fn days(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.start.iter_days().take(7)
+    }
+
+    fn end(&self) -> NaiveDate {
+        self.start + Days::new(6)
+    }
+
+    fn meals_on(&self, day: NaiveDate) -> impl Iterator<Item = &PlannedMeal> {
+        self.meals.iter().filter(move |m| m.day == day)
+    }
+}
+
+/// GET /meal-plan/export?week_start=YYYY-MM-DD&format=markdown|html
+///
+/// Printable week plan with a combined shopping summary. The summary uses the
+/// shopping-list merge keys but is not persisted.
+///
+/// # Errors
+/// 400 for an invalid date or format; Err if querying the database fails.
+pub async fn export_week(
+    State(state): State<AppState>,
+    Query(q): Query<ExportQuery>,
+) -> AppResult<Response> {
+    let start = if let Some(s) = q.week_start.as_deref() {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| (StatusCode::BAD_REQUEST, "invalid week_start".to_string()))?
+    } else {
+        let starts_on = get_setting(&state.pool, "week_start")
+            .await
+            .and_then(|s| s.parse::<Weekday>().ok())
+            .unwrap_or(Weekday::Mon);
+        today(&state.pool).await.week(starts_on).first_day()
+    };
+
+    let format = q.format.as_deref().unwrap_or("markdown");
+    if !matches!(format, "markdown" | "md" | "html") {
+        return Err((StatusCode::BAD_REQUEST, format!("unknown format: {format}")).into());
+    }
+
+    let plan = load_week(&state.pool, start).await?;
+
+    let resp = if format == "html" {
+        (
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            render_html(&plan),
+        )
+    } else {
+        (
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            render_markdown(&plan),
+        )
+    };
+    Ok(resp.into_response())
+}
+
+async fn load_week(pool: &sqlx::SqlitePool, start: NaiveDate) -> sqlx::Result<WeekPlan> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        day: String,
+        title: String,
+        #[sqlx(rename = "yield")]
+        r#yield: String,
+        share_token: Option<String>,
+        ingredients: String,
+    }
+
+    let end = start + Days::new(6);
+    let rows: Vec<Row> = sqlx::query_as(
+        r#"
+        SELECT mp.day, r.title, r."yield", r.share_token, r.ingredients
+          FROM meal_plan mp
+          JOIN recipes r ON r.id = mp.recipe_id
+         WHERE mp.day >= ? AND mp.day <= ?
+         ORDER BY mp.day, mp.id
+        "#,
+    )
+    .bind(start.format("%Y-%m-%d").to_string())
+    .bind(end.format("%Y-%m-%d").to_string())
+    .fetch_all(pool)
+    .await?;
+
+    let meals = rows
+        .into_iter()
+        .filter_map(|row| {
+            Some(PlannedMeal {
+                day: NaiveDate::parse_from_str(&row.day, "%Y-%m-%d").ok()?,
+                title: row.title,
+                r#yield: row.r#yield,
+                share_token: row.share_token,
+                ingredients: serde_json::from_str(&row.ingredients).unwrap_or_default(),
+            })
+        })
+        .collect();
+
+    Ok(WeekPlan { start, meals })
+}
+
+/// Combined ingredient lines for the week, merged by the same key as
+/// `POST /shopping/merge` (normalized name + canonical unit), in first-seen order.
+fn shopping_summary(meals: &[PlannedMeal]) -> Vec<String> {
+    let mut merged: Vec<MergeKey> = Vec::new();
+
+    for ing in meals.iter().flat_map(|m| &m.ingredients) {
+        if ing.section.is_some() || ing.name.trim().is_empty() {
+            continue;
+        }
+        let item = merge_key(&ing.name, ing.unit.as_deref(), ing.quantity);
+        if let Some(entry) = merged.iter_mut().find(|m| m.key == item.key) {
+            entry.quantity = merge_quantities(entry.quantity, item.quantity);
+        } else {
+            merged.push(item);
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|m| match (m.quantity, m.unit) {
+            (Some(q), Some(u)) => format!("{q} {u} {}", m.name),
+            (Some(q), None) => format!("{q} {}", m.name),
+            (None, _) => m.name,
+        })
+        .collect()
+}
+
+fn share_path(token: &str) -> String {
+    format!("/share/{token}")
+}
+
+fn render_markdown(plan: &WeekPlan) -> String {
+    let cell = |s: &str| s.replace('|', "\\|");
+    let mut out = format!(
+        "# Meal plan {} – {}\n\n| Day | Recipe | Yield |\n| --- | --- | --- |\n",
+        plan.start.format("%Y-%m-%d"),
+        plan.end().format("%Y-%m-%d")
+    );
+
+    for day in plan.days() {
+        let label = day.format("%a %Y-%m-%d");
+        let mut any = false;
+        for meal in plan.meals_on(day) {
+            any = true;
+            let title = meal.share_token.as_deref().map_or_else(
+                || cell(&meal.title),
+                |t| format!("[{}]({})", cell(&meal.title), share_path(t)),
+            );
+            let _ = writeln!(out, "| {label} | {title} | {} |", cell(&meal.r#yield));
+        }
+        if !any {
+            let _ = writeln!(out, "| {label} | — | |");
+        }
+    }
+
+    out.push_str("\n## Shopping list\n\n");
+    let summary = shopping_summary(&plan.meals);
+    if summary.is_empty() {
+        out.push_str("_Nothing to buy._\n");
+    }
+    for line in summary {
+        let _ = writeln!(out, "- {line}");
+    }
+    out
+}
+
+fn render_html(plan: &WeekPlan) -> String {
+    let heading = format!(
+        "Meal plan {} – {}",
+        plan.start.format("%Y-%m-%d"),
+        plan.end().format("%Y-%m-%d")
+    );
+    let mut out = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{heading}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #000; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ border: 1px solid #888; padding: 0.4em 0.6em; text-align: left; vertical-align: top; }}
+th {{ background: #eee; }}
+a {{ color: inherit; }}
+@media print {{ body {{ margin: 0; }} a {{ text-decoration: none; }} }}
+</style>
+</head>
+<body>
+<h1>{heading}</h1>
+<table>
+<thead><tr><th>Day</th><th>Recipe</th><th>Yield</th></tr></thead>
+<tbody>
+"#
+    );
+
+    for day in plan.days() {
+        let label = day.format("%a %Y-%m-%d");
+        let mut any = false;
+        for meal in plan.meals_on(day) {
+            any = true;
+            let title = escape_html(&meal.title);
+            let title = meal.share_token.as_deref().map_or_else(
+                || title.clone(),
+                |t| format!(r#"<a href="{}">{title}</a>"#, escape_html(&share_path(t))),
+            );
+            let _ = writeln!(
+                out,
+                "<tr><td>{label}</td><td>{title}</td><td>{}</td></tr>",
+                escape_html(&meal.r#yield)
+            );
+        }
+        if !any {
+            let _ = writeln!(out, "<tr><td>{label}</td><td>—</td><td></td></tr>");
+        }
+    }
+
+    out.push_str("</tbody>\n</table>\n<h2>Shopping list</h2>\n<ul>\n");
+    for line in shopping_summary(&plan.meals) {
+        let _ = writeln!(out, "<li>{}</li>", escape_html(&line));
+    }
+    out.push_str("</ul>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ing(quantity: Option<f64>, unit: Option<&str>, name: &str) -> Ingredient {
+        Ingredient {
+            section: None,
+            quantity,
+            unit: unit.map(str::to_string),
+            name: name.to_string(),
+            prep: None,
+            raw: false,
+        }
+    }
+
+    fn seeded_week() -> WeekPlan {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        WeekPlan {
+            start: day(5),
+            meals: vec![
+                PlannedMeal {
+                    day: day(5),
+                    title: "Carbonara".to_string(),
+                    r#yield: "2 servings".to_string(),
+                    share_token: Some("abc123".to_string()),
+                    ingredients: vec![
+                        ing(Some(200.0), Some("g"), "Spaghetti"),
+                        ing(Some(2.0), None, "eggs"),
+                    ],
+                },
+                PlannedMeal {
+                    day: day(5),
+                    title: "Salad | side".to_string(),
+                    r#yield: String::new(),
+                    share_token: None,
+                    ingredients: vec![ing(None, None, "Lettuce")],
+                },
+                PlannedMeal {
+                    day: day(7),
+                    title: "Pasta bake".to_string(),
+                    r#yield: "4 servings".to_string(),
+                    share_token: None,
+                    ingredients: vec![
+                        Ingredient {
+                            section: Some("Sauce".to_string()),
+                            ..ing(None, None, "")
+                        },
+                        ing(Some(300.0), Some("grams"), "spaghetti"),
+                        ing(Some(1.0), None, "Egg"),
+                        ing(Some(1.0), None, "eggs"),
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_markdown_snapshot() {
+        assert_eq!(
+            render_markdown(&seeded_week()),
+            include_str!("../../tests/fixtures/meal_plan_week.md")
+        );
+    }
+
+    #[test]
+    fn test_markdown_empty_week() {
+        let plan = WeekPlan {
+            start: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+            meals: Vec::new(),
+        };
+        let md = render_markdown(&plan);
+        assert_eq!(md.matches("| — | |").count(), 7);
+        assert!(md.ends_with("_Nothing to buy._\n"));
+    }
+
+    #[test]
+    fn test_html_escapes_and_links() {
+        let html = render_html(&seeded_week());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(r#"<a href="/share/abc123">Carbonara</a>"#));
+        assert!(html.contains("<li>500 g spaghetti</li>"));
+        assert!(html.contains("@media print"));
+    }
+}
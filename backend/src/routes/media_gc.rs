@@ -0,0 +1,18 @@
+use axum::extract::State;
+
+use crate::error::AppResult;
+use crate::extract::Json;
+use crate::media_gc::{self, GcReport};
+use crate::models::AppState;
+
+/// POST /admin/media/gc
+///
+/// Delete recipe images that no recipe references any more.
+///
+/// # Errors
+/// 503 when the media dir is unavailable; Err if scanning it fails.
+pub async fn collect(State(state): State<AppState>) -> AppResult<Json<GcReport>> {
+    state.media.ensure_available()?;
+    let report = media_gc::collect(&state.pool, &state.config.media_dir, media_gc::GRACE).await?;
+    Ok(Json(report))
+}
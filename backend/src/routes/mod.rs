@@ -1,12 +1,28 @@
+pub mod activity;
+pub mod api_tokens;
+pub mod app_state;
 pub mod auth;
 pub mod categories;
+pub mod db_backup;
+pub mod db_check;
+pub mod digest;
 pub mod import_recipe_images;
 pub mod import_recipesage;
+pub mod ingredient_suggest;
 pub mod llm_credits;
+pub mod llm_models;
+pub mod llm_usage;
 pub mod meal_plan;
+pub mod meal_plan_export;
+pub mod media_gc;
+pub mod pantry;
 pub mod parse_recipe;
 pub mod parse_recipe_image;
+pub mod recipe_backup;
+pub mod recipe_logs;
+pub mod recipe_suggest;
 pub mod recipes;
 pub mod settings;
 pub mod share_recipe;
 pub mod shopping;
+pub mod stores;
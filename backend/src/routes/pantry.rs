@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use sqlx::SqlitePool;
+
+use crate::{
+    error::AppResult,
+    extract::Json,
+    models::{AppState, NewPantryItem, PantryItem, UpdatePantryItem},
+    units::normalize_name,
+};
+
+/// Normalized names of every pantry item.
+pub async fn pantry_names(pool: &SqlitePool) -> sqlx::Result<HashSet<String>> {
+    let names: Vec<String> = sqlx::query_scalar(r"SELECT name FROM pantry_items")
+        .fetch_all(pool)
+        .await?;
+    Ok(names.into_iter().collect())
+}
+
+async fn fetch_item(pool: &SqlitePool, id: i64) -> AppResult<PantryItem> {
+    sqlx::query_as(r"SELECT id, name, note, created_at FROM pantry_items WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into())
+}
+
+fn item_name(raw: &str) -> AppResult<String> {
+    let name = normalize_name(raw);
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Pantry item name cannot be empty".to_string(),
+        )
+            .into());
+    }
+    Ok(name)
+}
+
+fn name_conflict(e: sqlx::Error, name: &str) -> crate::error::AppError {
+    if let sqlx::Error::Database(db) = &e
+        && db.is_unique_violation()
+    {
+        return (
+            StatusCode::CONFLICT,
+            format!("'{name}' is already in the pantry"),
+        )
+            .into();
+    }
+    e.into()
+}
+
+/// GET /pantry
+pub async fn list(State(state): State<AppState>) -> AppResult<Json<Vec<PantryItem>>> {
+    let rows: Vec<PantryItem> =
+        sqlx::query_as(r"SELECT id, name, note, created_at FROM pantry_items ORDER BY name")
+            .fetch_all(&state.pool)
+            .await?;
+    Ok(Json(rows))
+}
+
+/// POST /pantry
+pub async fn create(
+    State(state): State<AppState>,
+    Json(req): Json<NewPantryItem>,
+) -> AppResult<Json<PantryItem>> {
+    let name = item_name(&req.name)?;
+    let id: i64 =
+        sqlx::query_scalar(r"INSERT INTO pantry_items (name, note) VALUES (?, ?) RETURNING id")
+            .bind(&name)
+            .bind(req.note.trim())
+            .fetch_one(&state.pool)
+            .await
+            .map_err(|e| name_conflict(e, &name))?;
+
+    Ok(Json(fetch_item(&state.pool, id).await?))
+}
+
+/// PATCH /pantry/{id}
+pub async fn update(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdatePantryItem>,
+) -> AppResult<Json<PantryItem>> {
+    let current = fetch_item(&state.pool, id).await?;
+    let name = match req.name.as_deref() {
+        Some(raw) => item_name(raw)?,
+        None => current.name,
+    };
+    let note = req
+        .note
+        .as_deref()
+        .map_or(current.note, |n| n.trim().to_string());
+    sqlx::query(r"UPDATE pantry_items SET name = ?, note = ? WHERE id = ?")
+        .bind(&name)
+        .bind(&note)
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| name_conflict(e, &name))?;
+
+    Ok(Json(fetch_item(&state.pool, id).await?))
+}
+
+/// DELETE /pantry/{id}
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<serde_json::Value>> {
+    let affected = sqlx::query(r"DELETE FROM pantry_items WHERE id = ?")
+        .bind(id)
+        .execute(&state.pool)
+        .await?
+        .rows_affected();
+
+    Ok(Json(serde_json::json!({ "deleted": affected })))
+}
@@ -1,18 +1,30 @@
-use crate::error::AppResult;
-use crate::html::{clean_title, extract_title, fallback_title_from_url, html_to_plain_text};
+use crate::activity::Actor;
+use crate::error::{AppError, AppResult};
+use crate::html::{
+    clean_title, decode_html, extract_title, fallback_title_from_url, html_to_plain_text,
+    strip_tags,
+};
+use crate::instructions::tidy_steps;
 use crate::llm::LlmClient;
 use crate::models::Ingredient;
 use crate::routes::settings::LlmSettings;
 use crate::{
-    models::{AppState, NewRecipe, Recipe},
-    routes::{parse_recipe_image::extract_main_image_url, recipes},
+    extract::Json,
+    models::{AppState, ImportMethod, NewRecipe, Recipe},
+    routes::{
+        parse_recipe_image::extract_main_image_url, recipes, shopping::parse_ingredient_line,
+    },
+    safe_fetch::{self, FetchError, MAX_HTML_BYTES},
+    units::{
+        canon_unit_str, parse_quantity, preclean_text, servings_yield_text, to_canonical_qty_unit,
+    },
+    youtube::{self, YoutubeVideo},
 };
 use axum::{
-    Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::time::Duration;
 
@@ -30,88 +42,415 @@ pub struct ImportFromUrlReq {
     /// Returns a Recipe with id=0. Use this for re-import (updating an existing recipe).
     #[serde(default)]
     pub dry_run: bool,
+    /// Run the LLM stages even when the page has complete schema.org data.
+    /// Also accepted as `?force_llm=true`.
+    #[serde(default)]
+    pub force_llm: bool,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ImportFromUrlQuery {
+    #[serde(default)]
+    pub force_llm: bool,
+}
+
+/// Content the import pipeline runs over.
+pub struct ImportSource {
+    pub title_guess: String,
+    /// Plain text handed to the Stage 1 LLM extraction.
+    pub text: String,
+    /// Raw HTML for schema.org and image discovery (empty for non-HTML sources).
+    pub html: String,
+    /// Where `html` came from after redirects, for resolving relative image
+    /// URLs; `None` uses the requested URL.
+    pub page_url: Option<String>,
+    /// Image to attach instead of one discovered in `html` (e.g. a video thumbnail).
+    pub image_url: Option<String>,
+    /// Set for pasted text; picks the Stage 1 prompt.
+    pub pasted: Option<PasteKind>,
+}
+
+impl ImportSource {
+    async fn fetch_page(url: &str, allow_private: bool) -> AppResult<Self> {
+        let page = fetch_page_text(url, allow_private).await?;
+
+        if page.text.trim().is_empty() {
+            return Err((StatusCode::BAD_GATEWAY, "page has no readable text".into()).into());
+        }
+
+        Ok(Self {
+            title_guess: clean_title(&page.title),
+            text: page.text,
+            html: page.html,
+            page_url: Some(page.url),
+            image_url: None,
+            pasted: None,
+        })
+    }
+
+    /// Use a video's description as the page text and its thumbnail as the image.
+    ///
+    /// # Errors
+    ///
+    /// 422 `extraction_empty` when the description has no ingredient-like lines.
+    pub fn from_youtube(video: YoutubeVideo) -> AppResult<Self> {
+        if !youtube::has_ingredient_lines(&video.description) {
+            return Err(AppError::Code(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "extraction_empty",
+                "video description does not contain an ingredient list".into(),
+            ));
+        }
+
+        Ok(Self {
+            text: format!("{}\n\n{}", video.title, video.description),
+            title_guess: video.title,
+            html: String::new(),
+            page_url: None,
+            image_url: video.thumbnail_url,
+            pasted: None,
+        })
+    }
 }
 
 /// # Errors
 ///
+/// 422 `model_not_allowed` for a model override outside the allow-list;
 /// Err if we can't fetch from the url
-#[allow(clippy::too_many_lines)]
 pub async fn import_from_url(
     State(state): State<AppState>,
-    Json(req): Json<ImportFromUrlReq>,
+    Query(q): Query<ImportFromUrlQuery>,
+    Json(mut req): Json<ImportFromUrlReq>,
 ) -> AppResult<Json<Recipe>> {
-    const MAX_CHARS: usize = 12_000;
+    req.force_llm |= q.force_llm;
+    // Load LLM settings from database
+    let llm_settings = LlmSettings::load(&state).await;
+    llm_settings.check_override(req.model.as_deref())?;
+    let model = req.model.as_deref().unwrap_or(&llm_settings.model);
+    let llm = LlmClient::from_config(&state.config, model.to_string())?
+        .tracked(&state.pool, "recipes.import");
 
-    let (title_guess_raw, text, html) = fetch_page_text(&req.url)
-        .await
-        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("fetch failed: {e}")))?;
+    // YouTube watch pages are mostly player JS; the recipe lives in the description.
+    let source = if let Some(video_id) = youtube::video_id(&req.url) {
+        let video = youtube::fetch_video(&state.http, &video_id)
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, format!("fetch failed: {e}")))?;
+        ImportSource::from_youtube(video)?
+    } else {
+        ImportSource::fetch_page(&req.url, state.config.fetch_allow_private).await?
+    };
+
+    import_from_source(&state, &llm, &llm_settings, &req, source).await
+}
+
+/// What `POST /recipes/import/text` took a paste for.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteKind {
+    Url,
+    /// Free recipe text with ingredient and/or method sections.
+    RecipeText,
+    /// Just ingredient lines, no method.
+    IngredientList,
+}
 
-    let title_guess = clean_title(&title_guess_raw);
+/// Section headings that mark pasted text as a full recipe.
+const RECIPE_HEADINGS: &[&str] = &[
+    "ingredients",
+    "instructions",
+    "directions",
+    "method",
+    "preparation",
+    "steps",
+];
 
-    if text.trim().is_empty() {
-        return Err((StatusCode::BAD_GATEWAY, "page has no readable text".into()).into());
+/// The URL a paste consists of, if any. Surrounding whitespace is ignored
+/// and a missing scheme is taken as https (`www.site.com/recipe`).
+#[must_use]
+pub fn paste_url(text: &str) -> Option<String> {
+    let t = text.trim();
+    if t.is_empty() || t.contains(char::is_whitespace) {
+        return None;
+    }
+    let lower = t.to_ascii_lowercase();
+    let has_scheme = lower.starts_with("http://") || lower.starts_with("https://");
+    let candidate = if has_scheme {
+        t.to_string()
+    } else {
+        format!("https://{t}")
+    };
+    let url = url::Url::parse(&candidate).ok()?;
+    let host = url.domain()?;
+    // Without a scheme, demand a real-looking domain so "1.5" or
+    // "salt,pepper" stay text.
+    let tld = host.rsplit('.').next().unwrap_or_default();
+    if !has_scheme
+        && (!host.contains('.') || tld.len() < 2 || !tld.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        return None;
     }
+    Some(url.to_string())
+}
 
-    let token = state.config.llm_api_key.clone().unwrap_or_default();
-    if token.is_empty() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "LLM API key is not configured (use --llm-api-key or BLAZ_LLM_API_KEY)".into(),
-        )
-            .into());
+/// Classify a paste: a URL, a recipe with section headings or prose steps,
+/// or a bare list of ingredients.
+#[must_use]
+pub fn detect_paste(text: &str) -> PasteKind {
+    if paste_url(text).is_some() {
+        return PasteKind::Url;
+    }
+    let is_recipe = text.lines().any(|line| {
+        let heading = line
+            .trim()
+            .trim_start_matches(['#', '*', '='])
+            .trim_end_matches([':', '*', '='])
+            .trim()
+            .to_lowercase();
+        RECIPE_HEADINGS.contains(&heading.as_str()) || line.split_whitespace().count() > 12
+    });
+    if is_recipe {
+        PasteKind::RecipeText
+    } else {
+        PasteKind::IngredientList
     }
+}
 
-    // Load LLM settings from database
-    let llm_settings = LlmSettings::load(&state.pool).await;
-    let model = req.model.as_deref().unwrap_or(&llm_settings.model);
-    let base = state.config.llm_api_url.as_str();
+#[derive(Deserialize)]
+pub struct ImportTextReq {
+    pub text: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize)]
+pub struct ImportTextResponse {
+    pub detected: PasteKind,
+    #[serde(flatten)]
+    pub recipe: Recipe,
+}
+
+/// `POST /recipes/import/text`  `{ "text": "..." }`
+/// Import whatever the user pasted: a URL goes through the URL import,
+/// recipe text and ingredient lists straight to the LLM. `detected` tells
+/// which it was taken for.
+///
+/// # Errors
+///
+/// 400 for an empty paste; otherwise as [`import_from_url`]
+pub async fn import_from_text(
+    State(state): State<AppState>,
+    Json(req): Json<ImportTextReq>,
+) -> AppResult<Json<ImportTextResponse>> {
+    if req.text.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "nothing to import".into()).into());
+    }
+    let detected = detect_paste(&req.text);
+    let url_req = ImportFromUrlReq {
+        url: paste_url(&req.text).unwrap_or_default(),
+        model: req.model,
+        dry_run: req.dry_run,
+        force_llm: false,
+    };
+    let Json(recipe) = if detected == PasteKind::Url {
+        import_from_url(
+            State(state),
+            Query(ImportFromUrlQuery::default()),
+            Json(url_req),
+        )
+        .await?
+    } else {
+        let llm_settings = LlmSettings::load(&state).await;
+        llm_settings.check_override(url_req.model.as_deref())?;
+        let model = url_req.model.as_deref().unwrap_or(&llm_settings.model);
+        let llm = LlmClient::from_config(&state.config, model.to_string())?
+            .tracked(&state.pool, "recipes.import");
+        let source = ImportSource {
+            title_guess: String::new(),
+            text: req.text,
+            html: String::new(),
+            page_url: None,
+            image_url: None,
+            pasted: Some(detected),
+        };
+        import_from_source(&state, &llm, &llm_settings, &url_req, source).await?
+    };
+    Ok(Json(ImportTextResponse { detected, recipe }))
+}
+
+/// Run schema.org / LLM extraction over fetched content and persist the recipe
+/// (unless `dry_run`). Complete schema.org data skips the LLM entirely.
+///
+/// # Errors
+///
+/// Err if an LLM stage fails or the recipe can't be stored
+#[allow(clippy::too_many_lines)]
+pub async fn import_from_source(
+    state: &AppState,
+    llm: &LlmClient,
+    llm_settings: &LlmSettings,
+    req: &ImportFromUrlReq,
+    source: ImportSource,
+) -> AppResult<Json<Recipe>> {
+    const MAX_CHARS: usize = 12_000;
+
+    let ImportSource {
+        title_guess,
+        text,
+        html,
+        page_url,
+        image_url,
+        pasted,
+    } = source;
 
     let excerpt = if text.len() > MAX_CHARS {
-        &text[..MAX_CHARS]
+        &text[..text.floor_char_boundary(MAX_CHARS)]
     } else {
         &text
     };
 
-    let http = reqwest::Client::new();
-    let llm = LlmClient::new(base.to_string(), token.clone(), model.to_string());
+    let http = state.http.clone();
 
-    // TRY SCHEMA.ORG EXTRACTION FIRST
-    let (title, ingredient_strings, instruction_strings) =
-        if let Some(schema) = crate::schema_org::extract_schema_recipe(&html) {
+    // Complete schema.org data needs no LLM at all, unless the caller insists.
+    let schema = if req.force_llm {
+        None
+    } else {
+        crate::schema_org::extract_schema_recipe(&html)
+    };
+    let (method, title, structured_ingredients, instruction_strings, equipment) =
+        if let Some(schema) = schema {
             tracing::info!(
-                "Using schema.org data: {} ingredients",
+                "Using schema.org data: {} ingredients, skipping the LLM",
                 schema.ingredients.len()
             );
-            (schema.name, schema.ingredients, schema.instructions)
-        } else {
-            // FALLBACK: STAGE 1 LLM extraction
-            tracing::info!("No schema.org found, using Stage 1 LLM extraction");
-            let result = stage1_extract(
-                &llm,
-                &http,
-                &state,
-                &llm_settings,
-                excerpt,
-                &req.url,
-                &title_guess,
+            let ingredients = schema
+                .ingredients
+                .iter()
+                .filter_map(|line| parse_ingredient_line(line))
+                .collect();
+            (
+                ImportMethod::SchemaOrg,
+                schema.name,
+                ingredients,
+                normalize_instructions(schema.instructions.into()),
+                schema.tools,
             )
-            .await
-            .map_err(|e| {
-                (
-                    StatusCode::BAD_GATEWAY,
-                    format!("Stage 1 (extract) failed: {e}"),
-                )
-            })?;
+        } else {
+            tracing::info!("Using Stage 1 LLM extraction");
+            let user = match pasted {
+                None | Some(PasteKind::Url) => format!(
+                    "URL: {}\nTITLE: {title_guess}\n\nCONTENT:\n{excerpt}",
+                    req.url
+                ),
+                Some(PasteKind::RecipeText) => format!("PASTED RECIPE TEXT:\n{excerpt}"),
+                Some(PasteKind::IngredientList) => format!(
+                    "PASTED INGREDIENT LIST (there is no method: return an empty \
+                     instructions array and a short title naming the dish):\n{excerpt}"
+                ),
+            };
+            let needs_steps = pasted != Some(PasteKind::IngredientList);
+            let (title, ingredient_strings, instructions, equipment) =
+                stage1_extract(llm, &http, state, llm_settings, &user, needs_steps)
+                    .await
+                    .map_err(|e| {
+                        (
+                            StatusCode::BAD_GATEWAY,
+                            format!("Stage 1 (extract) failed: {e}"),
+                        )
+                    })?;
 
             tracing::info!(
                 "Stage 1 complete: title='{}', {} ingredient strings, {} instruction strings",
-                result.0,
-                result.1.len(),
-                result.2.len()
+                title,
+                ingredient_strings.len(),
+                instructions.len()
             );
-            result
+            let ingredients =
+                llm_structure_ingredients(llm, &http, state, llm_settings, &ingredient_strings)
+                    .await?;
+            (
+                ImportMethod::Llm,
+                title,
+                ingredients,
+                instructions,
+                equipment,
+            )
         };
 
+    let final_title = if title.trim().is_empty() {
+        fallback_title_from_url(&req.url).unwrap_or_else(|| "Imported recipe".to_string())
+    } else {
+        title
+    };
+
+    let payload = NewRecipe {
+        title: final_title,
+        source: req.url.clone(),
+        r#yield: String::new(),
+        notes: String::new(),
+        ingredients: structured_ingredients,
+        instructions: instruction_strings,
+        equipment,
+        allow_duplicate: false,
+    };
+
+    if req.dry_run {
+        // Caller wants the parsed data but will manage persistence themselves.
+        // Return a transient Recipe (id=0) without writing to the database.
+        let recipe = Recipe {
+            id: 0,
+            title: payload.title,
+            source: payload.source,
+            servings: crate::units::servings_from_yield(&payload.r#yield),
+            r#yield: payload.r#yield,
+            notes: payload.notes,
+            created_at: String::new(),
+            updated_at: String::new(),
+            ingredients: payload.ingredients,
+            instructions: payload.instructions,
+            equipment: crate::equipment::normalize(&payload.equipment),
+            image_path_small: None,
+            image_path_full: None,
+            image_import_status: None,
+            macros: None,
+            share_token: None,
+            prep_reminders: None,
+            is_favorite: false,
+            last_cooked_at: None,
+            times_cooked: 0,
+            avg_rating: None,
+            log_count: 0,
+            deduplicated: false,
+            import_method: Some(method),
+        };
+        return Ok(Json(recipe));
+    }
+
+    let created = recipes::create(State(state.clone()), Actor::Import, Json(payload)).await?;
+    let recipe_id = created.0.id;
+
+    let page_url = page_url.as_deref().unwrap_or(&req.url);
+    attach_image_recording_status(state, recipe_id, page_url, &html, image_url.as_deref()).await?;
+
+    let Json(mut fresh) = recipes::get(
+        State(state.clone()),
+        Path(recipe_id),
+        Query(recipes::GetQuery::default()),
+    )
+    .await?;
+    fresh.import_method = Some(method);
+    Ok(Json(fresh))
+}
+
+/// Stages 2 and 3: structure the extracted lines, then convert to metric.
+async fn llm_structure_ingredients(
+    llm: &LlmClient,
+    http: &reqwest::Client,
+    state: &AppState,
+    llm_settings: &LlmSettings,
+    ingredient_strings: &[String],
+) -> AppResult<Vec<Ingredient>> {
     for (i, ing) in ingredient_strings.iter().enumerate() {
         tracing::debug!("  Ingredient {}: {}", i, ing);
     }
@@ -121,8 +460,8 @@ pub async fn import_from_url(
         "Stage 2: Structuring {} ingredients",
         ingredient_strings.len()
     );
-    let mut structured_ingredients =
-        stage2_structure_ingredients(&llm, &http, &state, &llm_settings, &ingredient_strings)
+    let structured_ingredients =
+        stage2_structure_ingredients(llm, http, state, llm_settings, ingredient_strings)
             .await
             .map_err(|e| {
                 (
@@ -148,8 +487,8 @@ pub async fn import_from_url(
 
     // STAGE 3: Convert to metric
     tracing::info!("Stage 3: Converting to metric");
-    structured_ingredients =
-        stage3_convert_to_metric(&llm, &http, &state, &llm_settings, &structured_ingredients)
+    let structured_ingredients =
+        stage3_convert_to_metric(llm, http, state, llm_settings, &structured_ingredients)
             .await
             .map_err(|e| {
                 (
@@ -173,115 +512,154 @@ pub async fn import_from_url(
         );
     }
 
-    let final_title = if title.trim().is_empty() {
-        fallback_title_from_url(&req.url).unwrap_or_else(|| "Imported recipe".to_string())
-    } else {
-        title
-    };
-
-    let payload = NewRecipe {
-        title: final_title,
-        source: req.url.clone(),
-        r#yield: String::new(),
-        notes: String::new(),
-        ingredients: structured_ingredients,
-        instructions: instruction_strings,
-    };
-
-    if req.dry_run {
-        // Caller wants the parsed data but will manage persistence themselves.
-        // Return a transient Recipe (id=0) without writing to the database.
-        let recipe = Recipe {
-            id: 0,
-            title: payload.title,
-            source: payload.source,
-            r#yield: payload.r#yield,
-            notes: payload.notes,
-            created_at: String::new(),
-            updated_at: String::new(),
-            ingredients: payload.ingredients,
-            instructions: payload.instructions,
-            image_path_small: None,
-            image_path_full: None,
-            macros: None,
-            share_token: None,
-            prep_reminders: None,
-        };
-        return Ok(Json(recipe));
-    }
-
-    let created = recipes::create(State(state.clone()), Json(payload)).await?;
-    let recipe_id = created.0.id;
-
-    if let Err(e) = try_fetch_and_attach_image(&state, recipe_id, &req.url, &html).await {
-        tracing::warn!("image import failed for id {}: {}", recipe_id, e);
-    }
-
-    let fresh = recipes::get(State(state), Path(recipe_id)).await?;
-    Ok(fresh)
+    Ok(structured_ingredients)
 }
 
 /* =========================
  * HTML fetch + plain text
  * ========================= */
 
-async fn fetch_page_text(url: &str) -> Result<(String, String, String), String> {
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(url)
-        .timeout(Duration::from_secs(45))
-        .send()
-        .await
-        .map_err(|e| format!("request failed: {e}"))?;
-
-    if !resp.status().is_success() {
-        return Err(format!("HTTP {} fetching {}", resp.status(), url));
-    }
+struct PageText {
+    /// The page's URL after redirects.
+    url: String,
+    title: String,
+    text: String,
+    html: String,
+}
 
-    let html = resp.text().await.unwrap_or_default();
+async fn fetch_page_text(url: &str, allow_private: bool) -> Result<PageText, FetchError> {
+    let page = safe_fetch::get_page(url, MAX_HTML_BYTES, allow_private).await?;
+    let html = decode_html(&page.body, page.content_type.as_deref());
     let title = extract_title(&html).unwrap_or_default();
-    let text = html_to_plain_text(&html);
-
-    Ok((title, text, html))
+    let text = preclean_text(&html_to_plain_text(&html));
+
+    Ok(PageText {
+        url: page.url.into(),
+        title,
+        text,
+        html,
+    })
 }
 
 /* =========================
  * Image: reuse parse_recipe_image.rs
  * ========================= */
 
-async fn try_fetch_and_attach_image(
+/// Attach the page's image without failing the import, recording the
+/// outcome in `image_import_status` so the client can offer a retry.
+async fn attach_image_recording_status(
     state: &AppState,
     recipe_id: i64,
     page_url: &str,
     html: &str,
-) -> anyhow::Result<()> {
-    if let Some(img_url) = extract_main_image_url(html, page_url) {
-        let client = reqwest::Client::new();
-
-        // Download + generate stable full + small images under:
-        //   media/recipes/<id>/full.webp
-        //   media/recipes/<id>/small.webp
-        let (rel_full, rel_small) =
-            recipes::fetch_and_store_recipe_image(&client, &img_url, state, recipe_id).await?;
-
-        sqlx::query(
-            r"
-        UPDATE recipes
-           SET image_path_small = ?,
-               image_path_full  = ?
-         WHERE id = ?
-        ",
-        )
-        .bind(&rel_small)
-        .bind(&rel_full)
+    image_url: Option<&str>,
+) -> AppResult<()> {
+    let status = match try_fetch_and_attach_image(state, recipe_id, page_url, html, image_url).await
+    {
+        Ok(true) => "ok".to_string(),
+        Ok(false) => "none_found".to_string(),
+        Err(e) => {
+            tracing::warn!("image import failed for id {}: {}", recipe_id, e);
+            format!("failed: {e}")
+        }
+    };
+    record_image_status(state, recipe_id, &status).await
+}
+
+async fn record_image_status(state: &AppState, recipe_id: i64, status: &str) -> AppResult<()> {
+    sqlx::query("UPDATE recipes SET image_import_status = ? WHERE id = ?")
+        .bind(status)
         .bind(recipe_id)
         .execute(&state.pool)
         .await?;
+    Ok(())
+}
+
+/// Returns `false` when the page offers no image to fetch.
+async fn try_fetch_and_attach_image(
+    state: &AppState,
+    recipe_id: i64,
+    page_url: &str,
+    html: &str,
+    image_url: Option<&str>,
+) -> anyhow::Result<bool> {
+    let candidate = image_url
+        .map(str::to_string)
+        .or_else(|| extract_main_image_url(html, page_url));
+
+    let Some(img_url) = candidate else {
+        return Ok(false);
+    };
+    if !state.media.is_available() {
+        anyhow::bail!("media storage is not available");
+    }
+
+    // Download + generate stable full + small images under:
+    //   media/recipes/<id>/full.webp
+    //   media/recipes/<id>/small.webp
+    recipes::fetch_and_store_recipe_image(&img_url, state, recipe_id).await?;
+
+    Ok(true)
+}
+
+/// `POST /recipes/{id}/image/retry`
+///
+/// Re-fetches the recipe's source URL and runs image discovery and download
+/// again, updating `image_import_status`. A failed attempt is recorded in the
+/// status rather than returned as an error.
+///
+/// # Errors
+/// 404 for unknown recipes, 400 `no_source` when the recipe has no http(s)
+/// source, 503 `media_unavailable` while media storage is down.
+pub async fn retry_image_import(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Recipe>> {
+    state.media.ensure_available()?;
+
+    let source: Option<String> =
+        sqlx::query_scalar("SELECT source FROM recipes WHERE id = ? AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&state.pool)
+            .await?;
+    let source = source.ok_or(StatusCode::NOT_FOUND)?;
+    if !(source.starts_with("http://") || source.starts_with("https://")) {
+        return Err(AppError::Code(
+            StatusCode::BAD_REQUEST,
+            "no_source",
+            "recipe has no source URL to fetch an image from".into(),
+        ));
+    }
 
-        return Ok(());
+    match image_source(&state.http, &source, state.config.fetch_allow_private).await {
+        Ok((page_url, html, image_url)) => {
+            attach_image_recording_status(&state, id, &page_url, &html, image_url.as_deref())
+                .await?;
+        }
+        Err(e) => {
+            tracing::warn!("image retry could not fetch {source}: {e}");
+            record_image_status(&state, id, &format!("failed: fetch failed: {e}")).await?;
+        }
     }
 
-    anyhow::bail!("no image candidate found by extract_main_image_url")
+    recipes::get(State(state), Path(id), Query(recipes::GetQuery::default())).await
+}
+
+/// Page URL after redirects, HTML and preferred image URL for a source,
+/// mirroring what import used.
+async fn image_source(
+    http: &reqwest::Client,
+    url: &str,
+    allow_private: bool,
+) -> Result<(String, String, Option<String>), String> {
+    if let Some(video_id) = youtube::video_id(url) {
+        let video = youtube::fetch_video(http, &video_id).await?;
+        return Ok((url.to_string(), String::new(), video.thumbnail_url));
+    }
+    let page = fetch_page_text(url, allow_private)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((page.url, page.html, None))
 }
 
 /* =========================
@@ -293,20 +671,17 @@ async fn stage1_extract(
     http: &reqwest::Client,
     state: &AppState,
     llm_settings: &LlmSettings,
-    content: &str,
-    url: &str,
-    title_guess: &str,
-) -> anyhow::Result<(String, Vec<String>, Vec<String>)> {
-    let user = format!("URL: {url}\nTITLE: {title_guess}\n\nCONTENT:\n{content}");
-
+    user: &str,
+    needs_steps: bool,
+) -> anyhow::Result<(String, Vec<String>, Vec<String>, Vec<String>)> {
     let json = call_llm_with_retry(
         llm,
         http,
         &llm_settings.fallback_model,
         &state.config.system_prompt_extract,
-        &user,
+        user,
         0.1,
-        Duration::from_secs(120),
+        Duration::from_mins(2),
         Some(16_000),
     )
     .await?;
@@ -332,8 +707,14 @@ async fn stage1_extract(
                 .collect::<Vec<String>>()
         });
 
-    let instructions = json
-        .get("instructions")
+    let instructions =
+        normalize_instructions(json.get("instructions").cloned().unwrap_or(JsonValue::Null));
+
+    validate_stage1(&ingredients, &instructions, needs_steps)?;
+
+    // Optional: older prompts and models may omit it.
+    let equipment = json
+        .get("equipment")
         .and_then(|v| v.as_array())
         .map_or_else(Vec::new, |arr| {
             arr.iter()
@@ -342,9 +723,7 @@ async fn stage1_extract(
                 .collect::<Vec<String>>()
         });
 
-    validate_stage1(&ingredients, &instructions)?;
-
-    Ok((title, ingredients, instructions))
+    Ok((title, ingredients, instructions, equipment))
 }
 
 /* =========================
@@ -367,7 +746,7 @@ async fn stage2_structure_ingredients(
         &state.config.system_prompt_structure,
         &input_json,
         0.1,
-        Duration::from_secs(120),
+        Duration::from_mins(2),
         Some(16_000),
     )
     .await?;
@@ -437,7 +816,7 @@ async fn stage3_convert_to_metric(
         &state.config.system_prompt_convert,
         &input_json,
         0.1,
-        Duration::from_secs(120),
+        Duration::from_mins(2),
         Some(16_000),
     )
     .await?;
@@ -492,11 +871,15 @@ async fn call_llm_with_retry(
  * Validation functions
  * ========================= */
 
-fn validate_stage1(ingredients: &[String], instructions: &[String]) -> anyhow::Result<()> {
+fn validate_stage1(
+    ingredients: &[String],
+    instructions: &[String],
+    needs_steps: bool,
+) -> anyhow::Result<()> {
     if ingredients.is_empty() {
         anyhow::bail!("Stage 1 returned no ingredients");
     }
-    if instructions.is_empty() {
+    if needs_steps && instructions.is_empty() {
         anyhow::bail!("Stage 1 returned no instructions");
     }
     // Check for reasonable counts
@@ -530,9 +913,6 @@ const BANNED_UNITS: &[&str] = &[
     "quart",
     "gallon",
 ];
-const PREP_WORDS: &[&str] = &[
-    "sliced", "diced", "minced", "chopped", "grated", "shredded", "softened", "melted",
-];
 
 fn validate_stage2(ingredients: &[Ingredient]) {
     // Check for banned units (warning only, not fatal)
@@ -552,7 +932,7 @@ fn validate_stage2(ingredients: &[Ingredient]) {
         // Check name doesn't contain prep words (warning only)
         if !ing.name.is_empty() {
             let name_lower = ing.name.to_lowercase();
-            for prep_word in PREP_WORDS {
+            for prep_word in crate::units::PREP_WORDS {
                 if name_lower.contains(prep_word) {
                     tracing::warn!(
                         "Stage 2 validation: ingredient name '{}' contains prep word '{}'",
@@ -624,6 +1004,10 @@ fn validate_stage3(ingredients: &[Ingredient]) -> anyhow::Result<()> {
 #[derive(Default, Clone)]
 pub struct ExtractRaw {
     pub title: Option<String>,
+    /// From `yield` or `servings`; a bare number becomes "N servings".
+    pub r#yield: Option<String>,
+    /// Tips and notes, one per line when given as a list.
+    pub notes: Option<String>,
     pub ingredients: JsonValue,
     pub instructions: JsonValue,
 }
@@ -637,6 +1021,10 @@ impl ExtractRaw {
 
         Self {
             title,
+            r#yield: ["yield", "servings"]
+                .iter()
+                .find_map(|k| v.get(*k).and_then(yield_text)),
+            notes: v.get("notes").and_then(notes_text),
             ingredients: v.get("ingredients").cloned().unwrap_or(JsonValue::Null),
             instructions: v.get("instructions").cloned().unwrap_or(JsonValue::Null),
         }
@@ -650,40 +1038,72 @@ impl ExtractRaw {
     }
 }
 
+/// Yield text from an LLM value: numbers (also as strings) are servings,
+/// anything else ("1 loaf") is kept as written.
+fn yield_text(v: &JsonValue) -> Option<String> {
+    let servings = |n: f64| (n > 0.0).then(|| servings_yield_text(n));
+    match v {
+        JsonValue::Number(n) => n.as_f64().and_then(servings),
+        JsonValue::String(s) => {
+            let s = preclean_text(s).trim().to_string();
+            match parse_quantity(&s) {
+                Some(n) if s.chars().all(|c| c.is_ascii_digit() || ".,/ ".contains(c)) => {
+                    servings(n)
+                }
+                _ => Some(s).filter(|s| !s.is_empty()),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Notes from an LLM value: a string, or a list of strings, one per line.
+fn notes_text(v: &JsonValue) -> Option<String> {
+    let parts: Vec<&str> = match v {
+        JsonValue::String(s) => vec![s],
+        JsonValue::Array(items) => items.iter().filter_map(JsonValue::as_str).collect(),
+        _ => return None,
+    };
+    let text = parts
+        .iter()
+        .flat_map(|p| p.lines())
+        .map(|l| strip_tags(&preclean_text(l)))
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(text).filter(|t| !t.is_empty())
+}
+
 pub struct ExtractOut {
     pub ingredients: Vec<Ingredient>,
     pub instructions: Vec<String>,
 }
 
+/// Instruction lines from an LLM or schema.org value, as plain text: any
+/// markup the source carried is stripped so stored steps are clean, and
+/// inline numbering, fragments and overlong steps are tidied by [`tidy_steps`].
 pub fn normalize_instructions(v: JsonValue) -> Vec<String> {
-    match v {
+    let clean = |s: &str| Some(strip_tags(&preclean_text(s))).filter(|t| !t.is_empty());
+    let steps = match v {
         JsonValue::Array(items) => items
             .into_iter()
             .filter_map(|x| match x {
-                JsonValue::String(s) => {
-                    let t = s.trim().to_string();
-                    (!t.is_empty()).then_some(t)
-                }
+                JsonValue::String(s) => clean(&s),
                 // {"section": "Sauce"} → "## Sauce"
                 JsonValue::Object(m) => m
                     .get("section")
                     .and_then(|v| v.as_str())
-                    .map(str::trim)
-                    .filter(|s| !s.is_empty())
+                    .and_then(clean)
                     .map(|s| format!("## {s}")),
                 JsonValue::Number(n) => Some(n.to_string()),
                 JsonValue::Bool(b) => Some(b.to_string()),
                 _ => None,
             })
             .collect(),
-        JsonValue::String(s) => s
-            .lines()
-            .map(str::trim)
-            .filter(|l| !l.is_empty())
-            .map(std::string::ToString::to_string)
-            .collect(),
+        JsonValue::String(s) => s.lines().filter_map(clean).collect(),
         _ => Vec::new(),
-    }
+    };
+    tidy_steps(steps)
 }
 
 fn normalize_unit(unit: &str) -> String {
@@ -702,30 +1122,27 @@ fn normalize_unit(unit: &str) -> String {
 }
 
 pub fn normalize_ingredients(v: JsonValue) -> Vec<Ingredient> {
+    let text = |v: JsonValue| v.as_str().map(|s| preclean_text(s).trim().to_string());
     match v {
         JsonValue::Array(items) => items
             .into_iter()
             .filter_map(|x| match x {
                 JsonValue::Object(mut m) => {
                     // Section header: {"section": "Sauce"}
-                    if let Some(s) = m.get("section").and_then(|v| v.as_str()) {
-                        let label = s.trim().to_string();
-                        if !label.is_empty() {
-                            return Some(Ingredient {
-                                section: Some(label),
-                                quantity: None,
-                                unit: None,
-                                name: String::new(),
-                                prep: None,
-                                raw: false,
-                            });
-                        }
+                    if let Some(label) = m.remove("section").and_then(text)
+                        && !label.is_empty()
+                    {
+                        return Some(Ingredient {
+                            section: Some(label),
+                            quantity: None,
+                            unit: None,
+                            name: String::new(),
+                            prep: None,
+                            raw: false,
+                        });
                     }
 
-                    let name = m
-                        .remove("name")
-                        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
-                        .unwrap_or_default();
+                    let name = m.remove("name").and_then(text).unwrap_or_default();
 
                     if name.is_empty() {
                         return None;
@@ -737,20 +1154,25 @@ pub fn normalize_ingredients(v: JsonValue) -> Vec<Ingredient> {
                         .or_else(|| m.remove("amount"))
                         .and_then(|v| match v {
                             JsonValue::Number(n) => n.as_f64(),
-                            JsonValue::String(s) => s.trim().replace(',', ".").parse::<f64>().ok(),
+                            JsonValue::String(s) => parse_quantity(&preclean_text(&s)),
                             _ => None,
                         });
 
                     let unit = m
                         .remove("unit")
-                        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
+                        .and_then(text)
                         .filter(|s| !s.is_empty())
                         .map(|u| normalize_unit(&u));
+                    // Cups, ounces and friends the LLM let through become ml/g.
+                    let (unit, quantity) = match unit.as_deref().and_then(canon_unit_str) {
+                        Some(_) => {
+                            let (u, q) = to_canonical_qty_unit(unit.as_deref(), quantity);
+                            (u.map(str::to_string), q)
+                        }
+                        None => (unit, quantity),
+                    };
 
-                    let prep = m
-                        .remove("prep")
-                        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
-                        .filter(|s| !s.is_empty());
+                    let prep = m.remove("prep").and_then(text).filter(|s| !s.is_empty());
 
                     Some(Ingredient {
                         section: None,
@@ -767,3 +1189,121 @@ pub fn normalize_ingredients(v: JsonValue) -> Vec<Ingredient> {
         _ => Vec::new(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extract_raw_reads_yield_and_notes() {
+        let raw = |v: JsonValue| {
+            let r = ExtractRaw::from_json(&v);
+            (r.r#yield, r.notes)
+        };
+        let some = |s: &str| Some(s.to_string());
+
+        assert_eq!(raw(json!({"yield": 4})), (some("4 servings"), None));
+        assert_eq!(raw(json!({"servings": "6"})), (some("6 servings"), None));
+        assert_eq!(raw(json!({"servings": 1})), (some("1 serving"), None));
+        assert_eq!(raw(json!({"yield": "1 loaf"})), (some("1 loaf"), None));
+        assert_eq!(
+            raw(json!({"yield": "Serves 4", "servings": 2})),
+            (some("Serves 4"), None)
+        );
+        assert_eq!(raw(json!({"yield": 0, "servings": ""})), (None, None));
+        assert_eq!(
+            raw(json!({"yield": null, "notes": "Freezes well.\n\n<b>Tip:</b> add lime."})),
+            (None, some("Freezes well.\nTip: add lime."))
+        );
+        assert_eq!(
+            raw(json!({"notes": ["Use ripe bananas.", " ", "Nuts are optional."]})),
+            (None, some("Use ripe bananas.\nNuts are optional."))
+        );
+        assert_eq!(raw(json!({"notes": 3})), (None, None));
+    }
+
+    #[test]
+    fn normalize_ingredients_precleans_pasted_text() {
+        let got = normalize_ingredients(json!([
+            {"section": "\u{FEFF}Sauce"},
+            {"quantity": "1\u{00A0}000", "unit": "g", "name": "flour"},
+            {"quantity": "2,5", "unit": "\u{200B}tbsp", "name": "olive\u{00A0}oil"},
+            {"quantity": 1, "name": "\u{201C}San Marzano\u{201D} tomatoes", "prep": "crushed\u{00AD}"},
+        ]));
+
+        assert_eq!(got[0].section.as_deref(), Some("Sauce"));
+        assert_eq!(
+            got[1].quantity, None,
+            "a spaced thousands group stays unparsed"
+        );
+        assert_eq!(got[2].quantity, Some(2.5));
+        assert_eq!(got[2].unit.as_deref(), Some("tbsp"));
+        assert_eq!(got[2].name, "olive oil");
+        assert_eq!(got[3].name, "\"San Marzano\" tomatoes");
+        assert_eq!(got[3].prep.as_deref(), Some("crushed"));
+    }
+
+    #[test]
+    fn normalize_instructions_precleans_pasted_text() {
+        let got = normalize_instructions(json!(
+            "\u{FEFF}Preheat to 180\u{00A0}\u{00B0}C.\nAdd 1\u{2044}2 cup of \u{2018}stock\u{2019}."
+        ));
+        assert_eq!(
+            got,
+            ["Preheat to 180 \u{00B0}C.", "Add 1/2 cup of 'stock'."]
+        );
+    }
+
+    #[test]
+    fn paste_url_accepts_bare_and_padded_urls() {
+        assert_eq!(
+            paste_url("  https://site.com/recipe \n").as_deref(),
+            Some("https://site.com/recipe")
+        );
+        assert_eq!(
+            paste_url("www.site.com/recipe").as_deref(),
+            Some("https://www.site.com/recipe")
+        );
+        assert_eq!(
+            paste_url("site.co.uk/pie?x=1").as_deref(),
+            Some("https://site.co.uk/pie?x=1")
+        );
+        assert_eq!(
+            paste_url("HTTP://localhost:8080/r").as_deref(),
+            Some("http://localhost:8080/r")
+        );
+
+        for text in [
+            "",
+            "1.5",
+            "salt,pepper",
+            "2 eggs",
+            "flour.",
+            "see site.com/recipe",
+            "v1.2",
+        ] {
+            assert_eq!(paste_url(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn detect_paste_tells_recipes_from_ingredient_lists() {
+        assert_eq!(detect_paste(" www.site.com/recipe "), PasteKind::Url);
+        assert_eq!(
+            detect_paste("Pancakes\n\nIngredients:\n2 eggs\n200 g flour\n\n## Method\nMix."),
+            PasteKind::RecipeText
+        );
+        assert_eq!(
+            detect_paste(
+                "2 eggs\n200 g flour\nWhisk the eggs with the flour and the milk until \
+                 smooth, then rest the batter for half an hour."
+            ),
+            PasteKind::RecipeText
+        );
+        assert_eq!(
+            detect_paste("2 eggs\n200 g flour\n300 ml milk"),
+            PasteKind::IngredientList
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use futures_util::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json as SqlJson;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    activity::{self, Actor, Entity, Event},
+    error::{AppError, AppResult},
+    extract::Json,
+    media_path,
+    models::{AppState, Ingredient, NewRecipe, PrepReminder, Recipe, RecipeMacros, RecipeRow},
+    routes::recipes::{RECIPE_COLS, recipe_content_hash, save_recipe_image},
+    units::servings_from_yield,
+};
+
+/// `format` field identifying a blaz backup document.
+pub const BACKUP_FORMAT: &str = "blaz-backup";
+/// Schema version of the backup document; bump on incompatible changes.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// One recipe in a backup, with everything needed to recreate it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupRecipe {
+    pub title: String,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default, rename = "yield")]
+    pub r#yield: String,
+    #[serde(default)]
+    pub servings: Option<f64>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub ingredients: Vec<Ingredient>,
+    #[serde(default)]
+    pub instructions: Vec<String>,
+    #[serde(default)]
+    pub equipment: Vec<String>,
+    #[serde(default)]
+    pub macros: Option<RecipeMacros>,
+    #[serde(default)]
+    pub prep_reminders: Option<Vec<PrepReminder>>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+    /// Media-relative image paths, e.g. `recipes/12/full.webp`.
+    #[serde(default)]
+    pub image_path_full: Option<String>,
+    #[serde(default)]
+    pub image_path_small: Option<String>,
+    /// The full-size image as a `data:image/webp;base64,` URI, when exported
+    /// with `include_images=base64`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl From<Recipe> for BackupRecipe {
+    fn from(r: Recipe) -> Self {
+        Self {
+            title: r.title,
+            source: r.source,
+            r#yield: r.r#yield,
+            servings: r.servings,
+            notes: r.notes,
+            ingredients: r.ingredients,
+            instructions: r.instructions,
+            equipment: r.equipment,
+            macros: r.macros,
+            prep_reminders: r.prep_reminders,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+            image_path_full: r.image_path_full,
+            image_path_small: r.image_path_small,
+            image: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    /// `base64` inlines each full-size image; absent or `none` only
+    /// references them by media path.
+    pub include_images: Option<String>,
+}
+
+/// `GET /recipes/export[?include_images=base64]`
+///
+/// Every live recipe as a JSON backup document:
+/// `{"format": "blaz-backup", "version": 1, "exported_at", "recipes": [...]}`.
+/// The body is streamed one recipe at a time, so with inlined images only one
+/// image is held in memory at once.
+///
+/// # Errors
+/// 400 for an unknown `include_images`; Err if querying the database fails.
+pub async fn export(
+    State(state): State<AppState>,
+    Query(q): Query<ExportQuery>,
+) -> AppResult<Response> {
+    let inline_images = match q.include_images.as_deref().map(str::trim) {
+        None | Some("" | "none") => false,
+        Some("base64") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("unknown include_images: {other}"),
+            )
+                .into());
+        }
+    };
+
+    let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE deleted_at IS NULL ORDER BY id");
+    let rows: Vec<RecipeRow> = sqlx::query_as(&sql).fetch_all(&state.pool).await?;
+
+    let now = chrono::Utc::now();
+    let head = format!(
+        r#"{{"format":"{BACKUP_FORMAT}","version":{BACKUP_VERSION},"exported_at":"{}","recipes":["#,
+        now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    );
+    let media_dir = state.config.media_dir.clone();
+    let body = stream::once(async move { Ok::<_, io::Error>(head) })
+        .chain(
+            stream::iter(rows.into_iter().enumerate())
+                .then(move |(i, row)| recipe_chunk(media_dir.clone(), i, row, inline_images)),
+        )
+        .chain(stream::once(async { Ok("]}".to_string()) }));
+
+    let filename = format!("blaz-backup-{}.json", now.format("%Y-%m-%d"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+/// The JSON for the `i`th recipe, comma-led after the first.
+async fn recipe_chunk(
+    media_dir: PathBuf,
+    i: usize,
+    row: RecipeRow,
+    inline_images: bool,
+) -> io::Result<String> {
+    let mut recipe = BackupRecipe::from(Recipe::from(row));
+    if inline_images && let Some(rel) = recipe.image_path_full.as_deref() {
+        recipe.image = image_data_uri(&media_dir, rel).await;
+    }
+    let json = serde_json::to_string(&recipe)?;
+    Ok(if i == 0 { json } else { format!(",{json}") })
+}
+
+/// A media file as a webp data URI; `None` (logged) if it can't be read, so a
+/// missing image doesn't abort the whole backup.
+async fn image_data_uri(media_dir: &Path, rel: &str) -> Option<String> {
+    let read = async { tokio::fs::read(media_path::resolve(media_dir, rel)?).await };
+    match read.await {
+        Ok(bytes) => Some(format!(
+            "data:image/webp;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        )),
+        Err(e) => {
+            tracing::warn!(path = rel, error = %e, "backup: skipping unreadable image");
+            None
+        }
+    }
+}
+
+/// A backup document as uploaded. Recipes stay raw so one malformed entry is
+/// reported instead of rejecting the whole file.
+#[derive(Deserialize)]
+struct BackupDoc {
+    format: String,
+    version: u32,
+    #[serde(default)]
+    recipes: Vec<Value>,
+}
+
+/// What to do with a backup recipe whose title and source match a live one.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateMode {
+    /// Leave the existing recipe alone and report the entry as skipped.
+    #[default]
+    Skip,
+    /// Import it anyway as a new recipe.
+    Duplicate,
+    /// Replace the existing recipe's content with the backup's.
+    Overwrite,
+}
+
+#[derive(Deserialize)]
+pub struct ImportBackupQuery {
+    #[serde(default)]
+    pub mode: DuplicateMode,
+}
+
+#[derive(Serialize)]
+struct BackupFailure {
+    index: usize,
+    title: String,
+    /// `invalid_recipe` or `database_error`.
+    error_code: &'static str,
+    detail: String,
+}
+
+#[derive(Serialize)]
+struct BackupSkip {
+    index: usize,
+    title: String,
+    /// The live recipe with the same title and source.
+    existing_id: i64,
+}
+
+/// The recipe was restored, but its image could not be.
+#[derive(Serialize)]
+struct BackupImageFailure {
+    index: usize,
+    title: String,
+    recipe_id: i64,
+    detail: String,
+}
+
+#[derive(Serialize, Default)]
+struct ImportBackupResponse {
+    imported_count: usize,
+    overwritten_count: usize,
+    failed: Vec<BackupFailure>,
+    skipped: Vec<BackupSkip>,
+    image_failed: Vec<BackupImageFailure>,
+}
+
+enum Restored {
+    Imported(i64),
+    Overwritten(i64),
+    Skipped(i64),
+}
+
+/// POST /recipes/import-backup?mode=skip|duplicate|overwrite
+///
+/// Recreate recipes from a document produced by [`export`]. Inlined images
+/// are re-encoded; otherwise an image referenced by media path is reused if
+/// the file is still there. Only an unreadable document or an unsupported
+/// version is a 400; per-recipe problems are reported in the response.
+///
+/// # Errors
+/// 400 `invalid_json`, `invalid_backup` or `unsupported_version`.
+pub async fn import_backup(
+    State(state): State<AppState>,
+    Query(q): Query<ImportBackupQuery>,
+    body: String,
+) -> AppResult<Response> {
+    let doc: BackupDoc = serde_json::from_str(&body).map_err(|e| {
+        AppError::Code(
+            StatusCode::BAD_REQUEST,
+            "invalid_json",
+            format!("Invalid JSON: {e}"),
+        )
+    })?;
+    if doc.format != BACKUP_FORMAT {
+        return Err(AppError::Code(
+            StatusCode::BAD_REQUEST,
+            "invalid_backup",
+            format!("not a blaz backup (format '{}')", doc.format),
+        ));
+    }
+    if doc.version == 0 || doc.version > BACKUP_VERSION {
+        return Err(AppError::Code(
+            StatusCode::BAD_REQUEST,
+            "unsupported_version",
+            format!(
+                "backup version {} is not supported (expected 1 to {BACKUP_VERSION})",
+                doc.version
+            ),
+        ));
+    }
+
+    let mut report = ImportBackupResponse::default();
+    for (index, entry) in doc.recipes.into_iter().enumerate() {
+        let title = entry
+            .get("title")
+            .and_then(Value::as_str)
+            .unwrap_or("Untitled Recipe")
+            .to_string();
+        let recipe: BackupRecipe = match serde_json::from_value(entry) {
+            Ok(recipe) => recipe,
+            Err(e) => {
+                report.failed.push(BackupFailure {
+                    index,
+                    title,
+                    error_code: "invalid_recipe",
+                    detail: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let image = recipe.image.clone();
+        let image_path = recipe.image_path_full.clone();
+        let recipe_id = match restore_recipe(&state, recipe, q.mode).await {
+            Ok(Restored::Skipped(existing_id)) => {
+                report.skipped.push(BackupSkip {
+                    index,
+                    title,
+                    existing_id,
+                });
+                continue;
+            }
+            Ok(Restored::Imported(id)) => {
+                report.imported_count += 1;
+                id
+            }
+            Ok(Restored::Overwritten(id)) => {
+                report.overwritten_count += 1;
+                id
+            }
+            Err(e) => {
+                tracing::error!(?e, %title, "backup import failed");
+                report.failed.push(BackupFailure {
+                    index,
+                    title,
+                    error_code: "database_error",
+                    detail: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if let Err(e) =
+            restore_image(&state, recipe_id, image.as_deref(), image_path.as_deref()).await
+        {
+            tracing::warn!(recipe_id, error = %e, "backup: image not restored");
+            report.image_failed.push(BackupImageFailure {
+                index,
+                title,
+                recipe_id,
+                detail: format!("{e:#}"),
+            });
+        }
+    }
+
+    tracing::info!(
+        "Backup import complete: {} imported, {} overwritten, {} skipped, {} failed",
+        report.imported_count,
+        report.overwritten_count,
+        report.skipped.len(),
+        report.failed.len()
+    );
+    Ok(Json(report).into_response())
+}
+
+/// Insert `recipe`, or handle a live recipe with the same title and source
+/// per `mode`.
+async fn restore_recipe(
+    state: &AppState,
+    recipe: BackupRecipe,
+    mode: DuplicateMode,
+) -> sqlx::Result<Restored> {
+    let existing: Option<i64> = if mode == DuplicateMode::Duplicate {
+        None
+    } else {
+        sqlx::query_scalar(
+            "SELECT id FROM recipes WHERE title = ? AND source = ? AND deleted_at IS NULL \
+             ORDER BY id LIMIT 1",
+        )
+        .bind(&recipe.title)
+        .bind(&recipe.source)
+        .fetch_optional(&state.pool)
+        .await?
+    };
+    if let (Some(id), DuplicateMode::Skip) = (existing, mode) {
+        return Ok(Restored::Skipped(id));
+    }
+
+    let content_hash = recipe_content_hash(&NewRecipe {
+        title: recipe.title.clone(),
+        source: recipe.source.clone(),
+        r#yield: recipe.r#yield.clone(),
+        notes: recipe.notes.clone(),
+        ingredients: recipe.ingredients.clone(),
+        instructions: recipe.instructions.clone(),
+        equipment: recipe.equipment.clone(),
+        allow_duplicate: true,
+    });
+    let servings = recipe
+        .servings
+        .or_else(|| servings_from_yield(&recipe.r#yield));
+    let ingredients = SqlJson(&recipe.ingredients);
+    let instructions = SqlJson(&recipe.instructions);
+    let equipment = SqlJson(crate::equipment::normalize(&recipe.equipment));
+    let macros = recipe.macros.as_ref().map(SqlJson);
+    let prep_reminders = recipe.prep_reminders.as_ref().map(SqlJson);
+
+    let (id, outcome, verb) = if let Some(id) = existing {
+        sqlx::query(
+            r#"
+            UPDATE recipes
+               SET "yield" = ?, servings = ?, notes = ?, ingredients = ?, instructions = ?,
+                   equipment = ?, macros = ?, prep_reminders = ?, content_hash = ?,
+                   updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?
+            "#,
+        )
+        .bind(&recipe.r#yield)
+        .bind(servings)
+        .bind(&recipe.notes)
+        .bind(ingredients)
+        .bind(instructions)
+        .bind(equipment)
+        .bind(macros)
+        .bind(prep_reminders)
+        .bind(&content_hash)
+        .bind(id)
+        .execute(&state.pool)
+        .await?;
+        (id, Restored::Overwritten(id), "Overwrote")
+    } else {
+        let id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO recipes (title, source, "yield", servings, notes, ingredients, instructions,
+                                 equipment, macros, prep_reminders, content_hash, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                    COALESCE(NULLIF(?, ''), CURRENT_TIMESTAMP),
+                    COALESCE(NULLIF(?, ''), CURRENT_TIMESTAMP))
+            RETURNING id
+            "#,
+        )
+        .bind(&recipe.title)
+        .bind(&recipe.source)
+        .bind(&recipe.r#yield)
+        .bind(servings)
+        .bind(&recipe.notes)
+        .bind(ingredients)
+        .bind(instructions)
+        .bind(equipment)
+        .bind(macros)
+        .bind(prep_reminders)
+        .bind(&content_hash)
+        .bind(&recipe.created_at)
+        .bind(&recipe.updated_at)
+        .fetch_one(&state.pool)
+        .await?;
+        (id, Restored::Imported(id), "Restored")
+    };
+
+    activity::record(
+        state,
+        Actor::Import,
+        Event::new(
+            Entity::Recipe,
+            id,
+            "import",
+            format!("{verb} '{}' from a backup", recipe.title),
+        ),
+    )
+    .await;
+    Ok(outcome)
+}
+
+/// Attach the backup's image to `recipe_id`: the inlined data URI if there
+/// is one, else the referenced media file if it still exists.
+async fn restore_image(
+    state: &AppState,
+    recipe_id: i64,
+    data_uri: Option<&str>,
+    media_rel: Option<&str>,
+) -> anyhow::Result<()> {
+    let bytes = if let Some(uri) = data_uri {
+        let (_, encoded) = uri
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(";base64,"))
+            .ok_or_else(|| anyhow::anyhow!("image is not a base64 data URI"))?;
+        base64::engine::general_purpose::STANDARD.decode(encoded.trim())?
+    } else if let Some(rel) = media_rel {
+        let path = media_path::resolve(&state.config.media_dir, rel)?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            // Backed up by path from another server; nothing to restore.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    } else {
+        return Ok(());
+    };
+    save_recipe_image(state, recipe_id, bytes).await
+}
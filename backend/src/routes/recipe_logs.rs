@@ -0,0 +1,122 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::NaiveDate;
+
+use crate::{
+    activity::{self, Actor, Entity, Event},
+    error::{AppError, AppResult},
+    extract::Json,
+    models::{AppState, NewRecipeLog, RecipeLog},
+};
+
+const LOG_COLS: &str = "id, recipe_id, date, rating, note, created_at";
+
+fn bad_request(msg: &str) -> AppError {
+    (StatusCode::BAD_REQUEST, msg.to_string()).into()
+}
+
+/// Title of a live recipe, or 404.
+async fn recipe_title(state: &AppState, id: i64) -> AppResult<String> {
+    sqlx::query_scalar(r"SELECT title FROM recipes WHERE id = ? AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into())
+}
+
+/// GET /recipes/{id}/logs
+/// The cooking log of a recipe, most recent first.
+///
+/// # Errors
+/// 404 if the recipe doesn't exist; Err if querying the db fails.
+pub async fn list(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<RecipeLog>>> {
+    recipe_title(&state, id).await?;
+    let sql = format!(
+        "SELECT {LOG_COLS} FROM recipe_logs WHERE recipe_id = ? ORDER BY date DESC, id DESC"
+    );
+    let rows: Vec<RecipeLog> = sqlx::query_as(&sql).bind(id).fetch_all(&state.pool).await?;
+    Ok(Json(rows))
+}
+
+/// POST /recipes/{id}/logs  `{ "date": "2024-03-02", "rating": 4, "note": "too salty" }`
+///
+/// # Errors
+/// 400 for a rating outside 1-5 or a malformed or future date; 404 if the
+/// recipe doesn't exist; Err if the insert fails.
+pub async fn create(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Json(req): Json<NewRecipeLog>,
+) -> AppResult<Json<RecipeLog>> {
+    if req.rating.is_some_and(|r| !(1..=5).contains(&r)) {
+        return Err(bad_request("rating must be between 1 and 5"));
+    }
+    let today = chrono::Local::now().date_naive();
+    let date = match req.date.as_deref() {
+        None => today,
+        Some(raw) => NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+            .map_err(|_| bad_request("date must be YYYY-MM-DD"))?,
+    };
+    if date > today {
+        return Err(bad_request("date is in the future"));
+    }
+    let title = recipe_title(&state, id).await?;
+
+    let sql = format!(
+        "INSERT INTO recipe_logs (recipe_id, date, rating, note) VALUES (?, ?, ?, ?)
+         RETURNING {LOG_COLS}"
+    );
+    let log: RecipeLog = sqlx::query_as(&sql)
+        .bind(id)
+        .bind(date.to_string())
+        .bind(req.rating)
+        .bind(req.note.trim())
+        .fetch_one(&state.pool)
+        .await?;
+
+    let rating = log.rating.map(|r| format!(", {r}/5")).unwrap_or_default();
+    let summary = format!("Logged cooking '{title}' on {}{rating}", log.date);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "log", summary),
+    )
+    .await;
+    Ok(Json(log))
+}
+
+/// `DELETE /recipes/{id}/logs/{log_id}`
+///
+/// # Errors
+/// 404 if the recipe has no such log entry; Err if the delete fails.
+pub async fn delete(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path((id, log_id)): Path<(i64, i64)>,
+) -> AppResult<StatusCode> {
+    let date: Option<String> = sqlx::query_scalar(
+        r"DELETE FROM recipe_logs WHERE id = ? AND recipe_id = ? RETURNING date",
+    )
+    .bind(log_id)
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?;
+    let Some(date) = date else {
+        return Err(StatusCode::NOT_FOUND.into());
+    };
+
+    let summary = format!("Removed cooking log entry from {date}");
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "unlog", summary),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
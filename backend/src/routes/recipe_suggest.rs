@@ -0,0 +1,246 @@
+//! "What can I cook": rank recipes by how much of them the given ingredients
+//! cover.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use axum::{extract::State, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json as SqlJson;
+
+use crate::{
+    error::AppResult,
+    extract::Json,
+    models::{AppState, Ingredient},
+    routes::pantry::pantry_names,
+    units::normalize_name,
+};
+
+/// Ingredients assumed to be at hand even without a pantry entry.
+const STAPLES: [&str; 8] = [
+    "salt",
+    "pepper",
+    "black pepper",
+    "salt and pepper",
+    "water",
+    "oil",
+    "olive oil",
+    "sugar",
+];
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+#[derive(Deserialize)]
+pub struct SuggestReq {
+    pub ingredients: Vec<String>,
+    /// Number of recipes to return (default 10, at most 50).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CandidateRow {
+    id: i64,
+    title: String,
+    image_path_small: Option<String>,
+    ingredients: SqlJson<Vec<Ingredient>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Suggestion {
+    pub recipe_id: i64,
+    pub title: String,
+    pub image_path_small: Option<String>,
+    /// Share of the recipe's (non-staple) ingredients on hand, 0-1.
+    pub match_score: f64,
+    pub matched_ingredients: Vec<String>,
+    pub missing_ingredients: Vec<String>,
+}
+
+/// Lowercase words of a name, crudely singularized so "lemons" meets "lemon".
+fn tokens(name: &str) -> HashSet<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            if w.len() > 4 && w.ends_with("oes") {
+                w[..w.len() - 2].to_string()
+            } else if w.len() > 3 && w.ends_with('s') && !w.ends_with("ss") {
+                w[..w.len() - 1].to_string()
+            } else {
+                w.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Whether something on hand covers a recipe ingredient: all words of one
+/// appear in the other ("chicken" covers "chicken thighs").
+fn covers(have: &HashSet<String>, need: &HashSet<String>) -> bool {
+    if have.is_empty() || need.is_empty() {
+        return false;
+    }
+    have.is_subset(need) || need.is_subset(have)
+}
+
+/// Score one recipe's normalized ingredient names against what is on hand.
+/// Staples are ignored. `None` when nothing matches.
+fn score(
+    names: &[String],
+    have: &[HashSet<String>],
+    staples: &HashSet<String>,
+) -> Option<(f64, Vec<String>, Vec<String>)> {
+    let mut matched = Vec::new();
+    let mut missing = Vec::new();
+    for name in names {
+        if staples.contains(name) || matched.contains(name) || missing.contains(name) {
+            continue;
+        }
+        let need = tokens(name);
+        if have.iter().any(|h| covers(h, &need)) {
+            matched.push(name.clone());
+        } else {
+            missing.push(name.clone());
+        }
+    }
+    if matched.is_empty() {
+        return None;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let ratio = matched.len() as f64 / (matched.len() + missing.len()) as f64;
+    Some((ratio, matched, missing))
+}
+
+/// Best match first; equal scores go to the recipe missing fewer
+/// ingredients, then to the title.
+fn rank(a: &Suggestion, b: &Suggestion) -> Ordering {
+    b.match_score
+        .total_cmp(&a.match_score)
+        .then(
+            a.missing_ingredients
+                .len()
+                .cmp(&b.missing_ingredients.len()),
+        )
+        .then_with(|| a.title.cmp(&b.title))
+}
+
+/// `POST /recipes/suggest`  `{ "ingredients": ["chicken", "rice"], "limit": 10 }`
+/// Recipes that can be made (mostly) from the given ingredients.
+///
+/// # Errors
+///
+/// 400 when no ingredient is given; Err if querying the db fails
+pub async fn suggest(
+    State(state): State<AppState>,
+    Json(req): Json<SuggestReq>,
+) -> AppResult<Json<Vec<Suggestion>>> {
+    let have: Vec<HashSet<String>> = req
+        .ingredients
+        .iter()
+        .map(|i| tokens(&normalize_name(i)))
+        .filter(|t| !t.is_empty())
+        .collect();
+    if have.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "give at least one ingredient".to_string(),
+        )
+            .into());
+    }
+    let mut staples = pantry_names(&state.pool).await?;
+    staples.extend(STAPLES.iter().map(|s| (*s).to_string()));
+
+    let rows: Vec<CandidateRow> = sqlx::query_as(
+        r"SELECT id, title, image_path_small, ingredients FROM recipes WHERE deleted_at IS NULL",
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut suggestions: Vec<Suggestion> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let names: Vec<String> = row
+                .ingredients
+                .0
+                .iter()
+                .filter(|i| i.section.is_none())
+                .map(|i| normalize_name(&i.name))
+                .filter(|n| !n.is_empty())
+                .collect();
+            let (match_score, matched_ingredients, missing_ingredients) =
+                score(&names, &have, &staples)?;
+            Some(Suggestion {
+                recipe_id: row.id,
+                title: row.title,
+                image_path_small: row.image_path_small,
+                match_score,
+                matched_ingredients,
+                missing_ingredients,
+            })
+        })
+        .collect();
+    suggestions.sort_by(rank);
+    suggestions.truncate(req.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT));
+    Ok(Json(suggestions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn have(items: &[&str]) -> Vec<HashSet<String>> {
+        items.iter().map(|i| tokens(i)).collect()
+    }
+
+    fn names(items: &[&str]) -> Vec<String> {
+        items.iter().map(|i| (*i).to_string()).collect()
+    }
+
+    fn suggestion(title: &str, items: &[&str], on_hand: &[&str]) -> Option<Suggestion> {
+        let staples: HashSet<String> = STAPLES.iter().map(|s| (*s).to_string()).collect();
+        let (match_score, matched_ingredients, missing_ingredients) =
+            score(&names(items), &have(on_hand), &staples)?;
+        Some(Suggestion {
+            recipe_id: 0,
+            title: title.to_string(),
+            image_path_small: None,
+            match_score,
+            matched_ingredients,
+            missing_ingredients,
+        })
+    }
+
+    #[test]
+    fn test_score_matches_words_and_ignores_staples() {
+        let staples: HashSet<String> = STAPLES.iter().map(|s| (*s).to_string()).collect();
+        let (ratio, matched, missing) = score(
+            &names(&["chicken thighs", "rice", "lemons", "salt", "garlic"]),
+            &have(&["chicken", "basmati rice", "lemon"]),
+            &staples,
+        )
+        .unwrap();
+        assert_eq!(matched, ["chicken thighs", "rice", "lemons"]);
+        assert_eq!(missing, ["garlic"]);
+        assert!((ratio - 0.75).abs() < 1e-9);
+
+        // No substring matches inside other words.
+        assert!(score(&names(&["licorice"]), &have(&["rice"]), &staples).is_none());
+    }
+
+    #[test]
+    fn test_rank_breaks_ties_by_fewer_missing() {
+        let on_hand = ["chicken", "rice", "lemon", "garlic"];
+        let mut ranked: Vec<Suggestion> = [
+            suggestion("Big", &["chicken", "rice", "onion", "cream"], &on_hand),
+            suggestion("Small", &["chicken", "onion"], &on_hand),
+            suggestion("Full", &["chicken", "lemon", "salt"], &on_hand),
+            suggestion("None", &["beef"], &on_hand),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        ranked.sort_by(rank);
+        let titles: Vec<&str> = ranked.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["Full", "Small", "Big"]);
+    }
+}
@@ -1,20 +1,31 @@
-use crate::llm::LlmClient;
+use crate::activity::{self, Actor, Entity, Event};
+use crate::extract::Json;
+use crate::image_cache;
+use crate::llm::{LlmClient, RetryPolicy};
+use crate::macro_jobs::{JobFailure, JobStatus as MacroJobStatus};
+use crate::media_txn::MediaTxn;
 use crate::routes::settings::LlmSettings;
+use crate::routes::shopping::{self, InIngredient, MergeReq, MergeResult, StoreField};
 use axum::{
-    Json,
-    extract::{Multipart, Path, Query, State, rejection::JsonRejection},
-    http::StatusCode,
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::Arguments;
-use sqlx::sqlite::SqliteArguments;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{FromRow, QueryBuilder, Sqlite, SqlitePool};
 use std::fmt::Write as _;
 use tracing::error;
 
-use crate::models::RecipeMacros;
-use crate::models::{AppState, NewRecipe, Recipe, RecipeRow, UpdateRecipe};
+use crate::models::{
+    AppState, NewRecipe, Recipe, RecipeList, RecipeRow, RecipeSummary, UpdateRecipe,
+};
+use crate::models::{Ingredient, IngredientRepr, MacroTotals, MacrosField, RecipeMacros};
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
+use crate::units::{UnitSystem, convert_ingredient, servings_from_yield, servings_yield_text};
 
 use std::io;
 
@@ -24,9 +35,239 @@ pub struct ListQuery {
     limit: i64,
     #[serde(default)]
     offset: i64,
+    /// Comma-separated equipment names; a recipe must need all of them.
+    #[serde(default)]
+    equipment: Option<String>,
+    /// One of [`RecipeSort::ALLOWED`]; defaults to `id`, or to relevance
+    /// when searching.
+    #[serde(default)]
+    sort: Option<String>,
+    /// Words to find in the title, ingredient names, notes or instructions.
+    #[serde(default)]
+    q: Option<String>,
+    /// `true` for favorites only.
+    #[serde(default)]
+    favorite: bool,
+    /// Only recipes whose cooking log averages at least this rating.
+    #[serde(default)]
+    min_rating: Option<f64>,
+    /// Only recipes edited at or after this time (`YYYY-MM-DD HH:MM:SS`
+    /// UTC, or RFC 3339), for incremental sync. Inclusive because
+    /// timestamps have one-second resolution.
+    #[serde(default)]
+    updated_since: Option<String>,
+    #[serde(default)]
+    view: ListView,
+}
+
+/// Shape of the `GET /recipes` items.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ListView {
+    #[default]
+    Full,
+    /// [`RecipeSummary`] cards.
+    Summary,
+}
+
+/// Columns of [`RecipeSummary`].
+const SUMMARY_COLS: &str = r"
+    id, title, servings, image_path_small, equipment, macros,
+    is_favorite, last_cooked_at, created_at, updated_at
+";
+
+/// `updated_since` in the `updated_at` column's format.
+fn parse_updated_since(raw: &str) -> AppResult<String> {
+    let raw = raw.trim();
+    let ts = chrono::NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(raw).map(|t| t.naive_utc()))
+        .map_err(|_| {
+            AppError::Code(
+                StatusCode::BAD_REQUEST,
+                "invalid_updated_since",
+                "updated_since must be 'YYYY-MM-DD HH:MM:SS' or RFC 3339".into(),
+            )
+        })?;
+    Ok(ts.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// Orderings offered by `GET /recipes?sort=`. Every order ends in `id`, so
+/// it is total and `limit`/`offset` pages neither repeat nor skip rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RecipeSort {
+    Id,
+    /// Case-insensitive for ASCII only; accented initials sort after `z`.
+    Title,
+    /// Newest first.
+    CreatedAt,
+    /// Most recently edited first.
+    UpdatedAt,
+    /// Longest since last cooked first; never cooked comes first. Both
+    /// `POST /recipes/{id}/cooked` and past meal plan days count.
+    LastCooked,
+}
+
+impl RecipeSort {
+    const ALLOWED: [&'static str; 5] = ["id", "title", "created_at", "updated_at", "last_cooked"];
+
+    /// `None` when no sort was given.
+    fn parse(raw: Option<&str>) -> AppResult<Option<Self>> {
+        Ok(Some(match raw.map(str::trim) {
+            None | Some("") => return Ok(None),
+            Some("id") => Self::Id,
+            Some("title") => Self::Title,
+            Some("created_at") => Self::CreatedAt,
+            Some("updated_at") => Self::UpdatedAt,
+            Some("last_cooked") => Self::LastCooked,
+            Some(other) => {
+                return Err(AppError::Json(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    serde_json::json!({
+                        "error": format!("unknown sort '{other}'"),
+                        "code": "invalid_sort",
+                        "allowed": Self::ALLOWED,
+                    }),
+                ));
+            }
+        }))
+    }
+
+    const fn order_by(self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Title => "title COLLATE NOCASE, id",
+            Self::CreatedAt => "created_at DESC, id DESC",
+            Self::UpdatedAt => "updated_at DESC, id DESC",
+            Self::LastCooked => {
+                "(SELECT MAX(d) FROM (SELECT date(recipes.last_cooked_at) AS d \
+                   UNION ALL SELECT mp.day FROM meal_plan mp \
+                   WHERE mp.recipe_id = recipes.id AND mp.day <= date('now'))), id"
+            }
+        }
+    }
+}
+
+/// Free-text filter for `GET /recipes?q=`.
+enum Search {
+    /// FTS5 expression over `recipes_fts`.
+    Fts(String),
+    /// `LIKE` pattern, for input without words or when the index fails.
+    Like(String),
+}
+
+/// Relative weight of title, ingredients, notes and instructions matches.
+const FTS_WEIGHTS: &str = "10.0, 5.0, 2.0, 1.0";
+
+/// FTS5 expression requiring every word of `q` as a word prefix, so "card"
+/// finds "cardamom". `None` when `q` has no words.
+fn fts_match(q: &str) -> Option<String> {
+    let terms: Vec<String> = q
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{t}\"*"))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// `%q%` with `LIKE` wildcards in `q` escaped (use with `ESCAPE '\'`).
+fn like_pattern(q: &str) -> String {
+    let escaped = q
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Restrictions of `GET /recipes` other than the search.
+struct ListFilter {
+    /// Normalized equipment names a recipe must all need.
+    equipment: Vec<String>,
+    favorite: bool,
+    min_rating: Option<f64>,
+    /// Normalized `updated_since`.
+    updated_since: Option<String>,
+}
+
+/// `SELECT` for one page of `GET /recipes`. Searches without an explicit
+/// sort rank title matches first, then by relevance.
+fn list_query(
+    cols: &str,
+    filter: &ListFilter,
+    search: Option<&Search>,
+    sort: Option<RecipeSort>,
+    limit: i64,
+    offset: i64,
+) -> QueryBuilder<'static, Sqlite> {
+    let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {cols} FROM recipes"));
+    if let Some(Search::Fts(expr)) = search {
+        qb.push(format!(
+            " JOIN (SELECT rowid AS fts_id, bm25(recipes_fts, {FTS_WEIGHTS}) AS fts_rank \
+               FROM recipes_fts WHERE recipes_fts MATCH "
+        ))
+        .push_bind(expr.clone())
+        .push(") m ON m.fts_id = recipes.id");
+    }
+    qb.push(" WHERE deleted_at IS NULL");
+    if filter.favorite {
+        qb.push(" AND is_favorite = 1");
+    }
+    if let Some(since) = &filter.updated_since {
+        qb.push(" AND updated_at >= ").push_bind(since.clone());
+    }
+    if let Some(min) = filter.min_rating {
+        qb.push(
+            " AND (SELECT AVG(rating) FROM recipe_logs \
+               WHERE recipe_logs.recipe_id = recipes.id) >= ",
+        )
+        .push_bind(min);
+    }
+    for name in &filter.equipment {
+        qb.push(" AND EXISTS (SELECT 1 FROM json_each(recipes.equipment) WHERE value = ")
+            .push_bind(name.clone())
+            .push(")");
+    }
+    if let Some(Search::Like(pattern)) = search {
+        qb.push(" AND (title LIKE ")
+            .push_bind(pattern.clone())
+            .push(r" ESCAPE '\' OR notes LIKE ")
+            .push_bind(pattern.clone())
+            .push(r" ESCAPE '\' OR instructions LIKE ")
+            .push_bind(pattern.clone())
+            .push(
+                " ESCAPE '\\' OR EXISTS (SELECT 1 FROM json_each(recipes.ingredients) \
+                  WHERE json_each.type = 'object' AND json_extract(value, '$.name') LIKE ",
+            )
+            .push_bind(pattern.clone())
+            .push(r" ESCAPE '\'))");
+    }
+
+    qb.push(" ORDER BY ");
+    match (sort, search) {
+        (Some(sort), _) => {
+            qb.push(sort.order_by());
+        }
+        (None, None) => {
+            qb.push(RecipeSort::Id.order_by());
+        }
+        (None, Some(Search::Fts(expr))) => {
+            qb.push("recipes.id NOT IN (SELECT rowid FROM recipes_fts WHERE recipes_fts MATCH ")
+                .push_bind(format!("title : ({expr})"))
+                .push("), m.fts_rank, id");
+        }
+        (None, Some(Search::Like(pattern))) => {
+            qb.push("title NOT LIKE ")
+                .push_bind(pattern.clone())
+                .push(r" ESCAPE '\', id");
+        }
+    }
+    qb.push(" LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    qb
 }
 
-fn default_limit() -> i64 {
+const fn default_limit() -> i64 {
     100
 }
 
@@ -34,75 +275,173 @@ fn serialize_json_or_empty<T: serde::Serialize>(v: &T) -> String {
     serde_json::to_string(v).unwrap_or_else(|_| "[]".into())
 }
 
-async fn store_recipe_image_bytes(
+/// Window during which an identical create is treated as a double-submit.
+const DEDUP_WINDOW_MINUTES: i64 = 10;
+
+/// The newest live recipe with a content hash (first bind) created within
+/// [`dedup_window`] (second bind).
+const RECENT_DUPLICATE: &str = r"
+    SELECT id FROM recipes
+     WHERE content_hash = ?
+       AND deleted_at IS NULL
+       AND created_at >= datetime('now', ?)
+     ORDER BY id DESC
+     LIMIT 1";
+
+fn dedup_window() -> String {
+    format!("-{DEDUP_WINDOW_MINUTES} minutes")
+}
+
+/// Stable hash of the user-visible content of a new recipe.
+///
+/// Notes are included so that editing them before re-submitting yields a new
+/// recipe rather than being collapsed into the previous one.
+pub fn recipe_content_hash(new: &NewRecipe) -> String {
+    let payload = serde_json::json!([
+        new.title.trim(),
+        new.source.trim(),
+        new.notes.trim(),
+        new.ingredients,
+        new.instructions,
+    ]);
+    format!("{:x}", Sha256::digest(payload.to_string().as_bytes()))
+}
+
+/// Encode `bytes` as the recipe's full image and thumbnail and point the
+/// recipe at them. Files are staged and renamed into place only after the row
+/// update commits (see [`crate::media_txn`]).
+///
+/// # Errors
+/// Err if the image can't be decoded, or writing or the update fails.
+pub async fn save_recipe_image(
     state: &AppState,
     recipe_id: i64,
     bytes: Vec<u8>,
-) -> anyhow::Result<(String, String)> {
+) -> anyhow::Result<()> {
     let (full_webp, thumb_webp) =
         tokio::task::spawn_blocking(move || -> io::Result<(Vec<u8>, Vec<u8>)> {
-            let img = image::load_from_memory(&bytes)
+            let img = crate::image_io::decode_oriented(&bytes)
                 .map_err(|e| io::Error::other(format!("decode error: {e}")))?;
             crate::image_io::to_full_and_thumb_webp(&img)
         })
         .await??;
 
-    let rel_dir = format!("recipes/{recipe_id}");
-    let rel_full = format!("{rel_dir}/full.webp");
-    let rel_small = format!("{rel_dir}/small.webp");
+    let rel_full = format!("recipes/{recipe_id}/full.webp");
+    let rel_small = format!("recipes/{recipe_id}/small.webp");
+
+    let mut media = MediaTxn::new();
+    media
+        .stage(&state.config.media_dir, &rel_full, &full_webp)
+        .await?;
+    media
+        .stage(&state.config.media_dir, &rel_small, &thumb_webp)
+        .await?;
+
+    let previous: Option<(Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT image_path_small, image_path_full FROM recipes WHERE id = ?")
+            .bind(recipe_id)
+            .fetch_optional(&state.pool)
+            .await?;
+    let mut tx = state.pool.begin().await?;
+    sqlx::query(
+        r"
+        UPDATE recipes
+           SET image_path_full     = ?,
+               image_path_small    = ?,
+               image_import_status = NULL,
+               updated_at          = CURRENT_TIMESTAMP
+         WHERE id = ?
+        ",
+    )
+    .bind(&rel_full)
+    .bind(&rel_small)
+    .bind(recipe_id)
+    .execute(&mut *tx)
+    .await?;
+    media.commit(tx).await?;
 
-    let abs_dir = state.config.media_dir.join(&rel_dir);
-    tokio::fs::create_dir_all(&abs_dir).await?;
-    tokio::fs::write(abs_dir.join("full.webp"), &full_webp).await?;
-    tokio::fs::write(abs_dir.join("small.webp"), &thumb_webp).await?;
+    // Older uploads may have used other names; drop those files now.
+    let (small, full) = previous.unwrap_or_default();
+    let replaced = small
+        .into_iter()
+        .chain(full)
+        .filter(|rel| *rel != rel_full && *rel != rel_small);
+    remove_image_files(state, recipe_id, replaced).await;
+    Ok(())
+}
 
-    Ok((rel_full, rel_small))
+/// Best-effort delete of recipe image files; failures are only logged.
+async fn remove_image_files(state: &AppState, id: i64, rels: impl Iterator<Item = String>) {
+    for rel in rels {
+        match crate::media_path::remove_file(&state.config.media_dir, &rel).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!(id, rel, error = %e, "failed to delete recipe image"),
+        }
+    }
 }
 
 /// Keep SELECT/RETURNING columns in one place to avoid drift with structs.
 pub const RECIPE_COLS: &str = r#"
-    id, title, source, "yield", notes,
+    id, title, source, "yield", servings, notes,
     created_at, updated_at,
-    ingredients, instructions,
-    image_path_small, image_path_full,
-    macros, share_token, prep_reminders
+    ingredients, instructions, equipment,
+    image_path_small, image_path_full, image_import_status,
+    macros, share_token, prep_reminders,
+    is_favorite, last_cooked_at, times_cooked,
+    (SELECT AVG(rating) FROM recipe_logs WHERE recipe_logs.recipe_id = recipes.id) AS avg_rating,
+    (SELECT COUNT(*) FROM recipe_logs WHERE recipe_logs.recipe_id = recipes.id) AS log_count
 "#;
 
 /// # Errors
 ///
-/// Err if request fails
+/// Err if the URL is blocked (see `safe_fetch`), the download fails or is too
+/// large, or the image can't be stored
 pub async fn fetch_and_store_recipe_image(
-    client: &reqwest::Client,
     abs_url: &str,
     state: &AppState,
     recipe_id: i64,
-) -> anyhow::Result<(String, String)> {
-    let bytes = client
-        .get(abs_url)
-        .header(reqwest::header::USER_AGENT, "blaz/recipe-importer")
-        .send()
-        .await?
-        .error_for_status()?
-        .bytes()
-        .await?
-        .to_vec();
+) -> anyhow::Result<()> {
+    let bytes = crate::safe_fetch::get(
+        abs_url,
+        crate::safe_fetch::MAX_IMAGE_BYTES,
+        state.config.fetch_allow_private,
+    )
+    .await?;
 
-    store_recipe_image_bytes(state, recipe_id, bytes).await
+    save_recipe_image(state, recipe_id, bytes).await
 }
 
 /// # Errors
 ///
-/// Err if parsing of multipart fails
+/// 413 `image_too_large` over `max_image_upload_mb`; 415 `unsupported_image`
+/// unless the upload is a JPEG, PNG or WebP; Err if parsing of multipart fails
 pub async fn upload_image(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<i64>,
     mut multipart: Multipart,
 ) -> AppResult<Json<Recipe>> {
+    state.media.ensure_available()?;
+
     let mut bytes: Option<Vec<u8>> = None;
+    let max_mb = state.config.max_image_upload_mb;
+    let max_bytes = usize::try_from(max_mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX);
 
-    while let Some(field) = multipart.next_field().await? {
+    while let Some(mut field) = multipart.next_field().await? {
         if let Some("image" | "file") = field.name() {
-            bytes = Some(field.bytes().await?.to_vec());
+            let mut buf = Vec::new();
+            while let Some(chunk) = field.chunk().await? {
+                if buf.len() + chunk.len() > max_bytes {
+                    return Err(AppError::Code(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "image_too_large",
+                        format!("image is larger than {max_mb} MB"),
+                    ));
+                }
+                buf.extend_from_slice(&chunk);
+            }
+            bytes = Some(buf);
             break;
         }
     }
@@ -118,58 +457,327 @@ pub async fn upload_image(
         return Ok(Json(recipe));
     };
 
-    let (rel_full, rel_small) = store_recipe_image_bytes(&state, id, bytes).await?;
+    if crate::image_io::sniff_format(&bytes).is_none() {
+        return Err(AppError::Code(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "unsupported_image",
+            "expected a JPEG, PNG or WebP image".into(),
+        ));
+    }
+    save_recipe_image(&state, id, bytes).await?;
 
-    // Update DB (store relative filenames)
-    sqlx::query(
-        r"
-        UPDATE recipes
-           SET image_path_full  = ?,
-               image_path_small = ?,
-               updated_at       = CURRENT_TIMESTAMP
-         WHERE id = ?
-        ",
+    // Return updated recipe
+    let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE id = ?");
+    let recipe: Recipe = sqlx::query_as::<_, RecipeRow>(&sql)
+        .bind(id)
+        .fetch_one(&state.pool)
+        .await?
+        .into();
+
+    let summary = format!("Uploaded a new image for '{}'", recipe.title);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "image", summary),
+    )
+    .await;
+    Ok(Json(recipe))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImageQuery {
+    /// Wanted width in pixels; snaps to one of [`image_cache::WIDTHS`].
+    /// Absent means the largest.
+    w: Option<u32>,
+}
+
+/// How long clients may reuse a rendition before revalidating its `ETag`.
+const IMAGE_CACHE_CONTROL: &str = "public, max-age=604800";
+
+/// `GET /recipes/{id}/image?w=640`: the recipe image scaled to a fixed set
+/// of widths, rendered on first request and cached on disk.
+///
+/// # Errors
+///
+/// 404 if the recipe doesn't exist or has no image; 503 when the media dir
+/// is unavailable; Err if rendering fails
+pub async fn image(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(q): Query<ImageQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    state.media.ensure_available()?;
+    let full: Option<String> = sqlx::query_scalar(
+        "SELECT image_path_full FROM recipes WHERE id = ? AND deleted_at IS NULL",
     )
-    .bind(&rel_full)
-    .bind(&rel_small)
     .bind(id)
-    .execute(&state.pool)
+    .fetch_optional(&state.pool)
+    .await?
+    .flatten();
+    let Some(full) = full else {
+        return Err(StatusCode::NOT_FOUND.into());
+    };
+
+    let width = q.w.map_or(
+        image_cache::WIDTHS[image_cache::WIDTHS.len() - 1],
+        image_cache::snap_width,
+    );
+    let rendition = image_cache::load_or_render(&state.config.media_dir, id, &full, width)
+        .await
+        .map_err(|e| match e.downcast_ref::<io::Error>() {
+            Some(io) if io.kind() == io::ErrorKind::NotFound => StatusCode::NOT_FOUND.into(),
+            _ => AppError::from(e),
+        })?;
+
+    let cache_headers = [
+        (header::ETAG, rendition.etag.clone()),
+        (header::CACHE_CONTROL, IMAGE_CACHE_CONTROL.to_string()),
+    ];
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == rendition.etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+    Ok((
+        [(header::CONTENT_TYPE, "image/webp")],
+        cache_headers,
+        rendition.webp,
+    )
+        .into_response())
+}
+
+/// `DELETE /recipes/{id}/image`: drop both webp files and clear the paths.
+/// File removal is best-effort; the recipe is unlinked either way.
+///
+/// # Errors
+///
+/// 404 if the recipe doesn't exist; Err if updating the db fails
+pub async fn delete_image(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Recipe>> {
+    let paths: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+        r"SELECT image_path_small, image_path_full FROM recipes
+          WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(&state.pool)
     .await?;
+    let Some((small, full)) = paths else {
+        return Err(StatusCode::NOT_FOUND.into());
+    };
 
-    // Return updated recipe
-    let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE id = ?");
+    let sql = format!(
+        "UPDATE recipes
+            SET image_path_small = NULL, image_path_full = NULL,
+                updated_at = CURRENT_TIMESTAMP
+          WHERE id = ?
+          RETURNING {RECIPE_COLS}"
+    );
     let recipe: Recipe = sqlx::query_as::<_, RecipeRow>(&sql)
         .bind(id)
         .fetch_one(&state.pool)
         .await?
         .into();
 
+    remove_image_files(&state, id, small.into_iter().chain(full)).await;
+
+    let summary = format!("Removed the image of '{}'", recipe.title);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "image", summary),
+    )
+    .await;
     Ok(Json(recipe))
 }
 
+/// `POST /recipes/{id}/image/regenerate`: rebuild the thumbnail from the
+/// stored full-size image, e.g. after changing the thumbnail size or quality.
+///
 /// # Errors
 ///
-/// Err if querying the db fails
+/// 404 if the recipe doesn't exist; 409 `no_image` when it has no image;
+/// Err if reading, encoding or writing the image fails
+pub async fn regenerate_image(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Recipe>> {
+    state.media.ensure_available()?;
+
+    let full: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT image_path_full FROM recipes WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?;
+    let Some(full) = full else {
+        return Err(StatusCode::NOT_FOUND.into());
+    };
+    let Some(rel_full) = full else {
+        return Err(AppError::Code(
+            StatusCode::CONFLICT,
+            "no_image",
+            "recipe has no image to regenerate".into(),
+        ));
+    };
+
+    let bytes = tokio::fs::read(crate::media_path::resolve(
+        &state.config.media_dir,
+        &rel_full,
+    )?)
+    .await
+    .map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::Code(
+            StatusCode::CONFLICT,
+            "no_image",
+            "recipe image file is missing".into(),
+        ),
+        _ => e.into(),
+    })?;
+    let (_, thumb_webp) = tokio::task::spawn_blocking(move || {
+        let img = image::load_from_memory(&bytes)
+            .map_err(|e| io::Error::other(format!("decode error: {e}")))?;
+        crate::image_io::to_full_and_thumb_webp(&img)
+    })
+    .await
+    .map_err(anyhow::Error::from)??;
+
+    let rel_small = format!("recipes/{id}/small.webp");
+    let mut media = MediaTxn::new();
+    media
+        .stage(&state.config.media_dir, &rel_small, &thumb_webp)
+        .await?;
+    let mut tx = state.pool.begin().await?;
+    let sql = format!(
+        "UPDATE recipes SET image_path_small = ?, updated_at = CURRENT_TIMESTAMP
+          WHERE id = ? RETURNING {RECIPE_COLS}"
+    );
+    let recipe: Recipe = sqlx::query_as::<_, RecipeRow>(&sql)
+        .bind(&rel_small)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+    media.commit(tx).await?;
+
+    let summary = format!("Regenerated the thumbnail of '{}'", recipe.title);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "image", summary),
+    )
+    .await;
+    Ok(Json(recipe))
+}
+
+/// One page of `GET /recipes` as `T`, selecting `cols`. A full-text search
+/// that fails (e.g. a broken index) is retried as a `LIKE` search.
+async fn list_rows<T>(
+    pool: &SqlitePool,
+    cols: &str,
+    filter: &ListFilter,
+    q: Option<&str>,
+    sort: Option<RecipeSort>,
+    limit: i64,
+    offset: i64,
+) -> AppResult<Vec<T>>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let mut search =
+        q.map(|q| fts_match(q).map_or_else(|| Search::Like(like_pattern(q)), Search::Fts));
+
+    let mut result = list_query(cols, filter, search.as_ref(), sort, limit, offset)
+        .build_query_as::<T>()
+        .fetch_all(pool)
+        .await;
+    if let (Err(e), Some(q)) = (&result, q)
+        && matches!(search, Some(Search::Fts(_)))
+    {
+        tracing::warn!(error = %e, "recipe full-text search failed, falling back to LIKE");
+        search = Some(Search::Like(like_pattern(q)));
+        result = list_query(cols, filter, search.as_ref(), sort, limit, offset)
+            .build_query_as::<T>()
+            .fetch_all(pool)
+            .await;
+    }
+    Ok(result.map_err(|e| {
+        error!(?e, "recipes.list failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?)
+}
+
+/// `GET /recipes?q=curry&favorite=true&min_rating=4&sort=title&limit=50&offset=0`
+///
+/// `view=summary` returns [`RecipeSummary`] cards instead of full recipes;
+/// `updated_since` keeps recipes edited since the last sync.
+///
+/// # Errors
+///
+/// 422 `invalid_sort` for an unknown `sort`; 400 `invalid_updated_since`
+/// for an unreadable `updated_since`; Err if querying the db fails
 pub async fn list(
     State(state): State<AppState>,
     Query(query): Query<ListQuery>,
-) -> AppResult<Json<Vec<Recipe>>> {
-    let limit = query.limit.max(1).min(1000);
+) -> AppResult<Json<RecipeList>> {
+    let limit = query.limit.clamp(1, 1000);
     let offset = query.offset.max(0);
-    let sql = format!(
-        "SELECT {RECIPE_COLS} FROM recipes WHERE deleted_at IS NULL ORDER BY id LIMIT ? OFFSET ?"
-    );
-    let rows: Vec<RecipeRow> = sqlx::query_as::<_, RecipeRow>(&sql)
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&state.pool)
-        .await
-        .map_err(|e| {
-            error!(?e, "recipes.list failed");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let sort = RecipeSort::parse(query.sort.as_deref())?;
+    let filter = ListFilter {
+        equipment: query
+            .equipment
+            .as_deref()
+            .map(|raw| {
+                let parts: Vec<String> = raw.split(',').map(str::to_string).collect();
+                crate::equipment::normalize(&parts)
+            })
+            .unwrap_or_default(),
+        favorite: query.favorite,
+        min_rating: query.min_rating,
+        updated_since: query
+            .updated_since
+            .as_deref()
+            .map(parse_updated_since)
+            .transpose()?,
+    };
+    let q = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
 
-    Ok(Json(rows.into_iter().map(Recipe::from).collect()))
+    Ok(Json(match query.view {
+        ListView::Full => {
+            let rows: Vec<RecipeRow> =
+                list_rows(&state.pool, RECIPE_COLS, &filter, q, sort, limit, offset).await?;
+            RecipeList::Full(rows.into_iter().map(Recipe::from).collect())
+        }
+        ListView::Summary => RecipeList::Summary(
+            list_rows::<RecipeSummary>(&state.pool, SUMMARY_COLS, &filter, q, sort, limit, offset)
+                .await?,
+        ),
+    }))
+}
+
+/// Distinct equipment across all live recipes, sorted, for filter chips.
+///
+/// # Errors
+///
+/// Err if querying the db fails
+pub async fn list_equipment(State(state): State<AppState>) -> AppResult<Json<Vec<String>>> {
+    let names: Vec<String> = sqlx::query_scalar(
+        r"
+        SELECT DISTINCT je.value
+        FROM recipes r, json_each(r.equipment) je
+        WHERE r.deleted_at IS NULL
+        ORDER BY je.value
+        ",
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(names))
 }
 
 /// List soft-deleted recipes (trash)
@@ -315,10 +923,26 @@ fn title_similarity(a: &str, b: &str) -> f64 {
     intersection as f64 / union as f64
 }
 
+#[derive(Deserialize, Debug, Default)]
+pub struct GetQuery {
+    /// `imperial` converts structured quantities for display; stored data is untouched.
+    #[serde(default)]
+    units: Option<String>,
+}
+
 /// # Errors
 ///
-/// Err if querying the db fails
-pub async fn get(State(state): State<AppState>, Path(id): Path<i64>) -> AppResult<Json<Recipe>> {
+/// Err if querying the db fails; 400 for an unknown `units` value.
+pub async fn get(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(q): Query<GetQuery>,
+) -> AppResult<Json<Recipe>> {
+    let units = match q.units.as_deref() {
+        None => UnitSystem::Metric,
+        Some(u) => UnitSystem::parse(u)
+            .ok_or_else(|| AppError::Msg(StatusCode::BAD_REQUEST, format!("unknown units: {u}")))?,
+    };
     let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE id = ? AND deleted_at IS NULL");
     let row: RecipeRow = sqlx::query_as::<_, RecipeRow>(&sql)
         .bind(id)
@@ -329,7 +953,175 @@ pub async fn get(State(state): State<AppState>, Path(id): Path<i64>) -> AppResul
             StatusCode::NOT_FOUND
         })?;
 
-    Ok(Json(row.into()))
+    let mut recipe: Recipe = row.into();
+    for ing in &mut recipe.ingredients {
+        convert_ingredient(ing, units);
+    }
+    Ok(Json(recipe))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScaleQuery {
+    /// Target number of servings; needs a servings-like yield.
+    #[serde(default)]
+    servings: Option<f64>,
+    /// Multiplier applied to every quantity.
+    #[serde(default)]
+    factor: Option<f64>,
+}
+
+/// Factor turning `base` servings into what `query` asks for.
+fn scale_factor(query: &ScaleQuery, base: Option<f64>) -> AppResult<f64> {
+    let positive = |v: f64, what: &str| {
+        if v.is_finite() && v > 0.0 {
+            Ok(v)
+        } else {
+            Err(AppError::Msg(
+                StatusCode::BAD_REQUEST,
+                format!("{what} must be a positive number"),
+            ))
+        }
+    };
+    match (query.servings, query.factor) {
+        (Some(_), Some(_)) => Err(AppError::Msg(
+            StatusCode::BAD_REQUEST,
+            "give either servings or factor, not both".to_string(),
+        )),
+        (None, None) => Err(AppError::Msg(
+            StatusCode::BAD_REQUEST,
+            "servings or factor is required".to_string(),
+        )),
+        (None, Some(factor)) => positive(factor, "factor"),
+        (Some(servings), None) => {
+            let servings = positive(servings, "servings")?;
+            let base = base.ok_or_else(|| {
+                AppError::Code(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "unknown_servings",
+                    "the recipe yield doesn't say how many servings it makes; scale by factor instead"
+                        .to_string(),
+                )
+            })?;
+            Ok(servings / base)
+        }
+    }
+}
+
+/// Multiply every ingredient quantity by `factor` and update the yield to
+/// match when the original servings are known.
+fn scale_recipe(recipe: &mut Recipe, factor: f64) {
+    for ing in &mut recipe.ingredients {
+        if let Some(q) = ing.quantity {
+            ing.quantity = Some(crate::units::round_scaled_qty(
+                q * factor,
+                ing.unit.as_deref(),
+            ));
+        }
+    }
+    if let Some(base) = recipe.servings {
+        let servings = base * factor;
+        recipe.servings = Some(servings);
+        recipe.r#yield = servings_yield_text(servings);
+    }
+}
+
+/// `GET /recipes/{id}/scaled?servings=6` or `?factor=1.5`. The scaled recipe
+/// is computed on the fly and never saved.
+///
+/// # Errors
+///
+/// 404 if the recipe doesn't exist; 400 for a missing or non-positive
+/// target; 422 `unknown_servings` when scaling by servings a recipe whose
+/// yield isn't servings-like
+pub async fn scaled(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<ScaleQuery>,
+) -> AppResult<Json<Recipe>> {
+    let mut recipe = load_scalable_recipe(&state, id).await?;
+    let factor = scale_factor(&query, recipe.servings)?;
+    scale_recipe(&mut recipe, factor);
+    Ok(Json(recipe))
+}
+
+/// A live recipe, with `servings` falling back to what its yield says.
+async fn load_scalable_recipe(state: &AppState, id: i64) -> AppResult<Recipe> {
+    let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE id = ? AND deleted_at IS NULL");
+    let row: RecipeRow = sqlx::query_as::<_, RecipeRow>(&sql)
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut recipe = Recipe::from(row);
+    recipe.servings = recipe
+        .servings
+        .or_else(|| servings_from_yield(&recipe.r#yield));
+    Ok(recipe)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AddToShopping {
+    /// Servings to shop for; the recipe as written when absent.
+    #[serde(default)]
+    servings: Option<f64>,
+    /// Meal-plan day ("YYYY-MM-DD") the recipe is being shopped for.
+    #[serde(default)]
+    day: Option<String>,
+    /// Re-add even if this recipe/day was already sent to the list recently.
+    #[serde(default)]
+    force: bool,
+    /// Store for newly added items; see [`MergeReq::store_id`].
+    #[serde(default)]
+    store_id: StoreField,
+}
+
+/// `POST /recipes/{id}/add-to-shopping`
+///
+/// Merges the recipe's ingredients, scaled to `servings`, into the shopping
+/// list the same way `POST /shopping/merge` does (pantry staples included),
+/// and returns the list and what was skipped.
+///
+/// # Errors
+///
+/// 404 if the recipe doesn't exist; 422 `unknown_servings` for `servings` on
+/// a recipe without a servings-like yield; otherwise as `/shopping/merge`
+pub async fn add_to_shopping(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Json(req): Json<AddToShopping>,
+) -> AppResult<Json<MergeResult>> {
+    let mut recipe = load_scalable_recipe(&state, id).await?;
+    if req.servings.is_some() {
+        let query = ScaleQuery {
+            servings: req.servings,
+            factor: None,
+        };
+        let factor = scale_factor(&query, recipe.servings)?;
+        scale_recipe(&mut recipe, factor);
+    }
+
+    let items = recipe
+        .ingredients
+        .into_iter()
+        .filter(|ing| ing.section.is_none() && !ing.name.trim().is_empty())
+        .map(|ing| InIngredient {
+            quantity: ing.quantity,
+            unit: ing.unit,
+            name: ing.name,
+            category: None,
+        })
+        .collect();
+    let merge = MergeReq {
+        items,
+        recipe_id: Some(id),
+        day: req.day,
+        force: req.force,
+        store_id: req.store_id,
+    };
+    Ok(Json(
+        shopping::merge_into_list(&state, actor, &merge).await?,
+    ))
 }
 
 /// # Errors
@@ -337,6 +1129,7 @@ pub async fn get(State(state): State<AppState>, Path(id): Path<i64>) -> AppResul
 /// Err if querying the db fails
 pub async fn create(
     State(state): State<AppState>,
+    actor: Actor,
     Json(new): Json<NewRecipe>,
 ) -> AppResult<Json<Recipe>> {
     if new.title.trim().is_empty() {
@@ -363,32 +1156,66 @@ pub async fn create(
         }
     }
 
+    let content_hash = recipe_content_hash(&new);
+
     let ingredients_json = serialize_json_or_empty(&new.ingredients);
     let instructions_json = serialize_json_or_empty(&new.instructions);
+    let equipment_json = serialize_json_or_empty(&crate::equipment::normalize(&new.equipment));
 
+    // Guard against double-submits (e.g. a double-click on "confirm import").
+    // The check is part of the INSERT so concurrent submits can't both pass it.
     let sql = format!(
         r#"
-        INSERT INTO recipes (title, source, "yield", notes, ingredients, instructions, created_at, updated_at)
-        VALUES (?, ?, ?, ?, json(?), json(?), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        INSERT INTO recipes (title, source, "yield", servings, notes, ingredients, instructions, equipment, content_hash, created_at, updated_at)
+        SELECT ?, ?, ?, ?, ?, json(?), json(?), json(?), ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+         WHERE ? OR NOT EXISTS ({RECENT_DUPLICATE})
         RETURNING {RECIPE_COLS}
         "#
     );
 
-    let row: RecipeRow = sqlx::query_as::<_, RecipeRow>(&sql)
+    let servings = servings_from_yield(&new.r#yield);
+
+    let inserted: Option<RecipeRow> = sqlx::query_as::<_, RecipeRow>(&sql)
         .bind(new.title)
         .bind(new.source)
         .bind(new.r#yield)
+        .bind(servings)
         .bind(new.notes)
         .bind(ingredients_json)
         .bind(instructions_json)
-        .fetch_one(&state.pool)
+        .bind(equipment_json)
+        .bind(&content_hash)
+        .bind(new.allow_duplicate)
+        .bind(&content_hash)
+        .bind(dedup_window())
+        .fetch_optional(&state.pool)
         .await
         .map_err(|e| {
             error!(?e, "recipes.create failed");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    let Some(row) = inserted else {
+        let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE id = ({RECENT_DUPLICATE})");
+        let row: RecipeRow = sqlx::query_as(&sql)
+            .bind(&content_hash)
+            .bind(dedup_window())
+            .fetch_one(&state.pool)
+            .await?;
+        let mut recipe: Recipe = row.into();
+        recipe.deduplicated = true;
+        return Ok(Json(recipe));
+    };
+
     let recipe: Recipe = row.into();
+    let summary = format!("Created '{}'", recipe.title);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, recipe.id, "create", summary),
+    )
+    .await;
+
     let state_clone = state.clone();
     let recipe_id = recipe.id;
     tokio::spawn(async move {
@@ -401,27 +1228,40 @@ pub async fn create(
 ///
 /// Err if querying the db fails
 /// Soft delete a recipe (move to trash)
-pub async fn delete(State(state): State<AppState>, Path(id): Path<i64>) -> AppResult<StatusCode> {
-    let res = sqlx::query(
-        r"UPDATE recipes SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND deleted_at IS NULL",
+pub async fn delete(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> AppResult<StatusCode> {
+    let title: Option<String> = sqlx::query_scalar(
+        r"UPDATE recipes SET deleted_at = CURRENT_TIMESTAMP WHERE id = ? AND deleted_at IS NULL
+          RETURNING title",
     )
     .bind(id)
-    .execute(&state.pool)
+    .fetch_optional(&state.pool)
     .await
     .map_err(|e| {
         error!(?e, "recipes.delete failed");
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    if res.rows_affected() == 0 {
+    let Some(title) = title else {
         return Err(StatusCode::NOT_FOUND.into());
-    }
+    };
+    let summary = format!("Moved '{title}' to the trash");
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "delete", summary),
+    )
+    .await;
     Ok(StatusCode::NO_CONTENT)
 }
 
 /// Restore a soft-deleted recipe
 pub async fn restore(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<i64>,
 ) -> AppResult<Json<Recipe>> {
     let res = sqlx::query(
@@ -446,33 +1286,73 @@ pub async fn restore(
         .fetch_one(&state.pool)
         .await?;
 
+    let summary = format!("Restored '{}' from the trash", row.title);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "restore", summary),
+    )
+    .await;
     Ok(Json(row.into()))
 }
 
-/// Permanently delete a recipe (from trash)
+/// Permanently delete a recipe (from trash) along with its image files
 pub async fn permanent_delete(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<i64>,
 ) -> AppResult<StatusCode> {
     // Only allow permanent delete of already soft-deleted recipes
-    let res = sqlx::query(r"DELETE FROM recipes WHERE id = ? AND deleted_at IS NOT NULL")
-        .bind(id)
-        .execute(&state.pool)
-        .await
-        .map_err(|e| {
-            error!(?e, "recipes.permanent_delete failed");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let deleted: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        r"DELETE FROM recipes WHERE id = ? AND deleted_at IS NOT NULL
+          RETURNING title, image_path_small, image_path_full",
+    )
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| {
+        error!(?e, "recipes.permanent_delete failed");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    if res.rows_affected() == 0 {
+    let Some((title, small, full)) = deleted else {
         return Err(StatusCode::NOT_FOUND.into());
-    }
+    };
+    let summary = format!("Permanently deleted '{title}'");
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "purge", summary),
+    )
+    .await;
+
+    remove_image_files(&state, id, small.into_iter().chain(full)).await;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
 /// # Errors
 ///
 /// Err if querying the db fails
+/// The ingredients of a PATCH body as stored: lines are parsed, and kept as
+/// raw text when they don't parse.
+///
+/// # Errors
+///
+/// 400 for an ingredient without a name
+fn patched_ingredients(inputs: &[IngredientRepr]) -> AppResult<Vec<Ingredient>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let ing = Ingredient::from(input.clone());
+            if ing.section.is_none() && ing.name.trim().is_empty() {
+                return Err(StatusCode::BAD_REQUEST.into());
+            }
+            Ok(ing)
+        })
+        .collect()
+}
+
 fn build_update_args(up: &UpdateRecipe, id: i64) -> AppResult<(String, SqliteArguments<'static>)> {
     let mut sets: Vec<&'static str> = Vec::new();
     let mut args = SqliteArguments::default();
@@ -491,12 +1371,32 @@ fn build_update_args(up: &UpdateRecipe, id: i64) -> AppResult<(String, SqliteArg
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
     }
-    if let Some(y) = up.r#yield.clone() {
+    // Keep "yield" and servings in sync: a new yield re-derives servings,
+    // new servings regenerate a canonical yield.
+    if let Some(s) = up.servings
+        && !(s.is_finite() && s > 0.0)
+    {
+        return Err((StatusCode::BAD_REQUEST, "servings must be > 0".to_string()).into());
+    }
+    let yield_and_servings = match (up.r#yield.clone(), up.servings) {
+        (Some(y), s) => {
+            let s = s.or_else(|| servings_from_yield(&y));
+            Some((y, s))
+        }
+        (None, Some(s)) => Some((servings_yield_text(s), Some(s))),
+        (None, None) => None,
+    };
+    if let Some((y, servings)) = yield_and_servings {
         sets.push(r#""yield" = ?"#);
         args.add(y).map_err(|e| {
             error!(?e, "arg add (yield) failed");
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+        sets.push("servings = ?");
+        args.add(servings).map_err(|e| {
+            error!(?e, "arg add (servings) failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
     }
     if let Some(notes) = up.notes.clone() {
         sets.push("notes = ?");
@@ -505,13 +1405,8 @@ fn build_update_args(up: &UpdateRecipe, id: i64) -> AppResult<(String, SqliteArg
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
     }
-    if let Some(ref ings) = up.ingredients {
-        for ing in ings {
-            if ing.section.is_none() && ing.name.trim().is_empty() {
-                return Err(StatusCode::BAD_REQUEST.into());
-            }
-        }
-        let s = serialize_json_or_empty(ings);
+    if let Some(ref inputs) = up.ingredients {
+        let s = serialize_json_or_empty(&patched_ingredients(inputs)?);
         sets.push("ingredients = json(?)");
         args.add(s).map_err(|e| {
             error!(?e, "arg add (ingredients) failed");
@@ -526,6 +1421,14 @@ fn build_update_args(up: &UpdateRecipe, id: i64) -> AppResult<(String, SqliteArg
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
     }
+    if let Some(ref equipment) = up.equipment {
+        let s = serialize_json_or_empty(&crate::equipment::normalize(equipment));
+        sets.push("equipment = json(?)");
+        args.add(s).map_err(|e| {
+            error!(?e, "arg add (equipment) failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
     if let Some(ref reminders) = up.prep_reminders {
         let s = serialize_json_or_empty(reminders);
         sets.push("prep_reminders = json(?)");
@@ -534,6 +1437,14 @@ fn build_update_args(up: &UpdateRecipe, id: i64) -> AppResult<(String, SqliteArg
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
     }
+    push_macros_update(&up.macros, &mut sets, &mut args)?;
+    if let Some(favorite) = up.is_favorite {
+        sets.push("is_favorite = ?");
+        args.add(favorite).map_err(|e| {
+            error!(?e, "arg add (is_favorite) failed");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
     sets.push("updated_at = CURRENT_TIMESTAMP");
 
     let sql = format!("UPDATE recipes SET {} WHERE id = ?", sets.join(", "));
@@ -545,17 +1456,101 @@ fn build_update_args(up: &UpdateRecipe, id: i64) -> AppResult<(String, SqliteArg
     Ok((sql, args))
 }
 
+fn push_macros_update(
+    field: &MacrosField,
+    sets: &mut Vec<&'static str>,
+    args: &mut SqliteArguments<'static>,
+) -> AppResult<()> {
+    match field {
+        MacrosField::Absent => {}
+        MacrosField::Clear => sets.push("macros = NULL"),
+        MacrosField::Set(macros) => {
+            validate_macros(macros)?;
+            sets.push("macros = json(?)");
+            args.add(serialize_json_or_empty(macros)).map_err(|e| {
+                error!(?e, "arg add (macros) failed");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Hand-edited macros: a known basis and no negative or non-finite numbers.
+fn validate_macros(m: &RecipeMacros) -> AppResult<()> {
+    if !matches!(m.basis.as_str(), "per_serving" | "per_recipe") {
+        return Err(AppError::Msg(
+            StatusCode::BAD_REQUEST,
+            "macros basis must be per_serving or per_recipe".into(),
+        ));
+    }
+    let totals = |t: &MacroTotals| {
+        [
+            t.protein_g,
+            t.fat_g,
+            t.carbs_g,
+            t.kcal,
+            t.fiber_g.unwrap_or(0.0),
+        ]
+    };
+    let mut values = vec![
+        m.protein_g,
+        m.fat_g,
+        m.carbs_g,
+        m.kcal,
+        m.fiber_g.unwrap_or(0.0),
+    ];
+    values.extend(
+        m.per_recipe_totals
+            .as_ref()
+            .map(totals)
+            .into_iter()
+            .flatten(),
+    );
+    for ing in &m.ingredients {
+        values.extend([
+            ing.protein_g,
+            ing.fat_g,
+            ing.carbs_g,
+            ing.kcal,
+            ing.fiber_g.unwrap_or(0.0),
+        ]);
+    }
+    if values.iter().any(|v| !v.is_finite() || *v < 0.0) {
+        return Err(AppError::Msg(
+            StatusCode::BAD_REQUEST,
+            "macros must be non-negative numbers".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Names of the fields a PATCH sets, for the activity log.
+fn updated_fields(up: &UpdateRecipe) -> Vec<&'static str> {
+    [
+        ("title", up.title.is_some()),
+        ("source", up.source.is_some()),
+        ("yield", up.r#yield.is_some()),
+        ("servings", up.servings.is_some()),
+        ("notes", up.notes.is_some()),
+        ("ingredients", up.ingredients.is_some()),
+        ("instructions", up.instructions.is_some()),
+        ("equipment", up.equipment.is_some()),
+        ("prep reminders", up.prep_reminders.is_some()),
+        ("macros", !matches!(up.macros, MacrosField::Absent)),
+        ("favorite", up.is_favorite.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(name, set)| set.then_some(name))
+    .collect()
+}
+
 pub async fn update(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<i64>,
-    payload: Result<Json<UpdateRecipe>, JsonRejection>,
+    Json(up): Json<UpdateRecipe>,
 ) -> AppResult<Json<Recipe>> {
-    let Json(up) = payload.map_err(|rejection| {
-        let msg = rejection.body_text();
-        tracing::error!("JSON deserialization failed in recipes::update: {}", msg);
-        (StatusCode::UNPROCESSABLE_ENTITY, msg)
-    })?;
-
     // Re-run LLM prep detection only when instructions changed and the caller
     // didn't explicitly supply new prep_reminders (which would be overwritten).
     let should_reextract = up.instructions.is_some() && up.prep_reminders.is_none();
@@ -585,6 +1580,18 @@ pub async fn update(
         })?;
 
     let recipe: Recipe = row.into();
+    let summary = format!(
+        "Updated '{}': {}",
+        recipe.title,
+        updated_fields(&up).join(", ")
+    );
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "update", summary),
+    )
+    .await;
+
     if should_reextract {
         let state_clone = state.clone();
         let recipe_id = recipe.id;
@@ -595,44 +1602,165 @@ pub async fn update(
     Ok(Json(recipe))
 }
 
-/* ---------- Estimate & store macros ---------- */
+/// `POST /recipes/{id}/cooked`: record that the recipe was cooked just now.
+///
+/// # Errors
+///
+/// 404 if the recipe doesn't exist or is deleted; Err if the db update fails
+pub async fn mark_cooked(
+    State(state): State<AppState>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Recipe>> {
+    let sql = format!(
+        "UPDATE recipes SET last_cooked_at = CURRENT_TIMESTAMP, times_cooked = times_cooked + 1
+          WHERE id = ? AND deleted_at IS NULL RETURNING {RECIPE_COLS}"
+    );
+    let recipe: Recipe = sqlx::query_as::<_, RecipeRow>(&sql)
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?
+        .into();
 
-fn servings_from_yield(y: &str) -> Option<f64> {
-    let y = y.trim();
-    if y.is_empty() {
-        return None;
-    }
+    let summary = format!(
+        "Cooked '{}' ({} times so far)",
+        recipe.title, recipe.times_cooked
+    );
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, id, "cooked", summary),
+    )
+    .await;
+    Ok(Json(recipe))
+}
 
-    // Normalize decimals
-    let y_norm = y.replace(',', ".");
-    let y_lower = y_norm.to_ascii_lowercase();
+/* ---------- Bulk edits ---------- */
 
-    // Reject obvious non-serving yields, e.g. "500 g", "1 loaf"
-    if crate::units::NON_SERVING_YIELD_RE.is_match(&y_lower) {
-        return None;
-    }
+/// Largest number of ids accepted by one `POST /recipes/bulk`.
+const MAX_BULK_IDS: usize = 200;
+
+#[derive(Deserialize)]
+pub struct BulkUpdate {
+    pub ids: Vec<i64>,
+    pub set: BulkChanges,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct BulkChanges {
+    #[serde(default)]
+    pub equipment_add: Vec<String>,
+    #[serde(default)]
+    pub equipment_remove: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkStatus {
+    Ok,
+    NotFound,
+}
 
-    // Allow if:
-    // - the whole string is just a number/range
-    // - OR it contains a servings hint ("serves", "people", "portions", "makes", ...)
-    let looks_bare = crate::units::BARE_NUM_RANGE_RE.is_match(&y_lower);
-    let has_hint = crate::units::SERVINGS_HINT_RE.is_match(&y_lower);
+#[derive(Serialize)]
+pub struct BulkResult {
+    pub id: i64,
+    pub status: BulkStatus,
+    /// `false` when the changes were already in place (`updated_at` untouched).
+    pub changed: bool,
+}
 
-    if !looks_bare && !has_hint {
-        return None;
+/// `POST /recipes/bulk` — apply the same changes to many recipes in one
+/// transaction. Trashed and unknown ids are reported as `not_found`.
+///
+/// # Errors
+/// 400 for an empty or oversized batch, 422 for unknown fields, 500 on DB error.
+pub async fn bulk_update(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(req): Json<BulkUpdate>,
+) -> AppResult<Json<Vec<BulkResult>>> {
+    let mut ids = req.ids;
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+    if ids.is_empty() || ids.len() > MAX_BULK_IDS {
+        return Err(crate::error::AppError::Code(
+            StatusCode::BAD_REQUEST,
+            "invalid_batch",
+            format!("ids must contain between 1 and {MAX_BULK_IDS} recipes"),
+        ));
     }
 
-    // Extract first number/range using existing regex
-    if let Some(cap) = crate::units::SERVINGS_NUM_RE.captures(&y_norm) {
-        let a: f64 = cap.get(1)?.as_str().parse().ok()?;
-        if let Some(bm) = cap.get(2) {
-            let b: f64 = bm.as_str().parse().ok()?;
-            return Some(f64::midpoint(a, b));
+    let mut tx = state.pool.begin().await?;
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        let current: Option<sqlx::types::Json<Vec<String>>> =
+            sqlx::query_scalar("SELECT equipment FROM recipes WHERE id = ? AND deleted_at IS NULL")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(current) = current else {
+            results.push(BulkResult {
+                id,
+                status: BulkStatus::NotFound,
+                changed: false,
+            });
+            continue;
+        };
+
+        let equipment = crate::equipment::apply_changes(
+            &current.0,
+            &req.set.equipment_add,
+            &req.set.equipment_remove,
+        );
+        let changed = equipment != current.0;
+        if changed {
+            sqlx::query(
+                "UPDATE recipes SET equipment = json(?), updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            )
+            .bind(serialize_json_or_empty(&equipment))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            let summary = format!("Bulk edit set equipment to [{}]", equipment.join(", "));
+            activity::record_in(
+                &mut tx,
+                &state,
+                actor,
+                Event::new(Entity::Recipe, id, "bulk_update", summary),
+            )
+            .await;
         }
-        return Some(a);
+        results.push(BulkResult {
+            id,
+            status: BulkStatus::Ok,
+            changed,
+        });
     }
+    tx.commit().await?;
 
-    None
+    Ok(Json(results))
+}
+
+/* ---------- Estimate & store macros ---------- */
+
+/// Recipes with more ingredients than this are estimated in groups so a
+/// single response never has to list everything.
+const MACROS_CHUNK_THRESHOLD: usize = 25;
+const MACROS_CHUNK_SIZE: usize = 15;
+
+const MACROS_TOKENS_BASE: u32 = 400;
+const MACROS_TOKENS_PER_INGREDIENT: u32 = 80;
+const MACROS_TOKENS_MAX: u32 = 4000;
+
+/// `max_tokens` for a macros response listing `ingredients` entries.
+fn macros_token_budget(ingredients: usize) -> u32 {
+    u32::try_from(ingredients)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(MACROS_TOKENS_PER_INGREDIENT)
+        .saturating_add(MACROS_TOKENS_BASE)
+        .min(MACROS_TOKENS_MAX)
 }
 
 /// # Errors
@@ -642,24 +1770,217 @@ pub async fn estimate_macros(
     State(state): State<AppState>,
     Path(id): Path<i64>,
 ) -> AppResult<Json<Recipe>> {
-    let row = load_recipe_row(&state, id).await?;
-    let (servings, basis) = servings_and_basis(&row.r#yield);
+    // Load LLM settings from database
+    let llm_settings = LlmSettings::load(&state).await;
+    let llm = LlmClient::from_config(&state.config, llm_settings.model.clone())?
+        .with_retry(RetryPolicy::TIGHT)
+        .tracked(&state.pool, "recipes.macros");
+
+    estimate_and_save_macros(&state, &llm, &llm_settings, id).await?;
+
+    let final_row = load_recipe_row(&state, id).await?;
+    Ok(Json(Recipe::from(final_row)))
+}
 
-    let user = build_macros_user_prompt(servings, &row);
+/// Estimate one recipe's macros and store them.
+async fn estimate_and_save_macros(
+    state: &AppState,
+    llm: &LlmClient,
+    llm_settings: &LlmSettings,
+    id: i64,
+) -> AppResult<()> {
+    let row = load_recipe_row(state, id).await?;
+    let (servings, basis) = servings_and_basis(row.servings);
+    let lines = ingredient_lines(&row);
+    let llm = &llm.clone().for_recipe(id);
 
     let client = macros_http_client()?;
     let sys = &state.config.system_prompt_macros;
 
-    // Load LLM settings from database
-    let llm_settings = LlmSettings::load(&state.pool).await;
+    let macros = if lines.len() > MACROS_CHUNK_THRESHOLD {
+        estimate_macros_chunked(llm, &client, llm_settings, sys, &lines, servings).await?
+    } else {
+        let user = build_macros_user_prompt(servings, &lines, &row.instructions.0);
+        let budget = macros_token_budget(lines.len());
+        let ingredients =
+            call_and_parse_macros_llm(llm, &client, llm_settings, sys, &user, budget).await?;
+        // The model already answered per serving when servings were given.
+        let totals = macros_totals(&ingredients);
+        let per_recipe = servings.map(|sv| scaled_totals(totals, sv));
+        recipe_macros(
+            basis,
+            scaled_totals(totals, 1.0),
+            per_recipe,
+            ingredients,
+            None,
+        )
+    };
 
-    let macros =
-        call_and_parse_macros_llm(&client, &state.config, &llm_settings, sys, &user, basis).await?;
+    save_macros(state, id, &macros).await
+}
 
-    save_macros(&state, id, &macros).await?;
+/// Recipes estimated at once by `estimate-all`.
+const ESTIMATE_ALL_CONCURRENCY: usize = 2;
 
-    let final_row = load_recipe_row(&state, id).await?;
-    Ok(Json(Recipe::from(final_row)))
+/// Pause after each estimate so a batch stays under provider rate limits.
+const ESTIMATE_ALL_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// `POST /recipes/macros/estimate-all`: estimate macros for every recipe
+/// that has none, in the background. Poll `.../status` for progress.
+///
+/// # Errors
+/// 409 `job_running` while a previous batch is still going; LLM config
+/// errors as for a single estimate; Err if listing the recipes fails.
+pub async fn estimate_all_macros(
+    State(state): State<AppState>,
+    actor: Actor,
+) -> AppResult<(StatusCode, Json<MacroJobStatus>)> {
+    let llm_settings = LlmSettings::load(&state).await;
+    let llm = LlmClient::from_config(&state.config, llm_settings.model.clone())?
+        .with_retry(RetryPolicy::TIGHT)
+        .tracked(&state.pool, "recipes.macros");
+
+    let recipes: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, title FROM recipes WHERE macros IS NULL AND deleted_at IS NULL ORDER BY id",
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    if !state.macro_jobs.try_start(recipes.len()) {
+        return Err(AppError::Code(
+            StatusCode::CONFLICT,
+            "job_running",
+            "a macro estimation job is already running".into(),
+        ));
+    }
+    if !recipes.is_empty() {
+        tokio::spawn(run_estimate_all(
+            state.clone(),
+            actor,
+            llm,
+            llm_settings,
+            recipes,
+        ));
+    }
+    Ok((StatusCode::ACCEPTED, Json(state.macro_jobs.status())))
+}
+
+async fn run_estimate_all(
+    state: AppState,
+    actor: Actor,
+    llm: LlmClient,
+    llm_settings: LlmSettings,
+    recipes: Vec<(i64, String)>,
+) {
+    use futures_util::StreamExt;
+
+    let jobs = &state.macro_jobs;
+    futures_util::stream::iter(recipes)
+        .for_each_concurrent(ESTIMATE_ALL_CONCURRENCY, |(id, title)| {
+            let (state, llm, llm_settings) = (&state, &llm, &llm_settings);
+            async move {
+                jobs.begin(&title);
+                match estimate_and_save_macros(state, llm, llm_settings, id).await {
+                    Ok(()) => jobs.succeeded(),
+                    Err(e) => {
+                        tracing::warn!(id, error = ?e, "batch macro estimate failed");
+                        jobs.failed(JobFailure {
+                            recipe_id: id,
+                            title,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+                tokio::time::sleep(ESTIMATE_ALL_DELAY).await;
+            }
+        })
+        .await;
+    jobs.finish();
+
+    let status = jobs.status();
+    let summary = format!(
+        "Estimated macros for {} recipe(s), {} failed",
+        status.done, status.failed
+    );
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::Recipe, None, "macros", summary),
+    )
+    .await;
+}
+
+/// `GET /recipes/macros/estimate-all/status`
+pub async fn estimate_all_macros_status(State(state): State<AppState>) -> Json<MacroJobStatus> {
+    Json(state.macro_jobs.status())
+}
+
+/// Estimate each group of ingredients as a whole-recipe total, then sum the
+/// groups and divide by servings once at the end.
+async fn estimate_macros_chunked(
+    llm: &LlmClient,
+    client: &reqwest::Client,
+    llm_settings: &LlmSettings,
+    sys: &str,
+    lines: &[String],
+    servings: Option<f64>,
+) -> AppResult<RecipeMacros> {
+    let mut ingredients = Vec::with_capacity(lines.len());
+    for chunk in lines.chunks(MACROS_CHUNK_SIZE) {
+        // No servings and no instructions: each group must report only its
+        // own ingredients, or the whole dish gets counted once per group.
+        let user = build_macros_user_prompt(None, chunk, &[]);
+        let budget = macros_token_budget(chunk.len());
+        ingredients.extend(
+            call_and_parse_macros_llm(llm, client, llm_settings, sys, &user, budget).await?,
+        );
+    }
+
+    let totals = macros_totals(&ingredients);
+    let (divisor, basis) = match servings {
+        Some(sv) if sv > 0.0 => (sv, "per_serving"),
+        _ => (1.0, "per_recipe"),
+    };
+    let ingredients = ingredients
+        .into_iter()
+        .map(|ing| crate::models::IngredientMacros {
+            protein_g: round1(ing.protein_g / divisor),
+            fat_g: round1(ing.fat_g / divisor),
+            carbs_g: round1(ing.carbs_g / divisor),
+            kcal: round1(ing.kcal / divisor),
+            fiber_g: ing.fiber_g.map(|f| round1(f / divisor)),
+            ..ing
+        })
+        .collect();
+    let per_recipe = (basis == "per_serving").then(|| scaled_totals(totals, 1.0));
+
+    Ok(recipe_macros(
+        basis,
+        scaled_totals(totals, 1.0 / divisor),
+        per_recipe,
+        ingredients,
+        Some("chunked".to_string()),
+    ))
+}
+
+/// Assemble the stored estimate from totals already on the `basis`.
+fn recipe_macros(
+    basis: &str,
+    totals: MacroTotals,
+    per_recipe_totals: Option<MacroTotals>,
+    ingredients: Vec<crate::models::IngredientMacros>,
+    method: Option<String>,
+) -> RecipeMacros {
+    RecipeMacros {
+        basis: basis.to_string(),
+        protein_g: totals.protein_g,
+        fat_g: totals.fat_g,
+        carbs_g: totals.carbs_g,
+        kcal: totals.kcal,
+        fiber_g: totals.fiber_g,
+        per_recipe_totals,
+        ingredients,
+        method,
+    }
 }
 
 /* ---------- Re-parse ingredients with LLM ---------- */
@@ -685,16 +2006,18 @@ pub async fn reparse_ingredients(
 ) -> AppResult<Json<Vec<crate::models::Ingredient>>> {
     let row = load_recipe_row(&state, id).await?;
 
-    let token = state.config.llm_api_key.clone().unwrap_or_default();
-    if token.is_empty() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "LLM API key not configured".into(),
-        )
-            .into());
-    }
+    // Load LLM settings from database
+    let llm_settings = LlmSettings::load(&state).await;
+    let llm = LlmClient::from_config(&state.config, llm_settings.model.clone())?
+        .tracked(&state.pool, "recipes.reparse")
+        .for_recipe(id);
 
-    let original = row.ingredients.0;
+    let original: Vec<Ingredient> = row
+        .ingredients
+        .0
+        .into_iter()
+        .map(Ingredient::from)
+        .collect();
 
     // Build lines only for non-section ingredients so the LLM only sees real ingredients.
     let lines: Vec<String> = original
@@ -721,19 +2044,10 @@ pub async fn reparse_ingredients(
     let user = serde_json::to_string(&lines).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let http = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
+        .timeout(std::time::Duration::from_mins(1))
         .build()
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // Load LLM settings from database
-    let llm_settings = LlmSettings::load(&state.pool).await;
-
-    let llm = LlmClient::new(
-        state.config.llm_api_url.clone(),
-        token,
-        llm_settings.model.clone(),
-    );
-
     let json = llm
         .chat_json_with_fallback(
             &http,
@@ -741,7 +2055,7 @@ pub async fn reparse_ingredients(
             REPARSE_SYSTEM,
             &user,
             0.1,
-            std::time::Duration::from_secs(60),
+            std::time::Duration::from_mins(1),
             Some(2000),
         )
         .await
@@ -781,8 +2095,7 @@ async fn load_recipe_row(state: &AppState, id: i64) -> AppResult<RecipeRow> {
     Ok(row)
 }
 
-fn servings_and_basis(y: &str) -> (Option<f64>, &'static str) {
-    let servings = servings_from_yield(y);
+const fn servings_and_basis(servings: Option<f64>) -> (Option<f64>, &'static str) {
     let basis = if servings.is_some() {
         "per_serving"
     } else {
@@ -791,10 +2104,11 @@ fn servings_and_basis(y: &str) -> (Option<f64>, &'static str) {
     (servings, basis)
 }
 
-fn build_macros_user_prompt(servings: Option<f64>, row: &RecipeRow) -> String {
-    let ingredients_lines = ingredient_lines(row);
-    let instructions_lines = &row.instructions.0;
-
+fn build_macros_user_prompt(
+    servings: Option<f64>,
+    ingredients_lines: &[String],
+    instructions_lines: &[String],
+) -> String {
     let mut user = String::new();
 
     match servings {
@@ -807,7 +2121,7 @@ fn build_macros_user_prompt(servings: Option<f64>, row: &RecipeRow) -> String {
     }
 
     user.push_str("\nINGREDIENTS:\n");
-    for l in &ingredients_lines {
+    for l in ingredients_lines {
         let _ = writeln!(user, "- {l}");
     }
 
@@ -825,18 +2139,10 @@ fn ingredient_lines(row: &RecipeRow) -> Vec<String> {
     row.ingredients
         .0
         .iter()
-        .map(|i| {
-            let name = match i.prep.as_deref() {
-                Some(p) if !p.trim().is_empty() => format!("{}, {}", i.name, p.trim()),
-                _ => i.name.clone(),
-            };
-
-            match (i.quantity, i.unit.as_deref()) {
-                (Some(q), Some(u)) if !u.is_empty() => format!("{q} {u} {name}"),
-                (Some(q), _) => format!("{q} {name}"),
-                _ => name,
-            }
-        })
+        .cloned()
+        .map(Ingredient::from)
+        .filter(|i| i.section.is_none())
+        .map(|i| crate::units::format_ingredient_line(&i))
         .collect()
 }
 
@@ -847,14 +2153,15 @@ fn macros_http_client() -> Result<reqwest::Client, StatusCode> {
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
 }
 
+/// One macros LLM call; returns the per-ingredient estimates.
 async fn call_and_parse_macros_llm(
+    llm: &LlmClient,
     client: &reqwest::Client,
-    config: &crate::config::Config,
     llm_settings: &LlmSettings,
     sys: &str,
     user: &str,
-    basis: &'static str,
-) -> AppResult<RecipeMacros> {
+    max_tokens: u32,
+) -> AppResult<Vec<crate::models::IngredientMacros>> {
     #[allow(clippy::struct_field_names)]
     #[derive(Deserialize)]
     struct LlmIngredient {
@@ -862,6 +2169,10 @@ async fn call_and_parse_macros_llm(
         protein_g: f64,
         fat_g: f64,
         carbs_g: f64,
+        #[serde(default)]
+        kcal: Option<f64>,
+        #[serde(default)]
+        fiber_g: Option<f64>,
         skip: bool,
     }
 
@@ -870,11 +2181,6 @@ async fn call_and_parse_macros_llm(
         ingredients: Vec<LlmIngredient>,
     }
 
-    let base = &config.llm_api_url;
-    let token = config.llm_api_key.clone().unwrap_or_default();
-
-    let llm = LlmClient::new(base.clone(), token.clone(), llm_settings.model.clone());
-
     let val = llm
         .chat_json_with_fallback(
             client,
@@ -883,7 +2189,7 @@ async fn call_and_parse_macros_llm(
             user,
             0.1,
             std::time::Duration::from_secs(25),
-            Some(1500), // Increased token limit for per-ingredient response
+            Some(max_tokens),
         )
         .await
         .map_err(|e| {
@@ -896,8 +2202,7 @@ async fn call_and_parse_macros_llm(
         StatusCode::BAD_GATEWAY
     })?;
 
-    // Convert to API model and calculate totals
-    let ingredients: Vec<crate::models::IngredientMacros> = parsed
+    Ok(parsed
         .ingredients
         .into_iter()
         .map(|ing| crate::models::IngredientMacros {
@@ -905,25 +2210,47 @@ async fn call_and_parse_macros_llm(
             protein_g: round1(ing.protein_g),
             fat_g: round1(ing.fat_g),
             carbs_g: round1(ing.carbs_g),
+            kcal: round1(
+                ing.kcal
+                    .unwrap_or_else(|| kcal_from_macros(ing.protein_g, ing.fat_g, ing.carbs_g)),
+            ),
+            fiber_g: ing.fiber_g.map(round1),
             skipped: ing.skip,
         })
-        .collect();
+        .collect())
+}
+
+/// Atwater 4/9/4 estimate, for models that leave out `kcal`.
+fn kcal_from_macros(protein_g: f64, fat_g: f64, carbs_g: f64) -> f64 {
+    4.0f64.mul_add(protein_g, 9.0f64.mul_add(fat_g, 4.0 * carbs_g))
+}
 
-    // Sum up non-skipped ingredients for totals
-    let (protein, fat, carbs) = ingredients
+/// Sums over the non-skipped ingredients.
+fn macros_totals(ingredients: &[crate::models::IngredientMacros]) -> MacroTotals {
+    ingredients
         .iter()
         .filter(|ing| !ing.skipped)
-        .fold((0.0, 0.0, 0.0), |(p, f, c), ing| {
-            (p + ing.protein_g, f + ing.fat_g, c + ing.carbs_g)
-        });
+        .fold(MacroTotals::default(), |t, ing| MacroTotals {
+            protein_g: t.protein_g + ing.protein_g,
+            fat_g: t.fat_g + ing.fat_g,
+            carbs_g: t.carbs_g + ing.carbs_g,
+            kcal: t.kcal + ing.kcal,
+            fiber_g: match (t.fiber_g, ing.fiber_g) {
+                (None, None) => None,
+                (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+            },
+        })
+}
 
-    Ok(RecipeMacros {
-        basis: basis.to_string(),
-        protein_g: round1(protein),
-        fat_g: round1(fat),
-        carbs_g: round1(carbs),
-        ingredients,
-    })
+/// `totals` multiplied by `factor`, rounded for storage.
+fn scaled_totals(totals: MacroTotals, factor: f64) -> MacroTotals {
+    MacroTotals {
+        protein_g: round1(totals.protein_g * factor),
+        fat_g: round1(totals.fat_g * factor),
+        carbs_g: round1(totals.carbs_g * factor),
+        kcal: round1(totals.kcal * factor),
+        fiber_g: totals.fiber_g.map(|f| round1(f * factor)),
+    }
 }
 
 fn round1(v: f64) -> f64 {
@@ -1000,13 +2327,14 @@ async fn extract_and_save_prep_reminders(state: AppState, recipe_id: i64) {
     };
 
     // Load LLM settings from database
-    let llm_settings = LlmSettings::load(&state.pool).await;
+    let llm_settings = LlmSettings::load(&state).await;
 
-    let llm = LlmClient::new(
-        state.config.llm_api_url.clone(),
-        state.config.llm_api_key.clone().unwrap_or_default(),
-        llm_settings.model.clone(),
-    );
+    let Ok(llm) = LlmClient::from_config(&state.config, llm_settings.model.clone()) else {
+        return;
+    };
+    let llm = llm
+        .tracked(&state.pool, "recipes.prep_reminders")
+        .for_recipe(recipe_id);
 
     let val = match llm
         .chat_json_with_fallback(
@@ -1,8 +1,13 @@
-use axum::{Json, extract::State, http::StatusCode};
+use axum::{extract::State, http::StatusCode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::{error::AppResult, models::AppState};
+use crate::{
+    activity::{self, Actor, Entity, Event},
+    error::{AppError, AppResult},
+    extract::Json,
+    models::AppState,
+};
 
 /// Get all settings
 pub async fn get_all(State(state): State<AppState>) -> AppResult<Json<HashMap<String, String>>> {
@@ -28,15 +33,63 @@ pub struct UpdateResponse {
 /// Update multiple settings at once
 pub async fn update(
     State(state): State<AppState>,
+    actor: Actor,
     Json(req): Json<UpdateSettings>,
 ) -> AppResult<Json<UpdateResponse>> {
-    let mut updated = 0;
+    let mut changed = Vec::new();
 
     for (key, value) in req.settings {
         // Only allow known settings keys
         if !is_valid_setting_key(&key) {
             continue;
         }
+        if key == "unit_synonyms" && !value.trim().is_empty() {
+            crate::units::parse_unit_synonyms(&value).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        }
+        if key == "allowed_models" && !value.trim().is_empty() {
+            parse_allowed_models(&value).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        }
+        if key == "unit_system" && crate::units::UnitSystem::parse(&value).is_none() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "unit_system must be metric or imperial".to_string(),
+            )
+                .into());
+        }
+        if key == "week_start" && value.trim().parse::<chrono::Weekday>().is_err() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "week_start must be a weekday, e.g. Mon or Sunday".to_string(),
+            )
+                .into());
+        }
+        if key == "timezone"
+            && !value.trim().is_empty()
+            && value.trim().parse::<chrono_tz::Tz>().is_err()
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "timezone must be an IANA name such as Europe/Paris".to_string(),
+            )
+                .into());
+        }
+        if key == "backup_retention" && value.trim().parse::<usize>().is_err() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "backup_retention must be a whole number of backups to keep (0 keeps all)"
+                    .to_string(),
+            )
+                .into());
+        }
+        if key == "default_store_id" && !value.trim().is_empty() {
+            let known = match value.trim().parse::<i64>() {
+                Ok(id) => crate::routes::stores::store_exists(&state.pool, id).await?,
+                Err(_) => false,
+            };
+            if !known {
+                return Err((StatusCode::BAD_REQUEST, "invalid store".to_string()).into());
+            }
+        }
 
         sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
             .bind(&key)
@@ -45,16 +98,41 @@ pub async fn update(
             .await
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-        updated += 1;
+        changed.push(key);
     }
 
-    Ok(Json(UpdateResponse { updated }))
+    if !changed.is_empty() {
+        changed.sort();
+        let summary = format!("Changed {}", changed.join(", "));
+        activity::record(
+            &state,
+            actor,
+            Event::new(Entity::Settings, None, "update", summary),
+        )
+        .await;
+    }
+    Ok(Json(UpdateResponse {
+        updated: changed.len(),
+    }))
 }
 
 fn is_valid_setting_key(key: &str) -> bool {
     matches!(
         key,
-        "llm_model" | "llm_fallback_model" | "llm_vision_model" | "llm_vision_fallback_model"
+        "llm_model"
+            | "llm_fallback_model"
+            | "llm_vision_model"
+            | "llm_vision_fallback_model"
+            | "week_start"
+            | "timezone"
+            | "shopping_readd_undone"
+            | "use_llm_categories"
+            | "unit_synonyms"
+            | "allowed_models"
+            | "default_store_id"
+            | "unit_system"
+            | "share_include_notes"
+            | "backup_retention"
     )
 }
 
@@ -68,6 +146,19 @@ pub async fn get_setting(pool: &sqlx::SqlitePool, key: &str) -> Option<String> {
         .flatten()
 }
 
+/// Today's date in the `timezone` setting, or in the server's local time
+/// when it is unset or invalid.
+pub async fn today(pool: &sqlx::SqlitePool) -> chrono::NaiveDate {
+    let now = chrono::Utc::now();
+    get_setting(pool, "timezone")
+        .await
+        .and_then(|s| s.trim().parse::<chrono_tz::Tz>().ok())
+        .map_or_else(
+            || now.with_timezone(&chrono::Local).date_naive(),
+            |tz| now.with_timezone(&tz).date_naive(),
+        )
+}
+
 /// LLM settings struct for convenient access
 #[derive(Clone, Debug)]
 pub struct LlmSettings {
@@ -75,6 +166,8 @@ pub struct LlmSettings {
     pub fallback_model: String,
     pub vision_model: String,
     pub vision_fallback_model: String,
+    /// Extra models a request may pick as an override; see [`Self::is_model_allowed`].
+    pub allowed_models: Vec<String>,
 }
 
 impl Default for LlmSettings {
@@ -84,20 +177,24 @@ impl Default for LlmSettings {
             fallback_model: "openai/gpt-4o-mini".to_string(),
             vision_model: "google/gemini-2.0-flash-001".to_string(),
             vision_fallback_model: "openai/gpt-4o-mini".to_string(),
+            allowed_models: Vec::new(),
         }
     }
 }
 
 impl LlmSettings {
-    /// Load LLM settings from database, falling back to defaults
-    pub async fn load(pool: &sqlx::SqlitePool) -> Self {
+    /// Load LLM settings from database, falling back to defaults.
+    /// `BLAZ_LLM_MODEL` takes precedence over the stored model.
+    pub async fn load(state: &AppState) -> Self {
         let defaults = Self::default();
+        let pool = &state.pool;
+        let model = match state.config.llm_model.clone().filter(|m| !m.is_empty()) {
+            Some(model) => Some(model),
+            None => get_setting(pool, "llm_model").await,
+        };
 
         Self {
-            model: get_setting(pool, "llm_model")
-                .await
-                .filter(|s| !s.is_empty())
-                .unwrap_or(defaults.model),
+            model: model.filter(|s| !s.is_empty()).unwrap_or(defaults.model),
             fallback_model: get_setting(pool, "llm_fallback_model")
                 .await
                 .filter(|s| !s.is_empty())
@@ -110,6 +207,119 @@ impl LlmSettings {
                 .await
                 .filter(|s| !s.is_empty())
                 .unwrap_or(defaults.vision_fallback_model),
+            allowed_models: get_setting(pool, "allowed_models")
+                .await
+                .and_then(|s| parse_allowed_models(&s).ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The configured models followed by the `allowed_models` setting,
+    /// without duplicates.
+    #[must_use]
+    pub fn allowed(&self) -> Vec<String> {
+        let configured = [
+            &self.model,
+            &self.fallback_model,
+            &self.vision_model,
+            &self.vision_fallback_model,
+        ];
+        let mut out: Vec<String> = Vec::new();
+        for m in configured.into_iter().chain(&self.allowed_models) {
+            if !out.contains(m) {
+                out.push(m.clone());
+            }
+        }
+        out
+    }
+
+    /// Whether a request may override the model with `model`. The configured
+    /// models are always allowed; anything else must be listed in
+    /// `allowed_models`.
+    #[must_use]
+    pub fn is_model_allowed(&self, model: &str) -> bool {
+        self.allowed().iter().any(|m| m == model)
+    }
+
+    /// Validate an optional per-request model override.
+    ///
+    /// # Errors
+    ///
+    /// 422 `model_not_allowed` with the allowed list when `model` isn't allowed.
+    pub fn check_override(&self, model: Option<&str>) -> AppResult<()> {
+        match model {
+            Some(m) if !self.is_model_allowed(m) => Err(AppError::Json(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                serde_json::json!({
+                    "error": format!("model '{m}' is not in the allowed list"),
+                    "code": "model_not_allowed",
+                    "allowed": self.allowed(),
+                }),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parse the `allowed_models` setting: a JSON array of model ids.
+///
+/// # Errors
+///
+/// A message when the value isn't an array of non-empty strings.
+pub fn parse_allowed_models(raw: &str) -> Result<Vec<String>, String> {
+    let models: Vec<String> = serde_json::from_str(raw)
+        .map_err(|e| format!("allowed_models must be a JSON array of model ids: {e}"))?;
+    if models.iter().any(|m| m.trim().is_empty()) {
+        return Err("allowed_models must not contain empty model ids".to_string());
+    }
+    Ok(models.into_iter().map(|m| m.trim().to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(allowed: &[&str]) -> LlmSettings {
+        LlmSettings {
+            allowed_models: allowed.iter().map(ToString::to_string).collect(),
+            ..LlmSettings::default()
         }
     }
+
+    #[test]
+    fn empty_allow_list_permits_only_configured_models() {
+        let s = settings(&[]);
+        assert!(s.is_model_allowed("google/gemini-2.0-flash-001"));
+        assert!(s.is_model_allowed("openai/gpt-4o-mini"));
+        assert!(!s.is_model_allowed("anthropic/claude-opus"));
+        assert!(s.check_override(None).is_ok());
+        assert_eq!(
+            s.allowed(),
+            vec!["google/gemini-2.0-flash-001", "openai/gpt-4o-mini"]
+        );
+    }
+
+    #[test]
+    fn explicit_allow_list_extends_configured_models() {
+        let s = settings(&["deepseek/deepseek-chat-v3.1"]);
+        assert!(
+            s.check_override(Some("deepseek/deepseek-chat-v3.1"))
+                .is_ok()
+        );
+        assert!(
+            s.check_override(Some("google/gemini-2.0-flash-001"))
+                .is_ok()
+        );
+        assert!(s.check_override(Some("openai/o1-pro")).is_err());
+    }
+
+    #[test]
+    fn parse_allowed_models_rejects_bad_values() {
+        assert_eq!(
+            parse_allowed_models(r#"[" a/b "]"#).unwrap(),
+            vec!["a/b".to_string()]
+        );
+        assert!(parse_allowed_models(r#"{"a": "b"}"#).is_err());
+        assert!(parse_allowed_models(r#"[""]"#).is_err());
+    }
 }
@@ -1,13 +1,22 @@
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{Html, IntoResponse, Response},
 };
+use serde::Deserialize;
+use std::fmt::Write as _;
 use uuid::Uuid;
 
+use crate::config::Config;
 use crate::error::AppResult;
+use crate::extract::Json;
+use crate::html::{escape_html, strip_tags};
 use crate::models::{AppState, Recipe, RecipeRow};
 use crate::routes::recipes::RECIPE_COLS;
+use crate::routes::settings::get_setting;
+use crate::schema_org::recipe_jsonld;
+use crate::share_card;
+use crate::units::format_ingredient_line;
 
 /// `POST /recipes/:id/share` — generate (or return existing) share token.
 ///
@@ -68,14 +77,375 @@ pub async fn get_shared_recipe(
     State(state): State<AppState>,
     Path(token): Path<String>,
 ) -> AppResult<Json<Recipe>> {
-    let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE share_token = ?");
-    let recipe: Option<Recipe> = sqlx::query_as::<_, RecipeRow>(&sql)
-        .bind(&token)
-        .fetch_optional(&state.pool)
+    fetch_shared(&state, &token)
         .await?
-        .map(Into::into);
-
-    recipe
         .map(Json)
         .ok_or_else(|| (StatusCode::NOT_FOUND, "Share link not found".to_string()).into())
 }
+
+/// Base for absolute links: `--public-url` if set, otherwise the request's
+/// Host (honouring `X-Forwarded-Proto` from a reverse proxy).
+fn public_base_url(config: &Config, headers: &HeaderMap) -> String {
+    if let Some(url) = &config.public_url {
+        return url.trim_end_matches('/').to_string();
+    }
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let host = header_str(header::HOST.as_str()).unwrap_or("localhost");
+    let proto = header_str("x-forwarded-proto").unwrap_or("http");
+    format!("{proto}://{host}")
+}
+
+async fn fetch_recipe(state: &AppState, filter: &str, value: &str) -> AppResult<Option<Recipe>> {
+    let sql = format!("SELECT {RECIPE_COLS} FROM recipes WHERE {filter} = ?");
+    Ok(sqlx::query_as::<_, RecipeRow>(&sql)
+        .bind(value)
+        .fetch_optional(&state.pool)
+        .await?
+        .map(Into::into))
+}
+
+/// The recipe behind a share token, without its notes unless the
+/// `share_include_notes` setting allows them (anything but "false").
+async fn fetch_shared(state: &AppState, token: &str) -> AppResult<Option<Recipe>> {
+    let Some(mut recipe) = fetch_recipe(state, "share_token", token).await? else {
+        return Ok(None);
+    };
+    let include_notes = get_setting(&state.pool, "share_include_notes")
+        .await
+        .is_none_or(|v| v != "false");
+    if !include_notes {
+        recipe.notes.clear();
+    }
+    Ok(Some(recipe))
+}
+
+/// `GET /recipes/:id/jsonld` — the recipe as a schema.org `Recipe` object.
+///
+/// # Errors
+/// Returns 404 if recipe not found, 500 on DB error.
+pub async fn get_recipe_jsonld(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let recipe = fetch_recipe(&state, "id", &id.to_string())
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Recipe not found".to_string()))?;
+    let doc = recipe_jsonld(&recipe, &public_base_url(&state.config, &headers));
+    Ok(([(header::CONTENT_TYPE, "application/ld+json")], Json(doc)).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    /// `markdown` (default) or `jsonld`.
+    pub format: Option<String>,
+}
+
+/// `GET /recipes/:id/export?format=markdown|jsonld` — the recipe as a
+/// Markdown document or a schema.org JSON-LD file, served as a download.
+///
+/// # Errors
+/// Returns 400 for an unknown format, 404 if recipe not found, 500 on DB error.
+pub async fn export_recipe(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(q): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let format = q.format.as_deref().unwrap_or("markdown");
+    if !matches!(format, "markdown" | "md" | "jsonld") {
+        return Err((StatusCode::BAD_REQUEST, format!("unknown format: {format}")).into());
+    }
+    let recipe = fetch_recipe(&state, "id", &id.to_string())
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Recipe not found".to_string()))?;
+
+    let stem = filename_stem(&recipe.title);
+    let (content_type, filename, body) = if format == "jsonld" {
+        let doc = recipe_jsonld(&recipe, &public_base_url(&state.config, &headers));
+        let body = serde_json::to_string_pretty(&doc).map_err(anyhow::Error::from)?;
+        ("application/ld+json", format!("{stem}.jsonld"), body)
+    } else {
+        (
+            "text/markdown; charset=utf-8",
+            format!("{stem}.md"),
+            recipe_markdown(&recipe),
+        )
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// ASCII slug of a title for download filenames ("Pad Thai (vegan)" ->
+/// "pad-thai-vegan"); `recipe` when nothing is left.
+fn filename_stem(title: &str) -> String {
+    let slug = title
+        .to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "recipe".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Markdown rendering: title, yield, ingredients (section headers as
+/// subheadings), numbered steps restarting after each `## Name` line, and
+/// macros when estimated.
+fn recipe_markdown(recipe: &Recipe) -> String {
+    let mut out = format!("# {}\n\n", recipe.title.trim());
+    if !recipe.r#yield.trim().is_empty() {
+        let _ = writeln!(out, "**Yield:** {}\n", recipe.r#yield.trim());
+    }
+
+    if !recipe.ingredients.is_empty() {
+        out.push_str("## Ingredients\n\n");
+        for ing in &recipe.ingredients {
+            if ing.section.is_some() {
+                blank_line(&mut out);
+                let _ = writeln!(out, "### {}\n", format_ingredient_line(ing));
+            } else {
+                let _ = writeln!(out, "- {}", format_ingredient_line(ing));
+            }
+        }
+        out.push('\n');
+    }
+
+    let steps: Vec<String> = recipe
+        .instructions
+        .iter()
+        .map(|l| strip_tags(l))
+        .filter(|l| !l.is_empty())
+        .collect();
+    if !steps.is_empty() {
+        out.push_str("## Instructions\n\n");
+        let mut n = 0;
+        for step in &steps {
+            if let Some(name) = step.strip_prefix("## ") {
+                n = 0;
+                blank_line(&mut out);
+                let _ = writeln!(out, "### {}\n", name.trim());
+            } else {
+                n += 1;
+                let _ = writeln!(out, "{n}. {step}");
+            }
+        }
+        out.push('\n');
+    }
+
+    if let Some(m) = &recipe.macros {
+        let basis = if m.basis == "per_serving" {
+            "per serving"
+        } else {
+            "per recipe"
+        };
+        let grams = |g: f64| (g * 10.0).round() / 10.0;
+        let _ = writeln!(out, "## Nutrition ({basis})\n");
+        let _ = writeln!(out, "- Protein: {} g", grams(m.protein_g));
+        let _ = writeln!(out, "- Fat: {} g", grams(m.fat_g));
+        let _ = writeln!(out, "- Carbs: {} g", grams(m.carbs_g));
+    }
+
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    out.push('\n');
+    out
+}
+
+/// Headings need a blank line above them, but only one.
+fn blank_line(out: &mut String) {
+    if !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// `GET /share/:token` — the web app's page with the shared recipe's
+/// schema.org JSON-LD embedded in `<head>`, so other apps can import it from
+/// the link, and the preview card as `og:image`. Unknown tokens get the plain
+/// page and the app shows its own error.
+///
+/// # Errors
+/// 500 on DB error.
+pub async fn share_page(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> AppResult<Html<String>> {
+    let recipe = fetch_shared(&state, &token).await?;
+    let index = crate::embedded_web::index_html().unwrap_or_else(|| {
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n<body></body>\n</html>\n"
+            .to_string()
+    });
+    let Some(recipe) = recipe else {
+        return Ok(Html(index));
+    };
+
+    let base = public_base_url(&state.config, &headers);
+    let doc = recipe_jsonld(&recipe, &base);
+    // Markup characters only occur inside JSON strings, so escaping them as
+    // \uXXXX keeps the data intact while nothing in it can end the script
+    // element or open a comment.
+    let json = serde_json::to_string(&doc)
+        .map_err(anyhow::Error::from)?
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026");
+    let script = format!(r#"<script type="application/ld+json">{json}</script>"#);
+    let card = escape_html(&format!("{base}/share/{token}/card.png"));
+    let meta = format!(
+        "<meta property=\"og:image\" content=\"{card}\">\n\
+         <meta property=\"og:image:width\" content=\"{}\">\n\
+         <meta property=\"og:image:height\" content=\"{}\">\n\
+         <meta name=\"twitter:card\" content=\"summary_large_image\">\n",
+        share_card::WIDTH,
+        share_card::HEIGHT,
+    );
+    let pos = index.find("</head>").unwrap_or(0);
+    Ok(Html(format!(
+        "{}{meta}{script}\n{}",
+        &index[..pos],
+        &index[pos..]
+    )))
+}
+
+/// Inline styles for the standalone share page.
+const SHARE_HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:42rem;\
+margin:0 auto;padding:1rem;line-height:1.5;color:#222}img{width:100%;border-radius:8px}\
+h1{margin-bottom:.25rem}.yield{color:#666;margin-top:0}h3{margin-bottom:.25rem}\
+li{margin:.25rem 0}";
+
+/// `GET /share/:token/html` — a standalone, server-rendered page for the
+/// shared recipe (image, yield, ingredients, steps) with Open Graph tags so
+/// links unfurl in messaging apps. Needs no web build and shows nothing but
+/// the recipe.
+///
+/// # Errors
+/// 404 for unknown tokens, 500 on DB error.
+pub async fn share_html(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    let recipe = fetch_shared(&state, &token)
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Share link not found".to_string()))?;
+    let base = public_base_url(&state.config, &headers);
+    let page = recipe_html(&recipe, &base, &format!("{base}/share/{token}/card.png"));
+    Ok(([(header::CACHE_CONTROL, "public, max-age=300")], Html(page)).into_response())
+}
+
+/// The share page markup; every recipe field is escaped.
+fn recipe_html(recipe: &Recipe, base: &str, card_url: &str) -> String {
+    let title = escape_html(recipe.title.trim());
+    let notes = strip_tags(&recipe.notes);
+    let description = if notes.is_empty() {
+        recipe.r#yield.trim().to_string()
+    } else {
+        notes.chars().take(200).collect()
+    };
+
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    let _ = writeln!(out, "<title>{title}</title>");
+    let _ = writeln!(out, "<meta property=\"og:title\" content=\"{title}\">");
+    let _ = writeln!(
+        out,
+        "<meta property=\"og:image\" content=\"{}\">",
+        escape_html(card_url)
+    );
+    if !description.is_empty() {
+        let _ = writeln!(
+            out,
+            "<meta property=\"og:description\" content=\"{}\">",
+            escape_html(&description)
+        );
+    }
+    let _ = writeln!(out, "<style>{SHARE_HTML_STYLE}</style>\n</head>\n<body>");
+
+    if let Some(path) = &recipe.image_path_full {
+        let _ = writeln!(
+            out,
+            "<img src=\"{}\" alt=\"\">",
+            escape_html(&format!("{base}/media/{path}"))
+        );
+    }
+    let _ = writeln!(out, "<h1>{title}</h1>");
+    if !recipe.r#yield.trim().is_empty() {
+        let _ = writeln!(
+            out,
+            "<p class=\"yield\">{}</p>",
+            escape_html(recipe.r#yield.trim())
+        );
+    }
+
+    if !recipe.ingredients.is_empty() {
+        out.push_str("<h2>Ingredients</h2>\n<ul>\n");
+        for ing in &recipe.ingredients {
+            let line = escape_html(&format_ingredient_line(ing));
+            if ing.section.is_some() {
+                let _ = writeln!(out, "</ul>\n<h3>{line}</h3>\n<ul>");
+            } else {
+                let _ = writeln!(out, "<li>{line}</li>");
+            }
+        }
+        out.push_str("</ul>\n");
+    }
+
+    let steps: Vec<String> = recipe
+        .instructions
+        .iter()
+        .map(|l| strip_tags(l))
+        .filter(|l| !l.is_empty())
+        .collect();
+    if !steps.is_empty() {
+        out.push_str("<h2>Instructions</h2>\n<ol>\n");
+        for step in &steps {
+            if let Some(name) = step.strip_prefix("## ") {
+                let _ = writeln!(out, "</ol>\n<h3>{}</h3>\n<ol>", escape_html(name.trim()));
+            } else {
+                let _ = writeln!(out, "<li>{}</li>", escape_html(step));
+            }
+        }
+        out.push_str("</ol>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    // Drop lists left empty around section headings.
+    out.replace("<ul>\n</ul>\n", "")
+        .replace("<ol>\n</ol>\n", "")
+}
+
+/// `GET /share/:token/card.png` — 1200×630 preview card for the shared
+/// recipe, cached in the media dir until the recipe changes.
+///
+/// # Errors
+/// 404 for unknown tokens, 500 on DB or rendering errors.
+pub async fn share_card(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> AppResult<Response> {
+    let recipe = fetch_recipe(&state, "share_token", &token)
+        .await?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Share link not found".to_string()))?;
+    let png = share_card::load_or_render(&state.config.media_dir, &recipe).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/png"),
+            (header::CACHE_CONTROL, "public, max-age=3600"),
+        ],
+        png,
+    )
+        .into_response())
+}
@@ -1,32 +1,86 @@
-use crate::categories::{Category, guess_category, validate_category};
+use crate::activity::{self, Actor, Entity, Event};
+use crate::categories::{classify_uncached, guess_category, validate_category};
 use crate::error::AppError;
+use crate::extract::Json;
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::{
-    Json,
-    extract::{Path, State},
-};
-use serde::Deserialize;
+use axum::response::sse::{self, KeepAlive, Sse};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
 use sqlx::{QueryBuilder, Sqlite};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::error::AppResult;
 use crate::models::{AppState, NewItem, ShoppingItemView};
-use crate::units::{canon_unit_str, normalize_name, to_canonical_qty_unit};
+use crate::routes::pantry::pantry_names;
+use crate::routes::settings::get_setting;
+use crate::routes::stores;
+use crate::shopping_events::Change;
+use crate::units::{
+    MAX_UNIT_WORDS, canon_unit_str, canon_unit_with, convert_qty, deserialize_locale_number,
+    normalize_name, parse_fraction, parse_locale_number, parse_quantity, parse_unit_synonyms,
+    preclean_text, prepare_quantity_text, split_prep, to_canonical_qty_unit,
+};
 
 fn internal_err<E: std::error::Error>(err: E) -> AppError {
     (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into()
 }
 
-fn patch_update_err(err: sqlx::Error) -> AppError {
-    if let sqlx::Error::Database(db) = &err
-        && db.is_unique_violation()
-    {
-        return (
-            StatusCode::CONFLICT,
-            "shopping item with the same name/unit already exists".into(),
-        )
-            .into();
+const DUPLICATE_ITEM_MSG: &str = "shopping item with the same name/unit already exists";
+
+/// Map an update error; a key collision becomes 409 `duplicate_item` carrying
+/// the item that already holds `key`.
+async fn update_err(state: &AppState, err: sqlx::Error, key: &str, id: i64) -> AppError {
+    let sqlx::Error::Database(db) = &err else {
+        return internal_err(err);
+    };
+    if !db.is_unique_violation() {
+        return internal_err(err);
+    }
+
+    let existing: Option<(i64,)> =
+        sqlx::query_as(r"SELECT id FROM shopping_items WHERE key = ? AND id != ?")
+            .bind(key)
+            .bind(id)
+            .fetch_optional(&state.pool)
+            .await
+            .ok()
+            .flatten();
+    let view = match existing {
+        Some((eid,)) => fetch_view_by_id(state, eid).await.ok(),
+        None => None,
+    };
+    view.map_or_else(
+        || (StatusCode::CONFLICT, DUPLICATE_ITEM_MSG.to_string()).into(),
+        |v| AppError::conflict("duplicate_item", DUPLICATE_ITEM_MSG, v),
+    )
+}
+
+fn is_unique_violation(err: &sqlx::Error) -> bool {
+    matches!(err, sqlx::Error::Database(db) if db.is_unique_violation())
+}
+
+/// Run an upsert on `key`, retrying once if a concurrent request inserted the
+/// same key between our conflict check and the write. A second collision is
+/// reported as 409 `duplicate_item` instead of a 500.
+async fn upsert_with_retry<T, F, Fut>(state: &AppState, key: &str, mut run: F) -> AppResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, sqlx::Error>>,
+{
+    match run().await {
+        Err(err) if is_unique_violation(&err) => {
+            tracing::warn!(key, "shopping upsert hit a key collision; retrying once");
+            match run().await {
+                Err(err) if is_unique_violation(&err) => Err(update_err(state, err, key, 0).await),
+                other => other.map_err(Into::into),
+            }
+        }
+        other => other.map_err(Into::into),
     }
-    internal_err(err)
 }
 
 /* ---------- Request/response types ---------- */
@@ -36,6 +90,9 @@ pub struct UpdateShoppingItem {
     pub done: Option<bool>,
     pub category: Option<String>,
     pub notes: Option<String>,
+    /// `null` clears the store (buy anywhere); absent leaves it unchanged.
+    #[serde(default)]
+    pub store_id: StoreField,
 
     /// Backwards-compatible free-form update.
     /// If provided, it takes priority over name/unit/quantity fields.
@@ -44,11 +101,51 @@ pub struct UpdateShoppingItem {
     /// Structured edits:
     pub name: Option<String>,
     pub unit: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_locale_number")]
+    pub quantity: Option<f64>,
+
+    /// Move the item; see `POST /shopping/reorder` for whole-list moves.
+    pub position: Option<f64>,
+}
+
+/// `POST /shopping/reorder` body: item ids in their new order.
+#[derive(Deserialize)]
+pub struct ReorderItems {
+    pub ids: Vec<i64>,
+}
+
+/// `POST /shopping` response: the item, and whether an existing row absorbed it.
+#[derive(Serialize)]
+pub struct CreatedItem {
+    #[serde(flatten)]
+    pub item: ShoppingItemView,
+    pub updated: bool,
+    /// The input had no quantity and the usual one from purchase history was
+    /// filled in.
+    pub quantity_suggested: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SuggestQuery {
+    pub name: String,
+}
+
+/// What is usually bought for a name, from `shopping_purchases`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct QuantitySuggestion {
+    pub name: String,
     pub quantity: Option<f64>,
+    pub unit: Option<String>,
+    pub category: Option<String>,
+    /// Purchases with exactly this quantity/unit.
+    pub times: i64,
+    /// All recorded purchases of this name.
+    pub total: i64,
 }
 
 #[derive(Deserialize, Clone)]
 pub struct InIngredient {
+    #[serde(default, deserialize_with = "deserialize_locale_number")]
     pub quantity: Option<f64>,
     pub unit: Option<String>, // "g","kg","ml","L","tsp","tbsp" or null
     pub name: String,
@@ -59,89 +156,216 @@ pub struct InIngredient {
 pub struct MergeReq {
     pub items: Vec<InIngredient>,
     pub recipe_id: Option<i64>,
+    /// Meal-plan day ("YYYY-MM-DD") the recipe is being shopped for.
+    pub day: Option<String>,
+    /// Re-add even if this recipe/day was already sent to the list recently.
+    #[serde(default)]
+    pub force: bool,
+    /// Store for newly added items. Absent uses the `default_store_id`
+    /// setting; `null` means any store.
+    #[serde(default)]
+    pub store_id: StoreField,
+}
+
+/// A `store_id` request field, where `null` and absent mean different things.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StoreField {
+    #[default]
+    Absent,
+    AnyStore,
+    Store(i64),
+}
+
+impl<'de> Deserialize<'de> for StoreField {
+    fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        Ok(Option::<i64>::deserialize(de)?.map_or(Self::AnyStore, Self::Store))
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct ListQuery {
+    /// Only items for this store plus items for any store.
+    pub store: Option<i64>,
+    /// `category` groups the response by category.
+    pub group_by: Option<String>,
+}
+
+/// Items sharing a category, in list order.
+#[derive(Serialize)]
+pub struct CategoryGroup {
+    pub category: Option<String>,
+    pub items: Vec<ShoppingItemView>,
+}
+
+/// `GET /shopping` response: flat by default, grouped with `group_by=category`.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ShoppingList {
+    Flat(Vec<ShoppingItemView>),
+    Grouped(Vec<CategoryGroup>),
+}
+
+#[derive(Deserialize)]
+pub struct GenerationsQuery {
+    #[serde(default = "default_generation_days")]
+    pub days: u32,
+}
+
+const fn default_generation_days() -> u32 {
+    7
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ShoppingGeneration {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub recipe_title: Option<String>,
+    pub day: Option<String>,
+    pub merge_id: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct CombineReq {
+    pub target_id: i64,
+    pub source_ids: Vec<i64>,
+    /// Optional new display name for the target.
+    pub name: Option<String>,
+}
+
+/// A source row that was left untouched by `/shopping/combine`.
+#[derive(Serialize)]
+pub struct CombineSkip {
+    pub id: i64,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+pub struct CombineResp {
+    #[serde(flatten)]
+    pub item: ShoppingItemView,
+    pub skipped: Vec<CombineSkip>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ParsedItem {
     pub qty: Option<f64>,
     pub unit: Option<String>, // normalized short unit, e.g. "g","kg","ml","L","tsp","tbsp"
-    pub name_raw: String,     // as extracted from the line
+    pub name_raw: String,     // as extracted from the line, without prep
     pub name_norm: String,    // normalized for merge key/category
+    pub prep: Option<String>, // "diced" in "2 carrots, diced"; not part of the key
 }
 
 /* ---------- Alias types ---------- */
 
-/// Parse a simple fraction like "1/2" or "3/4" into f64
-fn parse_fraction(s: &str) -> Option<f64> {
-    let (num, denom) = s.split_once('/')?;
-    let numerator = num.trim().parse::<f64>().ok()?;
-    let denominator = denom.trim().parse::<f64>().ok()?;
-    if denominator == 0.0 {
-        return None;
-    }
-    Some(numerator / denominator)
-}
-
-/// Replace common Unicode fraction characters with decimal strings.
-/// Adds a leading space so "1½" becomes "1 0.5" for mixed number handling.
-fn replace_unicode_fractions(s: &str) -> String {
-    s.replace('½', " 0.5")
-        .replace('⅓', " 0.333333")
-        .replace('⅔', " 0.666667")
-        .replace('¼', " 0.25")
-        .replace('¾', " 0.75")
-        .replace('⅕', " 0.2")
-        .replace('⅖', " 0.4")
-        .replace('⅗', " 0.6")
-        .replace('⅘', " 0.8")
-        .replace('⅙', " 0.166667")
-        .replace('⅚', " 0.833333")
-        .replace('⅛', " 0.125")
-        .replace('⅜', " 0.375")
-        .replace('⅝', " 0.625")
-        .replace('⅞', " 0.875")
+/// A leading quantity token; see [`parse_quantity`].
+fn parse_qty_token(t: &str) -> Option<f64> {
+    parse_quantity(t)
 }
 
-fn parse_qty_token(t: &str) -> Option<f64> {
-    let t = t.trim().replace(',', ".");
-    if t.is_empty() {
+fn normalize_unit_token(t: &str, synonyms: &[(String, &'static str)]) -> Option<String> {
+    let u = t.trim();
+    if u.is_empty() {
         return None;
     }
+    canon_unit_with(u, synonyms).map(std::string::ToString::to_string)
+}
 
-    // Handle ranges (e.g., "2-3"), but not fraction ranges
-    if let Some((a, b)) = t.split_once('-').or_else(|| t.split_once('–'))
-        && !a.contains('/')
-        && !b.contains('/')
-    {
-        let x = a.trim().parse::<f64>().ok()?;
-        let y = b.trim().parse::<f64>().ok()?;
-        return Some(f64::midpoint(x, y));
-    }
+/// The longest run of up to `MAX_UNIT_WORDS` tokens at `idx` that names a
+/// unit ("c. à soupe"), with the number of tokens it spans.
+fn match_unit(
+    tokens: &[&str],
+    idx: usize,
+    synonyms: &[(String, &'static str)],
+) -> Option<(String, usize)> {
+    (1..=MAX_UNIT_WORDS).rev().find_map(|n| {
+        let words = tokens.get(idx..idx + n)?;
+        normalize_unit_token(&words.join(" "), synonyms).map(|u| (u, n))
+    })
+}
 
-    // Handle simple fractions (e.g., "1/2", "3/4")
-    if let Some(result) = parse_fraction(&t) {
-        return Some(result);
-    }
+/// Words between a unit and the name: "2 kg of rice", "200 g de farine".
+const CONNECTIVES: &[&str] = &["of", "de", "di"];
 
-    t.parse::<f64>().ok()
+/// Elided connectives glued to the name: "2 c. à soupe d'huile".
+const ELIDED_CONNECTIVES: &[&str] = &["d'", "d’"];
+
+/// The `unit_synonyms` setting; an unreadable value is logged and ignored.
+async fn unit_synonyms(pool: &sqlx::SqlitePool) -> Vec<(String, &'static str)> {
+    let Some(raw) = get_setting(pool, "unit_synonyms")
+        .await
+        .filter(|v| !v.trim().is_empty())
+    else {
+        return Vec::new();
+    };
+    parse_unit_synonyms(&raw).unwrap_or_else(|e| {
+        tracing::warn!("ignoring unit_synonyms setting: {e}");
+        Vec::new()
+    })
 }
 
-fn normalize_unit_token(t: &str) -> Option<String> {
-    let u = t.trim();
-    if u.is_empty() {
-        return None;
-    }
-    canon_unit_str(u).map(std::string::ToString::to_string)
+/// Multiplication signs of "eggs x12", "2x chicken breast", "×3 limes".
+const TIMES: &[char] = &['x', 'X', '×'];
+
+/// A count written with a multiplication sign on either side: "x12", "2x".
+fn multiplier(t: &str) -> Option<f64> {
+    let n = t.strip_prefix(TIMES).or_else(|| t.strip_suffix(TIMES))?;
+    parse_locale_number(n).filter(|q| *q > 0.0)
+}
+
+/// Leading count words and how many tokens they span: "a dozen" is 12.
+fn count_words(tokens: &[&str]) -> Option<(f64, usize)> {
+    let lower: Vec<String> = tokens.iter().take(3).map(|t| t.to_lowercase()).collect();
+    let words: Vec<&str> = lower.iter().map(String::as_str).collect();
+    Some(match words.as_slice() {
+        ["half", "a", "dozen", ..] => (6.0, 3),
+        ["a" | "an", "dozen", ..] => (12.0, 2),
+        ["a" | "an", "couple", ..] => (2.0, 2),
+        ["dozen", ..] => (12.0, 1),
+        ["couple", ..] => (2.0, 1),
+        ["a" | "an", ..] => (1.0, 1),
+        _ => return None,
+    })
+}
+
+/// A line without a leading number rewritten to start with its count:
+/// "eggs x12", "2x eggs", "a dozen eggs" and "eggs (12)" all become
+/// "12 eggs". `None` when no count notation is found or no name is left.
+fn leading_count_line(tokens: &[&str]) -> Option<String> {
+    let (qty, name): (f64, &[&str]) = if let Some(q) = multiplier(tokens[0]) {
+        (q, &tokens[1..])
+    } else if let Some((q, width)) = count_words(tokens) {
+        (q, &tokens[width..])
+    } else {
+        let (last, rest) = tokens.split_last()?;
+        if let Some(q) = multiplier(last) {
+            (q, rest)
+        } else if let Some(q) = last
+            .strip_prefix('(')
+            .and_then(|t| t.strip_suffix(')'))
+            .and_then(parse_qty_token)
+        {
+            (q, rest)
+        } else {
+            let (n, rest) = rest.split_last()?;
+            if !matches!(*n, "x" | "X" | "×") {
+                return None;
+            }
+            (parse_locale_number(last)?, rest)
+        }
+    };
+    (!name.is_empty()).then(|| format!("{qty} {}", name.join(" ")))
 }
 
 fn create_plain_name_item(raw: &str, reason: &str) -> ParsedItem {
-    let name_raw = raw.to_string();
+    let (name_raw, prep) = split_prep(raw);
     let name_norm = normalize_name(&name_raw);
     let parsed = ParsedItem {
         qty: None,
         unit: None,
         name_raw,
         name_norm,
+        prep,
     };
 
     tracing::info!(
@@ -150,6 +374,7 @@ fn create_plain_name_item(raw: &str, reason: &str) -> ParsedItem {
         unit = ?parsed.unit,
         name_raw = %parsed.name_raw,
         name_norm = %parsed.name_norm,
+        prep = ?parsed.prep,
         "parsed ingredient line ({reason})"
     );
 
@@ -165,14 +390,20 @@ fn create_plain_name_item(raw: &str, reason: &str) -> ParsedItem {
 /// - "1 1/2 cups flour" (mixed number)
 /// - "½ cup flour" (unicode fraction)
 /// - "1½ cups flour" (unicode mixed number)
+/// - "2 c. à soupe d'huile", "200 g de farine", "2 EL Öl", "3 dl mjölk"
+///   (localized units and connectives; `synonyms` adds household aliases)
+/// - "eggs x12", "2x chicken breast", "2 x limes", "×3 limes" (multipliers)
+/// - "a dozen eggs", "2 dozen eggs", "a couple of onions" (count words)
+/// - "chicken breast (2)" (parenthesized count)
 ///
 /// The function is intentionally tolerant:
 /// - If it doesn't start with a number, qty/unit are None and the whole line is the name.
 /// - If it starts with a number but the remaining name is empty, it falls back to treating
 ///   the whole line as the name.
-fn parse_item_line(raw: &str) -> Option<ParsedItem> {
-    // Preprocess: replace Unicode fractions with decimal equivalents
-    let raw = replace_unicode_fractions(raw);
+fn parse_item_line(raw: &str, synonyms: &[(String, &'static str)]) -> Option<ParsedItem> {
+    // Preprocess: plain ASCII spacing/slashes, then Unicode fractions to
+    // decimals and spaced ranges closed up ("1/2 - 3/4" -> "1/2-3/4")
+    let raw = prepare_quantity_text(&preclean_text(raw));
     let raw = raw.trim();
     if raw.is_empty() {
         return None;
@@ -186,8 +417,12 @@ fn parse_item_line(raw: &str) -> Option<ParsedItem> {
     // Try parse leading qty (handles decimals, ranges, and fractions like "1/2")
     let first_qty = parse_qty_token(tokens[0]);
 
-    // If no leading number, treat whole line as plain name
+    // If no leading number, look for a count elsewhere, else treat the
+    // whole line as plain name
     if first_qty.is_none() {
+        if let Some(line) = leading_count_line(&tokens) {
+            return parse_item_line(&line, synonyms);
+        }
         return Some(create_plain_name_item(raw, "no leading quantity"));
     }
 
@@ -202,7 +437,7 @@ fn parse_item_line(raw: &str) -> Option<ParsedItem> {
             idx = 2;
         }
         // Check if it's a decimal < 1 (likely from Unicode fraction conversion)
-        else if let Ok(decimal) = second_token.parse::<f64>()
+        else if let Some(decimal) = parse_locale_number(second_token)
             && decimal > 0.0
             && decimal < 1.0
         {
@@ -211,18 +446,29 @@ fn parse_item_line(raw: &str) -> Option<ParsedItem> {
         }
     }
 
+    // "2 x limes", "2 dozen eggs"
+    match tokens.get(idx).map(|t| t.to_lowercase()).as_deref() {
+        Some("x" | "×") => idx += 1,
+        Some("dozen") => {
+            qty = qty.map(|q| q * 12.0);
+            idx += 1;
+        }
+        _ => {}
+    }
+
     // Optional unit
     let mut unit: Option<String> = None;
 
-    if let Some(t1) = tokens.get(idx)
-        && let Some(un) = normalize_unit_token(t1)
-    {
+    if let Some((un, width)) = match_unit(&tokens, idx, synonyms) {
         unit = Some(un);
-        idx += 1;
+        idx += width;
     }
 
-    // Optional "of"
-    if tokens.get(idx).copied() == Some("of") {
+    // Optional "of" / "de"
+    if tokens
+        .get(idx)
+        .is_some_and(|t| CONNECTIVES.iter().any(|c| t.eq_ignore_ascii_case(c)))
+    {
         idx += 1;
     }
 
@@ -233,6 +479,15 @@ fn parse_item_line(raw: &str) -> Option<ParsedItem> {
     }
 
     let name_raw = tokens[idx..].join(" ");
+    let name_raw = ELIDED_CONNECTIVES
+        .iter()
+        .find_map(|c| {
+            let lower = name_raw.get(..c.len())?.to_lowercase();
+            let rest = &name_raw[c.len()..];
+            (lower == *c && !rest.is_empty()).then(|| rest.to_string())
+        })
+        .unwrap_or(name_raw);
+    let (name_raw, prep) = split_prep(&name_raw);
     let name_norm = normalize_name(&name_raw);
 
     let parsed = ParsedItem {
@@ -240,6 +495,7 @@ fn parse_item_line(raw: &str) -> Option<ParsedItem> {
         unit,
         name_raw,
         name_norm,
+        prep,
     };
 
     tracing::info!(
@@ -248,18 +504,35 @@ fn parse_item_line(raw: &str) -> Option<ParsedItem> {
         unit = ?parsed.unit,
         name_raw = %parsed.name_raw,
         name_norm = %parsed.name_norm,
+        prep = ?parsed.prep,
         "parsed ingredient line"
     );
 
     Some(parsed)
 }
 
+/// A recipe ingredient from one line of text, e.g. schema.org
+/// `recipeIngredient`, with the unit folded like a shopping item's.
+#[must_use]
+pub fn parse_ingredient_line(raw: &str) -> Option<crate::models::Ingredient> {
+    let p = parse_item_line(raw, &[])?;
+    let (unit, quantity) = to_canonical_qty_unit(p.unit.as_deref(), p.qty);
+    Some(crate::models::Ingredient {
+        section: None,
+        quantity,
+        unit: unit.map(str::to_string),
+        name: p.name_raw,
+        prep: p.prep,
+        raw: false,
+    })
+}
+
 /* ---------- DB helpers ---------- */
 
 async fn fetch_view_by_id(state: &AppState, id: i64) -> Result<ShoppingItemView, sqlx::Error> {
     sqlx::query_as::<_, ShoppingItemView>(
         r"
-        SELECT id, text, done, category, notes, recipe_ids, recipe_titles
+        SELECT id, text, done, category, notes, recipe_ids, recipe_titles, store_id, position
           FROM shopping_items_view
          WHERE id = ?
         ",
@@ -278,6 +551,8 @@ struct ShoppingItemRow {
     category: Option<String>,
     notes: String,
     recipe_ids: String,
+    display_unit: Option<String>,
+    display_qty: Option<f64>,
 }
 
 async fn fetch_raw_by_id(state: &AppState, id: i64) -> Result<ShoppingItemRow, sqlx::Error> {
@@ -290,7 +565,9 @@ async fn fetch_raw_by_id(state: &AppState, id: i64) -> Result<ShoppingItemRow, s
             done,
             category,
             notes,
-            COALESCE(recipe_ids, '[]') AS recipe_ids
+            COALESCE(recipe_ids, '[]') AS recipe_ids,
+            display_unit,
+            display_qty
           FROM shopping_items
          WHERE id = ?
         ",
@@ -310,6 +587,41 @@ struct ResolvedPatch {
     notes: String,
     recipe_ids: String,
     key: String,
+    display: Option<(String, f64)>,
+}
+
+/// The quantity and unit as written when storing converts the unit
+/// ("2 cups" is kept as 480 ml), so the list can still say "2 cups".
+/// `None` when the canonical form reads the same.
+fn written_amount(unit: Option<&str>, qty: Option<f64>) -> Option<(String, f64)> {
+    let written = unit.and_then(canon_unit_str)?;
+    let (canonical, _) = to_canonical_qty_unit(Some(written), qty);
+    if canonical == Some(written) {
+        return None;
+    }
+    Some((written.to_string(), qty?))
+}
+
+/// Written amounts of two merged rows: summed when written in the same unit,
+/// otherwise `None` so the merged row shows its canonical amount.
+fn merge_written(
+    existing: Option<(String, f64)>,
+    existing_qty: Option<f64>,
+    incoming: Option<(String, f64)>,
+    incoming_qty: Option<f64>,
+) -> Option<(String, f64)> {
+    match (existing, incoming) {
+        (Some((a, qa)), Some((b, qb))) if a == b => Some((a, qa + qb)),
+        (existing, None) if incoming_qty.is_none() => existing,
+        (None, incoming) if existing_qty.is_none() => incoming,
+        _ => None,
+    }
+}
+
+fn push_written(qb: &mut QueryBuilder<'_, Sqlite>, written: Option<(String, f64)>) {
+    let (unit, qty) = written.unzip();
+    qb.push(", display_unit = ").push_bind(unit);
+    qb.push(", display_qty = ").push_bind(qty);
 }
 
 fn merge_recipe_ids_json(existing: &str, incoming: &str) -> String {
@@ -332,7 +644,8 @@ fn merge_recipe_ids_json(existing: &str, incoming: &str) -> String {
     serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string())
 }
 
-fn merge_quantities(existing: Option<f64>, incoming: Option<f64>) -> Option<f64> {
+/// Sum of two merged quantities; a missing side keeps the other.
+pub fn merge_quantities(existing: Option<f64>, incoming: Option<f64>) -> Option<f64> {
     match (existing, incoming) {
         (Some(a), Some(b)) => Some(a + b),
         (Some(a), None) => Some(a),
@@ -341,6 +654,7 @@ fn merge_quantities(existing: Option<f64>, incoming: Option<f64>) -> Option<f64>
     }
 }
 
+#[allow(clippy::too_many_lines)]
 async fn resolve_patch_values(
     state: &AppState,
     id: i64,
@@ -371,8 +685,9 @@ async fn resolve_patch_values(
     };
 
     if let Some(t) = payload.text.as_deref() {
-        let parsed =
-            parse_item_line(t).ok_or_else(|| (StatusCode::BAD_REQUEST, "empty text".into()))?;
+        let synonyms = unit_synonyms(&state.pool).await;
+        let parsed = parse_item_line(t, &synonyms)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "empty text".into()))?;
         let (unit_norm, qty_norm) = to_canonical_qty_unit(parsed.unit.as_deref(), parsed.qty);
         let unit_norm = unit_norm.map(str::to_string);
         if qty_norm.is_none() {
@@ -394,6 +709,7 @@ async fn resolve_patch_values(
                 notes,
                 recipe_ids: current.recipe_ids,
                 key,
+                display: None,
             });
         }
 
@@ -413,6 +729,7 @@ async fn resolve_patch_values(
             notes,
             recipe_ids: current.recipe_ids,
             key,
+            display: written_amount(parsed.unit.as_deref(), parsed.qty),
         });
     }
 
@@ -429,6 +746,7 @@ async fn resolve_patch_values(
             notes,
             recipe_ids: current.recipe_ids,
             key,
+            display: current.display_unit.zip(current.display_qty),
         });
     }
 
@@ -473,6 +791,7 @@ async fn resolve_patch_values(
             notes,
             recipe_ids: current.recipe_ids,
             key,
+            display: None,
         });
     }
 
@@ -492,6 +811,7 @@ async fn resolve_patch_values(
         notes,
         recipe_ids: current.recipe_ids,
         key,
+        display: written_amount(new_unit_raw.as_deref(), new_qty),
     })
 }
 
@@ -508,14 +828,30 @@ async fn resolve_patch_conflict(
         conflict_category,
         conflict_notes,
         conflict_recipe_ids,
-    )) = sqlx::query_as::<_, (i64, Option<f64>, i64, Option<String>, String, String)>(
+        conflict_display_unit,
+        conflict_display_qty,
+    )) = sqlx::query_as::<
+        _,
+        (
+            i64,
+            Option<f64>,
+            i64,
+            Option<String>,
+            String,
+            String,
+            Option<String>,
+            Option<f64>,
+        ),
+    >(
         r"
         SELECT id,
                quantity,
                done,
                category,
                notes,
-               COALESCE(recipe_ids, '[]') AS recipe_ids
+               COALESCE(recipe_ids, '[]') AS recipe_ids,
+               display_unit,
+               display_qty
           FROM shopping_items
          WHERE key = ? AND id != ?
         ",
@@ -525,15 +861,18 @@ async fn resolve_patch_conflict(
     .fetch_optional(&state.pool)
     .await?
     else {
-        return Err((
-            StatusCode::CONFLICT,
-            "shopping item with the same name/unit already exists".into(),
-        )
-            .into());
+        return Err((StatusCode::CONFLICT, DUPLICATE_ITEM_MSG.to_string()).into());
     };
 
     let merged_recipe_ids = merge_recipe_ids_json(&conflict_recipe_ids, &resolved.recipe_ids);
     let merged_quantity = merge_quantities(conflict_quantity, resolved.quantity);
+    let (merged_display_unit, merged_display_qty) = merge_written(
+        conflict_display_unit.zip(conflict_display_qty),
+        conflict_quantity,
+        resolved.display,
+        resolved.quantity,
+    )
+    .unzip();
     let merged_done = resolved.done;
     let merged_category = conflict_category.or(resolved.category);
     let merged_notes = if resolved.notes.is_empty() {
@@ -551,7 +890,9 @@ async fn resolve_patch_conflict(
                done = ?,
                category = ?,
                notes = ?,
-               recipe_ids = ?
+               recipe_ids = ?,
+               display_unit = ?,
+               display_qty = ?
          WHERE id = ?
         ",
     )
@@ -562,6 +903,8 @@ async fn resolve_patch_conflict(
     .bind(&merged_category)
     .bind(merged_notes)
     .bind(&merged_recipe_ids)
+    .bind(merged_display_unit)
+    .bind(merged_display_qty)
     .bind(conflict_id)
     .execute(&state.pool)
     .await
@@ -581,45 +924,204 @@ async fn resolve_patch_conflict(
 
 /// Unique key used for merging rows: "<unit>|<name>" with normalized name/unit.
 /// For unit-less items the key starts with a leading pipe: "|<name>".
-fn make_key(name_norm: &str, unit_norm: Option<&str>) -> String {
+pub fn make_key(name_norm: &str, unit_norm: Option<&str>) -> String {
     match unit_norm {
         Some(u) if !u.is_empty() => format!("{u}|{name_norm}"),
         _ => format!("|{name_norm}"),
     }
 }
 
+/// How `POST /shopping/merge` identifies an ingredient: its normalized name,
+/// canonical unit and quantity, and the resulting [`make_key`]. The unit is
+/// dropped when there is no quantity.
+pub struct MergeKey {
+    pub name: String,
+    pub unit: Option<&'static str>,
+    pub quantity: Option<f64>,
+    pub key: String,
+}
+
+pub fn merge_key(name: &str, unit: Option<&str>, quantity: Option<f64>) -> MergeKey {
+    let name = normalize_name(name);
+    let (unit, quantity) = to_canonical_qty_unit(unit, quantity);
+    let unit = unit.filter(|_| quantity.is_some());
+    let key = make_key(&name, unit);
+    MergeKey {
+        name,
+        unit,
+        quantity,
+        key,
+    }
+}
+
+/// Past purchases with the same quantity needed before `create` fills it in.
+const SUGGEST_MIN_PURCHASES: i64 = 3;
+
+async fn quantity_suggestion(
+    pool: &sqlx::SqlitePool,
+    name_norm: &str,
+) -> sqlx::Result<QuantitySuggestion> {
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM shopping_purchases WHERE name = ?")
+        .bind(name_norm)
+        .fetch_one(pool)
+        .await?;
+
+    // Ties go to the most recent purchase.
+    let top: Option<(Option<f64>, Option<String>, i64)> = sqlx::query_as(
+        r"
+        SELECT quantity, unit, COUNT(*) AS times
+          FROM shopping_purchases
+         WHERE name = ? AND quantity IS NOT NULL
+         GROUP BY quantity, unit
+         ORDER BY times DESC, MAX(id) DESC
+         LIMIT 1
+        ",
+    )
+    .bind(name_norm)
+    .fetch_optional(pool)
+    .await?;
+
+    let category: Option<String> = sqlx::query_scalar(
+        r"
+        SELECT category
+          FROM shopping_purchases
+         WHERE name = ? AND category IS NOT NULL
+         GROUP BY category
+         ORDER BY COUNT(*) DESC, MAX(id) DESC
+         LIMIT 1
+        ",
+    )
+    .bind(name_norm)
+    .fetch_optional(pool)
+    .await?;
+
+    let (quantity, unit, times) = top.unwrap_or((None, None, 0));
+    Ok(QuantitySuggestion {
+        name: name_norm.to_string(),
+        quantity,
+        unit,
+        category,
+        times,
+        total,
+    })
+}
+
 /* ---------- Routes ---------- */
 
+/// GET /shopping/suggest?name=milk
+///
+/// The quantity/unit and category most often bought for `name`. Fields are
+/// null and `times` is 0 when there is no history.
+///
+/// # Errors
+/// 400 for an empty name; Err if querying the database fails.
+pub async fn suggest(
+    State(state): State<AppState>,
+    Query(q): Query<SuggestQuery>,
+) -> AppResult<Json<QuantitySuggestion>> {
+    let synonyms = unit_synonyms(&state.pool).await;
+    let parsed = parse_item_line(q.name.trim(), &synonyms).ok_or(StatusCode::BAD_REQUEST)?;
+    let name_norm = normalize_name(&parsed.name_raw);
+    Ok(Json(quantity_suggestion(&state.pool, &name_norm).await?))
+}
+
 /// GET /shopping
 ///
+/// Optional query: `store=<id>` and `group_by=category`.
+///
 /// Returns ONLY non-done items.
 /// Done items are kept in DB so their unit/category data remains for future edits.
 ///
+/// With `store`, items assigned to other stores are hidden and the store's
+/// category order takes precedence over the global one.
+///
 /// # Errors
-/// Err if querying the database fails.
-pub async fn list(State(state): State<AppState>) -> AppResult<Json<Vec<ShoppingItemView>>> {
+/// - Returns `404` if `store` does not exist.
+/// - Returns `400` if `group_by` is not `category`.
+/// - Err if querying the database fails.
+pub async fn list(
+    State(state): State<AppState>,
+    Query(q): Query<ListQuery>,
+) -> AppResult<Json<ShoppingList>> {
+    let grouped = match q.group_by.as_deref() {
+        None | Some("") => false,
+        Some("category") => true,
+        Some(_) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "group_by must be 'category'".into(),
+            )
+                .into());
+        }
+    };
+    if let Some(store) = q.store
+        && !stores::store_exists(&state.pool, store).await?
+    {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+
+    let rows = active_items(&state, q.store).await?;
+    if !grouped {
+        return Ok(Json(ShoppingList::Flat(rows)));
+    }
+
+    let mut groups: Vec<CategoryGroup> = Vec::new();
+    for row in rows {
+        match groups.last_mut() {
+            Some(g) if g.category == row.category => g.items.push(row),
+            _ => groups.push(CategoryGroup {
+                category: row.category.clone(),
+                items: vec![row],
+            }),
+        }
+    }
+    Ok(Json(ShoppingList::Grouped(groups)))
+}
+
+/// Non-done items, optionally limited to one store, in aisle order.
+async fn active_items(
+    state: &AppState,
+    store: Option<i64>,
+) -> Result<Vec<ShoppingItemView>, sqlx::Error> {
     let mut rows = sqlx::query_as::<_, ShoppingItemView>(
         r"
-        SELECT id, text, done, category, notes, recipe_ids, recipe_titles
+        SELECT id, text, done, category, notes, recipe_ids, recipe_titles, store_id, position
           FROM shopping_items_view
          WHERE done = 0
+           AND (?1 IS NULL OR store_id IS NULL OR store_id = ?1)
          ORDER BY id
         ",
     )
+    .bind(store)
     .fetch_all(&state.pool)
     .await?;
 
-    // Optional nicer ordering: category order (enum order), then id.
-    rows.sort_by_key(|r| {
-        let cat_key = r
-            .category
-            .as_deref()
-            .and_then(Category::from_str)
-            .map_or(255u8, Category::sort_key);
-        (cat_key, r.id)
+    let positions = match store {
+        Some(id) => stores::category_positions(&state.pool, id).await?,
+        None => HashMap::new(),
+    };
+    let categories = state.categories.names(&state.pool).await?;
+
+    // Store positions first, then the categories' `sort_order`, then the
+    // item's own position.
+    let category_key = |r: &ShoppingItemView| {
+        let cat = r.category.as_deref();
+        let store_key = cat
+            .and_then(|c| positions.get(c).copied())
+            .unwrap_or(i64::MAX);
+        let cat_key = cat
+            .and_then(|c| categories.iter().position(|n| n == c))
+            .unwrap_or(usize::MAX);
+        (store_key, cat_key)
+    };
+    rows.sort_by(|a, b| {
+        category_key(a)
+            .cmp(&category_key(b))
+            .then(a.position.total_cmp(&b.position))
+            .then(a.id.cmp(&b.id))
     });
 
-    Ok(Json(rows))
+    Ok(rows)
 }
 
 /// GET /shopping/all-texts
@@ -642,116 +1144,151 @@ pub async fn list_all_texts(State(state): State<AppState>) -> AppResult<Json<Vec
     Ok(Json(texts))
 }
 
+/// The usual purchase of `name_norm`, when it is bought often enough and not
+/// already on the list.
+async fn usual_amount(
+    pool: &sqlx::SqlitePool,
+    name_norm: &str,
+) -> sqlx::Result<Option<QuantitySuggestion>> {
+    let suggestion = quantity_suggestion(pool, name_norm).await?;
+    let on_list: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM shopping_items WHERE name = ? AND done = 0)",
+    )
+    .bind(name_norm)
+    .fetch_one(pool)
+    .await?;
+    Ok((suggestion.times >= SUGGEST_MIN_PURCHASES && !on_list).then_some(suggestion))
+}
+
 /// POST /shopping
 ///
+/// Adding an item that is already on the list updates that row (see the
+/// upsert below) and reports `"updated": true`.
+///
 /// # Errors
 /// Err if the input text is empty.
 /// Err if inserting or fetching the shopping item fails.
+/// 409 `duplicate_item` if the key still collides after one retry.
 pub async fn create(
     State(state): State<AppState>,
+    actor: Actor,
     Json(new): Json<NewItem>,
-) -> AppResult<Json<ShoppingItemView>> {
+) -> AppResult<Json<CreatedItem>> {
     let text = new.text.trim();
     if text.is_empty() {
         return Err(StatusCode::BAD_REQUEST.into());
     }
 
-    let parsed = parse_item_line(text).ok_or(StatusCode::BAD_REQUEST)?;
-
-    // Structured path only if a leading qty was detected
-    if parsed.qty.is_some() {
-        let (mut unit_norm, qty_norm) = to_canonical_qty_unit(parsed.unit.as_deref(), parsed.qty);
-        if qty_norm.is_none() {
-            unit_norm = None;
-        }
-
-        // Use alias table for merging (skip for manually-entered items)
-        let name_normalized = normalize_name(&parsed.name_raw.trim().to_lowercase());
-        let key = make_key(&name_normalized, unit_norm);
-
-        // Reuse existing category if present to avoid redundant LLM calls.
-        let existing: Option<(i64, Option<String>, i64)> =
-            sqlx::query_as(r"SELECT id, category, done FROM shopping_items WHERE key = ?")
-                .bind(&key)
-                .fetch_optional(&state.pool)
-                .await?;
-
-        let category_guess = match existing.as_ref().and_then(|(_, c, _)| c.clone()) {
-            Some(c) if !c.trim().is_empty() => c,
-            _ => guess_category(&state, &parsed.name_raw).await,
-        };
+    let synonyms = unit_synonyms(&state.pool).await;
+    let parsed = parse_item_line(text, &synonyms).ok_or(StatusCode::BAD_REQUEST)?;
 
-        sqlx::query(
-            r"
-            INSERT INTO shopping_items (name, unit, quantity, done, key, category)
-            VALUES (?, ?, ?, 0, ?, ?)
-            ON CONFLICT(key) DO UPDATE SET
-              quantity = CASE 
-                WHEN shopping_items.done = 1 THEN excluded.quantity
-                ELSE COALESCE(shopping_items.quantity, 0) + COALESCE(excluded.quantity, 0)
-              END,
-              category = COALESCE(shopping_items.category, excluded.category),
-              name = excluded.name,
-              unit = excluded.unit,
-              done = 0
-            ",
-        )
-        .bind(&name_normalized)
-        .bind(unit_norm)
-        .bind(qty_norm)
-        .bind(&key)
-        .bind(&category_guess)
-        .execute(&state.pool)
-        .await?;
+    let (unit_norm, mut qty_norm) = to_canonical_qty_unit(parsed.unit.as_deref(), parsed.qty);
+    // Keep the key unitless when there is no quantity, so "eggs" and
+    // "6 eggs" land on the same row.
+    let mut unit_norm = if qty_norm.is_none() {
+        None
+    } else {
+        unit_norm.map(str::to_string)
+    };
 
-        let (id,): (i64,) = sqlx::query_as("SELECT id FROM shopping_items WHERE key = ?")
-            .bind(&key)
-            .fetch_one(&state.pool)
-            .await?;
+    let name_normalized = normalize_name(&parsed.name_raw.trim().to_lowercase());
 
-        let row = fetch_view_by_id(&state, id).await?;
-        return Ok(Json(row));
+    // "milk" becomes "1 L milk" when that is what is usually bought, unless
+    // the item is already on the list.
+    let mut written = written_amount(parsed.unit.as_deref(), parsed.qty);
+    let usual = if qty_norm.is_none() {
+        usual_amount(&state.pool, &name_normalized).await?
+    } else {
+        None
+    };
+    let quantity_suggested = usual.is_some();
+    if let Some(usual) = usual {
+        qty_norm = usual.quantity;
+        unit_norm = usual.unit;
+        written = None;
     }
 
-    // Fallback: unitless item
-    let name_normalized = normalize_name(&parsed.name_raw.trim().to_lowercase());
-    let key = make_key(&name_normalized, None);
+    let key = make_key(&name_normalized, unit_norm.as_deref());
 
     // Reuse existing category if present to avoid redundant LLM calls.
-    let existing_cat: Option<String> =
-        sqlx::query_scalar(r"SELECT category FROM shopping_items WHERE key = ?")
+    let existing: Option<(i64, Option<String>)> =
+        sqlx::query_as(r"SELECT id, category FROM shopping_items WHERE key = ?")
             .bind(&key)
             .fetch_optional(&state.pool)
             .await?;
 
-    let category_guess = match existing_cat {
+    let category_guess = match existing.as_ref().and_then(|(_, c)| c.clone()) {
         Some(c) if !c.trim().is_empty() => c,
         _ => guess_category(&state, &parsed.name_raw).await,
     };
 
-    sqlx::query(
-        r"
-        INSERT INTO shopping_items (name, unit, quantity, done, key, category)
-        VALUES (?, NULL, NULL, 0, ?, ?)
+    let readd_undone = get_setting(&state.pool, "shopping_readd_undone")
+        .await
+        .is_none_or(|v| v != "false");
+
+    // One upsert for every input: quantities accumulate (a done item starts
+    // over), a plain name leaves the quantity alone, and re-adding a done item
+    // brings it back unless `shopping_readd_undone` is "false".
+    let (id,): (i64,) = upsert_with_retry(&state, &key, || {
+        sqlx::query_as(
+            r"
+        INSERT INTO shopping_items (name, unit, quantity, done, key, category,
+                                    display_unit, display_qty)
+        VALUES (?, ?, ?, 0, ?, ?, ?, ?)
         ON CONFLICT(key) DO UPDATE SET
+          quantity = CASE
+            WHEN excluded.quantity IS NULL THEN shopping_items.quantity
+            WHEN shopping_items.done = 1 OR shopping_items.quantity IS NULL THEN excluded.quantity
+            ELSE shopping_items.quantity + excluded.quantity
+          END,
+          display_unit = CASE
+            WHEN excluded.quantity IS NULL THEN shopping_items.display_unit
+            WHEN shopping_items.done = 1 OR shopping_items.quantity IS NULL
+              THEN excluded.display_unit
+            WHEN shopping_items.display_unit IS excluded.display_unit
+              THEN shopping_items.display_unit
+          END,
+          display_qty = CASE
+            WHEN excluded.quantity IS NULL THEN shopping_items.display_qty
+            WHEN shopping_items.done = 1 OR shopping_items.quantity IS NULL
+              THEN excluded.display_qty
+            WHEN shopping_items.display_unit IS excluded.display_unit
+              THEN shopping_items.display_qty + excluded.display_qty
+          END,
           category = COALESCE(shopping_items.category, excluded.category),
           name = excluded.name,
-          done = 0
+          done = CASE WHEN ? THEN 0 ELSE shopping_items.done END
+        RETURNING id
         ",
-    )
-    .bind(&name_normalized)
-    .bind(&key)
-    .bind(&category_guess)
-    .execute(&state.pool)
-    .await?;
-
-    let (id,): (i64,) = sqlx::query_as("SELECT id FROM shopping_items WHERE key = ?")
+        )
+        .bind(&name_normalized)
+        .bind(unit_norm.as_deref())
+        .bind(qty_norm)
         .bind(&key)
+        .bind(&category_guess)
+        .bind(written.as_ref().map(|(u, _)| u))
+        .bind(written.as_ref().map(|(_, q)| q))
+        .bind(readd_undone)
         .fetch_one(&state.pool)
-        .await?;
+    })
+    .await?;
 
-    let row = fetch_view_by_id(&state, id).await?;
-    Ok(Json(row))
+    let item = fetch_view_by_id(&state, id).await?;
+    let summary = format!("Added '{}'", item.text);
+    activity::record(
+        &state,
+        actor,
+        Event::new(Entity::ShoppingItem, id, "add", summary),
+    )
+    .await;
+    state
+        .shopping_events
+        .publish(Change::Upsert { item: item.clone() });
+    Ok(Json(CreatedItem {
+        item,
+        updated: existing.is_some(),
+        quantity_suggested,
+    }))
 }
 
 /* ---------- PATCH helpers ---------- */
@@ -764,20 +1301,20 @@ fn push_sep(qb: &mut QueryBuilder<Sqlite>, wrote: &mut bool) {
     }
 }
 
+/// Columns cleared when an item is ticked off, so it starts over when it is
+/// added again. The purchase trigger records the old quantity first.
+const DONE_RESET: &str =
+    "recipe_ids = '[]', quantity = NULL, display_unit = NULL, display_qty = NULL, notes = ''";
+
 fn apply_done_update(qb: &mut QueryBuilder<Sqlite>, wrote: &mut bool, done: Option<bool>) {
     if let Some(d) = done {
         push_sep(qb, wrote);
         qb.push("done = ");
         qb.push_bind(i64::from(d));
 
-        // Clear recipe_ids, quantity and notes when marking as done so list resets cleanly
         if d {
             push_sep(qb, wrote);
-            qb.push("recipe_ids = '[]'");
-            push_sep(qb, wrote);
-            qb.push("quantity = NULL");
-            push_sep(qb, wrote);
-            qb.push("notes = ''");
+            qb.push(DONE_RESET);
         }
     }
 }
@@ -790,6 +1327,45 @@ fn apply_notes_update(qb: &mut QueryBuilder<Sqlite>, wrote: &mut bool, notes: Op
     }
 }
 
+fn apply_position_update(
+    qb: &mut QueryBuilder<Sqlite>,
+    wrote: &mut bool,
+    position: Option<f64>,
+) -> AppResult<()> {
+    if let Some(p) = position {
+        if !p.is_finite() {
+            return Err((StatusCode::BAD_REQUEST, "invalid position".into()).into());
+        }
+        push_sep(qb, wrote);
+        qb.push("position = ");
+        qb.push_bind(p);
+    }
+    Ok(())
+}
+
+async fn apply_store_update(
+    qb: &mut QueryBuilder<'_, Sqlite>,
+    wrote: &mut bool,
+    state: &AppState,
+    store_id: StoreField,
+) -> AppResult<()> {
+    let store_id = match store_id {
+        StoreField::Absent => return Ok(()),
+        StoreField::AnyStore => None,
+        StoreField::Store(id) => {
+            if !stores::store_exists(&state.pool, id).await? {
+                return Err((StatusCode::BAD_REQUEST, "invalid store".into()).into());
+            }
+            Some(id)
+        }
+    };
+
+    push_sep(qb, wrote);
+    qb.push("store_id = ");
+    qb.push_bind(store_id);
+    Ok(())
+}
+
 async fn apply_category_update(
     qb: &mut QueryBuilder<'_, Sqlite>,
     wrote: &mut bool,
@@ -828,8 +1404,9 @@ async fn apply_text_update(
         return Ok(false);
     };
 
-    let parsed =
-        parse_item_line(t).ok_or_else(|| (StatusCode::BAD_REQUEST, "empty text".into()))?;
+    let synonyms = unit_synonyms(&state.pool).await;
+    let parsed = parse_item_line(t, &synonyms)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "empty text".into()))?;
 
     let (mut unit_norm, qty_norm) = to_canonical_qty_unit(parsed.unit.as_deref(), parsed.qty);
     if qty_norm.is_none() {
@@ -837,6 +1414,7 @@ async fn apply_text_update(
     }
 
     let key = make_key(&parsed.name_norm, unit_norm);
+    let written = written_amount(parsed.unit.as_deref(), parsed.qty);
 
     push_sep(qb, wrote);
 
@@ -859,6 +1437,7 @@ async fn apply_text_update(
 
     qb.push(", key = ");
     qb.push_bind(key);
+    push_written(qb, written);
 
     // If `category` was NOT explicitly provided, refresh it based on the name.
     if payload.category.is_none() {
@@ -915,6 +1494,8 @@ async fn apply_structured_update(
     }
 
     let key = make_key(&new_name_norm, unit_norm);
+    // Amounts edited in the stored unit drop the written form.
+    let written = written_amount(new_unit_raw.as_deref(), new_qty);
 
     push_sep(qb, wrote);
 
@@ -937,6 +1518,7 @@ async fn apply_structured_update(
 
     qb.push(", key = ");
     qb.push_bind(key);
+    push_written(qb, written);
 
     // Auto-guess category only if:
     // - `category` wasn't explicitly provided
@@ -969,6 +1551,7 @@ async fn apply_structured_update(
 /// - Returns `500` on unexpected database errors.
 pub async fn patch_shopping_item(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<i64>,
     Json(payload): Json<UpdateShoppingItem>,
 ) -> AppResult<Json<ShoppingItemView>> {
@@ -978,6 +1561,8 @@ pub async fn patch_shopping_item(
     apply_done_update(&mut qb, &mut wrote, payload.done);
     apply_category_update(&mut qb, &mut wrote, &state, payload.category.clone()).await?;
     apply_notes_update(&mut qb, &mut wrote, payload.notes.clone());
+    apply_position_update(&mut qb, &mut wrote, payload.position)?;
+    apply_store_update(&mut qb, &mut wrote, &state, payload.store_id).await?;
 
     // `text` takes priority over structured fields.
     let did_text = apply_text_update(&mut qb, &mut wrote, &state, &payload).await?;
@@ -998,15 +1583,52 @@ pub async fn patch_shopping_item(
     let rid = match qb.build_query_as::<(i64,)>().fetch_one(&state.pool).await {
         Ok((rid,)) => rid,
         Err(sqlx::Error::Database(db)) if db.is_unique_violation() => {
-            return resolve_patch_conflict(&state, id, &payload).await;
+            let Json(dto) = resolve_patch_conflict(&state, id, &payload).await?;
+            // `id` was merged into `dto` and is gone.
+            state.shopping_events.publish(Change::Delete { id });
+            record_patch(&state, actor, &payload, &dto).await;
+            return Ok(Json(dto));
         }
-        Err(err) => return Err(patch_update_err(err)),
+        Err(err) => return Err(internal_err(err)),
     };
 
     let dto = fetch_view_by_id(&state, rid).await.map_err(internal_err)?;
+    record_patch(&state, actor, &payload, &dto).await;
     Ok(Json(dto))
 }
 
+async fn record_patch(
+    state: &AppState,
+    actor: Actor,
+    payload: &UpdateShoppingItem,
+    item: &ShoppingItemView,
+) {
+    let fields: Vec<&str> = [
+        ("done", payload.done.is_some()),
+        ("category", payload.category.is_some()),
+        ("notes", payload.notes.is_some()),
+        ("store", !matches!(payload.store_id, StoreField::Absent)),
+        ("text", payload.text.is_some()),
+        ("name", payload.name.is_some()),
+        ("unit", payload.unit.is_some()),
+        ("quantity", payload.quantity.is_some()),
+        ("position", payload.position.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(name, set)| set.then_some(name))
+    .collect();
+    let summary = match (payload.done, fields.as_slice()) {
+        (Some(true), ["done"]) => format!("Checked off '{}'", item.text),
+        (Some(false), ["done"]) => format!("Put '{}' back on the list", item.text),
+        _ => format!("Updated '{}': {}", item.text, fields.join(", ")),
+    };
+    let event = Event::new(Entity::ShoppingItem, item.id, "update", summary);
+    activity::record(state, actor, event).await;
+    state
+        .shopping_events
+        .publish(Change::Upsert { item: item.clone() });
+}
+
 /// DELETE /shopping/{id}
 ///
 /// This is still a hard delete for explicit user intent.
@@ -1016,15 +1638,245 @@ pub async fn patch_shopping_item(
 /// Err if deleting the shopping item fails.
 pub async fn delete(
     State(state): State<AppState>,
+    actor: Actor,
     Path(id): Path<i64>,
 ) -> AppResult<Json<serde_json::Value>> {
-    let affected = sqlx::query("DELETE FROM shopping_items WHERE id = ?")
-        .bind(id)
-        .execute(&state.pool)
-        .await?
-        .rows_affected();
+    let name: Option<String> =
+        sqlx::query_scalar("DELETE FROM shopping_items WHERE id = ? RETURNING name")
+            .bind(id)
+            .fetch_optional(&state.pool)
+            .await?;
+
+    if let Some(name) = &name {
+        let summary = format!("Removed '{name}'");
+        activity::record(
+            &state,
+            actor,
+            Event::new(Entity::ShoppingItem, id, "delete", summary),
+        )
+        .await;
+        state.shopping_events.publish(Change::Delete { id });
+    }
+    Ok(Json(
+        serde_json::json!({ "deleted": u64::from(name.is_some()) }),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct BulkReq {
+    pub action: String,
+    /// Limit the action to these items; unknown ids are ignored.
+    #[serde(default)]
+    pub ids: Option<Vec<i64>>,
+}
+
+/// Every item, done or not, by id.
+async fn all_items(state: &AppState) -> Result<Vec<ShoppingItemView>, sqlx::Error> {
+    sqlx::query_as::<_, ShoppingItemView>(
+        r"
+        SELECT id, text, done, category, notes, recipe_ids, recipe_titles, store_id, position
+          FROM shopping_items_view
+         ORDER BY id
+        ",
+    )
+    .fetch_all(&state.pool)
+    .await
+}
+
+/// GET /shopping/events
+///
+/// Server-sent `shopping` events, one per change to the list, with the
+/// sequence number as the event id. A gap in the ids means events were
+/// missed and the list should be re-fetched. Comments every 25s keep proxies
+/// from closing an idle stream.
+pub async fn events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<sse::Event, Infallible>>> {
+    let rx = state.shopping_events.subscribe();
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(ev) => {
+                    let event = sse::Event::default()
+                        .id(ev.seq.to_string())
+                        .event("shopping")
+                        .json_data(&ev)
+                        .unwrap_or_else(|_| sse::Event::default().event("shopping"));
+                    return Some((Ok(event), rx));
+                }
+                // The next event's id shows the gap.
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(25)))
+}
+
+/// POST /shopping/bulk
+///
+/// One statement for `delete_done`, `delete_all`, `mark_all_done` or
+/// `mark_all_undone`, optionally limited to `ids`. Returns the whole list,
+/// done items included.
+///
+/// # Errors
+/// 400 for an unknown action; Err if the statement fails.
+pub async fn bulk(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(req): Json<BulkReq>,
+) -> AppResult<Json<Vec<ShoppingItemView>>> {
+    let (sql, verb, past) = match req.action.as_str() {
+        "delete_done" => (
+            "DELETE FROM shopping_items WHERE done = 1".to_string(),
+            "bulk_delete",
+            "Removed",
+        ),
+        "delete_all" => (
+            "DELETE FROM shopping_items WHERE 1 = 1".to_string(),
+            "bulk_delete",
+            "Removed",
+        ),
+        "mark_all_done" => (
+            format!("UPDATE shopping_items SET done = 1, {DONE_RESET} WHERE done = 0"),
+            "bulk_done",
+            "Checked off",
+        ),
+        "mark_all_undone" => (
+            "UPDATE shopping_items SET done = 0 WHERE done = 1".to_string(),
+            "bulk_undone",
+            "Unchecked",
+        ),
+        other => {
+            return Err((StatusCode::BAD_REQUEST, format!("unknown action: {other}")).into());
+        }
+    };
+
+    let mut qb = QueryBuilder::<Sqlite>::new(sql);
+    if let Some(ids) = &req.ids {
+        if ids.is_empty() {
+            return Ok(Json(all_items(&state).await?));
+        }
+        qb.push(" AND id IN (");
+        let mut sep = qb.separated(", ");
+        for id in ids {
+            sep.push_bind(id);
+        }
+        sep.push_unseparated(")");
+    }
+    let affected = qb.build().execute(&state.pool).await?.rows_affected();
+
+    if affected > 0 {
+        let summary = format!("{past} {affected} item(s)");
+        activity::record(
+            &state,
+            actor,
+            Event::new(Entity::ShoppingItem, None, verb, summary),
+        )
+        .await;
+        state.shopping_events.publish(Change::Refresh);
+    }
+    Ok(Json(all_items(&state).await?))
+}
+
+/// POST /shopping/reorder
+///
+/// Rewrites the positions of `ids` to their order in the list, in one
+/// transaction. Returns the active list.
+///
+/// # Errors
+/// 400 when an id is repeated or isn't on the list (nothing is changed);
+/// Err if a write fails.
+pub async fn reorder(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(req): Json<ReorderItems>,
+) -> AppResult<Json<Vec<ShoppingItemView>>> {
+    let mut seen = std::collections::HashSet::new();
+    if let Some(dup) = req.ids.iter().find(|id| !seen.insert(**id)) {
+        return Err((StatusCode::BAD_REQUEST, format!("duplicate item {dup}")).into());
+    }
+
+    let mut tx = state.pool.begin().await?;
+    for (idx, id) in req.ids.iter().enumerate() {
+        #[allow(clippy::cast_precision_loss)]
+        let position = (idx + 1) as f64;
+        let updated = sqlx::query("UPDATE shopping_items SET position = ? WHERE id = ?")
+            .bind(position)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if updated == 0 {
+            // Dropping the transaction rolls back the positions written so far.
+            return Err((StatusCode::BAD_REQUEST, format!("unknown item {id}")).into());
+        }
+    }
+    if !req.ids.is_empty() {
+        let summary = format!("Reordered {} item(s)", req.ids.len());
+        activity::record_in(
+            &mut tx,
+            &state,
+            actor,
+            Event::new(Entity::ShoppingItem, None, "reorder", summary),
+        )
+        .await;
+    }
+    tx.commit().await?;
+    if !req.ids.is_empty() {
+        state.shopping_events.publish(Change::Refresh);
+    }
+
+    Ok(Json(active_items(&state, None).await?))
+}
 
-    Ok(Json(serde_json::json!({ "deleted": affected })))
+#[derive(Serialize)]
+pub struct RecategorizeResult {
+    pub changed: usize,
+}
+
+/// POST /shopping/recategorize
+///
+/// Classifies every item without a category or in "Other" again, e.g. after
+/// adding keywords or turning on `use_llm_categories`.
+///
+/// # Errors
+/// Err if reading or updating items fails.
+pub async fn recategorize(
+    State(state): State<AppState>,
+    actor: Actor,
+) -> AppResult<Json<RecategorizeResult>> {
+    let rows: Vec<(i64, String, Option<String>)> = sqlx::query_as(
+        "SELECT id, name, category FROM shopping_items WHERE category IS NULL OR category = 'Other'",
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut changed = 0;
+    for (id, name, old) in rows {
+        let category = classify_uncached(&state, &name).await.category;
+        if old.as_deref() == Some(category.as_str()) {
+            continue;
+        }
+        sqlx::query("UPDATE shopping_items SET category = ? WHERE id = ?")
+            .bind(&category)
+            .bind(id)
+            .execute(&state.pool)
+            .await?;
+        changed += 1;
+    }
+
+    if changed > 0 {
+        let summary = format!("Recategorized {changed} item(s)");
+        activity::record(
+            &state,
+            actor,
+            Event::new(Entity::ShoppingItem, None, "recategorize", summary),
+        )
+        .await;
+        state.shopping_events.publish(Change::Refresh);
+    }
+    Ok(Json(RecategorizeResult { changed }))
 }
 
 /// POST /shopping/merge
@@ -1034,62 +1886,122 @@ pub async fn delete(
 /// Err if fetching the updated shopping list fails.
 pub async fn merge_items(
     State(state): State<AppState>,
+    actor: Actor,
     Json(req): Json<MergeReq>,
-) -> AppResult<Json<Vec<ShoppingItemView>>> {
-    for it in &req.items {
-        let merge_name_norm = normalize_name(&it.name);
+) -> AppResult<Json<MergeResult>> {
+    Ok(Json(merge_into_list(&state, actor, &req).await?))
+}
 
-        // Parse qty/unit from the ingredient fields
-        let (mut unit_norm, qty_norm) = to_canonical_qty_unit(it.unit.as_deref(), it.quantity);
-        if qty_norm.is_none() {
-            unit_norm = None;
+/// `POST /shopping/merge` response.
+#[derive(Serialize)]
+pub struct MergeResult {
+    /// The active list after the merge.
+    pub items: Vec<ShoppingItemView>,
+    /// Names of ingredients left off because they are pantry staples.
+    pub skipped: Vec<String>,
+}
+
+/// Category for a merged item: the requested one (validated), else the one
+/// already stored under `key`, else a fresh guess.
+async fn merge_category(state: &AppState, it: &InIngredient, key: &str) -> AppResult<String> {
+    let requested = it
+        .category
+        .as_deref()
+        .map(crate::units::norm_whitespace)
+        .filter(|s| !s.is_empty());
+    if let Some(c) = requested {
+        if !validate_category(state, &c).await {
+            return Err((StatusCode::BAD_REQUEST, "invalid category".into()).into());
         }
+        return Ok(c);
+    }
+    // Reuse existing category if already set; call LLM for new items.
+    let existing: Option<String> =
+        sqlx::query_scalar(r"SELECT category FROM shopping_items WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&state.pool)
+            .await?
+            .flatten();
+    Ok(match existing {
+        Some(c) if !c.trim().is_empty() => c,
+        _ => guess_category(state, &it.name).await,
+    })
+}
 
-        let key = make_key(&merge_name_norm, unit_norm);
+/// Upsert `req.items` into `shopping_items`, adding to the quantity of items
+/// already on the list, and return the active list. Pantry staples are
+/// skipped.
+///
+/// # Errors
+/// 400 for an invalid day, category or store; 409 `already_generated` when
+/// the recipe was sent recently and `force` is unset; Err if a write fails.
+pub async fn merge_into_list(
+    state: &AppState,
+    actor: Actor,
+    req: &MergeReq,
+) -> AppResult<MergeResult> {
+    if let Some(day) = req.day.as_deref() {
+        chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d")
+            .map_err(|_| (StatusCode::BAD_REQUEST, "invalid day".to_string()))?;
+    }
+    if let Some(recipe_id) = req.recipe_id
+        && !req.force
+    {
+        ensure_not_recently_generated(state, recipe_id, req.day.as_deref()).await?;
+    }
+    let store_id = merge_store(state, req.store_id).await?;
+    let pantry = pantry_names(&state.pool).await?;
 
-        // Normalize/validate incoming category, if present
-        let chosen_cat = it.category.as_ref().and_then(|s| {
-            let s = crate::units::norm_whitespace(s);
-            if s.is_empty() { None } else { Some(s) }
-        });
+    let mut skipped = Vec::new();
+    for it in &req.items {
+        let MergeKey {
+            name: merge_name_norm,
+            unit: unit_norm,
+            quantity: qty_norm,
+            key,
+        } = merge_key(&it.name, it.unit.as_deref(), it.quantity);
+        if pantry.contains(&merge_name_norm) {
+            skipped.push(it.name.clone());
+            continue;
+        }
 
-        let chosen_cat = if let Some(c) = chosen_cat {
-            if !validate_category(&state, &c).await {
-                return Err((StatusCode::BAD_REQUEST, "invalid category".into()).into());
-            }
-            Some(c)
-        } else {
-            // Reuse existing category if already set; call LLM for new items.
-            let existing: Option<String> =
-                sqlx::query_scalar(r"SELECT category FROM shopping_items WHERE key = ?")
-                    .bind(&key)
-                    .fetch_optional(&state.pool)
-                    .await?
-                    .flatten();
-            match existing {
-                Some(c) if !c.trim().is_empty() => Some(c),
-                _ => Some(guess_category(&state, &it.name).await),
-            }
-        };
+        let written = written_amount(it.unit.as_deref(), it.quantity);
+
+        let chosen_cat = merge_category(state, it, &key).await?;
 
         // Prepare recipe_ids JSON array
         let recipe_ids_json = req
             .recipe_id
             .map_or_else(|| "[]".to_string(), |rid| format!("[{rid}]"));
 
-        sqlx::query(
-            r"
-            INSERT INTO shopping_items (name, unit, quantity, done, key, category, recipe_ids)
-            VALUES (?, ?, ?, 0, ?, ?, ?)
+        upsert_with_retry(state, &key, || {
+            sqlx::query(
+                r"
+            INSERT INTO shopping_items (name, unit, quantity, done, key, category, recipe_ids, store_id,
+                                        display_unit, display_qty)
+            VALUES (?, ?, ?, 0, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(key) DO UPDATE SET
               quantity = CASE
                 WHEN excluded.quantity IS NULL THEN shopping_items.quantity
                 WHEN shopping_items.quantity IS NULL THEN excluded.quantity
                 ELSE shopping_items.quantity + excluded.quantity
               END,
+              display_unit = CASE
+                WHEN excluded.quantity IS NULL THEN shopping_items.display_unit
+                WHEN shopping_items.quantity IS NULL THEN excluded.display_unit
+                WHEN shopping_items.display_unit IS excluded.display_unit
+                  THEN shopping_items.display_unit
+              END,
+              display_qty = CASE
+                WHEN excluded.quantity IS NULL THEN shopping_items.display_qty
+                WHEN shopping_items.quantity IS NULL THEN excluded.display_qty
+                WHEN shopping_items.display_unit IS excluded.display_unit
+                  THEN shopping_items.display_qty + excluded.display_qty
+              END,
               name = excluded.name,
               unit = excluded.unit,
               category = COALESCE(shopping_items.category, excluded.category),
+              store_id = COALESCE(shopping_items.store_id, excluded.store_id),
               recipe_ids = (
                 SELECT json_group_array(DISTINCT value)
                 FROM (
@@ -1101,19 +2013,325 @@ pub async fn merge_items(
               ),
               done = 0
             ",
-        )
-        .bind(&merge_name_norm)
-        .bind(unit_norm)
-        .bind(qty_norm)
-        .bind(&key)
-        .bind(chosen_cat)
-        .bind(&recipe_ids_json)
-        .execute(&state.pool)
+            )
+            .bind(&merge_name_norm)
+            .bind(unit_norm)
+            .bind(qty_norm)
+            .bind(&key)
+            .bind(&chosen_cat)
+            .bind(&recipe_ids_json)
+            .bind(store_id)
+            .bind(written.as_ref().map(|(u, _)| u))
+            .bind(written.as_ref().map(|(_, q)| q))
+            .execute(&state.pool)
+        })
         .await?;
     }
 
+    if let Some(recipe_id) = req.recipe_id {
+        record_generation(state, recipe_id, req.day.as_deref()).await?;
+    }
+
+    record_merge(state, actor, req, req.items.len() - skipped.len()).await;
+    if skipped.len() < req.items.len() {
+        state.shopping_events.publish(Change::Refresh);
+    }
+
     // Return the active (not done) list
-    list(State(state)).await
+    Ok(MergeResult {
+        items: active_items(state, None).await?,
+        skipped,
+    })
+}
+
+/// Remember that `recipe_id` was sent to the list, for `already_generated`.
+async fn record_generation(
+    state: &AppState,
+    recipe_id: i64,
+    day: Option<&str>,
+) -> sqlx::Result<()> {
+    sqlx::query(r"INSERT INTO shopping_generations (recipe_id, day, merge_id) VALUES (?, ?, ?)")
+        .bind(recipe_id)
+        .bind(day)
+        .bind(uuid::Uuid::new_v4().to_string())
+        .execute(&state.pool)
+        .await?;
+    Ok(())
+}
+
+async fn record_merge(state: &AppState, actor: Actor, req: &MergeReq, n: usize) {
+    let summary = req.recipe_id.map_or_else(
+        || format!("Added {n} item(s)"),
+        |recipe_id| format!("Added {n} item(s) from recipe {recipe_id}"),
+    );
+    activity::record(
+        state,
+        actor,
+        Event::new(Entity::ShoppingItem, None, "merge", summary),
+    )
+    .await;
+}
+
+/// Store for merged items: the request's choice, else the `default_store_id`
+/// setting. A default pointing at a deleted store is ignored.
+async fn merge_store(state: &AppState, requested: StoreField) -> AppResult<Option<i64>> {
+    match requested {
+        StoreField::AnyStore => return Ok(None),
+        StoreField::Store(id) => {
+            if !stores::store_exists(&state.pool, id).await? {
+                return Err((StatusCode::BAD_REQUEST, "invalid store".into()).into());
+            }
+            return Ok(Some(id));
+        }
+        StoreField::Absent => {}
+    }
+
+    let Some(id) = get_setting(&state.pool, "default_store_id")
+        .await
+        .and_then(|v| v.trim().parse::<i64>().ok())
+    else {
+        return Ok(None);
+    };
+    Ok(stores::store_exists(&state.pool, id).await?.then_some(id))
+}
+
+/// Reject a merge with 409 when the same recipe/day was already sent to the
+/// shopping list within the configured window.
+async fn ensure_not_recently_generated(
+    state: &AppState,
+    recipe_id: i64,
+    day: Option<&str>,
+) -> AppResult<()> {
+    let window = format!("-{} days", state.config.shopping_generation_window_days);
+    let previous: Option<(String, String)> = sqlx::query_as(
+        r"
+        SELECT merge_id, created_at
+          FROM shopping_generations
+         WHERE recipe_id = ? AND day IS ? AND created_at >= datetime('now', ?)
+         ORDER BY id DESC
+         LIMIT 1
+        ",
+    )
+    .bind(recipe_id)
+    .bind(day)
+    .bind(window)
+    .fetch_optional(&state.pool)
+    .await?;
+
+    match previous {
+        Some((merge_id, created_at)) => Err(AppError::conflict(
+            "already_generated",
+            "recipe was already added to the shopping list",
+            serde_json::json!({
+                "recipe_id": recipe_id,
+                "day": day,
+                "merge_id": merge_id,
+                "generated_at": created_at,
+            }),
+        )
+        .with_details(serde_json::json!({
+            "hint": "resend with \"force\": true to add it again",
+        }))),
+        None => Ok(()),
+    }
+}
+
+/// GET /shopping/generations?days=7
+///
+/// Recipes sent to the shopping list in the last `days` days, newest first.
+///
+/// # Errors
+/// Err if querying the database fails.
+pub async fn list_generations(
+    State(state): State<AppState>,
+    Query(q): Query<GenerationsQuery>,
+) -> AppResult<Json<Vec<ShoppingGeneration>>> {
+    let rows = sqlx::query_as::<_, ShoppingGeneration>(
+        r"
+        SELECT g.id, g.recipe_id, r.title AS recipe_title, g.day, g.merge_id, g.created_at
+          FROM shopping_generations g
+          LEFT JOIN recipes r ON r.id = g.recipe_id
+         WHERE g.created_at >= datetime('now', ?)
+         ORDER BY g.id DESC
+        ",
+    )
+    .bind(format!("-{} days", q.days))
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(rows))
+}
+
+/// Add a source row's quantity onto the running target quantity/unit.
+fn add_source_quantity(
+    quantity: &mut Option<f64>,
+    unit: &mut Option<String>,
+    src: &ShoppingItemRow,
+) -> Result<(), String> {
+    match (*quantity, src.quantity) {
+        (_, None) => {}
+        (None, Some(q)) => {
+            *quantity = Some(q);
+            unit.clone_from(&src.unit);
+        }
+        (Some(t), Some(q)) => {
+            let converted =
+                convert_qty(q, src.unit.as_deref(), unit.as_deref()).ok_or_else(|| {
+                    format!(
+                        "incompatible units: {} vs {}",
+                        src.unit.as_deref().unwrap_or("count"),
+                        unit.as_deref().unwrap_or("count"),
+                    )
+                })?;
+            *quantity = Some(t + converted);
+        }
+    }
+    Ok(())
+}
+
+/// POST /shopping/combine
+///
+/// Manually folds `source_ids` into `target_id`: quantities are summed when
+/// their units are in the same class (converted to the target's unit),
+/// categories/notes are kept from the target when set, recipe links are
+/// unioned, and the sources are deleted. The target's key is recomputed
+/// (after an optional rename) so future merges land on it.
+///
+/// Sources whose quantity cannot be reconciled are reported in `skipped`
+/// and left as-is.
+///
+/// # Errors
+/// - Returns `404` if the target does not exist.
+/// - Returns `400` if `name` is empty.
+/// - Returns `409` `duplicate_item` with the existing item if the resulting key
+///   collides with another item.
+#[allow(clippy::too_many_lines)]
+pub async fn combine_items(
+    State(state): State<AppState>,
+    actor: Actor,
+    Json(req): Json<CombineReq>,
+) -> AppResult<Json<CombineResp>> {
+    const ROW_SQL: &str = r"
+        SELECT name, unit, quantity, done, category, notes,
+               COALESCE(recipe_ids, '[]') AS recipe_ids, display_unit, display_qty
+          FROM shopping_items
+         WHERE id = ?
+        ";
+
+    let new_name = match req.name.as_deref().map(str::trim) {
+        Some("") => return Err((StatusCode::BAD_REQUEST, "empty name".into()).into()),
+        Some(n) => Some(normalize_name(&n.to_lowercase())),
+        None => None,
+    };
+
+    let mut tx = state.pool.begin().await?;
+
+    let target: ShoppingItemRow = sqlx::query_as(ROW_SQL)
+        .bind(req.target_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut unit = target.unit;
+    let mut quantity = target.quantity;
+    let mut category = target.category.filter(|c| !c.trim().is_empty());
+    let mut notes = target.notes;
+    let mut recipe_ids = target.recipe_ids;
+    // Kept only while nothing is folded in, since sources convert to the
+    // stored unit.
+    let mut written = target.display_unit.zip(target.display_qty);
+    let mut skipped = Vec::new();
+    let mut seen = Vec::new();
+
+    for &sid in &req.source_ids {
+        if sid == req.target_id || seen.contains(&sid) {
+            continue;
+        }
+        seen.push(sid);
+
+        let Some(src): Option<ShoppingItemRow> = sqlx::query_as(ROW_SQL)
+            .bind(sid)
+            .fetch_optional(&mut *tx)
+            .await?
+        else {
+            skipped.push(CombineSkip {
+                id: sid,
+                reason: "not found".into(),
+            });
+            continue;
+        };
+
+        if let Err(reason) = add_source_quantity(&mut quantity, &mut unit, &src) {
+            skipped.push(CombineSkip { id: sid, reason });
+            continue;
+        }
+
+        if category.is_none() {
+            category = src.category.filter(|c| !c.trim().is_empty());
+        }
+        if notes.trim().is_empty() {
+            notes = src.notes;
+        }
+        recipe_ids = merge_recipe_ids_json(&recipe_ids, &src.recipe_ids);
+        if src.quantity.is_some() {
+            written = None;
+        }
+
+        sqlx::query("DELETE FROM shopping_items WHERE id = ?")
+            .bind(sid)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let name = new_name.unwrap_or(target.name);
+    let key = make_key(&name, unit.as_deref());
+
+    let updated = sqlx::query(
+        r"
+        UPDATE shopping_items
+           SET name = ?, unit = ?, quantity = ?, key = ?,
+               category = ?, notes = ?, recipe_ids = ?,
+               display_unit = ?, display_qty = ?
+         WHERE id = ?
+        ",
+    )
+    .bind(&name)
+    .bind(&unit)
+    .bind(quantity)
+    .bind(&key)
+    .bind(&category)
+    .bind(&notes)
+    .bind(&recipe_ids)
+    .bind(written.as_ref().map(|(u, _)| u))
+    .bind(written.as_ref().map(|(_, q)| q))
+    .bind(req.target_id)
+    .execute(&mut *tx)
+    .await;
+
+    if let Err(err) = updated {
+        // Roll back before looking up the conflicting item.
+        drop(tx);
+        return Err(update_err(&state, err, &key, req.target_id).await);
+    }
+    let summary = format!(
+        "Combined {} item(s) into '{name}'",
+        seen.len() - skipped.len()
+    );
+    activity::record_in(
+        &mut tx,
+        &state,
+        actor,
+        Event::new(Entity::ShoppingItem, req.target_id, "combine", summary),
+    )
+    .await;
+    tx.commit().await?;
+
+    state.shopping_events.publish(Change::Refresh);
+
+    let item = fetch_view_by_id(&state, req.target_id)
+        .await
+        .map_err(internal_err)?;
+    Ok(Json(CombineResp { item, skipped }))
 }
 
 #[cfg(test)]
@@ -1138,26 +2356,148 @@ mod tests {
     }
 
     #[test]
-    fn test_normalize_unit_token() {
-        assert_eq!(normalize_unit_token("g"), Some("g".to_string()));
-        assert_eq!(normalize_unit_token("kg"), Some("kg".to_string()));
-        assert_eq!(normalize_unit_token("ml"), Some("ml".to_string()));
-        assert_eq!(normalize_unit_token("L"), Some("L".to_string()));
-        assert_eq!(normalize_unit_token("tsp"), Some("tsp".to_string()));
-        assert_eq!(normalize_unit_token("tbsp"), Some("tbsp".to_string()));
+    fn test_parse_item_line_fractions() {
+        let cases = [
+            ("1/2 cup sugar", 0.5, Some("cup"), "sugar"),
+            ("3/4 kg flour", 0.75, Some("kg"), "flour"),
+            ("1/2 kg flour", 0.5, Some("kg"), "flour"),
+            ("1 1/2 tsp salt", 1.5, Some("tsp"), "salt"),
+            ("1 ½ tsp salt", 1.5, Some("tsp"), "salt"),
+            ("1½ tbsp oil", 1.5, Some("tbsp"), "oil"),
+            ("¾ l milk", 0.75, Some("L"), "milk"),
+            ("⅓ cup cream", 1.0 / 3.0, Some("cup"), "cream"),
+            ("⅔ kg rice", 2.0 / 3.0, Some("kg"), "rice"),
+            ("¼ tsp pepper", 0.25, Some("tsp"), "pepper"),
+            (
+                "1/2 - 3/4 tsp chili flakes",
+                0.625,
+                Some("tsp"),
+                "chili flakes",
+            ),
+            (
+                "1/2-3/4 tsp chili flakes",
+                0.625,
+                Some("tsp"),
+                "chili flakes",
+            ),
+            ("½–¾ tsp cumin", 0.625, Some("tsp"), "cumin"),
+            ("2 - 3 apples", 2.5, None, "apples"),
+            ("2 ⅛ kg potatoes", 2.125, Some("kg"), "potatoes"),
+        ];
+        for (line, qty, unit, name) in cases {
+            let p = parse_item_line(line, &[]).unwrap();
+            let got = p.qty.unwrap();
+            assert!((got - qty).abs() < 1e-4, "{line:?}: {got}");
+            assert_eq!(p.unit.as_deref(), unit, "{line:?}");
+            assert_eq!(p.name_raw, name, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_item_line_counts_and_multipliers() {
+        let cases = [
+            ("eggs x12", Some(12.0), None, "eggs"),
+            ("eggs X12", Some(12.0), None, "eggs"),
+            ("eggs ×12", Some(12.0), None, "eggs"),
+            ("eggs 12x", Some(12.0), None, "eggs"),
+            ("eggs x 12", Some(12.0), None, "eggs"),
+            ("2x chicken breast", Some(2.0), None, "chicken breast"),
+            ("2× chicken breast", Some(2.0), None, "chicken breast"),
+            ("x2 chicken breast", Some(2.0), None, "chicken breast"),
+            ("2 x chicken breast", Some(2.0), None, "chicken breast"),
+            ("3x 1 l milk", Some(3.0), None, "1 l milk"),
+            ("chicken breast (2)", Some(2.0), None, "chicken breast"),
+            ("a dozen eggs", Some(12.0), None, "eggs"),
+            ("dozen eggs", Some(12.0), None, "eggs"),
+            ("2 dozen eggs", Some(24.0), None, "eggs"),
+            ("half a dozen eggs", Some(6.0), None, "eggs"),
+            ("a couple of onions", Some(2.0), None, "onions"),
+            ("couple onions", Some(2.0), None, "onions"),
+            ("an apple", Some(1.0), None, "apple"),
+            ("A lemon", Some(1.0), None, "lemon"),
+            ("a kg of flour", Some(1.0), Some("kg"), "flour"),
+            // Not counts: the whole line stays the name.
+            ("xanthan gum", None, None, "xanthan gum"),
+            ("a", None, None, "a"),
+            ("a dozen", None, None, "a dozen"),
+            ("eggs x", None, None, "eggs x"),
+            ("7up", None, None, "7up"),
+            (
+                "chicken breast (large)",
+                None,
+                None,
+                "chicken breast (large)",
+            ),
+        ];
+        for (line, qty, unit, name) in cases {
+            let p = parse_item_line(line, &[]).unwrap();
+            assert_eq!(p.qty, qty, "{line:?}");
+            assert_eq!(p.unit.as_deref(), unit, "{line:?}");
+            assert_eq!(p.name_raw, name, "{line:?}");
+        }
+
+        let key = |line: &str| {
+            let p = parse_item_line(line, &[]).unwrap();
+            let (unit, _) = to_canonical_qty_unit(p.unit.as_deref(), p.qty);
+            make_key(&p.name_norm, unit)
+        };
+        for line in ["eggs x12", "a dozen eggs", "Eggs (12)"] {
+            assert_eq!(key(line), key("12 eggs"), "{line:?}");
+        }
+    }
 
-        assert_eq!(normalize_unit_token("gram"), Some("g".to_string()));
-        assert_eq!(normalize_unit_token("GRAMS"), Some("g".to_string()));
+    #[test]
+    fn written_amounts_only_for_converted_units_and_merge_in_one_unit() {
+        let cups = |q: f64| Some(("cup".to_string(), q));
+        assert_eq!(written_amount(Some("cup"), Some(2.0)), cups(2.0));
+        assert_eq!(written_amount(Some("tbsp"), Some(2.0)), None);
+        assert_eq!(written_amount(Some("ml"), Some(2.0)), None);
+        assert_eq!(written_amount(Some("cup"), None), None);
+        assert_eq!(written_amount(None, Some(2.0)), None);
+
+        assert_eq!(
+            merge_written(cups(2.0), Some(480.0), cups(1.0), Some(240.0)),
+            cups(3.0)
+        );
+        assert_eq!(
+            merge_written(cups(2.0), Some(480.0), None, Some(100.0)),
+            None
+        );
+        assert_eq!(merge_written(cups(2.0), Some(480.0), None, None), cups(2.0));
+        assert_eq!(merge_written(None, None, cups(1.0), Some(240.0)), cups(1.0));
+        assert_eq!(
+            merge_written(
+                cups(2.0),
+                Some(480.0),
+                Some(("dl".into(), 1.0)),
+                Some(100.0)
+            ),
+            None
+        );
+    }
 
-        assert_eq!(normalize_unit_token(""), None);
-        assert_eq!(normalize_unit_token("  "), None);
-        assert_eq!(normalize_unit_token("cup"), None);
-        assert_eq!(normalize_unit_token("oz"), None);
+    #[test]
+    fn test_normalize_unit_token() {
+        assert_eq!(normalize_unit_token("g", &[]), Some("g".to_string()));
+        assert_eq!(normalize_unit_token("kg", &[]), Some("kg".to_string()));
+        assert_eq!(normalize_unit_token("ml", &[]), Some("ml".to_string()));
+        assert_eq!(normalize_unit_token("L", &[]), Some("L".to_string()));
+        assert_eq!(normalize_unit_token("tsp", &[]), Some("tsp".to_string()));
+        assert_eq!(normalize_unit_token("tbsp", &[]), Some("tbsp".to_string()));
+
+        assert_eq!(normalize_unit_token("gram", &[]), Some("g".to_string()));
+        assert_eq!(normalize_unit_token("GRAMS", &[]), Some("g".to_string()));
+
+        assert_eq!(normalize_unit_token("", &[]), None);
+        assert_eq!(normalize_unit_token("  ", &[]), None);
+        assert_eq!(normalize_unit_token("cup", &[]), Some("cup".to_string()));
+        assert_eq!(normalize_unit_token("oz", &[]), Some("oz".to_string()));
+        assert_eq!(normalize_unit_token("cupboard", &[]), None);
     }
 
     #[test]
     fn test_parse_item_line_simple() {
-        let p = parse_item_line("milk").unwrap();
+        let p = parse_item_line("milk", &[]).unwrap();
         assert_eq!(p.qty, None);
         assert_eq!(p.unit, None);
         assert_eq!(p.name_raw, "milk");
@@ -1166,7 +2506,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_with_qty() {
-        let p = parse_item_line("2 apples").unwrap();
+        let p = parse_item_line("2 apples", &[]).unwrap();
         assert_eq!(p.qty, Some(2.0));
         assert_eq!(p.unit, None);
         assert_eq!(p.name_raw, "apples");
@@ -1175,7 +2515,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_with_qty_and_unit() {
-        let p = parse_item_line("120 g flour").unwrap();
+        let p = parse_item_line("120 g flour", &[]).unwrap();
         assert_eq!(p.qty, Some(120.0));
         assert_eq!(p.unit, Some("g".to_string()));
         assert_eq!(p.name_raw, "flour");
@@ -1184,7 +2524,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_range() {
-        let p = parse_item_line("2-3 kg potatoes").unwrap();
+        let p = parse_item_line("2-3 kg potatoes", &[]).unwrap();
         assert_eq!(p.qty, Some(2.5));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "potatoes");
@@ -1193,7 +2533,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_with_of() {
-        let p = parse_item_line("2 kg of rice").unwrap();
+        let p = parse_item_line("2 kg of rice", &[]).unwrap();
         assert_eq!(p.qty, Some(2.0));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "rice");
@@ -1202,7 +2542,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_decimal() {
-        let p = parse_item_line("1.5 L water").unwrap();
+        let p = parse_item_line("1.5 L water", &[]).unwrap();
         assert_eq!(p.qty, Some(1.5));
         assert_eq!(p.unit, Some("L".to_string()));
         assert_eq!(p.name_raw, "water");
@@ -1211,16 +2551,122 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_comma_decimal() {
-        let p = parse_item_line("1,5 kg sugar").unwrap();
+        let p = parse_item_line("1,5 kg sugar", &[]).unwrap();
         assert_eq!(p.qty, Some(1.5));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "sugar");
         assert_eq!(p.name_norm, "sugar");
     }
 
+    #[test]
+    fn test_parse_item_line_thousands_separators() {
+        assert_eq!(
+            parse_item_line("1.500,5 g flour", &[]).unwrap().qty,
+            Some(1500.5)
+        );
+        assert_eq!(
+            parse_item_line("1,500.5 g flour", &[]).unwrap().qty,
+            Some(1500.5)
+        );
+        assert_eq!(
+            parse_item_line("1,5-2,5 kg apples", &[]).unwrap().qty,
+            Some(2.0)
+        );
+    }
+
+    /// (line, quantity, unit, name) as pasted from word processors and sites,
+    /// with the offending characters spelled as escapes.
+    const PASTED_CORPUS: &[(&str, f64, Option<&str>, &str)] = &[
+        ("1\u{00A0}\u{00BD} kg potatoes", 1.5, Some("kg"), "potatoes"),
+        ("200\u{00A0}g flour", 200.0, Some("g"), "flour"),
+        ("250\u{202F}ml cream", 250.0, Some("ml"), "cream"),
+        ("\u{FEFF}3 eggs", 3.0, None, "eggs"),
+        ("2\u{200B} tbsp oil", 2.0, Some("tbsp"), "oil"),
+        ("1\u{2044}2 tsp salt", 0.5, Some("tsp"), "salt"),
+        ("1 3\u{2044}4 l milk", 1.75, Some("L"), "milk"),
+        (
+            "100 g \u{2018}00\u{2019} flour",
+            100.0,
+            Some("g"),
+            "'00' flour",
+        ),
+    ];
+
+    #[test]
+    fn test_parse_item_line_pasted_text() {
+        for &(line, qty, unit, name) in PASTED_CORPUS {
+            let p = parse_item_line(line, &[]).unwrap_or_else(|| panic!("{line:?}"));
+            assert_eq!(p.qty, Some(qty), "{line:?}");
+            assert_eq!(p.unit.as_deref(), unit, "{line:?}");
+            assert_eq!(p.name_raw, name, "{line:?}");
+        }
+    }
+
+    /// (line, quantity, unit, name) for recipes written in other languages.
+    const LOCALIZED_CORPUS: &[(&str, f64, Option<&str>, &str)] = &[
+        // French
+        ("200 g de farine", 200.0, Some("g"), "farine"),
+        ("2 c. à soupe d'huile", 2.0, Some("tbsp"), "huile"),
+        ("1 c. à café de sel", 1.0, Some("tsp"), "sel"),
+        ("1 c.à.c cannelle", 1.0, Some("tsp"), "cannelle"),
+        ("3 cuillères à soupe de sucre", 3.0, Some("tbsp"), "sucre"),
+        ("1 cuillère de miel", 1.0, Some("tbsp"), "miel"),
+        ("50 grammes d’amandes", 50.0, Some("g"), "amandes"),
+        (
+            "1,5 kilo de pommes de terre",
+            1.5,
+            Some("kg"),
+            "pommes de terre",
+        ),
+        // German
+        ("2 EL Olivenöl", 2.0, Some("tbsp"), "olivenöl"),
+        ("1 TL Salz", 1.0, Some("tsp"), "salz"),
+        ("3 Esslöffel Zucker", 3.0, Some("tbsp"), "zucker"),
+        ("250 Gramm Mehl", 250.0, Some("g"), "mehl"),
+        ("0,5 l Milch", 0.5, Some("L"), "milch"),
+        // Swedish
+        ("3 dl mjölk", 3.0, Some("dl"), "mjölk"),
+        ("2 msk smör", 2.0, Some("tbsp"), "smör"),
+        ("1 tsk salt", 1.0, Some("tsp"), "salt"),
+        ("1 krm saffran", 1.0, Some("ml"), "saffran"),
+        ("2 ägg", 2.0, None, "ägg"),
+    ];
+
+    #[test]
+    fn test_parse_item_line_localized_corpus() {
+        for (line, qty, unit, name) in LOCALIZED_CORPUS {
+            let p = parse_item_line(line, &[]).unwrap();
+            assert_eq!(p.qty, Some(*qty), "{line}");
+            assert_eq!(p.unit.as_deref(), *unit, "{line}");
+            assert_eq!(p.name_norm, *name, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_parse_item_line_dl_becomes_ml() {
+        let p = parse_item_line("2,5 dl grädde", &[]).unwrap();
+        let (unit, qty) = to_canonical_qty_unit(p.unit.as_deref(), p.qty);
+        assert_eq!((unit, qty), (Some("ml"), Some(250.0)));
+    }
+
+    #[test]
+    fn test_parse_item_line_household_synonyms() {
+        let synonyms = parse_unit_synonyms(r#"{"knivsudd": "ml", "Schuss": "tsp"}"#).unwrap();
+        let p = parse_item_line("1 knivsudd salt", &synonyms).unwrap();
+        assert_eq!(p.unit.as_deref(), Some("ml"));
+        assert_eq!(p.name_norm, "salt");
+        let p = parse_item_line("1 schuss Essig", &synonyms).unwrap();
+        assert_eq!(p.unit.as_deref(), Some("tsp"));
+
+        // Without the setting they stay part of the name.
+        let p = parse_item_line("1 knivsudd salt", &[]).unwrap();
+        assert_eq!(p.unit, None);
+        assert_eq!(p.name_norm, "knivsudd salt");
+    }
+
     #[test]
     fn test_parse_item_line_case_insensitive() {
-        let p = parse_item_line("200 ML Milk").unwrap();
+        let p = parse_item_line("200 ML Milk", &[]).unwrap();
         assert_eq!(p.qty, Some(200.0));
         assert_eq!(p.unit, Some("ml".to_string()));
         assert_eq!(p.name_raw, "Milk");
@@ -1229,7 +2675,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_missing_name_fallback() {
-        let p = parse_item_line("2 kg").unwrap();
+        let p = parse_item_line("2 kg", &[]).unwrap();
         assert_eq!(p.qty, None);
         assert_eq!(p.unit, None);
         assert_eq!(p.name_raw, "2 kg");
@@ -1238,22 +2684,71 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_empty() {
-        assert!(parse_item_line("").is_none());
-        assert!(parse_item_line("   ").is_none());
+        assert!(parse_item_line("", &[]).is_none());
+        assert!(parse_item_line("   ", &[]).is_none());
     }
 
     #[test]
     fn test_parse_item_line_unknown_unit() {
-        let p = parse_item_line("2 cups flour").unwrap();
+        let p = parse_item_line("2 handfuls spinach", &[]).unwrap();
         assert_eq!(p.qty, Some(2.0));
         assert_eq!(p.unit, None);
-        assert_eq!(p.name_raw, "cups flour");
-        assert_eq!(p.name_norm, "cups flour");
+        assert_eq!(p.name_raw, "handfuls spinach");
+        assert_eq!(p.name_norm, "handfuls spinach");
+    }
+
+    #[test]
+    fn test_parse_item_line_prep() {
+        let p = parse_item_line("2 carrots, diced", &[]).unwrap();
+        assert_eq!(p.qty, Some(2.0));
+        assert_eq!(p.name_raw, "carrots");
+        assert_eq!(p.prep.as_deref(), Some("diced"));
+
+        let p = parse_item_line("200 g cheddar grated", &[]).unwrap();
+        assert_eq!(p.name_norm, "cheddar");
+        assert_eq!(p.prep.as_deref(), Some("grated"));
+
+        // Prep before the name isn't understood; the line stays whole.
+        let p = parse_item_line("diced onion, 2", &[]).unwrap();
+        assert_eq!(p.qty, None);
+        assert_eq!(p.name_raw, "diced onion, 2");
+        assert_eq!(p.prep, None);
+    }
+
+    #[test]
+    fn test_parse_item_line_imperial_units() {
+        let p = parse_item_line("2 cups flour", &[]).unwrap();
+        assert_eq!(p.unit.as_deref(), Some("cup"));
+        assert_eq!(p.name_raw, "flour");
+        assert_eq!(
+            to_canonical_qty_unit(p.unit.as_deref(), p.qty),
+            (Some("ml"), Some(480.0))
+        );
+
+        let p = parse_item_line("4 fl oz cream", &[]).unwrap();
+        assert_eq!(p.unit.as_deref(), Some("fl oz"));
+        assert_eq!(p.name_raw, "cream");
+    }
+
+    #[test]
+    fn parse_ingredient_line_folds_units_and_keeps_prep() {
+        let ing = parse_ingredient_line("1 1/2 cups milk, warmed").unwrap();
+        assert_eq!(ing.quantity, Some(360.0));
+        assert_eq!(ing.unit.as_deref(), Some("ml"));
+        assert_eq!(ing.name, "milk");
+        assert_eq!(ing.prep.as_deref(), Some("warmed"));
+        assert!(!ing.raw);
+
+        let ing = parse_ingredient_line("salt").unwrap();
+        assert_eq!(
+            (ing.quantity, ing.unit, ing.name.as_str()),
+            (None, None, "salt")
+        );
     }
 
     #[test]
     fn test_parse_item_line_whitespace_normalization() {
-        let p = parse_item_line("  2   kg    of   flour  ").unwrap();
+        let p = parse_item_line("  2   kg    of   flour  ", &[]).unwrap();
         assert_eq!(p.qty, Some(2.0));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "flour");
@@ -1316,7 +2811,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_simple_fraction() {
-        let p = parse_item_line("1/2 kg flour").unwrap();
+        let p = parse_item_line("1/2 kg flour", &[]).unwrap();
         assert_eq!(p.qty, Some(0.5));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "flour");
@@ -1324,7 +2819,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_mixed_number() {
-        let p = parse_item_line("1 1/2 kg flour").unwrap();
+        let p = parse_item_line("1 1/2 kg flour", &[]).unwrap();
         assert_eq!(p.qty, Some(1.5));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "flour");
@@ -1332,7 +2827,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_unicode_half() {
-        let p = parse_item_line("½ kg flour").unwrap();
+        let p = parse_item_line("½ kg flour", &[]).unwrap();
         assert_eq!(p.qty, Some(0.5));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "flour");
@@ -1340,7 +2835,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_unicode_mixed() {
-        let p = parse_item_line("1½ kg flour").unwrap();
+        let p = parse_item_line("1½ kg flour", &[]).unwrap();
         assert_eq!(p.qty, Some(1.5));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "flour");
@@ -1348,7 +2843,7 @@ mod tests {
 
     #[test]
     fn test_parse_item_line_unicode_three_quarters() {
-        let p = parse_item_line("¾ kg butter").unwrap();
+        let p = parse_item_line("¾ kg butter", &[]).unwrap();
         assert_eq!(p.qty, Some(0.75));
         assert_eq!(p.unit, Some("kg".to_string()));
         assert_eq!(p.name_raw, "butter");
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use sqlx::SqlitePool;
+
+use crate::{
+    error::AppResult,
+    extract::Json,
+    models::{AppState, NewStore, ReorderCategories, Store, StoreCategoryOrder},
+    shopping_events::Change,
+};
+
+/// Whether a store with this id exists.
+pub async fn store_exists(pool: &SqlitePool, id: i64) -> sqlx::Result<bool> {
+    let found: Option<i64> = sqlx::query_scalar(r"SELECT id FROM stores WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(found.is_some())
+}
+
+/// The store's aisle position per category name. Categories without a
+/// position are absent and sort by the global order instead.
+pub async fn category_positions(
+    pool: &SqlitePool,
+    store_id: i64,
+) -> sqlx::Result<HashMap<String, i64>> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        r"
+        SELECT c.name, o.sort_order
+          FROM store_category_order o
+          JOIN shopping_categories c ON c.id = o.category_id
+         WHERE o.store_id = ?
+        ",
+    )
+    .bind(store_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+async fn fetch_store(pool: &SqlitePool, id: i64) -> AppResult<Store> {
+    sqlx::query_as(r"SELECT id, name, created_at FROM stores WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into())
+}
+
+fn store_name(raw: &str) -> AppResult<&str> {
+    let name = raw.trim();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Store name cannot be empty".to_string(),
+        )
+            .into());
+    }
+    Ok(name)
+}
+
+fn name_conflict(e: sqlx::Error, name: &str) -> crate::error::AppError {
+    if let sqlx::Error::Database(db) = &e
+        && db.is_unique_violation()
+    {
+        return (
+            StatusCode::CONFLICT,
+            format!("Store '{name}' already exists"),
+        )
+            .into();
+    }
+    e.into()
+}
+
+/// GET /stores
+pub async fn list(State(state): State<AppState>) -> AppResult<Json<Vec<Store>>> {
+    let rows: Vec<Store> =
+        sqlx::query_as(r"SELECT id, name, created_at FROM stores ORDER BY name, id")
+            .fetch_all(&state.pool)
+            .await?;
+    Ok(Json(rows))
+}
+
+/// POST /stores
+pub async fn create(
+    State(state): State<AppState>,
+    Json(req): Json<NewStore>,
+) -> AppResult<Json<Store>> {
+    let name = store_name(&req.name)?;
+    let id: i64 = sqlx::query_scalar(r"INSERT INTO stores (name) VALUES (?) RETURNING id")
+        .bind(name)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| name_conflict(e, name))?;
+
+    Ok(Json(fetch_store(&state.pool, id).await?))
+}
+
+/// PATCH /stores/{id}
+/// Rename a store.
+pub async fn update(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<NewStore>,
+) -> AppResult<Json<Store>> {
+    fetch_store(&state.pool, id).await?;
+    let name = store_name(&req.name)?;
+    sqlx::query(r"UPDATE stores SET name = ? WHERE id = ?")
+        .bind(name)
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| name_conflict(e, name))?;
+
+    Ok(Json(fetch_store(&state.pool, id).await?))
+}
+
+/// DELETE /stores/{id}
+/// Items assigned to the store go back to "any store"; the default-store
+/// setting is cleared if it pointed here.
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<serde_json::Value>> {
+    let mut tx = state.pool.begin().await?;
+    let unassigned = sqlx::query(r"UPDATE shopping_items SET store_id = NULL WHERE store_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    sqlx::query(r"DELETE FROM store_category_order WHERE store_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(r"DELETE FROM settings WHERE key = 'default_store_id' AND value = ?")
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    let affected = sqlx::query(r"DELETE FROM stores WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    tx.commit().await?;
+    if unassigned > 0 {
+        state.shopping_events.publish(Change::Refresh);
+    }
+
+    Ok(Json(serde_json::json!({ "deleted": affected })))
+}
+
+/// GET /stores/{id}/category-order
+///
+/// Every category in the order the store's list uses: the store's own
+/// positions first, then the remaining categories in global order with
+/// `sort_order: null`.
+pub async fn get_category_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> AppResult<Json<Vec<StoreCategoryOrder>>> {
+    fetch_store(&state.pool, id).await?;
+    let rows: Vec<StoreCategoryOrder> = sqlx::query_as(
+        r"
+        SELECT c.id AS category_id, c.name, o.sort_order
+          FROM shopping_categories c
+          LEFT JOIN store_category_order o
+            ON o.category_id = c.id AND o.store_id = ?
+         ORDER BY o.sort_order IS NULL, o.sort_order, c.sort_order, c.id
+        ",
+    )
+    .bind(id)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(rows))
+}
+
+/// PUT /stores/{id}/category-order
+/// Replace the store's aisle order with the given category IDs.
+pub async fn set_category_order(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<ReorderCategories>,
+) -> AppResult<Json<Vec<StoreCategoryOrder>>> {
+    fetch_store(&state.pool, id).await?;
+
+    let mut tx = state.pool.begin().await?;
+    sqlx::query(r"DELETE FROM store_category_order WHERE store_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for (idx, category_id) in req.order.iter().enumerate() {
+        let known: Option<i64> =
+            sqlx::query_scalar(r"SELECT id FROM shopping_categories WHERE id = ?")
+                .bind(category_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if known.is_none() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("unknown category id {category_id}"),
+            )
+                .into());
+        }
+        #[allow(clippy::cast_possible_wrap)]
+        let order = idx as i64;
+        sqlx::query(
+            r"INSERT OR REPLACE INTO store_category_order (store_id, category_id, sort_order)
+              VALUES (?, ?, ?)",
+        )
+        .bind(id)
+        .bind(category_id)
+        .bind(order)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    get_category_order(State(state), Path(id)).await
+}
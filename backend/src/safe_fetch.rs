@@ -0,0 +1,288 @@
+//! Fetching user-supplied URLs (recipe pages, their images) without turning
+//! the server into an SSRF proxy.
+//!
+//! Only http(s) URLs whose host resolves to public addresses are fetched, and
+//! the connection is pinned to the addresses that were checked. Redirects are
+//! followed by hand so every hop is checked the same way. Bodies are
+//! decompressed (gzip, brotli), read in chunks and cut off at a size limit.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use url::{Host, Url};
+
+use crate::error::AppError;
+
+/// Largest recipe page we read.
+pub const MAX_HTML_BYTES: usize = 5 * 1024 * 1024;
+
+/// Largest remote image we download.
+pub const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+const MAX_REDIRECTS: usize = 5;
+
+const TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Browser-like, as some recipe sites answer unknown agents with 403.
+const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 \
+                          (KHTML, like Gecko) Chrome/126.0 Safari/537.36 blaz/recipe-importer";
+
+const ACCEPT_LANGUAGE: &str = "en-US,en;q=0.9,*;q=0.5";
+
+/// A fetched response body with what is needed to interpret it.
+pub struct Page {
+    /// Where the body came from, after redirects.
+    pub url: Url,
+    /// The `Content-Type` header, which may name the charset.
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// The URL, or a redirect target, is not something we fetch.
+    Blocked(String),
+    /// The body is larger than the limit (in bytes).
+    TooLarge(usize),
+    /// Network failure or a non-success status.
+    Failed(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blocked(msg) | Self::Failed(msg) => f.write_str(msg),
+            Self::TooLarge(limit) => {
+                write!(f, "response is larger than {} MB", limit / (1024 * 1024))
+            }
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<FetchError> for AppError {
+    fn from(e: FetchError) -> Self {
+        match e {
+            FetchError::Blocked(msg) => Self::Code(StatusCode::BAD_REQUEST, "url_blocked", msg),
+            e => Self::Msg(StatusCode::BAD_GATEWAY, format!("fetch failed: {e}")),
+        }
+    }
+}
+
+/// Whether `ip` is a globally routable unicast address. Loopback, private,
+/// link-local (incl. cloud metadata), CGNAT, multicast and unspecified
+/// addresses are not; IPv4-mapped IPv6 addresses are judged as IPv4.
+#[must_use]
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_v4(v4);
+            }
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || v6.is_unique_local()
+                || v6.is_unicast_link_local())
+        }
+    }
+}
+
+fn is_public_v4(v4: Ipv4Addr) -> bool {
+    let [a, b, ..] = v4.octets();
+    let shared = a == 100 && (64..128).contains(&b); // 100.64.0.0/10
+    !(a == 0
+        || shared
+        || v4.is_private()
+        || v4.is_loopback()
+        || v4.is_link_local()
+        || v4.is_multicast()
+        || v4.is_broadcast()
+        || v4.is_documentation())
+}
+
+/// GET `url` and return at most `max_bytes` of body.
+///
+/// `allow_private` skips the address check (`--fetch-allow-private`), for
+/// recipe servers on the local network.
+///
+/// # Errors
+/// `Blocked` for non-http(s) URLs or hosts with a non-public address,
+/// including after a redirect; `TooLarge` over `max_bytes`; `Failed` for
+/// network errors and non-success statuses.
+pub async fn get(url: &str, max_bytes: usize, allow_private: bool) -> Result<Vec<u8>, FetchError> {
+    Ok(get_page(url, max_bytes, allow_private).await?.body)
+}
+
+/// [`get`], also returning the final URL and the `Content-Type`.
+///
+/// # Errors
+/// As for [`get`].
+pub async fn get_page(
+    url: &str,
+    max_bytes: usize,
+    allow_private: bool,
+) -> Result<Page, FetchError> {
+    let mut url = Url::parse(url).map_err(|e| FetchError::Blocked(format!("invalid URL: {e}")))?;
+    for _ in 0..=MAX_REDIRECTS {
+        let client = pinned_client(&url, allow_private).await?;
+        let resp = client
+            .get(url.clone())
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .header(reqwest::header::ACCEPT_LANGUAGE, ACCEPT_LANGUAGE)
+            .send()
+            .await
+            .map_err(|e| FetchError::Failed(format!("request failed: {e}")))?;
+
+        let status = resp.status();
+        if status.is_redirection() {
+            let location = resp
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| FetchError::Failed(format!("HTTP {status} without a Location")))?;
+            url = url
+                .join(location)
+                .map_err(|e| FetchError::Failed(format!("bad redirect to {location}: {e}")))?;
+            continue;
+        }
+        if !status.is_success() {
+            return Err(FetchError::Failed(format!("HTTP {status} fetching {url}")));
+        }
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = read_capped(resp, max_bytes).await?;
+        return Ok(Page {
+            url,
+            content_type,
+            body,
+        });
+    }
+    Err(FetchError::Failed(format!(
+        "more than {MAX_REDIRECTS} redirects"
+    )))
+}
+
+/// A client that can only reach `url`'s host at addresses that passed
+/// the check, so a second DNS answer can't point it elsewhere.
+async fn pinned_client(url: &Url, allow_private: bool) -> Result<reqwest::Client, FetchError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(FetchError::Blocked(format!(
+            "only http and https URLs can be fetched, not {}:",
+            url.scheme()
+        )));
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let (domain, addrs) = match url.host() {
+        Some(Host::Ipv4(ip)) => (None, vec![SocketAddr::new(ip.into(), port)]),
+        Some(Host::Ipv6(ip)) => (None, vec![SocketAddr::new(ip.into(), port)]),
+        Some(Host::Domain(domain)) => {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| FetchError::Failed(format!("cannot resolve {domain}: {e}")))?
+                .collect();
+            (Some(domain), addrs)
+        }
+        None => return Err(FetchError::Blocked("URL has no host".into())),
+    };
+    if !allow_private && let Some(addr) = addrs.iter().find(|a| !is_public_ip(a.ip())) {
+        return Err(FetchError::Blocked(format!(
+            "{} is not a public address",
+            addr.ip()
+        )));
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(TIMEOUT);
+    if let Some(domain) = domain {
+        builder = builder.resolve_to_addrs(domain, &addrs);
+    }
+    builder
+        .build()
+        .map_err(|e| FetchError::Failed(format!("http client: {e}")))
+}
+
+async fn read_capped(mut resp: reqwest::Response, max_bytes: usize) -> Result<Vec<u8>, FetchError> {
+    if resp
+        .content_length()
+        .is_some_and(|len| len > max_bytes as u64)
+    {
+        return Err(FetchError::TooLarge(max_bytes));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| FetchError::Failed(format!("reading body failed: {e}")))?
+    {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(FetchError::TooLarge(max_bytes));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.10",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "224.0.0.1",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn internet_addresses_are_public() {
+        for ip in [
+            "93.184.216.34",
+            "1.1.1.1",
+            "100.128.0.1",
+            "172.32.0.1",
+            "2606:4700::1111",
+            "::ffff:8.8.8.8",
+        ] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn non_http_and_private_urls_are_blocked() {
+        for url in [
+            "file:///etc/passwd",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://localhost:8080/app-state",
+            "http://[::1]/",
+        ] {
+            let err = get(url, MAX_HTML_BYTES, false).await.unwrap_err();
+            assert!(matches!(err, FetchError::Blocked(_)), "{url}: {err}");
+        }
+    }
+}
@@ -1,12 +1,17 @@
-/// Extract structured recipe data from schema.org JSON-LD markup
+/// Extract structured recipe data from schema.org JSON-LD markup, and emit it
+/// for our own recipes.
 use scraper::{Html, Selector};
+use serde::Serialize;
 use serde_json::Value as JsonValue;
 
+use crate::models::{Recipe, RecipeMacros};
+
 #[derive(Debug, Clone)]
 pub struct SchemaRecipe {
     pub name: String,
     pub ingredients: Vec<String>,
     pub instructions: Vec<String>,
+    pub tools: Vec<String>,
 }
 
 /// Extract recipe data from schema.org JSON-LD in HTML
@@ -24,22 +29,7 @@ pub fn extract_schema_recipe(html: &str) -> Option<SchemaRecipe> {
             Err(_) => continue,
         };
 
-        // Handle different JSON-LD formats:
-        // 1. Direct Recipe object: {"@type": "Recipe", ...}
-        // 2. Array with Recipe: [{"@type": "WebSite"}, {"@type": "Recipe"}]
-        // 3. Graph format: {"@graph": [{"@type": "Recipe"}]}
-        let recipe = if let Some(graph) = json.get("@graph").and_then(|g| g.as_array()) {
-            // Handle @graph format (common with Yoast SEO)
-            graph.iter().find(|item| is_recipe_type(item))
-        } else if json.is_array() {
-            // Handle array format
-            json.as_array()?.iter().find(|item| is_recipe_type(item))
-        } else if is_recipe_type(&json) {
-            // Handle direct Recipe object
-            Some(&json)
-        } else {
-            None
-        };
+        let recipe = find_recipe(&json);
 
         if let Some(recipe_data) = recipe
             && let Some(extracted) = extract_recipe_fields(recipe_data)
@@ -57,6 +47,23 @@ pub fn extract_schema_recipe(html: &str) -> Option<SchemaRecipe> {
     None
 }
 
+/// First `Recipe` node, looking through the shapes sites emit: a bare object,
+/// an array, a `@graph` (Yoast and friends, possibly nested inside an array)
+/// and a page's `mainEntity`.
+fn find_recipe(json: &JsonValue) -> Option<&JsonValue> {
+    if is_recipe_type(json) {
+        return Some(json);
+    }
+    match json {
+        JsonValue::Array(items) => items.iter().find_map(find_recipe),
+        JsonValue::Object(obj) => ["@graph", "mainEntity"]
+            .iter()
+            .filter_map(|key| obj.get(*key))
+            .find_map(find_recipe),
+        _ => None,
+    }
+}
+
 fn is_recipe_type(json: &JsonValue) -> bool {
     if let Some(type_val) = json.get("@type") {
         if let Some(type_str) = type_val.as_str() {
@@ -87,9 +94,27 @@ fn extract_recipe_fields(recipe: &JsonValue) -> Option<SchemaRecipe> {
         name,
         ingredients,
         instructions,
+        tools: extract_tools(recipe.get("tool")),
     })
 }
 
+/// Names from a `tool` value: a string, a `HowToTool` object, or an array of
+/// either. Anything else yields nothing.
+pub fn extract_tools(tool: Option<&JsonValue>) -> Vec<String> {
+    let one = |v: &JsonValue| {
+        v.as_str()
+            .or_else(|| v.get("name").and_then(|n| n.as_str()))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+    };
+    match tool {
+        Some(JsonValue::Array(arr)) => arr.iter().filter_map(one).collect(),
+        Some(v) => one(v).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
 fn extract_ingredients(recipe: &JsonValue) -> Option<Vec<String>> {
     let ing_value = recipe.get("recipeIngredient")?;
 
@@ -98,6 +123,7 @@ fn extract_ingredients(recipe: &JsonValue) -> Option<Vec<String>> {
     if let Some(arr) = ing_value.as_array() {
         for item in arr {
             if let Some(text) = item.as_str() {
+                let text = crate::units::preclean_text(text);
                 let trimmed = text.trim();
                 if !trimmed.is_empty() {
                     ingredients.push(trimmed.to_string());
@@ -114,58 +140,219 @@ fn extract_ingredients(recipe: &JsonValue) -> Option<Vec<String>> {
 }
 
 fn extract_instructions(recipe: &JsonValue) -> Option<Vec<String>> {
-    let inst_value = recipe.get("recipeInstructions")?;
-
     let mut instructions = Vec::new();
+    push_instructions(recipe.get("recipeInstructions")?, &mut instructions);
 
-    // Handle array of instructions
-    if let Some(arr) = inst_value.as_array() {
-        for item in arr {
-            // Handle string
-            if let Some(text) = item.as_str() {
-                let trimmed = text.trim();
-                if !trimmed.is_empty() {
-                    instructions.push(trimmed.to_string());
-                }
+    if instructions.is_empty() {
+        None
+    } else {
+        Some(instructions)
+    }
+}
+
+/// Flatten `recipeInstructions`: a string, a `HowToStep`, a `HowToSection`
+/// (its name becomes a `## Name` line), or an array of any of these.
+fn push_instructions(value: &JsonValue, out: &mut Vec<String>) {
+    let mut push = |text: &str| {
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            out.push(trimmed.to_string());
+        }
+    };
+    match value {
+        JsonValue::String(text) => push(text),
+        JsonValue::Array(items) => {
+            for item in items {
+                push_instructions(item, out);
             }
-            // Handle HowToStep object
-            else if let Some(text) = item.get("text").and_then(|v| v.as_str()) {
-                let trimmed = text.trim();
-                if !trimmed.is_empty() {
-                    instructions.push(trimmed.to_string());
+        }
+        JsonValue::Object(obj) => {
+            let is_section = obj.get("@type").and_then(JsonValue::as_str) == Some("HowToSection")
+                || obj.contains_key("itemListElement");
+            if is_section {
+                if let Some(name) = obj
+                    .get("name")
+                    .and_then(JsonValue::as_str)
+                    .map(str::trim)
+                    .filter(|n| !n.is_empty())
+                {
+                    push(&format!("## {name}"));
                 }
-            }
-            // Handle HowToSection with itemListElement
-            else if let Some(name) = item.get("name").and_then(|v| v.as_str()) {
-                instructions.push(format!("## {name}"));
-                if let Some(steps) = item.get("itemListElement").and_then(|v| v.as_array()) {
-                    for step in steps {
-                        if let Some(text) = step.get("text").and_then(|v| v.as_str()) {
-                            let trimmed = text.trim();
-                            if !trimmed.is_empty() {
-                                instructions.push(trimmed.to_string());
-                            }
-                        }
-                    }
+                if let Some(steps) = obj.get("itemListElement") {
+                    push_instructions(steps, out);
                 }
+            } else if let Some(text) = obj
+                .get("text")
+                .or_else(|| obj.get("name"))
+                .and_then(JsonValue::as_str)
+            {
+                push(text);
             }
         }
+        _ => {}
     }
-    // Handle single string
-    else if let Some(text) = inst_value.as_str() {
-        let trimmed = text.trim();
-        if !trimmed.is_empty() {
-            instructions.push(trimmed.to_string());
+}
+
+/* =========================
+ * Emitting schema.org Recipe
+ * ========================= */
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeJsonLd {
+    #[serde(rename = "@context")]
+    pub context: &'static str,
+    #[serde(rename = "@type")]
+    pub kind: &'static str,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_based_on: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipe_yield: Option<String>,
+    pub recipe_ingredient: Vec<String>,
+    pub recipe_instructions: Vec<Instruction>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nutrition: Option<NutritionInformation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_modified: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Instruction {
+    Step(HowToStep),
+    Section(HowToSection),
+}
+
+#[derive(Serialize, Debug)]
+pub struct HowToStep {
+    #[serde(rename = "@type")]
+    pub kind: &'static str,
+    pub text: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HowToSection {
+    #[serde(rename = "@type")]
+    pub kind: &'static str,
+    pub name: String,
+    pub item_list_element: Vec<HowToStep>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NutritionInformation {
+    #[serde(rename = "@type")]
+    pub kind: &'static str,
+    pub serving_size: String,
+    pub calories: String,
+    pub protein_content: String,
+    pub fat_content: String,
+    pub carbohydrate_content: String,
+}
+
+impl HowToStep {
+    fn new(text: &str) -> Self {
+        Self {
+            kind: "HowToStep",
+            text: text.to_string(),
         }
     }
+}
 
-    if instructions.is_empty() {
-        None
-    } else {
-        Some(instructions)
+/// schema.org representation of a stored recipe. `base_url` (no trailing
+/// slash) makes image and share links absolute.
+#[must_use]
+pub fn recipe_jsonld(recipe: &Recipe, base_url: &str) -> RecipeJsonLd {
+    let non_empty = |s: &str| (!s.trim().is_empty()).then(|| s.trim().to_string());
+    let is_web = |s: &str| s.starts_with("http://") || s.starts_with("https://");
+
+    RecipeJsonLd {
+        context: "https://schema.org",
+        kind: "Recipe",
+        name: recipe.title.clone(),
+        url: recipe
+            .share_token
+            .as_deref()
+            .map(|t| format!("{base_url}/share/{t}")),
+        is_based_on: Some(recipe.source.clone()).filter(|s| is_web(s)),
+        image: recipe
+            .image_path_full
+            .as_deref()
+            .map(|p| format!("{base_url}/media/{p}")),
+        description: non_empty(&crate::html::sanitize_fragment(&recipe.notes)),
+        recipe_yield: non_empty(&recipe.r#yield),
+        recipe_ingredient: recipe
+            .ingredients
+            .iter()
+            .filter(|i| i.section.is_none())
+            .map(crate::units::format_ingredient_line)
+            .collect(),
+        recipe_instructions: instructions_jsonld(&recipe.instructions),
+        tool: recipe.equipment.clone(),
+        nutrition: recipe.macros.as_ref().map(nutrition_jsonld),
+        date_published: sqlite_to_iso8601(&recipe.created_at),
+        date_modified: sqlite_to_iso8601(&recipe.updated_at),
+    }
+}
+
+/// Steps become `HowToStep`s; a `## Name` line opens a `HowToSection` that
+/// collects the following steps.
+fn instructions_jsonld(lines: &[String]) -> Vec<Instruction> {
+    let mut out = Vec::new();
+    let lines = lines.iter().map(|l| crate::html::strip_tags(l));
+    for line in lines.filter(|l| !l.is_empty()) {
+        let line = line.as_str();
+        if let Some(name) = line.strip_prefix("## ") {
+            out.push(Instruction::Section(HowToSection {
+                kind: "HowToSection",
+                name: name.trim().to_string(),
+                item_list_element: Vec::new(),
+            }));
+        } else if let Some(Instruction::Section(section)) = out.last_mut() {
+            section.item_list_element.push(HowToStep::new(line));
+        } else {
+            out.push(Instruction::Step(HowToStep::new(line)));
+        }
+    }
+    out
+}
+
+fn nutrition_jsonld(m: &RecipeMacros) -> NutritionInformation {
+    let grams = |g: f64| format!("{} g", (g * 10.0).round() / 10.0);
+    let kcal = 4.0f64.mul_add(m.protein_g, 9.0f64.mul_add(m.fat_g, 4.0 * m.carbs_g));
+    NutritionInformation {
+        kind: "NutritionInformation",
+        serving_size: if m.basis == "per_serving" {
+            "1 serving".to_string()
+        } else {
+            "1 recipe".to_string()
+        },
+        calories: format!("{} calories", kcal.round()),
+        protein_content: grams(m.protein_g),
+        fat_content: grams(m.fat_g),
+        carbohydrate_content: grams(m.carbs_g),
     }
 }
 
+/// `CURRENT_TIMESTAMP` values are UTC `YYYY-MM-DD HH:MM:SS`.
+fn sqlite_to_iso8601(ts: &str) -> Option<String> {
+    chrono::NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,6 +443,108 @@ mod tests {
         assert_eq!(recipe.instructions.len(), 2);
     }
 
+    #[test]
+    fn test_extract_recipe_from_graph_nested_in_array() {
+        let html = r#"
+            <script type="application/ld+json">
+            [
+                {"@context": "https://schema.org", "@graph": [
+                    {"@type": "WebPage", "mainEntity": {
+                        "@type": ["Recipe", "NewsArticle"],
+                        "name": "Nested Recipe",
+                        "recipeIngredient": ["1 egg"],
+                        "recipeInstructions": "Fry the egg."
+                    }}
+                ]}
+            ]
+            </script>
+        "#;
+
+        let recipe = extract_schema_recipe(html).unwrap();
+        assert_eq!(recipe.name, "Nested Recipe");
+        assert_eq!(recipe.instructions, ["Fry the egg."]);
+    }
+
+    #[test]
+    fn test_extract_instructions_from_sections_and_steps() {
+        let html = r#"
+            <script type="application/ld+json">
+            {
+                "@type": "Recipe",
+                "name": "Layered",
+                "recipeIngredient": ["200 g flour"],
+                "recipeInstructions": [
+                    {"@type": "HowToStep", "text": " Preheat the oven. "},
+                    {"@type": "HowToSection", "name": "Sauce", "itemListElement": [
+                        {"@type": "HowToStep", "text": "Whisk."},
+                        {"@type": "HowToStep", "name": "Simmer."}
+                    ]},
+                    {"@type": "HowToSection", "itemListElement": [
+                        {"@type": "HowToStep", "text": "Assemble."}
+                    ]}
+                ]
+            }
+            </script>
+        "#;
+
+        let recipe = extract_schema_recipe(html).unwrap();
+        assert_eq!(
+            recipe.instructions,
+            [
+                "Preheat the oven.",
+                "## Sauce",
+                "Whisk.",
+                "Simmer.",
+                "Assemble."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_tools() {
+        let html = r#"
+            <html>
+            <head>
+                <script type="application/ld+json">
+                {
+                    "@type": "Recipe",
+                    "name": "Stew",
+                    "recipeIngredient": ["1 onion"],
+                    "recipeInstructions": ["Cook"],
+                    "tool": [
+                        "Crockpot",
+                        {"@type": "HowToTool", "name": "Ladle"},
+                        {"@type": "HowToTool"}
+                    ]
+                }
+                </script>
+            </head>
+            </html>
+        "#;
+
+        let recipe = extract_schema_recipe(html).unwrap();
+        assert_eq!(recipe.tools, vec!["Crockpot", "Ladle"]);
+    }
+
+    #[test]
+    fn instructions_group_under_sections() {
+        let lines: Vec<String> = ["Preheat oven", "## Sauce", "Whisk", "Simmer"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let got = serde_json::to_value(instructions_jsonld(&lines)).unwrap();
+        assert_eq!(
+            got,
+            serde_json::json!([
+                {"@type": "HowToStep", "text": "Preheat oven"},
+                {"@type": "HowToSection", "name": "Sauce", "itemListElement": [
+                    {"@type": "HowToStep", "text": "Whisk"},
+                    {"@type": "HowToStep", "text": "Simmer"}
+                ]}
+            ])
+        );
+    }
+
     #[test]
     fn test_no_recipe_returns_none() {
         let html = r#"
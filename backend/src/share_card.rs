@@ -0,0 +1,372 @@
+//! Social preview card (`og:image`) for shared recipes: the recipe photo
+//! cropped to 1200×630 under a dark gradient, with the title on top.
+//!
+//! Text is drawn with an embedded `DejaVu` Sans Bold (Latin, Greek and
+//! Cyrillic). A title with characters the font lacks, such as CJK, is left
+//! off rather than drawn as boxes; the card still shows the photo and brand.
+
+use std::io;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use ab_glyph::{Font, FontRef, Glyph, Point, PxScale, ScaleFont, point};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+
+use crate::models::Recipe;
+
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+
+/// Cached cards live here, relative to the media dir.
+pub const CACHE_DIR: &str = "share-cards";
+
+const MARGIN: u32 = 64;
+const TITLE_PX: f32 = 72.0;
+const BRAND_PX: f32 = 40.0;
+const SHADOW_OFFSET: u32 = 3;
+const MAX_TITLE_LINES: usize = 3;
+
+const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+const SHADOW: Rgb<u8> = Rgb([0, 0, 0]);
+const BRAND: Rgb<u8> = Rgb([0x4d, 0xb6, 0xac]);
+const BACKGROUND_TOP: [u8; 3] = [0x00, 0x79, 0x6b];
+const BACKGROUND_BOTTOM: [u8; 3] = [0x00, 0x4d, 0x40];
+
+/// `DejaVu` Sans Bold; see `assets/fonts/LICENSE-DejaVu.txt`.
+static FONT: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf"))
+        .expect("embedded font parses")
+});
+
+/// Relative media name of the card for this recipe version.
+#[must_use]
+pub fn cache_name(recipe: &Recipe) -> String {
+    let stamp: String = recipe
+        .updated_at
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect();
+    format!("{CACHE_DIR}/{}-{stamp}.png", recipe.id)
+}
+
+/// The card PNG for `recipe`, from the on-disk cache when this version was
+/// rendered before. A new version replaces older cards of the same recipe.
+/// Cache failures (e.g. a read-only media dir) only cost a re-render.
+///
+/// # Errors
+/// Err if rendering or PNG encoding fails.
+pub async fn load_or_render(media_dir: &Path, recipe: &Recipe) -> anyhow::Result<Vec<u8>> {
+    let name = cache_name(recipe);
+    let path = crate::media_path::resolve(media_dir, &name);
+    if let Ok(path) = &path
+        && let Ok(png) = tokio::fs::read(path).await
+    {
+        return Ok(png);
+    }
+
+    let photo = load_photo(media_dir, recipe).await;
+    let title = recipe.title.clone();
+    let png =
+        tokio::task::spawn_blocking(move || encode_png(&render(&title, photo.as_ref()))).await??;
+
+    match path {
+        Ok(path) => {
+            if let Err(e) = store(&path, recipe.id, &png).await {
+                tracing::warn!(recipe_id = recipe.id, "could not cache share card: {e}");
+            }
+        }
+        Err(e) => tracing::warn!(recipe_id = recipe.id, "share card cache unavailable: {e}"),
+    }
+    Ok(png)
+}
+
+async fn store(path: &Path, recipe_id: i64, png: &[u8]) -> io::Result<()> {
+    let Some(dir) = path.parent() else {
+        return Ok(());
+    };
+    tokio::fs::create_dir_all(dir).await?;
+    let tmp = path.with_extension("png.tmp");
+    tokio::fs::write(&tmp, png).await?;
+    tokio::fs::rename(&tmp, path).await?;
+
+    // Drop cards rendered for earlier versions of this recipe.
+    let prefix = format!("{recipe_id}-");
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file = entry.file_name();
+        let file = file.to_string_lossy();
+        if file.starts_with(&prefix)
+            && Path::new(file.as_ref())
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("png"))
+            && entry.path() != path
+        {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// The recipe's full image, or its thumbnail, if either decodes.
+async fn load_photo(media_dir: &Path, recipe: &Recipe) -> Option<DynamicImage> {
+    for name in [&recipe.image_path_full, &recipe.image_path_small]
+        .into_iter()
+        .flatten()
+    {
+        let Ok(path) = crate::media_path::resolve(media_dir, name) else {
+            continue;
+        };
+        let Ok(bytes) = tokio::fs::read(&path).await else {
+            continue;
+        };
+        if let Ok(img) = image::load_from_memory(&bytes) {
+            return Some(img);
+        }
+    }
+    None
+}
+
+/// # Errors
+/// Err if the encoder fails.
+pub fn encode_png(img: &RgbImage) -> io::Result<Vec<u8>> {
+    let mut out = io::Cursor::new(Vec::new());
+    img.write_to(&mut out, ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    Ok(out.into_inner())
+}
+
+/// Draw the card: `photo` scaled and cropped to cover (or the plain branded
+/// background), a gradient darkening towards the bottom, the brand name and
+/// the wrapped title.
+#[must_use]
+pub fn render(title: &str, photo: Option<&DynamicImage>) -> RgbImage {
+    let mut img = photo.map_or_else(branded_background, |p| {
+        p.resize_to_fill(WIDTH, HEIGHT, FilterType::Triangle)
+            .to_rgb8()
+    });
+    darken_towards_bottom(&mut img);
+
+    draw_text(&mut img, "Blaz", MARGIN, MARGIN, BRAND_PX, BRAND);
+
+    if !can_draw(title) {
+        return img;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let max_width = (WIDTH - 2 * MARGIN) as f32;
+    let lines = wrap_title(title, MAX_TITLE_LINES, |line| {
+        text_width(line, TITLE_PX) <= max_width
+    });
+    let font = FONT.as_scaled(PxScale::from(TITLE_PX));
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let line_height = (font.height() + font.line_gap()).ceil() as u32;
+    #[allow(clippy::cast_possible_truncation)]
+    let block = lines.len() as u32 * line_height;
+    let mut y = HEIGHT - MARGIN - block;
+    for line in &lines {
+        draw_text(&mut img, line, MARGIN, y, TITLE_PX, WHITE);
+        y += line_height;
+    }
+    img
+}
+
+fn branded_background() -> RgbImage {
+    RgbImage::from_fn(WIDTH, HEIGHT, |_, y| {
+        let t = f64::from(y) / f64::from(HEIGHT - 1);
+        Rgb(std::array::from_fn(|i| {
+            lerp(BACKGROUND_TOP[i], BACKGROUND_BOTTOM[i], t)
+        }))
+    })
+}
+
+fn darken_towards_bottom(img: &mut RgbImage) {
+    for (_, y, px) in img.enumerate_pixels_mut() {
+        let t = f64::from(y) / f64::from(HEIGHT - 1);
+        let keep = 1.0 - (0.7 * t).mul_add(t, 0.15);
+        for c in &mut px.0 {
+            *c = lerp(0, *c, keep);
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn lerp(a: u8, b: u8, t: f64) -> u8 {
+    (f64::from(b) - f64::from(a))
+        .mul_add(t, f64::from(a))
+        .round()
+        .clamp(0.0, 255.0) as u8
+}
+
+/// Whether the font has a glyph for every character of `title`.
+fn can_draw(title: &str) -> bool {
+    title
+        .chars()
+        .all(|c| c.is_whitespace() || FONT.glyph_id(c).0 != 0)
+}
+
+/// Break `title` into at most `max_lines` lines for which `fits` holds,
+/// splitting on spaces where possible. Overflow ends the last line in `…`.
+#[must_use]
+pub fn wrap_title(title: &str, max_lines: usize, fits: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in title.split_whitespace() {
+        let joined = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{current} {word}")
+        };
+        if fits(&joined) {
+            current = joined;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        // Words longer than a line are hard-split.
+        let mut rest: Vec<char> = word.chars().collect();
+        while !fits(&rest.iter().collect::<String>()) {
+            let n = (1..rest.len())
+                .take_while(|&n| fits(&rest[..n].iter().collect::<String>()))
+                .last()
+                .unwrap_or(1);
+            lines.push(rest.drain(..n).collect());
+        }
+        current = rest.into_iter().collect();
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            while !last.is_empty() && !fits(&format!("{}…", last.trim_end())) {
+                last.pop();
+            }
+            *last = format!("{}…", last.trim_end());
+        }
+    }
+    lines
+}
+
+/// Glyphs of `text` laid out on one line from `origin` (a baseline point).
+fn layout(text: &str, px: f32, origin: Point) -> Vec<Glyph> {
+    let font = FONT.as_scaled(PxScale::from(px));
+    let mut caret = origin;
+    let mut prev = None;
+    text.chars()
+        .map(|c| {
+            let id = font.glyph_id(c);
+            if let Some(prev) = prev {
+                caret.x += font.kern(prev, id);
+            }
+            let glyph = id.with_scale_and_position(font.scale(), caret);
+            caret.x += font.h_advance(id);
+            prev = Some(id);
+            glyph
+        })
+        .collect()
+}
+
+fn text_width(text: &str, px: f32) -> f32 {
+    let font = FONT.as_scaled(PxScale::from(px));
+    layout(text, px, point(0.0, 0.0))
+        .last()
+        .map_or(0.0, |g| g.position.x + font.h_advance(g.id))
+}
+
+/// Draw `text` with a drop shadow, its top-left corner at (`x`, `top`).
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn draw_text(img: &mut RgbImage, text: &str, x: u32, top: u32, px: f32, color: Rgb<u8>) {
+    let ascent = FONT.as_scaled(PxScale::from(px)).ascent();
+    for (offset, color) in [(SHADOW_OFFSET, SHADOW), (0, color)] {
+        let origin = point((x + offset) as f32, (top + offset) as f32 + ascent);
+        for glyph in layout(text, px, origin) {
+            let Some(outline) = FONT.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let (px, py) = (bounds.min.x + gx as f32, bounds.min.y + gy as f32);
+                if px < 0.0 || py < 0.0 {
+                    return;
+                }
+                let (px, py) = (px as u32, py as u32);
+                if px < img.width() && py < img.height() {
+                    let pixel = img.get_pixel_mut(px, py);
+                    for (c, target) in pixel.0.iter_mut().zip(color.0) {
+                        *c = lerp(*c, target, f64::from(coverage));
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(n: usize) -> impl Fn(&str) -> bool {
+        move |s| s.chars().count() <= n
+    }
+
+    #[test]
+    fn test_wrap_title_breaks_on_words() {
+        assert_eq!(
+            wrap_title("Creamy tomato soup with basil", 3, chars(12)),
+            ["Creamy", "tomato soup", "with basil"]
+        );
+        assert_eq!(wrap_title("  Pho  ", 3, chars(12)), ["Pho"]);
+        assert!(wrap_title("", 3, chars(12)).is_empty());
+    }
+
+    #[test]
+    fn test_wrap_title_ellipsis_and_long_words() {
+        let lines = wrap_title(
+            "Grandma's very best slow cooked beef and vegetable stew for cold nights",
+            2,
+            chars(12),
+        );
+        assert_eq!(lines, ["Grandma's", "very best…"]);
+
+        assert_eq!(
+            wrap_title("Rindfleischrouladen", 3, chars(8)),
+            ["Rindflei", "schroula", "den"]
+        );
+    }
+
+    #[test]
+    fn test_non_latin_titles() {
+        // Greek and Cyrillic are in the font and drawn as text.
+        for title in ["Μουσακάς", "Борщ с пампушками", "Crème brûlée – façon"]
+        {
+            assert!(can_draw(title), "{title}");
+            assert!(text_width(title, TITLE_PX) > 0.0);
+            assert_ne!(render(title, None), render("", None), "{title}");
+        }
+        // CJK is not: the card falls back to no title instead of boxes.
+        assert!(!can_draw("红烧肉"));
+        assert_eq!(render("红烧肉", None), render("", None));
+    }
+
+    #[test]
+    fn test_render_dimensions_with_and_without_photo() {
+        let plain = render("Pancakes", None);
+        assert_eq!(plain.dimensions(), (WIDTH, HEIGHT));
+
+        // A tall red photo is cropped to cover the whole card.
+        let photo = DynamicImage::ImageRgb8(RgbImage::from_pixel(300, 900, Rgb([200, 0, 0])));
+        let card = render("Pancakes", Some(&photo));
+        assert_eq!(card.dimensions(), (WIDTH, HEIGHT));
+        let top_right = card.get_pixel(WIDTH - 1, 0);
+        assert!(top_right[0] > 150 && top_right[1] < 10, "{top_right:?}");
+        // The gradient darkens the bottom.
+        assert!(card.get_pixel(WIDTH - 1, HEIGHT - 1)[0] < 40);
+    }
+}
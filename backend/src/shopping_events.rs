@@ -0,0 +1,67 @@
+//! Live shopping-list changes for `GET /shopping/events`.
+//!
+//! Every write to the list publishes one event on a broadcast channel held in
+//! `AppState`. Events carry a sequence number that grows by one per event, so
+//! a client that sees a gap (it lagged behind, or reconnected) knows to
+//! re-fetch the list.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::models::ShoppingItemView;
+
+/// Events buffered per subscriber before it starts missing some.
+const CAPACITY: usize = 256;
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// An item was added or changed.
+    Upsert { item: ShoppingItemView },
+    /// An item was removed.
+    Delete { id: i64 },
+    /// Many items changed at once; re-fetch the list.
+    Refresh,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ShoppingEvent {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Clone)]
+pub struct ShoppingEvents {
+    tx: broadcast::Sender<ShoppingEvent>,
+    /// Last sequence number handed out. Held while sending so events go out
+    /// in sequence order.
+    seq: Arc<Mutex<u64>>,
+}
+
+impl Default for ShoppingEvents {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(CAPACITY).0,
+            seq: Arc::default(),
+        }
+    }
+}
+
+impl ShoppingEvents {
+    /// Send `change` to every open stream and return its sequence number.
+    pub fn publish(&self, change: Change) -> u64 {
+        let mut seq = self.seq.lock().unwrap_or_else(PoisonError::into_inner);
+        *seq += 1;
+        // No subscribers is fine: nobody is watching the list.
+        let _ = self.tx.send(ShoppingEvent { seq: *seq, change });
+        *seq
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<ShoppingEvent> {
+        self.tx.subscribe()
+    }
+}
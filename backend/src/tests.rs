@@ -21,19 +21,38 @@ mod integration {
 
         let jwt_secret = "integration-test-secret".to_string();
         let jwt_encoding = jsonwebtoken::EncodingKey::from_secret(jwt_secret.as_bytes());
+        let jwt_decoding = jsonwebtoken::DecodingKey::from_secret(jwt_secret.as_bytes());
 
         let config = crate::config::Config {
             verbose: 0,
             quiet: 0,
             bind: "127.0.0.1:0".parse().unwrap(),
             media_dir: tmp.path().to_path_buf(),
+            media_wait_secs: 0,
+            media_gc_on_startup: false,
+            max_image_upload_mb: 15,
+            // Tests fetch from mock sites on 127.0.0.1.
+            fetch_allow_private: true,
             database_path: ":memory:".to_string(),
+            backup_dir: tmp.path().join("backups"),
             log_file: tmp.path().join("test.log"),
-            cors_origin: None,
+            log_format: crate::logging::LogFormat::Text,
+            access_log_exclude: vec!["/healthz".to_string(), "/metrics".to_string()],
+            access_log_sample: Vec::new(),
+            access_log_sample_rate: 100,
+            access_log_not_found_level: crate::logging::AccessLogLevel::Warn,
+            cors_origins: vec!["*".to_string()],
+            public_url: None,
             jwt_secret: Some(jwt_secret),
+            jwt_ttl_hours: 7 * 365 * 24,
+            jwt_leeway_secs: 60,
             password_hash: None,
             llm_api_key: None,
             llm_api_url: "http://localhost/".to_string(),
+            llm_model: None,
+            llm_cassette_mode: crate::llm::CassetteMode::Off,
+            llm_cassette_dir: tmp.path().join("cassettes"),
+            offline: false,
             system_prompt_import: String::new(),
             system_prompt_extract: String::new(),
             system_prompt_structure: String::new(),
@@ -42,16 +61,35 @@ mod integration {
             system_prompt_normalize: String::new(),
             system_prompt_prep_reminders: String::new(),
             ntfy_url: None,
+            shopping_generation_window_days: 7,
+            activity_log_max_rows: 10_000,
+            digest_weekday: None,
+            digest_hour: 18,
+            digest_ntfy_url: None,
+            disable_compression: false,
         };
 
         crate::models::AppState {
             pool,
             jwt_encoding,
+            jwt_decoding,
             config,
+            media: crate::media_health::MediaHealth::default(),
+            http: reqwest::Client::new(),
+            macro_jobs: crate::macro_jobs::MacroJobs::default(),
+            shopping_events: crate::shopping_events::ShoppingEvents::default(),
+            ingredient_suggest: crate::routes::ingredient_suggest::SuggestCache::default(),
+            categories: crate::categories::CategoryCache::default(),
         }
     }
 
     fn make_token() -> String {
+        make_token_expiring_in(3600)
+    }
+
+    /// A token without a `jti`, like those issued before logout existed.
+    /// `secs` may be negative for an already expired token.
+    fn make_token_expiring_in(secs: i64) -> String {
         use jsonwebtoken::{Algorithm, Header, encode};
         #[derive(serde::Serialize)]
         struct Claims {
@@ -63,7 +101,7 @@ mod integration {
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs()
-            + 3600;
+            .saturating_add_signed(secs);
 
         encode(
             &Header::new(Algorithm::HS256),
@@ -127,405 +165,8146 @@ mod integration {
         assert!(body["version"].is_string());
     }
 
-    // ── auth guard ───────────────────────────────────────────────────────────
-
     #[tokio::test]
-    async fn recipes_list_allows_unauthenticated() {
+    #[allow(clippy::literal_string_with_formatting_args)]
+    async fn metrics_count_requests_by_route_template() {
         let tmp = tempfile::tempdir().unwrap();
         let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let id = create_recipe(&app, &token, json!({"title": "Counted"})).await;
+        for _ in 0..2 {
+            let resp = app
+                .clone()
+                .oneshot(auth_get(&format!("/recipes/{id}"), &token))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
 
+        // No token needed.
         let resp = app
-            .oneshot(Request::get("/recipes").body(Body::empty()).unwrap())
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
             .await
             .unwrap();
-
         assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            resp.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/plain; version=0.0.4")
+        );
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        // The registry is shared with tests running alongside, so only the
+        // series are checked, not their exact counts.
+        for series in [
+            r#"blaz_http_requests_total{method="GET",route="/recipes/{id}",status="200"} "#,
+            r#"blaz_http_requests_total{method="POST",route="/recipes",status="200"} "#,
+            r#"blaz_http_request_duration_seconds_bucket{method="GET",route="/recipes/{id}",status="200",le="+Inf"} "#,
+            "# TYPE blaz_llm_calls_total counter",
+            "# TYPE blaz_image_processing_seconds histogram",
+            "blaz_recipes 1\n",
+            "blaz_shopping_items 0\n",
+        ] {
+            assert!(text.contains(series), "missing {series:?} in\n{text}");
+        }
+        assert!(!text.contains(&format!("/recipes/{id}\"")));
     }
 
+    // ── auth guard ───────────────────────────────────────────────────────────
+
     #[tokio::test]
-    async fn recipes_list_allows_bad_token() {
+    async fn recipe_reads_reject_missing_and_malformed_tokens() {
         let tmp = tempfile::tempdir().unwrap();
         let app = crate::app::build_app(make_test_state(&tmp).await);
 
-        let resp = app
+        for (uri, auth) in [
+            ("/recipes", None),
+            ("/recipes/1", Some("Bearer notavalidtoken".to_string())),
+            ("/recipes/equipment", Some(make_token())),
+            ("/recipes/1/scaled?servings=2", Some("Bearer ".to_string())),
+        ] {
+            let mut req = Request::get(uri);
+            if let Some(auth) = auth {
+                req = req.header(header::AUTHORIZATION, auth);
+            }
+            let resp = app
+                .clone()
+                .oneshot(req.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{uri}");
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            assert!(body.is_empty(), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_token_passes_only_within_leeway() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        let token = make_token_expiring_in(1);
+        tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+
+        let status = |state: crate::models::AppState| {
+            let req = auth_get("/settings", &token);
+            async move {
+                crate::app::build_app(state)
+                    .oneshot(req)
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        assert_eq!(status(state.clone()).await, StatusCode::OK);
+        state.config.jwt_leeway_secs = 0;
+        assert_eq!(status(state).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn login_reports_expiry_from_configured_ttl() {
+        use password_hash::{PasswordHasher, SaltString};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        let salt = SaltString::generate(&mut rand::rngs::OsRng);
+        state.config.password_hash = Some(
+            argon2::Argon2::default()
+                .hash_password(b"hunter2", &salt)
+                .unwrap()
+                .to_string(),
+        );
+        state.config.jwt_ttl_hours = 2;
+
+        let resp = crate::app::build_app(state)
             .oneshot(
-                Request::get("/recipes")
-                    .header(header::AUTHORIZATION, "Bearer notavalidtoken")
-                    .body(Body::empty())
+                Request::post("/auth/login")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(json!({"password": "hunter2"}).to_string()))
                     .unwrap(),
             )
             .await
             .unwrap();
-
         assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let expires_at = body["expires_at"].as_u64().unwrap();
+        assert!(expires_at.abs_diff(now + 2 * 3600) <= 5, "{expires_at}");
     }
 
-    // ── recipe CRUD ──────────────────────────────────────────────────────────
+    fn bearer_post(uri: &str, token: &str) -> Request<Body> {
+        Request::post(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap()
+    }
 
     #[tokio::test]
-    async fn recipes_list_empty_on_fresh_db() {
+    async fn refresh_accepts_tokens_only_within_grace_window() {
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
-        let token = make_token();
-        let app = crate::app::build_app(state);
-
-        let resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let grace = i64::try_from(crate::routes::auth::REFRESH_GRACE_SECS).unwrap();
+        let leeway = 60;
 
+        let resp = app
+            .clone()
+            .oneshot(bearer_post(
+                "/auth/refresh",
+                &make_token_expiring_in(-(grace + leeway) + 30),
+            ))
+            .await
+            .unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
         let body = json_body(resp.into_body()).await;
-        assert_eq!(body.as_array().unwrap().len(), 0);
+        let fresh = body["token"].as_str().unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(body["expires_at"].as_u64().unwrap() > now + 3600);
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/settings", fresh))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Expired, but not yet past the grace window: still no access.
+        let recent = make_token_expiring_in(-2 * 3600);
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/settings", &recent))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        for token in [
+            make_token_expiring_in(-(grace + leeway) - 30),
+            "garbage".to_string(),
+        ] {
+            let resp = app
+                .clone()
+                .oneshot(bearer_post("/auth/refresh", &token))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
     }
 
     #[tokio::test]
-    async fn recipe_create_and_get() {
+    async fn change_password_checks_input_and_invalidates_old_tokens() {
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
-        let token = make_token();
+        let mut state = make_test_state(&tmp).await;
+        state.config.password_hash =
+            Some(crate::routes::auth::hash_new_password("hunter22").unwrap());
         let app = crate::app::build_app(state);
-
-        let new_recipe = json!({
-            "title": "Carbonara",
-            "source": "https://example.com/carbonara",
-            "yield": "2 servings",
-            "notes": "Classic Roman pasta",
-            "ingredients": [
-                {"quantity": 200.0, "unit": "g", "name": "spaghetti", "raw": false}
-            ],
-            "instructions": ["Boil pasta", "Mix eggs and cheese", "Combine"]
-        });
+        let old = make_token();
+        let change = |current: &str, new: &str| {
+            auth_json(
+                "POST",
+                "/auth/change-password",
+                &old,
+                &json!({"current_password": current, "new_password": new}),
+            )
+        };
 
         let resp = app
             .clone()
-            .oneshot(auth_json("POST", "/recipes", &token, &new_recipe))
+            .oneshot(change("wrong-password", "correct horse"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = app
+            .clone()
+            .oneshot(change("hunter22", "short"))
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
+        let resp = app
+            .clone()
+            .oneshot(change("hunter22", "correct horse"))
+            .await
+            .unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
-        let created = json_body(resp.into_body()).await;
-        let id = created["id"].as_i64().expect("id in response");
-        assert_eq!(created["title"], "Carbonara");
-        assert_eq!(created["instructions"].as_array().unwrap().len(), 3);
+        let fresh = json_body(resp.into_body()).await["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
 
-        // GET the individual recipe
         let resp = app
-            .oneshot(auth_get(&format!("/recipes/{id}"), &token))
+            .clone()
+            .oneshot(auth_get("/settings", &old))
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/settings", &fresh))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
 
+        let login = |password: &str| {
+            Request::post("/auth/login")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({"password": password}).to_string()))
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(login("hunter22")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = app.oneshot(login("correct horse")).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
-        let fetched = json_body(resp.into_body()).await;
-        assert_eq!(fetched["id"], id);
-        assert_eq!(fetched["title"], "Carbonara");
     }
 
     #[tokio::test]
-    async fn recipe_create_then_list() {
+    async fn logout_revokes_the_token() {
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
-        let token = make_token();
-        let app = crate::app::build_app(state);
+        let app = crate::app::build_app(make_test_state(&tmp).await);
 
-        let recipe = json!({"title": "Risotto", "ingredients": [], "instructions": []});
-        app.clone()
-            .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+        let resp = app
+            .clone()
+            .oneshot(bearer_post("/auth/refresh", &make_token()))
             .await
             .unwrap();
+        let token = json_body(resp.into_body()).await["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let other = make_token();
 
-        let resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
-
-        let list = json_body(resp.into_body()).await;
-        assert_eq!(list.as_array().unwrap().len(), 1);
-        assert_eq!(list[0]["title"], "Risotto");
-    }
+        let resp = app
+            .clone()
+            .oneshot(bearer_post("/auth/logout", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
 
-    #[tokio::test]
-    async fn recipe_update() {
-        let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
-        let token = make_token();
-        let app = crate::app::build_app(state);
+        for req in [
+            auth_get("/settings", &token),
+            bearer_post("/auth/refresh", &token),
+            bearer_post("/auth/logout", &token),
+        ] {
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
 
-        // Create
+        // Other sessions are unaffected; tokens without a jti can't be revoked.
         let resp = app
             .clone()
-            .oneshot(auth_json(
-                "POST",
-                "/recipes",
-                &token,
-                &json!({"title": "Old Title", "ingredients": [], "instructions": []}),
-            ))
+            .oneshot(auth_get("/settings", &other))
             .await
             .unwrap();
-        let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .oneshot(bearer_post("/auth/logout", &other))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
 
-        // Update title
+    // ── api tokens ───────────────────────────────────────────────────────────
+
+    async fn create_api_token(app: &axum::Router, scopes: Value) -> (i64, String) {
         let resp = app
             .clone()
             .oneshot(auth_json(
-                "PATCH",
-                &format!("/recipes/{id}"),
-                &token,
-                &json!({"title": "New Title"}),
+                "POST",
+                "/auth/tokens",
+                &make_token(),
+                &json!({"name": "home assistant", "scopes": scopes}),
             ))
             .await
             .unwrap();
-
         assert_eq!(resp.status(), StatusCode::OK);
-        let updated = json_body(resp.into_body()).await;
-        assert_eq!(updated["title"], "New Title");
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["scopes"], scopes);
+        (
+            body["id"].as_i64().unwrap(),
+            body["token"].as_str().unwrap().to_string(),
+        )
     }
 
     #[tokio::test]
-    async fn recipe_delete() {
+    async fn shopping_events_stream_changes_with_sequence_ids() {
+        use futures_util::StreamExt;
+
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
+        let app = crate::app::build_app(make_test_state(&tmp).await);
         let token = make_token();
-        let app = crate::app::build_app(state);
 
-        // Create
         let resp = app
             .clone()
             .oneshot(auth_json(
                 "POST",
-                "/recipes",
+                "/shopping",
                 &token,
-                &json!({"title": "Delete Me", "ingredients": [], "instructions": []}),
+                &json!({"text": "2 apples"}),
             ))
             .await
             .unwrap();
         let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
 
-        // Delete
         let resp = app
             .clone()
             .oneshot(
-                Request::builder()
-                    .method("DELETE")
-                    .uri(format!("/recipes/{id}"))
-                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                Request::get("/shopping/events")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
 
-        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
         let resp = app
-            .oneshot(auth_get(&format!("/recipes/{id}"), &token))
+            .clone()
+            .oneshot(auth_get("/shopping/events", &token))
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let mut stream = resp.into_body().into_data_stream();
+        let mut next_event = async || -> (String, Value) {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+                .await
+                .expect("event in time")
+                .unwrap()
+                .unwrap();
+            let text = String::from_utf8(frame.to_vec()).unwrap();
+            let field = |name: &str| {
+                text.lines()
+                    .find_map(|l| l.strip_prefix(name))
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            assert_eq!(field("event: "), "shopping", "{text}");
+            (
+                field("id: "),
+                serde_json::from_str(&field("data: ")).unwrap(),
+            )
+        };
 
-        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{id}"),
+                &token,
+                &json!({"done": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        // The add before subscribing was event 1.
+        let (seq, data) = next_event().await;
+        assert_eq!(seq, "2");
+        assert_eq!(data["seq"], 2);
+        assert_eq!(data["kind"], "upsert");
+        assert_eq!(data["item"]["id"], id);
+        assert_eq!(data["item"]["done"], 1);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "DELETE",
+                &format!("/shopping/{id}"),
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let (seq, data) = next_event().await;
+        assert_eq!(seq, "3");
+        assert_eq!(data, json!({"seq": 3, "kind": "delete", "id": id}));
     }
 
     #[tokio::test]
-    async fn recipe_get_nonexistent_returns_404() {
+    async fn shopping_events_report_the_row_a_merging_patch_removes() {
+        use futures_util::StreamExt;
+
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
+        let app = crate::app::build_app(make_test_state(&tmp).await);
         let token = make_token();
-        let app = crate::app::build_app(state);
+        let bread = add_shopping_full(&app, &token, "bread").await["id"].clone();
+        let pears = add_shopping_full(&app, &token, "pears").await["id"].clone();
 
         let resp = app
-            .oneshot(auth_get("/recipes/999999", &token))
+            .clone()
+            .oneshot(auth_get("/shopping/events", &token))
             .await
             .unwrap();
+        let mut stream = resp.into_body().into_data_stream();
 
-        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
-    }
+        // Renaming pears to bread merges it into the bread row.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{pears}"),
+                &token,
+                &json!({"text": "bread"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["id"], bread);
 
-    // ── recipesage import ────────────────────────────────────────────────────
+        let mut events = Vec::new();
+        while events.len() < 2 {
+            let frame = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+                .await
+                .expect("event in time")
+                .unwrap()
+                .unwrap();
+            let text = String::from_utf8(frame.to_vec()).unwrap();
+            let data = text.lines().find_map(|l| l.strip_prefix("data: "));
+            events.extend(data.map(|d| serde_json::from_str::<Value>(d).unwrap()));
+        }
+        assert_eq!(events[0], json!({"seq": 3, "kind": "delete", "id": pears}));
+        assert_eq!(events[1]["seq"], 4);
+        assert_eq!(events[1]["kind"], "upsert");
+        assert_eq!(events[1]["item"]["id"], bread);
+    }
 
     #[tokio::test]
-    async fn recipesage_import_creates_recipes() {
+    async fn api_token_shopping_only_scope_is_enforced() {
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
-        let token = make_token();
-        let app = crate::app::build_app(state);
-
-        let payload = json!([
-            {
-                "name": "Imported Soup",
-                "url": "https://example.com/soup",
-                "recipeIngredient": ["2 carrots", "1 L water"],
-                "recipeInstructions": ["Boil everything"],
-                "recipeYield": "4"
-            },
-            {
-                "name": "Imported Cake",
-                "recipeIngredient": ["200 g flour"],
-                "recipeInstructions": [{"@type": "HowToStep", "text": "Mix and bake"}]
-            }
-        ]);
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let (_, token) = create_api_token(&app, json!(["shopping-only"])).await;
+        assert!(token.starts_with("blz_"));
 
         let resp = app
             .clone()
             .oneshot(auth_json(
                 "POST",
-                "/recipes/import/recipesage",
+                "/shopping",
                 &token,
-                &payload,
+                &json!({"text": "2 apples"}),
             ))
             .await
             .unwrap();
-
         assert_eq!(resp.status(), StatusCode::OK);
-        let body = json_body(resp.into_body()).await;
-        assert_eq!(body["imported_count"], 2);
-        assert_eq!(body["failed"].as_array().unwrap().len(), 0);
 
-        // Verify recipes actually exist
-        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
-        let recipes = json_body(list_resp.into_body()).await;
-        assert_eq!(recipes.as_array().unwrap().len(), 2);
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Nope"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // API tokens cannot manage tokens.
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/auth/tokens", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // The listing never exposes the secret, and records use in the background.
+        let mut listed = Value::Null;
+        for _ in 0..50 {
+            let resp = app
+                .clone()
+                .oneshot(auth_get("/auth/tokens", &make_token()))
+                .await
+                .unwrap();
+            listed = json_body(resp.into_body()).await;
+            if !listed[0]["last_used_at"].is_null() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(listed[0]["name"], "home assistant");
+        assert!(listed[0].get("token").is_none());
+        assert!(listed[0]["last_used_at"].is_string());
     }
 
     #[tokio::test]
-    async fn recipesage_import_skips_duplicate_by_source() {
+    async fn api_token_read_scope_rejects_writes() {
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
-        let token = make_token();
-        let app = crate::app::build_app(state);
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let (_, token) = create_api_token(&app, json!(["read"])).await;
 
-        let payload = json!([{
-            "name": "Pasta",
-            "url": "https://example.com/pasta",
-            "recipeIngredient": [],
-            "recipeInstructions": []
-        }]);
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
 
-        // First import
-        app.clone()
+        let resp = app
+            .clone()
             .oneshot(auth_json(
                 "POST",
-                "/recipes/import/recipesage",
+                "/shopping",
                 &token,
-                &payload,
+                &json!({"text": "milk"}),
             ))
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // A backup holds the password and token hashes.
+        for uri in ["/admin/backup/latest", "/admin/backups", "/admin/db/check"] {
+            let resp = app.clone().oneshot(auth_get(uri, &token)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn api_token_revocation_is_immediate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let (id, token) = create_api_token(&app, json!(["write"])).await;
 
-        // Second import — should be skipped
         let resp = app
             .clone()
-            .oneshot(auth_json(
-                "POST",
-                "/recipes/import/recipesage",
-                &token,
-                &payload,
-            ))
+            .oneshot(auth_get("/shopping", &token))
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
 
-        let body = json_body(resp.into_body()).await;
-        // The handler returns Ok(()) for both created and skipped, so failed must be empty
-        assert_eq!(body["failed"].as_array().unwrap().len(), 0);
+        let revoke = || {
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/auth/tokens/{id}"))
+                .header(header::AUTHORIZATION, format!("Bearer {}", make_token()))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(revoke()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = app.clone().oneshot(revoke()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 
-        // DB should still have only 1 recipe
-        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
-        let recipes = json_body(list_resp.into_body()).await;
-        assert_eq!(recipes.as_array().unwrap().len(), 1);
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = app
+            .oneshot(auth_get("/shopping", "blz_not-a-real-token"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
 
     #[tokio::test]
-    async fn recipesage_import_skips_duplicate_by_title_when_no_source() {
+    async fn api_keys_paths_manage_the_same_tokens() {
         let tmp = tempfile::tempdir().unwrap();
-        let state = make_test_state(&tmp).await;
-        let token = make_token();
-        let app = crate::app::build_app(state);
-
-        let payload = json!([{
-            "name": "No Source Recipe",
-            "recipeIngredient": [],
-            "recipeInstructions": []
-        }]);
+        let app = crate::app::build_app(make_test_state(&tmp).await);
 
-        app.clone()
+        let resp = app
+            .clone()
             .oneshot(auth_json(
                 "POST",
-                "/recipes/import/recipesage",
-                &token,
-                &payload,
+                "/auth/api-keys",
+                &make_token(),
+                &json!({"name": "script", "scopes": ["read"]}),
             ))
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let created = json_body(resp.into_body()).await;
+        let id = created["id"].as_i64().unwrap();
+        let key = created["token"].as_str().unwrap().to_string();
 
-        app.clone()
-            .oneshot(auth_json(
-                "POST",
-                "/recipes/import/recipesage",
-                &token,
-                &payload,
-            ))
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes", &key))
             .await
             .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
 
-        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
-        let recipes = json_body(list_resp.into_body()).await;
-        assert_eq!(recipes.as_array().unwrap().len(), 1);
+        for uri in ["/auth/api-keys", "/auth/tokens"] {
+            let resp = app
+                .clone()
+                .oneshot(auth_get(uri, &make_token()))
+                .await
+                .unwrap();
+            let listed = json_body(resp.into_body()).await;
+            assert_eq!(listed[0]["id"], id, "{uri}");
+            assert!(!listed.to_string().contains(&key), "{uri}");
+        }
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/auth/api-keys/{id}"))
+                    .header(header::AUTHORIZATION, format!("Bearer {}", make_token()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = app.oneshot(auth_get("/recipes", &key)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
 
     #[tokio::test]
-    async fn recipesage_import_invalid_json_returns_400() {
+    async fn api_token_list_masks_secret_and_throttles_last_used() {
         let tmp = tempfile::tempdir().unwrap();
         let state = make_test_state(&tmp).await;
-        let token = make_token();
+        let pool = state.pool.clone();
         let app = crate::app::build_app(state);
+        let (id, token) = create_api_token(&app, json!(["read"])).await;
 
         let resp = app
-            .oneshot(
-                Request::builder()
-                    .method("POST")
-                    .uri("/recipes/import/recipesage")
-                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
-                    .header(header::CONTENT_TYPE, "application/json")
-                    .body(Body::from("not json at all"))
-                    .unwrap(),
+            .clone()
+            .oneshot(auth_get("/auth/tokens", &make_token()))
+            .await
+            .unwrap();
+        let listed = json_body(resp.into_body()).await;
+        assert_eq!(listed[0]["prefix"], token[..8]);
+        assert!(listed[0].get("token").is_none());
+        assert!(!listed.to_string().contains(&token));
+
+        let last_used = || async {
+            sqlx::query_scalar::<_, Option<String>>(
+                "SELECT last_used_at FROM api_tokens WHERE id = ?",
             )
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+        let set_last_used = |stamp: &'static str| {
+            sqlx::query("UPDATE api_tokens SET last_used_at = datetime('now', ?) WHERE id = ?")
+                .bind(stamp)
+                .bind(id)
+                .execute(&pool)
+        };
+        let use_token = || async {
+            let resp = app
+                .clone()
+                .oneshot(auth_get("/recipes", &token))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            // The timestamp is written by a background task.
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        };
+
+        set_last_used("-30 seconds").await.unwrap();
+        let recent = last_used().await.unwrap();
+        use_token().await;
+        assert_eq!(last_used().await.unwrap(), recent);
+
+        set_last_used("-2 minutes").await.unwrap();
+        use_token().await;
+        let now: String = sqlx::query_scalar("SELECT datetime('now', '-5 seconds')")
+            .fetch_one(&pool)
             .await
             .unwrap();
+        assert!(last_used().await.unwrap() >= now);
+    }
 
-        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    #[tokio::test]
+    async fn api_token_create_validates_input() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+
+        for (body, status) in [
+            (
+                json!({"name": " ", "scopes": ["read"]}),
+                StatusCode::BAD_REQUEST,
+            ),
+            (json!({"name": "x", "scopes": []}), StatusCode::BAD_REQUEST),
+            (
+                json!({"name": "x", "scopes": ["admin"]}),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+        ] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json("POST", "/auth/tokens", &make_token(), &body))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), status, "{body}");
+        }
     }
 
-    // ── shopping list ────────────────────────────────────────────────────────
+    // ── recipe CRUD ──────────────────────────────────────────────────────────
 
     #[tokio::test]
-    async fn shopping_list_starts_empty() {
+    async fn recipes_list_empty_on_fresh_db() {
         let tmp = tempfile::tempdir().unwrap();
         let state = make_test_state(&tmp).await;
         let token = make_token();
         let app = crate::app::build_app(state);
 
-        let resp = app.oneshot(auth_get("/shopping", &token)).await.unwrap();
+        let resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
 
         assert_eq!(resp.status(), StatusCode::OK);
-        assert_eq!(
-            json_body(resp.into_body()).await.as_array().unwrap().len(),
-            0
-        );
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body.as_array().unwrap().len(), 0);
     }
 
     #[tokio::test]
-    async fn shopping_add_and_list_item() {
+    async fn recipe_create_and_get() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let new_recipe = json!({
+            "title": "Carbonara",
+            "source": "https://example.com/carbonara",
+            "yield": "2 servings",
+            "notes": "Classic Roman pasta",
+            "ingredients": [
+                {"quantity": 200.0, "unit": "g", "name": "spaghetti", "raw": false}
+            ],
+            "instructions": ["Boil pasta", "Mix eggs and cheese", "Combine"]
+        });
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", &token, &new_recipe))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let created = json_body(resp.into_body()).await;
+        let id = created["id"].as_i64().expect("id in response");
+        assert_eq!(created["title"], "Carbonara");
+        assert_eq!(created["instructions"].as_array().unwrap().len(), 3);
+
+        // GET the individual recipe
+        let resp = app
+            .oneshot(auth_get(&format!("/recipes/{id}"), &token))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let fetched = json_body(resp.into_body()).await;
+        assert_eq!(fetched["id"], id);
+        assert_eq!(fetched["title"], "Carbonara");
+    }
+
+    #[tokio::test]
+    async fn recipe_create_then_list() {
         let tmp = tempfile::tempdir().unwrap();
         let state = make_test_state(&tmp).await;
         let token = make_token();
         let app = crate::app::build_app(state);
 
+        let recipe = json!({"title": "Risotto", "ingredients": [], "instructions": []});
         app.clone()
+            .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+            .await
+            .unwrap();
+
+        let resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+
+        let list = json_body(resp.into_body()).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+        assert_eq!(list[0]["title"], "Risotto");
+    }
+
+    #[tokio::test]
+    async fn recipe_update() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        // Create
+        let resp = app
+            .clone()
             .oneshot(auth_json(
                 "POST",
-                "/shopping",
+                "/recipes",
                 &token,
-                &json!({"text": "2 kg potatoes"}),
+                &json!({"title": "Old Title", "ingredients": [], "instructions": []}),
             ))
             .await
             .unwrap();
+        let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
 
-        let resp = app.oneshot(auth_get("/shopping", &token)).await.unwrap();
+        // Update title
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"title": "New Title"}),
+            ))
+            .await
+            .unwrap();
 
-        let items = json_body(resp.into_body()).await;
-        assert_eq!(items.as_array().unwrap().len(), 1);
-        assert!(items[0]["text"].as_str().unwrap().contains("potatoes"));
+        assert_eq!(resp.status(), StatusCode::OK);
+        let updated = json_body(resp.into_body()).await;
+        assert_eq!(updated["title"], "New Title");
+    }
+
+    #[tokio::test]
+    async fn recipe_update_accepts_ingredient_lines_and_objects() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let id = create_recipe(
+            &app,
+            &token,
+            json!({"title": "Soup", "ingredients": [], "instructions": []}),
+        )
+        .await;
+        let patch = |ingredients: Value| {
+            app.clone().oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"ingredients": ingredients}),
+            ))
+        };
+
+        let resp = patch(json!([
+            {"section": "Base"},
+            {"quantity": 2, "unit": null, "name": "leeks", "prep": "sliced"},
+        ]))
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let ings = json_body(resp.into_body()).await["ingredients"].clone();
+        assert_eq!(ings[0]["section"], "Base");
+        assert_eq!(ings[1]["name"], "leeks");
+        assert_eq!(ings[1]["prep"], "sliced");
+
+        // Lines are parsed; both shapes can be mixed.
+        let resp = patch(json!([
+            "500 ml stock",
+            "salt, to taste",
+            {"quantity": 1, "unit": "tbsp", "name": "butter"},
+        ]))
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let ings = json_body(resp.into_body()).await["ingredients"].clone();
+        assert_eq!(ings[0]["quantity"], 500.0);
+        assert_eq!(ings[0]["unit"], "ml");
+        assert_eq!(ings[0]["name"], "stock");
+        assert_eq!(ings[1]["name"], "salt");
+        assert_eq!(ings[2]["unit"], "tbsp");
+        assert_eq!(ings[2]["name"], "butter");
+
+        let resp = patch(json!(["  "])).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = patch(json!([42])).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn recipe_yield_and_servings_stay_in_sync() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Chili", "yield": "Serves 4", "ingredients": [], "instructions": []}),
+            ))
+            .await
+            .unwrap();
+        let created = json_body(resp.into_body()).await;
+        let id = created["id"].as_i64().unwrap();
+        assert_eq!(created["yield"], "Serves 4");
+        assert_eq!(created["servings"], 4.0);
+
+        // PATCH yield -> servings re-derived
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"yield": "6-8 portions"}),
+            ))
+            .await
+            .unwrap();
+        let updated = json_body(resp.into_body()).await;
+        assert_eq!(updated["yield"], "6-8 portions");
+        assert_eq!(updated["servings"], 7.0);
+
+        // PATCH servings -> canonical yield regenerated
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"servings": 2}),
+            ))
+            .await
+            .unwrap();
+        let updated = json_body(resp.into_body()).await;
+        assert_eq!(updated["yield"], "2 servings");
+        assert_eq!(updated["servings"], 2.0);
+
+        // Non-servings yield clears servings
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"yield": "1 loaf"}),
+            ))
+            .await
+            .unwrap();
+        let updated = json_body(resp.into_body()).await;
+        assert_eq!(updated["yield"], "1 loaf");
+        assert!(updated["servings"].is_null());
+
+        // Invalid servings rejected
+        let resp = app
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"servings": 0}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn recipe_delete() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        // Create
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Delete Me", "ingredients": [], "instructions": []}),
+            ))
+            .await
+            .unwrap();
+        let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+
+        // Delete
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/recipes/{id}"))
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = app
+            .oneshot(auth_get(&format!("/recipes/{id}"), &token))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn recipe_permanent_delete_removes_images_but_never_escapes_media_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        let media = tmp.path().join("media");
+        std::fs::create_dir_all(media.join("recipes/1")).unwrap();
+        state.config.media_dir = media.clone();
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        std::fs::write(media.join("recipes/1/small.webp"), b"small").unwrap();
+        let outside = tmp.path().join("outside.webp");
+        std::fs::write(&outside, b"not ours").unwrap();
+
+        sqlx::query(
+            r"INSERT INTO recipes (id, title, ingredients, instructions, image_path_small, image_path_full, deleted_at)
+              VALUES (1, 'Trashed', '[]', '[]', 'recipes/1/small.webp', '../outside.webp', CURRENT_TIMESTAMP)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/recipes/1/permanent")
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(!media.join("recipes/1/small.webp").exists());
+        assert!(outside.exists(), "crafted path must not be deleted");
+    }
+
+    #[tokio::test]
+    async fn recipe_get_nonexistent_returns_404() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let resp = app
+            .oneshot(auth_get("/recipes/999999", &token))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn recipe_create_twice_is_deduplicated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let recipe = json!({
+            "title": "Pancakes",
+            "notes": "Fluffy",
+            "ingredients": [{"quantity": 250.0, "unit": "g", "name": "flour"}],
+            "instructions": ["Mix", "Fry"]
+        });
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let first = json_body(resp.into_body()).await;
+        assert!(first.get("deduplicated").is_none());
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let second = json_body(resp.into_body()).await;
+        assert_eq!(second["id"], first["id"]);
+        assert_eq!(second["deduplicated"], true);
+
+        let resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        let list = json_body(resp.into_body()).await;
+        assert_eq!(list.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn recipe_concurrent_identical_creates_insert_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        // On a file, so concurrent writers wait for each other.
+        let mut state = make_test_state(&tmp).await;
+        state.pool = crate::db::make_pool(tmp.path().join("live.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+        let recipe = json!({"title": "Waffles", "ingredients": [], "instructions": ["Bake"]});
+
+        let submits = (0..8).map(|_| {
+            let app = app.clone();
+            let req = auth_json("POST", "/recipes", &token, &recipe);
+            tokio::spawn(async move { app.oneshot(req).await.unwrap() })
+        });
+        let mut ids = Vec::new();
+        let mut deduplicated = 0;
+        for submit in futures_util::future::join_all(submits).await {
+            let resp = submit.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body = json_body(resp.into_body()).await;
+            ids.push(body["id"].as_i64().unwrap());
+            deduplicated += usize::from(body["deduplicated"] == true);
+        }
+
+        ids.dedup();
+        assert_eq!(ids.len(), 1, "{ids:?}");
+        assert_eq!(deduplicated, 7);
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM recipes WHERE title = 'Waffles'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[tokio::test]
+    async fn recipe_create_with_changed_notes_is_not_deduplicated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let mut recipe = json!({
+            "title": "Pancakes",
+            "notes": "Fluffy",
+            "ingredients": [],
+            "instructions": ["Mix", "Fry"]
+        });
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+            .await
+            .unwrap();
+        let first_id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+
+        recipe["notes"] = json!("Extra fluffy");
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+            .await
+            .unwrap();
+        let second = json_body(resp.into_body()).await;
+        assert_ne!(second["id"].as_i64().unwrap(), first_id);
+        assert!(second.get("deduplicated").is_none());
+    }
+
+    #[tokio::test]
+    async fn recipe_create_allow_duplicate_inserts_new_row() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let recipe = json!({"title": "Soup", "ingredients": [], "instructions": []});
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+            .await
+            .unwrap();
+        let first_id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+
+        let forced = json!({
+            "title": "Soup",
+            "ingredients": [],
+            "instructions": [],
+            "allow_duplicate": true
+        });
+        let resp = app
+            .oneshot(auth_json("POST", "/recipes", &token, &forced))
+            .await
+            .unwrap();
+        let second_id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+        assert_ne!(second_id, first_id);
+    }
+
+    async fn create_with_equipment(
+        app: &axum::Router,
+        token: &str,
+        title: &str,
+        equipment: &[&str],
+    ) -> i64 {
+        let body = json!({"title": title, "equipment": equipment});
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", token, &body))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body()).await["id"].as_i64().unwrap()
+    }
+
+    async fn recipe_titles(app: &axum::Router, uri: &str, token: &str) -> Vec<String> {
+        let resp = app.clone().oneshot(auth_get(uri, token)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body())
+            .await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["title"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn recipe_equipment_is_normalized_on_create_and_update() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let id =
+            create_with_equipment(&app, &token, "Chili", &["Crockpot", " Slow  Cooker", ""]).await;
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{id}"), &token))
+            .await
+            .unwrap();
+        assert_eq!(
+            json_body(resp.into_body()).await["equipment"],
+            json!(["slow cooker"])
+        );
+
+        let patch = json!({"equipment": ["Dutch Oven", "dutch oven", "Sheet pan"]});
+        let resp = app
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &patch,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await["equipment"],
+            json!(["dutch oven", "baking sheet"])
+        );
+    }
+
+    #[tokio::test]
+    async fn recipes_filter_by_equipment_requires_all_values() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        create_with_equipment(&app, &token, "Chili", &["slow cooker"]).await;
+        create_with_equipment(&app, &token, "Pulled pork", &["slow cooker", "oven"]).await;
+        create_with_equipment(&app, &token, "Bread", &["oven"]).await;
+        create_with_equipment(&app, &token, "Salad", &[]).await;
+
+        assert_eq!(
+            recipe_titles(&app, "/recipes?equipment=slow+cooker", &token).await,
+            ["Chili", "Pulled pork"]
+        );
+        // Synonyms and case in the query are normalized like stored values.
+        assert_eq!(
+            recipe_titles(&app, "/recipes?equipment=Crockpot,OVEN", &token).await,
+            ["Pulled pork"]
+        );
+        assert!(
+            recipe_titles(&app, "/recipes?equipment=wok", &token)
+                .await
+                .is_empty()
+        );
+        assert_eq!(recipe_titles(&app, "/recipes", &token).await.len(), 4);
+
+        let resp = app
+            .oneshot(auth_get("/recipes/equipment", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await,
+            json!(["oven", "slow cooker"])
+        );
+    }
+
+    #[tokio::test]
+    async fn recipes_list_sorts_and_paginates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        for title in ["Éclair", "apple", "Banana", "banana bread", "Zucchini"] {
+            create_with_equipment(&app, &token, title, &[]).await;
+        }
+
+        assert_eq!(
+            recipe_titles(&app, "/recipes?sort=title", &token).await,
+            ["apple", "Banana", "banana bread", "Zucchini", "Éclair"]
+        );
+        assert_eq!(
+            recipe_titles(&app, "/recipes", &token).await,
+            ["Éclair", "apple", "Banana", "banana bread", "Zucchini"]
+        );
+
+        // Same timestamps everywhere: the id tiebreaker keeps pages disjoint.
+        let mut paged = Vec::new();
+        for offset in (0..5).step_by(2) {
+            paged.extend(
+                recipe_titles(
+                    &app,
+                    &format!("/recipes?sort=created_at&limit=2&offset={offset}"),
+                    &token,
+                )
+                .await,
+            );
+        }
+        assert_eq!(
+            paged,
+            ["Zucchini", "banana bread", "Banana", "apple", "Éclair"]
+        );
+
+        sqlx::query("UPDATE recipes SET updated_at = '2030-01-01 00:00:00' WHERE title = 'apple'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            recipe_titles(&app, "/recipes?sort=updated_at&limit=1", &token).await,
+            ["apple"]
+        );
+
+        // Never planned first, then the longest since it was last planned.
+        for (title, day) in [
+            ("Banana", "2020-01-01"),
+            ("apple", "2020-06-01"),
+            ("Éclair", "2019-01-01"),
+            ("Éclair", "2021-01-01"),
+            ("Zucchini", "2099-01-01"),
+        ] {
+            sqlx::query(
+                "INSERT INTO meal_plan (day, recipe_id, title) SELECT ?, id, title FROM recipes WHERE title = ?",
+            )
+            .bind(day)
+            .bind(title)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        assert_eq!(
+            recipe_titles(&app, "/recipes?sort=last_cooked", &token).await,
+            ["banana bread", "Zucchini", "Banana", "apple", "Éclair"]
+        );
+
+        let resp = app
+            .oneshot(auth_get(
+                "/recipes?sort=title;DROP%20TABLE%20recipes",
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["code"], "invalid_sort");
+        assert_eq!(body["allowed"][1], "title");
+    }
+
+    #[tokio::test]
+    async fn recipes_list_summary_view_and_updated_since() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let soup = create_recipe(
+            &app,
+            &token,
+            json!({
+                "title": "Soup",
+                "ingredients": [{"name": "leek"}],
+                "instructions": ["Simmer"],
+                "equipment": ["pot"]
+            }),
+        )
+        .await;
+        create_recipe(&app, &token, json!({"title": "Stew"})).await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes?view=summary", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let cards = json_body(resp.into_body()).await;
+        let cards = cards.as_array().unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0]["id"], soup);
+        assert_eq!(cards[0]["title"], "Soup");
+        assert_eq!(cards[0]["equipment"], json!(["pot"]));
+        for card in cards {
+            let card = card.as_object().unwrap();
+            assert!(card.contains_key("updated_at"));
+            assert!(!card.contains_key("ingredients"));
+            assert!(!card.contains_key("instructions"));
+            assert!(!card.contains_key("notes"));
+        }
+        // The default shape is unchanged.
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes", &token))
+            .await
+            .unwrap();
+        assert_eq!(
+            json_body(resp.into_body()).await[0]["ingredients"][0]["name"],
+            "leek"
+        );
+
+        sqlx::query("UPDATE recipes SET updated_at = '2020-01-01 00:00:00'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{soup}"),
+                &token,
+                &json!({"notes": "Better the next day"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        assert_eq!(
+            recipe_titles(&app, "/recipes?updated_since=2021-01-01%2000:00:00", &token).await,
+            ["Soup"]
+        );
+        assert_eq!(
+            recipe_titles(
+                &app,
+                "/recipes?view=summary&updated_since=2021-01-01T01:00:00%2B01:00",
+                &token
+            )
+            .await,
+            ["Soup"]
+        );
+        assert_eq!(
+            recipe_titles(&app, "/recipes?updated_since=2020-01-01%2000:00:00", &token).await,
+            ["Soup", "Stew"]
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes?updated_since=yesterday", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "invalid_updated_since"
+        );
+        let resp = app
+            .oneshot(auth_get("/recipes?view=tiny", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn ingredient_suggest_ranks_used_names() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        create_recipe(
+            &app,
+            &token,
+            json!({"title": "Bread", "ingredients": [{"name": "Flour", "unit": "g"}]}),
+        )
+        .await;
+        create_recipe(
+            &app,
+            &token,
+            json!({"title": "Gratin", "ingredients": [{"name": "cauliflower"}]}),
+        )
+        .await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/ingredients/suggest?q=FLO", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await,
+            json!([
+                {"name": "flour", "count": 1, "unit": "g"},
+                {"name": "cauliflower", "count": 1, "unit": null},
+            ])
+        );
+
+        let resp = app
+            .oneshot(auth_get("/ingredients/suggest?q=f", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(resp.into_body()).await["code"], "query_too_short");
+    }
+
+    async fn create_recipe(app: &axum::Router, token: &str, recipe: Value) -> i64 {
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes", token, &recipe))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body()).await["id"].as_i64().unwrap()
+    }
+
+    #[tokio::test]
+    async fn cooked_counter_and_favorite_filter() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let soup = create_recipe(&app, &token, json!({"title": "Soup"})).await;
+        let stew = create_recipe(&app, &token, json!({"title": "Stew"})).await;
+
+        for expected in [1, 2] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json(
+                    "POST",
+                    &format!("/recipes/{soup}/cooked"),
+                    &token,
+                    &json!({}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body = json_body(resp.into_body()).await;
+            assert_eq!(body["times_cooked"], expected);
+            assert!(body["last_cooked_at"].is_string());
+        }
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes/999/cooked", &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{stew}"),
+                &token,
+                &json!({"is_favorite": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["is_favorite"], true);
+        assert_eq!(
+            recipe_titles(&app, "/recipes?favorite=true", &token).await,
+            ["Stew"]
+        );
+        assert_eq!(recipe_titles(&app, "/recipes", &token).await.len(), 2);
+
+        // Never cooked first.
+        assert_eq!(
+            recipe_titles(&app, "/recipes?sort=last_cooked", &token).await,
+            ["Stew", "Soup"]
+        );
+    }
+
+    #[tokio::test]
+    async fn cooking_log_validates_and_feeds_recipe_rating() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let soup = create_recipe(&app, &token, json!({"title": "Soup"})).await;
+        create_recipe(&app, &token, json!({"title": "Stew"})).await;
+        let logs = format!("/recipes/{soup}/logs");
+        let add = |body: Value| auth_json("POST", &logs, &token, &body);
+
+        for body in [
+            json!({"date": "2024-03-02", "rating": 0}),
+            json!({"date": "2024-03-02", "rating": 6}),
+            json!({"date": "2999-01-01"}),
+            json!({"date": "02/03/2024"}),
+        ] {
+            let resp = app.clone().oneshot(add(body.clone())).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{body}");
+        }
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes/999/logs", &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let mut ids = Vec::new();
+        for body in [
+            json!({"date": "2024-03-02", "rating": 4, "note": "too salty"}),
+            json!({"date": "2024-04-10", "rating": 5}),
+            json!({"note": "no rating today"}),
+        ] {
+            let resp = app.clone().oneshot(add(body)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            ids.push(json_body(resp.into_body()).await["id"].as_i64().unwrap());
+        }
+
+        let resp = app.clone().oneshot(auth_get(&logs, &token)).await.unwrap();
+        let listed = json_body(resp.into_body()).await;
+        assert_eq!(listed.as_array().unwrap().len(), 3);
+        assert_eq!(listed[2]["note"], "too salty");
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{soup}"), &token))
+            .await
+            .unwrap();
+        let recipe = json_body(resp.into_body()).await;
+        assert_eq!(recipe["avg_rating"], 4.5);
+        assert_eq!(recipe["log_count"], 3);
+        assert_eq!(
+            recipe_titles(&app, "/recipes?min_rating=4", &token).await,
+            ["Soup"]
+        );
+
+        let delete = |id: i64| {
+            Request::delete(format!("{logs}/{id}"))
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(delete(ids[1])).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let resp = app.clone().oneshot(delete(ids[1])).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert!(
+            recipe_titles(&app, "/recipes?min_rating=4.5", &token)
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn suggest_ranks_recipes_by_ingredients_on_hand() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let ing = |names: &[&str]| -> Value { names.iter().map(|n| json!({"name": n})).collect() };
+        for (title, names) in [
+            (
+                "Lemon Chicken",
+                ing(&["chicken breasts", "lemons", "salt", "garlic"]),
+            ),
+            (
+                "Fried Rice",
+                ing(&["rice", "eggs", "soy sauce", "scallions"]),
+            ),
+            ("Beef Stew", ing(&["beef", "carrots"])),
+        ] {
+            create_recipe(&app, &token, json!({"title": title, "ingredients": names})).await;
+        }
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/suggest",
+                &token,
+                &json!({"ingredients": ["Chicken", "lemon", "rice"]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        let titles: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles, ["Lemon Chicken", "Fried Rice"]);
+        assert_eq!(body[0]["missing_ingredients"], json!(["garlic"]));
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/suggest",
+                &token,
+                &json!({"ingredients": [" "]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn completing_a_meal_plan_day_marks_recipes_cooked_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let soup = create_recipe(&app, &token, json!({"title": "Soup"})).await;
+        for meal_type in ["lunch", "dinner"] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json(
+                    "POST",
+                    "/meal-plan",
+                    &token,
+                    &json!({"day": "2024-03-02", "recipe_id": soup, "meal_type": meal_type}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        let complete = |day: &str| {
+            auth_json(
+                "POST",
+                &format!("/meal-plan/{day}/complete"),
+                &token,
+                &json!({}),
+            )
+        };
+        let resp = app.clone().oneshot(complete("2024-03-02")).await.unwrap();
+        assert_eq!(json_body(resp.into_body()).await["cooked"], 1);
+        let resp = app.clone().oneshot(complete("2024-03-02")).await.unwrap();
+        assert_eq!(json_body(resp.into_body()).await["cooked"], 0);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{soup}"), &token))
+            .await
+            .unwrap();
+        let recipe = json_body(resp.into_body()).await;
+        assert_eq!(recipe["times_cooked"], 2);
+        assert_eq!(recipe["last_cooked_at"], "2024-03-02 00:00:00");
+
+        for day in ["2099-01-01", "yesterday"] {
+            let resp = app.clone().oneshot(complete(day)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{day}");
+        }
+    }
+
+    #[tokio::test]
+    async fn recipes_search_matches_words_and_ranks_titles_first() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let tea = json!({
+            "title": "Masala Tea",
+            "ingredients": [{"name": "black tea"}],
+            "instructions": ["Simmer with a crushed cardamom pod."],
+        });
+        create_recipe(&app, &token, tea).await;
+        let buns = json!({
+            "title": "Spiced Buns",
+            "ingredients": [{"name": "flour", "quantity": 500.0, "unit": "g"}, {"name": "cardamom"}],
+            "instructions": ["Knead.", "Bake."],
+        });
+        create_recipe(&app, &token, buns).await;
+        let cake = json!({"title": "Cardamom Cake", "ingredients": [], "instructions": []});
+        create_recipe(&app, &token, cake).await;
+        let soup = json!({
+            "title": "Winter Soup",
+            "notes": "Freezes well",
+            "ingredients": [{"name": "red lentils"}],
+            "instructions": [],
+        });
+        let soup = create_recipe(&app, &token, soup).await;
+
+        assert_eq!(
+            recipe_titles(&app, "/recipes?q=cardamom", &token).await,
+            ["Cardamom Cake", "Spiced Buns", "Masala Tea"]
+        );
+        // Word prefixes, any case, every word required.
+        assert_eq!(
+            recipe_titles(&app, "/recipes?q=CARDA", &token).await,
+            ["Cardamom Cake", "Spiced Buns", "Masala Tea"]
+        );
+        assert_eq!(
+            recipe_titles(&app, "/recipes?q=lent", &token).await,
+            ["Winter Soup"]
+        );
+        assert_eq!(
+            recipe_titles(&app, "/recipes?q=freez%20soup", &token).await,
+            ["Winter Soup"]
+        );
+        assert!(
+            recipe_titles(&app, "/recipes?q=cardamom%20lentil", &token)
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            recipe_titles(&app, "/recipes?q=cardamom&sort=title", &token).await,
+            ["Cardamom Cake", "Masala Tea", "Spiced Buns"]
+        );
+        // No words to index: plain substring match.
+        assert!(
+            recipe_titles(&app, "/recipes?q=%25", &token)
+                .await
+                .is_empty()
+        );
+
+        // The index follows edits and deletes.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{soup}"),
+                &token,
+                &json!({"ingredients": [{"name": "split peas"}]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            recipe_titles(&app, "/recipes?q=lentils", &token)
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            recipe_titles(&app, "/recipes?q=peas", &token).await,
+            ["Winter Soup"]
+        );
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/recipes/{soup}"))
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert!(
+            recipe_titles(&app, "/recipes?q=peas", &token)
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn recipes_get_converts_to_imperial_on_request() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let cake = json!({
+            "title": "Cake",
+            "ingredients": [
+                {"name": "flour", "quantity": 250.0, "unit": "g"},
+                {"name": "milk", "quantity": 240.0, "unit": "ml"},
+                {"name": "eggs", "quantity": 2.0},
+            ],
+            "instructions": [],
+        });
+        let cake = create_recipe(&app, &token, cake).await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{cake}?units=imperial"), &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["ingredients"][0]["quantity"], 8.75);
+        assert_eq!(body["ingredients"][0]["unit"], "oz");
+        assert_eq!(body["ingredients"][1]["quantity"], 1.0);
+        assert_eq!(body["ingredients"][1]["unit"], "cup");
+        assert_eq!(body["ingredients"][2]["quantity"], 2.0);
+
+        // Stored data stays metric.
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{cake}"), &token))
+            .await
+            .unwrap();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["ingredients"][0]["unit"], "g");
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{cake}?units=us"), &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // The preference lives in settings and is reported by /app-state.
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/app-state", &token))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["unit_system"], "metric");
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"unit_system": "furlongs"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"unit_system": "imperial"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app.oneshot(auth_get("/app-state", &token)).await.unwrap();
+        assert_eq!(json_body(resp.into_body()).await["unit_system"], "imperial");
+    }
+
+    #[tokio::test]
+    async fn llm_model_env_overrides_setting_without_saving_it() {
+        use crate::routes::settings::{LlmSettings, get_setting};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        let app = crate::app::build_app(state.clone());
+        let token = make_token();
+        let resp = app
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"llm_model": "stored/model"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(LlmSettings::load(&state).await.model, "stored/model");
+
+        state.config.llm_model = Some("env/model".to_string());
+        let settings = LlmSettings::load(&state).await;
+        assert_eq!(settings.model, "env/model");
+        assert!(settings.is_model_allowed("env/model"));
+        assert_eq!(
+            get_setting(&state.pool, "llm_model").await.as_deref(),
+            Some("stored/model")
+        );
+    }
+
+    #[tokio::test]
+    async fn servings_backfill_runs_once_per_database() {
+        let tmp = tempfile::tempdir().unwrap();
+        let pool = crate::db::make_pool(tmp.path().join("db.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let insert = |title: &'static str, yield_text: &'static str| {
+            sqlx::query(
+                r#"INSERT INTO recipes (title, "yield", ingredients, instructions)
+                   VALUES (?, ?, '[]', '[]')"#,
+            )
+            .bind(title)
+            .bind(yield_text)
+            .execute(&pool)
+        };
+        insert("Stew", "Serves 4").await.unwrap();
+        insert("Bread", "1 loaf").await.unwrap();
+
+        assert_eq!(crate::db::backfill_recipe_servings(&pool).await.unwrap(), 1);
+        let servings: Option<f64> =
+            sqlx::query_scalar("SELECT servings FROM recipes WHERE title = 'Stew'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(servings, Some(4.0));
+
+        // Later boots skip the scan entirely.
+        insert("Soup", "6 servings").await.unwrap();
+        assert_eq!(crate::db::backfill_recipe_servings(&pool).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn jwt_secret_is_generated_once_and_kept_out_of_the_database() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("jwt_secret");
+
+        let secret = crate::routes::auth::stored_jwt_secret(&path).unwrap();
+        assert_eq!(secret.len(), 64);
+        assert_eq!(
+            crate::routes::auth::stored_jwt_secret(&path).unwrap(),
+            secret
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::write(&path, "\n").unwrap();
+        let regenerated = crate::routes::auth::stored_jwt_secret(&path).unwrap();
+        assert_eq!(regenerated.len(), 64);
+        assert_ne!(regenerated, secret);
+    }
+
+    #[tokio::test]
+    async fn recipes_scaled_multiplies_quantities_without_saving() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let stew = json!({
+            "title": "Stew",
+            "yield": "4-6 servings",
+            "ingredients": [
+                {"name": "beef", "quantity": 700.0, "unit": "g"},
+                {"name": "paprika", "quantity": 1.0, "unit": "tbsp"},
+                {"name": "eggs", "quantity": 2.0},
+                {"name": "salt"},
+            ],
+            "instructions": [],
+        });
+        let stew = create_recipe(&app, &token, stew).await;
+        let loaf =
+            json!({"title": "Loaf", "yield": "1 loaf", "ingredients": [], "instructions": []});
+        let loaf = create_recipe(&app, &token, loaf).await;
+
+        // "4-6 servings" counts as 5.
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                &format!("/recipes/{stew}/scaled?servings=7"),
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["servings"], 7.0);
+        assert_eq!(body["yield"], "7 servings");
+        assert_eq!(body["ingredients"][0]["quantity"], 980.0);
+        assert_eq!(body["ingredients"][1]["quantity"], 1.5);
+        assert_eq!(body["ingredients"][2]["quantity"], 2.75);
+        assert!(body["ingredients"][3]["quantity"].is_null());
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                &format!("/recipes/{stew}/scaled?factor=0.5"),
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["yield"], "2.5 servings");
+        assert_eq!(body["ingredients"][0]["quantity"], 350.0);
+
+        // Nothing is written back.
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{stew}"), &token))
+            .await
+            .unwrap();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["yield"], "4-6 servings");
+        assert_eq!(body["ingredients"][0]["quantity"], 700.0);
+
+        // A yield that isn't servings-like only scales by factor.
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                &format!("/recipes/{loaf}/scaled?servings=2"),
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "unknown_servings"
+        );
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                &format!("/recipes/{loaf}/scaled?factor=2"),
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["yield"], "1 loaf");
+
+        for query in ["", "?factor=0", "?factor=2&servings=3"] {
+            let resp = app
+                .clone()
+                .oneshot(auth_get(&format!("/recipes/{stew}/scaled{query}"), &token))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{query}");
+        }
+    }
+
+    async fn export_backup(app: &axum::Router, token: &str, query: &str) -> Value {
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/export{query}"), token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let disposition = resp.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap();
+        assert!(disposition.starts_with("attachment; filename=\"blaz-backup-"));
+        json_body(resp.into_body()).await
+    }
+
+    #[tokio::test]
+    async fn recipes_export_round_trips_through_create() {
+        use base64::Engine;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let soup = json!({
+            "title": "Soup",
+            "source": "https://example.com/soup",
+            "yield": "4 servings",
+            "notes": "Better the next day",
+            "ingredients": [
+                {"section": "Base", "name": "Base"},
+                {"name": "onion", "quantity": 1.0, "prep": "diced"},
+                {"name": "stock", "quantity": 1.0, "unit": "L"},
+            ],
+            "instructions": ["Sweat the onion.", "Add stock."],
+            "equipment": ["stock pot"],
+        });
+        let soup_id = create_recipe(&app, &token, soup).await;
+        let toast = json!({"title": "Toast", "ingredients": [{"name": "bread"}], "instructions": ["Toast."]});
+        create_recipe(&app, &token, toast).await;
+
+        let image = b"RIFF-not-really-webp".to_vec();
+        std::fs::create_dir_all(tmp.path().join(format!("recipes/{soup_id}"))).unwrap();
+        std::fs::write(
+            tmp.path().join(format!("recipes/{soup_id}/full.webp")),
+            &image,
+        )
+        .unwrap();
+        sqlx::query("UPDATE recipes SET image_path_full = ? WHERE id = ?")
+            .bind(format!("recipes/{soup_id}/full.webp"))
+            .bind(soup_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let backup = export_backup(&app, &token, "").await;
+        assert_eq!(backup["format"], "blaz-backup");
+        assert_eq!(backup["version"], 1);
+        let recipes = backup["recipes"].as_array().unwrap();
+        assert_eq!(recipes.len(), 2);
+        assert_eq!(
+            recipes[0]["image_path_full"],
+            format!("recipes/{soup_id}/full.webp")
+        );
+        assert!(recipes[0].get("image").is_none());
+        assert!(recipes[0]["created_at"].is_string());
+
+        let inlined = export_backup(&app, &token, "?include_images=base64").await;
+        let uri = inlined["recipes"][0]["image"].as_str().unwrap();
+        let encoded = uri.strip_prefix("data:image/webp;base64,").unwrap();
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .unwrap(),
+            image
+        );
+        assert!(inlined["recipes"][1].get("image").is_none());
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes/export?include_images=zip", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Wipe, recreate from the backup, and export again.
+        sqlx::query("DELETE FROM recipes")
+            .execute(&pool)
+            .await
+            .unwrap();
+        for recipe in recipes {
+            let mut recipe = recipe.clone();
+            recipe["allow_duplicate"] = json!(true);
+            create_recipe(&app, &token, recipe).await;
+        }
+        let restored = export_backup(&app, &token, "").await;
+        let restored = restored["recipes"].as_array().unwrap();
+        assert_eq!(restored.len(), recipes.len());
+        for (before, after) in recipes.iter().zip(restored) {
+            for field in [
+                "title",
+                "source",
+                "yield",
+                "servings",
+                "notes",
+                "ingredients",
+                "instructions",
+                "equipment",
+            ] {
+                assert_eq!(before[field], after[field], "{field}");
+            }
+        }
+    }
+
+    async fn import_backup(app: &axum::Router, token: &str, query: &str, backup: &Value) -> Value {
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                &format!("/recipes/import-backup{query}"),
+                token,
+                backup,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body()).await
+    }
+
+    #[tokio::test]
+    async fn recipes_backup_import_restores_an_export() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state.clone());
+        let token = make_token();
+
+        let stew = json!({
+            "title": "Stew",
+            "source": "https://example.com/stew",
+            "yield": "4 servings",
+            "ingredients": [{"name": "beef", "quantity": 500.0, "unit": "g"}],
+            "instructions": ["Brown.", "Simmer."],
+        });
+        let stew_id = create_recipe(&app, &token, stew).await;
+        let salad =
+            json!({"title": "Salad", "ingredients": [{"name": "lettuce"}], "instructions": []});
+        create_recipe(&app, &token, salad).await;
+        sqlx::query("UPDATE recipes SET macros = ? WHERE id = ?")
+            .bind(
+                json!({"basis": "per_serving", "protein_g": 30.0, "fat_g": 12.0, "carbs_g": 5.0})
+                    .to_string(),
+            )
+            .bind(stew_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        crate::routes::recipes::save_recipe_image(&state, stew_id, png.into_inner())
+            .await
+            .unwrap();
+
+        let backup = export_backup(&app, &token, "?include_images=base64").await;
+
+        // Wipe the database and media, then restore.
+        sqlx::query("DELETE FROM recipes")
+            .execute(&pool)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(tmp.path().join("recipes")).unwrap();
+        let report = import_backup(&app, &token, "", &backup).await;
+        assert_eq!(report["imported_count"], 2);
+        assert!(report["failed"].as_array().unwrap().is_empty());
+        assert!(report["image_failed"].as_array().unwrap().is_empty());
+
+        let restored = export_backup(&app, &token, "").await;
+        let before = backup["recipes"].as_array().unwrap();
+        let after = restored["recipes"].as_array().unwrap();
+        assert_eq!(after.len(), 2);
+        for (b, a) in before.iter().zip(after) {
+            for field in [
+                "title",
+                "source",
+                "yield",
+                "servings",
+                "notes",
+                "ingredients",
+                "instructions",
+                "equipment",
+                "macros",
+                "created_at",
+            ] {
+                assert_eq!(b[field], a[field], "{field}");
+            }
+        }
+        let image = after[0]["image_path_full"].as_str().unwrap();
+        assert!(tmp.path().join(image).exists());
+        assert!(after[1]["image_path_full"].is_null());
+
+        // Same title and source: skipped by default, or added again, or replaced.
+        let report = import_backup(&app, &token, "", &backup).await;
+        assert_eq!(report["imported_count"], 0);
+        assert_eq!(report["skipped"].as_array().unwrap().len(), 2);
+        let mut edited = backup.clone();
+        edited["recipes"][0]["notes"] = json!("Use chuck");
+        let report = import_backup(&app, &token, "?mode=overwrite", &edited).await;
+        assert_eq!(report["overwritten_count"], 2);
+        let report = import_backup(&app, &token, "?mode=duplicate", &edited).await;
+        assert_eq!(report["imported_count"], 2);
+        let notes: Vec<String> =
+            sqlx::query_scalar("SELECT notes FROM recipes WHERE title = 'Stew' ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(notes, ["Use chuck", "Use chuck"]);
+
+        // A bad entry is reported; the rest still import.
+        let mut partial = backup.clone();
+        partial["recipes"][0]["ingredients"] = json!("not a list");
+        let report = import_backup(&app, &token, "?mode=duplicate", &partial).await;
+        assert_eq!(report["imported_count"], 1);
+        assert_eq!(report["failed"][0]["index"], 0);
+        assert_eq!(report["failed"][0]["title"], "Stew");
+        assert_eq!(report["failed"][0]["error_code"], "invalid_recipe");
+    }
+
+    #[tokio::test]
+    async fn recipes_backup_import_rejects_unsupported_documents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        for (doc, code) in [
+            (
+                json!({"format": "blaz-backup", "version": 99, "recipes": []}),
+                "unsupported_version",
+            ),
+            (
+                json!({"format": "other", "version": 1, "recipes": []}),
+                "invalid_backup",
+            ),
+            (json!([1, 2]), "invalid_json"),
+        ] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json("POST", "/recipes/import-backup", &token, &doc))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(json_body(resp.into_body()).await["code"], code);
+        }
+    }
+
+    #[tokio::test]
+    async fn recipes_bulk_update_reports_partial_success() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let has_oven = create_with_equipment(&app, &token, "Bread", &["oven"]).await;
+        let no_oven = create_with_equipment(&app, &token, "Stir fry", &["wok"]).await;
+        sqlx::query("UPDATE recipes SET updated_at = '2020-01-01 00:00:00'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let body = json!({
+            "ids": [has_oven, 999, no_oven, has_oven],
+            "set": {"equipment_add": ["Oven"], "equipment_remove": ["WOK"]}
+        });
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/recipes/bulk", &token, &body))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await,
+            json!([
+                {"id": has_oven, "status": "ok", "changed": false},
+                {"id": 999, "status": "not_found", "changed": false},
+                {"id": no_oven, "status": "ok", "changed": true}
+            ])
+        );
+
+        let updated_at = |id: i64| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, String>("SELECT updated_at FROM recipes WHERE id = ?")
+                    .bind(id)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        assert_eq!(updated_at(has_oven).await, "2020-01-01 00:00:00");
+        assert_ne!(updated_at(no_oven).await, "2020-01-01 00:00:00");
+        assert_eq!(
+            recipe_titles(&app, "/recipes?equipment=oven", &token).await,
+            ["Bread", "Stir fry"]
+        );
+
+        let too_many: Vec<i64> = (1..=201).collect();
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/bulk",
+                &token,
+                &json!({"ids": too_many, "set": {}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/bulk",
+                &token,
+                &json!({"ids": [has_oven], "set": {"tags_add": ["x"]}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    async fn insert_jsonld_fixture_recipe(pool: &sqlx::SqlitePool) {
+        sqlx::query(
+            r#"INSERT INTO recipes (id, title, source, "yield", servings, notes, ingredients,
+                   instructions, equipment, image_path_small, image_path_full, macros,
+                   share_token, created_at, updated_at)
+               VALUES (7, 'Shakshuka', 'https://example.com/shakshuka', '2 servings', 2,
+                   'Great with crusty bread.', ?, ?, '["skillet"]',
+                   'recipes/7/small.webp', 'recipes/7/full.webp', ?, 'tok-7',
+                   '2024-03-01 08:30:00', '2024-03-02 19:05:10')"#,
+        )
+        .bind(
+            json!([
+                {"section": "Base"},
+                {"quantity": 400.0, "unit": "g", "name": "chopped tomatoes", "raw": false},
+                {"quantity": 1.0, "name": "onion", "prep": "diced", "raw": false},
+                {"name": "salt", "raw": false},
+                {"quantity": 4.0, "name": "eggs", "raw": false}
+            ])
+            .to_string(),
+        )
+        .bind(
+            json!([
+                "Soften the onion.",
+                "## Sauce",
+                "Add tomatoes and simmer 10 minutes.",
+                "Crack in the eggs and cover."
+            ])
+            .to_string(),
+        )
+        .bind(
+            json!({
+                "basis": "per_serving",
+                "protein_g": 16.04,
+                "fat_g": 11.5,
+                "carbs_g": 18.0,
+                "ingredients": []
+            })
+            .to_string(),
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn recipe_jsonld_matches_golden() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        insert_jsonld_fixture_recipe(&state.pool).await;
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let mut req = auth_get("/recipes/7/jsonld", &token);
+        req.headers_mut()
+            .insert(header::HOST, "blaz.example".parse().unwrap());
+        req.headers_mut()
+            .insert("x-forwarded-proto", "https".parse().unwrap());
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/ld+json");
+        let doc = json_body(resp.into_body()).await;
+
+        // Required by schema.org / Google's Recipe rich result.
+        assert_eq!(doc["@context"], "https://schema.org");
+        assert_eq!(doc["@type"], "Recipe");
+        for field in ["name", "image", "recipeIngredient", "recipeInstructions"] {
+            assert!(!doc[field].is_null(), "missing {field}");
+        }
+        assert!(doc["image"].as_str().unwrap().starts_with("https://"));
+
+        let golden: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/recipe_jsonld.json")).unwrap();
+        assert_eq!(doc, golden);
+
+        // Our own importer reads it back.
+        let html = format!(r#"<script type="application/ld+json">{doc}</script>"#);
+        let parsed = crate::schema_org::extract_schema_recipe(&html).unwrap();
+        assert_eq!(parsed.name, "Shakshuka");
+        assert_eq!(parsed.ingredients.len(), 4);
+        assert_eq!(parsed.tools, ["skillet"]);
+
+        let resp = app
+            .oneshot(auth_get("/recipes/99/jsonld", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn recipe_export_renders_markdown_and_jsonld_downloads() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        insert_jsonld_fixture_recipe(&state.pool).await;
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes/7/export?format=markdown", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "text/markdown; charset=utf-8"
+        );
+        assert_eq!(
+            resp.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"shakshuka.md\""
+        );
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(bytes.to_vec()).unwrap(),
+            include_str!("../tests/fixtures/recipe_export.md")
+        );
+
+        let mut req = auth_get("/recipes/7/export?format=jsonld", &token);
+        req.headers_mut()
+            .insert(header::HOST, "blaz.example".parse().unwrap());
+        req.headers_mut()
+            .insert("x-forwarded-proto", "https".parse().unwrap());
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/ld+json");
+        assert_eq!(
+            resp.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"shakshuka.jsonld\""
+        );
+        let golden: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/recipe_jsonld.json")).unwrap();
+        assert_eq!(json_body(resp.into_body()).await, golden);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes/7/export?format=pdf", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app
+            .oneshot(auth_get("/recipes/99/export", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn share_page_neutralizes_markup_in_notes_and_steps() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        sqlx::query(
+            r"INSERT INTO recipes (title, notes, ingredients, instructions, share_token)
+               VALUES ('Toast', ?, '[]', ?, 'tok-xss')",
+        )
+        .bind("<b>Tip</b>: serve hot<script>alert(1)</script></script><!--")
+        .bind(
+            json!([
+                "Toast.</script><script>alert(document.cookie)</script>",
+                "<img src=x onerror=alert(1)>Butter."
+            ])
+            .to_string(),
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let app = crate::app::build_app(state);
+
+        let req = Request::builder()
+            .uri("/share/tok-xss")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8(bytes.to_vec()).unwrap();
+
+        assert_eq!(html.matches("<script").count(), 1, "{html}");
+        assert_eq!(html.matches("</script>").count(), 1, "{html}");
+        for bad in ["alert", "onerror", "<img", "<!--"] {
+            assert!(!html.contains(bad), "{bad} survived: {html}");
+        }
+
+        let start = html.find("application/ld+json\">").unwrap() + 21;
+        let end = html[start..].find("</script>").unwrap() + start;
+        let doc: Value = serde_json::from_str(&html[start..end]).unwrap();
+        assert_eq!(doc["description"], "<b>Tip</b>: serve hot");
+        assert_eq!(doc["recipeInstructions"][0]["text"], "Toast.");
+        assert_eq!(doc["recipeInstructions"][1]["text"], "Butter.");
+    }
+
+    #[tokio::test]
+    async fn share_html_renders_escaped_standalone_page() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        sqlx::query(
+            r#"INSERT INTO recipes (id, title, "yield", notes, ingredients, instructions, image_path_full, share_token)
+               VALUES (41, ?, '2 slices', 'Crunchy <script>alert(2)</script>', ?, ?, 'recipes/41/full.webp', 'tok-html')"#,
+        )
+        .bind("Toast<script>alert(1)</script>")
+        .bind(
+            json!([
+                {"section": "Base"},
+                {"quantity": 2.0, "name": "bread \"<b>sourdough</b>\""},
+            ])
+            .to_string(),
+        )
+        .bind(json!(["Toast.</li><script>alert(3)</script>", "## Serve", "Butter."]).to_string())
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let app = crate::app::build_app(state);
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(get("/share/tok-html/html"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let headers = resp.headers().clone();
+        assert!(
+            headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/html")
+        );
+        assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=300");
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8(bytes.to_vec()).unwrap();
+
+        assert!(!html.contains("<script"), "{html}");
+        assert!(!html.contains("alert(3)"), "{html}");
+        assert!(html.contains("<h1>Toast&lt;script&gt;alert(1)&lt;/script&gt;</h1>"));
+        assert!(html.contains(r#"<meta property="og:title" content="Toast&lt;script&gt;"#));
+        assert!(html.contains("/share/tok-html/card.png"));
+        assert!(html.contains(r#"<meta property="og:description" content="Crunchy""#));
+        assert!(html.contains("/media/recipes/41/full.webp"));
+        assert!(html.contains("<h3>Base</h3>"));
+        assert!(html.contains("&quot;&lt;b&gt;sourdough&lt;/b&gt;&quot;"));
+        assert!(html.contains("<li>Butter.</li>"));
+        assert!(!html.contains("/recipes/41\""), "no edit links");
+        assert!(!html.contains("<ul>\n</ul>"));
+
+        let resp = app.oneshot(get("/share/nope/html")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn share_page_embeds_jsonld_without_auth() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        insert_jsonld_fixture_recipe(&state.pool).await;
+        let app = crate::app::build_app(state);
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let resp = app.clone().oneshot(get("/share/tok-7")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8(bytes.to_vec()).unwrap();
+        let parsed = crate::schema_org::extract_schema_recipe(&html).expect("JSON-LD in page");
+        assert_eq!(parsed.name, "Shakshuka");
+
+        let resp = app.oneshot(get("/share/unknown")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("application/ld+json"));
+    }
+
+    #[tokio::test]
+    async fn shared_recipe_hides_notes_when_setting_is_off() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        sqlx::query(
+            r"INSERT INTO recipes (title, notes, ingredients, instructions, share_token)
+               VALUES ('Toast', 'Gran''s secret: more butter', '[]', '[]', 'tok-notes')",
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+        let get = || {
+            Request::builder()
+                .uri("/api/share/tok-notes")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(get()).await.unwrap();
+        assert_eq!(
+            json_body(resp.into_body()).await["notes"],
+            "Gran's secret: more butter"
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"share_include_notes": "false"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["updated"], 1);
+
+        let resp = app.clone().oneshot(get()).await.unwrap();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["title"], "Toast");
+        assert_eq!(body["notes"], "");
+
+        let page = Request::builder()
+            .uri("/share/tok-notes")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(page).await.unwrap();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn share_card_renders_png_and_reuses_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let photo = image::RgbImage::from_pixel(640, 480, image::Rgb([180, 90, 20]));
+        std::fs::create_dir_all(tmp.path().join("recipes/1")).unwrap();
+        photo.save(tmp.path().join("recipes/1/full.png")).unwrap();
+        sqlx::query(
+            r"INSERT INTO recipes (id, title, ingredients, instructions, image_path_full,
+                                  share_token, updated_at)
+               VALUES (1, 'A very long title for a humble bowl of weeknight lentil soup with lemon',
+                       '[]', '[]', 'recipes/1/full.png', 'tok-card', '2026-01-01 10:00:00')",
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+
+        let fetch_card = || async {
+            let req = Request::builder()
+                .uri("/share/tok-card/card.png")
+                .body(Body::empty())
+                .unwrap();
+            let resp = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(resp.headers()[header::CONTENT_TYPE], "image/png");
+            let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            image::load_from_memory_with_format(&bytes, image::ImageFormat::Png).unwrap()
+        };
+
+        let card = fetch_card().await;
+        assert_eq!((card.width(), card.height()), (1200, 630));
+
+        let cached = tmp.path().join("share-cards/1-20260101100000.png");
+        let mtime = std::fs::metadata(&cached).unwrap().modified().unwrap();
+        fetch_card().await;
+        assert_eq!(
+            std::fs::metadata(&cached).unwrap().modified().unwrap(),
+            mtime,
+            "second request must be served from the cache"
+        );
+
+        // An edit renders a new card and drops the stale one.
+        sqlx::query("UPDATE recipes SET title = 'Lentil soup', updated_at = '2026-01-02 08:00:00'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        fetch_card().await;
+        assert!(tmp.path().join("share-cards/1-20260102080000.png").exists());
+        assert!(!cached.exists());
+
+        let req = Request::builder()
+            .uri("/share/unknown/card.png")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn share_card_without_photo_and_og_image_tag() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.public_url = Some("https://food.example".into());
+        sqlx::query(
+            r"INSERT INTO recipes (title, ingredients, instructions, share_token)
+               VALUES ('Toast', '[]', '[]', 'tok-plain')",
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let app = crate::app::build_app(state);
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let resp = app
+            .clone()
+            .oneshot(get("/share/tok-plain/card.png"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let card = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((card.width(), card.height()), (1200, 630));
+
+        let resp = app.oneshot(get("/share/tok-plain")).await.unwrap();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let html = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(html.contains(
+            r#"<meta property="og:image" content="https://food.example/share/tok-plain/card.png">"#
+        ));
+    }
+
+    // ── recipesage import ────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn recipesage_import_creates_recipes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let payload = json!([
+            {
+                "name": "Imported Soup",
+                "url": "https://example.com/soup",
+                "recipeIngredient": ["2 carrots", "1 L water"],
+                "recipeInstructions": ["Boil everything"],
+                "recipeYield": "4"
+            },
+            {
+                "name": "Imported Cake",
+                "recipeIngredient": ["200 g flour"],
+                "recipeInstructions": [{"@type": "HowToStep", "text": "Mix and bake"}]
+            }
+        ]);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage",
+                &token,
+                &payload,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["imported_count"], 2);
+        assert_eq!(body["failed"].as_array().unwrap().len(), 0);
+
+        // Verify recipes actually exist
+        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        let recipes = json_body(list_resp.into_body()).await;
+        assert_eq!(recipes.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_skips_duplicate_by_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let payload = json!([{
+            "name": "Pasta",
+            "url": "https://example.com/pasta",
+            "recipeIngredient": [],
+            "recipeInstructions": []
+        }]);
+
+        // First import
+        app.clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage",
+                &token,
+                &payload,
+            ))
+            .await
+            .unwrap();
+
+        // Second import — should be skipped
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage",
+                &token,
+                &payload,
+            ))
+            .await
+            .unwrap();
+
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["imported_count"], 0);
+        assert_eq!(body["failed"].as_array().unwrap().len(), 0);
+        assert_eq!(body["skipped"][0]["reason"], "duplicate_source");
+
+        // DB should still have only 1 recipe
+        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        let recipes = json_body(list_resp.into_body()).await;
+        assert_eq!(recipes.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_skips_duplicate_by_content_when_no_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let payload = json!([{
+            "name": "No Source Recipe",
+            "recipeIngredient": [],
+            "recipeInstructions": []
+        }]);
+
+        app.clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage",
+                &token,
+                &payload,
+            ))
+            .await
+            .unwrap();
+
+        app.clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage",
+                &token,
+                &payload,
+            ))
+            .await
+            .unwrap();
+
+        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        let recipes = json_body(list_resp.into_body()).await;
+        assert_eq!(recipes.as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_invalid_json_returns_400() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/recipes/import/recipesage")
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from("not json at all"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_accepts_exports_over_the_global_body_limit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        // Past the 50 MB default: read in full, then rejected as JSON, not 413.
+        let mut body = b"not json".to_vec();
+        body.resize(51 * 1024 * 1024, b' ');
+        let resp = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/recipes/import/recipesage")
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::CONTENT_LENGTH, body.len())
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Asserts the report for `tests/fixtures/recipesage_export.json`: one good
+    /// entry, malformed instructions, a missing image, and a duplicate.
+    fn assert_recipesage_fixture_report(body: &Value) {
+        let failed = body["failed"].as_array().unwrap();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0]["index"], 1);
+        assert_eq!(failed[0]["title"], "Broken Bread");
+        assert_eq!(failed[0]["error_code"], "invalid_instructions");
+        assert_eq!(failed[1]["index"], 2);
+        assert_eq!(failed[1]["title"], "Pictureless Pie");
+        assert_eq!(failed[1]["error_code"], "image_not_found");
+        assert!(
+            failed[1]["detail"]
+                .as_str()
+                .unwrap()
+                .contains("does-not-exist.jpg")
+        );
+
+        let skipped = body["skipped"].as_array().unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0]["index"], 3);
+        assert_eq!(skipped[0]["title"], "Lentil Soup");
+        assert_eq!(skipped[0]["reason"], "duplicate_content");
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_dry_run_reports_without_writing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+        let fixture: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/recipesage_export.json")).unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage?dry_run=true",
+                &token,
+                &fixture,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["would_import"], 1);
+        assert_eq!(body["imported_count"], 0);
+        assert_recipesage_fixture_report(&body);
+
+        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        let recipes = json_body(list_resp.into_body()).await;
+        assert!(recipes.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_reports_structured_failures() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+        let fixture: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/recipesage_export.json")).unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage",
+                &token,
+                &fixture,
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["imported_count"], 1);
+        assert!(body.get("would_import").is_none());
+        assert_recipesage_fixture_report(&body);
+
+        let list_resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        let recipes = json_body(list_resp.into_body()).await;
+        assert_eq!(recipes.as_array().unwrap().len(), 1);
+        assert_eq!(recipes[0]["title"], "Lentil Soup");
+    }
+
+    /// Recipe pages that answer after `delay`, each pointing at `/img/{n}.png`.
+    /// Images whose number is a multiple of 5 are missing (404).
+    async fn spawn_slow_recipe_site(delay: std::time::Duration) -> String {
+        use axum::extract::{Path, State};
+        use axum::response::IntoResponse;
+        use axum::routing::get;
+
+        async fn page(
+            State((base, delay)): State<(String, std::time::Duration)>,
+            Path(n): Path<u32>,
+        ) -> axum::response::Response {
+            tokio::time::sleep(delay).await;
+            axum::response::Html(format!(
+                r#"<html><head><meta property="og:image" content="{base}/img/{n}.png"></head></html>"#
+            ))
+            .into_response()
+        }
+
+        async fn img(Path(name): Path<String>) -> axum::response::Response {
+            let n: u32 = name.trim_end_matches(".png").parse().unwrap();
+            if n.is_multiple_of(5) {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+            let mut png = std::io::Cursor::new(Vec::new());
+            image::RgbImage::new(8, 8)
+                .write_to(&mut png, image::ImageFormat::Png)
+                .unwrap();
+            ([(header::CONTENT_TYPE, "image/png")], png.into_inner()).into_response()
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = axum::Router::new()
+            .route("/recipe/{n}", get(page))
+            .route("/img/{name}", get(img))
+            .with_state((base.clone(), delay));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_fetches_images_concurrently() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let delay = std::time::Duration::from_millis(300);
+        let site = spawn_slow_recipe_site(delay).await;
+        let payload: Vec<Value> = (1..=20)
+            .map(|n| {
+                json!({
+                    "name": format!("Recipe {n}"),
+                    "url": format!("{site}/recipe/{n}"),
+                    "recipeIngredient": [format!("{n} g flour")],
+                    "recipeInstructions": ["Bake."]
+                })
+            })
+            .collect();
+
+        let started = std::time::Instant::now();
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage",
+                &token,
+                &json!(payload),
+            ))
+            .await
+            .unwrap();
+        let elapsed = started.elapsed();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["imported_count"], 20);
+        assert!(body["failed"].as_array().unwrap().is_empty());
+
+        // Sequential fetching would take at least 20 × delay.
+        assert!(
+            elapsed < delay * 10,
+            "import took {elapsed:?}, expected well under {:?}",
+            delay * 20
+        );
+
+        // Missing images are reported but do not fail their recipes.
+        let image_failed = body["image_failed"].as_array().unwrap();
+        let titles: Vec<&str> = image_failed
+            .iter()
+            .map(|f| f["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles, ["Recipe 5", "Recipe 10", "Recipe 15", "Recipe 20"]);
+
+        let with_image: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM recipes WHERE image_path_full IS NOT NULL")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(with_image, 16);
+    }
+
+    #[tokio::test]
+    async fn recipesage_import_stream_reports_progress_then_report() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+        let fixture: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/recipesage_export.json")).unwrap();
+
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/recipesage/stream",
+                &token,
+                &fixture,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            resp.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/event-stream")
+        );
+
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        let events: Vec<(&str, Value)> = text
+            .split("\n\n")
+            .filter_map(|block| {
+                let name = block.lines().find_map(|l| l.strip_prefix("event: "))?;
+                let data = block.lines().find_map(|l| l.strip_prefix("data: "))?;
+                Some((name, serde_json::from_str(data).unwrap()))
+            })
+            .collect();
+
+        let recipes: Vec<&Value> = events
+            .iter()
+            .filter(|(name, v)| *name == "progress" && v["type"] == "recipe")
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(recipes.len(), fixture.as_array().unwrap().len());
+        assert_eq!(recipes[0]["status"], "imported");
+        assert_eq!(recipes[1]["status"], "failed");
+        assert_eq!(recipes[3]["status"], "skipped");
+
+        let (name, report) = events.last().unwrap();
+        assert_eq!(*name, "done");
+        assert_eq!(report["imported_count"], 1);
+        assert_recipesage_fixture_report(report);
+    }
+
+    // ── shopping list ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn shopping_list_starts_empty() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let resp = app.oneshot(auth_get("/shopping", &token)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await.as_array().unwrap().len(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn shopping_add_and_list_item() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        app.clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping",
+                &token,
+                &json!({"text": "2 kg potatoes"}),
+            ))
+            .await
+            .unwrap();
+
+        let resp = app.oneshot(auth_get("/shopping", &token)).await.unwrap();
+
+        let items = json_body(resp.into_body()).await;
+        assert_eq!(items.as_array().unwrap().len(), 1);
+        assert!(items[0]["text"].as_str().unwrap().contains("potatoes"));
+    }
+
+    async fn add_shopping(app: &axum::Router, token: &str, text: &str) -> i64 {
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping",
+                token,
+                &json!({"text": text}),
+            ))
+            .await
+            .unwrap();
+        json_body(resp.into_body()).await["id"].as_i64().unwrap()
+    }
+
+    async fn add_shopping_full(app: &axum::Router, token: &str, text: &str) -> Value {
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping",
+                token,
+                &json!({"text": text}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body()).await
+    }
+
+    async fn shopping_texts(app: &axum::Router, token: &str) -> Vec<Value> {
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", token))
+            .await
+            .unwrap();
+        json_body(resp.into_body())
+            .await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["text"].clone())
+            .collect()
+    }
+
+    /// Same inputs in either order must give the same list: a plain name
+    /// never changes the quantity, quantities always add up.
+    #[tokio::test]
+    async fn shopping_create_is_order_independent() {
+        for inputs in [
+            ["eggs", "6 eggs", "3 eggs"],
+            ["6 eggs", "eggs", "3 eggs"],
+            ["3 eggs", "6 eggs", "eggs"],
+        ] {
+            let tmp = tempfile::tempdir().unwrap();
+            let app = crate::app::build_app(make_test_state(&tmp).await);
+            let token = make_token();
+
+            let mut flags = Vec::new();
+            for text in inputs {
+                flags.push(add_shopping_full(&app, &token, text).await["updated"].clone());
+            }
+            assert_eq!(flags, [false, true, true], "{inputs:?}");
+            assert_eq!(shopping_texts(&app, &token).await, ["9 eggs"], "{inputs:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn shopping_create_merges_imperial_into_grams() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let first = add_shopping_full(&app, &token, "8 oz cream cheese").await;
+        // Stored as 224 g, shown as written until merged with grams.
+        assert_eq!(first["text"], "8 oz cream cheese");
+        let merged = add_shopping_full(&app, &token, "200 g cream cheese").await;
+        assert_eq!(merged["updated"], true);
+        assert_eq!(merged["id"], first["id"]);
+        assert_eq!(shopping_texts(&app, &token).await, ["424 g cream cheese"]);
+    }
+
+    #[tokio::test]
+    async fn shopping_create_ignores_prep_when_merging() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        add_shopping_full(&app, &token, "2 carrots, diced").await;
+        let merged = add_shopping_full(&app, &token, "3 carrots, julienned").await;
+        assert_eq!(merged["updated"], true);
+        add_shopping_full(&app, &token, "diced onion, 2").await;
+        assert_eq!(
+            shopping_texts(&app, &token).await,
+            ["5 carrots", "diced onion, 2"]
+        );
+
+        // Prep on recipe ingredients survives an update.
+        let stew = json!({
+            "title": "Stew",
+            "ingredients": [{"name": "carrots", "quantity": 2.0, "prep": "diced"}],
+            "instructions": [],
+        });
+        let stew = create_recipe(&app, &token, stew).await;
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{stew}"),
+                &token,
+                &json!({"ingredients": [{"name": "carrots", "quantity": 3.0, "prep": "julienned"}]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .oneshot(auth_get(&format!("/recipes/{stew}"), &token))
+            .await
+            .unwrap();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["ingredients"][0]["name"], "carrots");
+        assert_eq!(body["ingredients"][0]["prep"], "julienned");
+    }
+
+    async fn shopping_bulk(app: &axum::Router, token: &str, body: &Value) -> (StatusCode, Value) {
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/shopping/bulk", token, body))
+            .await
+            .unwrap();
+        let status = resp.status();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn shopping_bulk_delete_done_keeps_undone_items() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let milk = add_shopping_full(&app, &token, "1 L milk").await["id"].clone();
+        let eggs = add_shopping_full(&app, &token, "6 eggs").await["id"].clone();
+        add_shopping_full(&app, &token, "bread").await;
+
+        // Unknown ids are ignored.
+        let (status, list) = shopping_bulk(
+            &app,
+            &token,
+            &json!({"action": "mark_all_done", "ids": [milk, eggs, 9999]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let done: Vec<i64> = list
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["done"].as_i64().unwrap())
+            .collect();
+        assert_eq!(done, [1, 1, 0]);
+
+        let (status, list) = shopping_bulk(&app, &token, &json!({"action": "delete_done"})).await;
+        assert_eq!(status, StatusCode::OK);
+        let texts: Vec<&str> = list
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, ["bread"]);
+        assert_eq!(shopping_texts(&app, &token).await, ["bread"]);
+
+        let (status, _) = shopping_bulk(&app, &token, &json!({"action": "delete_some"})).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, list) = shopping_bulk(&app, &token, &json!({"action": "delete_all"})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(list, json!([]));
+    }
+
+    #[tokio::test]
+    async fn shopping_bulk_done_items_start_over_when_merged_again() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let recipe_id = create_recipe(
+            &app,
+            &token,
+            json!({"title": "Risotto", "ingredients": [], "instructions": []}),
+        )
+        .await;
+        let merge = json!({
+            "items": [{"quantity": 200.0, "unit": "g", "name": "rice"}],
+            "recipe_id": recipe_id,
+            "force": true
+        });
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &merge))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let (status, list) = shopping_bulk(&app, &token, &json!({"action": "mark_all_done"})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(list[0]["quantity"].is_null(), "{list}");
+        assert_eq!(list[0]["recipe_ids"], "[]");
+
+        let purchased: Vec<Option<f64>> =
+            sqlx::query_scalar("SELECT quantity FROM shopping_purchases")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(purchased, [Some(200.0)]);
+
+        let resp = app
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &merge))
+            .await
+            .unwrap();
+        let items = &json_body(resp.into_body()).await["items"];
+        assert_eq!(items[0]["text"], "200 g rice", "{items}");
+        assert_eq!(items[0]["recipe_ids"], format!("[{recipe_id}]"));
+    }
+
+    #[tokio::test]
+    async fn shopping_reorder_sets_order_within_category() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let mut ids = Vec::new();
+        for text in ["apples", "bananas", "pears"] {
+            ids.push(add_shopping_full(&app, &token, text).await["id"].clone());
+        }
+        assert_eq!(
+            shopping_texts(&app, &token).await,
+            ["apples", "bananas", "pears"]
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/reorder",
+                &token,
+                &json!({"ids": [ids[2], ids[0], ids[1]]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            shopping_texts(&app, &token).await,
+            ["pears", "apples", "bananas"]
+        );
+
+        // An unknown id fails the whole reorder.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/reorder",
+                &token,
+                &json!({"ids": [ids[0], ids[1], 9999]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            shopping_texts(&app, &token).await,
+            ["pears", "apples", "bananas"]
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{}", ids[1]),
+                &token,
+                &json!({"position": 0.5}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["position"], 0.5);
+        assert_eq!(
+            shopping_texts(&app, &token).await,
+            ["bananas", "pears", "apples"]
+        );
+
+        // New items go to the end.
+        add_shopping_full(&app, &token, "plums").await;
+        assert_eq!(
+            shopping_texts(&app, &token).await,
+            ["bananas", "pears", "apples", "plums"]
+        );
+    }
+
+    #[tokio::test]
+    async fn shopping_llm_categories_follow_setting_and_recategorize() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let set_llm = |on: &str| {
+            auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"use_llm_categories": on}}),
+            )
+        };
+        let resp = app.clone().oneshot(set_llm("false")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let item = add_shopping_full(&app, &token, "mystery grain").await;
+        assert_eq!(item["category"], "Other");
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/app-state", &token))
+            .await
+            .unwrap();
+        assert_eq!(
+            json_body(resp.into_body()).await["use_llm_categories"],
+            false
+        );
+
+        let resp = app.clone().oneshot(set_llm("true")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let item = add_shopping_full(&app, &token, "odd powder").await;
+        assert_eq!(item["category"], "Pantry");
+
+        let recategorize = || auth_json("POST", "/shopping/recategorize", &token, &json!({}));
+        let resp = app.clone().oneshot(recategorize()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["changed"], 1);
+        let resp = app.clone().oneshot(recategorize()).await.unwrap();
+        assert_eq!(json_body(resp.into_body()).await["changed"], 0);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", &token))
+            .await
+            .unwrap();
+        let categories: Vec<Value> = json_body(resp.into_body())
+            .await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["category"].clone())
+            .collect();
+        assert_eq!(categories, ["Pantry", "Pantry"]);
+    }
+
+    #[tokio::test]
+    async fn shopping_create_readds_done_items() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let mark_done = |id: i64| {
+            auth_json(
+                "PATCH",
+                &format!("/shopping/{id}"),
+                &token,
+                &json!({"done": true}),
+            )
+        };
+
+        // Re-adding a bought item brings it back with only the new quantity,
+        // whether or not the new input has one.
+        for (readd, expected) in [("2 eggs", json!(["2 eggs"])), ("eggs", json!(["eggs"]))] {
+            let id = add_shopping(&app, &token, "6 eggs").await;
+            app.clone().oneshot(mark_done(id)).await.unwrap();
+            assert!(shopping_texts(&app, &token).await.is_empty());
+
+            let body = add_shopping_full(&app, &token, readd).await;
+            assert_eq!(body["id"], id);
+            assert_eq!(body["updated"], true);
+            assert_eq!(json!(shopping_texts(&app, &token).await), expected);
+            app.clone().oneshot(mark_done(id)).await.unwrap();
+        }
+
+        // With the setting off, re-adding a done item leaves it done.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"shopping_readd_undone": "false"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = add_shopping_full(&app, &token, "eggs").await;
+        assert_eq!(body["updated"], true);
+        assert_eq!(body["done"], 1);
+        assert!(shopping_texts(&app, &token).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn shopping_combine_converts_compatible_units() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let kg = add_shopping(&app, &token, "1 kg flour").await;
+        let g = add_shopping(&app, &token, "500 g flour").await;
+        let ml = add_shopping(&app, &token, "200 ml flour").await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/combine",
+                &token,
+                &json!({"target_id": kg, "source_ids": [g, ml]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["id"], kg);
+        assert_eq!(body["text"], "1.5 kg flour");
+        assert_eq!(body["skipped"].as_array().unwrap().len(), 1);
+        assert_eq!(body["skipped"][0]["id"], ml);
+
+        // The incompatible source is kept, the compatible one is gone.
+        let resp = app.oneshot(auth_get("/shopping", &token)).await.unwrap();
+        let ids: Vec<i64> = json_body(resp.into_body())
+            .await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["id"].as_i64().unwrap())
+            .collect();
+        assert!(ids.contains(&kg));
+        assert!(ids.contains(&ml));
+        assert!(!ids.contains(&g));
+    }
+
+    #[tokio::test]
+    async fn shopping_combine_rename_recomputes_key() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let typo = add_shopping(&app, &token, "2 corriander").await;
+        let good = add_shopping(&app, &token, "1 coriander").await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/combine",
+                &token,
+                &json!({"target_id": typo, "source_ids": [good], "name": "Coriander"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["text"], "3 coriander");
+        assert!(body["skipped"].as_array().unwrap().is_empty());
+
+        // A later add of the corrected name merges into the combined row.
+        let again = add_shopping(&app, &token, "1 coriander").await;
+        assert_eq!(again, typo);
+    }
+
+    #[tokio::test]
+    async fn shopping_combine_conflict_returns_existing_item() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let typo = add_shopping(&app, &token, "2 corriander").await;
+        let good = add_shopping(&app, &token, "1 coriander").await;
+
+        // Renaming without combining collides with the existing row.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/combine",
+                &token,
+                &json!({"target_id": typo, "source_ids": [], "name": "coriander"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["error"]["code"], "duplicate_item");
+        assert_eq!(body["error"]["existing"]["id"], good);
+        assert_eq!(body["error"]["existing"]["text"], "1 coriander");
+
+        // The rejected rename was rolled back.
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", &token))
+            .await
+            .unwrap();
+        let list = json_body(resp.into_body()).await;
+        assert!(
+            list.as_array()
+                .unwrap()
+                .iter()
+                .any(|i| i["text"] == "2 corriander")
+        );
+    }
+
+    #[tokio::test]
+    async fn shopping_combine_missing_target_returns_404() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/combine",
+                &token,
+                &json!({"target_id": 999, "source_ids": []}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn shopping_merge_same_recipe_day_conflicts_unless_forced() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Curry", "ingredients": [], "instructions": []}),
+            ))
+            .await
+            .unwrap();
+        let recipe_id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+
+        let merge = json!({
+            "items": [{"quantity": 200.0, "unit": "g", "name": "rice"}],
+            "recipe_id": recipe_id,
+            "day": "2030-01-01"
+        });
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &merge))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Second add for the same recipe/day is rejected with details.
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &merge))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["error"]["code"], "already_generated");
+        assert!(body["error"]["hint"].is_string());
+        assert!(body["error"]["existing"]["generated_at"].is_string());
+        assert_eq!(body["error"]["existing"]["recipe_id"], recipe_id);
+        assert_eq!(body["error"]["existing"]["day"], "2030-01-01");
+
+        // Quantity was not doubled by the rejected request.
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", &token))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await[0]["text"], "200 g rice");
+
+        // Forced re-add goes through and records a second generation.
+        let mut forced = merge.clone();
+        forced["force"] = json!(true);
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &forced))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await["items"][0]["text"],
+            "400 g rice"
+        );
+
+        let resp = app
+            .oneshot(auth_get("/shopping/generations?days=7", &token))
+            .await
+            .unwrap();
+        let generations = json_body(resp.into_body()).await;
+        let generations = generations.as_array().unwrap();
+        assert_eq!(generations.len(), 2);
+        assert_eq!(generations[0]["recipe_title"], "Curry");
+        assert_eq!(generations[0]["day"], "2030-01-01");
+        assert_ne!(generations[0]["merge_id"], generations[1]["merge_id"]);
+    }
+
+    #[tokio::test]
+    async fn shopping_merge_same_recipe_other_day_is_allowed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        for day in ["2030-01-01", "2030-01-08"] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json(
+                    "POST",
+                    "/shopping/merge",
+                    &token,
+                    &json!({"items": [{"name": "rice"}], "recipe_id": 1, "day": day}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn shopping_merge_skips_pantry_staples() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let mut salt = 0;
+        for name in ["Salt", " olive   oil "] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json("POST", "/pantry", &token, &json!({"name": name})))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let item = json_body(resp.into_body()).await;
+            if name == "Salt" {
+                salt = item["id"].as_i64().unwrap();
+            }
+        }
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/pantry",
+                &token,
+                &json!({"name": "SALT"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/pantry", &token))
+            .await
+            .unwrap();
+        let names: Vec<Value> = json_body(resp.into_body())
+            .await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].clone())
+            .collect();
+        assert_eq!(names, ["olive oil", "salt"]);
+
+        let merge = json!({"items": [
+            {"name": "salt", "quantity": 1.0, "unit": "tsp"},
+            {"name": "Olive Oil", "quantity": 2.0, "unit": "tbsp"},
+            {"name": "salted peanuts", "quantity": 100.0, "unit": "g"},
+        ]});
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &merge))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["skipped"], json!(["salt", "Olive Oil"]));
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["text"], "100 g salted peanuts");
+
+        // Removing salt from the pantry lets it through again.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "DELETE",
+                &format!("/pantry/{salt}"),
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["deleted"], 1);
+        let resp = app
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &merge))
+            .await
+            .unwrap();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["skipped"], json!(["Olive Oil"]));
+    }
+
+    #[tokio::test]
+    async fn recipes_add_to_shopping_twice_doubles_quantities() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let pancakes = json!({
+            "title": "Pancakes",
+            "yield": "4 servings",
+            "ingredients": [
+                {"section": "Batter", "name": "Batter"},
+                {"name": "flour", "quantity": 200.0, "unit": "g"},
+                {"name": "milk", "quantity": 3.0, "unit": "dl"},
+                {"name": "eggs", "quantity": 2.0},
+            ],
+            "instructions": [],
+        });
+        let id = create_recipe(&app, &token, pancakes).await;
+        let uri = format!("/recipes/{id}/add-to-shopping");
+
+        let texts = |body: Value| -> Vec<String> {
+            let mut texts: Vec<String> = body["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i["text"].as_str().unwrap().to_string())
+                .collect();
+            texts.sort();
+            texts
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", &uri, &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(texts(body), ["2 eggs", "200 g flour", "3 dl milk"]);
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", &uri, &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                &uri,
+                &token,
+                &json!({"force": true, "servings": 4}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(texts(body), ["4 eggs", "400 g flour", "6 dl milk"]);
+
+        // Half the recipe for 2 servings.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                &uri,
+                &token,
+                &json!({"force": true, "servings": 2}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(texts(body), ["5 eggs", "500 g flour", "7.5 dl milk"]);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/9999/add-to-shopping",
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn seed_purchases(pool: &sqlx::SqlitePool, rows: &[(&str, Option<f64>, Option<&str>)]) {
+        for (name, quantity, unit) in rows {
+            sqlx::query(
+                "INSERT INTO shopping_purchases (name, quantity, unit, category) VALUES (?, ?, ?, 'Dairy')",
+            )
+            .bind(name)
+            .bind(quantity)
+            .bind(unit)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn shopping_suggest_returns_most_frequent_purchase() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        seed_purchases(
+            &state.pool,
+            &[
+                ("milk", Some(1.0), Some("L")),
+                ("milk", Some(2.0), Some("L")),
+                ("milk", Some(1.0), Some("L")),
+                ("milk", None, None),
+            ],
+        )
+        .await;
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping/suggest?name=Milk", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await,
+            json!({"name": "milk", "quantity": 1.0, "unit": "L", "category": "Dairy", "times": 2, "total": 4})
+        );
+
+        let resp = app
+            .oneshot(auth_get("/shopping/suggest?name=saffron", &token))
+            .await
+            .unwrap();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["times"], 0);
+        assert!(body["quantity"].is_null());
+    }
+
+    async fn tick_off(app: &axum::Router, token: &str, id: i64) {
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{id}"),
+                token,
+                &json!({"done": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn shopping_create_applies_confident_suggestion_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        for _ in 0..2 {
+            let body = add_shopping_full(&app, &token, "1 L milk").await;
+            tick_off(&app, &token, body["id"].as_i64().unwrap()).await;
+        }
+
+        // Two matching purchases are not enough.
+        let body = add_shopping_full(&app, &token, "milk").await;
+        assert_eq!(body["text"], "milk");
+        assert_eq!(body["quantity_suggested"], false);
+        // Ticking off a plain "milk" records a purchase without a quantity.
+        tick_off(&app, &token, body["id"].as_i64().unwrap()).await;
+
+        let body = add_shopping_full(&app, &token, "1 L milk").await;
+        tick_off(&app, &token, body["id"].as_i64().unwrap()).await;
+        let recorded: Vec<Option<f64>> =
+            sqlx::query_scalar("SELECT quantity FROM shopping_purchases ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(recorded.len(), 4);
+        assert_eq!(recorded.iter().filter(|q| q.is_some()).count(), 3);
+
+        let body = add_shopping_full(&app, &token, "Milk").await;
+        assert_eq!(body["text"], "1 L milk");
+        assert_eq!(body["quantity_suggested"], true);
+
+        // Already on the list: a plain re-add leaves it alone.
+        let body = add_shopping_full(&app, &token, "milk").await;
+        assert_eq!(body["quantity_suggested"], false);
+
+        // An explicit quantity always wins.
+        let body = add_shopping_full(&app, &token, "2 eggs").await;
+        assert_eq!(body["text"], "2 eggs");
+        assert_eq!(body["quantity_suggested"], false);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_shopping_creates_merge_into_one_row() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        // A file-backed pool, so the concurrent requests really use separate
+        // connections to the same database.
+        state.pool = crate::db::make_pool(tmp.path().join("race.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let requests = (0..10).map(|_| {
+            let app = app.clone();
+            let req = auth_json("POST", "/shopping", &token, &json!({"text": "2 apples"}));
+            tokio::spawn(async move { app.oneshot(req).await.unwrap().status() })
+        });
+        for status in futures_util::future::join_all(requests).await {
+            assert_eq!(status.unwrap(), StatusCode::OK);
+        }
+
+        let rows: Vec<(Option<f64>,)> = sqlx::query_as("SELECT quantity FROM shopping_items")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![(Some(20.0),)]);
+    }
+
+    #[tokio::test]
+    async fn shopping_text_with_multiplier_round_trips_and_merges() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping",
+                &token,
+                &json!({"text": "milk"}),
+            ))
+            .await
+            .unwrap();
+        let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{id}"),
+                &token,
+                &json!({"text": "eggs x12"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["text"], "12 eggs");
+
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/shopping",
+                &token,
+                &json!({"text": "a dozen eggs"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let rows: Vec<(String, Option<f64>)> =
+            sqlx::query_as("SELECT name, quantity FROM shopping_items")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(rows, vec![("eggs".to_string(), Some(24.0))]);
+    }
+
+    #[tokio::test]
+    async fn shopping_text_keeps_written_units_until_merged_across_units() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+        let add = |text: &'static str| {
+            let app = app.clone();
+            let token = token.clone();
+            async move {
+                let resp = app
+                    .oneshot(auth_json(
+                        "POST",
+                        "/shopping",
+                        &token,
+                        &json!({"text": text}),
+                    ))
+                    .await
+                    .unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                json_body(resp.into_body()).await
+            }
+        };
+        let stored = |name: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_as::<_, (Option<String>, Option<f64>)>(
+                    "SELECT unit, quantity FROM shopping_items WHERE name = ?",
+                )
+                .bind(name)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+            }
+        };
+
+        assert_eq!(add("2 tbsp olive oil").await["text"], "2 tbsp olive oil");
+        assert_eq!(add("1 tbsp olive oil").await["text"], "3 tbsp olive oil");
+        assert_eq!(stored("olive oil").await, (Some("tbsp".into()), Some(3.0)));
+
+        assert_eq!(add("2 cups milk").await["text"], "2 cup milk");
+        let milk = add("1 cup milk").await;
+        assert_eq!(milk["text"], "3 cup milk");
+        assert_eq!(stored("milk").await, (Some("ml".into()), Some(720.0)));
+
+        let id = add("1 dl cream").await["id"].as_i64().unwrap();
+        let patch = |body: Value| {
+            let app = app.clone();
+            let token = token.clone();
+            async move {
+                let resp = app
+                    .oneshot(auth_json(
+                        "PATCH",
+                        &format!("/shopping/{id}"),
+                        &token,
+                        &body,
+                    ))
+                    .await
+                    .unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                json_body(resp.into_body()).await["text"].clone()
+            }
+        };
+        assert_eq!(patch(json!({"text": "2 dl cream"})).await, "2 dl cream");
+        assert_eq!(stored("cream").await, (Some("ml".into()), Some(200.0)));
+        assert_eq!(patch(json!({"quantity": 250})).await, "250 ml cream");
+        assert_eq!(patch(json!({"text": "1 cup cream"})).await, "1 cup cream");
+        // Merging into "3 cup milk" through a rename keeps the cups.
+        let merged = patch(json!({"text": "1 cup milk"})).await;
+        assert_eq!(merged, "4 cup milk");
+        assert_eq!(stored("milk").await, (Some("ml".into()), Some(960.0)));
+        // Written in another unit: back to the stored amount.
+        assert_eq!(add("100 ml milk").await["text"], "1060 ml milk");
+    }
+
+    // ── categories ───────────────────────────────────────────────────────────
+
+    async fn classify(app: &axum::Router, token: &str, name: &str) -> Value {
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                &format!("/categories/classify?name={}", name.replace(' ', "%20")),
+                token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body()).await
+    }
+
+    #[tokio::test]
+    async fn categories_classify_lookup_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let v = classify(&app, &token, "Zucchini").await;
+        assert_eq!(v, json!({"category": "Vegetables", "source": "builtin"}));
+
+        // No LLM configured in tests, so unknown names fall back to Other.
+        let v = classify(&app, &token, "mystery widget").await;
+        assert_eq!(v, json!({"category": "Other", "source": "fallback"}));
+
+        // User keywords win over the built-in map.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/categories/Online/keywords",
+                &token,
+                &json!({"keyword": "  Oat-Milk "}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await, json!(["oat milk"]));
+        let v = classify(&app, &token, "oat milk").await;
+        assert_eq!(v, json!({"category": "Online", "source": "user"}));
+
+        // An item already on the list wins over keywords.
+        let id = add_shopping(&app, &token, "oat milk").await;
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{id}"),
+                &token,
+                &json!({"category": "Drinks"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let v = classify(&app, &token, "Oat  Milk").await;
+        assert_eq!(v, json!({"category": "Drinks", "source": "cache"}));
+
+        // Creating an item without a category uses the same lookup.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping",
+                &token,
+                &json!({"text": "2 tofu blocks"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["category"], "Vegan");
+    }
+
+    #[tokio::test]
+    async fn categories_keyword_endpoints() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let add = |cat: &str, kw: &str| {
+            auth_json(
+                "POST",
+                &format!("/categories/{cat}/keywords"),
+                &token,
+                &json!({"keyword": kw}),
+            )
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(add("Pantry", "jackfruit"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .clone()
+            .oneshot(add("Canned", "Jackfruit"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let resp = app.clone().oneshot(add("Pantry", " - ")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app.clone().oneshot(add("Nope", "x")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/categories/Pantry/keywords", &token))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await, json!(["jackfruit"]));
+
+        let del = || {
+            Request::builder()
+                .method("DELETE")
+                .uri("/categories/Pantry/keywords/jackfruit")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(del()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await, json!([]));
+        let resp = app.clone().oneshot(del()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/categories/classify?name=", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// "Category: text" for each item on the list, in list order.
+    async fn categorized_texts(app: &axum::Router, token: &str) -> Vec<String> {
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", token))
+            .await
+            .unwrap();
+        json_body(resp.into_body())
+            .await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| {
+                format!(
+                    "{}: {}",
+                    i["category"].as_str().unwrap(),
+                    i["text"].as_str().unwrap()
+                )
+            })
+            .collect()
+    }
+
+    async fn set_item_category(
+        app: &axum::Router,
+        token: &str,
+        id: &Value,
+        category: &str,
+    ) -> StatusCode {
+        app.clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{id}"),
+                token,
+                &json!({"category": category}),
+            ))
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn categories_custom_crud_cascades_to_items_and_list_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/categories",
+                &token,
+                &json!({"name": "Butcher"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let butcher = json_body(resp.into_body()).await;
+        assert_eq!(butcher["sort_order"], 14);
+        let butcher_id = butcher["id"].as_i64().unwrap();
+
+        // A new category is usable right away, and sorts after the seeded ones.
+        for (text, category) in [
+            ("sausages", "Butcher"),
+            ("bread", "Bakery"),
+            ("apples", "Fruits"),
+        ] {
+            let id = add_shopping_full(&app, &token, text).await["id"].clone();
+            assert_eq!(
+                set_item_category(&app, &token, &id, category).await,
+                StatusCode::OK
+            );
+        }
+        assert_eq!(
+            categorized_texts(&app, &token).await,
+            ["Fruits: apples", "Bakery: bread", "Butcher: sausages"]
+        );
+
+        let fruits_id = category_id(&app, &token, "Fruits").await;
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/categories/reorder",
+                &token,
+                &json!({"order": [butcher_id, fruits_id]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            categorized_texts(&app, &token).await,
+            ["Butcher: sausages", "Fruits: apples", "Bakery: bread"]
+        );
+
+        // Renaming carries the items along and keeps teaching the classifier.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/categories/{butcher_id}"),
+                &token,
+                &json!({"name": "Meat"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            categorized_texts(&app, &token).await,
+            ["Meat: sausages", "Fruits: apples", "Bakery: bread"]
+        );
+        assert_eq!(
+            classify(&app, &token, "sausages").await,
+            json!({"category": "Meat", "source": "cache"})
+        );
+        let chops = add_shopping_full(&app, &token, "chops").await["id"].clone();
+        assert_eq!(
+            set_item_category(&app, &token, &chops, "Butcher").await,
+            StatusCode::BAD_REQUEST
+        );
+
+        // Deleting moves the items to "Other", which cannot be deleted.
+        let delete = |id: i64| {
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/categories/{id}"))
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let resp = app.clone().oneshot(delete(butcher_id)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await,
+            json!({"deleted": true, "moved_items": 1})
+        );
+        assert_eq!(
+            categorized_texts(&app, &token).await,
+            [
+                "Other: sausages",
+                "Other: chops",
+                "Fruits: apples",
+                "Bakery: bread"
+            ]
+        );
+        let other_id = category_id(&app, &token, "Other").await;
+        let resp = app.clone().oneshot(delete(other_id)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn categories_other_cannot_be_renamed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let other_id = category_id(&app, &token, "Other").await;
+
+        // Deleted categories hand their items to "Other" by name.
+        let rename_other =
+            |body: Value| auth_json("PATCH", &format!("/categories/{other_id}"), &token, &body);
+        let resp = app
+            .clone()
+            .oneshot(rename_other(json!({"name": "Misc"})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let resp = app
+            .clone()
+            .oneshot(rename_other(json!({"name": "Other", "sort_order": 0})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(category_id(&app, &token, "Other").await, other_id);
+    }
+
+    // ── meal plan ────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn meal_plan_conflicts_return_existing_entry() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        sqlx::query(
+            r#"INSERT INTO recipes (id, title, "yield", ingredients, instructions)
+               VALUES (5, 'Curry', '', '[]', '[]')"#,
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let assign = |day: &str| {
+            auth_json(
+                "POST",
+                "/meal-plan",
+                &token,
+                &json!({"day": day, "recipe_id": 5}),
+            )
+        };
+        let resp = app.clone().oneshot(assign("2030-03-01")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let first = json_body(resp.into_body()).await;
+
+        let resp = app.clone().oneshot(assign("2030-03-01")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["error"]["code"], "already_assigned");
+        assert_eq!(body["error"]["existing"], first);
+        assert_eq!(body["error"]["existing"]["title"], "Curry");
+
+        // Moving onto a day that already has the recipe reports the same entry.
+        let resp = app.clone().oneshot(assign("2030-03-02")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/meal-plan/2030-03-02/5",
+                &token,
+                &json!({"new_day": "2030-03-01"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["error"]["code"], "already_assigned");
+        assert_eq!(body["error"]["existing"]["id"], first["id"]);
+    }
+
+    #[tokio::test]
+    async fn meal_plan_same_recipe_for_lunch_and_dinner() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        sqlx::query(
+            r#"INSERT INTO recipes (id, title, "yield", ingredients, instructions)
+               VALUES (5, 'Curry', '', '[]', '[]')"#,
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        for body in [
+            json!({"day": "2030-03-01", "recipe_id": 5}),
+            json!({"day": "2030-03-01", "recipe_id": 5, "meal_type": "lunch"}),
+        ] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json("POST", "/meal-plan", &token, &body))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/meal-plan",
+                &token,
+                &json!({"day": "2030-03-01", "recipe_id": 5, "meal_type": "brunch"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let meals = |uri: &'static str| {
+            let app = app.clone();
+            let token = token.clone();
+            async move {
+                let resp = app.oneshot(auth_get(uri, &token)).await.unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                json_body(resp.into_body())
+                    .await
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|e| e["meal_type"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            meals("/meal-plan?day=2030-03-01").await,
+            ["dinner", "lunch"]
+        );
+        assert_eq!(
+            meals("/meal-plan?day=2030-03-01&meal_type=lunch").await,
+            ["lunch"]
+        );
+
+        // Without ?meal_type= the dinner entry is the one addressed.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/meal-plan/2030-03-01/5?meal_type=lunch",
+                &token,
+                &json!({"new_day": "2030-03-02"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["meal_type"], "lunch");
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/meal-plan/2030-03-01/5")
+                    .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["deleted"], 1);
+        assert!(meals("/meal-plan?day=2030-03-01").await.is_empty());
+        assert_eq!(meals("/meal-plan?day=2030-03-02").await, ["lunch"]);
+    }
+
+    #[tokio::test]
+    async fn meal_plan_day_include_recipe_embeds_details() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let macros =
+            json!({"basis": "per_serving", "protein_g": 20.0, "fat_g": 10.0, "carbs_g": 50.0});
+        for i in 1..=7_i64 {
+            // Recipe 4 has corrupt macros JSON; it must not break the day.
+            let macros_text = if i == 4 {
+                "{not json".to_string()
+            } else {
+                macros.to_string()
+            };
+            sqlx::query(
+                r#"INSERT INTO recipes (id, title, "yield", servings, ingredients, instructions, macros, image_path_small)
+                   VALUES (?, ?, '4 servings', 4, '[]', '[]', ?, ?)"#,
+            )
+            .bind(i)
+            .bind(format!("Recipe {i}"))
+            .bind(macros_text)
+            .bind(format!("recipes/{i}/small.webp"))
+            .execute(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO meal_plan (day, recipe_id, title) VALUES ('2026-01-05', ?, ?)",
+            )
+            .bind(i)
+            .bind(format!("Recipe {i}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/meal-plan?day=2026-01-05&include=recipe", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let entries = json_body(resp.into_body()).await;
+        let entries = entries.as_array().unwrap();
+        assert_eq!(entries.len(), 7);
+        for (i, e) in entries.iter().enumerate() {
+            let n = i + 1;
+            assert_eq!(e["recipe_id"], n);
+            assert_eq!(e["image_path_small"], format!("recipes/{n}/small.webp"));
+            assert_eq!(e["yield"], "4 servings");
+            assert_eq!(e["servings"], 4.0);
+            if n == 4 {
+                assert!(e["macros"].is_null());
+            } else {
+                assert_eq!(e["macros"]["protein_g"], 20.0);
+            }
+        }
+
+        // Default shape stays lean
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/meal-plan?day=2026-01-05", &token))
+            .await
+            .unwrap();
+        let entries = json_body(resp.into_body()).await;
+        assert_eq!(entries.as_array().unwrap().len(), 7);
+        assert!(entries[0].get("macros").is_none());
+
+        let resp = app
+            .oneshot(auth_get("/meal-plan?day=2026-01-05&include=bogus", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// SQL statements sqlx ran under each [`statements_run_by`] span. sqlx
+    /// logs from its worker thread, so this needs a process-wide subscriber.
+    static STATEMENTS: std::sync::LazyLock<
+        std::sync::Mutex<std::collections::HashMap<tracing::span::Id, Vec<String>>>,
+    > = std::sync::LazyLock::new(Default::default);
+
+    struct StatementLog;
+
+    impl<S> tracing_subscriber::Layer<S> for StatementLog
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            struct Fields(String);
+            impl tracing::field::Visit for Fields {
+                fn record_debug(&mut self, _: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                    use std::fmt::Write as _;
+                    let _ = write!(self.0, "{value:?} ");
+                }
+            }
+
+            if event.metadata().target() != "sqlx::query" {
+                return;
+            }
+            let Some(root) = ctx.event_scope(event).and_then(|s| s.from_root().next()) else {
+                return;
+            };
+            if root.name() != "statements_run_by" {
+                return;
+            }
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            STATEMENTS
+                .lock()
+                .unwrap()
+                .entry(root.id())
+                .or_default()
+                .push(fields.0);
+        }
+    }
+
+    /// Await `fut` and return its output with the SQL statements it ran.
+    async fn statements_run_by<F: std::future::Future>(fut: F) -> (F::Output, Vec<String>) {
+        use tracing::Instrument;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            tracing::subscriber::set_global_default(
+                tracing_subscriber::registry().with(StatementLog),
+            )
+            .expect("no other global subscriber in tests");
+        });
+
+        let span = tracing::info_span!("statements_run_by");
+        let id = span.id().expect("span enabled");
+        let out = fut.instrument(span.clone()).await;
+        // `span` is still alive, so its id cannot have been reused.
+        let statements = STATEMENTS.lock().unwrap().remove(&id).unwrap_or_default();
+        (out, statements)
+    }
+
+    #[tokio::test]
+    async fn meal_plan_include_recipe_runs_one_query_for_any_number_of_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        for i in 1..=8_i64 {
+            sqlx::query(
+                r#"INSERT INTO recipes (id, title, "yield", ingredients, instructions, macros)
+                   VALUES (?, ?, '2 servings', '[]', '[]', '{"protein_g": 5.0}')"#,
+            )
+            .bind(i)
+            .bind(format!("Recipe {i}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+            // Seven entries on Monday, one on Tuesday.
+            let day = if i <= 7 { "2026-01-05" } else { "2026-01-06" };
+            sqlx::query("INSERT INTO meal_plan (day, recipe_id, title) VALUES (?, ?, ?)")
+                .bind(day)
+                .bind(i)
+                .bind(format!("Recipe {i}"))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let mut counts = Vec::new();
+        for (query, entries) in [
+            ("day=2026-01-05", 7),
+            ("day=2026-01-06", 1),
+            ("from=2026-01-05&to=2026-01-11", 8),
+        ] {
+            let uri = format!("/meal-plan?{query}&include=recipe");
+            let (resp, mut statements) =
+                statements_run_by(app.clone().oneshot(auth_get(&uri, &token))).await;
+            let resp = resp.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body = json_body(resp.into_body()).await;
+            assert_eq!(body.as_array().unwrap().len(), entries, "{query}");
+            // The pool may open a connection mid-request; its setup is noise.
+            statements.retain(|s| !s.contains("PRAGMA"));
+            assert_eq!(
+                statements
+                    .iter()
+                    .filter(|s| s.contains("meal_plan"))
+                    .count(),
+                1,
+                "{query}: {statements:#?}"
+            );
+            counts.push(statements.len());
+        }
+        // Anything else (the auth lookup) does not grow with the entries.
+        assert!(counts.iter().all(|&n| n == counts[0]), "{counts:?}");
+    }
+
+    #[tokio::test]
+    async fn meal_plan_nutrition_sums_days_and_lists_unestimated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let per_serving = json!({"basis": "per_serving", "protein_g": 20.0, "fat_g": 10.0, "carbs_g": 50.0, "kcal": 370.0});
+        let per_recipe = json!({"basis": "per_recipe", "protein_g": 60.0, "fat_g": 30.0, "carbs_g": 100.0, "kcal": 910.0});
+        for (id, macros) in [
+            (1_i64, Some(per_serving.to_string())),
+            (2, Some(per_recipe.to_string())),
+            (3, None),
+        ] {
+            sqlx::query(
+                r"INSERT INTO recipes (id, title, ingredients, instructions, macros)
+                   VALUES (?, ?, '[]', '[]', ?)",
+            )
+            .bind(id)
+            .bind(format!("Recipe {id}"))
+            .bind(macros)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        for (day, recipe_id, meal_type) in [
+            ("2026-01-05", 1, "lunch"),
+            ("2026-01-05", 2, "dinner"),
+            ("2026-01-06", 1, "dinner"),
+            ("2026-01-06", 3, "lunch"),
+        ] {
+            sqlx::query(
+                "INSERT INTO meal_plan (day, recipe_id, title, meal_type) VALUES (?, ?, '', ?)",
+            )
+            .bind(day)
+            .bind(recipe_id)
+            .bind(meal_type)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                "/meal-plan/nutrition?from=2026-01-05&to=2026-01-11",
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        let days = body["days"].as_array().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0]["day"], "2026-01-05");
+        assert_eq!(days[0]["protein_g"], 80.0);
+        assert_eq!(days[0]["kcal"], 1280.0);
+        assert_eq!(days[0]["entries"][0]["per_recipe"], false);
+        assert_eq!(days[0]["entries"][1]["per_recipe"], true);
+        assert_eq!(days[1]["carbs_g"], 50.0);
+        assert_eq!(days[1]["entries"].as_array().unwrap().len(), 1);
+        assert_eq!(body["totals"]["protein_g"], 100.0);
+        assert_eq!(body["totals"]["fat_g"], 50.0);
+        assert_eq!(body["unestimated"][0]["recipe_id"], 3);
+        assert_eq!(body["unestimated"][0]["day"], "2026-01-06");
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                "/meal-plan/nutrition?from=2026-01-05&to=2026-01-11&meal_type=lunch",
+                &token,
+            ))
+            .await
+            .unwrap();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["totals"]["protein_g"], 20.0);
+
+        let resp = app
+            .oneshot(auth_get("/meal-plan/nutrition?from=2026-01-05", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn meal_plan_range_returns_days_in_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        for i in 1..=3_i64 {
+            sqlx::query(
+                r#"INSERT INTO recipes (id, title, "yield", ingredients, instructions)
+                   VALUES (?, ?, '2 servings', '[]', '[]')"#,
+            )
+            .bind(i)
+            .bind(format!("Recipe {i}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        // Inserted out of day order; the last one is outside the range.
+        for (day, recipe_id) in [
+            ("2026-01-07", 1),
+            ("2026-01-05", 2),
+            ("2026-01-07", 3),
+            ("2026-01-05", 1),
+            ("2026-01-11", 2),
+            ("2026-01-12", 3),
+        ] {
+            sqlx::query("INSERT INTO meal_plan (day, recipe_id, title) VALUES (?, ?, '')")
+                .bind(day)
+                .bind(recipe_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        for include in ["", "&include=recipe"] {
+            let uri = format!("/meal-plan?from=2026-01-05&to=2026-01-11{include}");
+            let resp = app.clone().oneshot(auth_get(&uri, &token)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            let entries = json_body(resp.into_body()).await;
+            let got: Vec<(String, i64)> = entries
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| {
+                    (
+                        e["day"].as_str().unwrap().to_string(),
+                        e["recipe_id"].as_i64().unwrap(),
+                    )
+                })
+                .collect();
+            let expected = [
+                ("2026-01-05", 2),
+                ("2026-01-05", 1),
+                ("2026-01-07", 1),
+                ("2026-01-07", 3),
+                ("2026-01-11", 2),
+            ];
+            let expected: Vec<(String, i64)> = expected
+                .iter()
+                .map(|(d, r)| ((*d).to_string(), *r))
+                .collect();
+            assert_eq!(got, expected, "{uri}");
+        }
+
+        // A one-day range matches ?day=.
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/meal-plan?from=2026-01-12&to=2026-01-12", &token))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await[0]["recipe_id"], 3);
+
+        for bad in [
+            "/meal-plan?from=2026-01-07&to=2026-01-05",
+            "/meal-plan?from=2026-01-05&to=not-a-day",
+            "/meal-plan?from=2026-01-01&to=2026-03-04",
+            "/meal-plan?from=2026-01-05",
+            "/meal-plan?day=2026-01-05&from=2026-01-05&to=2026-01-06",
+            "/meal-plan",
+        ] {
+            let resp = app.clone().oneshot(auth_get(bad, &token)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+        }
+        // 62 days, inclusive, is the longest range served.
+        let resp = app
+            .oneshot(auth_get("/meal-plan?from=2026-01-01&to=2026-03-03", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn meal_plan_autofill_avoids_recent_and_repeats() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        for i in 1..=4_i64 {
+            sqlx::query(
+                r"INSERT INTO recipes (id, title, ingredients, instructions)
+                   VALUES (?, ?, '[]', '[]')",
+            )
+            .bind(i)
+            .bind(format!("Recipe {i}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        // Recipe 1 was planned recently; 2026-01-06 already has a dinner.
+        for (day, recipe_id) in [("2026-01-01", 1), ("2026-01-06", 2)] {
+            sqlx::query("INSERT INTO meal_plan (day, recipe_id, title) VALUES (?, ?, '')")
+                .bind(day)
+                .bind(recipe_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let fill = |seed: u64| {
+            auth_json(
+                "POST",
+                "/meal-plan/autofill",
+                &token,
+                &json!({"from": "2026-01-05", "days": 4, "avoid_recent_days": 7, "seed": seed}),
+            )
+        };
+        let picks = |body: &Value| -> Vec<(String, i64)> {
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|e| {
+                    (
+                        e["day"].as_str().unwrap().to_string(),
+                        e["recipe_id"].as_i64().unwrap(),
+                    )
+                })
+                .collect()
+        };
+
+        let resp = app.clone().oneshot(fill(7)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let first = picks(&json_body(resp.into_body()).await);
+        let days: Vec<&str> = first.iter().map(|(d, _)| d.as_str()).collect();
+        assert_eq!(days, ["2026-01-05", "2026-01-07", "2026-01-08"]);
+        // Fresh recipes first; the recent one only once they run out.
+        let mut early: Vec<i64> = first[..2].iter().map(|(_, r)| *r).collect();
+        early.sort_unstable();
+        assert_eq!(early, [3, 4]);
+        assert_eq!(first[2].1, 1);
+
+        // Nothing left to fill.
+        let resp = app.clone().oneshot(fill(7)).await.unwrap();
+        assert_eq!(json_body(resp.into_body()).await, json!([]));
+
+        // The same seed gives the same plan.
+        sqlx::query("DELETE FROM meal_plan WHERE day <> '2026-01-01' AND day <> '2026-01-06'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let resp = app.clone().oneshot(fill(7)).await.unwrap();
+        assert_eq!(picks(&json_body(resp.into_body()).await), first);
+
+        for bad in [
+            json!({"from": "05/01/2026"}),
+            json!({"from": "2026-01-05", "days": 0}),
+            json!({"from": "2026-01-05", "days": 63}),
+        ] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json("POST", "/meal-plan/autofill", &token, &bad))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn meal_plan_export_renders_week() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Soup", "yield": "4 servings", "ingredients": [], "instructions": []}),
+            ))
+            .await
+            .unwrap();
+        let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+        app.clone()
+            .oneshot(auth_json(
+                "POST",
+                "/meal-plan",
+                &token,
+                &json!({"day": "2026-01-06", "recipe_id": id}),
+            ))
+            .await
+            .unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/meal-plan/export?week_start=2026-01-05", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            resp.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/markdown")
+        );
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let md = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(md.contains("| Tue 2026-01-06 | Soup | 4 servings |"));
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                "/meal-plan/export?week_start=2026-01-05&format=html",
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(
+            resp.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/html")
+        );
+
+        let resp = app
+            .oneshot(auth_get("/meal-plan/export?format=pdf", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn meal_plan_export_defaults_to_this_week_in_the_user_timezone() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let token = make_token();
+        let app = crate::app::build_app(state);
+
+        for bad in [
+            json!({"week_start": "Funday"}),
+            json!({"week_start": ""}),
+            json!({"timezone": "Mars/Olympus_Mons"}),
+        ] {
+            let resp = app
+                .clone()
+                .oneshot(auth_json(
+                    "PATCH",
+                    "/settings",
+                    &token,
+                    &json!({"settings": bad}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{bad}");
+        }
+
+        // UTC+14 is a different calendar day from the server for most of
+        // the day, so the default week must come from the setting.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"week_start": "Sun", "timezone": "Pacific/Kiritimati"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let today = chrono::Utc::now()
+            .with_timezone(&chrono_tz::Pacific::Kiritimati)
+            .date_naive();
+        let start = today.week(chrono::Weekday::Sun).first_day();
+        let resp = app
+            .oneshot(auth_get("/meal-plan/export", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let md = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(
+            md.starts_with(&format!("# Meal plan {}", start.format("%Y-%m-%d"))),
+            "{md}"
+        );
+    }
+
+    // ── compression ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn recipes_list_is_gzip_encoded_when_requested() {
+        use std::io::Read;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        for i in 0..10 {
+            let recipe = json!({
+                "title": format!("Recipe {i}"),
+                "notes": "A reasonably long note so the list is worth compressing. ".repeat(4),
+                "ingredients": [],
+                "instructions": ["Step one", "Step two"]
+            });
+            app.clone()
+                .oneshot(auth_json("POST", "/recipes", &token, &recipe))
+                .await
+                .unwrap();
+        }
+
+        let plain = app
+            .clone()
+            .oneshot(auth_get("/recipes", &token))
+            .await
+            .unwrap();
+        assert!(plain.headers().get(header::CONTENT_ENCODING).is_none());
+        let plain_json = json_body(plain.into_body()).await;
+
+        let req = Request::get("/recipes")
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_ENCODING], "gzip");
+
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&bytes[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        let gz_json: Value = serde_json::from_str(&decoded).unwrap();
+        assert_eq!(gz_json, plain_json);
+    }
+
+    #[tokio::test]
+    async fn cors_echoes_configured_origins_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.cors_origins = vec![
+            "https://blaz.example.com".to_string(),
+            "http://localhost:8080".to_string(),
+        ];
+        let app = crate::app::build_app(state);
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri("/recipes")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(preflight("https://blaz.example.com"))
+            .await
+            .unwrap();
+        let h = resp.headers();
+        assert_eq!(
+            h[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://blaz.example.com"
+        );
+        assert_eq!(h[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(h[header::ACCESS_CONTROL_ALLOW_METHODS], "POST");
+
+        let resp = app
+            .oneshot(preflight("https://evil.example.com"))
+            .await
+            .unwrap();
+        assert!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn small_responses_are_not_compressed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+
+        let req = Request::get("/healthz")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
+    // ── media health ─────────────────────────────────────────────────────────
+
+    fn image_upload_request(uri: &str, token: &str) -> Request<Body> {
+        image_upload_request_with(uri, token, b"not-an-image")
+    }
+
+    fn image_upload_request_with(uri: &str, token: &str, image: &[u8]) -> Request<Body> {
+        let boundary = "blazboundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"a.png\"\r\n\
+             Content-Type: image/png\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(image);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {token}"))
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn backup_snapshots_the_database_and_prunes_old_ones() {
+        let tmp = tempfile::tempdir().unwrap();
+        // A file in WAL mode, like the real thing.
+        let mut state = make_test_state(&tmp).await;
+        state.pool = crate::db::make_pool(tmp.path().join("live.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/admin/backup/latest", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        create_recipe(&app, &token, json!({"title": "Backed Up Soup"})).await;
+        let backup = |app: axum::Router| {
+            let req = auth_json("POST", "/admin/backup", &token, &json!({}));
+            async move {
+                let resp = app.oneshot(req).await.unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                json_body(resp.into_body()).await
+            }
+        };
+        let first = backup(app.clone()).await;
+        assert!(first["name"].as_str().unwrap().starts_with("blaz-"));
+        assert!(first["size_bytes"].as_u64().unwrap() > 0);
+        assert_eq!(first["pruned"], json!([]));
+
+        // The snapshot is a complete database of its own.
+        let path = first["path"].as_str().unwrap();
+        let snapshot = sqlx::SqlitePool::connect(&format!("sqlite://{path}"))
+            .await
+            .unwrap();
+        let titles: Vec<String> = sqlx::query_scalar("SELECT title FROM recipes")
+            .fetch_all(&snapshot)
+            .await
+            .unwrap();
+        assert_eq!(titles, ["Backed Up Soup"]);
+        snapshot.close().await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"backup_retention": "2"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        backup(app.clone()).await;
+        let third = backup(app.clone()).await;
+        assert_eq!(third["pruned"], json!([first["name"]]));
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/admin/backups", &token))
+            .await
+            .unwrap();
+        let listed = json_body(resp.into_body()).await;
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+        assert_eq!(listed[0]["name"], third["name"]);
+
+        let resp = app
+            .oneshot(auth_get("/admin/backup/latest", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let disposition = resp.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(disposition.contains(third["name"].as_str().unwrap()));
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(bytes.starts_with(b"SQLite format 3\0"));
+    }
+
+    #[tokio::test]
+    async fn db_check_reports_and_repair_fixes_corrupted_rows() {
+        let tmp = tempfile::tempdir().unwrap();
+        // On a file: `PRAGMA integrity_check` can wait forever on the table
+        // locks of the shared-cache in-memory database.
+        let mut state = make_test_state(&tmp).await;
+        state.pool = crate::db::make_pool(tmp.path().join("live.sqlite").display().to_string())
+            .await
+            .unwrap();
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        create_recipe(&app, &token, json!({"title": "Healthy"})).await;
+        let insert = |title: &str, ingredients: &str, instructions: &str, macros: Option<&str>| {
+            sqlx::query(
+                "INSERT INTO recipes (title, ingredients, instructions, macros) VALUES (?, ?, ?, ?)",
+            )
+            .bind(title.to_string())
+            .bind(ingredients.to_string())
+            .bind(instructions.to_string())
+            .bind(macros.map(str::to_string))
+        };
+        let lines = insert("Lines", r#"["2 carrots", "200 g flour"]"#, "[]", None)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let broken = insert("Broken", "[]", r#""stir""#, Some(r#"{"kcal": "lots"}"#))
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let hopeless = insert("Hopeless", "42", "[]", None)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        // Orphans only appear with foreign keys off, as in older databases.
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        let orphan = sqlx::query(
+            "INSERT INTO meal_plan (day, recipe_id, title) VALUES ('2025-01-01', 9999, 'Gone')",
+        )
+        .execute(&mut *conn)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/admin/db/check", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let report = json_body(resp.into_body()).await;
+        assert_eq!(report["ok"], false);
+        assert_eq!(report["integrity"], json!(["ok"]));
+        assert_eq!(
+            report["invalid_columns"],
+            json!({
+                "ingredients": [lines, hopeless],
+                "instructions": [broken],
+                "equipment": [],
+                "macros": [broken],
+                "prep_reminders": [],
+            })
+        );
+        assert_eq!(report["orphaned_meal_plan"], json!([orphan]));
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/admin/db/repair", &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let fixed = json_body(resp.into_body()).await;
+        assert_eq!(fixed["ingredients_rewritten"], json!([lines]));
+        assert_eq!(fixed["ingredients_unrepaired"], json!([hopeless]));
+        assert_eq!(fixed["meal_plan_deleted"], json!([orphan]));
+
+        // The rewritten recipe loads again, with parsed ingredients.
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{lines}"), &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let recipe = json_body(resp.into_body()).await;
+        assert_eq!(recipe["ingredients"][1]["name"], "flour");
+        assert_eq!(recipe["ingredients"][1]["quantity"], 200.0);
+
+        let resp = app
+            .oneshot(auth_get("/admin/db/check", &token))
+            .await
+            .unwrap();
+        let report = json_body(resp.into_body()).await;
+        assert_eq!(report["invalid_columns"]["ingredients"], json!([hopeless]));
+        assert_eq!(report["orphaned_meal_plan"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn legacy_string_ingredients_load_as_parsed_ingredients() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let id = sqlx::query(
+            "INSERT INTO recipes (title, ingredients, instructions) \
+             VALUES ('Old soup', ?, '[]')",
+        )
+        .bind(r#"["2 carrots", {"name": "salt"}, "fresh basil"]"#)
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/recipes/{id}"), &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let recipe = json_body(resp.into_body()).await;
+        assert_eq!(recipe["ingredients"][0]["name"], "carrots");
+        assert_eq!(recipe["ingredients"][0]["quantity"], 2.0);
+        assert_eq!(recipe["ingredients"][1]["name"], "salt");
+        assert_eq!(recipe["ingredients"][2]["name"], "fresh basil");
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"title": "Older soup"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = app.oneshot(auth_get("/recipes", &token)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let list = json_body(resp.into_body()).await;
+        let listed = list
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["id"] == id)
+            .unwrap();
+        assert_eq!(listed["title"], "Older soup");
+        assert_eq!(listed["ingredients"][0]["unit"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn malformed_json_gets_structured_422_with_request_id() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let post_raw = |body: Vec<u8>| {
+            Request::builder()
+                .method("POST")
+                .uri("/recipes")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(post_raw(br#"{"title": "Sou"#.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let request_id = resp.headers()["x-request-id"].to_str().unwrap().to_string();
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["code"], "invalid_json");
+        assert_eq!(body["request_id"], request_id);
+        assert!(body["error"].as_str().unwrap().contains("EOF"));
+
+        let resp = app
+            .clone()
+            .oneshot(post_raw(br#"{"title": 5}"#.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(resp.into_body()).await["field_hint"], "title");
+
+        // Updates go through the same extractor.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/recipes/1",
+                &token,
+                &json!({"title": []}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json_body(resp.into_body()).await["field_hint"], "title");
+
+        let mut no_type = post_raw(br#"{"title": "Soup"}"#.to_vec());
+        no_type.headers_mut().remove(header::CONTENT_TYPE);
+        let resp = app.clone().oneshot(no_type).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "unsupported_media_type"
+        );
+
+        let mut big = br#"{"title": ""#.to_vec();
+        big.resize(3 * 1024 * 1024, b'a');
+        big.extend_from_slice(br#""}"#);
+        let resp = app.clone().oneshot(post_raw(big)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(json_body(resp.into_body()).await["code"], "body_too_large");
+
+        // Uploads keep a larger limit: 10 MB reaches the handler.
+        let resp = app
+            .oneshot(image_upload_request_with(
+                "/recipes/1/image",
+                &token,
+                &vec![0u8; 10 * 1024 * 1024],
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "unsupported_image"
+        );
+    }
+
+    #[tokio::test]
+    async fn unavailable_media_dir_degrades_image_endpoints_until_it_recovers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        // A regular file where a parent directory should be: unlike permission
+        // bits, this also blocks root, which the test may run as.
+        let blocker = tmp.path().join("mount");
+        std::fs::write(&blocker, b"").unwrap();
+        let media_dir = blocker.join("media");
+        state.config.media_dir.clone_from(&media_dir);
+
+        assert!(!crate::media_health::wait_for_media(&media_dir, std::time::Duration::ZERO).await);
+        let health = state.media.clone();
+        assert!(!health.refresh(&media_dir).await);
+
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(image_upload_request("/recipes/1/image", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "media_unavailable"
+        );
+
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+        let resp = app
+            .clone()
+            .oneshot(get("/media/recipes/1/full.webp"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "media_unavailable"
+        );
+
+        let resp = app.clone().oneshot(get("/readyz")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await,
+            json!({"status": "degraded", "media": false})
+        );
+
+        // Everything else keeps working.
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/recipes", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The mount appears; the next probe clears the flag.
+        std::fs::remove_file(&blocker).unwrap();
+        assert!(health.refresh(&media_dir).await);
+        assert!(media_dir.is_dir());
+
+        let resp = app.clone().oneshot(get("/readyz")).await.unwrap();
+        assert_eq!(
+            json_body(resp.into_body()).await,
+            json!({"status": "ready", "media": true})
+        );
+        let resp = app
+            .clone()
+            .oneshot(get("/media/recipes/1/full.webp"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp = app
+            .oneshot(image_upload_request("/recipes/1/image", &token))
+            .await
+            .unwrap();
+        assert_ne!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn recipe_image_delete_and_regenerate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let id = create_recipe(&app, &token, json!({"title": "Toast"})).await;
+        let regenerate = format!("/recipes/{id}/image/regenerate");
+        let image = format!("/recipes/{id}/image");
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", &regenerate, &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        assert_eq!(json_body(resp.into_body()).await["code"], "no_image");
+
+        let img = image::RgbImage::from_pixel(40, 30, image::Rgb([200, 120, 0]));
+        let png = crate::share_card::encode_png(&img).unwrap();
+        let resp = app
+            .clone()
+            .oneshot(image_upload_request_with(&image, &token, &png))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let small = tmp.path().join(format!("recipes/{id}/small.webp"));
+        let full = tmp.path().join(format!("recipes/{id}/full.webp"));
+
+        // A stale thumbnail is rebuilt from the full-size image.
+        std::fs::write(&small, b"stale").unwrap();
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", &regenerate, &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["image_path_small"], format!("recipes/{id}/small.webp"));
+        assert!(image::load_from_memory(&std::fs::read(&small).unwrap()).is_ok());
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json("DELETE", &image, &token, &json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert!(body["image_path_full"].is_null());
+        assert!(body["image_path_small"].is_null());
+        assert!(!small.exists() && !full.exists());
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "DELETE",
+                "/recipes/999/image",
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/999/image/regenerate",
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn recipe_image_renditions_are_cached_by_width() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let id = create_recipe(&app, &token, json!({"title": "Toast"})).await;
+        let get = |uri: String| Request::get(uri).body(Body::empty()).unwrap();
+
+        let resp = app
+            .clone()
+            .oneshot(get(format!("/recipes/{id}/image?w=640")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let img = image::RgbImage::from_pixel(800, 400, image::Rgb([200, 120, 0]));
+        let png = crate::share_card::encode_png(&img).unwrap();
+        let resp = app
+            .clone()
+            .oneshot(image_upload_request_with(
+                &format!("/recipes/{id}/image"),
+                &token,
+                &png,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // 700 snaps to 640.
+        let resp = app
+            .clone()
+            .oneshot(get(format!("/recipes/{id}/image?w=700")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "image/webp");
+        assert!(
+            resp.headers()[header::CACHE_CONTROL]
+                .to_str()
+                .unwrap()
+                .contains("max-age")
+        );
+        let etag = resp.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert!(etag.ends_with("-640\""), "{etag}");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let rendition = image::load_from_memory(&body).unwrap();
+        assert_eq!((rendition.width(), rendition.height()), (640, 320));
+        let cached = tmp.path().join(format!("cache/{id}-full-640.webp"));
+        assert!(cached.exists());
+
+        let resp = app
+            .clone()
+            .oneshot(
+                Request::get(format!("/recipes/{id}/image?w=640"))
+                    .header(header::IF_NONE_MATCH, &etag)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        // Later requests are served from disk without re-encoding.
+        std::fs::write(&cached, b"cached").unwrap();
+        let resp = app
+            .oneshot(get(format!("/recipes/{id}/image?w=640")))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"cached");
+    }
+
+    #[tokio::test]
+    async fn image_upload_rejects_oversized_and_non_image_payloads() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.max_image_upload_mb = 1;
+        let app = crate::app::build_app(state);
+        let token = make_token();
+        let id = create_recipe(&app, &token, json!({"title": "Toast"})).await;
+        let uri = format!("/recipes/{id}/image");
+
+        let oversized = vec![0u8; 1024 * 1024 + 1];
+        let resp = app
+            .clone()
+            .oneshot(image_upload_request_with(&uri, &token, &oversized))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(json_body(resp.into_body()).await["code"], "image_too_large");
+
+        let resp = app
+            .clone()
+            .oneshot(image_upload_request_with(&uri, &token, b"just some notes"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "unsupported_image"
+        );
+
+        let resp = app
+            .oneshot(auth_get(&uri.replace("/image", ""), &token))
+            .await
+            .unwrap();
+        assert!(json_body(resp.into_body()).await["image_path_full"].is_null());
+    }
+
+    // ── offline mode ─────────────────────────────────────────────────────────
+
+    async fn make_offline_state(tmp: &tempfile::TempDir) -> crate::models::AppState {
+        let mut state = make_test_state(tmp).await;
+        state.config.offline = true;
+        state.config.llm_api_key = Some("would-be-used-if-online".to_string());
+        state
+    }
+
+    #[tokio::test]
+    async fn offline_import_returns_501_without_network() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_offline_state(&tmp).await);
+        let token = make_token();
+
+        // Unroutable address: if the handler tried to fetch it, we would time out.
+        let req = auth_json(
+            "POST",
+            "/recipes/import",
+            &token,
+            &json!({"url": "http://10.255.255.1/recipe"}),
+        );
+        let resp = tokio::time::timeout(std::time::Duration::from_secs(2), app.oneshot(req))
+            .await
+            .expect("offline import must not wait on the network")
+            .unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NOT_IMPLEMENTED);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["code"], "feature_disabled");
+    }
+
+    #[tokio::test]
+    async fn offline_shopping_create_still_works() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_offline_state(&tmp).await);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping",
+                &token,
+                &json!({"text": "3 apples"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let item = json_body(resp.into_body()).await;
+        assert_eq!(item["category"], "Fruits");
+
+        let resp = app.oneshot(auth_get("/app-state", &token)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["offline"], true);
+    }
+
+    // ── youtube import ───────────────────────────────────────────────────────
+
+    /// OpenAI-compatible stand-in that answers each import stage by its system
+    /// prompt, plus a PNG "thumbnail". Returns the base URL.
+    async fn spawn_mock_llm() -> String {
+        use axum::response::IntoResponse;
+        use axum::routing::{get, post};
+
+        async fn chat(axum::Json(body): axum::Json<Value>) -> axum::response::Response {
+            let system = body["messages"][0]["content"].as_str().unwrap_or_default();
+            let user = body["messages"][1]["content"].as_str().unwrap_or_default();
+            let content = match system {
+                "EXTRACT" if user.contains("200 g spaghetti") => json!({
+                    "title": "Quick Tomato Spaghetti",
+                    "ingredients": ["200 g spaghetti", "400 g canned tomatoes"],
+                    "instructions": ["Boil the pasta.", "Simmer the tomatoes.", "Toss everything together."]
+                }),
+                "IMPORT" => json!({
+                    "title": "Photo Flapjacks",
+                    "servings": 12,
+                    "notes": ["Keeps for a week in a tin.", "Swap honey for golden syrup."],
+                    "ingredients": [{"quantity": 100, "unit": "g", "name": "oats"}],
+                    "instructions": ["Bake."]
+                }),
+                "STRUCTURE" | "CONVERT" => json!([
+                    {"quantity": 200.0, "unit": "g", "name": "spaghetti"},
+                    {"quantity": 400.0, "unit": "g", "name": "canned tomatoes"}
+                ]),
+                s if s.starts_with("You are a strict shopping-item category classifier") => {
+                    json!({"category": "Pantry"})
+                }
+                "MACROS" if user.contains("holiday item") => holiday_macros(user),
+                "MACROS" if user.contains("mystery") => {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "model overloaded").into_response();
+                }
+                "MACROS" => json!({"ingredients": [
+                    {"name": "spaghetti", "protein_g": 26.0, "fat_g": 3.0, "carbs_g": 150.0, "kcal": 740.0, "fiber_g": 8.0, "skip": false},
+                    {"name": "salt", "protein_g": 0.0, "fat_g": 0.0, "carbs_g": 0.0, "skip": true}
+                ]}),
+                _ => return (StatusCode::BAD_REQUEST, "unexpected prompt").into_response(),
+            };
+            axum::Json(json!({
+                "choices": [{"message": {"content": content.to_string()}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 120, "completion_tokens": 30}
+            }))
+            .into_response()
+        }
+
+        /// Echo each "holiday item N" line back with N g protein. A prompt
+        /// that carries instructions also gets a whole-dish entry, which is
+        /// what a model tends to add when it sees the full recipe.
+        fn holiday_macros(user: &str) -> Value {
+            let mut ingredients: Vec<Value> = user
+                .lines()
+                .filter_map(|l| l.split("holiday item ").nth(1))
+                .map(|n| {
+                    let n: f64 = n.trim().parse().unwrap();
+                    json!({"name": format!("holiday item {n}"), "protein_g": n, "fat_g": 1.0, "carbs_g": 2.0, "skip": false})
+                })
+                .collect();
+            if user.contains("INSTRUCTIONS") {
+                ingredients.push(json!({"name": "whole dish", "protein_g": 1000.0, "fat_g": 0.0, "carbs_g": 0.0, "skip": false}));
+            }
+            json!({ "ingredients": ingredients })
+        }
+
+        async fn thumbnail() -> axum::response::Response {
+            let mut png = std::io::Cursor::new(Vec::new());
+            image::RgbImage::new(16, 9)
+                .write_to(&mut png, image::ImageFormat::Png)
+                .unwrap();
+            ([(header::CONTENT_TYPE, "image/png")], png.into_inner()).into_response()
+        }
+
+        let app = axum::Router::new()
+            .route("/chat/completions", post(chat))
+            .route(
+                "/models",
+                get(|| async {
+                    axum::Json(json!({"data": [
+                        {"id": "google/gemini-2.0-flash-001", "name": "Gemini 2.0 Flash"},
+                        {"id": "deepseek/deepseek-chat-v3.1", "name": "DeepSeek V3.1"},
+                        {"id": "openai/o1-pro", "name": "o1-pro"}
+                    ]}))
+                }),
+            )
+            .route("/thumb.png", get(thumbnail));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn llm_cassettes_record_then_replay_macros_without_network() {
+        use crate::llm::CassetteMode;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_macros = "MACROS".into();
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        state.config.llm_cassette_mode = CassetteMode::Record;
+        sqlx::query(
+            r#"INSERT INTO recipes (id, title, "yield", servings, ingredients, instructions)
+               VALUES (3, 'Pasta', '1 serving', 1, ?, '[]')"#,
+        )
+        .bind(
+            json!([
+                {"quantity": 200.0, "unit": "g", "name": "spaghetti", "raw": false},
+                {"name": "salt", "raw": false}
+            ])
+            .to_string(),
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let token = make_token();
+        let estimate = || auth_json("POST", "/recipes/3/macros/estimate", &token, &json!({}));
+
+        let resp = crate::app::build_app(state.clone())
+            .oneshot(estimate())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let recorded = json_body(resp.into_body()).await["macros"].clone();
+        assert_eq!(recorded["protein_g"], 26.0);
+        assert_eq!(recorded["kcal"], 740.0);
+        assert_eq!(recorded["fiber_g"], 8.0);
+        assert_eq!(recorded["per_recipe_totals"]["kcal"], 740.0);
+
+        let cassettes: Vec<_> = std::fs::read_dir(&state.config.llm_cassette_dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(cassettes.len(), 1);
+        let saved: Value =
+            serde_json::from_str(&std::fs::read_to_string(&cassettes[0]).unwrap()).unwrap();
+        assert_eq!(saved["request"]["messages"][0]["content"], "MACROS");
+        assert!(saved["response"]["choices"][0]["message"]["content"].is_string());
+        assert!(!saved.to_string().contains("test-key"));
+
+        // Replay: no key, and an unroutable provider that would hang if used.
+        state.config.llm_cassette_mode = CassetteMode::Replay;
+        state.config.llm_api_key = None;
+        state.config.llm_api_url = "http://10.255.255.1".into();
+        sqlx::query("UPDATE recipes SET macros = NULL")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let app = crate::app::build_app(state.clone());
+        let resp = tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            app.clone().oneshot(estimate()),
+        )
+        .await
+        .expect("replay must not touch the network")
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(json_body(resp.into_body()).await["macros"], recorded);
+
+        // A prompt that was never recorded fails fast with a pointer to the fix.
+        sqlx::query(r#"UPDATE recipes SET ingredients = '[{"name":"rice","raw":false}]'"#)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let resp = tokio::time::timeout(std::time::Duration::from_secs(2), app.oneshot(estimate()))
+            .await
+            .expect("missing cassette must fail fast")
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    /// Insert a recipe with `count` "holiday item N" ingredients (N = 1..=count).
+    async fn insert_holiday_menu(pool: &sqlx::SqlitePool, id: i64, count: usize) {
+        let ingredients: Vec<Value> = (1..=count)
+            .map(|n| json!({"quantity": 100.0, "unit": "g", "name": format!("holiday item {n}"), "raw": false}))
+            .collect();
+        sqlx::query(
+            r#"INSERT INTO recipes (id, title, "yield", servings, ingredients, instructions)
+               VALUES (?, 'Holiday Menu', '4 servings', 4, ?, '["Roast everything.","Serve."]')"#,
+        )
+        .bind(id)
+        .bind(Value::from(ingredients).to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn estimate_holiday_macros(count: usize) -> Value {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_macros = "MACROS".into();
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        insert_holiday_menu(&state.pool, 7, count).await;
+        let pool = state.pool.clone();
+
+        let resp = crate::app::build_app(state)
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/7/macros/estimate",
+                &make_token(),
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let macros = json_body(resp.into_body()).await["macros"].clone();
+
+        let stored: String = sqlx::query_scalar("SELECT macros FROM recipes WHERE id = 7")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&stored).unwrap(), macros);
+        macros
+    }
+
+    #[tokio::test]
+    async fn settings_patch_reaches_the_next_llm_call_without_restart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_macros = "MACROS".into();
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        insert_holiday_menu(&state.pool, 7, 2).await;
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+        let estimate_with = |app: axum::Router| async {
+            let resp = app
+                .oneshot(auth_json(
+                    "POST",
+                    "/recipes/7/macros/estimate",
+                    &token,
+                    &json!({}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            sqlx::query_scalar::<_, String>("SELECT model FROM llm_usage ORDER BY id DESC LIMIT 1")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        assert_eq!(
+            estimate_with(app.clone()).await,
+            "google/gemini-2.0-flash-001"
+        );
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"llm_model": "mock/hot-model"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(estimate_with(app).await, "mock/hot-model");
+    }
+
+    #[tokio::test]
+    async fn recipes_patch_sets_updates_and_clears_macros() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let id = create_recipe(&app, &token, json!({"title": "Rice"})).await;
+        let uri = format!("/recipes/{id}");
+        let patch = |body: Value| app.clone().oneshot(auth_json("PATCH", &uri, &token, &body));
+        let macros = |protein: f64| json!({"basis": "per_serving", "protein_g": protein, "fat_g": 1.0, "carbs_g": 45.0, "kcal": 209.0});
+
+        let resp = patch(json!({"macros": macros(5.0)})).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            json_body(resp.into_body()).await["macros"]["protein_g"],
+            5.0
+        );
+
+        let resp = patch(json!({"macros": macros(6.5)})).await.unwrap();
+        assert_eq!(
+            json_body(resp.into_body()).await["macros"]["protein_g"],
+            6.5
+        );
+
+        // Other edits leave the macros alone.
+        let resp = patch(json!({"notes": "fluffy"})).await.unwrap();
+        assert_eq!(
+            json_body(resp.into_body()).await["macros"]["protein_g"],
+            6.5
+        );
+
+        for bad in [
+            macros(-1.0),
+            json!({"basis": "per_cup", "protein_g": 1.0, "fat_g": 1.0, "carbs_g": 1.0}),
+        ] {
+            let resp = patch(json!({"macros": bad})).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let resp = patch(json!({"macros": null})).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(json_body(resp.into_body()).await["macros"].is_null());
+    }
+
+    #[tokio::test]
+    async fn recipes_with_macros_from_before_kcal_still_load() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        let old = json!({
+            "basis": "per_serving", "protein_g": 20.0, "fat_g": 10.0, "carbs_g": 50.0,
+            "ingredients": [{"name": "rice", "protein_g": 5.0, "fat_g": 1.0, "carbs_g": 45.0, "skipped": false}]
+        });
+        sqlx::query(
+            "INSERT INTO recipes (id, title, ingredients, instructions, macros) VALUES (4, 'Rice', '[]', '[]', ?)",
+        )
+        .bind(old.to_string())
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        let resp = crate::app::build_app(state)
+            .oneshot(auth_get("/recipes/4", &make_token()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let macros = json_body(resp.into_body()).await["macros"].clone();
+        assert_eq!(macros["protein_g"], 20.0);
+        assert_eq!(macros["kcal"], 0.0);
+        assert!(macros.get("per_recipe_totals").is_none());
+    }
+
+    #[tokio::test]
+    async fn macros_estimate_all_runs_in_background_past_failures() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_macros = "MACROS".into();
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        for (id, title, ingredient, macros) in [
+            (11, "Pasta", "spaghetti", None),
+            (12, "Mystery Stew", "mystery meat", None),
+            (13, "More Pasta", "spaghetti", None),
+            (
+                14,
+                "Done Already",
+                "rice",
+                Some(r#"{"basis":"per_recipe","protein_g":1,"fat_g":1,"carbs_g":1}"#),
+            ),
+        ] {
+            sqlx::query(
+                "INSERT INTO recipes (id, title, ingredients, instructions, macros) VALUES (?, ?, ?, '[]', ?)",
+            )
+            .bind(id)
+            .bind(title)
+            .bind(json!([{"quantity": 100.0, "unit": "g", "name": ingredient, "raw": false}]).to_string())
+            .bind(macros)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        }
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+        let start = || auth_json("POST", "/recipes/macros/estimate-all", &token, &json!({}));
+
+        let resp = app.clone().oneshot(start()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+        let status = json_body(resp.into_body()).await;
+        assert_eq!(status["total"], 3);
+        assert_eq!(status["running"], true);
+
+        let resp = app.clone().oneshot(start()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        assert_eq!(json_body(resp.into_body()).await["code"], "job_running");
+
+        let mut status = Value::Null;
+        for _ in 0..100 {
+            let resp = app
+                .clone()
+                .oneshot(auth_get("/recipes/macros/estimate-all/status", &token))
+                .await
+                .unwrap();
+            status = json_body(resp.into_body()).await;
+            if status["running"] == false {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(status["running"], false);
+        assert_eq!(status["done"], 2);
+        assert_eq!(status["failed"], 1);
+        assert_eq!(status["failures"][0]["recipe_id"], 12);
+        assert_eq!(status["failures"][0]["title"], "Mystery Stew");
+
+        let missing: Vec<i64> =
+            sqlx::query_scalar("SELECT id FROM recipes WHERE macros IS NULL ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(missing, [12]);
+    }
+
+    #[tokio::test]
+    async fn macros_estimate_without_api_key_is_a_client_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = make_test_state(&tmp).await;
+        insert_holiday_menu(&state.pool, 7, 3).await;
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/7/macros/estimate",
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("LLM API key not configured"));
+
+        let resp = app.oneshot(auth_get("/recipes/7", &token)).await.unwrap();
+        assert!(json_body(resp.into_body()).await["macros"].is_null());
+    }
+
+    #[tokio::test]
+    async fn llm_usage_is_logged_per_call_and_aggregated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_macros = "MACROS".into();
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        insert_holiday_menu(&state.pool, 7, 3).await;
+        sqlx::query(
+            "INSERT INTO llm_usage (created_at, route, model, prompt_tokens, completion_tokens)
+             VALUES ('2025-01-02 10:00:00', 'recipes.import', 'old-model', 1000, 500)",
+        )
+        .execute(&state.pool)
+        .await
+        .unwrap();
+        let pool = state.pool.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/7/macros/estimate",
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let (route, recipe_id, prompt, completion): (String, Option<i64>, i64, i64) =
+            sqlx::query_as(
+                "SELECT route, recipe_id, prompt_tokens, completion_tokens
+                   FROM llm_usage WHERE route != 'recipes.import'",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(route, "recipes.macros");
+        assert_eq!(recipe_id, Some(7));
+        assert_eq!((prompt, completion), (120, 30));
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/llm/usage", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let report = json_body(resp.into_body()).await;
+        assert_eq!(
+            report["totals"],
+            json!({"calls": 2, "prompt_tokens": 1120, "completion_tokens": 530})
+        );
+        assert_eq!(report["by_day"].as_array().unwrap().len(), 2);
+        assert_eq!(report["by_day"][0]["day"], "2025-01-02");
+        assert_eq!(report["by_route"][1]["route"], "recipes.macros");
+        assert_eq!(report["by_route"][1]["prompt_tokens"], 120);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/llm/usage?from=2025-01-01&to=2025-01-31", &token))
+            .await
+            .unwrap();
+        let report = json_body(resp.into_body()).await;
+        assert_eq!(report["totals"]["calls"], 1);
+        assert_eq!(report["by_route"][0]["route"], "recipes.import");
+
+        let resp = app
+            .oneshot(auth_get("/llm/usage?from=January", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn macros_estimate_chunks_large_recipes_and_sums_groups() {
+        let macros = estimate_holiday_macros(60).await;
+
+        assert_eq!(macros["method"], "chunked");
+        assert_eq!(macros["basis"], "per_serving");
+        let ingredients = macros["ingredients"].as_array().unwrap();
+        assert_eq!(
+            ingredients.len(),
+            60,
+            "no whole-dish entry from instructions"
+        );
+        // Group responses sum to 1+2+…+60 = 1830 g protein, 60 g fat and
+        // 120 g carbs for the recipe, then get divided by the 4 servings.
+        assert_eq!(macros["protein_g"], 457.5);
+        assert_eq!(macros["fat_g"], 15.0);
+        assert_eq!(macros["carbs_g"], 30.0);
+        assert_eq!(ingredients[59]["protein_g"], 15.0);
+        // No kcal from the model: 4/9/4 from the macros, and no fiber at all.
+        assert_eq!(macros["kcal"], 2085.0);
+        assert!(macros.get("fiber_g").is_none());
+        assert_eq!(
+            macros["per_recipe_totals"],
+            json!({"protein_g": 1830.0, "fat_g": 60.0, "carbs_g": 120.0, "kcal": 8340.0})
+        );
+    }
+
+    #[tokio::test]
+    async fn macros_estimate_below_threshold_is_a_single_call() {
+        let macros = estimate_holiday_macros(25).await;
+
+        assert!(macros.get("method").is_none());
+        let ingredients = macros["ingredients"].as_array().unwrap();
+        // One prompt with instructions, answered per serving as-is.
+        assert_eq!(ingredients.len(), 26);
+        assert_eq!(macros["protein_g"], 1325.0);
+    }
+
+    #[tokio::test]
+    async fn youtube_import_extracts_from_description() {
+        use crate::routes::parse_recipe::{ImportFromUrlReq, ImportSource, import_from_source};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let base = spawn_mock_llm().await;
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_extract = "EXTRACT".to_string();
+        state.config.system_prompt_structure = "STRUCTURE".to_string();
+        state.config.system_prompt_convert = "CONVERT".to_string();
+
+        let oembed = json!({
+            "title": "Quick Tomato Spaghetti",
+            "thumbnail_url": format!("{base}/thumb.png"),
+        });
+        let video = crate::youtube::video_from_parts(
+            &oembed,
+            include_str!("../tests/fixtures/youtube_watch.html"),
+        );
+        let Ok(source) = ImportSource::from_youtube(video) else {
+            panic!("fixture description has ingredients");
+        };
+
+        let llm = crate::llm::LlmClient::new(base, "test-key".into(), "mock-model".into());
+        let settings = crate::routes::settings::LlmSettings::load(&state).await;
+        let req = ImportFromUrlReq {
+            url: "https://youtu.be/dQw4w9WgXcQ".to_string(),
+            model: None,
+            dry_run: false,
+            force_llm: false,
+        };
+
+        let recipe = import_from_source(&state, &llm, &settings, &req, source)
+            .await
+            .unwrap()
+            .0;
+
+        assert!(recipe.id > 0);
+        assert_eq!(recipe.title, "Tomato Spaghetti"); // clean_title drops "Quick"
+        assert_eq!(recipe.source, "https://youtu.be/dQw4w9WgXcQ");
+        assert_eq!(recipe.ingredients.len(), 2);
+        assert_eq!(recipe.ingredients[0].name, "spaghetti");
+        assert_eq!(recipe.ingredients[0].unit.as_deref(), Some("g"));
+        assert_eq!(recipe.instructions.len(), 3);
+        assert!(recipe.image_path_full.is_some(), "thumbnail was attached");
+        assert_eq!(recipe.image_import_status.as_deref(), Some("ok"));
+    }
+
+    #[tokio::test]
+    async fn youtube_description_without_ingredients_is_extraction_empty() {
+        use axum::response::IntoResponse;
+
+        let video = crate::youtube::YoutubeVideo {
+            title: "Vlog".to_string(),
+            description: "Thanks for watching!\n0:00 Intro\n2:15 Market haul".to_string(),
+            thumbnail_url: None,
+        };
+        let Err(err) = crate::routes::parse_recipe::ImportSource::from_youtube(video) else {
+            panic!("expected extraction_empty");
+        };
+
+        let resp = err.into_response();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            json_body(resp.into_body()).await["code"],
+            "extraction_empty"
+        );
+    }
+
+    #[tokio::test]
+    async fn photo_import_keeps_first_photo_as_recipe_image() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_import = "IMPORT".to_string();
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        let media_dir = state.config.media_dir.clone();
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let mut jpeg = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(32, 24, image::Rgb([200, 120, 40]))
+            .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+            .unwrap();
+        let resp = app
+            .oneshot(image_upload_request_with(
+                "/recipes/import/images",
+                &token,
+                &jpeg.into_inner(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["title"], "Photo Flapjacks");
+        assert_eq!(body["yield"], "12 servings");
+        assert_eq!(body["servings"], 12.0);
+        assert_eq!(
+            body["notes"],
+            "Keeps for a week in a tin.\nSwap honey for golden syrup."
+        );
+        assert_eq!(body["ingredients"][0]["name"], "oats");
+        let id = body["id"].as_i64().unwrap();
+        assert_eq!(body["image_path_small"], format!("recipes/{id}/small.webp"));
+        assert!(media_dir.join(format!("recipes/{id}/full.webp")).exists());
+    }
+
+    #[tokio::test]
+    async fn text_import_reports_detected_paste_kind() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_extract = "EXTRACT".to_string();
+        state.config.system_prompt_structure = "STRUCTURE".to_string();
+        state.config.system_prompt_convert = "CONVERT".to_string();
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let text = "Spaghetti\n\nIngredients:\n200 g spaghetti\n400 g canned tomatoes\n\nMethod:\nBoil, simmer, toss.";
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/text",
+                &token,
+                &json!({"text": text, "dry_run": true}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["detected"], "recipe_text");
+        assert_eq!(body["title"], "Tomato Spaghetti");
+        assert_eq!(body["ingredients"][0]["name"], "spaghetti");
+        assert_eq!(body["id"], 0);
+
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import/text",
+                &token,
+                &json!({"text": "  \n"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn url_import_uses_complete_json_ld_without_the_llm() {
+        use crate::routes::parse_recipe::{ImportFromUrlReq, ImportSource, import_from_source};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_extract = "EXTRACT".to_string();
+        state.config.system_prompt_structure = "STRUCTURE".to_string();
+        state.config.system_prompt_convert = "CONVERT".to_string();
+        let settings = crate::routes::settings::LlmSettings::load(&state).await;
+        let source = || ImportSource {
+            title_guess: "Quick Tomato Spaghetti".to_string(),
+            text: "200 g spaghetti\n400 g canned tomatoes".to_string(),
+            html: r#"<script type="application/ld+json">{"@graph": [
+                {"@type": "WebSite", "name": "Pasta Blog"},
+                {"@type": "Recipe", "name": "Tomato Spaghetti",
+                 "recipeIngredient": ["200 g spaghetti", "1 cup passata", "salt"],
+                 "recipeInstructions": [
+                    {"@type": "HowToSection", "name": "Pasta", "itemListElement": [
+                        {"@type": "HowToStep", "text": "Boil the pasta."}
+                    ]},
+                    {"@type": "HowToStep", "text": "Toss with the passata."}
+                 ]}
+            ]}</script>"#
+                .to_string(),
+            page_url: None,
+            image_url: None,
+            pasted: None,
+        };
+        let req = |force_llm| ImportFromUrlReq {
+            url: "https://pasta.example/spaghetti".to_string(),
+            model: None,
+            dry_run: true,
+            force_llm,
+        };
+
+        // Nothing listens here: any LLM call would fail the import.
+        let offline = crate::llm::LlmClient::new(
+            "http://127.0.0.1:9".into(),
+            "test-key".into(),
+            "mock-model".into(),
+        )
+        .with_retry(crate::llm::RetryPolicy::NONE);
+        let recipe = import_from_source(&state, &offline, &settings, &req(false), source())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(
+            recipe.import_method,
+            Some(crate::models::ImportMethod::SchemaOrg)
+        );
+        assert_eq!(recipe.title, "Tomato Spaghetti");
+        let ingredients: Vec<_> = recipe
+            .ingredients
+            .iter()
+            .map(|i| (i.quantity, i.unit.as_deref(), i.name.as_str()))
+            .collect();
+        assert_eq!(
+            ingredients,
+            [
+                (Some(200.0), Some("g"), "spaghetti"),
+                (Some(240.0), Some("ml"), "passata"),
+                (None, None, "salt"),
+            ]
+        );
+        assert_eq!(
+            recipe.instructions,
+            ["## Pasta", "Boil the pasta.", "Toss with the passata."]
+        );
+
+        let llm = crate::llm::LlmClient::new(
+            spawn_mock_llm().await,
+            "test-key".into(),
+            "mock-model".into(),
+        );
+        let recipe = import_from_source(&state, &llm, &settings, &req(true), source())
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(recipe.import_method, Some(crate::models::ImportMethod::Llm));
+        assert_eq!(recipe.ingredients.len(), 2, "the mock LLM's answer");
+        assert_eq!(
+            serde_json::to_value(&recipe).unwrap()["import_method"],
+            "llm"
+        );
+    }
+
+    // ── image import status ──────────────────────────────────────────────────
+
+    /// Recipe page whose og:image 404s until the returned flag is set.
+    async fn spawn_flaky_image_site() -> (String, std::sync::Arc<std::sync::atomic::AtomicBool>) {
+        use axum::response::IntoResponse;
+        use axum::routing::get;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let fixed = std::sync::Arc::new(AtomicBool::new(false));
+        let flag = fixed.clone();
+        let app = axum::Router::new()
+            .route(
+                "/recipe",
+                get(|| async {
+                    axum::response::Html(
+                        r#"<html><head><title>Quick Tomato Spaghetti</title>
+                        <meta property="og:image" content="/photo.png"></head>
+                        <body>200 g spaghetti</body></html>"#,
+                    )
+                }),
+            )
+            .route(
+                "/photo.png",
+                get(move || {
+                    let flag = flag.clone();
+                    async move {
+                        if !flag.load(Ordering::SeqCst) {
+                            return StatusCode::NOT_FOUND.into_response();
+                        }
+                        let mut png = std::io::Cursor::new(Vec::new());
+                        image::RgbImage::new(16, 9)
+                            .write_to(&mut png, image::ImageFormat::Png)
+                            .unwrap();
+                        ([(header::CONTENT_TYPE, "image/png")], png.into_inner()).into_response()
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), fixed)
+    }
+
+    #[tokio::test]
+    async fn failed_image_import_is_recorded_and_retryable() {
+        use crate::routes::parse_recipe::{ImportFromUrlReq, ImportSource, import_from_source};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let llm_base = spawn_mock_llm().await;
+        let (site, fixed) = spawn_flaky_image_site().await;
+        let mut state = make_test_state(&tmp).await;
+        state.config.system_prompt_extract = "EXTRACT".to_string();
+        state.config.system_prompt_structure = "STRUCTURE".to_string();
+        state.config.system_prompt_convert = "CONVERT".to_string();
+
+        let page_url = format!("{site}/recipe");
+        let html = reqwest::get(&page_url).await.unwrap().text().await.unwrap();
+        let source = ImportSource {
+            title_guess: "Quick Tomato Spaghetti".to_string(),
+            text: "200 g spaghetti\n400 g canned tomatoes".to_string(),
+            html,
+            page_url: None,
+            image_url: None,
+            pasted: None,
+        };
+        let llm = crate::llm::LlmClient::new(llm_base, "test-key".into(), "mock-model".into());
+        let settings = crate::routes::settings::LlmSettings::load(&state).await;
+        let req = ImportFromUrlReq {
+            url: page_url,
+            model: None,
+            dry_run: false,
+            force_llm: false,
+        };
+
+        let recipe = import_from_source(&state, &llm, &settings, &req, source)
+            .await
+            .expect("an image failure must not fail the import")
+            .0;
+        assert!(recipe.image_path_full.is_none());
+        let status = recipe.image_import_status.unwrap();
+        assert!(status.starts_with("failed: "), "{status}");
+        assert!(status.contains("404"), "{status}");
+
+        fixed.store(true, std::sync::atomic::Ordering::SeqCst);
+        let resp = crate::app::build_app(state)
+            .oneshot(auth_json(
+                "POST",
+                &format!("/recipes/{}/image/retry", recipe.id),
+                &make_token(),
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["image_import_status"], "ok");
+        assert!(body["image_path_full"].is_string());
+        assert!(body["image_path_small"].is_string());
+    }
+
+    #[tokio::test]
+    async fn image_retry_without_source_url_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Toast"}),
+            ))
+            .await
+            .unwrap();
+        let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                &format!("/recipes/{id}/image/retry"),
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(resp.into_body()).await["code"], "no_source");
+    }
+
+    // ── model allow-list ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn import_rejects_model_outside_allow_list() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import",
+                &make_token(),
+                &json!({"url": "http://127.0.0.1:9/recipe", "model": "openai/o1-pro"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["code"], "model_not_allowed");
+        assert_eq!(
+            body["allowed"],
+            json!(["google/gemini-2.0-flash-001", "openai/gpt-4o-mini"])
+        );
+    }
+
+    #[tokio::test]
+    async fn allowed_models_setting_opens_overrides_and_marks_provider_list() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.llm_api_url = spawn_mock_llm().await;
+        state.config.llm_api_key = Some("test-key".into());
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"allowed_models": "[\"deepseek/deepseek-chat-v3.1\"]"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Allowed: the request gets past the check and fails on the fetch instead.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import",
+                &token,
+                &json!({"url": "http://127.0.0.1:9/recipe", "model": "deepseek/deepseek-chat-v3.1"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+
+        let resp = app.oneshot(auth_get("/llm/models", &token)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        let allowed: Vec<(&str, bool)> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| (m["id"].as_str().unwrap(), m["allowed"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(
+            allowed,
+            vec![
+                ("google/gemini-2.0-flash-001", true),
+                ("deepseek/deepseek-chat-v3.1", true),
+                ("openai/o1-pro", false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_allowed_models_setting_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let resp = app
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &make_token(),
+                &json!({"settings": {"allowed_models": "openai/o1-pro"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    // ── import pipeline golden files ─────────────────────────────────────────
+    //
+    // Each case under tests/fixtures/import/<case>/ has:
+    //   page.html    served at /recipe by an in-test site (any other path is an image)
+    //   llm.json     canned answers per pipeline stage, keyed by system prompt,
+    //                plus snippets each stage's prompt must contain
+    //   golden.json  the created recipe with ids, timestamps and ports masked
+    //
+    // After an intended pipeline change, regenerate the goldens with
+    //   BLAZ_UPDATE_GOLDENS=1 cargo test import_golden
+    // and review the diff.
+
+    type Prompts = std::sync::Arc<std::sync::Mutex<Vec<(String, String)>>>;
+
+    fn import_fixture_dir(case: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/import")
+            .join(case)
+    }
+
+    /// Serve `page.html` at `/recipe` and a small PNG at any other path.
+    async fn spawn_fixture_site(page: String) -> String {
+        use axum::response::IntoResponse;
+
+        let app = axum::Router::new().fallback(move |uri: axum::http::Uri| {
+            let page = page.clone();
+            async move {
+                if uri.path() == "/recipe" {
+                    return axum::response::Html(page).into_response();
+                }
+                let mut png = std::io::Cursor::new(Vec::new());
+                image::RgbImage::new(32, 18)
+                    .write_to(&mut png, image::ImageFormat::Png)
+                    .unwrap();
+                ([(header::CONTENT_TYPE, "image/png")], png.into_inner()).into_response()
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    /// LLM answering each stage from `responses` and recording the prompts.
+    async fn spawn_fixture_llm(responses: Value) -> (String, Prompts) {
+        use axum::response::IntoResponse;
+        use axum::routing::post;
+
+        let prompts = Prompts::default();
+        let seen = prompts.clone();
+        let app = axum::Router::new().route(
+            "/chat/completions",
+            post(move |axum::Json(body): axum::Json<Value>| {
+                let responses = responses.clone();
+                let seen = seen.clone();
+                async move {
+                    let system = body["messages"][0]["content"].as_str().unwrap_or_default();
+                    let user = body["messages"][1]["content"].as_str().unwrap_or_default();
+                    seen.lock()
+                        .unwrap()
+                        .push((system.to_string(), user.to_string()));
+                    let Some(content) = responses.get(system) else {
+                        return (StatusCode::BAD_REQUEST, "unexpected prompt").into_response();
+                    };
+                    axum::Json(json!({
+                        "choices": [{"message": {"content": content.to_string()}, "finish_reason": "stop"}]
+                    }))
+                    .into_response()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), prompts)
+    }
+
+    /// Drop ids and timestamps and mask the fixture site's port so the
+    /// created recipe compares stably across runs.
+    fn normalize_imported(mut recipe: Value, site: &str) -> Value {
+        let id = recipe["id"].as_i64().unwrap();
+        let obj = recipe.as_object_mut().unwrap();
+        for key in ["id", "created_at", "updated_at"] {
+            obj.remove(key);
+        }
+        obj["source"] = json!(obj["source"].as_str().unwrap().replace(site, "<site>"));
+        for key in ["image_path_full", "image_path_small"] {
+            if let Some(path) = obj[key].as_str() {
+                obj[key] = json!(path.replace(&format!("recipes/{id}/"), "recipes/<id>/"));
+            }
+        }
+        recipe
+    }
+
+    async fn run_import_golden(case: &str) {
+        let dir = import_fixture_dir(case);
+        let page = std::fs::read_to_string(dir.join("page.html")).unwrap();
+        let fixture: Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("llm.json")).unwrap()).unwrap();
+
+        let site = spawn_fixture_site(page).await;
+        let (llm_base, prompts) = spawn_fixture_llm(fixture["responses"].clone()).await;
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.llm_api_url = llm_base;
+        state.config.llm_api_key = Some("test-key".into());
+        state.config.system_prompt_extract = "EXTRACT".to_string();
+        state.config.system_prompt_structure = "STRUCTURE".to_string();
+        state.config.system_prompt_convert = "CONVERT".to_string();
+
+        let resp = crate::app::build_app(state)
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import",
+                &make_token(),
+                &json!({"url": format!("{site}/recipe")}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "{case}");
+        let got = normalize_imported(json_body(resp.into_body()).await, &site);
+
+        let prompts = prompts.lock().unwrap().clone();
+        if fixture["responses"].as_object().unwrap().is_empty() {
+            let stages = ["EXTRACT", "STRUCTURE", "CONVERT"];
+            assert!(
+                prompts
+                    .iter()
+                    .all(|(system, _)| !stages.contains(&system.as_str())),
+                "{case}: expected no import LLM calls"
+            );
+        }
+        let expected = fixture["prompts_contain"].as_object().unwrap();
+        for (stage, snippets) in expected {
+            let (_, user) = prompts
+                .iter()
+                .find(|(system, _)| system == stage)
+                .unwrap_or_else(|| panic!("{case}: no {stage} call"));
+            for snippet in snippets.as_array().unwrap() {
+                let snippet = snippet.as_str().unwrap();
+                assert!(
+                    user.contains(snippet),
+                    "{case}: {stage} prompt lacks {snippet:?}"
+                );
+            }
+        }
+
+        let golden_path = dir.join("golden.json");
+        if std::env::var_os("BLAZ_UPDATE_GOLDENS").is_some() {
+            let pretty = serde_json::to_string_pretty(&got).unwrap();
+            std::fs::write(&golden_path, pretty + "\n").unwrap();
+            return;
+        }
+        let golden = std::fs::read_to_string(&golden_path).unwrap_or_else(|_| {
+            panic!("{case}: missing golden.json; run with BLAZ_UPDATE_GOLDENS=1")
+        });
+        let want: Value = serde_json::from_str(&golden).unwrap();
+        assert_eq!(
+            got,
+            want,
+            "{case}: import differs from golden.json:\n{}",
+            serde_json::to_string_pretty(&got).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn url_import_refuses_internal_addresses_and_huge_pages() {
+        let site = spawn_fixture_site("x".repeat(6 * 1024 * 1024)).await;
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.llm_api_key = Some("test-key".into());
+        let import = |url: String| {
+            auth_json(
+                "POST",
+                "/recipes/import",
+                &make_token(),
+                &json!({ "url": url }),
+            )
+        };
+
+        let app = crate::app::build_app(state.clone());
+        let resp = app.oneshot(import(format!("{site}/recipe"))).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("larger than 5 MB"));
+
+        state.config.fetch_allow_private = false;
+        let app = crate::app::build_app(state);
+        for url in [
+            format!("{site}/recipe"),
+            "http://169.254.169.254/latest/meta-data/".to_string(),
+            "ftp://example.com/recipe".to_string(),
+        ] {
+            let resp = app.clone().oneshot(import(url.clone())).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{url}");
+            assert_eq!(json_body(resp.into_body()).await["code"], "url_blocked");
+        }
+    }
+
+    #[tokio::test]
+    async fn import_golden_jsonld_site() {
+        run_import_golden("jsonld_site").await;
+    }
+
+    /// A latin-1, gzip-compressed page behind a redirect, whose relative
+    /// image only exists next to the final URL.
+    #[tokio::test]
+    async fn url_import_decodes_latin1_page_and_resolves_image_after_redirect() {
+        use axum::response::IntoResponse;
+        use std::io::Write as _;
+
+        let page = r#"<html><head><title>Crème brûlée</title>
+            <meta property="og:image" content="photo.png">
+            <script type="application/ld+json">{"@type": "Recipe", "name": "Crème brûlée",
+              "recipeIngredient": ["500 ml crème fraîche", "5 egg yolks"],
+              "recipeInstructions": ["Whisk.", "Bake in a bain-marie."]}</script>
+            </head><body><p>Crème brûlée</p></body></html>"#;
+        let (latin1, _, unmappable) = encoding_rs::WINDOWS_1252.encode(page);
+        assert!(!unmappable);
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&latin1).unwrap();
+        let body = gz.finish().unwrap();
+
+        let app = axum::Router::new().fallback(move |uri: axum::http::Uri| {
+            let body = body.clone();
+            async move {
+                match uri.path() {
+                    "/old/recipe" => (
+                        StatusCode::MOVED_PERMANENTLY,
+                        [(header::LOCATION, "/new/recipe")],
+                    )
+                        .into_response(),
+                    "/new/recipe" => (
+                        [
+                            (header::CONTENT_TYPE, "text/html; charset=iso-8859-1"),
+                            (header::CONTENT_ENCODING, "gzip"),
+                        ],
+                        body,
+                    )
+                        .into_response(),
+                    "/new/photo.png" => {
+                        let mut png = std::io::Cursor::new(Vec::new());
+                        image::RgbImage::new(32, 18)
+                            .write_to(&mut png, image::ImageFormat::Png)
+                            .unwrap();
+                        ([(header::CONTENT_TYPE, "image/png")], png.into_inner()).into_response()
+                    }
+                    _ => StatusCode::NOT_FOUND.into_response(),
+                }
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        // Complete schema.org data: any LLM call would fail the import.
+        state.config.llm_api_url = "http://127.0.0.1:9".into();
+        state.config.llm_api_key = Some("test-key".into());
+        let source = format!("http://{addr}/old/recipe");
+        let resp = crate::app::build_app(state)
+            .oneshot(auth_json(
+                "POST",
+                "/recipes/import",
+                &make_token(),
+                &json!({"url": source}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let recipe = json_body(resp.into_body()).await;
+        assert_eq!(recipe["title"], "Crème brûlée");
+        assert_eq!(recipe["ingredients"][0]["name"], "crème fraîche");
+        assert_eq!(recipe["source"], source);
+        assert_eq!(recipe["image_import_status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn import_golden_messy_blog() {
+        run_import_golden("messy_blog").await;
+    }
+
+    #[tokio::test]
+    async fn import_golden_consent_wall() {
+        run_import_golden("consent_wall").await;
+    }
+
+    #[tokio::test]
+    async fn import_golden_markup_in_steps() {
+        run_import_golden("markup_in_steps").await;
+        let golden: Value = serde_json::from_str(
+            &std::fs::read_to_string(import_fixture_dir("markup_in_steps").join("golden.json"))
+                .unwrap(),
+        )
+        .unwrap();
+        for step in golden["instructions"].as_array().unwrap() {
+            assert!(
+                !step.as_str().unwrap().contains('<'),
+                "markup survived: {step}"
+            );
+        }
+    }
+
+    // ── weekly digest ────────────────────────────────────────────────────────
+
+    type Posts = std::sync::Arc<std::sync::Mutex<Vec<(String, String)>>>;
+
+    /// ntfy stand-in recording `(Title header, body)` for each post.
+    async fn spawn_mock_ntfy() -> (String, Posts) {
+        let posts = Posts::default();
+        let seen = posts.clone();
+        let app = axum::Router::new().route(
+            "/digest",
+            axum::routing::post(move |headers: axum::http::HeaderMap, body: String| {
+                let seen = seen.clone();
+                async move {
+                    let title = headers
+                        .get("Title")
+                        .and_then(|v| v.to_str().ok())
+                        .unwrap_or_default()
+                        .to_string();
+                    seen.lock().unwrap().push((title, body));
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}/digest"), posts)
+    }
+
+    #[tokio::test]
+    async fn digest_send_now_posts_next_week_and_shopping() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (ntfy, posts) = spawn_mock_ntfy().await;
+        let mut state = make_test_state(&tmp).await;
+        state.config.digest_ntfy_url = Some(ntfy);
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Shakshuka"}),
+            ))
+            .await
+            .unwrap();
+        let recipe_id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+        let next_monday =
+            crate::digest::next_week_start(chrono::Local::now().date_naive(), chrono::Weekday::Mon);
+        app.clone()
+            .oneshot(auth_json(
+                "POST",
+                "/meal-plan",
+                &token,
+                &json!({"day": next_monday.format("%Y-%m-%d").to_string(), "recipe_id": recipe_id}),
+            ))
+            .await
+            .unwrap();
+        add_shopping(&app, &token, "2 onions").await;
+
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/admin/digest/send-now",
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["sent"], true);
+        let text = body["text"].as_str().unwrap();
+        assert!(text.contains(": Shakshuka\n"), "{text}");
+        assert!(text.contains("6 days without a planned meal."), "{text}");
+        assert!(text.contains("- 2 onions\n"), "{text}");
+
+        let posts = posts.lock().unwrap().clone();
+        assert_eq!(
+            posts,
+            vec![("Blaz weekly digest".to_string(), text.to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn digest_send_now_without_ntfy_only_composes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let resp = app
+            .oneshot(auth_json(
+                "POST",
+                "/admin/digest/send-now",
+                &make_token(),
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = json_body(resp.into_body()).await;
+        assert_eq!(body["sent"], false);
+        assert!(body["text"].as_str().unwrap().contains("Nothing to buy."));
+    }
+
+    #[tokio::test]
+    async fn digest_scheduler_sends_once_across_restarts_and_stops() {
+        use chrono::TimeZone;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let (ntfy, posts) = spawn_mock_ntfy().await;
+        let mut state = make_test_state(&tmp).await;
+        state.config.digest_ntfy_url = Some(ntfy);
+
+        // Sunday 2026-01-04 18:30, half an hour after the slot.
+        let sunday_evening = chrono::Local
+            .with_ymd_and_hms(2026, 1, 4, 18, 30, 0)
+            .single()
+            .unwrap();
+        let tick = std::time::Duration::from_millis(10);
+
+        for _ in 0..2 {
+            let (stop, stopped) = tokio::sync::watch::channel(false);
+            let task = crate::digest::spawn_scheduler(
+                state.clone(),
+                chrono::Weekday::Sun,
+                18,
+                tick,
+                move || sunday_evening,
+                stopped,
+            );
+            tokio::time::sleep(tick * 10).await;
+            stop.send(true).unwrap();
+            tokio::time::timeout(std::time::Duration::from_secs(5), task)
+                .await
+                .expect("scheduler stops on shutdown")
+                .unwrap();
+        }
+
+        let posts = posts.lock().unwrap().clone();
+        assert_eq!(posts.len(), 1, "the second run must not re-send");
+        assert!(
+            posts[0]
+                .1
+                .starts_with("Meal plan Mon 05 Jan – Sun 11 Jan\n")
+        );
+    }
+
+    // ── stores ───────────────────────────────────────────────────────────────
+
+    async fn create_store(app: &axum::Router, token: &str, name: &str) -> i64 {
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/stores", token, &json!({"name": name})))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body()).await["id"].as_i64().unwrap()
+    }
+
+    async fn category_id(app: &axum::Router, token: &str, name: &str) -> i64 {
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/categories", token))
+            .await
+            .unwrap();
+        let cats = json_body(resp.into_body()).await;
+        cats.as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == name)
+            .unwrap()["id"]
+            .as_i64()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn shopping_list_filters_by_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let market = create_store(&app, &token, "Market").await;
+        let bakery = create_store(&app, &token, "Bakery").await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/merge",
+                &token,
+                &json!({
+                    "items": [{"name": "apples", "quantity": 3.0, "category": "Fruits"}],
+                    "store_id": market
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bread = add_shopping(&app, &token, "bread").await;
+        add_shopping(&app, &token, "salt").await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{bread}"),
+                &token,
+                &json!({"store_id": bakery}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["store_id"], bakery);
+
+        let names = |v: &Value| -> Vec<String> {
+            let mut names: Vec<String> = v
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i["text"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/shopping?store={market}"), &token))
+            .await
+            .unwrap();
+        assert_eq!(
+            names(&json_body(resp.into_body()).await),
+            ["3 apples", "salt"]
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/shopping", &token))
+            .await
+            .unwrap();
+        assert_eq!(
+            names(&json_body(resp.into_body()).await),
+            ["3 apples", "bread", "salt"]
+        );
+
+        // null puts the item back on every store's list.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{bread}"),
+                &token,
+                &json!({"store_id": null}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["store_id"], Value::Null);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/shopping/{bread}"),
+                &token,
+                &json!({"store_id": 999}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = app
+            .oneshot(auth_get("/shopping?store=999", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn store_category_order_takes_precedence_over_global_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let store = create_store(&app, &token, "Corner shop").await;
+
+        let items = json!({"items": [
+            {"name": "apples", "category": "Fruits"},
+            {"name": "bread", "category": "Bakery"},
+            {"name": "carrots", "category": "Vegetables"},
+        ]});
+        let resp = app
+            .clone()
+            .oneshot(auth_json("POST", "/shopping/merge", &token, &items))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let categories = |v: Value| -> Vec<String> {
+            v.as_array()
+                .unwrap()
+                .iter()
+                .map(|g| g["category"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Global order: Fruits, Vegetables, Bakery.
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                &format!("/shopping?store={store}&group_by=category"),
+                &token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            categories(json_body(resp.into_body()).await),
+            ["Fruits", "Vegetables", "Bakery"]
+        );
+
+        // The shop has bread by the entrance; unlisted categories keep their
+        // global order after the listed ones.
+        let bakery = category_id(&app, &token, "Bakery").await;
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PUT",
+                &format!("/stores/{store}/category-order"),
+                &token,
+                &json!({"order": [bakery]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let order = json_body(resp.into_body()).await;
+        assert_eq!(order[0]["name"], "Bakery");
+        assert_eq!(order[0]["sort_order"], 0);
+        assert_eq!(order[1]["sort_order"], Value::Null);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get(
+                &format!("/shopping?store={store}&group_by=category"),
+                &token,
+            ))
+            .await
+            .unwrap();
+        let groups = json_body(resp.into_body()).await;
+        assert_eq!(groups[0]["items"][0]["text"], "bread");
+        assert_eq!(categories(groups), ["Bakery", "Fruits", "Vegetables"]);
+
+        // Without a store the global order applies.
+        let resp = app
+            .oneshot(auth_get("/shopping?group_by=category", &token))
+            .await
+            .unwrap();
+        assert_eq!(
+            categories(json_body(resp.into_body()).await),
+            ["Fruits", "Vegetables", "Bakery"]
+        );
+    }
+
+    #[tokio::test]
+    async fn merge_uses_default_store_unless_overridden() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+        let store = create_store(&app, &token, "Market").await;
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"default_store_id": "999"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                "/settings",
+                &token,
+                &json!({"settings": {"default_store_id": store.to_string()}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/merge",
+                &token,
+                &json!({"items": [
+                    {"name": "leeks", "category": "Vegetables"},
+                ]}),
+            ))
+            .await
+            .unwrap();
+        let list = json_body(resp.into_body()).await["items"].clone();
+        assert_eq!(list[0]["store_id"], store);
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/shopping/merge",
+                &token,
+                &json!({
+                    "items": [{"name": "limes", "category": "Fruits"}],
+                    "store_id": null
+                }),
+            ))
+            .await
+            .unwrap();
+        let list = json_body(resp.into_body()).await["items"].clone();
+        let limes = list
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["text"] == "limes")
+            .unwrap();
+        assert_eq!(limes["store_id"], Value::Null);
+
+        // Deleting the store releases its items and clears the default.
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "DELETE",
+                &format!("/stores/{store}"),
+                &token,
+                &json!({}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body(resp.into_body()).await["deleted"], 1);
+        let resp = app.oneshot(auth_get("/shopping", &token)).await.unwrap();
+        let list = json_body(resp.into_body()).await;
+        assert!(
+            list.as_array()
+                .unwrap()
+                .iter()
+                .all(|i| i["store_id"].is_null())
+        );
+    }
+
+    // ── activity log ─────────────────────────────────────────────────────────
+
+    async fn activity(app: &axum::Router, query: &str) -> Vec<Value> {
+        let resp = app
+            .clone()
+            .oneshot(auth_get(&format!("/activity{query}"), &make_token()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        json_body(resp.into_body())
+            .await
+            .as_array()
+            .unwrap()
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn activity_log_records_one_entry_per_edit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = crate::app::build_app(make_test_state(&tmp).await);
+        let token = make_token();
+
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "POST",
+                "/recipes",
+                &token,
+                &json!({"title": "Soup", "ingredients": [], "instructions": []}),
+            ))
+            .await
+            .unwrap();
+        let id = json_body(resp.into_body()).await["id"].as_i64().unwrap();
+        let resp = app
+            .clone()
+            .oneshot(auth_json(
+                "PATCH",
+                &format!("/recipes/{id}"),
+                &token,
+                &json!({"ingredients": [{"name": "leek", "quantity": 2.0}]}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let rows = activity(&app, &format!("?entity=recipe&id={id}")).await;
+        assert_eq!(rows.len(), 2, "{rows:?}");
+        assert_eq!(rows[0]["action"], "update");
+        assert_eq!(rows[0]["summary"], "Updated 'Soup': ingredients");
+        assert_eq!(rows[0]["actor"], "user");
+        assert_eq!(rows[1]["action"], "create");
+        assert_eq!(rows[1]["entity_id"], id);
+
+        // Changes made with an API token name the token.
+        let (token_id, api_token) = create_api_token(&app, json!(["shopping-only"])).await;
+        let item = add_shopping(&app, &api_token, "bread").await;
+        let rows = activity(&app, &format!("?entity=shopping_item&id={item}")).await;
+        assert_eq!(rows.len(), 1, "{rows:?}");
+        assert_eq!(rows[0]["summary"], "Added 'bread'");
+        assert_eq!(rows[0]["actor"], format!("api_token:{token_id}"));
+
+        let resp = app
+            .clone()
+            .oneshot(auth_get("/activity?entity=nope", &token))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn activity_log_prunes_oldest_rows() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut state = make_test_state(&tmp).await;
+        state.config.activity_log_max_rows = 3;
+        let app = crate::app::build_app(state);
+        let token = make_token();
+
+        for text in ["apples", "bread", "cheese", "dates", "eggs"] {
+            add_shopping(&app, &token, text).await;
+        }
+
+        let rows = activity(&app, "").await;
+        let summaries: Vec<&str> = rows
+            .iter()
+            .map(|r| r["summary"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            summaries,
+            ["Added 'eggs'", "Added 'dates'", "Added 'cheese'"]
+        );
+        assert_eq!(activity(&app, "?limit=1").await.len(), 1);
     }
 }
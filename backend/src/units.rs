@@ -1,11 +1,12 @@
 use regex::Regex;
 use std::sync::LazyLock;
 
-pub static SERVINGS_NUM_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"(?i)(\d+(?:[.,]\d+)?)(?:\s*[–-]\s*(\d+(?:[.,]\d+)?))?").unwrap());
+pub static SERVINGS_NUM_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(\d+(?:[.,]\d+)?)(?:\s*(?:[–-]|to)\s*(\d+(?:[.,]\d+)?))?").unwrap()
+});
 
 pub static BARE_NUM_RANGE_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"(?ix)^\s*(\d+(?:[.,]\d+)?)(?:\s*[–-]\s*(\d+(?:[.,]\d+)?))?\s*$").unwrap()
+    Regex::new(r"(?ix)^\s*(\d+(?:[.,]\d+)?)(?:\s*(?:[–-]|to)\s*(\d+(?:[.,]\d+)?))?\s*$").unwrap()
 });
 
 pub static SERVINGS_HINT_RE: LazyLock<Regex> = LazyLock::new(|| {
@@ -35,21 +36,252 @@ pub fn canon_unit_str(u: &str) -> Option<&'static str> {
         "l" | "liter" | "litre" | "liters" | "litres" => Some("L"),
         "tsp" | "teaspoon" | "teaspoons" => Some("tsp"),
         "tbsp" | "tablespoon" | "tablespoons" => Some("tbsp"),
-        _ => None,
+        "dl" | "deciliter" | "decilitre" | "deciliters" | "decilitres" => Some("dl"),
+        "cl" | "centiliter" | "centilitre" | "centiliters" | "centilitres" => Some("cl"),
+        "cup" | "cups" => Some("cup"),
+        "fl oz" | "fl. oz." | "fl.oz." | "floz" | "fluid ounce" | "fluid ounces" => Some("fl oz"),
+        "oz" | "ounce" | "ounces" => Some("oz"),
+        "lb" | "lbs" | "pound" | "pounds" => Some("lb"),
+        "stick" | "sticks" => Some("stick"),
+        _ => localized_unit(&unit_lookup_key(u), &[]),
     }
 }
 
-// No unit conversion — each unit is stored as-is so "1 kg potatoes" and
-// "500 g potatoes" appear as separate shopping items.
+/// Unit spellings from French, German and Nordic recipes, keyed by
+/// [`unit_lookup_key`]. A bare "cuillère" is read as a tablespoon.
+const LOCALIZED_UNITS: &[(&str, &str)] = &[
+    // French
+    ("c a soupe", "tbsp"),
+    ("c a s", "tbsp"),
+    ("cas", "tbsp"),
+    ("cs", "tbsp"),
+    ("cuillere a soupe", "tbsp"),
+    ("cuilleres a soupe", "tbsp"),
+    ("cuillere", "tbsp"),
+    ("cuilleres", "tbsp"),
+    ("c a cafe", "tsp"),
+    ("c a c", "tsp"),
+    ("cac", "tsp"),
+    ("cc", "tsp"),
+    ("cuillere a cafe", "tsp"),
+    ("cuilleres a cafe", "tsp"),
+    ("gramme", "g"),
+    ("grammes", "g"),
+    ("kilo", "kg"),
+    ("kilos", "kg"),
+    // German
+    ("el", "tbsp"),
+    ("esslöffel", "tbsp"),
+    ("tl", "tsp"),
+    ("teelöffel", "tsp"),
+    ("gramm", "g"),
+    // Swedish, Norwegian, Danish
+    ("msk", "tbsp"),
+    ("ss", "tbsp"),
+    ("spsk", "tbsp"),
+    ("tsk", "tsp"),
+    ("ts", "tsp"),
+    ("krm", "ml"),
+];
+
+/// Longest unit spelling, in whitespace-separated words ("cuillères à soupe").
+pub const MAX_UNIT_WORDS: usize = 3;
+
+/// Lookup form of a unit: lowercase, dots read as spaces, whitespace
+/// collapsed, and the accents French units carry dropped, so "c. à s.",
+/// "c.à.s" and "C à S" all become "c a s".
+#[must_use]
+pub fn unit_lookup_key(u: &str) -> String {
+    let lowered: String = u
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '.' => ' ',
+            'à' | 'â' => 'a',
+            'è' | 'é' | 'ê' => 'e',
+            _ => c,
+        })
+        .collect();
+    norm_whitespace(&lowered)
+}
+
+fn localized_unit(key: &str, extra: &[(String, &'static str)]) -> Option<&'static str> {
+    extra
+        .iter()
+        .find(|(alias, _)| alias == key)
+        .map(|(_, unit)| *unit)
+        .or_else(|| {
+            LOCALIZED_UNITS
+                .iter()
+                .find(|(alias, _)| *alias == key)
+                .map(|(_, unit)| *unit)
+        })
+}
+
+/// [`canon_unit_str`] plus household-specific aliases (the `unit_synonyms`
+/// setting, see [`parse_unit_synonyms`]). Aliases win over built-ins.
+#[must_use]
+pub fn canon_unit_with(u: &str, extra: &[(String, &'static str)]) -> Option<&'static str> {
+    localized_unit(&unit_lookup_key(u), extra).or_else(|| canon_unit_str(u))
+}
+
+/// Parse the `unit_synonyms` setting: a JSON object mapping an alias to a
+/// unit `canon_unit_str` knows, e.g. `{"knivsudd": "ml"}`.
+///
+/// # Errors
+/// A message naming the problem when the value is not such an object.
+pub fn parse_unit_synonyms(raw: &str) -> Result<Vec<(String, &'static str)>, String> {
+    let map: std::collections::BTreeMap<String, String> = serde_json::from_str(raw)
+        .map_err(|e| format!("unit_synonyms must be a JSON object: {e}"))?;
+    map.into_iter()
+        .map(|(alias, unit)| {
+            let canonical = canon_unit_str(&unit)
+                .ok_or_else(|| format!("unknown unit {unit:?} for {alias:?}"))?;
+            let key = unit_lookup_key(&alias);
+            if key.is_empty() {
+                return Err("unit_synonyms has an empty alias".to_string());
+            }
+            Ok((key, canonical))
+        })
+        .collect()
+}
+
+/// Units folded into g or ml, with their size there. Imperial factors match
+/// the ones the import prompt gives the LLM; a stick is a stick of butter.
+const FOLDED_UNITS: &[(&str, &str, f64)] = &[
+    ("dl", "ml", 100.0),
+    ("cl", "ml", 10.0),
+    ("cup", "ml", 240.0),
+    ("fl oz", "ml", 30.0),
+    ("oz", "g", 28.0),
+    ("lb", "g", 454.0),
+    ("stick", "g", 113.0),
+];
+
+// Metric units are stored as-is so "1 kg potatoes" and "500 g potatoes"
+// appear as separate shopping items. Units with no canonical form of their
+// own (dl/cl and the imperial ones) become ml or g.
 #[must_use]
 pub fn to_canonical_qty_unit(
     unit: Option<&str>,
     qty: Option<f64>,
 ) -> (Option<&'static str>, Option<f64>) {
-    match (unit.map(str::to_ascii_lowercase), qty) {
-        (Some(u), q) => (canon_unit_str(&u), q),
-        (None, q) => (None, q),
+    let canonical = unit.and_then(canon_unit_str);
+    if let Some(&(_, to, factor)) = FOLDED_UNITS.iter().find(|(u, ..)| Some(*u) == canonical) {
+        // Round away float noise from the conversion (0.7 dl -> 70 ml).
+        let converted = qty.map(|q| (q * factor * 1e6).round() / 1e6);
+        return (Some(to), converted);
     }
+    (canonical, qty)
+}
+
+/// Dimension a canonical unit measures; quantities only combine within a class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnitClass {
+    /// No unit ("2 eggs").
+    Count,
+    Mass,
+    Volume,
+}
+
+/// Class of a canonical unit and its factor to the class base (g, ml, 1).
+/// Returns `None` for units outside the canonical set.
+#[must_use]
+pub fn unit_class(unit: Option<&str>) -> Option<(UnitClass, f64)> {
+    Some(match unit {
+        None | Some("") => (UnitClass::Count, 1.0),
+        Some("g") => (UnitClass::Mass, 1.0),
+        Some("kg") => (UnitClass::Mass, 1000.0),
+        Some("ml") => (UnitClass::Volume, 1.0),
+        Some("L") => (UnitClass::Volume, 1000.0),
+        Some("tsp") => (UnitClass::Volume, 5.0),
+        Some("tbsp") => (UnitClass::Volume, 15.0),
+        Some(_) => return None,
+    })
+}
+
+/// Convert `qty` from one canonical unit to another of the same class.
+#[must_use]
+pub fn convert_qty(qty: f64, from: Option<&str>, to: Option<&str>) -> Option<f64> {
+    let (from_class, from_factor) = unit_class(from)?;
+    let (to_class, to_factor) = unit_class(to)?;
+    (from_class == to_class).then(|| qty * from_factor / to_factor)
+}
+
+/// Human-readable line for an ingredient ("200 g spaghetti, broken").
+/// Section headers yield their name.
+#[must_use]
+pub fn format_ingredient_line(ing: &crate::models::Ingredient) -> String {
+    if let Some(section) = &ing.section {
+        return section.clone();
+    }
+    let name = match ing.prep.as_deref() {
+        Some(p) if !p.trim().is_empty() => format!("{}, {}", ing.name, p.trim()),
+        _ => ing.name.clone(),
+    };
+    match (ing.quantity, ing.unit.as_deref()) {
+        (Some(q), Some(u)) if !u.is_empty() => format!("{q} {u} {name}"),
+        (Some(q), _) => format!("{q} {name}"),
+        _ => name,
+    }
+}
+
+/// Participles that describe preparation rather than the ingredient.
+pub const PREP_WORDS: &[&str] = &[
+    "sliced",
+    "diced",
+    "minced",
+    "chopped",
+    "grated",
+    "shredded",
+    "softened",
+    "melted",
+    "julienned",
+    "crushed",
+    "peeled",
+    "cubed",
+    "halved",
+];
+
+/// Split preparation off an ingredient name: everything after the first
+/// comma ("carrots, diced") or a trailing participle ("carrots diced").
+/// A comma followed by no words ("diced onion, 2") is left alone.
+#[must_use]
+pub fn split_prep(name: &str) -> (String, Option<String>) {
+    let name = name.trim();
+    if let Some((head, tail)) = name.split_once(',') {
+        let (head, tail) = (head.trim(), tail.trim());
+        if !head.is_empty() && tail.chars().any(char::is_alphabetic) {
+            return (head.to_string(), Some(tail.to_string()));
+        }
+        return (name.to_string(), None);
+    }
+    if let Some((head, last)) = name.rsplit_once(' ')
+        && PREP_WORDS.contains(&last.to_lowercase().as_str())
+        && !head.trim().is_empty()
+    {
+        return (head.trim().to_string(), Some(last.to_string()));
+    }
+    (name.to_string(), None)
+}
+
+/// Undo typography that pasted text (Word, Google Docs, recipe sites) brings
+/// along so whitespace splitting and number parsing see plain ASCII:
+/// no-break and thin spaces become spaces, BOMs, zero-width characters and
+/// soft hyphens are dropped, fraction/division slashes become `/`, and smart
+/// quotes and primes become `'` / `"`.
+#[must_use]
+pub fn preclean_text(s: &str) -> String {
+    s.chars()
+        .filter_map(|ch| match ch {
+            '\u{00A0}' | '\u{2007}' | '\u{2009}' | '\u{200A}' | '\u{202F}' => Some(' '),
+            '\u{FEFF}' | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{00AD}' => None,
+            '\u{2044}' | '\u{2215}' => Some('/'),
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => Some('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => Some('"'),
+            other => Some(other),
+        })
+        .collect()
 }
 
 #[must_use]
@@ -70,15 +302,383 @@ pub fn norm_whitespace(s: &str) -> String {
     out.trim().to_string()
 }
 
+/// Parse a number typed with either decimal separator, optionally with
+/// thousands separators and surrounding whitespace.
+///
+/// When both `.` and `,` appear, the last one is the decimal separator and
+/// the other must group the integer part in threes. A separator that appears
+/// more than once is a thousands separator. A single separator is always a
+/// decimal point, so `"1,500"` and `"1.500"` are both 1.5: in ingredient
+/// quantities "1,5 kg" is far more common than a grouped 1500.
+///
+/// | input       | result    |
+/// |-------------|-----------|
+/// | `1,5`       | 1.5       |
+/// | `1,500`     | 1.5       |
+/// | `1.500,5`   | 1500.5    |
+/// | `1,500.5`   | 1500.5    |
+/// | `1.500.000` | 1500000   |
+/// | `1,50,000`  | `None` (only groups of three) |
+/// | `1.5.5`     | `None`    |
+#[must_use]
+pub fn parse_locale_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let (sign, body) = s.strip_prefix('-').map_or_else(
+        || (1.0, s.strip_prefix('+').unwrap_or(s)),
+        |rest| (-1.0, rest),
+    );
+    if !body.chars().any(|c| c.is_ascii_digit())
+        || !body
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    {
+        return None;
+    }
+
+    let (int_part, frac_part, group_sep) = match (body.rfind('.'), body.rfind(',')) {
+        (Some(dot), Some(comma)) => {
+            let (pos, group) = if dot > comma {
+                (dot, ',')
+            } else {
+                (comma, '.')
+            };
+            (&body[..pos], &body[pos + 1..], Some(group))
+        }
+        (Some(pos), None) | (None, Some(pos)) => {
+            let sep = if body.as_bytes()[pos] == b'.' {
+                '.'
+            } else {
+                ','
+            };
+            if body.matches(sep).count() > 1 {
+                (body, "", Some(sep))
+            } else {
+                (&body[..pos], &body[pos + 1..], None)
+            }
+        }
+        (None, None) => (body, "", None),
+    };
+
+    let int_digits = match group_sep {
+        Some(sep) if int_part.contains(sep) => {
+            let groups: Vec<&str> = int_part.split(sep).collect();
+            let well_grouped =
+                (1..=3).contains(&groups[0].len()) && groups[1..].iter().all(|g| g.len() == 3);
+            if !well_grouped {
+                return None;
+            }
+            groups.concat()
+        }
+        _ => int_part.to_string(),
+    };
+    if !int_digits.chars().all(|c| c.is_ascii_digit())
+        || !frac_part.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let int_digits = if int_digits.is_empty() {
+        "0"
+    } else {
+        &int_digits
+    };
+    let frac_part = if frac_part.is_empty() { "0" } else { frac_part };
+    let value: f64 = format!("{int_digits}.{frac_part}").parse().ok()?;
+    Some(sign * value)
+}
+
+/// Unicode vulgar fractions and their values.
+const UNICODE_FRACTIONS: &[(char, &str)] = &[
+    ('½', "0.5"),
+    ('⅓', "0.333333"),
+    ('⅔', "0.666667"),
+    ('¼', "0.25"),
+    ('¾', "0.75"),
+    ('⅕', "0.2"),
+    ('⅖', "0.4"),
+    ('⅗', "0.6"),
+    ('⅘', "0.8"),
+    ('⅙', "0.166667"),
+    ('⅚', "0.833333"),
+    ('⅛', "0.125"),
+    ('⅜', "0.375"),
+    ('⅝', "0.625"),
+    ('⅞', "0.875"),
+];
+
+/// Dashes between two amounts, with the spacing around them ("1/2 - 3/4").
+static SPACED_RANGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([\d/.,])\s*[-–]\s*(\d)").unwrap());
+
+/// Rewrite quantities so they split into whitespace tokens: Unicode fractions
+/// become decimals with a leading space ("1½" -> "1 0.5", a mixed number) and
+/// spaced ranges close up ("½ - ¾" -> "0.5-0.75").
+#[must_use]
+pub fn prepare_quantity_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match UNICODE_FRACTIONS.iter().find(|(f, _)| *f == c) {
+            Some((_, value)) => {
+                out.push(' ');
+                out.push_str(value);
+            }
+            None => out.push(c),
+        }
+    }
+    SPACED_RANGE_RE.replace_all(&out, "$1-$2").into_owned()
+}
+
+/// A simple fraction like "1/2" or "3/4".
+#[must_use]
+pub fn parse_fraction(s: &str) -> Option<f64> {
+    let (num, denom) = s.split_once('/')?;
+    let numerator = parse_locale_number(num)?;
+    let denominator = parse_locale_number(denom)?;
+    if denominator == 0.0 {
+        return None;
+    }
+    Some(numerator / denominator)
+}
+
+/// One amount: a number, a fraction, or a mixed number ("1 1/2", "1 0.5").
+fn parse_amount(s: &str) -> Option<f64> {
+    let single = |t: &str| parse_fraction(t).or_else(|| parse_locale_number(t));
+    let mut parts = s.split_whitespace();
+    let first = single(parts.next()?)?;
+    let Some(second) = parts.next() else {
+        return Some(first);
+    };
+    let frac = single(second)?;
+    let mixed = parts.next().is_none() && first.fract() == 0.0 && frac > 0.0 && frac < 1.0;
+    mixed.then_some(first + frac)
+}
+
+/// Parse a quantity: decimals in either locale, fractions ("1/2"), mixed
+/// numbers ("1 1/2"), Unicode fractions ("1½", "¾") and ranges of any of
+/// them ("2-3", "1/2 - 3/4"), which count as their midpoint.
+#[must_use]
+pub fn parse_quantity(s: &str) -> Option<f64> {
+    let s = prepare_quantity_text(s);
+    let s = s.trim();
+    if let Some((a, b)) = s.split_once('-').or_else(|| s.split_once('–'))
+        && !a.trim().is_empty()
+    {
+        return Some(f64::midpoint(parse_amount(a)?, parse_amount(b)?));
+    }
+    parse_amount(s)
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(f64),
+    Text(String),
+}
+
+/// Serde helper for optional quantities: accepts a JSON number, `null`, or a
+/// string parsed with [`parse_locale_number`] (blank strings become `None`).
+/// Use with `#[serde(default, deserialize_with = "...")]`.
+///
+/// # Errors
+/// A string that is not a number in either locale.
+pub fn deserialize_locale_number<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    use serde::de::Error;
+
+    match Option::<NumberOrText>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(NumberOrText::Number(n)) => Ok(Some(n)),
+        Some(NumberOrText::Text(t)) if t.trim().is_empty() => Ok(None),
+        Some(NumberOrText::Text(t)) => parse_locale_number(&t)
+            .map(Some)
+            .ok_or_else(|| D::Error::custom(format!("invalid number: {t:?}"))),
+    }
+}
+
 #[must_use]
 pub fn normalize_name(s: &str) -> String {
     norm_whitespace(&s.to_lowercase())
 }
 
+/// Parse a free-text yield ("Serves 4", "4-6 portions", "2") into a number of
+/// servings. Ranges resolve to their midpoint. Returns `None` for yields that
+/// are not servings-like ("500 g", "1 loaf").
+#[must_use]
+pub fn servings_from_yield(y: &str) -> Option<f64> {
+    let y = y.trim();
+    if y.is_empty() {
+        return None;
+    }
+
+    let y_lower = y.to_ascii_lowercase();
+
+    // Reject obvious non-serving yields, e.g. "500 g", "1 loaf"
+    if NON_SERVING_YIELD_RE.is_match(&y_lower) {
+        return None;
+    }
+
+    // Allow if:
+    // - the whole string is just a number/range
+    // - OR it contains a servings hint ("serves", "people", "portions", "makes", ...)
+    let looks_bare = BARE_NUM_RANGE_RE.is_match(&y_lower);
+    let has_hint = SERVINGS_HINT_RE.is_match(&y_lower);
+
+    if !looks_bare && !has_hint {
+        return None;
+    }
+
+    let cap = SERVINGS_NUM_RE.captures(&y_lower)?;
+    let a = parse_locale_number(cap.get(1)?.as_str())?;
+    let servings = match cap.get(2) {
+        Some(bm) => f64::midpoint(a, parse_locale_number(bm.as_str())?),
+        None => a,
+    };
+
+    (servings > 0.0).then_some(servings)
+}
+
+/// Canonical yield text for a number of servings ("1 serving", "4 servings").
+#[must_use]
+pub fn servings_yield_text(servings: f64) -> String {
+    let n = if servings.fract() == 0.0 {
+        format!("{servings:.0}")
+    } else {
+        let s = format!("{servings:.2}");
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    };
+    if n == "1" {
+        "1 serving".to_string()
+    } else {
+        format!("{n} servings")
+    }
+}
+
+/// Round a scaled quantity to something a cook can measure: whole grams and
+/// millilitres, quarter steps for spoons and counts ("1.5 eggs"), two
+/// decimals for anything else. Tiny amounts never round down to zero.
+#[must_use]
+pub fn round_scaled_qty(qty: f64, unit: Option<&str>) -> f64 {
+    let step = match unit
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .map(canon_unit_str)
+    {
+        Some(Some("g" | "ml")) => 1.0,
+        None | Some(Some("tsp" | "tbsp")) => 0.25,
+        Some(_) => 0.01,
+    };
+    let rounded = (qty / step).round() * step;
+    if rounded == 0.0 && qty > 0.0 {
+        (qty * 100.0).round() / 100.0
+    } else {
+        rounded
+    }
+}
+
+/// Which units recipes are shown in. Stored data is always metric.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl UnitSystem {
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "metric" => Some(Self::Metric),
+            "imperial" => Some(Self::Imperial),
+            _ => None,
+        }
+    }
+}
+
+const GRAMS_PER_OZ: f64 = 28.349_523_125;
+const GRAMS_PER_LB: f64 = 453.592_37;
+const ML_PER_TSP: f64 = 4.928_921_593_75;
+const ML_PER_TBSP: f64 = 3.0 * ML_PER_TSP;
+const ML_PER_FL_OZ: f64 = 2.0 * ML_PER_TBSP;
+const ML_PER_CUP: f64 = 8.0 * ML_PER_FL_OZ;
+
+/// Round to the nearest quarter without letting a positive amount reach zero.
+fn round_quarter(qty: f64) -> f64 {
+    let rounded = (qty * 4.0).round() / 4.0;
+    if rounded == 0.0 && qty > 0.0 {
+        0.25
+    } else {
+        rounded
+    }
+}
+
+/// US customary equivalent of a metric quantity, rounded to quarter steps
+/// ("28 g" -> 1 oz, "240 ml" -> 1 cup). Masses under a pound become oz,
+/// heavier ones lb; volumes pick tsp, tbsp, fl oz or cups by size. Spoons,
+/// counts and units outside the canonical set are left alone (`None`).
+#[must_use]
+pub fn to_imperial(qty: f64, unit: Option<&str>) -> Option<(f64, &'static str)> {
+    if matches!(unit, Some("tsp" | "tbsp")) {
+        return None;
+    }
+    let (class, factor) = unit_class(unit)?;
+    let base = qty * factor;
+    let (amount, unit) = match class {
+        UnitClass::Count => return None,
+        UnitClass::Mass if base < GRAMS_PER_LB => (base / GRAMS_PER_OZ, "oz"),
+        UnitClass::Mass => (base / GRAMS_PER_LB, "lb"),
+        UnitClass::Volume if base < ML_PER_TBSP => (base / ML_PER_TSP, "tsp"),
+        UnitClass::Volume if base < ML_PER_FL_OZ => (base / ML_PER_TBSP, "tbsp"),
+        UnitClass::Volume if base < ML_PER_CUP / 4.0 => (base / ML_PER_FL_OZ, "fl oz"),
+        UnitClass::Volume => (base / ML_PER_CUP, "cup"),
+    };
+    Some((round_quarter(amount), unit))
+}
+
+/// Convert an ingredient's quantity and unit in place for display in `system`.
+pub fn convert_ingredient(ing: &mut crate::models::Ingredient, system: UnitSystem) {
+    if system == UnitSystem::Metric || ing.section.is_some() {
+        return;
+    }
+    let Some(qty) = ing.quantity else {
+        return;
+    };
+    if let Some((amount, unit)) = to_imperial(qty, ing.unit.as_deref()) {
+        ing.quantity = Some(amount);
+        ing.unit = Some(unit.to_string());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_preclean_text() {
+        // Each input spells its special characters as escapes so the
+        // fixtures stay byte-exact whatever the editor does to them.
+        let cases = [
+            ("1\u{00A0}cup flour", "1 cup flour"),
+            ("200\u{202F}g sugar", "200 g sugar"),
+            ("2\u{2009}tbsp oil", "2 tbsp oil"),
+            ("\u{FEFF}3 eggs", "3 eggs"),
+            ("1\u{200B}2\u{2060}0 g", "120 g"),
+            ("but\u{00AD}ter", "butter"),
+            ("1\u{2044}2 tsp salt", "1/2 tsp salt"),
+            ("3\u{2215}4 cup", "3/4 cup"),
+            ("\u{2018}00\u{2019} flour", "'00' flour"),
+            ("\u{201C}al dente\u{201D}", "\"al dente\""),
+            ("a 9\u{2033} pan", "a 9\" pan"),
+            ("plain text", "plain text"),
+        ];
+        for (input, want) in cases {
+            assert_eq!(preclean_text(input), want, "{input:?}");
+        }
+    }
+
     #[test]
     fn test_canon_unit_str() {
         assert_eq!(canon_unit_str("g"), Some("g"));
@@ -113,8 +713,13 @@ mod tests {
         assert_eq!(canon_unit_str("tablespoon"), Some("tbsp"));
         assert_eq!(canon_unit_str("tablespoons"), Some("tbsp"));
 
+        assert_eq!(canon_unit_str("cups"), Some("cup"));
+        assert_eq!(canon_unit_str("Fl Oz"), Some("fl oz"));
+        assert_eq!(canon_unit_str("ounces"), Some("oz"));
+        assert_eq!(canon_unit_str("lbs"), Some("lb"));
+        assert_eq!(canon_unit_str("sticks"), Some("stick"));
+
         assert_eq!(canon_unit_str("unknown"), None);
-        assert_eq!(canon_unit_str("cup"), None);
         assert_eq!(canon_unit_str(""), None);
     }
 
@@ -175,6 +780,64 @@ mod tests {
         assert_eq!(to_canonical_qty_unit(None, None), (None, None));
     }
 
+    #[test]
+    fn test_canon_unit_str_localized() {
+        assert_eq!(canon_unit_str("c. à soupe"), Some("tbsp"));
+        assert_eq!(canon_unit_str("C.à.S."), Some("tbsp"));
+        assert_eq!(canon_unit_str("cuillères à café"), Some("tsp"));
+        assert_eq!(canon_unit_str("EL"), Some("tbsp"));
+        assert_eq!(canon_unit_str("Teelöffel"), Some("tsp"));
+        assert_eq!(canon_unit_str("msk"), Some("tbsp"));
+        assert_eq!(canon_unit_str("dl"), Some("dl"));
+        assert_eq!(canon_unit_str("soupe"), None);
+    }
+
+    #[test]
+    fn test_to_canonical_qty_unit_folds_dl_and_cl() {
+        assert_eq!(
+            to_canonical_qty_unit(Some("dl"), Some(0.7)),
+            (Some("ml"), Some(70.0))
+        );
+        assert_eq!(
+            to_canonical_qty_unit(Some("cl"), Some(33.0)),
+            (Some("ml"), Some(330.0))
+        );
+        assert_eq!(to_canonical_qty_unit(Some("dl"), None), (Some("ml"), None));
+    }
+
+    #[test]
+    fn test_to_canonical_qty_unit_converts_imperial() {
+        let cases = [
+            ("cups", 2.0, "ml", 480.0),
+            ("cup", 0.5, "ml", 120.0),
+            ("fl oz", 4.0, "ml", 120.0),
+            ("oz", 8.0, "g", 224.0),
+            ("ounce", 1.0, "g", 28.0),
+            ("lb", 1.5, "g", 681.0),
+            ("pounds", 2.0, "g", 908.0),
+            ("stick", 1.0, "g", 113.0),
+        ];
+        for (unit, qty, want_unit, want_qty) in cases {
+            assert_eq!(
+                to_canonical_qty_unit(Some(unit), Some(qty)),
+                (Some(want_unit), Some(want_qty)),
+                "{qty} {unit}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_unit_synonyms() {
+        let got = parse_unit_synonyms(r#"{"Knivsudd": "ml", "nypa": "g"}"#).unwrap();
+        assert_eq!(
+            got,
+            vec![("knivsudd".to_string(), "ml"), ("nypa".to_string(), "g")]
+        );
+        assert!(parse_unit_synonyms(r#"{"pinch": "handful"}"#).is_err());
+        assert!(parse_unit_synonyms("[]").is_err());
+        assert_eq!(canon_unit_with("KNIVSUDD", &got), Some("ml"));
+    }
+
     #[test]
     fn test_norm_whitespace() {
         assert_eq!(norm_whitespace("  hello   world  "), "hello world");
@@ -196,4 +859,257 @@ mod tests {
         assert_eq!(normalize_name("  Mixed\t\tCase  "), "mixed case");
         assert_eq!(normalize_name(""), "");
     }
+
+    #[test]
+    fn test_servings_from_yield() {
+        assert_eq!(servings_from_yield("4"), Some(4.0));
+        assert_eq!(servings_from_yield("Serves 4"), Some(4.0));
+        assert_eq!(servings_from_yield("4 servings"), Some(4.0));
+        assert_eq!(servings_from_yield("4-6 portions"), Some(5.0));
+        assert_eq!(servings_from_yield("4 to 6 people"), Some(5.0));
+        assert_eq!(servings_from_yield("2,5"), Some(2.5));
+        assert_eq!(servings_from_yield("Makes 12"), Some(12.0));
+
+        assert_eq!(servings_from_yield(""), None);
+        assert_eq!(servings_from_yield("500 g"), None);
+        assert_eq!(servings_from_yield("1 loaf"), None);
+        assert_eq!(servings_from_yield("a lot"), None);
+        assert_eq!(servings_from_yield("0"), None);
+    }
+
+    #[test]
+    fn test_servings_yield_text() {
+        assert_eq!(servings_yield_text(1.0), "1 serving");
+        assert_eq!(servings_yield_text(4.0), "4 servings");
+        assert_eq!(servings_yield_text(2.5), "2.5 servings");
+        assert_eq!(servings_from_yield(&servings_yield_text(6.0)), Some(6.0));
+    }
+
+    #[test]
+    fn test_round_scaled_qty() {
+        let cases = [
+            (333.333, Some("g"), 333.0),
+            (187.5, Some("ml"), 188.0),
+            (1.6, Some("tsp"), 1.5),
+            (0.7, Some("tablespoons"), 0.75),
+            (2.9, None, 3.0),
+            (1.4, Some(""), 1.5),
+            (1.2345, Some("kg"), 1.23),
+            (0.3, Some("g"), 0.3),
+            (0.0, Some("g"), 0.0),
+        ];
+        for (qty, unit, expected) in cases {
+            let got = round_scaled_qty(qty, unit);
+            assert!((got - expected).abs() < 1e-9, "{qty} {unit:?} -> {got}");
+        }
+    }
+
+    #[test]
+    fn test_convert_qty() {
+        assert_eq!(convert_qty(500.0, Some("g"), Some("kg")), Some(0.5));
+        assert_eq!(convert_qty(1.5, Some("L"), Some("ml")), Some(1500.0));
+        assert_eq!(convert_qty(1.0, Some("tbsp"), Some("tsp")), Some(3.0));
+        assert_eq!(convert_qty(2.0, None, None), Some(2.0));
+
+        assert_eq!(convert_qty(1.0, Some("g"), Some("ml")), None);
+        assert_eq!(convert_qty(1.0, None, Some("g")), None);
+        assert_eq!(convert_qty(1.0, Some("cup"), Some("ml")), None);
+    }
+
+    #[test]
+    fn test_parse_locale_number_table() {
+        let cases: &[(&str, Option<f64>)] = &[
+            ("1,5", Some(1.5)),
+            ("1.5", Some(1.5)),
+            ("  2,25 ", Some(2.25)),
+            ("1,500", Some(1.5)),
+            ("1.500", Some(1.5)),
+            ("1.500,5", Some(1500.5)),
+            ("1,500.5", Some(1500.5)),
+            ("12.345.678,9", Some(12_345_678.9)),
+            ("1.500.000", Some(1_500_000.0)),
+            ("1,500,000", Some(1_500_000.0)),
+            (",5", Some(0.5)),
+            ("3.", Some(3.0)),
+            ("-0,75", Some(-0.75)),
+            ("42", Some(42.0)),
+            ("1,50,000", None),
+            ("1.5.5", None),
+            ("15.00,0.5", None),
+            ("1.50,5", None),
+            ("", None),
+            (",", None),
+            ("1e5", None),
+            ("inf", None),
+            ("1 500", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_locale_number(input), *expected, "input {input:?}");
+        }
+    }
+
+    /// Format `cents / 100` the way a user in each locale might type it.
+    fn locale_spellings(cents: u64) -> Vec<String> {
+        let int = cents / 100;
+        let frac = cents % 100;
+        let digits = int.to_string();
+        let grouped = |sep: char| {
+            let mut out = String::new();
+            for (i, ch) in digits.chars().enumerate() {
+                if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                    out.push(sep);
+                }
+                out.push(ch);
+            }
+            out
+        };
+        vec![
+            format!("{int}.{frac:02}"),
+            format!("{int},{frac:02}"),
+            format!("{}.{frac:02}", grouped(',')),
+            format!("{},{frac:02}", grouped('.')),
+            format!(" {int},{frac:02}\t"),
+        ]
+    }
+
+    #[test]
+    fn test_parse_locale_number_round_trips() {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1469);
+        for _ in 0..2000 {
+            let cents: u64 = rng.gen_range(0..10_000_000_000);
+            let expected = f64::from(u32::try_from(cents / 100).unwrap())
+                + f64::from(u32::try_from(cents % 100).unwrap()) / 100.0;
+            for text in locale_spellings(cents) {
+                let got = parse_locale_number(&text).unwrap_or_else(|| panic!("{text:?}"));
+                assert!((got - expected).abs() < 1e-6, "{text:?} -> {got}");
+            }
+            // Rust's own formatting reads back exactly.
+            let x = f64::from(rng.gen_range(0u32..1_000_000)) / 1000.0;
+            assert_eq!(parse_locale_number(&x.to_string()), Some(x));
+        }
+    }
+
+    #[test]
+    fn test_deserialize_locale_number() {
+        #[derive(serde::Deserialize)]
+        struct Q {
+            #[serde(default, deserialize_with = "deserialize_locale_number")]
+            quantity: Option<f64>,
+        }
+        let q = |v: &str| serde_json::from_str::<Q>(v).map(|q| q.quantity);
+
+        assert_eq!(q(r#"{"quantity": 1.5}"#).unwrap(), Some(1.5));
+        assert_eq!(q(r#"{"quantity": "1,5"}"#).unwrap(), Some(1.5));
+        assert_eq!(q(r#"{"quantity": "1.500,5"}"#).unwrap(), Some(1500.5));
+        assert_eq!(q(r#"{"quantity": null}"#).unwrap(), None);
+        assert_eq!(q(r#"{"quantity": " "}"#).unwrap(), None);
+        assert_eq!(q("{}").unwrap(), None);
+        assert!(q(r#"{"quantity": "lots"}"#).is_err());
+    }
+
+    #[test]
+    fn to_imperial_picks_friendly_units() {
+        let cases = [
+            (28.0, Some("g"), Some((1.0, "oz"))),
+            (100.0, Some("g"), Some((3.5, "oz"))),
+            (1.0, Some("g"), Some((0.25, "oz"))),
+            (500.0, Some("g"), Some((1.0, "lb"))),
+            (1.0, Some("kg"), Some((2.25, "lb"))),
+            (240.0, Some("ml"), Some((1.0, "cup"))),
+            (1.0, Some("L"), Some((4.25, "cup"))),
+            (120.0, Some("ml"), Some((0.5, "cup"))),
+            (45.0, Some("ml"), Some((1.5, "fl oz"))),
+            (20.0, Some("ml"), Some((1.25, "tbsp"))),
+            (5.0, Some("ml"), Some((1.0, "tsp"))),
+            (2.0, Some("tbsp"), None),
+            (3.0, None, None),
+            (1.0, Some("pinch"), None),
+        ];
+        for (qty, unit, want) in cases {
+            assert_eq!(to_imperial(qty, unit), want, "{qty} {unit:?}");
+        }
+    }
+
+    #[test]
+    fn convert_ingredient_leaves_metric_and_sections_alone() {
+        let ing = |qty: Option<f64>, unit: Option<&str>| crate::models::Ingredient {
+            section: None,
+            quantity: qty,
+            unit: unit.map(str::to_string),
+            name: "flour".to_string(),
+            prep: None,
+            raw: false,
+        };
+
+        let mut metric = ing(Some(250.0), Some("g"));
+        convert_ingredient(&mut metric, UnitSystem::Metric);
+        assert_eq!(
+            (metric.quantity, metric.unit.as_deref()),
+            (Some(250.0), Some("g"))
+        );
+
+        let mut imperial = ing(Some(250.0), Some("g"));
+        convert_ingredient(&mut imperial, UnitSystem::Imperial);
+        assert_eq!(
+            (imperial.quantity, imperial.unit.as_deref()),
+            (Some(8.75), Some("oz"))
+        );
+
+        let mut unquantified = ing(None, Some("g"));
+        convert_ingredient(&mut unquantified, UnitSystem::Imperial);
+        assert_eq!(unquantified.unit.as_deref(), Some("g"));
+
+        assert_eq!(UnitSystem::parse("imperial"), Some(UnitSystem::Imperial));
+        assert_eq!(UnitSystem::parse("US"), None);
+    }
+
+    #[test]
+    fn parse_quantity_handles_fractions_and_ranges() {
+        let cases = [
+            ("2", Some(2.0)),
+            ("1,5", Some(1.5)),
+            ("1/2", Some(0.5)),
+            ("3/4", Some(0.75)),
+            ("1 1/2", Some(1.5)),
+            ("½", Some(0.5)),
+            ("1½", Some(1.5)),
+            ("1 ½", Some(1.5)),
+            ("⅛", Some(0.125)),
+            ("2-3", Some(2.5)),
+            ("1/2 - 3/4", Some(0.625)),
+            ("½–¾", Some(0.625)),
+            ("-1", Some(-1.0)),
+            ("1/0", None),
+            ("2 3", None),
+            ("abc", None),
+            ("", None),
+        ];
+        for (input, want) in cases {
+            assert_eq!(parse_quantity(input), want, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn split_prep_separates_preparation() {
+        let cases = [
+            ("carrots, diced", "carrots", Some("diced")),
+            ("carrots, julienned", "carrots", Some("julienned")),
+            ("onion, finely chopped", "onion", Some("finely chopped")),
+            ("garlic minced", "garlic", Some("minced")),
+            ("diced onion, 2", "diced onion, 2", None),
+            ("diced", "diced", None),
+            (", sliced", ", sliced", None),
+            ("milk", "milk", None),
+        ];
+        for (input, name, prep) in cases {
+            let (got_name, got_prep) = split_prep(input);
+            assert_eq!(
+                (got_name.as_str(), got_prep.as_deref()),
+                (name, prep),
+                "{input:?}"
+            );
+        }
+    }
 }
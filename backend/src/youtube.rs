@@ -0,0 +1,295 @@
+use regex::Regex;
+use serde_json::Value as JsonValue;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use crate::html::extract_title;
+
+/// Metadata needed to import a recipe from a video.
+pub struct YoutubeVideo {
+    pub title: String,
+    pub description: String,
+    pub thumbnail_url: Option<String>,
+}
+
+/// Video id for youtube.com / youtu.be links (watch, shorts, embed, live), if any.
+pub fn video_id(url: &str) -> Option<String> {
+    let u = url::Url::parse(url.trim()).ok()?;
+    let host = u.host_str()?.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let mut segments = u.path_segments()?.filter(|s| !s.is_empty());
+
+    let id = match host {
+        "youtu.be" => segments.next()?.to_string(),
+        "youtube.com" | "m.youtube.com" | "music.youtube.com" => match segments.next()? {
+            "watch" => u
+                .query_pairs()
+                .find(|(k, _)| k == "v")
+                .map(|(_, v)| v.into_owned())?,
+            "shorts" | "embed" | "live" | "v" => segments.next()?.to_string(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let valid = id.len() == 11
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(id)
+}
+
+pub fn watch_url(video_id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={video_id}")
+}
+
+fn oembed_url(video_url: &str) -> String {
+    url::Url::parse_with_params(
+        "https://www.youtube.com/oembed",
+        &[("url", video_url), ("format", "json")],
+    )
+    .map(String::from)
+    .unwrap_or_default()
+}
+
+/// Fetch title/thumbnail via oEmbed and the full description from the watch page.
+///
+/// # Errors
+///
+/// Err if either request fails (private or deleted videos fail at oEmbed).
+pub async fn fetch_video(http: &reqwest::Client, video_id: &str) -> Result<YoutubeVideo, String> {
+    let watch = watch_url(video_id);
+
+    let oembed: JsonValue = http
+        .get(oembed_url(&watch))
+        .timeout(Duration::from_secs(20))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| format!("oEmbed request failed: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("oEmbed response invalid: {e}"))?;
+
+    // The consent cookie skips the EU cookie wall, which has no ytInitial* data.
+    let html = http
+        .get(&watch)
+        .header(reqwest::header::ACCEPT_LANGUAGE, "en")
+        .header(reqwest::header::COOKIE, "CONSENT=YES+1")
+        .timeout(Duration::from_secs(45))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| format!("watch page request failed: {e}"))?
+        .text()
+        .await
+        .unwrap_or_default();
+
+    Ok(video_from_parts(&oembed, &html))
+}
+
+/// Combine an oEmbed response with the watch-page HTML. oEmbed wins for the
+/// title; the description only exists in the page.
+pub fn video_from_parts(oembed: &JsonValue, html: &str) -> YoutubeVideo {
+    let (page_title, description) = parse_watch_page(html);
+    let oembed_str = |key: &str| {
+        oembed
+            .get(key)
+            .and_then(JsonValue::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    let title = oembed_str("title")
+        .or(page_title)
+        .or_else(|| extract_title(html).map(|t| t.trim_end_matches(" - YouTube").to_string()))
+        .unwrap_or_default();
+
+    YoutubeVideo {
+        title,
+        description: description.unwrap_or_default(),
+        thumbnail_url: oembed_str("thumbnail_url"),
+    }
+}
+
+/// Title and full description from the JSON blobs embedded in a watch page.
+///
+/// `ytInitialPlayerResponse.videoDetails` is tried first; `ytInitialData`
+/// (the rendered description panel) is the fallback.
+pub fn parse_watch_page(html: &str) -> (Option<String>, Option<String>) {
+    if let Some(details) = embedded_json(html, "ytInitialPlayerResponse")
+        .as_ref()
+        .and_then(|p| p.get("videoDetails"))
+    {
+        let get = |key: &str| {
+            details
+                .get(key)
+                .and_then(JsonValue::as_str)
+                .filter(|s| !s.trim().is_empty())
+                .map(str::to_string)
+        };
+        if let Some(description) = get("shortDescription") {
+            return (get("title"), Some(description));
+        }
+    }
+
+    let Some(data) = embedded_json(html, "ytInitialData") else {
+        return (None, None);
+    };
+
+    let description = find_key(&data, "attributedDescription")
+        .and_then(|d| d.get("content"))
+        .and_then(JsonValue::as_str)
+        .map(str::to_string);
+
+    let title = find_key(&data, "videoPrimaryInfoRenderer")
+        .and_then(|r| r.pointer("/title/runs"))
+        .and_then(JsonValue::as_array)
+        .map(|runs| {
+            runs.iter()
+                .filter_map(|r| r.get("text").and_then(JsonValue::as_str))
+                .collect::<String>()
+        })
+        .filter(|t| !t.trim().is_empty());
+
+    (title, description)
+}
+
+/// Parse the object assigned to `name` (`var name = {...};` or
+/// `window["name"] = {...};`) inside a script tag.
+fn embedded_json(html: &str, name: &str) -> Option<JsonValue> {
+    html.match_indices(name).find_map(|(i, _)| {
+        let rest = html[i + name.len()..]
+            .trim_start_matches(['"', ']'])
+            .trim_start()
+            .strip_prefix('=')?
+            .trim_start();
+        if !rest.starts_with('{') {
+            return None;
+        }
+        serde_json::Deserializer::from_str(rest)
+            .into_iter::<JsonValue>()
+            .next()?
+            .ok()
+    })
+}
+
+/// Depth-first search for the first value stored under `key`.
+fn find_key<'a>(v: &'a JsonValue, key: &str) -> Option<&'a JsonValue> {
+    match v {
+        JsonValue::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|child| find_key(child, key))),
+        JsonValue::Array(arr) => arr.iter().find_map(|child| find_key(child, key)),
+        _ => None,
+    }
+}
+
+/// Whether a video description plausibly contains an ingredient list: an
+/// "Ingredients" heading, or at least two lines starting with a quantity.
+pub fn has_ingredient_lines(description: &str) -> bool {
+    static HEADING_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?im)^\W*ingredients?\W*$").unwrap());
+    static QTY_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"(?i)^[-•*·▪]?\s*(?:\d+(?:[.,/]\d+)?|[½¼¾⅓⅔⅛])\s*(?:g|kg|mg|ml|cl|dl|l|tsp|tbsp|teaspoons?|tablespoons?|cups?|oz|ounces?|lbs?|pounds?|cloves?|pinch(?:es)?|cans?|x)?\s+\p{L}",
+        )
+        .unwrap()
+    });
+
+    if HEADING_RE.is_match(description) {
+        return true;
+    }
+    description
+        .lines()
+        .filter(|l| QTY_LINE_RE.is_match(l.trim()))
+        .take(2)
+        .count()
+        == 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const WATCH_PAGE: &str = include_str!("../tests/fixtures/youtube_watch.html");
+
+    #[test]
+    fn test_video_id() {
+        let id = Some("dQw4w9WgXcQ".to_string());
+        assert_eq!(video_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), id);
+        assert_eq!(
+            video_id("https://m.youtube.com/watch?feature=share&v=dQw4w9WgXcQ"),
+            id
+        );
+        assert_eq!(video_id("https://youtu.be/dQw4w9WgXcQ?t=42"), id);
+        assert_eq!(video_id("https://youtube.com/shorts/dQw4w9WgXcQ"), id);
+        assert_eq!(video_id("https://www.youtube.com/embed/dQw4w9WgXcQ"), id);
+
+        assert_eq!(video_id("https://www.youtube.com/@somechef"), None);
+        assert_eq!(video_id("https://www.youtube.com/watch?v=short"), None);
+        assert_eq!(video_id("https://notyoutube.com/watch?v=dQw4w9WgXcQ"), None);
+        assert_eq!(video_id("not a url"), None);
+    }
+
+    #[test]
+    fn test_parse_watch_page_fixture() {
+        let (title, description) = parse_watch_page(WATCH_PAGE);
+        assert_eq!(title.as_deref(), Some("Quick Tomato Spaghetti"));
+        let description = description.unwrap();
+        assert!(description.contains("200 g spaghetti"));
+        assert!(description.contains("Boil the pasta"));
+    }
+
+    #[test]
+    fn test_parse_watch_page_initial_data_fallback() {
+        let html = r#"<script>window["ytInitialData"] = {"contents":[{"videoPrimaryInfoRenderer":{"title":{"runs":[{"text":"Easy "},{"text":"Pancakes"}]}}},{"videoSecondaryInfoRenderer":{"attributedDescription":{"content":"2 eggs\n250 ml milk"}}}]};</script>"#;
+        let (title, description) = parse_watch_page(html);
+        assert_eq!(title.as_deref(), Some("Easy Pancakes"));
+        assert_eq!(description.as_deref(), Some("2 eggs\n250 ml milk"));
+    }
+
+    #[test]
+    fn test_parse_watch_page_without_data() {
+        assert_eq!(
+            parse_watch_page("<html><title>Before you continue</title></html>"),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn test_video_from_parts_prefers_oembed() {
+        let oembed = json!({
+            "title": "Tomato Spaghetti (oEmbed)",
+            "thumbnail_url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"
+        });
+        let video = video_from_parts(&oembed, WATCH_PAGE);
+        assert_eq!(video.title, "Tomato Spaghetti (oEmbed)");
+        assert_eq!(
+            video.thumbnail_url.as_deref(),
+            Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg")
+        );
+        assert!(video.description.contains("1 tbsp olive oil"));
+
+        let video = video_from_parts(&json!({}), WATCH_PAGE);
+        assert_eq!(video.title, "Quick Tomato Spaghetti");
+        assert_eq!(video.thumbnail_url, None);
+    }
+
+    #[test]
+    fn test_has_ingredient_lines() {
+        let (_, description) = parse_watch_page(WATCH_PAGE);
+        assert!(has_ingredient_lines(&description.unwrap()));
+        assert!(has_ingredient_lines("You need:\n- 2 eggs\n- 1 cup flour"));
+        assert!(has_ingredient_lines("INGREDIENTS:\nsalt\npepper"));
+        assert!(has_ingredient_lines("200g flour\n½ tsp salt"));
+
+        assert!(!has_ingredient_lines(
+            "Thanks for watching!\n0:00 Intro\n1:30 Cooking\nSubscribe for more"
+        ));
+        assert!(!has_ingredient_lines("1. Preheat the oven\n2. Bake"));
+        assert!(!has_ingredient_lines(""));
+    }
+}
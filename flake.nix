@@ -178,17 +178,29 @@
           description = "Directory to store media files (recipe images)";
         };
 
+        backupDir = lib.mkOption {
+          type = lib.types.str;
+          default = "/var/lib/blaz/backups";
+          description = "Directory for database snapshots made from the admin API";
+        };
+
         logFile = lib.mkOption {
           type = lib.types.str;
           default = "/var/lib/blaz/blaz.log";
           description = "Path to log file";
         };
 
+        logFormat = lib.mkOption {
+          type = lib.types.enum ["text" "json"];
+          default = "text";
+          description = "Log line format for stdout and the log file; json emits one object per line";
+        };
+
         corsOrigin = lib.mkOption {
           type = lib.types.nullOr lib.types.str;
           default = null;
           example = "https://blaz.yourdomain.com";
-          description = "CORS allowed origin. If null, allows any origin";
+          description = "CORS allowed origins, comma-separated. If null, allows any origin";
         };
 
         verbosity = lib.mkOption {
@@ -212,7 +224,7 @@
         jwtSecret = lib.mkOption {
           type = lib.types.nullOr lib.types.str;
           default = null;
-          description = "JWT secret. If not set, generates a random one.";
+          description = "JWT secret. If not set, one is generated and kept next to the database (jwt_secret, mode 0600).";
         };
 
         jwtSecretFile = lib.mkOption {
@@ -239,12 +251,10 @@
           description = "Path to file containing LLM API key (for sops-nix)";
         };
 
-        # Kept for compatibility with existing deployments. Current Blaz
-        # stores model selections through the /settings API.
         llmModel = lib.mkOption {
-          type = lib.types.str;
-          default = "deepseek/deepseek-v4-flash";
-          description = "Legacy LLM model setting retained for compatibility";
+          type = lib.types.nullOr lib.types.str;
+          default = null;
+          description = "LLM model overriding the one chosen in settings (not saved)";
         };
 
         systemPromptImport = lib.mkOption {
@@ -294,6 +304,30 @@
           default = null;
           description = "ntfy URL for backend error notifications";
         };
+
+        offline = lib.mkOption {
+          type = lib.types.bool;
+          default = false;
+          description = "Disable all LLM features (no calls to the LLM provider)";
+        };
+
+        disableCompression = lib.mkOption {
+          type = lib.types.bool;
+          default = false;
+          description = "Disable gzip/brotli responses (e.g. when a reverse proxy already compresses)";
+        };
+
+        mediaGcOnStartup = lib.mkOption {
+          type = lib.types.bool;
+          default = false;
+          description = "Delete recipe images no recipe references when the service starts";
+        };
+
+        fetchAllowPrivate = lib.mkOption {
+          type = lib.types.bool;
+          default = false;
+          description = "Allow recipe imports from private and loopback addresses";
+        };
       };
 
       config = lib.mkIf cfg.enable {
@@ -327,6 +361,7 @@
         systemd.tmpfiles.rules = [
           "d ${dirOf cfg.databasePath} 0750 blaz blaz - -"
           "d ${cfg.mediaDir} 0750 blaz blaz - -"
+          "d ${cfg.backupDir} 0750 blaz blaz - -"
           "d ${dirOf cfg.logFile} 0750 blaz blaz - -"
           "f ${cfg.logFile} 0640 blaz blaz - -"
         ];
@@ -341,10 +376,12 @@
               BLAZ_BIND_ADDR = cfg.bindAddr;
               BLAZ_DATABASE_PATH = cfg.databasePath;
               BLAZ_MEDIA_DIR = cfg.mediaDir;
+              BLAZ_BACKUP_DIR = cfg.backupDir;
               BLAZ_LOG_FILE = cfg.logFile;
+              BLAZ_LOG_FORMAT = cfg.logFormat;
               BLAZ_LLM_API_URL = cfg.llmApiUrl;
-              BLAZ_LLM_MODEL = cfg.llmModel;
             }
+            // lib.optionalAttrs (cfg.llmModel != null) {BLAZ_LLM_MODEL = cfg.llmModel;}
             // lib.optionalAttrs (cfg.corsOrigin != null) {BLAZ_CORS_ORIGIN = cfg.corsOrigin;}
             // lib.optionalAttrs (cfg.passwordHash != null) {BLAZ_PASSWORD_HASH = cfg.passwordHash;}
             // lib.optionalAttrs (cfg.jwtSecret != null) {BLAZ_JWT_SECRET = cfg.jwtSecret;}
@@ -372,7 +409,11 @@
             }
             // lib.optionalAttrs (cfg.ntfyUrl != null) {
               BLAZ_NTFY_URL = cfg.ntfyUrl;
-            };
+            }
+            // lib.optionalAttrs cfg.offline {BLAZ_OFFLINE = "true";}
+            // lib.optionalAttrs cfg.disableCompression {BLAZ_DISABLE_COMPRESSION = "true";}
+            // lib.optionalAttrs cfg.mediaGcOnStartup {BLAZ_MEDIA_GC_ON_STARTUP = "true";}
+            // lib.optionalAttrs cfg.fetchAllowPrivate {BLAZ_FETCH_ALLOW_PRIVATE = "true";};
 
           script = let
             passwordHashLoader =
@@ -411,6 +452,7 @@
             ReadWritePaths = [
               (dirOf cfg.databasePath)
               cfg.mediaDir
+              cfg.backupDir
             ];
             SocketBindAllow = let
               port = lib.last (lib.splitString ":" cfg.bindAddr);
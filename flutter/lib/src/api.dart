@@ -250,12 +250,16 @@ class RecipeMacros {
   final double protein; // grams
   final double fat; // grams (total)
   final double carbs; // grams (excluding fiber)
+  final double kcal; // 0 for estimates made before kcal was tracked
+  final double? fiber; // grams
   final List<IngredientMacros> ingredients;
 
   const RecipeMacros({
     required this.protein,
     required this.fat,
     required this.carbs,
+    this.kcal = 0,
+    this.fiber,
     this.ingredients = const [],
   });
 
@@ -288,6 +292,8 @@ class RecipeMacros {
       protein: read('protein', 'protein_g'),
       fat: read('fat', 'fat_g'),
       carbs: read('carbs', 'carbs_g'),
+      kcal: (m['kcal'] as num?)?.toDouble() ?? 0,
+      fiber: (m['fiber_g'] as num?)?.toDouble(),
       ingredients: ingredients,
     );
   }
@@ -296,6 +302,8 @@ class RecipeMacros {
     'protein': protein,
     'fat': fat,
     'carbs': carbs,
+    'kcal': kcal,
+    if (fiber != null) 'fiber_g': fiber,
   };
 }
 
@@ -827,19 +835,15 @@ Future<Recipe> uploadRecipeImage({
 }
 
 Future<List<Recipe>> fetchRecipes() async {
-  final res = await http.get(_u('/recipes?limit=1000'), headers: _headers(null, false));
-  if (res.statusCode != 200) {
-    throw Exception('HTTP ${res.statusCode}: ${res.body}');
-  }
+  final res = await http.get(_u('/recipes?limit=1000'), headers: _headers());
+  if (res.statusCode != 200) _throw(res);
   final List data = jsonDecode(res.body) as List;
   return data.map((e) => Recipe.fromJson(e as Map<String, dynamic>)).toList();
 }
 
 Future<Recipe> fetchRecipe(int id) async {
-  final res = await http.get(_u('/recipes/$id'), headers: _headers(null, false));
-  if (res.statusCode != 200) {
-    throw Exception('HTTP ${res.statusCode}: ${res.body}');
-  }
+  final res = await http.get(_u('/recipes/$id'), headers: _headers());
+  if (res.statusCode != 200) _throw(res);
   return Recipe.fromJson(jsonDecode(res.body) as Map<String, dynamic>);
 }
 
@@ -1090,7 +1094,8 @@ Future<List<ShoppingItem>> mergeShoppingIngredients(
     body: jsonEncode(body),
   );
   if (r.statusCode != 200) _throw(r);
-  final List data = jsonDecode(r.body) as List;
+  final body = jsonDecode(r.body) as Map<String, dynamic>;
+  final List data = body['items'] as List;
   return data
       .map((e) => ShoppingItem.fromJson(e as Map<String, dynamic>))
       .toList();
@@ -76,7 +76,7 @@ class _CategoriesPageState extends State<CategoriesPage> {
       builder: (ctx) => AlertDialog(
         title: const Text('Delete category?'),
         content: Text(
-          'Delete "${cat.name}"? Items using this category will need to be reassigned.',
+          'Delete "${cat.name}"? Items in this category move to "Other".',
         ),
         actions: [
           TextButton(